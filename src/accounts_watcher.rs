@@ -0,0 +1,401 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::rollback::{RollbackAction, RollbackManifest};
+
+/// One /etc/passwd entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswdEntry {
+    pub line: String,
+    pub name: String,
+    pub uid: u32,
+}
+
+/// An account added or escalated since the previous scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountFinding {
+    pub file: PathBuf,
+    pub name: String,
+    pub uid: Option<u32>,  // None for shadow-only entries
+    pub line: String,
+    pub reasons: Vec<String>,
+    /// A brand-new UID 0 account, the only kind `restore` will remove
+    pub new_root_account: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Snapshot {
+    passwd_hash: String,
+    shadow_hash: String,
+    passwd: HashMap<String, PasswdEntry>,
+    shadow_users: HashSet<String>,
+}
+
+/// Hashes /etc/passwd and /etc/shadow and reports new accounts, new UID 0
+/// (root-equivalent) accounts and existing accounts raised to UID 0
+pub struct AccountsWatcher {
+    etc_dir: PathBuf,
+    baseline: Option<Snapshot>,
+}
+
+impl AccountsWatcher {
+    pub fn new() -> Self {
+        Self::with_etc_dir(Path::new("/etc"))
+    }
+
+    fn with_etc_dir(etc_dir: &Path) -> Self {
+        Self {
+            etc_dir: etc_dir.to_path_buf(),
+            baseline: None,
+        }
+    }
+
+    fn passwd_path(&self) -> PathBuf {
+        self.etc_dir.join("passwd")
+    }
+
+    fn shadow_path(&self) -> PathBuf {
+        self.etc_dir.join("shadow")
+    }
+
+    /// Read both files; None if passwd can't be read or parsed (nothing is compared then)
+    fn snapshot(&self) -> Option<Snapshot> {
+        let passwd = fs::read_to_string(self.passwd_path()).ok()?;
+        let Some(entries) = parse_passwd(&passwd) else {
+            warn!("⚠️  Could not parse {:?}, not tracking accounts", self.passwd_path());
+            return None;
+        };
+        // Unreadable without root; only passwd is compared then
+        let shadow = fs::read_to_string(self.shadow_path()).unwrap_or_default();
+        Some(Snapshot {
+            passwd_hash: sha256_hex(&passwd),
+            shadow_hash: sha256_hex(&shadow),
+            passwd: entries.into_iter().map(|e| (e.name.clone(), e)).collect(),
+            shadow_users: shadow_users(&shadow),
+        })
+    }
+
+    /// Compare against the previous snapshot. The first call only records the baseline.
+    pub fn scan(&mut self) -> Vec<AccountFinding> {
+        let Some(current) = self.snapshot() else {
+            return Vec::new();
+        };
+        let Some(previous) = self.baseline.replace(current) else {
+            return Vec::new();
+        };
+        let current = self.baseline.as_ref().expect("baseline just set");
+        if current.passwd_hash == previous.passwd_hash && current.shadow_hash == previous.shadow_hash {
+            return Vec::new();
+        }
+
+        let mut findings = Vec::new();
+        let mut entries: Vec<&PasswdEntry> = current.passwd.values().collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        for entry in entries {
+            let mut reasons = Vec::new();
+            let mut new_root_account = false;
+            match previous.passwd.get(&entry.name) {
+                None if entry.uid == 0 => {
+                    reasons.push("new root-equivalent account (UID 0)".to_string());
+                    new_root_account = true;
+                }
+                None => reasons.push("new account".to_string()),
+                Some(known) if known.uid != 0 && entry.uid == 0 => {
+                    reasons.push(format!("UID changed from {} to 0 (root-equivalent)", known.uid));
+                }
+                Some(_) => continue,
+            }
+            findings.push(AccountFinding {
+                file: self.passwd_path(),
+                name: entry.name.clone(),
+                uid: Some(entry.uid),
+                line: entry.line.clone(),
+                reasons,
+                new_root_account,
+            });
+        }
+
+        // Shadow entries for accounts that weren't just reported through passwd
+        let mut added: Vec<&String> = current.shadow_users.difference(&previous.shadow_users)
+            .filter(|name| !findings.iter().any(|f| &f.name == *name))
+            .collect();
+        added.sort();
+        for name in added {
+            findings.push(AccountFinding {
+                file: self.shadow_path(),
+                name: name.clone(),
+                uid: current.passwd.get(name).map(|e| e.uid),
+                line: String::new(),  // Never copy password hashes into alerts
+                reasons: vec!["new shadow entry".to_string()],
+                new_root_account: false,
+            });
+        }
+
+        if findings.is_empty() {
+            debug!("Account files changed without new accounts (password or GECOS update)");
+        }
+        findings
+    }
+
+    /// Remove a newly added UID 0 account from passwd and shadow. Both files are
+    /// backed up into `rollback_dir` and a rollback manifest is written before either
+    /// is touched; the root account itself and anything other than a brand-new UID 0
+    /// entry are refused. The files are rewritten under the lckpwdf(3) lock.
+    pub fn restore(&mut self, finding: &AccountFinding, rollback_dir: &Path, signing_key: Option<&[u8]>) -> Result<()> {
+        if !finding.new_root_account || finding.name == "root" {
+            bail!("Only newly added UID 0 accounts other than root are removed automatically");
+        }
+        let _lock = PasswdLock::acquire(&self.etc_dir)?;
+        let passwd_path = self.passwd_path();
+        let passwd = fs::read_to_string(&passwd_path)
+            .with_context(|| format!("Failed to read {:?}", passwd_path))?;
+        if parse_passwd(&passwd).is_none() {
+            bail!("{:?} no longer parses, leaving it untouched", passwd_path);
+        }
+        let new_passwd = remove_lines(&passwd, |line| line == finding.line)
+            .with_context(|| format!("Entry for {} not found verbatim in {:?}, leaving it untouched", finding.name, passwd_path))?;
+        let remaining = parse_passwd(&new_passwd).unwrap_or_default();
+        if !remaining.iter().any(|e| e.name == "root" && e.uid == 0) {
+            bail!("Removing {} would leave {:?} without a root entry, leaving it untouched", finding.name, passwd_path);
+        }
+
+        let shadow_path = self.shadow_path();
+        let shadow = fs::read_to_string(&shadow_path).ok();
+        let prefix = format!("{}:", finding.name);
+        let new_shadow = shadow.as_deref().and_then(|s| remove_lines(s, |line| line.starts_with(&prefix)));
+
+        // Backups + manifest before any modification, outside /etc
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let mut manifest = RollbackManifest::new();
+        let mut targets = vec![(passwd_path.clone(), passwd, new_passwd)];
+        if let (Some(shadow), Some(new_shadow)) = (shadow, new_shadow) {
+            targets.push((shadow_path, shadow, new_shadow));
+        }
+        fs::create_dir_all(rollback_dir)?;
+        for (path, original, _) in &targets {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+            let backup = rollback_dir.join(format!("account_{}_{}_{}.backup", finding.name, file_name, timestamp));
+            write_private(&backup, original).with_context(|| format!("Failed to back up {:?}", path))?;
+            manifest.add_action(RollbackAction::RestoreFile {
+                from: backup.to_string_lossy().to_string(),
+                to: path.to_string_lossy().to_string(),
+            });
+        }
+        if let Some(key) = signing_key {
+            manifest.sign(key)?;
+        }
+        manifest.save(&rollback_dir.join(format!("account_{}_{}.rollback", finding.name, timestamp)))?;
+
+        for (path, _, content) in &targets {
+            let metadata = fs::metadata(path)?;
+            let temp = path.with_extension("hora-tmp");
+            // Created private, then given the original's owner and mode before it replaces it
+            let file = write_private(&temp, content)?;
+            std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()))
+                .with_context(|| format!("Failed to set the owner of {:?}", temp))?;
+            file.set_permissions(fs::Permissions::from_mode(metadata.permissions().mode()))?;
+            file.sync_all()?;
+            fs::rename(&temp, path).with_context(|| format!("Failed to replace {:?}", path))?;
+        }
+        info!("🧹 Removed UID 0 account {} from {:?}", finding.name, passwd_path);
+
+        // Don't re-report our own edit on the next scan
+        self.baseline = self.snapshot();
+        Ok(())
+    }
+}
+
+impl Default for AccountsWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The lock lckpwdf(3) takes: a write lock on `.pwd.lock` next to passwd, held by
+/// useradd, passwd, chpasswd and friends while they edit the account files.
+/// Released when dropped.
+struct PasswdLock {
+    _file: fs::File,
+}
+
+impl PasswdLock {
+    /// Fails instead of waiting if another tool holds the lock
+    fn acquire(etc_dir: &Path) -> Result<Self> {
+        let path = etc_dir.join(".pwd.lock");
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(&path)
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        // SAFETY: an all-zero flock is valid; the fields lckpwdf sets are filled in below
+        let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+        lock.l_type = libc::F_WRLCK as libc::c_short;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+        // SAFETY: valid descriptor and a pointer to an initialized flock
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) } != 0 {
+            bail!("Account files are locked by another tool ({:?}): {}", path, std::io::Error::last_os_error());
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// Create `path` with mode 0600 (never world-readable, even briefly) and write `content`
+fn write_private(path: &Path, content: &str) -> Result<fs::File> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to create {:?}", path))?;
+    file.write_all(content.as_bytes())?;
+    Ok(file)
+}
+
+/// `content` without the lines matching `remove`, or None if none matched
+fn remove_lines(content: &str, remove: impl Fn(&str) -> bool) -> Option<String> {
+    let kept: Vec<&str> = content.lines().filter(|line| !remove(line)).collect();
+    if kept.len() == content.lines().count() {
+        return None;
+    }
+    Some(kept.join("\n") + "\n")
+}
+
+/// Entries in /etc/passwd, or None if any line isn't `name:pw:uid:gid:gecos:home:shell`
+pub fn parse_passwd(content: &str) -> Option<Vec<PasswdEntry>> {
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        // NIS compat entries (+user, -user, +@netgroup) carry no local account
+        if line.trim().is_empty() || line.starts_with('#') || line.starts_with('+') || line.starts_with('-') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(':').collect();
+        let [name, _, uid, _, _, _, _] = fields.as_slice() else {
+            return None;
+        };
+        if name.is_empty() {
+            return None;
+        }
+        entries.push(PasswdEntry {
+            line: line.to_string(),
+            name: name.to_string(),
+            uid: uid.parse().ok()?,
+        });
+    }
+    Some(entries)
+}
+
+fn shadow_users(content: &str) -> HashSet<String> {
+    content.lines()
+        .filter_map(|line| line.split_once(':').map(|(name, _)| name))
+        .filter(|name| !name.is_empty() && !name.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn sha256_hex(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/bash\ndaemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin\nwww-data:x:33:33:www-data:/var/www:/usr/sbin/nologin\n";
+    const SHADOW: &str = "root:$6$abc$def:19000:0:99999:7:::\ndaemon:*:19000:0:99999:7:::\nwww-data:*:19000:0:99999:7:::\n";
+
+    fn setup() -> (tempfile::TempDir, AccountsWatcher) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("passwd"), PASSWD).unwrap();
+        fs::write(dir.path().join("shadow"), SHADOW).unwrap();
+        fs::set_permissions(dir.path().join("shadow"), fs::Permissions::from_mode(0o640)).unwrap();
+        let watcher = AccountsWatcher::with_etc_dir(dir.path());
+        (dir, watcher)
+    }
+
+    #[test]
+    fn flags_added_root_equivalent_account() {
+        let (dir, mut watcher) = setup();
+        assert!(watcher.scan().is_empty());  // Baseline
+        assert!(watcher.scan().is_empty());
+
+        let backdoor = "sysupdate:x:0:0::/root:/bin/bash";
+        fs::write(dir.path().join("passwd"), format!("{}{}\nbackup:x:1001:1001::/home/backup:/bin/sh\n", PASSWD, backdoor)).unwrap();
+        fs::write(dir.path().join("shadow"), format!("{}sysupdate:$1$x$y:19000:0:99999:7:::\n", SHADOW)).unwrap();
+
+        let findings = watcher.scan();
+        assert_eq!(findings.len(), 2);
+        assert_eq!((findings[0].name.as_str(), findings[0].uid), ("backup", Some(1001)));
+        assert_eq!(findings[0].reasons, vec!["new account"]);
+        assert_eq!((findings[1].name.as_str(), findings[1].uid, findings[1].line.as_str()), ("sysupdate", Some(0), backdoor));
+        assert!(findings[1].new_root_account);
+
+        // Reported once
+        assert!(watcher.scan().is_empty());
+    }
+
+    #[test]
+    fn flags_uid_raised_to_zero_and_shadow_only_entries() {
+        let (dir, mut watcher) = setup();
+        watcher.scan();
+        fs::write(dir.path().join("passwd"), PASSWD.replace("www-data:x:33:33", "www-data:x:0:33")).unwrap();
+        fs::write(dir.path().join("shadow"), format!("{}ghost:$6$a$b:19000::::::\n", SHADOW)).unwrap();
+
+        let findings = watcher.scan();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].reasons, vec!["UID changed from 33 to 0 (root-equivalent)"]);
+        assert!(!findings[0].new_root_account);
+        assert_eq!((findings[1].name.as_str(), findings[1].uid), ("ghost", None));
+        assert!(findings[1].line.is_empty());
+    }
+
+    #[test]
+    fn restore_removes_new_root_account_with_backups() {
+        let (dir, mut watcher) = setup();
+        watcher.scan();
+        fs::write(dir.path().join("passwd"), format!("{}toor:x:0:0::/root:/bin/bash\n", PASSWD)).unwrap();
+        fs::write(dir.path().join("shadow"), format!("{}toor:$1$x$y:19000:0:99999:7:::\n", SHADOW)).unwrap();
+
+        let findings = watcher.scan();
+        let rollback_dir = dir.path().join("rollbacks");
+        watcher.restore(&findings[0], &rollback_dir, None).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.path().join("passwd")).unwrap(), PASSWD);
+        assert_eq!(fs::read_to_string(dir.path().join("shadow")).unwrap(), SHADOW);
+        let mode = fs::metadata(dir.path().join("shadow")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        // Backups live with the manifest, private, and nothing is left behind in etc
+        let backups: Vec<PathBuf> = fs::read_dir(&rollback_dir).unwrap().flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "backup"))
+            .collect();
+        assert_eq!(backups.len(), 2);
+        assert!(backups.iter().all(|b| fs::metadata(b).unwrap().permissions().mode() & 0o777 == 0o600));
+        let leftovers = fs::read_dir(dir.path()).unwrap().flatten()
+            .filter(|e| e.file_name().to_string_lossy().contains("backup") || e.file_name().to_string_lossy().contains("hora-tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(watcher.scan().is_empty());
+    }
+
+    #[test]
+    fn never_removes_anything_but_new_root_accounts() {
+        let (dir, mut watcher) = setup();
+        watcher.scan();
+        fs::write(dir.path().join("passwd"), format!("{}backup:x:1001:1001::/home/backup:/bin/sh\n", PASSWD)).unwrap();
+        let findings = watcher.scan();
+        assert!(watcher.restore(&findings[0], &dir.path().join("rollbacks"), None).is_err());
+        assert!(!dir.path().join("rollbacks").exists());
+
+        assert!(parse_passwd("root:x:0:0:root:/root:/bin/bash\nbroken line\n").is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::process_monitor::ProcessInfo;
+use crate::safe_kill::KillActionType;
+
+/// Enforcement decided for a manager-backed process, waiting out its confirmation window
+#[derive(Debug, Clone)]
+pub struct ScheduledAction {
+    pub action: KillActionType,
+    pub process: ProcessInfo,
+    pub reason: String,
+    pub confidence: f32,
+    pub due_at: Instant,
+}
+
+/// Delayed actions keyed by PID. Clones share state so a Telegram listener
+/// task can cancel actions the daemon loop will otherwise execute.
+#[derive(Clone, Default)]
+pub struct PendingActions {
+    inner: Arc<Mutex<HashMap<i32, ScheduledAction>>>,
+    cancelled: Arc<Mutex<HashMap<i32, u64>>>, // pid -> start_time, so re-detections stay cancelled
+}
+
+impl PendingActions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an action of this type waits for the confirmation window
+    pub fn needs_delay(action: &KillActionType, delay: Duration) -> bool {
+        !delay.is_zero() && matches!(action, KillActionType::StopUnit | KillActionType::StopPm2 | KillActionType::StopContainer)
+    }
+
+    /// Schedule an action; returns false if one is already pending for the PID
+    /// or the operator cancelled enforcement against this process
+    pub fn schedule(
+        &self,
+        action: KillActionType,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+        delay: Duration,
+    ) -> bool {
+        if self.cancelled.lock().unwrap().get(&process.pid) == Some(&process.start_time) {
+            return false;
+        }
+        let mut pending = self.inner.lock().unwrap();
+        if pending.contains_key(&process.pid) {
+            return false;
+        }
+        pending.insert(process.pid, ScheduledAction {
+            action,
+            process: process.clone(),
+            reason: reason.to_string(),
+            confidence,
+            due_at: Instant::now() + delay,
+        });
+        true
+    }
+
+    pub fn is_pending(&self, pid: i32) -> bool {
+        self.inner.lock().unwrap().contains_key(&pid)
+    }
+
+    /// Abort the pending action for a PID
+    pub fn cancel(&self, pid: i32) -> Option<ScheduledAction> {
+        let cancelled = self.inner.lock().unwrap().remove(&pid);
+        if let Some(action) = &cancelled {
+            self.cancelled.lock().unwrap().insert(pid, action.process.start_time);
+            info!("⏹️  Pending action for PID {} cancelled", pid);
+        }
+        cancelled
+    }
+
+    /// Remove and return every action whose window has elapsed
+    pub fn take_due(&self, now: Instant) -> Vec<ScheduledAction> {
+        let mut pending = self.inner.lock().unwrap();
+        let due: Vec<i32> = pending.iter()
+            .filter(|(_, a)| a.due_at <= now)
+            .map(|(&pid, _)| pid)
+            .collect();
+        due.into_iter().filter_map(|pid| pending.remove(&pid)).collect()
+    }
+
+    /// Apply cancel requests dropped into `dir` as files named by PID (see `cancel-action`)
+    pub fn apply_cancel_requests(&self, dir: &Path) -> Vec<i32> {
+        let mut cancelled = Vec::new();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return cancelled;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(pid) = path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.parse::<i32>().ok()) else {
+                continue;
+            };
+            if self.cancel(pid).is_some() {
+                cancelled.push(pid);
+            }
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Failed to remove cancel request {:?}: {}", path, e);
+            }
+        }
+        cancelled
+    }
+}
+
+/// Parse "/cancel 1234", "/cancel@bot 1234" or callback data "cancel:1234"
+pub fn parse_cancel_command(text: &str) -> Option<i32> {
+    let text = text.trim();
+    let rest = text.strip_prefix("cancel:").or_else(|| {
+        let (command, rest) = text.split_once(char::is_whitespace)?;
+        let command = command.split('@').next()?;
+        (command == "/cancel").then_some(rest)
+    })?;
+    rest.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            binary_path: "/usr/bin/node".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn schedules_cancels_and_releases_due_actions() {
+        let pending = PendingActions::new();
+        let delay = Duration::from_secs(60);
+        assert!(pending.schedule(KillActionType::StopPm2, &process(10), "cpu", 0.97, delay));
+        assert!(!pending.schedule(KillActionType::StopPm2, &process(10), "cpu", 0.97, delay));
+        assert!(pending.schedule(KillActionType::StopUnit, &process(20), "cpu", 0.97, delay));
+
+        assert!(pending.take_due(Instant::now()).is_empty());
+
+        // Cancel via the shared handle a listener task would hold
+        assert!(pending.clone().cancel(10).is_some());
+        assert!(!pending.is_pending(10));
+        // Repeated detections of the same process don't re-arm the action
+        assert!(!pending.schedule(KillActionType::StopPm2, &process(10), "cpu", 0.97, delay));
+
+        let due = pending.take_due(Instant::now() + delay);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].process.pid, 20);
+        assert!(!pending.is_pending(20));
+    }
+
+    #[test]
+    fn applies_file_cancel_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let pending = PendingActions::new();
+        pending.schedule(KillActionType::StopUnit, &process(42), "cpu", 0.99, Duration::from_secs(60));
+        std::fs::write(dir.path().join("42"), "").unwrap();
+
+        assert_eq!(pending.apply_cancel_requests(dir.path()), vec![42]);
+        assert!(!pending.is_pending(42));
+        assert!(!dir.path().join("42").exists());
+    }
+
+    #[test]
+    fn only_manager_actions_are_delayed() {
+        let delay = Duration::from_secs(30);
+        assert!(PendingActions::needs_delay(&KillActionType::StopUnit, delay));
+        assert!(!PendingActions::needs_delay(&KillActionType::KillDirect, delay));
+        assert!(!PendingActions::needs_delay(&KillActionType::StopPm2, Duration::ZERO));
+    }
+
+    #[test]
+    fn parses_cancel_commands() {
+        assert_eq!(parse_cancel_command("/cancel 1234"), Some(1234));
+        assert_eq!(parse_cancel_command("/cancel@hora_bot  99"), Some(99));
+        assert_eq!(parse_cancel_command("cancel:77"), Some(77));
+        assert_eq!(parse_cancel_command("/status 1234"), None);
+        assert_eq!(parse_cancel_command("/cancel abc"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::config::{Config, HookFailurePolicy};
+use crate::process_monitor::ProcessInfo;
+use crate::safe_kill::KillActionType;
+
+/// What the hook receives on stdin, as one JSON object:
+///
+/// ```json
+/// {"pid": 4242, "uid": 33, "binary_path": "/tmp/.x/xmrig", "command_line": "/tmp/.x/xmrig -o pool:443",
+///  "reason": "CPU abuse", "confidence": 0.93, "action": "KillDirect",
+///  "timestamp": "2024-05-01T12:00:00Z"}
+/// ```
+///
+/// `action` is one of StopUnit, StopPm2, StopContainer, KillDirect, KillTree.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookEvent<'a> {
+    pub pid: i32,
+    pub uid: u32,
+    pub binary_path: &'a str,
+    pub command_line: &'a str,
+    pub reason: &'a str,
+    pub confidence: f32,
+    pub action: &'a KillActionType,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl<'a> HookEvent<'a> {
+    pub fn new(process: &'a ProcessInfo, action: &'a KillActionType, reason: &'a str, confidence: f32) -> Self {
+        Self {
+            pid: process.pid,
+            uid: process.uid,
+            binary_path: &process.binary_path,
+            command_line: &process.command_line,
+            reason,
+            confidence,
+            action,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookVerdict {
+    Proceed,
+    Veto(String),
+}
+
+/// Operator script consulted before every enforcement action. Exit 0 lets the
+/// action run, any other exit vetoes it. A hook that can't be started, times out
+/// or dies from a signal is handled per `on_failure`.
+#[derive(Debug, Clone)]
+pub struct PreActionHook {
+    pub path: PathBuf,
+    pub timeout: Duration,
+    pub on_failure: HookFailurePolicy,
+}
+
+impl PreActionHook {
+    /// None when `pre_action_hook` is empty
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (!config.pre_action_hook.is_empty()).then(|| Self {
+            path: PathBuf::from(&config.pre_action_hook),
+            timeout: Duration::from_secs(config.pre_action_hook_timeout_seconds),
+            on_failure: config.pre_action_hook_on_failure,
+        })
+    }
+
+    pub async fn check(&self, event: &HookEvent<'_>) -> HookVerdict {
+        match self.run(event).await {
+            Ok(Some(0)) => HookVerdict::Proceed,
+            Ok(Some(code)) => HookVerdict::Veto(format!("pre-action hook exited with status {}", code)),
+            Ok(None) => self.on_hook_failure(event.pid, "hook was killed by a signal"),
+            Err(e) => self.on_hook_failure(event.pid, &e.to_string()),
+        }
+    }
+
+    fn on_hook_failure(&self, pid: i32, error: &str) -> HookVerdict {
+        match self.on_failure {
+            HookFailurePolicy::FailOpen => {
+                warn!("⚠️  Pre-action hook failed for PID {} ({}), proceeding (fail_open)", pid, error);
+                HookVerdict::Proceed
+            }
+            HookFailurePolicy::FailClosed => {
+                HookVerdict::Veto(format!("pre-action hook failed ({}), aborting (fail_closed)", error))
+            }
+        }
+    }
+
+    /// Exit code, or None if the hook was terminated by a signal
+    async fn run(&self, event: &HookEvent<'_>) -> Result<Option<i32>> {
+        let input = serde_json::to_vec(event)?;
+        let mut child = tokio::process::Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.path.display()))?;
+
+        let run = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                // A hook that decides without reading stdin closes the pipe early
+                if let Err(e) = stdin.write_all(&input).await {
+                    if e.kind() != std::io::ErrorKind::BrokenPipe {
+                        return Err(e);
+                    }
+                }
+            }
+            child.wait_with_output().await
+        };
+
+        let output = tokio::time::timeout(self.timeout, run).await
+            .map_err(|_| anyhow::anyhow!("timed out after {}s", self.timeout.as_secs()))??;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            info!("Pre-action hook for PID {}: {}", event.pid, stderr.trim());
+        }
+        Ok(output.status.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn hook(dir: &tempfile::TempDir, script: &str, on_failure: HookFailurePolicy) -> PreActionHook {
+        let path = dir.path().join("hook.sh");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        PreActionHook { path, timeout: Duration::from_millis(500), on_failure }
+    }
+
+    #[tokio::test]
+    async fn exit_status_decides_and_event_arrives_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let process = ProcessInfo { pid: 4242, binary_path: "/tmp/.x/xmrig".to_string(), ..Default::default() };
+        let action = KillActionType::KillDirect;
+        let event = HookEvent::new(&process, &action, "CPU abuse", 0.93);
+
+        // Veto only this exact pid/action
+        let hook = hook(&dir, r#"case "$(cat)" in *'"pid":4242,'*'"action":"KillDirect"'*) exit 3;; esac"#,
+                        HookFailurePolicy::FailOpen);
+        assert_eq!(hook.check(&event).await, HookVerdict::Veto("pre-action hook exited with status 3".to_string()));
+
+        let other = ProcessInfo { pid: 7, ..process.clone() };
+        assert_eq!(hook.check(&HookEvent::new(&other, &action, "CPU abuse", 0.93)).await, HookVerdict::Proceed);
+    }
+
+    #[tokio::test]
+    async fn hook_failures_follow_the_configured_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let process = ProcessInfo { pid: 1, ..Default::default() };
+        let action = KillActionType::KillTree;
+        let event = HookEvent::new(&process, &action, "test", 0.9);
+
+        let slow = hook(&dir, "sleep 30", HookFailurePolicy::FailOpen);
+        assert_eq!(slow.check(&event).await, HookVerdict::Proceed);
+        let slow = PreActionHook { on_failure: HookFailurePolicy::FailClosed, ..slow };
+        assert!(matches!(slow.check(&event).await, HookVerdict::Veto(_)));
+
+        let missing = PreActionHook { path: dir.path().join("missing"), ..slow };
+        assert!(matches!(missing.check(&event).await, HookVerdict::Veto(_)));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::config::AlertTemplatesConfig;
+
+/// Every alert the daemon sends, keyed in `[alert_templates]` by `key()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    MalwareDetected,
+    SuspiciousProcess,
+    FilelessMalware,
+    SuspiciousCron,
+    MalwareFile,
+    MalwareFileReported,
+    SelfIntegrity,
+    DiskLow,
+    DiskCritical,
+    SudoersPersistence,
+    PtraceAttachment,
+    DenylistedProcess,
+    SystemdPersistence,
+    WhitelistReplaced,
+    PendingEnforcement,
+    EnforcementDisabled,
+    KillFailed,
+    UnkillableDState,
+    PermissionDenied,
+    AccountPersistence,
+    MalwareHardlinks,
+    KernelModuleLoaded,
+    SpawnChain,
+    ManagerNotFound,
+}
+
+impl AlertKind {
+    pub const ALL: &'static [AlertKind] = &[
+        AlertKind::MalwareDetected,
+        AlertKind::SuspiciousProcess,
+        AlertKind::FilelessMalware,
+        AlertKind::SuspiciousCron,
+        AlertKind::MalwareFile,
+        AlertKind::MalwareFileReported,
+        AlertKind::SelfIntegrity,
+        AlertKind::DiskLow,
+        AlertKind::DiskCritical,
+        AlertKind::SudoersPersistence,
+        AlertKind::PtraceAttachment,
+        AlertKind::DenylistedProcess,
+        AlertKind::SystemdPersistence,
+        AlertKind::WhitelistReplaced,
+        AlertKind::PendingEnforcement,
+        AlertKind::EnforcementDisabled,
+        AlertKind::KillFailed,
+        AlertKind::UnkillableDState,
+        AlertKind::PermissionDenied,
+        AlertKind::AccountPersistence,
+        AlertKind::MalwareHardlinks,
+        AlertKind::KernelModuleLoaded,
+        AlertKind::SpawnChain,
+        AlertKind::ManagerNotFound,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            AlertKind::MalwareDetected => "malware_detected",
+            AlertKind::SuspiciousProcess => "suspicious_process",
+            AlertKind::FilelessMalware => "fileless_malware",
+            AlertKind::SuspiciousCron => "suspicious_cron",
+            AlertKind::MalwareFile => "malware_file",
+            AlertKind::MalwareFileReported => "malware_file_reported",
+            AlertKind::SelfIntegrity => "self_integrity",
+            AlertKind::DiskLow => "disk_low",
+            AlertKind::DiskCritical => "disk_critical",
+            AlertKind::SudoersPersistence => "sudoers_persistence",
+            AlertKind::PtraceAttachment => "ptrace_attachment",
+            AlertKind::DenylistedProcess => "denylisted_process",
+            AlertKind::SystemdPersistence => "systemd_persistence",
+            AlertKind::WhitelistReplaced => "whitelist_replaced",
+            AlertKind::PendingEnforcement => "pending_enforcement",
+            AlertKind::EnforcementDisabled => "enforcement_disabled",
+            AlertKind::KillFailed => "kill_failed",
+            AlertKind::UnkillableDState => "cannot_kill_d_state",
+            AlertKind::PermissionDenied => "permission_denied",
+            AlertKind::AccountPersistence => "account_persistence",
+            AlertKind::MalwareHardlinks => "malware_hardlinks",
+            AlertKind::KernelModuleLoaded => "kernel_module_loaded",
+            AlertKind::SpawnChain => "spawn_chain",
+            AlertKind::ManagerNotFound => "manager_not_found",
+        }
+    }
+
+    pub fn default_title(&self) -> &'static str {
+        match self {
+            AlertKind::MalwareDetected => "Malware Detected",
+            AlertKind::SuspiciousProcess => "Suspicious Process Detected",
+            AlertKind::FilelessMalware => "Fileless Malware Detected",
+            AlertKind::SuspiciousCron => "Suspicious Cron Job",
+            AlertKind::MalwareFile => "Malware File Detected",
+            AlertKind::MalwareFileReported => "Malware File Reported",
+            AlertKind::SelfIntegrity => "Self-Integrity Violation",
+            AlertKind::DiskLow => "Disk Space Low",
+            AlertKind::DiskCritical => "Disk Critically Low",
+            AlertKind::SudoersPersistence => "Sudoers Persistence",
+            AlertKind::PtraceAttachment => "Ptrace Attachment",
+            AlertKind::DenylistedProcess => "Denylisted Process",
+            AlertKind::SystemdPersistence => "Systemd Persistence",
+            AlertKind::WhitelistReplaced => "Whitelisted Binary Replaced",
+            AlertKind::PendingEnforcement => "Pending Enforcement",
+            AlertKind::EnforcementDisabled => "Enforcement Disabled",
+            AlertKind::KillFailed => "Kill Failed",
+            AlertKind::UnkillableDState => "Cannot Kill D-State Process",
+            AlertKind::PermissionDenied => "No Permission to Kill",
+            AlertKind::AccountPersistence => "Account Persistence",
+            AlertKind::MalwareHardlinks => "Hard-Linked Malware",
+            AlertKind::KernelModuleLoaded => "Kernel Module Loaded",
+            AlertKind::SpawnChain => "Web Server Spawned a Payload",
+            AlertKind::ManagerNotFound => "Process Manager Lost Track",
+        }
+    }
+
+    /// Built-in message body; the placeholders listed here are the ones the daemon fills in
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            AlertKind::MalwareDetected =>
+                "Killed process PID {pid} ({binary})\nReason: {reason}\nConfidence: {confidence}%",
+            AlertKind::SuspiciousProcess =>
+                "Suspicious process detected (not killed due to safety policy):\n\nPID: {pid}\nBinary: {binary}\nCPU: {cpu}%\nDuration: {duration}s\nConfidence: {confidence}%",
+            AlertKind::FilelessMalware =>
+                "Fileless (memfd or deleted executable) process killed with its children:\n\nPID: {pid}\nExe: {binary}\nCommand: {command}\nCPU: {cpu}%\nConfidence: {confidence}%\n\nNo file on disk - investigate the parent ({ppid}) for the loader.",
+            AlertKind::SuspiciousCron =>
+                "Suspicious cron job detected:\nFile: {file}\nUser: {user}\nReasons: {reason}",
+            AlertKind::MalwareFile =>
+                "Malware file detected and {action}!\n\nFile: {file}\nSignature: {signature}\nThreat Level: {threat_level}%\nHash: {hash}{timestomp}{iocs}{web_requests}{cleanup}",
+            AlertKind::MalwareFileReported =>
+                "Possible malware file detected (report only, not quarantined)\n\nFile: {file}\nSignature: {signature}\nThreat Level: {threat_level}%\nHash: {hash}{timestomp}{iocs}\n\nSet file_scanning.home_scan_mode = \"enforce\" to act on /home automatically.",
+            AlertKind::SelfIntegrity =>
+                "hora-police file changed unexpectedly:\n\nPath: {file}\nExpected SHA256: {expected}\nCurrent SHA256: {actual}\n\nIf this was an intentional upgrade, create {upgrade_marker} before upgrading.",
+            AlertKind::DiskLow | AlertKind::DiskCritical =>
+                "Low disk space on the {purpose} filesystem:\n\nPath: {path}\nFree: {free_mb} MB of {total_mb} MB\n\n{consequence}",
+            AlertKind::SudoersPersistence =>
+                "Sudo grant added outside of policy:\n\nFile: {file}\nLine: {line}\nWhy: {reason}\nAction: {action}",
+            AlertKind::PtraceAttachment =>
+                "{summary}\n\nTracer: {tracer_binary} (PID {tracer_pid}, UID {tracer_uid})\nCommand: {tracer_command}\nTracee: {binary} (PID {pid}, UID {uid})\nCommand: {command}",
+            AlertKind::DenylistedProcess =>
+                "Denylisted binary running:\n\nPID: {pid}\nBinary: {binary}\nCommand: {command}\nMatch: {reason}\nAction: {action}",
+            AlertKind::SystemdPersistence =>
+                "Suspicious systemd unit installed:\n\nUnit: {unit}\nFile: {file}\nWhy: {reason}\nAction: {action}",
+            AlertKind::WhitelistReplaced =>
+                "Whitelisted binary changed outside a deploy:\n\nPath: {file}\nExpected SHA256: {expected}\nCurrent SHA256: {actual}\n\n{removed} whitelist entries it vouched for were removed; processes using it are now evaluated normally.",
+            AlertKind::PendingEnforcement =>
+                "{action} scheduled in {delay}s:\n\nPID: {pid}\nBinary: {binary}\nReason: {reason}\nConfidence: {confidence}%",
+            AlertKind::EnforcementDisabled =>
+                "Could not record {action} for PID {pid} in the database ({reason}).\n\nProcesses will only be reported, not stopped, until the database accepts writes again.",
+            AlertKind::KillFailed =>
+                "PID {pid} is still alive {waited}s after {signal} (state: {state}).\n\nLikely stuck in uninterruptible I/O (D-state); manual investigation needed.",
+            AlertKind::UnkillableDState =>
+                "PID {pid} ({binary}) is in uninterruptible sleep (D state); signals cannot stop it until its I/O completes.\n\nIt won't be scored or signalled again until it leaves D state. Check for hung storage or NFS mounts.",
+            AlertKind::PermissionDenied =>
+                "PID {pid} ({binary}) could not be sent {signal}: operation not permitted (EPERM).\n\nHora-Police lacks the privilege to signal this process. Run it as root, or grant CAP_KILL (AmbientCapabilities=CAP_KILL in the systemd unit).\n\nIt won't be retried until the process restarts.",
+            AlertKind::AccountPersistence =>
+                "Account added or changed:\n\nFile: {file}\nUser: {user} (UID {uid})\nWhy: {reason}\nAction: {action}",
+            AlertKind::MalwareHardlinks =>
+                "Malware file {file} ({signature}) has {link_count} hard links, a way to survive deletion of one name.\n\nOther links found in the scan roots:\n{links}\n\nAction: {action}",
+            AlertKind::KernelModuleLoaded =>
+                "Kernel module loaded after startup:\n\nModule: `{module}`\nSize: {size} bytes\nWhy: {reason}\n\nRootkits load modules to hide processes and files; check `modinfo {module}` and `dmesg`. Modules are never unloaded automatically.",
+            AlertKind::SpawnChain =>
+                "A network-facing daemon ran a shell that started {payload}:\n\n{chain}\n\nPID: {pid}\nBinary: {binary}\nCommand: {command}\nConfidence: {confidence}%\nAction: {action}\n\nLikely remote code execution; check the daemon's access logs around this time.",
+            AlertKind::ManagerNotFound =>
+                "Wanted to stop PID {pid} ({binary}) through its {manager}, but it was not found: {why}\n\nReason: {reason}\nConfidence: {confidence}%\n\nThe process was left running. Stop it by hand, or set `manager_fallback = \"direct_kill\"` to kill such processes directly.",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<AlertKind> {
+        Self::ALL.iter().copied().find(|kind| kind.key() == key)
+    }
+}
+
+/// Alert titles and bodies, with operator overrides from `[alert_templates]`
+#[derive(Debug, Clone)]
+pub struct AlertTemplates {
+    emoji: bool,
+    titles: HashMap<String, String>,
+    messages: HashMap<String, String>,
+}
+
+impl Default for AlertTemplates {
+    fn default() -> Self {
+        Self { emoji: true, titles: HashMap::new(), messages: HashMap::new() }
+    }
+}
+
+impl AlertTemplates {
+    /// Overrides for unknown alert types are ignored with a warning rather than failing startup
+    pub fn from_config(config: &AlertTemplatesConfig) -> Self {
+        for key in config.titles.keys().chain(config.messages.keys()) {
+            if AlertKind::from_key(key).is_none() {
+                warn!("Ignoring alert template for unknown alert type {:?}", key);
+            }
+        }
+        Self {
+            emoji: config.emoji,
+            titles: config.titles.clone(),
+            messages: config.messages.clone(),
+        }
+    }
+
+    pub fn emoji(&self) -> bool {
+        self.emoji
+    }
+
+    /// Title and body for `kind`, falling back to the built-in wording
+    pub fn render(&self, kind: AlertKind, vars: &[(&str, String)]) -> (String, String) {
+        let title = self.titles.get(kind.key()).map(String::as_str).unwrap_or(kind.default_title());
+        let body = self.messages.get(kind.key()).map(String::as_str).unwrap_or(kind.default_template());
+        (self.finish(render_template(title, vars)), self.finish(render_template(body, vars)))
+    }
+
+    /// Drop emojis from a finished message when they are turned off
+    pub fn finish(&self, message: String) -> String {
+        if self.emoji {
+            message
+        } else {
+            strip_emoji(&message)
+        }
+    }
+}
+
+/// Replace `{name}` placeholders with their values. Unknown placeholders are left
+/// as written so a typo shows up in the alert instead of silently vanishing, and
+/// substituted values are never expanded again.
+pub fn render_template(template: &str, vars: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}')
+            .map(|end| (end, &after[..end]))
+            .and_then(|(end, name)| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| (end, v)));
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF   // Pictographs, emoticons, transport, supplemental symbols
+        | 0x2600..=0x27BF   // Miscellaneous symbols and dingbats
+        | 0x2B00..=0x2BFF   // Arrows and stars
+        | 0x2190..=0x21FF   // Arrows
+        | 0x2300..=0x23FF   // Technical (⏳, ⌛)
+        | 0xFE00..=0xFE0F   // Variation selectors
+        | 0x200D            // Zero-width joiner
+        | 0x2139)           // ℹ
+}
+
+/// Remove emojis along with the space that separated them from the text
+fn strip_emoji(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut skip_space = false;
+    for c in message.chars() {
+        if is_emoji(c) {
+            skip_space = true;
+            continue;
+        }
+        if !(skip_space && c == ' ') {
+            out.push(c);
+        }
+        skip_space = false;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_named_placeholders() {
+        let vars = [("pid", "4242".to_string()), ("binary", "/tmp/{pid}".to_string())];
+        assert_eq!(render_template("PID {pid} ({binary})", &vars), "PID 4242 (/tmp/{pid})");
+        assert_eq!(render_template("{pid} {unknown} {pid", &vars), "4242 {unknown} {pid");
+        assert_eq!(render_template("no fields", &vars), "no fields");
+    }
+
+    #[test]
+    fn overrides_fall_back_to_defaults() {
+        let mut config = AlertTemplatesConfig::default();
+        config.messages.insert("kill_failed".to_string(), "{pid} survived {signal}".to_string());
+        config.titles.insert("kill_failed".to_string(), "🚨 PID {pid} stuck".to_string());
+        config.emoji = false;
+        let templates = AlertTemplates::from_config(&config);
+        let vars = [("pid", "7".to_string()), ("signal", "SIGKILL".to_string())];
+
+        assert_eq!(templates.render(AlertKind::KillFailed, &vars),
+                   ("PID 7 stuck".to_string(), "7 survived SIGKILL".to_string()));
+        let (title, body) = templates.render(AlertKind::MalwareDetected, &[("pid", "7".to_string())]);
+        assert_eq!(title, "Malware Detected");
+        assert!(body.starts_with("Killed process PID 7 ({binary})"));
+    }
+
+    #[test]
+    fn every_kind_has_a_unique_key() {
+        for kind in AlertKind::ALL {
+            assert_eq!(AlertKind::from_key(kind.key()), Some(*kind));
+        }
+        assert_eq!(strip_emoji("🌐 Preceded by ⚠️ two"), "Preceded by two");
+    }
+}
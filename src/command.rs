@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::process::Output;
+use std::time::{Duration, Instant};
+
+/// A command that didn't finish in time; it has been killed
+#[derive(Debug, thiserror::Error)]
+#[error("{program} timed out after {timeout_secs}s")]
+pub struct CommandTimeout {
+    pub program: String,
+    pub timeout_secs: u64,
+}
+
+/// Run an external tool, killing it if it hasn't exited within `timeout` so a hung
+/// systemctl/pm2/ss can't stall the daemon loop
+pub async fn run_command(program: &str, args: &[&str], timeout: Duration) -> Result<Output> {
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    match tokio::time::timeout(timeout, child).await {
+        Ok(output) => output.with_context(|| format!("Failed to execute {}", program)),
+        Err(_) => Err(CommandTimeout {
+            program: program.to_string(),
+            timeout_secs: timeout.as_secs(),
+        }.into()),
+    }
+}
+
+/// When an integration's cached view is stale enough to query its tool again.
+/// Failures (timeouts) double the wait, up to `max_backoff`.
+#[derive(Debug, Clone)]
+pub struct RefreshSchedule {
+    interval: Option<Duration>,
+    max_backoff: Duration,
+    last_attempt: Option<Instant>,
+    failures: u32,
+}
+
+impl RefreshSchedule {
+    /// A zero interval never refreshes (the integration stays empty)
+    pub fn new(interval: Duration, max_backoff: Duration) -> Self {
+        Self {
+            interval: (!interval.is_zero()).then_some(interval),
+            max_backoff: max_backoff.max(interval),
+            last_attempt: None,
+            failures: 0,
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.is_due_at(Instant::now())
+    }
+
+    pub fn is_due_at(&self, now: Instant) -> bool {
+        match (self.interval, self.last_attempt) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(_), Some(last)) => now.saturating_duration_since(last) >= self.current_delay(),
+        }
+    }
+
+    pub fn record_success(&mut self, now: Instant) {
+        self.last_attempt = Some(now);
+        self.failures = 0;
+    }
+
+    pub fn record_failure(&mut self, now: Instant) {
+        self.last_attempt = Some(now);
+        self.failures = self.failures.saturating_add(1);
+    }
+
+    fn current_delay(&self) -> Duration {
+        let interval = self.interval.unwrap_or_default();
+        let factor = 1u32.checked_shl(self.failures.min(16)).unwrap_or(u32::MAX);
+        interval.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn kills_commands_that_exceed_the_timeout() {
+        let output = run_command("echo", &["ok"], Duration::from_secs(5)).await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+
+        let started = Instant::now();
+        let err = run_command("sleep", &["30"], Duration::from_millis(200)).await.unwrap_err();
+        assert!(err.is::<CommandTimeout>());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn backs_off_after_failures() {
+        let mut schedule = RefreshSchedule::new(Duration::from_secs(30), Duration::from_secs(100));
+        let start = Instant::now();
+        assert!(schedule.is_due_at(start));
+
+        schedule.record_success(start);
+        assert!(!schedule.is_due_at(start + Duration::from_secs(29)));
+        assert!(schedule.is_due_at(start + Duration::from_secs(30)));
+
+        schedule.record_failure(start);
+        assert!(!schedule.is_due_at(start + Duration::from_secs(59)));
+        assert!(schedule.is_due_at(start + Duration::from_secs(60)));
+
+        // Capped at max_backoff
+        schedule.record_failure(start);
+        schedule.record_failure(start);
+        assert!(schedule.is_due_at(start + Duration::from_secs(100)));
+
+        schedule.record_success(start);
+        assert!(schedule.is_due_at(start + Duration::from_secs(30)));
+
+        assert!(!RefreshSchedule::new(Duration::ZERO, Duration::from_secs(100)).is_due_at(start));
+    }
+}
@@ -1,7 +1,18 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use std::fs;
+use crate::command::RefreshSchedule;
+use crate::termination::KillSignals;
+use crate::denylist::Denylist;
+use crate::maintenance::MaintenanceWindows;
+use crate::scoring::SignalCategory;
+use crate::scan_priority::{ScanIoClass, ScanPriority};
+use crate::suspicious_paths::SuspiciousPaths;
+use crate::whitelist_override::WhitelistOverrideAction;
+use crate::syslog::{SyslogFacility, SyslogProtocol, SyslogSeverityMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -25,7 +36,12 @@ pub struct Config {
     pub canary_mode: bool,
     
     #[serde(default = "default_false")]
-    pub audit_only: bool,
+    pub audit_only: bool,  // Like dry_run, but intended actions are logged to the decisions table
+    #[serde(default)]
+    pub learning_report: LearningReportConfig,
+
+    #[serde(default = "default_false")]
+    pub paranoid_tmp_exec: bool,  // Anything executing under suspicious_path_prefixes is a threat, CPU or not
     
     #[serde(default = "default_deploy_grace")]
     pub deploy_grace_minutes: u64,
@@ -38,6 +54,10 @@ pub struct Config {
     
     #[serde(default)]
     pub whitelist: WhitelistConfig,
+    #[serde(default)]
+    pub denylist: DenylistConfig,
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindowConfig>,
     
     #[serde(default = "default_true")]
     pub adaptive_polling: bool,
@@ -46,6 +66,186 @@ pub struct Config {
     pub adaptive_polling_load_factor: f64,
     #[serde(default = "default_file_blocking")]
     pub file_blocking: FileBlockingConfig,
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,  // Days of history kept by DB maintenance
+    #[serde(default = "default_raw_history_hours")]
+    pub raw_history_hours: u64,  // Raw process_history kept before hourly rollup (0 disables rollup)
+    #[serde(default = "default_process_record_batch_size")]
+    pub process_record_batch_size: usize,  // Process samples buffered per DB write
+    #[serde(default)]
+    pub build_users: BuildUsersConfig,
+    #[serde(default = "default_sigterm_timeout")]
+    pub sigterm_timeout_seconds: u64,  // Grace period after SIGTERM before SIGKILL
+    #[serde(default = "default_sigkill_timeout")]
+    pub sigkill_timeout_seconds: u64,  // How long to wait for SIGKILL before reporting kill_failed
+    #[serde(default = "default_kill_signals")]
+    pub kill_signals: Vec<String>,  // Initial signal and optional escalation, e.g. ["SIGTERM", "SIGKILL"] or ["SIGSTOP"]
+    #[serde(default = "default_d_state_samples")]
+    pub d_state_samples: u32,  // Consecutive D-state observations before a process is skipped and alerted on
+    #[serde(default = "default_self_integrity_interval")]
+    pub self_integrity_interval_minutes: u64,  // 0 disables self-integrity checks
+    #[serde(default = "default_upgrade_marker_path")]
+    pub upgrade_marker_path: String,  // Touch before an intentional upgrade to accept new hashes
+    #[serde(default = "default_pause_file")]
+    pub pause_file: String,  // While it exists every action is downgraded to Notify ("" disables)
+    #[serde(default)]
+    pub action_delay_seconds: u64,  // Confirmation window before stopping systemd/pm2 apps and containers (0 = immediate)
+    #[serde(default = "default_action_cancel_dir")]
+    pub action_cancel_dir: String,  // `cancel-action <pid>` drops request files here
+    #[serde(default)]
+    pub collect_evidence: bool,  // SIGSTOP and copy maps/fds/environ/binary before stopping a process
+    #[serde(default = "default_evidence_dir")]
+    pub evidence_dir: String,
+    #[serde(default)]
+    pub pre_action_hook: String,  // Script that can veto enforcement; gets the event as JSON on stdin ("" disables)
+    #[serde(default = "default_pre_action_hook_timeout")]
+    pub pre_action_hook_timeout_seconds: u64,
+    #[serde(default)]
+    pub pre_action_hook_on_failure: HookFailurePolicy,
+    #[serde(default = "default_startup_warmup")]
+    pub startup_warmup_seconds: u64,  // Observe-only period after start while CPU counters stabilize
+    #[serde(default)]
+    pub thread_fingerprint: ThreadFingerprintConfig,
+    #[serde(default)]
+    pub daemonized_dropper: DaemonizedDropperConfig,
+    #[serde(default)]
+    pub web_uploads: WebUploadConfig,
+    #[serde(default)]
+    pub nginx_logs: NginxLogConfig,
+    #[serde(default)]
+    pub min_record_confidence: f32,  // Suspicious processes below this are counted but not persisted
+    #[serde(default = "default_event_socket_path")]
+    pub event_socket_path: String,  // Unix socket streaming live events to `hora-police watch` ("" disables)
+    #[serde(default = "default_false")]
+    pub journald_events: bool,  // Also write events to the systemd journal with structured fields
+    #[serde(default)]
+    pub syslog: Option<SyslogConfig>,  // Also send events to a remote syslog server (RFC 5424)
+    #[serde(default)]
+    pub collector: Option<CollectorConfig>,  // Also forward detection records to a central HTTP collector
+    #[serde(default)]
+    pub probe: ProbeConfig,
+    #[serde(default = "default_startup_report")]
+    pub startup_report: bool,  // One-time Telegram summary of environment, config and detected apps at start
+    #[serde(default = "default_true")]
+    pub exec_events: bool,  // Check new executions as they happen (builds with the `exec-events` feature only)
+    #[serde(default)]
+    pub miner_profiling: MinerProfilingConfig,
+    #[serde(default)]
+    pub spawn_chain: SpawnChainConfig,
+    #[serde(default)]
+    pub memory_scan: MemoryScanConfig,
+    #[serde(default)]
+    pub docker: DockerConfig,
+    #[serde(default)]
+    pub disk_space: DiskSpaceConfig,
+    #[serde(default)]
+    pub resource_signals: ResourceSignalsConfig,
+    #[serde(default)]
+    pub listener_signals: ListenerSignalsConfig,
+    #[serde(default)]
+    pub manager_fallback: ManagerFallback,
+    #[serde(default)]
+    pub on_permission_denied: PermissionDeniedPolicy,
+    #[serde(default)]
+    pub sudoers: SudoersConfig,
+    #[serde(default)]
+    pub accounts: AccountsConfig,
+    #[serde(default)]
+    pub kernel_modules: KernelModulesConfig,
+    #[serde(default)]
+    pub systemd_persistence: SystemdPersistenceConfig,
+    #[serde(default)]
+    pub file_growth: FileGrowthConfig,
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+    #[serde(default)]
+    pub react_detection: ReactDetectionConfig,
+    #[serde(default)]
+    pub alert_templates: AlertTemplatesConfig,
+    #[serde(default)]
+    pub require_corroboration: usize,  // Distinct signal categories needed before anything beyond Notify (0 = off)
+    #[serde(default)]
+    pub confidence_bands: ConfidenceBandsConfig,
+    #[serde(default = "default_suspicious_path_prefixes")]
+    pub suspicious_path_prefixes: Vec<String>,  // Binaries under these directories score as staged payloads
+    #[serde(default = "default_suspicious_path_substrings")]
+    pub suspicious_path_substrings: Vec<String>,  // ...and so do paths containing any of these
+}
+
+/// What to do when a systemd/PM2/Docker stop was chosen but the manager no
+/// longer knows the PID. Killing it directly can orphan a service that the
+/// manager then restarts or marks failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagerFallback {
+    DirectKill,  // SIGTERM/SIGKILL the PID anyway
+    #[default]
+    Notify,  // manager_not_found alert, process left running
+    Skip,  // Log only
+}
+
+/// What to do when the kernel refuses a kill signal (EPERM), which happens when
+/// hora-police runs without root or CAP_KILL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDeniedPolicy {
+    #[default]
+    ManagerStop,  // Stop it through systemd/PM2/Docker if it has a manager, else alert once
+    AlertOnce,  // Alert once, then leave the process alone until it restarts
+    Retry,  // Try again every cycle, alerting every time
+}
+
+/// How detections in a confidence band are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BandAction {
+    #[default]
+    Standard,  // Built-in rules: managed apps need high confidence, staging dirs are killed
+    Notify,  // Alert only
+    Enforce,  // Stop now, through the process's manager when it has one
+    NotifyThenEnforce,  // Alert now, stop after escalation_delay_seconds unless cancelled
+}
+
+/// Per-band overrides of the action choice. Medium is threat_confidence_threshold up to
+/// high_confidence_threshold; high is at or above it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceBandsConfig {
+    #[serde(default)]
+    pub medium: BandAction,
+    #[serde(default)]
+    pub high: BandAction,
+    #[serde(default = "default_escalation_delay")]
+    pub escalation_delay_seconds: u64,
+}
+
+impl Default for ConfidenceBandsConfig {
+    fn default() -> Self {
+        Self {
+            medium: BandAction::Standard,
+            high: BandAction::Standard,
+            escalation_delay_seconds: default_escalation_delay(),
+        }
+    }
+}
+
+impl ConfidenceBandsConfig {
+    /// Whether any band waits for an operator before enforcing
+    pub fn escalates(&self) -> bool {
+        self.medium == BandAction::NotifyThenEnforce || self.high == BandAction::NotifyThenEnforce
+    }
+}
+
+fn default_escalation_delay() -> u64 {
+    300
+}
+
+/// What to do when the pre-action hook can't give an answer (missing, timed out, killed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookFailurePolicy {
+    FailOpen,  // Enforce anyway
+    #[default]
+    FailClosed,  // Abort the action
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,12 +266,115 @@ pub struct FileScanningConfig {
     pub parallel_scan: bool,
     #[serde(default = "default_max_scan_threads")]
     pub max_scan_threads: usize,
+    #[serde(default = "default_true")]
+    pub kill_on_signature_match: bool,  // Tree-kill processes using a high-threat file before quarantine
+    #[serde(default = "default_signature_kill_threshold")]
+    pub signature_kill_threshold: f32,
+    #[serde(default = "default_false")]
+    pub encrypt_quarantine: bool,  // Store quarantined files as gzip+AES-256-GCM archives
+    #[serde(default = "default_false")]
+    pub aggressive_cron_cleanup: bool,  // Also drop cron lines using wget/curl/base64/eval during origin cleanup
+    #[serde(default = "default_suspicious_names")]
+    pub suspicious_names: Vec<String>,  // File-name fragments marking siblings of a malware file as part of its drop
+    #[serde(default = "default_protected_paths")]
+    pub protected_paths: Vec<String>,  // Origin cleanup never deletes anything but the malware file under these
+    #[serde(default = "default_true")]
+    pub extract_miner_iocs: bool,  // Pull pool hosts and wallets from config files next to detected miners
+    #[serde(default = "default_pool_connection_boost")]
+    pub pool_connection_boost: f32,  // Added for processes connected to one of those pools
+    #[serde(default)]
+    pub home_scan_mode: HomeScanMode,
+    #[serde(default = "default_max_scan_depth")]
+    pub max_scan_depth: usize,
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+    #[serde(default)]
+    pub signature_files: Vec<String>,  // TOML files of extra [[signature]] entries, loaded after the builtins
+    #[serde(default = "default_true")]
+    pub skip_network_filesystems: bool,  // Don't descend into NFS/SMB/sshfs/... mounts
+    #[serde(default = "default_file_io_timeout_ms")]
+    pub file_io_timeout_ms: u64,  // Per-file stat/read limit before the file is skipped
+    #[serde(default = "default_scan_nice")]
+    pub scan_nice: i32,  // Nice value of scan workers while they stat and hash files
+    #[serde(default)]
+    pub scan_ioclass: ScanIoClass,
+    #[serde(default = "default_timestomp_min_gap_hours")]
+    pub timestomp_min_gap_hours: u64,  // ctime this far past mtime marks a backdated file (0 disables)
+    #[serde(default = "default_timestomp_boost")]
+    pub timestomp_boost: f32,  // Added to the threat level of a backdated signature match, capped below signature_kill_threshold
+    #[serde(default = "default_min_free_mb_for_scan")]
+    pub min_free_mb_for_scan: u64,  // Defer full scans while MemAvailable is below this (0 disables)
+}
+
+/// Which symlinks a directory scan follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    #[default]
+    Skip,  // Never follow symlinks
+    FollowFiles,  // Scan the targets of symlinks to files, never descend into linked dirs
+    FollowAll,  // Follow links to files and dirs; each directory is walked at most once
+}
+
+/// How malware found under /home is handled; users' own binaries and scripts
+/// are the likeliest false positives, so they are only reported by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HomeScanMode {
+    Off,  // Ignore detections under /home
+    #[default]
+    ReportOnly,  // Record and alert, never kill/quarantine/delete
+    Enforce,  // Same handling as every other path
+}
+
+impl FileScanningConfig {
+    /// Handling for a detection at `path`: `home_scan_mode` under /home, enforce elsewhere
+    pub fn mode_for_path(&self, path: &Path) -> HomeScanMode {
+        if path.starts_with("/home") {
+            self.home_scan_mode
+        } else {
+            HomeScanMode::Enforce
+        }
+    }
+
+    /// Whether a signature match is severe enough to kill its processes immediately
+    pub fn should_kill_on_match(&self, threat_level: f32) -> bool {
+        self.kill_on_signature_match && threat_level >= self.signature_kill_threshold
+    }
+}
+
+fn default_signature_kill_threshold() -> f32 {
+    0.9
+}
+
+fn default_timestomp_min_gap_hours() -> u64 {
+    24
+}
+
+fn default_min_free_mb_for_scan() -> u64 {
+    128
+}
+
+fn default_timestomp_boost() -> f32 {
+    0.1
 }
 
 fn default_max_scan_threads() -> usize {
     4
 }
 
+fn default_max_scan_depth() -> usize {
+    20
+}
+
+fn default_file_io_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_scan_nice() -> i32 {
+    10
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileBlockingConfig {
     #[serde(default = "default_true")]
@@ -94,14 +397,6 @@ fn default_file_blocking() -> FileBlockingConfig {
     }
 }
 
-fn default_file_blocking() -> FileBlockingConfig {
-    FileBlockingConfig {
-        enabled: true,
-        block_recreation: true,
-        monitor_interval_seconds: 5,
-    }
-}
-
 fn default_aggressive_cleanup() -> bool {
     true
 }
@@ -122,6 +417,90 @@ fn default_adaptive_load_factor() -> f64 {
     1.5
 }
 
+fn default_retention_days() -> u64 {
+    30
+}
+
+fn default_raw_history_hours() -> u64 {
+    24
+}
+
+fn default_sigterm_timeout() -> u64 {
+    2
+}
+
+fn default_sigkill_timeout() -> u64 {
+    5
+}
+
+fn default_startup_report() -> bool {
+    true
+}
+
+fn default_suspicious_names() -> Vec<String> {
+    crate::file_quarantine::DEFAULT_SUSPICIOUS_NAMES.iter().map(|n| n.to_string()).collect()
+}
+
+fn default_suspicious_path_prefixes() -> Vec<String> {
+    crate::suspicious_paths::DEFAULT_PREFIXES.iter().map(|p| p.to_string()).collect()
+}
+
+fn default_suspicious_path_substrings() -> Vec<String> {
+    crate::suspicious_paths::DEFAULT_SUBSTRINGS.iter().map(|s| s.to_string()).collect()
+}
+
+fn default_protected_paths() -> Vec<String> {
+    crate::file_quarantine::DEFAULT_PROTECTED_PATHS.iter().map(|p| p.to_string()).collect()
+}
+
+fn default_pool_connection_boost() -> f32 {
+    0.5
+}
+
+fn default_kill_signals() -> Vec<String> {
+    vec!["SIGTERM".to_string(), "SIGKILL".to_string()]
+}
+
+fn default_d_state_samples() -> u32 {
+    3
+}
+
+fn default_self_integrity_interval() -> u64 {
+    10
+}
+
+fn default_upgrade_marker_path() -> String {
+    "/var/lib/hora-police/upgrade-in-progress".to_string()
+}
+
+fn default_pause_file() -> String {
+    "/run/hora-police.pause".to_string()
+}
+
+fn default_startup_warmup() -> u64 {
+    30
+}
+
+fn default_evidence_dir() -> String {
+    "/var/lib/hora-police/evidence".to_string()
+}
+
+fn default_pre_action_hook_timeout() -> u64 {
+    10
+}
+
+fn default_action_cancel_dir() -> String {
+    "/var/lib/hora-police/cancel".to_string()
+}
+
+fn default_event_socket_path() -> String {
+    "/run/hora-police/events.sock".to_string()
+}
+
+fn default_process_record_batch_size() -> usize {
+    100
+}
+
 fn default_file_scanning() -> FileScanningConfig {
     FileScanningConfig {
         enabled: true,
@@ -139,77 +518,1173 @@ fn default_file_scanning() -> FileScanningConfig {
         incremental_scan: true,
         parallel_scan: true,
         max_scan_threads: 4,
+        kill_on_signature_match: true,
+        signature_kill_threshold: 0.9,
+        encrypt_quarantine: false,
+        aggressive_cron_cleanup: false,
+        suspicious_names: default_suspicious_names(),
+        protected_paths: default_protected_paths(),
+        extract_miner_iocs: true,
+        pool_connection_boost: default_pool_connection_boost(),
+        home_scan_mode: HomeScanMode::ReportOnly,
+        max_scan_depth: default_max_scan_depth(),
+        symlink_policy: SymlinkPolicy::Skip,
+        signature_files: Vec::new(),
+        skip_network_filesystems: true,
+        file_io_timeout_ms: default_file_io_timeout_ms(),
+        scan_nice: default_scan_nice(),
+        scan_ioclass: ScanIoClass::default(),
+        timestomp_min_gap_hours: default_timestomp_min_gap_hours(),
+        timestomp_boost: default_timestomp_boost(),
+        min_free_mb_for_scan: default_min_free_mb_for_scan(),
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
     pub bot_token: String,
-    pub chat_id: String,
+    #[serde(default)]
+    pub chat_id: String,  // Legacy single chat, receives every severity
     pub daily_report_time: String, // HH:MM format
+    #[serde(default)]
+    pub chats: Vec<TelegramChat>,
+    /// Days between weekly "report card" summaries (0 disables them)
+    #[serde(default = "default_weekly_report_days")]
+    pub weekly_report_days: u64,
+}
+
+fn default_weekly_report_days() -> u64 {
+    7
+}
+
+/// Remote syslog server receiving every daemon event as an RFC 5424 message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogConfig {
+    pub host: String,
+    #[serde(default = "default_syslog_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    #[serde(default)]
+    pub facility: SyslogFacility,
+    #[serde(default)]
+    pub severity: SyslogSeverityMap,  // Syslog severity per alert severity
+    #[serde(default = "default_syslog_app_name")]
+    pub app_name: String,
+}
+
+fn default_syslog_port() -> u16 {
+    514
+}
+
+fn default_syslog_app_name() -> String {
+    "hora-police".to_string()
+}
+
+/// Central HTTP endpoint receiving every kill, decision, malware file and safety
+/// event as JSON, alongside the local database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorConfig {
+    pub url: String,
+    #[serde(default)]
+    pub auth_token: String,  // Sent as a bearer token ("" sends none)
+    #[serde(default = "default_collector_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_collector_timeout_ms() -> u64 {
+    5000
+}
+
+/// HTTP endpoint serving daemon status and self-metrics as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,  // Start it without `--probe` (e.g. from a systemd unit)
+    #[serde(default = "default_probe_bind_address")]
+    pub bind_address: String,  // IP address only; use 0.0.0.0 or :: to expose it beyond localhost
+    #[serde(default = "default_probe_port")]
+    pub port: u16,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_probe_bind_address(),
+            port: default_probe_port(),
+        }
+    }
+}
+
+impl ProbeConfig {
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        let ip: IpAddr = self.bind_address.parse()
+            .with_context(|| format!("probe.bind_address {:?} is not an IP address", self.bind_address))?;
+        if self.port == 0 {
+            anyhow::bail!("probe.port must not be 0");
+        }
+        Ok(SocketAddr::new(ip, self.port))
+    }
+}
+
+fn default_probe_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_probe_port() -> u16 {
+    9999
+}
+
+/// Additional chat that only receives alerts at or above `min_severity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramChat {
+    pub chat_id: String,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Per-alert overrides of the Telegram wording, keyed by alert type (see
+/// `alert_templates::AlertKind`). Unset types keep the built-in text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertTemplatesConfig {
+    #[serde(default = "default_true")]
+    pub emoji: bool,  // false strips emojis from every alert
+    #[serde(default)]
+    pub titles: HashMap<String, String>,
+    #[serde(default)]
+    pub messages: HashMap<String, String>,  // Bodies with {placeholder} fields
+}
+
+impl Default for AlertTemplatesConfig {
+    fn default() -> Self {
+        Self { emoji: true, titles: HashMap::new(), messages: HashMap::new() }
+    }
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::Info
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoTuneConfig {
     #[serde(default = "default_true")]
     pub enabled: bool,
     pub vcpu_override: Option<usize>,
     pub ram_override_mb: Option<u64>,
+    /// Share of one core (percent) a single process must hold to be flagged;
+    /// divided by the vCPU count to get the system-wide threshold
+    #[serde(default = "default_per_core_threshold")]
+    pub per_core_threshold: f32,
+}
+
+fn default_per_core_threshold() -> f32 {
+    80.0
+}
+
+impl Default for AutoTuneConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vcpu_override: None,
+            ram_override_mb: None,
+            per_core_threshold: default_per_core_threshold(),
+        }
+    }
 }
 
 fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct WhitelistConfig {
-    #[serde(default = "default_true")]
-    pub auto_detect: bool,
+/// Users (names or UIDs) whose builds legitimately peg the CPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildUsersConfig {
     #[serde(default)]
-    pub manual_patterns: Vec<String>,
+    pub users: Vec<String>,
+    #[serde(default = "default_build_cpu_multiplier")]
+    pub cpu_threshold_multiplier: f32,
+    #[serde(default = "default_build_duration_multiplier")]
+    pub duration_multiplier: f32,
+    #[serde(default)]
+    pub relaxed_cgroup_prefixes: Vec<String>,  // Cgroup path prefixes of CI/build workloads (e.g. a runner namespace)
+    #[serde(default)]
+    pub relaxed_cgroup_action: RelaxedCgroupAction,
 }
 
-impl Config {
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = fs::read_to_string(path.as_ref())
-            .with_context(|| format!("Failed to read config from {:?}", path.as_ref()))?;
-        
-        let config: Config = toml::from_str(&content)
-            .context("Failed to parse config TOML")?;
-        
-        Ok(config)
-    }
+/// How processes under `relaxed_cgroup_prefixes` are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelaxedCgroupAction {
+    #[default]
+    Relax,  // The build users' multipliers apply to their CPU threshold and window
+    Notify,  // Usual thresholds, but never stopped, only alerted on
+}
 
-    pub fn default() -> Self {
+impl Default for BuildUsersConfig {
+    fn default() -> Self {
         Self {
-            cpu_threshold: 20.0,
-            duration_minutes: 5,
-            real_time_alerts: false,
-            auto_kill: true,
-            learning_mode: true,
-            database_path: "/var/lib/hora-police/intelligence.db".to_string(),
-            telegram: None,
-            polling_interval_ms: 5000, // 5 seconds
-            threat_confidence_threshold: 0.7,
-            file_scanning: default_file_scanning(),
-            dry_run: false,
-            canary_mode: false,
-            audit_only: false,
-            deploy_grace_minutes: 10,
-            high_confidence_threshold: 0.95,
-            auto_tune: AutoTuneConfig {
-                enabled: true,
-                vcpu_override: None,
-                ram_override_mb: None,
-            },
-            whitelist: WhitelistConfig {
-                auto_detect: true,
-                manual_patterns: Vec::new(),
-            },
-            adaptive_polling: true,
-            adaptive_polling_load_factor: 1.5,
-            file_blocking: default_file_blocking(),
+            users: Vec::new(),
+            cpu_threshold_multiplier: default_build_cpu_multiplier(),
+            duration_multiplier: default_build_duration_multiplier(),
+            relaxed_cgroup_prefixes: Vec::new(),
+            relaxed_cgroup_action: RelaxedCgroupAction::default(),
         }
     }
 }
 
+fn default_build_cpu_multiplier() -> f32 {
+    2.0
+}
+
+fn default_build_duration_multiplier() -> f32 {
+    3.0
+}
+
+/// Miner fingerprint: one busy thread per vCPU from a writable location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadFingerprintConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_thread_count_tolerance")]
+    pub tolerance: usize,  // Allowed |threads - vcpu_count| difference
+    #[serde(default = "default_thread_min_cpu")]
+    pub min_cpu_percent: f32,
+    #[serde(default = "default_thread_confidence_boost")]
+    pub confidence_boost: f32,
+}
+
+impl Default for ThreadFingerprintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tolerance: default_thread_count_tolerance(),
+            min_cpu_percent: default_thread_min_cpu(),
+            confidence_boost: default_thread_confidence_boost(),
+        }
+    }
+}
+
+fn default_thread_count_tolerance() -> usize {
+    1
+}
+
+fn default_thread_min_cpu() -> f32 {
+    50.0
+}
+
+fn default_thread_confidence_boost() -> f32 {
+    0.25
+}
+
+/// Young processes from writable dirs reading CPU topology (needs root to inspect fds/maps)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinerProfilingConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    #[serde(default = "default_profiling_window")]
+    pub new_process_seconds: u64,  // Only processes younger than this are probed
+    #[serde(default = "default_profiling_boost")]
+    pub confidence_boost: f32,
+}
+
+impl Default for MinerProfilingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            new_process_seconds: default_profiling_window(),
+            confidence_boost: default_profiling_boost(),
+        }
+    }
+}
+
+fn default_profiling_window() -> u64 {
+    120
+}
+
+fn default_profiling_boost() -> f32 {
+    0.25
+}
+
+/// Web-facing daemons running a shell that runs a downloader or a staged binary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnChainConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_spawn_chain_daemons")]
+    pub daemons: Vec<String>,  // Executable or comm names; a trailing version (php-fpm8.2) also matches
+    #[serde(default = "default_false")]
+    pub kill_shell: bool,  // Also act on the shell between the daemon and the payload
+}
+
+impl Default for SpawnChainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            daemons: default_spawn_chain_daemons(),
+            kill_shell: false,
+        }
+    }
+}
+
+fn default_spawn_chain_daemons() -> Vec<String> {
+    ["nginx", "node", "nodejs", "php-fpm", "apache2", "httpd"].iter().map(|s| s.to_string()).collect()
+}
+
+/// Search the anonymous memory of flagged processes for mining protocol strings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryScanConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub only_fileless: bool,  // Only processes without an executable on disk (memfd, deleted)
+    #[serde(default = "default_memory_scan_tokens")]
+    pub tokens: Vec<String>,  // Matched case-insensitively
+    #[serde(default = "default_memory_scan_max_read_mb")]
+    pub max_read_mb: u64,  // Per process, across all regions
+    #[serde(default = "default_memory_scan_max_region_mb")]
+    pub max_region_mb: u64,  // Per region; larger mappings are only read from the start
+    #[serde(default = "default_memory_scan_boost")]
+    pub confidence_boost: f32,
+}
+
+impl Default for MemoryScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            only_fileless: true,
+            tokens: default_memory_scan_tokens(),
+            max_read_mb: default_memory_scan_max_read_mb(),
+            max_region_mb: default_memory_scan_max_region_mb(),
+            confidence_boost: default_memory_scan_boost(),
+        }
+    }
+}
+
+fn default_memory_scan_tokens() -> Vec<String> {
+    [
+        "stratum+tcp://", "stratum+ssl://", "stratum2+tcp://",
+        "mining.subscribe", "mining.authorize", "mining.submit",
+        "cryptonight", "randomx", "donate-level",
+    ].iter().map(|t| t.to_string()).collect()
+}
+
+fn default_memory_scan_max_read_mb() -> u64 {
+    16
+}
+
+fn default_memory_scan_max_region_mb() -> u64 {
+    4
+}
+
+fn default_memory_scan_boost() -> f32 {
+    0.5
+}
+
+/// Enforcement against processes running inside Docker containers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_false")]
+    pub disable_restart_policy: bool,  // `docker update --restart=no` before stopping
+    #[serde(default = "default_false")]
+    pub force_kill: bool,  // `docker kill` instead of `docker stop`
+    #[serde(default = "default_docker_stop_timeout")]
+    pub stop_timeout_seconds: u64,
+}
+
+impl Default for DockerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            disable_restart_policy: false,
+            force_kill: false,
+            stop_timeout_seconds: default_docker_stop_timeout(),
+        }
+    }
+}
+
+fn default_docker_stop_timeout() -> u64 {
+    10
+}
+
+/// "Dropped and daemonized": no controlling terminal, started recently, running
+/// from a writable location and climbing in CPU. Interactive processes have a TTY.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonizedDropperConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_daemonized_max_age")]
+    pub max_age_seconds: u64,  // "Recently started" cut-off
+    #[serde(default = "default_daemonized_boost")]
+    pub confidence_boost: f32,
+}
+
+impl Default for DaemonizedDropperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_seconds: default_daemonized_max_age(),
+            confidence_boost: default_daemonized_boost(),
+        }
+    }
+}
+
+/// Executables under web upload directories: the webshell -> miner drop point.
+/// In `dirs`, `*` matches exactly one path component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebUploadConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_web_upload_dirs")]
+    pub dirs: Vec<String>,
+    #[serde(default = "default_web_upload_boost")]
+    pub confidence_boost: f32,
+    #[serde(default = "default_web_upload_recent_minutes")]
+    pub recent_minutes: u64,  // Files changed this recently get the extra boost
+    #[serde(default = "default_web_upload_recent_boost")]
+    pub recent_boost: f32,
+}
+
+impl Default for WebUploadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dirs: default_web_upload_dirs(),
+            confidence_boost: default_web_upload_boost(),
+            recent_minutes: default_web_upload_recent_minutes(),
+            recent_boost: default_web_upload_recent_boost(),
+        }
+    }
+}
+
+fn default_web_upload_dirs() -> Vec<String> {
+    [
+        "/var/www/*/uploads",
+        "/var/www/*/public/uploads",
+        "/var/www/*/wp-content/uploads",
+        "/var/www/*/storage/app/public",
+        "/var/www/*/public/storage",
+        "/srv/www/*/uploads",
+        "/home/*/public_html/uploads",
+        "/home/*/public_html/wp-content/uploads",
+        "/usr/share/nginx/html/uploads",
+    ].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_web_upload_boost() -> f32 {
+    0.25
+}
+
+fn default_web_upload_recent_minutes() -> u64 {
+    60
+}
+
+fn default_web_upload_recent_boost() -> f32 {
+    0.15
+}
+
+fn default_daemonized_max_age() -> u64 {
+    1800
+}
+
+fn default_daemonized_boost() -> f32 {
+    0.2
+}
+
+/// Open file descriptor and TCP/UDP socket counts as abuse signals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceSignalsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_fd_threshold")]
+    pub fd_threshold: usize,
+    #[serde(default = "default_fd_boost")]
+    pub fd_boost: f32,
+    #[serde(default = "default_socket_threshold")]
+    pub socket_threshold: usize,
+    #[serde(default = "default_socket_boost")]
+    pub socket_boost: f32,
+}
+
+impl Default for ResourceSignalsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fd_threshold: default_fd_threshold(),
+            fd_boost: default_fd_boost(),
+            socket_threshold: default_socket_threshold(),
+            socket_boost: default_socket_boost(),
+        }
+    }
+}
+
+fn default_fd_threshold() -> usize {
+    1024
+}
+
+fn default_fd_boost() -> f32 {
+    0.1
+}
+
+fn default_socket_threshold() -> usize {
+    256
+}
+
+fn default_socket_boost() -> f32 {
+    0.2
+}
+
+/// Listening sockets opened from writable locations. Ports that Nginx proxies to are
+/// legitimate backends and never count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerSignalsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_listener_boost")]
+    pub confidence_boost: f32,
+    #[serde(default = "default_listener_port_boost")]
+    pub port_boost: f32,  // Extra when the port is in suspicious_ports
+    #[serde(default = "default_listener_suspicious_ports")]
+    pub suspicious_ports: Vec<u16>,  // Stratum proxy and C2 favourites
+}
+
+impl Default for ListenerSignalsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            confidence_boost: default_listener_boost(),
+            port_boost: default_listener_port_boost(),
+            suspicious_ports: default_listener_suspicious_ports(),
+        }
+    }
+}
+
+fn default_listener_boost() -> f32 {
+    0.3
+}
+
+fn default_listener_port_boost() -> f32 {
+    0.2
+}
+
+fn default_listener_suspicious_ports() -> Vec<u16> {
+    vec![1337, 3333, 4444, 5555, 6666, 6667, 7777, 9999, 14433, 14444, 31337, 45560, 45700]
+}
+
+/// Free-space monitoring for the filesystems holding the database and quarantine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskSpaceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_disk_check_interval")]
+    pub check_interval_minutes: u64,
+    #[serde(default = "default_disk_warn_free_mb")]
+    pub warn_free_mb: u64,
+    #[serde(default = "default_disk_critical_free_mb")]
+    pub critical_free_mb: u64,  // Below this, malware is deleted instead of quarantined
+}
+
+impl Default for DiskSpaceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_minutes: default_disk_check_interval(),
+            warn_free_mb: default_disk_warn_free_mb(),
+            critical_free_mb: default_disk_critical_free_mb(),
+        }
+    }
+}
+
+fn default_disk_check_interval() -> u64 {
+    10
+}
+
+fn default_disk_warn_free_mb() -> u64 {
+    1024
+}
+
+fn default_disk_critical_free_mb() -> u64 {
+    100
+}
+
+/// Watch /etc/sudoers and /etc/sudoers.d for privilege-escalation persistence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SudoersConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_false")]
+    pub restore: bool,  // Remove flagged grants (backup + rollback manifest first)
+    #[serde(default = "default_sudoers_principals")]
+    pub allowed_principals: Vec<String>,  // New grants to anyone else are flagged
+}
+
+impl Default for SudoersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            restore: false,
+            allowed_principals: default_sudoers_principals(),
+        }
+    }
+}
+
+fn default_sudoers_principals() -> Vec<String> {
+    ["root", "%sudo", "%admin", "%wheel"].iter().map(|s| s.to_string()).collect()
+}
+
+/// Onboarding mode: enforce nothing (as audit_only) and, every `observation_hours`,
+/// report what would have been acted on along with the whitelist that was built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningReportConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    #[serde(default = "default_learning_observation_hours")]
+    pub observation_hours: u64,
+    #[serde(default = "default_learning_report_path")]
+    pub output_path: String,  // Markdown report, overwritten each period; empty disables
+    #[serde(default = "default_true")]
+    pub telegram: bool,
+}
+
+impl Default for LearningReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            observation_hours: default_learning_observation_hours(),
+            output_path: default_learning_report_path(),
+            telegram: true,
+        }
+    }
+}
+
+fn default_learning_observation_hours() -> u64 {
+    24
+}
+
+fn default_learning_report_path() -> String {
+    "/var/lib/hora-police/learning-report.md".to_string()
+}
+
+/// Watch /etc/passwd and /etc/shadow for account-creation persistence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_false")]
+    pub restore: bool,  // Remove new UID 0 accounts (backups + rollback manifest first)
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            restore: false,
+        }
+    }
+}
+
+/// Alert on kernel modules loaded after startup (rootkits hiding a miner)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KernelModulesConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_kernel_module_allowlist")]
+    pub allowlist: Vec<String>,  // Expected on-demand loads; `nf_*` matches a prefix. Only applies to shipped modules
+}
+
+impl Default for KernelModulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowlist: default_kernel_module_allowlist(),
+        }
+    }
+}
+
+fn default_kernel_module_allowlist() -> Vec<String> {
+    ["nf_*", "nft_*", "xt_*", "ip_*", "ip6_*", "iptable_*", "ip6table_*", "br_netfilter", "bridge",
+     "veth", "overlay", "tun", "loop", "fuse", "dm_*", "xfrm_*", "binfmt_misc", "isofs", "squashfs"]
+        .iter().map(|s| s.to_string()).collect()
+}
+
+/// New or changed .service files whose Exec* lines run from writable dirs or fetch payloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemdPersistenceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_false")]
+    pub remove: bool,  // Stop, disable and delete flagged units (backup + rollback manifest first)
+}
+
+impl Default for SystemdPersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            remove: false,
+        }
+    }
+}
+
+/// How often the pm2/systemd/nginx views are re-queried, and how long each command may run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrationsConfig {
+    #[serde(default = "default_command_timeout")]
+    pub command_timeout_seconds: u64,  // systemctl/pm2/ss/docker are killed after this long
+    #[serde(default = "default_pm2_refresh")]
+    pub pm2_refresh_seconds: u64,  // 0 never queries pm2
+    #[serde(default = "default_systemd_refresh")]
+    pub systemd_refresh_seconds: u64,  // 0 never queries systemctl
+    #[serde(default = "default_nginx_refresh")]
+    pub nginx_refresh_seconds: u64,  // 0 never queries ss
+    #[serde(default = "default_integration_max_backoff")]
+    pub max_backoff_seconds: u64,  // Refresh interval doubles after each timeout, up to this
+}
+
+impl Default for IntegrationsConfig {
+    fn default() -> Self {
+        Self {
+            command_timeout_seconds: default_command_timeout(),
+            pm2_refresh_seconds: default_pm2_refresh(),
+            systemd_refresh_seconds: default_systemd_refresh(),
+            nginx_refresh_seconds: default_nginx_refresh(),
+            max_backoff_seconds: default_integration_max_backoff(),
+        }
+    }
+}
+
+impl IntegrationsConfig {
+    pub fn command_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.command_timeout_seconds.max(1))
+    }
+
+    pub fn schedule(&self, refresh_seconds: u64) -> RefreshSchedule {
+        RefreshSchedule::new(
+            std::time::Duration::from_secs(refresh_seconds),
+            std::time::Duration::from_secs(self.max_backoff_seconds),
+        )
+    }
+}
+
+/// Heuristics for abuse of Node server frameworks (React Flight payloads, injected miners)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactDetectionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_react_frameworks")]
+    pub frameworks: Vec<String>,  // Command-line keywords of the frameworks whose CPU use is judged
+    #[serde(default = "default_react_cpu_threshold")]
+    pub cpu_threshold: f32,
+    #[serde(default = "default_react_sustained_cpu_threshold")]
+    pub sustained_cpu_threshold: f32,  // Higher CPU tier; replaces the cpu_threshold signal rather than adding to it
+    #[serde(default = "default_react_min_confidence")]
+    pub min_confidence: f32,  // Detections need more than this
+}
+
+impl Default for ReactDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            frameworks: default_react_frameworks(),
+            cpu_threshold: default_react_cpu_threshold(),
+            sustained_cpu_threshold: default_react_sustained_cpu_threshold(),
+            min_confidence: default_react_min_confidence(),
+        }
+    }
+}
+
+fn default_react_frameworks() -> Vec<String> {
+    vec!["react".to_string(), "next".to_string(), "remix".to_string()]
+}
+
+fn default_react_cpu_threshold() -> f32 {
+    15.0
+}
+
+fn default_react_sustained_cpu_threshold() -> f32 {
+    20.0
+}
+
+fn default_react_min_confidence() -> f32 {
+    0.5
+}
+
+fn default_command_timeout() -> u64 {
+    10
+}
+
+fn default_pm2_refresh() -> u64 {
+    30
+}
+
+fn default_systemd_refresh() -> u64 {
+    60
+}
+
+fn default_nginx_refresh() -> u64 {
+    60
+}
+
+fn default_integration_max_backoff() -> u64 {
+    600
+}
+
+/// Flag files growing fast in staging directories and signature-scan them right away
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileGrowthConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_growth_paths")]
+    pub paths: Vec<String>,
+    #[serde(default = "default_growth_sample_interval")]
+    pub sample_interval_seconds: u64,
+    #[serde(default = "default_growth_min_bytes_per_second")]
+    pub min_bytes_per_second: u64,
+    #[serde(default = "default_growth_max_tracked")]
+    pub max_tracked_files: usize,  // Bound on files whose size is remembered between samples
+}
+
+impl Default for FileGrowthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            paths: default_growth_paths(),
+            sample_interval_seconds: default_growth_sample_interval(),
+            min_bytes_per_second: default_growth_min_bytes_per_second(),
+            max_tracked_files: default_growth_max_tracked(),
+        }
+    }
+}
+
+fn default_growth_paths() -> Vec<String> {
+    ["/tmp", "/var/tmp", "/dev/shm"].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_growth_sample_interval() -> u64 {
+    30
+}
+
+fn default_growth_min_bytes_per_second() -> u64 {
+    1024 * 1024
+}
+
+fn default_growth_max_tracked() -> usize {
+    10_000
+}
+
+/// Access-log scanning for the web exploitation that precedes a miner drop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NginxLogConfig {
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    #[serde(default = "default_access_logs")]
+    pub access_logs: Vec<String>,
+    #[serde(default = "default_correlation_window")]
+    pub correlation_window_minutes: u64,
+    #[serde(default = "default_correlation_boost")]
+    pub confidence_boost: f32,
+    #[serde(default = "default_correlated_signals")]
+    pub correlated_signals: Vec<SignalCategory>,  // Only processes already showing one of these get the boost
+    #[serde(default = "default_max_tracked_attempts")]
+    pub max_tracked_attempts: usize,
+    #[serde(default = "default_max_url_length")]
+    pub max_url_length: usize,
+    #[serde(default = "default_post_burst_threshold")]
+    pub post_burst_threshold: usize,  // POSTs from one client to one path per minute
+}
+
+impl Default for NginxLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            access_logs: default_access_logs(),
+            correlation_window_minutes: default_correlation_window(),
+            confidence_boost: default_correlation_boost(),
+            correlated_signals: default_correlated_signals(),
+            max_tracked_attempts: default_max_tracked_attempts(),
+            max_url_length: default_max_url_length(),
+            post_burst_threshold: default_post_burst_threshold(),
+        }
+    }
+}
+
+fn default_access_logs() -> Vec<String> {
+    vec!["/var/log/nginx/access.log".to_string()]
+}
+
+fn default_correlation_window() -> u64 {
+    30
+}
+
+fn default_correlation_boost() -> f32 {
+    0.2
+}
+
+/// Evidence of a dropped payload; CPU use alone isn't tied to the web exploit
+fn default_correlated_signals() -> Vec<SignalCategory> {
+    vec![
+        SignalCategory::SuspiciousLocation,
+        SignalCategory::MiningCommand,
+        SignalCategory::Fileless,
+        SignalCategory::EncodedPayload,
+        SignalCategory::SpawnChain,
+    ]
+}
+
+fn default_max_tracked_attempts() -> usize {
+    1000
+}
+
+fn default_max_url_length() -> usize {
+    2048
+}
+
+fn default_post_burst_threshold() -> usize {
+    20
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistConfig {
+    #[serde(default = "default_true")]
+    pub auto_detect: bool,
+    #[serde(default)]
+    pub manual_patterns: Vec<String>,
+    #[serde(default = "default_whitelist_revalidate")]
+    pub revalidate_interval_minutes: u64,  // Re-hash whitelisted binaries to catch replacements (0 = off)
+    #[serde(default)]
+    pub override_action: WhitelistOverrideAction,  // When a whitelisted process shows a strong malware signal
+    #[serde(default = "default_whitelist_override_min_strength")]
+    pub override_min_strength: f32,  // Weakest signal (0.0-1.0) that overrides a whitelist match
+}
+
+impl Default for WhitelistConfig {
+    fn default() -> Self {
+        Self {
+            auto_detect: default_true(),
+            manual_patterns: Vec::new(),
+            revalidate_interval_minutes: default_whitelist_revalidate(),
+            override_action: WhitelistOverrideAction::default(),
+            override_min_strength: default_whitelist_override_min_strength(),
+        }
+    }
+}
+
+fn default_whitelist_revalidate() -> u64 {
+    60
+}
+
+fn default_whitelist_override_min_strength() -> f32 {
+    0.8
+}
+
+/// Binaries stopped unconditionally, overriding the whitelist and manager guards
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DenylistConfig {
+    #[serde(default)]
+    pub paths: Vec<String>,  // Exact executable paths
+    #[serde(default)]
+    pub name_patterns: Vec<String>,  // Regexes on the executable file name or comm
+    #[serde(default)]
+    pub sha256: Vec<String>,  // Hashes of the running executable
+}
+
+/// Predictable heavy-CPU period (backup, log rotation, batch job) during which
+/// stopping actions are downgraded to Notify. Set `cron` (with `duration_minutes`)
+/// or a daily `start`/`end` range in HH:MM local time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindowConfig {
+    pub name: String,
+    #[serde(default)]
+    pub cron: Option<String>,
+    #[serde(default = "default_maintenance_duration")]
+    pub duration_minutes: u64,
+    #[serde(default)]
+    pub start: Option<String>,
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+fn default_maintenance_duration() -> u64 {
+    60
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Failed to read config from {:?}", path.as_ref()))?;
+        
+        let config: Config = toml::from_str(&content)
+            .context("Failed to parse config TOML")?;
+
+        KillSignals::parse(&config.kill_signals).context("Invalid kill_signals")?;
+        Denylist::from_config(&config.denylist).context("Invalid denylist")?;
+        MaintenanceWindows::from_config(&config.maintenance_windows)?;
+        ScanPriority::from_config(&config.file_scanning).context("Invalid file_scanning priority")?;
+        SuspiciousPaths::from_config(&config).context("Invalid suspicious paths")?;
+        config.probe.socket_addr().context("Invalid probe address")?;
+        if config.learning_report.enabled && config.learning_report.observation_hours == 0 {
+            anyhow::bail!("learning_report.observation_hours must be at least 1");
+        }
+        if config.syslog.as_ref().is_some_and(|s| s.host.is_empty()) {
+            anyhow::bail!("syslog.host must not be empty");
+        }
+        if let Some(ref collector) = config.collector {
+            if !collector.url.starts_with("http://") && !collector.url.starts_with("https://") {
+                anyhow::bail!("collector.url must be an http:// or https:// URL, got {:?}", collector.url);
+            }
+        }
+        if config.confidence_bands.escalates() && config.confidence_bands.escalation_delay_seconds == 0 {
+            anyhow::bail!("confidence_bands.escalation_delay_seconds must be at least 1 with notify_then_enforce");
+        }
+        if !(0.0..=1.0).contains(&config.whitelist.override_min_strength) {
+            anyhow::bail!("whitelist.override_min_strength must be between 0.0 and 1.0, got {}",
+                          config.whitelist.override_min_strength);
+        }
+        
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cpu_threshold: 20.0,
+            duration_minutes: 5,
+            real_time_alerts: false,
+            auto_kill: true,
+            learning_mode: true,
+            database_path: "/var/lib/hora-police/intelligence.db".to_string(),
+            telegram: None,
+            polling_interval_ms: 5000, // 5 seconds
+            threat_confidence_threshold: 0.7,
+            file_scanning: default_file_scanning(),
+            dry_run: false,
+            canary_mode: false,
+            audit_only: false,
+            learning_report: LearningReportConfig::default(),
+            paranoid_tmp_exec: false,
+            deploy_grace_minutes: 10,
+            high_confidence_threshold: 0.95,
+            auto_tune: AutoTuneConfig::default(),
+            whitelist: WhitelistConfig::default(),
+            denylist: DenylistConfig::default(),
+            maintenance_windows: Vec::new(),
+            adaptive_polling: true,
+            adaptive_polling_load_factor: 1.5,
+            file_blocking: default_file_blocking(),
+            retention_days: 30,
+            raw_history_hours: default_raw_history_hours(),
+            process_record_batch_size: 100,
+            build_users: BuildUsersConfig::default(),
+            sigterm_timeout_seconds: 2,
+            sigkill_timeout_seconds: 5,
+            kill_signals: default_kill_signals(),
+            d_state_samples: default_d_state_samples(),
+            self_integrity_interval_minutes: 10,
+            upgrade_marker_path: default_upgrade_marker_path(),
+            pause_file: default_pause_file(),
+            action_delay_seconds: 0,
+            action_cancel_dir: default_action_cancel_dir(),
+            collect_evidence: false,
+            evidence_dir: default_evidence_dir(),
+            pre_action_hook: String::new(),
+            pre_action_hook_timeout_seconds: default_pre_action_hook_timeout(),
+            pre_action_hook_on_failure: HookFailurePolicy::default(),
+            startup_warmup_seconds: 30,
+            thread_fingerprint: ThreadFingerprintConfig::default(),
+            daemonized_dropper: DaemonizedDropperConfig::default(),
+            web_uploads: WebUploadConfig::default(),
+            nginx_logs: NginxLogConfig::default(),
+            min_record_confidence: 0.0,
+            event_socket_path: default_event_socket_path(),
+            journald_events: false,
+            syslog: None,
+            collector: None,
+            probe: ProbeConfig::default(),
+            startup_report: true,
+            exec_events: true,
+            miner_profiling: MinerProfilingConfig::default(),
+            spawn_chain: SpawnChainConfig::default(),
+            memory_scan: MemoryScanConfig::default(),
+            docker: DockerConfig::default(),
+            disk_space: DiskSpaceConfig::default(),
+            resource_signals: ResourceSignalsConfig::default(),
+            listener_signals: ListenerSignalsConfig::default(),
+            manager_fallback: ManagerFallback::default(),
+            on_permission_denied: PermissionDeniedPolicy::default(),
+            sudoers: SudoersConfig::default(),
+            accounts: AccountsConfig::default(),
+            kernel_modules: KernelModulesConfig::default(),
+            systemd_persistence: SystemdPersistenceConfig::default(),
+            file_growth: FileGrowthConfig::default(),
+            integrations: IntegrationsConfig::default(),
+            react_detection: ReactDetectionConfig::default(),
+            alert_templates: AlertTemplatesConfig::default(),
+            require_corroboration: 0,
+            confidence_bands: ConfidenceBandsConfig::default(),
+            suspicious_path_prefixes: default_suspicious_path_prefixes(),
+            suspicious_path_substrings: default_suspicious_path_substrings(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_detections_default_to_report_only() {
+        let mut scanning = default_file_scanning();
+        assert_eq!(scanning.mode_for_path(Path::new("/home/alice/bin/tool")), HomeScanMode::ReportOnly);
+        assert_eq!(scanning.mode_for_path(Path::new("/tmp/xmrig")), HomeScanMode::Enforce);
+        // Only the /home tree, not lookalike prefixes
+        assert_eq!(scanning.mode_for_path(Path::new("/homework/x")), HomeScanMode::Enforce);
+
+        scanning.home_scan_mode = toml::from_str::<std::collections::HashMap<String, HomeScanMode>>("m = \"off\"").unwrap()["m"];
+        assert_eq!(scanning.mode_for_path(Path::new("/home/alice")), HomeScanMode::Off);
+    }
+
+    #[test]
+    fn omitted_whitelist_section_keeps_its_defaults() {
+        let config: Config = toml::from_str(concat!(
+            "cpu_threshold = 20.0\n",
+            "duration_minutes = 5\n",
+            "real_time_alerts = true\n",
+            "auto_kill = true\n",
+            "learning_mode = true\n",
+            "database_path = \"/var/lib/hora-police/intelligence.db\"\n",
+            "polling_interval_ms = 5000\n",
+            "threat_confidence_threshold = 0.7\n",
+        )).unwrap();
+        assert!(config.whitelist.auto_detect);
+        assert_eq!(config.whitelist.override_action, WhitelistOverrideAction::Notify);
+        assert_eq!(config.whitelist.override_min_strength, 0.8);
+        // 0 would turn fingerprint re-validation off
+        assert_eq!(config.whitelist.revalidate_interval_minutes, 60);
+    }
+
+    #[test]
+    fn probe_address_is_validated() {
+        let mut probe = ProbeConfig::default();
+        assert_eq!(probe.socket_addr().unwrap(), "127.0.0.1:9999".parse().unwrap());
+        probe.bind_address = "::".to_string();
+        probe.port = 9100;
+        assert_eq!(probe.socket_addr().unwrap(), "[::]:9100".parse().unwrap());
+
+        probe.bind_address = "localhost".to_string();
+        assert!(probe.socket_addr().is_err());
+        probe.bind_address = "0.0.0.0".to_string();
+        probe.port = 0;
+        assert!(probe.socket_addr().is_err());
+    }
+}
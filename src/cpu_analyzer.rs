@@ -1,9 +1,10 @@
-use anyhow::Result;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use crate::process_monitor::ProcessInfo;
+use crate::users::BuildUserPolicy;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuAbuseDetection {
     pub pid: i32,
     pub cpu_percent: f32,
@@ -14,45 +15,89 @@ pub struct CpuAbuseDetection {
 
 pub struct CpuAnalyzer {
     threshold: f32,
+    /// Divides `cpu_percent` (percent of one core) before comparing: the vCPU count
+    /// when the threshold is auto-tuned to a share of the whole machine, else 1
+    cpu_scale: f32,
     duration_seconds: u64,
-    process_history: HashMap<i32, (f32, DateTime<Utc>)>, // (pid, (max_cpu, first_seen))
+    process_history: HashMap<i32, (f32, DateTime<Utc>, u64)>, // (pid, (max_cpu, first_seen, start_time))
+    build_users: BuildUserPolicy,
 }
 
 impl CpuAnalyzer {
     pub fn new(threshold: f32, duration_minutes: u64) -> Self {
         Self {
             threshold,
+            cpu_scale: 1.0,
             duration_seconds: duration_minutes * 60,
             process_history: HashMap::new(),
+            build_users: BuildUserPolicy::default(),
         }
     }
 
+    /// Apply a higher threshold and longer window to processes owned by build users
+    pub fn set_build_users(&mut self, policy: BuildUserPolicy) {
+        self.build_users = policy;
+    }
+
+    /// Effective CPU threshold after auto-tuning, in percent of one core like `cpu_threshold`
+    pub fn threshold(&self) -> f32 {
+        self.threshold * self.cpu_scale
+    }
+
+    pub fn duration_minutes(&self) -> u64 {
+        self.duration_seconds / 60
+    }
+
     pub fn new_with_environment(
         base_threshold: f32,
         base_duration_minutes: u64,
         env: &crate::environment::SystemEnvironment,
-        vcpu_override: Option<usize>,
+        auto_tune: &crate::config::AutoTuneConfig,
     ) -> Self {
-        let threshold = env.compute_cpu_threshold(base_threshold, vcpu_override);
+        let threshold = env.compute_cpu_threshold(base_threshold, auto_tune.per_core_threshold, auto_tune.vcpu_override);
         let duration_minutes = env.compute_duration_minutes(base_duration_minutes);
-        
-        Self::new(threshold, duration_minutes)
+        let vcpu = auto_tune.vcpu_override.unwrap_or(env.vcpu_count);
+
+        Self {
+            cpu_scale: vcpu.max(1) as f32,
+            ..Self::new(threshold, duration_minutes)
+        }
     }
 
     pub fn analyze(&mut self, processes: &[ProcessInfo]) -> Vec<CpuAbuseDetection> {
-        let now = Utc::now();
+        self.analyze_at(processes, Utc::now())
+    }
+
+    /// Analyze a snapshot taken at `now` (used when replaying recorded snapshots)
+    pub fn analyze_at(&mut self, processes: &[ProcessInfo], now: DateTime<Utc>) -> Vec<CpuAbuseDetection> {
         let mut detections = Vec::new();
 
         for process in processes {
+            // No executable to judge (kernel threads) or only partly read: never scored
+            if !process.is_complete() {
+                self.process_history.remove(&process.pid);
+                continue;
+            }
+
+            let threshold = self.build_users.cpu_threshold(self.threshold, process);
+            let required_duration = self.build_users.duration_seconds(self.duration_seconds, process);
+
             // Skip if CPU is below threshold
-            if process.cpu_percent < self.threshold {
+            if process.cpu_percent / self.cpu_scale < threshold {
                 // Remove from history if it was being tracked
                 self.process_history.remove(&process.pid);
                 continue;
             }
 
+            // PID was recycled by an unrelated process: restart its timer
+            if let Some((_, _, start_time)) = self.process_history.get(&process.pid) {
+                if *start_time != process.start_time {
+                    self.process_history.remove(&process.pid);
+                }
+            }
+
             // Check if we're already tracking this process
-            if let Some((max_cpu, first_seen)) = self.process_history.get_mut(&process.pid) {
+            if let Some((max_cpu, first_seen, _)) = self.process_history.get_mut(&process.pid) {
                 // Update max CPU if higher
                 if process.cpu_percent > *max_cpu {
                     *max_cpu = process.cpu_percent;
@@ -60,7 +105,7 @@ impl CpuAnalyzer {
 
                 // Check if duration threshold exceeded
                 let duration = (now - *first_seen).num_seconds() as u64;
-                if duration >= self.duration_seconds {
+                if duration >= required_duration {
                     detections.push(CpuAbuseDetection {
                         pid: process.pid,
                         cpu_percent: *max_cpu,
@@ -73,7 +118,7 @@ impl CpuAnalyzer {
                 // Start tracking this process
                 self.process_history.insert(
                     process.pid,
-                    (process.cpu_percent, now),
+                    (process.cpu_percent, now, process.start_time),
                 );
             }
         }
@@ -91,3 +136,109 @@ impl CpuAnalyzer {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_monitor::ExeState;
+
+    fn busy_process(start_time: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            binary_path: "/tmp/worker".to_string(),
+            cpu_percent: 90.0,
+            start_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn build_users_get_relaxed_threshold_and_window() {
+        let mut analyzer = CpuAnalyzer::new(20.0, 5);
+        analyzer.set_build_users(BuildUserPolicy::new([1001].into_iter().collect(), 2.0, 3.0));
+        let t0: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let minutes = |m| t0 + chrono::Duration::minutes(m);
+
+        let build = |cpu| ProcessInfo { pid: 10, uid: 1001, cpu_percent: cpu, ..Default::default() };
+        let other = ProcessInfo { pid: 11, uid: 1000, cpu_percent: 35.0, ..Default::default() };
+
+        // 35% is below the doubled 40% threshold for the build user
+        analyzer.analyze_at(&[build(35.0), other.clone()], t0);
+        assert_eq!(analyzer.get_tracked_pids(), vec![11]);
+
+        analyzer.analyze_at(&[build(90.0), other.clone()], minutes(1));
+        let detections = analyzer.analyze_at(&[build(90.0), other.clone()], minutes(6));
+        assert_eq!(detections.iter().map(|d| d.pid).collect::<Vec<_>>(), vec![11]);
+
+        // Build user needs 15 minutes instead of 5
+        let detections = analyzer.analyze_at(&[build(90.0), other], minutes(16));
+        assert_eq!(detections.len(), 2);
+    }
+
+    #[test]
+    fn pid_reuse_resets_duration_timer() {
+        let mut analyzer = CpuAnalyzer::new(20.0, 5);
+        let t0: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let minutes = |m| t0 + chrono::Duration::minutes(m);
+
+        assert!(analyzer.analyze_at(&[busy_process(1000)], t0).is_empty());
+
+        // Same PID, different start time: a new process, so no carried-over duration
+        assert!(analyzer.analyze_at(&[busy_process(2000)], minutes(6)).is_empty());
+
+        let detections = analyzer.analyze_at(&[busy_process(2000)], minutes(12));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].first_seen, minutes(6));
+        assert_eq!(detections[0].duration_seconds, 360);
+    }
+
+    #[test]
+    fn auto_tuned_threshold_compares_against_the_whole_machine() {
+        let env = crate::environment::SystemEnvironment {
+            vcpu_count: 16,
+            total_ram_mb: 8192,
+            has_ebpf: false,
+            has_cgroups_v2: false,
+            load_average: (1.0, 1.0, 1.0),
+        };
+        let mut analyzer = CpuAnalyzer::new_with_environment(100.0, 5, &env, &Default::default());
+        // 5% of the machine, reported as 80% of one core
+        assert_eq!(analyzer.threshold(), 80.0);
+        let t0: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        // 30% of one core is under 2% of a 16 vCPU machine; 90% of one core is above 5%
+        let idle = ProcessInfo { pid: 7, cpu_percent: 30.0, ..busy_process(1000) };
+        analyzer.analyze_at(&[idle, busy_process(1000)], t0);
+        assert_eq!(analyzer.get_tracked_pids(), vec![4242]);
+    }
+
+    #[test]
+    fn auto_tune_keeps_the_default_threshold_per_core() {
+        let env = crate::environment::SystemEnvironment {
+            vcpu_count: 4,
+            total_ram_mb: 8192,
+            has_ebpf: false,
+            has_cgroups_v2: false,
+            load_average: (1.0, 1.0, 1.0),
+        };
+        let mut analyzer = CpuAnalyzer::new_with_environment(20.0, 5, &env, &Default::default());
+        assert_eq!(analyzer.threshold(), 20.0);
+        let t0: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        // 25% of one core is over the default 20%, as without auto_tune; 15% is not
+        let flagged = ProcessInfo { pid: 7, cpu_percent: 25.0, ..busy_process(1000) };
+        let quiet = ProcessInfo { pid: 8, cpu_percent: 15.0, ..busy_process(1000) };
+        analyzer.analyze_at(&[flagged, quiet], t0);
+        assert_eq!(analyzer.get_tracked_pids(), vec![7]);
+    }
+
+    #[test]
+    fn processes_without_a_known_exe_are_not_scored() {
+        let mut analyzer = CpuAnalyzer::new(20.0, 5);
+        let t0: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+
+        let kworker = ProcessInfo { exe_state: ExeState::NoExecutable, binary_path: String::new(), ..busy_process(1000) };
+        analyzer.analyze_at(std::slice::from_ref(&kworker), t0);
+        assert!(analyzer.get_tracked_pids().is_empty());
+        assert!(analyzer.analyze_at(&[kworker], t0 + chrono::Duration::minutes(10)).is_empty());
+    }
+}
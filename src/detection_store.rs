@@ -0,0 +1,309 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::{CollectorConfig, Config};
+use crate::database::{AuditDecision, EvidenceRecord, IntelligenceDB, KillAction, KillOutcome, MalwareFile, NpmInfection};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Where detections and enforcement records are written. Reads (history, reports,
+/// prior kills) always go to the local `IntelligenceDB`.
+pub trait DetectionStore: Send + Sync {
+    /// Returns the new record's id so an action recorded up front can be resolved or withdrawn
+    fn record_kill_action<'a>(&'a self, action: &'a KillAction) -> StoreFuture<'a, i64>;
+    fn set_kill_outcome<'a>(&'a self, id: i64, signal_sent: Option<&'a str>, outcome: KillOutcome) -> StoreFuture<'a, ()>;
+    fn delete_kill_action(&self, id: i64) -> StoreFuture<'_, ()>;
+    fn record_decision<'a>(&'a self, decision: &'a AuditDecision) -> StoreFuture<'a, i64>;
+    fn record_evidence<'a>(&'a self, evidence: &'a EvidenceRecord) -> StoreFuture<'a, ()>;
+    fn record_malware_file<'a>(&'a self, malware: &'a MalwareFile) -> StoreFuture<'a, ()>;
+    fn record_npm_infection<'a>(&'a self, infection: &'a NpmInfection) -> StoreFuture<'a, ()>;
+    fn record_safety_event<'a>(&'a self, kind: &'a str, detail: &'a str) -> StoreFuture<'a, ()>;
+}
+
+impl DetectionStore for IntelligenceDB {
+    fn record_kill_action<'a>(&'a self, action: &'a KillAction) -> StoreFuture<'a, i64> {
+        Box::pin(IntelligenceDB::record_kill_action(self, action))
+    }
+
+    fn set_kill_outcome<'a>(&'a self, id: i64, signal_sent: Option<&'a str>, outcome: KillOutcome) -> StoreFuture<'a, ()> {
+        Box::pin(IntelligenceDB::set_kill_outcome(self, id, signal_sent, outcome))
+    }
+
+    fn delete_kill_action(&self, id: i64) -> StoreFuture<'_, ()> {
+        Box::pin(IntelligenceDB::delete_kill_action(self, id))
+    }
+
+    fn record_decision<'a>(&'a self, decision: &'a AuditDecision) -> StoreFuture<'a, i64> {
+        Box::pin(IntelligenceDB::record_decision(self, decision))
+    }
+
+    fn record_evidence<'a>(&'a self, evidence: &'a EvidenceRecord) -> StoreFuture<'a, ()> {
+        Box::pin(IntelligenceDB::record_evidence(self, evidence))
+    }
+
+    fn record_malware_file<'a>(&'a self, malware: &'a MalwareFile) -> StoreFuture<'a, ()> {
+        Box::pin(IntelligenceDB::record_malware_file(self, malware))
+    }
+
+    fn record_npm_infection<'a>(&'a self, infection: &'a NpmInfection) -> StoreFuture<'a, ()> {
+        Box::pin(IntelligenceDB::record_npm_infection(self, infection))
+    }
+
+    fn record_safety_event<'a>(&'a self, kind: &'a str, detail: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(IntelligenceDB::record_safety_event(self, kind, detail))
+    }
+}
+
+/// The store the daemon writes through: the local database, forwarding to the
+/// configured `[collector]` if there is one
+pub fn from_config(config: &Config, db: IntelligenceDB) -> Arc<dyn DetectionStore> {
+    match config.collector {
+        Some(ref collector) => Arc::new(HttpCollectorStore::new(db, collector)),
+        None => Arc::new(db),
+    }
+}
+
+/// Writes to the local database first, then POSTs each record to a central HTTP
+/// collector for fleet-wide aggregation. Forwarding happens in the background and
+/// its failures are only logged: a collector outage must never block or disable
+/// enforcement, and the local copy stays authoritative.
+pub struct HttpCollectorStore {
+    local: IntelligenceDB,
+    client: reqwest::Client,
+    url: String,
+    auth_token: String,
+    host: String,
+    // Kill records are forwarded once their outcome is known; withdrawn ones never are
+    pending_kills: Mutex<HashMap<i64, KillAction>>,
+}
+
+/// One forwarded record, as the collector receives it
+#[derive(Debug, Serialize)]
+struct CollectorRecord<'a, T: Serialize> {
+    host: &'a str,
+    kind: &'a str,
+    record: &'a T,
+}
+
+#[derive(Debug, Serialize)]
+struct SafetyEvent<'a> {
+    kind: &'a str,
+    detail: &'a str,
+}
+
+impl HttpCollectorStore {
+    pub fn new(local: IntelligenceDB, config: &CollectorConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .unwrap_or_default();
+        let host = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|h| h.trim().to_string())
+            .unwrap_or_default();
+        Self {
+            local,
+            client,
+            url: config.url.clone(),
+            auth_token: config.auth_token.clone(),
+            host,
+            pending_kills: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn forward<T: Serialize>(&self, kind: &str, record: &T) {
+        let body = match serde_json::to_value(CollectorRecord { host: &self.host, kind, record }) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize {} record for the collector: {}", kind, e);
+                return;
+            }
+        };
+        let mut request = self.client.post(&self.url).json(&body);
+        if !self.auth_token.is_empty() {
+            request = request.bearer_auth(&self.auth_token);
+        }
+        let kind = kind.to_string();
+        tokio::spawn(async move {
+            match request.send().await.and_then(|response| response.error_for_status()) {
+                Ok(_) => debug!("Forwarded {} record to the collector", kind),
+                Err(e) => warn!("Failed to forward {} record to the collector: {}", kind, e),
+            }
+        });
+    }
+}
+
+impl DetectionStore for HttpCollectorStore {
+    fn record_kill_action<'a>(&'a self, action: &'a KillAction) -> StoreFuture<'a, i64> {
+        Box::pin(async move {
+            let id = self.local.record_kill_action(action).await?;
+            if action.outcome == KillOutcome::Pending {
+                if let Ok(mut pending) = self.pending_kills.lock() {
+                    pending.insert(id, KillAction { id, ..action.clone() });
+                }
+            } else {
+                self.forward("kill_action", &KillAction { id, ..action.clone() });
+            }
+            Ok(id)
+        })
+    }
+
+    fn set_kill_outcome<'a>(&'a self, id: i64, signal_sent: Option<&'a str>, outcome: KillOutcome) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.local.set_kill_outcome(id, signal_sent, outcome).await?;
+            let resolved = self.pending_kills.lock().ok().and_then(|mut pending| pending.remove(&id));
+            if let Some(mut action) = resolved {
+                action.signal_sent = signal_sent.map(str::to_string);
+                action.outcome = outcome;
+                self.forward("kill_action", &action);
+            }
+            Ok(())
+        })
+    }
+
+    fn delete_kill_action(&self, id: i64) -> StoreFuture<'_, ()> {
+        Box::pin(async move {
+            self.local.delete_kill_action(id).await?;
+            if let Ok(mut pending) = self.pending_kills.lock() {
+                pending.remove(&id);
+            }
+            Ok(())
+        })
+    }
+
+    fn record_decision<'a>(&'a self, decision: &'a AuditDecision) -> StoreFuture<'a, i64> {
+        Box::pin(async move {
+            let id = self.local.record_decision(decision).await?;
+            self.forward("decision", &AuditDecision { id, ..decision.clone() });
+            Ok(id)
+        })
+    }
+
+    fn record_evidence<'a>(&'a self, evidence: &'a EvidenceRecord) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.local.record_evidence(evidence).await?;
+            self.forward("evidence", evidence);
+            Ok(())
+        })
+    }
+
+    fn record_malware_file<'a>(&'a self, malware: &'a MalwareFile) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.local.record_malware_file(malware).await?;
+            self.forward("malware_file", malware);
+            Ok(())
+        })
+    }
+
+    fn record_npm_infection<'a>(&'a self, infection: &'a NpmInfection) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.local.record_npm_infection(infection).await?;
+            self.forward("npm_infection", infection);
+            Ok(())
+        })
+    }
+
+    fn record_safety_event<'a>(&'a self, kind: &'a str, detail: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            self.local.record_safety_event(kind, detail).await?;
+            self.forward("safety_event", &SafetyEvent { kind, detail });
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Accept one HTTP request, answer 200 and return its body
+    async fn receive_one(listener: &TcpListener) -> serde_json::Value {
+        let (mut socket, _) = tokio::time::timeout(Duration::from_secs(5), listener.accept()).await.unwrap().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length: usize = head.lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    assert!(head.to_ascii_lowercase().contains("authorization: bearer secret"), "{}", head);
+                    socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n").await.unwrap();
+                    return serde_json::from_str(&body[..length]).unwrap();
+                }
+            }
+            assert!(n > 0, "connection closed before the body arrived");
+        }
+    }
+
+    fn kill(outcome: KillOutcome) -> KillAction {
+        KillAction {
+            id: 0,
+            pid: 4242,
+            uid: 1000,
+            binary_path: "/tmp/.x/xmrig".to_string(),
+            reason: "CPU abuse".to_string(),
+            confidence: 0.95,
+            timestamp: Utc::now(),
+            signal_sent: None,
+            outcome,
+            exe_hash: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn collector_gets_records_after_the_local_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let store: Box<dyn DetectionStore> = Box::new(HttpCollectorStore::new(db.clone(), &CollectorConfig {
+            url: format!("http://{}/ingest", listener.local_addr().unwrap()),
+            auth_token: "secret".to_string(),
+            timeout_ms: 5000,
+        }));
+
+        // A withdrawn kill is never forwarded; a resolved one is, with its outcome
+        let withdrawn = store.record_kill_action(&kill(KillOutcome::Pending)).await.unwrap();
+        store.delete_kill_action(withdrawn).await.unwrap();
+        let id = store.record_kill_action(&kill(KillOutcome::Pending)).await.unwrap();
+        store.set_kill_outcome(id, Some("SIGKILL"), KillOutcome::Escalated).await.unwrap();
+
+        let received = receive_one(&listener).await;
+        assert_eq!(received["kind"], "kill_action");
+        assert_eq!(received["record"]["id"], id);
+        assert_eq!(received["record"]["outcome"], "escalated");
+        assert_eq!(received["record"]["signal_sent"], "SIGKILL");
+        assert_eq!(db.get_kill_action(id).await.unwrap().unwrap().outcome, KillOutcome::Escalated);
+
+        store.record_safety_event("kill_failed", "PID 4242 survived").await.unwrap();
+        let received = receive_one(&listener).await;
+        assert_eq!(received["kind"], "safety_event");
+        assert_eq!(received["record"]["detail"], "PID 4242 survived");
+    }
+
+    #[tokio::test]
+    async fn unreachable_collector_does_not_fail_the_write() {
+        // Bound then dropped: nothing listens on the port any more
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let store = HttpCollectorStore::new(db.clone(), &CollectorConfig {
+            url: format!("http://{}/ingest", addr),
+            auth_token: String::new(),
+            timeout_ms: 500,
+        });
+
+        let id = store.record_kill_action(&kill(KillOutcome::Terminated)).await.unwrap();
+        assert!(id > 0);
+        assert_eq!(db.get_kill_action(id).await.unwrap().unwrap().outcome, KillOutcome::Terminated);
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use nix::sys::statvfs::statvfs;
+use std::path::{Path, PathBuf};
+
+use crate::config::DiskSpaceConfig;
+
+/// Free-space level of a filesystem, ordered from healthy to critical
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiskLevel {
+    Ok,
+    Low,
+    Critical,  // Writes are about to fail
+}
+
+/// Free space available to hora-police on the filesystem holding a path
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskStatus {
+    pub path: PathBuf,
+    pub free_mb: u64,
+    pub total_mb: u64,
+    pub level: DiskLevel,
+}
+
+pub fn classify(free_mb: u64, config: &DiskSpaceConfig) -> DiskLevel {
+    if free_mb < config.critical_free_mb {
+        DiskLevel::Critical
+    } else if free_mb < config.warn_free_mb {
+        DiskLevel::Low
+    } else {
+        DiskLevel::Ok
+    }
+}
+
+/// statvfs the nearest existing ancestor of `path` (the quarantine dir may not exist yet)
+pub fn check_path(path: &Path, config: &DiskSpaceConfig) -> Result<DiskStatus> {
+    let existing = path.ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let stat = statvfs(existing)
+        .with_context(|| format!("Failed to statvfs {:?}", existing))?;
+
+    let fragment = stat.fragment_size() as u64;
+    // blocks_available excludes root-reserved blocks, matching what a write can actually use
+    let free_mb = (stat.blocks_available() as u64).saturating_mul(fragment) / (1024 * 1024);
+    let total_mb = (stat.blocks() as u64).saturating_mul(fragment) / (1024 * 1024);
+
+    Ok(DiskStatus {
+        path: path.to_path_buf(),
+        free_mb,
+        total_mb,
+        level: classify(free_mb, config),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_free_space() {
+        let config = DiskSpaceConfig::default();
+        assert_eq!(classify(config.warn_free_mb, &config), DiskLevel::Ok);
+        assert_eq!(classify(config.warn_free_mb - 1, &config), DiskLevel::Low);
+        assert_eq!(classify(config.critical_free_mb - 1, &config), DiskLevel::Critical);
+        assert!(DiskLevel::Critical > DiskLevel::Low);
+    }
+
+    #[test]
+    fn checks_missing_paths_via_ancestor() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = check_path(&dir.path().join("not/yet/created"), &DiskSpaceConfig::default()).unwrap();
+        assert!(status.total_mb > 0);
+        assert!(status.free_mb <= status.total_mb);
+    }
+}
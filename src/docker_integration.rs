@@ -0,0 +1,106 @@
+use anyhow::Result;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::command::run_command;
+use crate::config::DockerConfig;
+
+/// Maps PIDs to the Docker containers they run in and stops those containers,
+/// so a restart policy can't respawn a killed miner.
+#[derive(Clone)]
+pub struct DockerIntegration {
+    config: DockerConfig,
+    command_timeout: Duration,
+}
+
+impl DockerIntegration {
+    /// `command_timeout` bounds each docker call; `docker stop` also gets its grace period
+    pub fn new(config: DockerConfig, command_timeout: Duration) -> Self {
+        Self { config, command_timeout }
+    }
+
+    /// Container ID of the PID, from its cgroup path
+    pub fn container_for_pid(&self, pid: i32) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+        let cgroup = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+        container_id_from_cgroup(&cgroup)
+    }
+
+    pub fn is_container_process(&self, pid: i32) -> bool {
+        self.container_for_pid(pid).is_some()
+    }
+
+    /// Clear the restart policy (if configured), then `docker stop` or `docker kill`
+    pub async fn stop_container(&self, container_id: &str) -> Result<()> {
+        if self.config.disable_restart_policy {
+            let output = run_command("docker", &["update", "--restart=no", container_id], self.command_timeout).await?;
+            if !output.status.success() {
+                warn!("Failed to clear restart policy of container {}: {}",
+                      container_id, String::from_utf8_lossy(&output.stderr).trim());
+            }
+        }
+
+        let timeout = self.config.stop_timeout_seconds.to_string();
+        let args: Vec<&str> = if self.config.force_kill {
+            vec!["kill", container_id]
+        } else {
+            vec!["stop", "-t", &timeout, container_id]
+        };
+
+        info!("Stopping Docker container: {} (docker {})", container_id, args[0]);
+        let grace = if self.config.force_kill { 0 } else { self.config.stop_timeout_seconds };
+        let output = run_command("docker", &args, self.command_timeout + Duration::from_secs(grace)).await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("docker {} failed: {}", args[0], stderr.trim()));
+        }
+
+        info!("Successfully stopped Docker container: {}", container_id);
+        Ok(())
+    }
+}
+
+/// Extract a Docker container ID from /proc/<pid>/cgroup contents.
+/// Handles cgroupfs (`/docker/<id>`) and systemd (`docker-<id>.scope`) drivers, cgroup v1 and v2.
+pub fn container_id_from_cgroup(content: &str) -> Option<String> {
+    for line in content.lines() {
+        // hierarchy-ID:controller-list:cgroup-path
+        let path = line.splitn(3, ':').nth(2).unwrap_or("");
+        for segment in path.split('/') {
+            let candidate = segment
+                .strip_prefix("docker-")
+                .and_then(|s| s.strip_suffix(".scope"))
+                .unwrap_or(segment);
+            if is_container_id(candidate) && (path.contains("/docker") || segment.starts_with("docker-")) {
+                return Some(candidate.to_string());
+            }
+        }
+    }
+    None
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4f2c5b1e8d9a7c6b3e2f1a0d9c8b7a6f5e4d3c2b1a0f9e8d7c6b5a4f3e2d1c0b";
+
+    #[test]
+    fn parses_container_ids_from_cgroups() {
+        let v1 = format!("12:pids:/docker/{id}\n11:cpu,cpuacct:/docker/{id}\n", id = ID);
+        assert_eq!(container_id_from_cgroup(&v1).as_deref(), Some(ID));
+
+        let v2_systemd = format!("0::/system.slice/docker-{}.scope\n", ID);
+        assert_eq!(container_id_from_cgroup(&v2_systemd).as_deref(), Some(ID));
+
+        assert_eq!(container_id_from_cgroup("0::/system.slice/nginx.service\n"), None);
+        assert_eq!(container_id_from_cgroup("0::/user.slice/user-1000.slice/session-3.scope\n"), None);
+    }
+}
@@ -0,0 +1,342 @@
+use nix::unistd::{access, AccessFlags};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::database::IntelligenceDB;
+use crate::environment::{auto_tuned_cpu_threshold, SystemEnvironment};
+use crate::telegram::TelegramReporter;
+
+/// Placeholder shipped in config.toml.example
+const EXAMPLE_BOT_TOKEN: &str = "YOUR_BOT_TOKEN_HERE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,  // Probably intended, worth knowing
+    Warning,  // Likely not what was meant
+    Error,  // A feature that is configured won't work
+}
+
+/// One likely mistake in the effective configuration, with what to change
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub topic: &'static str,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, topic: &'static str, problem: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { severity, topic, problem: problem.into(), fix: fix.into() }
+    }
+}
+
+/// Static checks plus the ones that need the network (Telegram)
+pub async fn run_doctor(config: &Config) -> Vec<Finding> {
+    let vcpu_count = config.auto_tune.vcpu_override
+        .or_else(|| SystemEnvironment::detect().ok().map(|env| env.vcpu_count))
+        .unwrap_or(0);
+    let mut findings = diagnose(config, vcpu_count);
+    if let Some(finding) = check_telegram_reachable(config).await {
+        findings.push(finding);
+    }
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+/// Configuration mistakes that can be spotted without contacting anything.
+/// `vcpu_count` is 0 when unknown.
+pub fn diagnose(config: &Config, vcpu_count: usize) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    check_enforcement_flags(config, &mut findings);
+    check_thresholds(config, vcpu_count, &mut findings);
+    check_paths(config, &mut findings);
+    check_whitelist(config, &mut findings);
+    check_telegram_config(config, &mut findings);
+    findings
+}
+
+fn check_enforcement_flags(config: &Config, findings: &mut Vec<Finding>) {
+    let topic = "enforcement";
+    if config.auto_kill {
+        for (flag, set, effect) in [
+            ("dry_run", config.dry_run, "actions are only logged"),
+            ("audit_only", config.audit_only, "actions are only written to the decisions table"),
+            ("learning_report.enabled", config.learning_report.enabled, "the daemon runs as audit_only and reports"),
+        ] {
+            if set {
+                findings.push(Finding::new(Severity::Warning, topic,
+                    format!("auto_kill = true but {} is set: {}, nothing is stopped", flag, effect),
+                    format!("Set {} = false to enforce, or auto_kill = false to make observe-only explicit", flag)));
+            }
+        }
+    } else {
+        findings.push(Finding::new(Severity::Note, topic,
+            "auto_kill = false: detections are reported but never acted on",
+            "Set auto_kill = true once alerts look right (try learning_report first)"));
+    }
+    if config.dry_run && config.audit_only {
+        findings.push(Finding::new(Severity::Warning, topic,
+            "dry_run and audit_only are both set; dry_run wins and decisions are not recorded",
+            "Keep only audit_only if you want to review decisions with `hora-police decisions`"));
+    }
+    if config.canary_mode && (config.dry_run || !config.auto_kill) {
+        findings.push(Finding::new(Severity::Warning, topic,
+            "canary_mode limits enforcement, but enforcement is already off",
+            "Drop canary_mode, or enable auto_kill without dry_run"));
+    }
+}
+
+fn check_thresholds(config: &Config, vcpu_count: usize, findings: &mut Vec<Finding>) {
+    let topic = "thresholds";
+    if config.threat_confidence_threshold > 1.0 {
+        findings.push(Finding::new(Severity::Error, topic,
+            format!("threat_confidence_threshold = {} can never be reached (confidence is at most 1.0)",
+                    config.threat_confidence_threshold),
+            "Use a value between 0.5 and 0.9; 0.7 is the default"));
+    }
+    if config.high_confidence_threshold < config.threat_confidence_threshold {
+        findings.push(Finding::new(Severity::Warning, topic,
+            format!("high_confidence_threshold ({}) is below threat_confidence_threshold ({}): every threat counts as high-confidence and PM2/systemd apps are stopped without the usual margin",
+                    config.high_confidence_threshold, config.threat_confidence_threshold),
+            "Set high_confidence_threshold above threat_confidence_threshold (default 0.95)"));
+    }
+    if config.polling_interval_ms == 0 {
+        findings.push(Finding::new(Severity::Error, topic,
+            "polling_interval_ms = 0 makes the monitoring loop spin",
+            "Use 5000 (the default) or more"));
+    }
+
+    if config.auto_tune.enabled {
+        let auto_tuned = auto_tuned_cpu_threshold(config.auto_tune.per_core_threshold, vcpu_count);
+        match auto_tuned {
+            None => findings.push(Finding::new(Severity::Warning, "auto_tune",
+                "auto_tune is enabled but has nothing to work with (per_core_threshold <= 0 or unknown vCPU count)",
+                "Set auto_tune.per_core_threshold (default 80) or auto_tune.vcpu_override")),
+            // Auto-tuning only ever lowers the threshold
+            Some(threshold) if threshold >= config.cpu_threshold / vcpu_count as f32 => findings.push(Finding::new(Severity::Note, "auto_tune",
+                format!("auto_tune is inert: cpu_threshold = {}% of one core is already at or below the {}% per core it would compute for {} vCPU",
+                        config.cpu_threshold, threshold * vcpu_count as f32, vcpu_count),
+                "Raise cpu_threshold to let auto_tune pick, or disable auto_tune")),
+            Some(_) => {}
+        }
+    }
+}
+
+fn check_paths(config: &Config, findings: &mut Vec<Finding>) {
+    if let Some(parent) = Path::new(&config.database_path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Some(problem) = dir_problem(parent, true) {
+            findings.push(Finding::new(Severity::Error, "database",
+                format!("database_path directory {}: {}", parent.display(), problem),
+                "Create the directory (and run the daemon as root) or point database_path elsewhere"));
+        }
+    }
+
+    let scanning = &config.file_scanning;
+    if !scanning.enabled {
+        return;
+    }
+    if scanning.scan_paths.is_empty() {
+        findings.push(Finding::new(Severity::Warning, "file scanning",
+            "file_scanning is enabled with no scan_paths",
+            "Add directories to file_scanning.scan_paths or set enabled = false"));
+    }
+    for path in &scanning.scan_paths {
+        if let Err(e) = std::fs::read_dir(path) {
+            findings.push(Finding::new(Severity::Warning, "file scanning",
+                format!("scan path {} can't be read: {}", path, e),
+                "Fix or remove it from file_scanning.scan_paths"));
+        }
+    }
+    if !scanning.auto_delete {
+        let quarantine = Path::new(&scanning.quarantine_path);
+        match dir_problem(quarantine, true) {
+            Some(problem) if quarantine.exists() => findings.push(Finding::new(Severity::Error, "quarantine",
+                format!("quarantine_path {}: {}", quarantine.display(), problem),
+                "Make file_scanning.quarantine_path a directory writable by the daemon")),
+            Some(_) => findings.push(Finding::new(Severity::Warning, "quarantine",
+                format!("quarantine_path {} doesn't exist", quarantine.display()),
+                format!("Create it now (mkdir -p {} && chmod 700 {}) so the first quarantine doesn't fail",
+                        quarantine.display(), quarantine.display()))),
+            None => {}
+        }
+    }
+    for file in &scanning.signature_files {
+        if !Path::new(file).is_file() {
+            findings.push(Finding::new(Severity::Error, "signatures",
+                format!("signature file {} doesn't exist", file),
+                "Fix the path in file_scanning.signature_files; check with `hora-police validate-signatures`"));
+        }
+    }
+}
+
+/// Why `dir` can't be used as a directory (written to, if `write`), None if it can
+fn dir_problem(dir: &Path, write: bool) -> Option<String> {
+    match std::fs::metadata(dir) {
+        Err(e) => Some(e.to_string()),
+        Ok(meta) if !meta.is_dir() => Some("not a directory".to_string()),
+        Ok(_) if write && access(dir, AccessFlags::W_OK).is_err() => Some("not writable".to_string()),
+        Ok(_) => None,
+    }
+}
+
+fn check_whitelist(config: &Config, findings: &mut Vec<Finding>) {
+    if !config.whitelist.auto_detect && config.whitelist.manual_patterns.is_empty() {
+        findings.push(Finding::new(Severity::Warning, "whitelist",
+            "whitelist.auto_detect is off and manual_patterns is empty: nothing is protected, busy PM2/systemd/nginx apps can be stopped",
+            "Set whitelist.auto_detect = true or list your apps in whitelist.manual_patterns"));
+    }
+}
+
+fn check_telegram_config(config: &Config, findings: &mut Vec<Finding>) {
+    let Some(ref telegram) = config.telegram else {
+        findings.push(Finding::new(Severity::Note, "telegram",
+            "no [telegram] section: alerts only go to the log",
+            "Add [telegram] with bot_token and chat_id"));
+        return;
+    };
+    if telegram.bot_token.is_empty() || telegram.bot_token == EXAMPLE_BOT_TOKEN {
+        findings.push(Finding::new(Severity::Error, "telegram",
+            "bot_token is empty or still the example placeholder",
+            "Create a bot with @BotFather and put its token in telegram.bot_token"));
+    }
+    if telegram.chat_id.is_empty() && telegram.chats.is_empty() {
+        findings.push(Finding::new(Severity::Error, "telegram",
+            "no chat_id and no [[telegram.chats]]: alerts have nowhere to go",
+            "Set telegram.chat_id (message @userinfobot to find yours)"));
+    }
+    if chrono::NaiveTime::parse_from_str(&telegram.daily_report_time, "%H:%M").is_err() {
+        findings.push(Finding::new(Severity::Warning, "telegram",
+            format!("daily_report_time {:?} isn't HH:MM, so no daily report is sent", telegram.daily_report_time),
+            "Use 24-hour HH:MM, e.g. \"09:00\""));
+    }
+}
+
+/// Ask Telegram whether the bot token works; nothing is sent to the chats
+async fn check_telegram_reachable(config: &Config) -> Option<Finding> {
+    let telegram = config.telegram.as_ref()?;
+    if telegram.bot_token.is_empty() || telegram.bot_token == EXAMPLE_BOT_TOKEN {
+        return None;  // Already reported
+    }
+    let db = IntelligenceDB::new_in_memory().await.ok()?;
+    match TelegramReporter::new(config.telegram.clone(), db).check_bot().await {
+        Ok(_) => None,
+        Err(e) => Some(Finding::new(Severity::Error, "telegram",
+            format!("Telegram is configured but unreachable: {}", e),
+            "Check telegram.bot_token and outbound HTTPS to api.telegram.org; `hora-police selftest` sends a test message")),
+    }
+}
+
+/// Render findings, most severe first, or a clean bill of health
+pub fn format_report(findings: &[Finding]) -> String {
+    if findings.is_empty() {
+        return "✅ No likely misconfigurations found\n".to_string();
+    }
+    let mut out = String::new();
+    for finding in findings {
+        let mark = match finding.severity {
+            Severity::Error => "❌ ERROR",
+            Severity::Warning => "⚠️  WARN ",
+            Severity::Note => "ℹ️  NOTE ",
+        };
+        out.push_str(&format!("{} [{}] {}\n", mark, finding.topic, finding.problem));
+        out.push_str(&format!("         ↳ {}\n", finding.fix));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(findings: &[Finding]) -> Vec<(Severity, &'static str)> {
+        findings.iter().map(|f| (f.severity, f.topic)).collect()
+    }
+
+    #[test]
+    fn flags_conflicting_enforcement_settings() {
+        let mut config = Config {
+            telegram: None,
+            database_path: String::new(),
+            dry_run: true,
+            audit_only: true,
+            canary_mode: true,
+            ..Default::default()
+        };
+        config.file_scanning.enabled = false;
+        config.whitelist.auto_detect = false;
+        // With the default cpu_threshold auto_tune has nothing to lower; covered below
+        config.auto_tune.enabled = false;
+
+        let findings = diagnose(&config, 8);
+        assert!(findings.iter().any(|f| f.problem.starts_with("auto_kill = true but dry_run is set")));
+        assert!(findings.iter().any(|f| f.problem.starts_with("auto_kill = true but audit_only is set")));
+        assert!(findings.iter().any(|f| f.problem.starts_with("dry_run and audit_only are both set")));
+        assert!(findings.iter().any(|f| f.problem.starts_with("canary_mode")));
+        assert!(problems(&findings).contains(&(Severity::Warning, "whitelist")));
+
+        config.dry_run = false;
+        config.audit_only = false;
+        config.canary_mode = false;
+        config.whitelist.auto_detect = true;
+        let clean = diagnose(&config, 8);
+        assert_eq!(problems(&clean), vec![(Severity::Note, "telegram")], "{:?}", clean);
+    }
+
+    #[test]
+    fn flags_missing_paths_and_inert_auto_tune() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            telegram: None,
+            database_path: dir.path().join("hora.db").display().to_string(),
+            // 80% per core is above the configured 20% of one core: auto_tune changes nothing
+            cpu_threshold: 20.0,
+            ..Default::default()
+        };
+        config.file_scanning.enabled = true;
+        config.file_scanning.auto_delete = false;
+        config.file_scanning.scan_paths = vec![dir.path().display().to_string(), "/nonexistent/www".to_string()];
+        config.file_scanning.quarantine_path = dir.path().join("quarantine").display().to_string();
+        config.file_scanning.signature_files = vec!["/nonexistent/extra.toml".to_string()];
+
+        let findings = diagnose(&config, 2);
+        let scan: Vec<&Finding> = findings.iter().filter(|f| f.topic == "file scanning").collect();
+        assert_eq!(scan.len(), 1);
+        assert!(scan[0].problem.contains("/nonexistent/www"));
+        assert!(problems(&findings).contains(&(Severity::Warning, "quarantine")));
+        assert!(problems(&findings).contains(&(Severity::Error, "signatures")));
+        assert!(problems(&findings).contains(&(Severity::Note, "auto_tune")));
+        assert!(!findings.iter().any(|f| f.topic == "database"));
+
+        // A full core is 6.25% of 16 vCPU; auto_tune lowers it to 5% (80% of one core)
+        std::fs::create_dir(dir.path().join("quarantine")).unwrap();
+        config.cpu_threshold = 100.0;
+        let findings = diagnose(&config, 16);
+        assert!(!findings.iter().any(|f| f.topic == "auto_tune" || f.topic == "quarantine"));
+
+        config.database_path = "/nonexistent/db/hora.db".to_string();
+        assert!(problems(&diagnose(&config, 16)).contains(&(Severity::Error, "database")));
+    }
+
+    #[test]
+    fn flags_placeholder_telegram_settings() {
+        let mut config = Config {
+            database_path: String::new(),
+            telegram: Some(crate::config::TelegramConfig {
+                bot_token: EXAMPLE_BOT_TOKEN.to_string(),
+                chat_id: String::new(),
+                daily_report_time: "9am".to_string(),
+                chats: Vec::new(),
+                weekly_report_days: 7,
+            }),
+            ..Default::default()
+        };
+        config.file_scanning.enabled = false;
+        let telegram: Vec<Severity> = diagnose(&config, 8).into_iter()
+            .filter(|f| f.topic == "telegram")
+            .map(|f| f.severity)
+            .collect();
+        assert_eq!(telegram, vec![Severity::Error, Severity::Error, Severity::Warning]);
+        assert!(format_report(&diagnose(&config, 8)).contains("❌ ERROR [telegram] bot_token is empty"));
+    }
+}
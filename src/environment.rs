@@ -2,6 +2,9 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 
+/// Auto-tuning never lowers the CPU threshold below this (system %)
+pub const MIN_CPU_THRESHOLD: f32 = 5.0;
+
 #[derive(Debug, Clone)]
 pub struct SystemEnvironment {
     pub vcpu_count: usize,
@@ -60,19 +63,14 @@ impl SystemEnvironment {
     fn detect_ram() -> Result<u64> {
         let meminfo = fs::read_to_string("/proc/meminfo")
             .context("Failed to read /proc/meminfo")?;
+        Ok(meminfo_mb(&meminfo, "MemTotal").unwrap_or(1024)) // Default to 1GB if detection fails
+    }
 
-        for line in meminfo.lines() {
-            if line.starts_with("MemTotal:") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    if let Ok(kb) = parts[1].parse::<u64>() {
-                        return Ok(kb / 1024); // Convert KB to MB
-                    }
-                }
-            }
-        }
-
-        Ok(1024) // Default to 1GB if detection fails
+    /// MemAvailable from /proc/meminfo in MB: what can be allocated without swapping
+    pub fn available_memory_mb() -> Result<u64> {
+        let meminfo = fs::read_to_string("/proc/meminfo")
+            .context("Failed to read /proc/meminfo")?;
+        meminfo_mb(&meminfo, "MemAvailable").context("No MemAvailable in /proc/meminfo")
     }
 
     fn check_ebpf() -> bool {
@@ -117,23 +115,18 @@ impl SystemEnvironment {
     }
 
     /// Compute auto-tuned CPU threshold based on vCPU count
-    /// Formula: max(20.0, 8.0 * (1.0 / vcpu_count) * 100)
-    /// This represents a significant portion of one core
-    pub fn compute_cpu_threshold(&self, base_threshold: f32, vcpu_override: Option<usize>) -> f32 {
+    /// Formula: min(base / vcpu_count, max(MIN_CPU_THRESHOLD, per_core_threshold / vcpu_count))
+    /// Both `base_threshold` and `per_core_threshold` are percent of one core; the
+    /// result is a share of the whole machine: compare it against a process's
+    /// `cpu_percent` (percent of one core) divided by the vCPU count.
+    pub fn compute_cpu_threshold(&self, base_threshold: f32, per_core_threshold: f32, vcpu_override: Option<usize>) -> f32 {
         let vcpu = vcpu_override.unwrap_or(self.vcpu_count);
-        
-        if vcpu == 0 {
-            return base_threshold;
+        let system_base = base_threshold / vcpu.max(1) as f32;
+        match auto_tuned_cpu_threshold(per_core_threshold, vcpu) {
+            // Auto-tuning only ever makes detection more sensitive than the configured base
+            Some(system_threshold) => system_base.min(system_threshold),
+            None => system_base,
         }
-
-        // Calculate per-vCPU sensitivity
-        // On 4 vCPU: 8.0 * (1.0 / 4) * 100 = 200% of one core = 50% system
-        // But we want to be conservative, so use: 25% of one core = 6.25% system on 4 vCPU
-        let per_core_threshold = 25.0; // 25% of one core
-        let system_threshold = (per_core_threshold / vcpu as f32).max(5.0); // Minimum 5%
-        
-        // Use the higher of base threshold or auto-tuned threshold
-        base_threshold.max(system_threshold)
     }
 
     /// Scale duration with load average
@@ -168,23 +161,90 @@ impl SystemEnvironment {
     }
 }
 
+/// System-wide share of `per_core_threshold` percent of one core on `vcpu` cores,
+/// e.g. 80% of one core: 80% system on 1 vCPU, 10% on 8, 5% (floor) on 16+.
+/// None when there is nothing to derive it from.
+pub fn auto_tuned_cpu_threshold(per_core_threshold: f32, vcpu: usize) -> Option<f32> {
+    (vcpu > 0 && per_core_threshold > 0.0)
+        .then(|| (per_core_threshold / vcpu as f32).max(MIN_CPU_THRESHOLD))
+}
+
+/// A `Key:   1234 kB` field of /proc/meminfo, converted to MB
+fn meminfo_mb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+/// Whether a full file scan may start with `available_mb` free. A reading that
+/// couldn't be taken doesn't block the scan; `min_free_mb` = 0 disables the check.
+pub fn memory_allows_scan(available_mb: Option<u64>, min_free_mb: u64) -> bool {
+    available_mb.is_none_or(|available| available >= min_free_mb)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_cpu_threshold_calculation() {
-        let env = SystemEnvironment {
-            vcpu_count: 4,
+    fn env(vcpu_count: usize) -> SystemEnvironment {
+        SystemEnvironment {
+            vcpu_count,
             total_ram_mb: 8192,
             has_ebpf: false,
             has_cgroups_v2: false,
             load_average: (1.0, 1.0, 1.0),
-        };
+        }
+    }
 
-        let threshold = env.compute_cpu_threshold(20.0, None);
-        // On 4 vCPU: 25% / 4 = 6.25%, but we use max(20.0, 6.25) = 20.0
-        assert!(threshold >= 5.0);
+    #[test]
+    fn test_cpu_threshold_calculation() {
+        let threshold = env(4).compute_cpu_threshold(20.0, 80.0, None);
+        // On 4 vCPU: 20% of one core is 5% of the machine, below the auto-tuned 80% / 4 = 20%
+        assert_eq!(threshold, 5.0);
+        // On 8 vCPU with a base above per_core_threshold: 10% of the machine, i.e. 80% of one core
+        assert_eq!(env(8).compute_cpu_threshold(100.0, 80.0, None) * 8.0, 80.0);
     }
-}
 
+    #[test]
+    fn cpu_threshold_never_exceeds_the_configured_share_of_one_core() {
+        // Defaults: per_core_threshold is above cpu_threshold, so it stays 20% of one core
+        for vcpu in [1, 2, 4, 16, 64] {
+            assert_eq!(env(vcpu).compute_cpu_threshold(20.0, 80.0, None) * vcpu as f32, 20.0);
+        }
+
+        // A per-core setting below the base shows the curve before the floor
+        let thresholds: Vec<f32> = [1, 2, 4, 16, 64].iter()
+            .map(|&vcpu| env(vcpu).compute_cpu_threshold(200.0, 160.0, None))
+            .collect();
+        assert_eq!(thresholds, vec![160.0, 80.0, 40.0, 10.0, 200.0 / 64.0]);
+        assert!(thresholds.windows(2).all(|w| w[0] >= w[1]), "{:?}", thresholds);
+        // Each is at most the configured share of one core
+        for (&vcpu, threshold) in [1, 2, 4, 16, 64].iter().zip(&thresholds) {
+            assert!(threshold * vcpu as f32 <= 200.0);
+        }
+    }
+
+    #[test]
+    fn cpu_threshold_override_and_degenerate_inputs() {
+        assert_eq!(env(1).compute_cpu_threshold(100.0, 80.0, Some(8)), 10.0);
+        assert_eq!(env(0).compute_cpu_threshold(20.0, 80.0, None), 20.0);
+        assert_eq!(env(8).compute_cpu_threshold(20.0, 0.0, None), 2.5);
+        // The floor never raises the threshold above the configured base
+        assert_eq!(env(64).compute_cpu_threshold(640.0, 80.0, None), MIN_CPU_THRESHOLD);
+        assert_eq!(env(64).compute_cpu_threshold(2.0, 80.0, None), 2.0 / 64.0);
+    }
+
+    #[test]
+    fn low_available_memory_defers_the_scan() {
+        let meminfo = "MemTotal:        2014712 kB\nMemFree:           61204 kB\nMemAvailable:      98304 kB\n";
+        assert_eq!(meminfo_mb(meminfo, "MemTotal"), Some(1967));
+        let available = meminfo_mb(meminfo, "MemAvailable");
+        assert_eq!(available, Some(96));
+
+        assert!(!memory_allows_scan(available, 128));
+        assert!(memory_allows_scan(available, 96));
+        assert!(memory_allows_scan(available, 0));
+        assert!(memory_allows_scan(None, 128));
+    }
+}
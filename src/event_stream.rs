@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::config::AlertSeverity;
+use crate::process_monitor::ProcessInfo;
+
+/// Events buffered per subscriber before a slow `watch` client starts missing some
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Detection,
+    Kill,
+    Scan,
+    Malware,
+    Persistence,  // Privilege or boot persistence (sudoers, ...)
+}
+
+/// Something the daemon did or saw, streamed to `hora-police watch`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaemonEvent {
+    pub timestamp: DateTime<Utc>,
+    pub severity: AlertSeverity,
+    pub kind: EventKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,  // Malware signature that matched
+}
+
+impl DaemonEvent {
+    pub fn new(severity: AlertSeverity, kind: EventKind, message: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            severity,
+            kind,
+            message: message.into(),
+            pid: None,
+            path: None,
+            confidence: None,
+            signature: None,
+        }
+    }
+
+    pub fn with_process(mut self, process: &ProcessInfo) -> Self {
+        self.pid = Some(process.pid);
+        self.path = Some(process.binary_path.clone());
+        self
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.display().to_string());
+        self
+    }
+
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+
+    pub fn with_signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+}
+
+/// Broadcast channel of daemon events. Publishing never blocks and is a no-op
+/// while nobody is watching.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<DaemonEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: DaemonEvent) {
+        // Err only means there are no subscribers
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Stream events as JSON lines to every client of a root-only Unix socket
+pub async fn serve(bus: EventBus, socket_path: &Path) -> Result<()> {
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Stale socket from a previous run
+    if socket_path.exists() {
+        std::fs::remove_file(socket_path)?;
+    }
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to bind event socket {:?}", socket_path))?;
+    std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))?;
+    info!("📡 Event stream listening on {:?}", socket_path);
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut receiver = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        debug!("Event watcher lagged, {} events dropped", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Ok(mut line) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                line.push('\n');
+                if stream.write_all(line.as_bytes()).await.is_err() {
+                    break; // Watcher disconnected
+                }
+            }
+        });
+    }
+}
+
+/// Connect to the daemon's event socket and print events until it closes
+pub async fn watch(socket_path: &Path, json: bool) -> Result<()> {
+    let stream = UnixStream::connect(socket_path).await
+        .with_context(|| format!("Failed to connect to {:?} (is the daemon running?)", socket_path))?;
+    let color = std::io::stdout().is_terminal();
+    let mut lines = BufReader::new(stream).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if json {
+            println!("{}", line);
+            continue;
+        }
+        match serde_json::from_str::<DaemonEvent>(&line) {
+            Ok(event) => println!("{}", format_event(&event, color)),
+            Err(e) => warn!("Unreadable event from daemon: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// One terminal line per event, colored by severity when `color` is set
+pub fn format_event(event: &DaemonEvent, color: bool) -> String {
+    let (label, ansi) = match event.severity {
+        AlertSeverity::Info => ("INFO", "\x1b[36m"),
+        AlertSeverity::Warning => ("WARN", "\x1b[33m"),
+        AlertSeverity::Critical => ("CRIT", "\x1b[1;31m"),
+    };
+    let severity = if color {
+        format!("{}{:<4}\x1b[0m", ansi, label)
+    } else {
+        format!("{:<4}", label)
+    };
+
+    let kind = serde_json::to_value(event.kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let mut line = format!(
+        "{} {} {:<9} {}",
+        event.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+        severity,
+        kind,
+        event.message
+    );
+    if let Some(pid) = event.pid {
+        line.push_str(&format!(" [pid {}]", pid));
+    }
+    if let Some(ref path) = event.path {
+        line.push_str(&format!(" {}", path));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_events_with_and_without_color() {
+        let process = ProcessInfo { pid: 4242, binary_path: "/tmp/.x/xmrig".to_string(), ..Default::default() };
+        let event = DaemonEvent::new(AlertSeverity::Critical, EventKind::Kill, "Killed").with_process(&process);
+
+        let plain = format_event(&event, false);
+        assert!(plain.contains("CRIT kill      Killed [pid 4242] /tmp/.x/xmrig"));
+        assert!(!plain.contains('\x1b'));
+        assert!(format_event(&event, true).contains("\x1b[1;31mCRIT\x1b[0m"));
+
+        let parsed: DaemonEvent = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+        assert_eq!(parsed, event);
+    }
+
+    #[tokio::test]
+    async fn streams_published_events_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("events.sock");
+        let bus = EventBus::new();
+        let server_bus = bus.clone();
+        let server_socket = socket.clone();
+        tokio::spawn(async move { serve(server_bus, &server_socket).await });
+
+        let mut stream = None;
+        for _ in 0..50 {
+            if let Ok(s) = UnixStream::connect(&socket).await {
+                stream = Some(s);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let mut lines = BufReader::new(stream.expect("event socket never came up")).lines();
+
+        // The subscription is registered after accept; publish until it arrives
+        let event = DaemonEvent::new(AlertSeverity::Info, EventKind::Scan, "File scan started");
+        let received = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                bus.publish(event.clone());
+                if let Ok(Ok(Some(line))) = tokio::time::timeout(
+                    std::time::Duration::from_millis(50), lines.next_line()).await {
+                    return line;
+                }
+            }
+        }).await.unwrap();
+        assert_eq!(serde_json::from_str::<DaemonEvent>(&received).unwrap(), event);
+    }
+}
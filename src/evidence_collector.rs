@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::process_monitor::ProcessInfo;
+use crate::termination::process_state;
+
+/// /proc/<pid> files copied into every bundle
+const PROC_ARTIFACTS: &[&str] = &["cmdline", "environ", "maps", "status", "stat", "cgroup", "limits"];
+
+/// NUL-separated /proc files that get a readable one-entry-per-line copy alongside
+const NUL_SEPARATED: &[&str] = &["cmdline", "environ"];
+
+/// How long to wait for SIGSTOP to take effect before collecting anyway
+const FREEZE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Forensic artifacts gathered from a frozen process
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceBundle {
+    pub pid: i32,
+    pub binary_path: String,
+    pub directory: PathBuf,
+    pub binary_sha256: Option<String>,
+    pub artifacts: Vec<String>,
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Freezes a process and copies its maps, fds, environ, cmdline and binary into a
+/// timestamped directory so responders keep artifacts, not just a dead process
+pub struct EvidenceCollector {
+    base_dir: PathBuf,
+}
+
+impl EvidenceCollector {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self { base_dir: base_dir.into() }
+    }
+
+    /// SIGSTOP the process and gather its bundle. On success the process is left
+    /// stopped for the caller to kill or `release`; on failure it is released.
+    pub async fn collect(&self, process: &ProcessInfo) -> Result<EvidenceBundle> {
+        freeze(process.pid).await?;
+        match self.gather(process) {
+            Ok(bundle) => Ok(bundle),
+            Err(e) => {
+                if let Err(release_err) = release(process.pid) {
+                    warn!("Failed to release PID {} after evidence collection failed: {}", process.pid, release_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn gather(&self, process: &ProcessInfo) -> Result<EvidenceBundle> {
+        let collected_at = Utc::now();
+        let directory = self.base_dir.join(format!("{}_pid{}", collected_at.format("%Y%m%d_%H%M%S"), process.pid));
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create evidence directory {:?}", directory))?;
+        fs::set_permissions(&directory, fs::Permissions::from_mode(0o700))?;
+
+        let proc_dir = PathBuf::from(format!("/proc/{}", process.pid));
+        let mut artifacts = Vec::new();
+
+        for name in PROC_ARTIFACTS {
+            let content = match fs::read(proc_dir.join(name)) {
+                Ok(content) => content,
+                Err(e) => {
+                    debug!("Skipping /proc/{}/{}: {}", process.pid, name, e);
+                    continue;
+                }
+            };
+            write_private(&directory.join(name), &content)?;
+            artifacts.push(name.to_string());
+
+            if NUL_SEPARATED.contains(name) {
+                let readable: Vec<u8> = content.iter().map(|&b| if b == 0 { b'\n' } else { b }).collect();
+                let readable_name = format!("{}.txt", name);
+                write_private(&directory.join(&readable_name), &readable)?;
+                artifacts.push(readable_name);
+            }
+        }
+
+        if let Ok(fds) = list_fds(&proc_dir) {
+            write_private(&directory.join("fds.txt"), fds.as_bytes())?;
+            artifacts.push("fds.txt".to_string());
+        }
+
+        // /proc/<pid>/exe still reads a binary that was deleted after starting
+        let binary_sha256 = match fs::read(proc_dir.join("exe")) {
+            Ok(binary) => {
+                // Read-only and not executable, so the copy can't be run by accident
+                let copy = directory.join("binary");
+                fs::write(&copy, &binary)?;
+                fs::set_permissions(&copy, fs::Permissions::from_mode(0o400))?;
+                artifacts.push("binary".to_string());
+                Some(hex::encode(Sha256::digest(&binary)))
+            }
+            Err(e) => {
+                warn!("Could not copy binary of PID {}: {}", process.pid, e);
+                None
+            }
+        };
+
+        let bundle = EvidenceBundle {
+            pid: process.pid,
+            binary_path: process.binary_path.clone(),
+            directory: directory.clone(),
+            binary_sha256,
+            artifacts,
+            collected_at,
+        };
+        write_private(&directory.join("bundle.json"), serde_json::to_string_pretty(&bundle)?.as_bytes())?;
+
+        info!("🧾 Collected evidence for PID {} into {} ({} artifacts)",
+              process.pid, directory.display(), bundle.artifacts.len());
+        Ok(bundle)
+    }
+}
+
+/// SIGSTOP the process and wait briefly until it is actually stopped
+pub async fn freeze(pid: i32) -> Result<()> {
+    signal::kill(Pid::from_raw(pid), Signal::SIGSTOP)
+        .with_context(|| format!("Failed to SIGSTOP PID {}", pid))?;
+    let deadline = tokio::time::Instant::now() + FREEZE_TIMEOUT;
+    while process_state(pid) != Some('T') && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    Ok(())
+}
+
+/// Let a frozen process run again (the decision was not to kill it)
+pub fn release(pid: i32) -> Result<()> {
+    signal::kill(Pid::from_raw(pid), Signal::SIGCONT)
+        .with_context(|| format!("Failed to SIGCONT PID {}", pid))?;
+    Ok(())
+}
+
+fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("Failed to write {:?}", path))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+/// One "fd -> target" line per open descriptor (files, sockets, pipes, memfds)
+fn list_fds(proc_dir: &Path) -> Result<String> {
+    let mut fds: Vec<(u32, String)> = fs::read_dir(proc_dir.join("fd"))?
+        .flatten()
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_string_lossy().parse().ok()?;
+            let target = fs::read_link(entry.path()).ok()?;
+            Some((fd, target.to_string_lossy().to_string()))
+        })
+        .collect();
+    fds.sort();
+    Ok(fds.iter().map(|(fd, target)| format!("{} -> {}\n", fd, target)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn collects_bundle_from_frozen_process_and_releases_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .env("HORA_EVIDENCE_TEST", "1")
+            .spawn()
+            .unwrap();
+        let pid = child.id() as i32;
+        let process = ProcessInfo { pid, binary_path: "/usr/bin/sleep".to_string(), ..Default::default() };
+
+        let bundle = EvidenceCollector::new(dir.path()).collect(&process).await.unwrap();
+        assert_eq!(process_state(pid), Some('T'));
+        assert!(bundle.directory.starts_with(dir.path()));
+        for artifact in ["cmdline.txt", "environ.txt", "maps", "fds.txt", "binary", "bundle.json"] {
+            assert!(bundle.directory.join(artifact).exists(), "missing {}", artifact);
+        }
+        let environ = fs::read_to_string(bundle.directory.join("environ.txt")).unwrap();
+        assert!(environ.lines().any(|l| l == "HORA_EVIDENCE_TEST=1"));
+        assert_eq!(fs::read_to_string(bundle.directory.join("cmdline.txt")).unwrap(), "sleep\n30\n");
+
+        let binary = fs::read(format!("/proc/{}/exe", pid)).unwrap();
+        assert_eq!(bundle.binary_sha256, Some(hex::encode(Sha256::digest(&binary))));
+        let mode = fs::metadata(bundle.directory.join("binary")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o400);
+
+        release(pid).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_ne!(process_state(pid), Some('T'));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
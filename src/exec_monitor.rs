@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Where tracefs is mounted on current and older kernels
+const TRACEFS_ROOTS: &[&str] = &["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+
+/// Private tracing instance, so enabling the event doesn't disturb other tracers
+const INSTANCE_NAME: &str = "hora-police";
+
+const EXEC_EVENT: &str = "events/sched/sched_process_exec";
+
+/// Queued exec events before new ones are dropped; the polling loop still sees them
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// How long the reader waits on an empty trace_pipe before checking for shutdown
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// One `sched_process_exec` tracepoint hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecEvent {
+    pub pid: i32,
+    pub filename: String,
+}
+
+/// Parse a trace_pipe line such as
+/// `  bash-1234  [002] d..1. 5678.901234: sched_process_exec: filename=/usr/bin/ls pid=5678 old_pid=5678`
+pub fn parse_exec_event(line: &str) -> Option<ExecEvent> {
+    let fields = line.split_once("sched_process_exec:")?.1;
+    // filename may contain spaces, so take everything up to the " pid=" field
+    let (filename, rest) = fields.trim_start().strip_prefix("filename=")?.rsplit_once(" pid=")?;
+    let pid = rest.split_whitespace().next()?.parse().ok()?;
+    Some(ExecEvent { pid, filename: filename.to_string() })
+}
+
+/// Streams new executions from the kernel's `sched_process_exec` tracepoint, read
+/// through a tracefs instance's trace_pipe (no BPF program is loaded, so no BPF
+/// toolchain or extra crates are needed). Dropping the monitor stops and joins the
+/// reader thread, then disables the event and removes the instance.
+pub struct ExecMonitor {
+    instance: PathBuf,
+    stop: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl ExecMonitor {
+    /// Enable the tracepoint and start a reader thread sending new PIDs. Fails when
+    /// tracefs or the event isn't available, in which case polling alone is used.
+    pub fn start() -> Result<(Self, mpsc::Receiver<i32>)> {
+        let root = TRACEFS_ROOTS.iter()
+            .map(Path::new)
+            .find(|root| root.join(EXEC_EVENT).exists())
+            .context("tracefs with the sched_process_exec event is not mounted")?;
+
+        let instance = root.join("instances").join(INSTANCE_NAME);
+        if !instance.exists() {
+            // mkdir in instances/ creates a fresh trace buffer
+            fs::create_dir(&instance)
+                .with_context(|| format!("Failed to create tracing instance {}", instance.display()))?;
+        }
+        fs::write(instance.join(EXEC_EVENT).join("enable"), "1")
+            .context("Failed to enable the sched_process_exec tracepoint")?;
+        // Non-blocking, so the reader can notice shutdown while no one is exec'ing
+        let pipe = fs::File::options()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(instance.join("trace_pipe"))
+            .context("Failed to open trace_pipe")?;
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let stop = Arc::new(AtomicBool::new(false));
+        let reader_stop = Arc::clone(&stop);
+        let reader = std::thread::Builder::new()
+            .name("exec-events".to_string())
+            .spawn(move || read_events(BufReader::new(pipe), &tx, &reader_stop))
+            .context("Failed to spawn exec event reader")?;
+
+        info!("⚡ Watching sched_process_exec through {}", instance.display());
+        Ok((Self { instance, stop, reader: Some(reader) }, rx))
+    }
+}
+
+/// Send the PID of every exec event until `stop` is set, the daemon stops listening
+/// or trace_pipe fails
+fn read_events(mut pipe: impl BufRead, tx: &mpsc::Sender<i32>, stop: &AtomicBool) {
+    let mut line = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        // On WouldBlock a partial line stays in `line` and is completed by the next read
+        match pipe.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) if line.ends_with(b"\n") => {
+                if let Some(event) = parse_exec_event(&String::from_utf8_lossy(&line)) {
+                    debug!("exec: PID {} {}", event.pid, event.filename);
+                    if let Err(mpsc::error::TrySendError::Closed(_)) = tx.try_send(event.pid) {
+                        break;
+                    }
+                }
+                line.clear();
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(IDLE_WAIT),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => {
+                warn!("Reading trace_pipe failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+impl Drop for ExecMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(reader) = self.reader.take() {
+            if reader.join().is_err() {
+                warn!("Exec event reader panicked");
+            }
+        }
+        if let Err(e) = fs::write(self.instance.join(EXEC_EVENT).join("enable"), "0") {
+            warn!("Failed to disable the sched_process_exec tracepoint: {}", e);
+        }
+        // trace_pipe is closed now, so the instance isn't busy
+        if let Err(e) = fs::remove_dir(&self.instance) {
+            debug!("Failed to remove tracing instance {}: {}", self.instance.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_trace_pipe_lines() {
+        let line = "            bash-1234    [002] d..1.  5678.901234: sched_process_exec: filename=/tmp/.x/kdevtmpfsi pid=5678 old_pid=5678";
+        assert_eq!(parse_exec_event(line), Some(ExecEvent { pid: 5678, filename: "/tmp/.x/kdevtmpfsi".to_string() }));
+
+        let spaced = "sh-1 [000] ..... 1.0: sched_process_exec: filename=/tmp/my app/run pid=42 old_pid=42";
+        assert_eq!(parse_exec_event(spaced).map(|e| e.filename), Some("/tmp/my app/run".to_string()));
+
+        assert_eq!(parse_exec_event("sh-1 [000] ..... 1.0: sched_process_fork: comm=sh pid=1 child_pid=2"), None);
+        assert_eq!(parse_exec_event("sh-1 [000] ..... 1.0: sched_process_exec: filename=/bin/ls"), None);
+    }
+
+    #[test]
+    fn reader_sends_pids_and_stops_at_end_of_pipe() {
+        let trace = "sh-1 [000] ..... 1.0: sched_process_exec: filename=/tmp/.x/xmrig pid=42 old_pid=42\n\
+                     sh-1 [000] ..... 1.0: sched_process_fork: comm=sh pid=1 child_pid=2\n\
+                     sh-1 [000] ..... 1.1: sched_process_exec: filename=/usr/bin/ls pid=43 old_pid=43\n";
+        let (tx, mut rx) = mpsc::channel(8);
+        read_events(trace.as_bytes(), &tx, &AtomicBool::new(false));
+        assert_eq!(rx.try_recv().ok(), Some(42));
+        assert_eq!(rx.try_recv().ok(), Some(43));
+        assert!(rx.try_recv().is_err());
+
+        // A stop request ends the loop before anything more is read
+        let (tx, mut rx) = mpsc::channel(8);
+        read_events(trace.as_bytes(), &tx, &AtomicBool::new(true));
+        assert!(rx.try_recv().is_err());
+    }
+}
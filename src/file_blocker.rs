@@ -2,6 +2,7 @@ use anyhow::{Result, Context};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::os::unix::fs::PermissionsExt;
 use chrono::Utc;
 use tracing::{info, warn, error};
 use crate::file_watcher::FileWatcher;
@@ -65,7 +66,7 @@ impl FileBlocker {
         }
 
         // Record in database if available
-        if let Some(ref db) = self.db {
+        if self.db.is_some() {
             // Could add a table for blocked paths tracking
             // For now, we'll just log
             info!("🚫 Blocked path: {}", path.display());
@@ -122,7 +123,7 @@ impl FileBlocker {
         if path.exists() {
             // Remove write protection if present
             let mut perms = fs::metadata(path)?.permissions();
-            perms.set_readonly(false);
+            perms.set_mode(perms.mode() | 0o200);
             fs::set_permissions(path, perms)?;
             
             // Delete the file
@@ -1,16 +1,77 @@
 use anyhow::{Result, Context};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
-use chrono::Utc;
-use tracing::{info, warn, error};
-use walkdir::WalkDir;
+use std::os::unix::fs::PermissionsExt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::os::unix::fs::MetadataExt;
+use tracing::{info, warn};
 use nix::unistd::Pid;
 use nix::sys::signal;
+use crate::termination::{is_alive, terminate, KillSignals, KillTimeouts, TerminationOutcome};
+use crate::quarantine_crypto::{self, QuarantineKey, SEALED_EXTENSION};
+
+/// File-name fragments that mark a file in a malware drop directory as part of the
+/// drop. Generic app words (e.g. "next", as in Next.js) don't belong here.
+pub const DEFAULT_SUSPICIOUS_NAMES: &[&str] = &[
+    "solrz", "e386", "payload.so", "miner", "xmrig",
+    "ccminer", "cpuminer", "malware", "trojan", "virus",
+];
+
+/// Directories (and everything under them) origin cleanup never deletes wholesale
+pub const DEFAULT_PROTECTED_PATHS: &[&str] = &[
+    "/bin", "/boot", "/etc", "/lib", "/lib64", "/opt", "/sbin", "/srv", "/usr", "/var/lib", "/var/www",
+];
+
+/// Files or directories that mark an application's directory (Node/Next.js builds,
+/// checkouts): origin cleanup never touches its siblings
+const APP_MARKERS: &[&str] = &["package.json", "node_modules", ".next", ".git", "next.config.js", "next.config.mjs"];
+
+/// Evidence sidecar stored next to each quarantined file (`<name>.meta.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineMetadata {
+    pub original_path: PathBuf,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub quarantined_at: DateTime<Utc>,
+    #[serde(default)]
+    pub encrypted: bool,  // Stored as a gzip+AES-256-GCM archive
+    #[serde(default)]
+    pub path_tag: String,  // `path_tag(original_path)`, also embedded in the quarantine file name
+}
+
+/// Hex characters of the original path's SHA256 embedded in quarantine names
+const PATH_TAG_LEN: usize = 12;
+
+/// Short hash of a file's full original path. Quarantine names are
+/// `<timestamp>_<tag>_<file name>`, so same-named files from different
+/// directories never collide and copies of a path can be found by tag.
+pub fn path_tag(original_path: &Path) -> String {
+    let digest = Sha256::digest(original_path.as_os_str().as_encoded_bytes());
+    hex::encode(digest)[..PATH_TAG_LEN].to_string()
+}
+
+impl QuarantineMetadata {
+    pub fn sidecar_path(quarantine_path: &Path) -> PathBuf {
+        let mut name = quarantine_path.as_os_str().to_owned();
+        name.push(".meta.json");
+        PathBuf::from(name)
+    }
+}
 
 pub struct FileQuarantine {
     quarantine_dir: PathBuf,
     auto_delete: bool,
     aggressive_cleanup: bool,
+    kill_timeouts: KillTimeouts,
+    encryption_key: Option<QuarantineKey>,
+    aggressive_cron_cleanup: bool,
+    prefer_delete: bool,
+    suspicious_names: Vec<String>,  // Lowercase
+    protected_paths: Vec<PathBuf>,
 }
 
 impl FileQuarantine {
@@ -28,32 +89,217 @@ impl FileQuarantine {
             quarantine_dir,
             auto_delete,
             aggressive_cleanup,
+            kill_timeouts: KillTimeouts::default(),
+            encryption_key: None,
+            aggressive_cron_cleanup: false,
+            prefer_delete: false,
+            suspicious_names: DEFAULT_SUSPICIOUS_NAMES.iter().map(|n| n.to_string()).collect(),
+            protected_paths: DEFAULT_PROTECTED_PATHS.iter().map(PathBuf::from).collect(),
         }
     }
 
+    pub fn set_kill_timeouts(&mut self, timeouts: KillTimeouts) {
+        self.kill_timeouts = timeouts;
+    }
+
+    /// Delete instead of quarantining, e.g. while the quarantine disk is critically low
+    pub fn set_prefer_delete(&mut self, prefer_delete: bool) {
+        self.prefer_delete = prefer_delete;
+    }
+
+    pub fn prefers_delete(&self) -> bool {
+        self.prefer_delete && !self.auto_delete
+    }
+
+    /// Also remove cron lines matching broad download/decode patterns during origin cleanup
+    pub fn set_aggressive_cron_cleanup(&mut self, enabled: bool) {
+        self.aggressive_cron_cleanup = enabled;
+    }
+
+    /// File-name fragments that make a sibling of a malware file part of the drop
+    pub fn set_suspicious_names(&mut self, names: &[String]) {
+        self.suspicious_names = names.iter().map(|n| n.to_lowercase()).collect();
+    }
+
+    /// Directories origin cleanup never deletes from, beyond the malware file itself
+    pub fn set_protected_paths(&mut self, paths: &[String]) {
+        self.protected_paths = paths.iter().map(PathBuf::from).collect();
+    }
+
+    /// Store quarantined files as encrypted archives that can't be executed in place
+    pub fn set_encryption_key(&mut self, key: QuarantineKey) {
+        self.encryption_key = Some(key);
+    }
+
     /// Quarantine a file by moving it to the quarantine directory
     pub fn quarantine_file(&self, file_path: &Path) -> Result<PathBuf> {
-        if !file_path.exists() {
-            return Err(anyhow::anyhow!("File does not exist: {}", file_path.display()));
-        }
+        self.quarantine_file_to(file_path, &self.plan_quarantine_path(file_path))
+    }
 
-        // Generate quarantine filename with timestamp
+    /// Where `file_path` would be quarantined now. A rollback manifest written before
+    /// the cleanup records this, and `quarantine_file_to` then uses exactly this path.
+    pub fn plan_quarantine_path(&self, file_path: &Path) -> PathBuf {
         let file_name = file_path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown");
-        
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-        let quarantine_name = format!("{}_{}", timestamp, file_name);
-        let quarantine_path = self.quarantine_dir.join(&quarantine_name);
+        self.unique_quarantine_path(&path_tag(file_path), file_name)
+    }
+
+    /// Quarantine a file to a path from `plan_quarantine_path`
+    pub fn quarantine_file_to(&self, file_path: &Path, quarantine_path: &Path) -> Result<PathBuf> {
+        if !file_path.exists() {
+            return Err(anyhow::anyhow!("File does not exist: {}", file_path.display()));
+        }
+        if quarantine_path.exists() {
+            return Err(anyhow::anyhow!("Quarantine path already taken: {}", quarantine_path.display()));
+        }
+        let tag = path_tag(file_path);
+
+        // Capture original ownership/permissions so the file can be restored later
+        let metadata = fs::metadata(file_path)?;
+        let evidence = QuarantineMetadata {
+            original_path: file_path.to_path_buf(),
+            mode: metadata.mode() & 0o7777,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            quarantined_at: Utc::now(),
+            encrypted: self.encryption_key.is_some(),
+            path_tag: tag,
+        };
+
+        if let Some(ref key) = self.encryption_key {
+            // Seal into an archive (0600) and drop the original executable
+            let data = fs::read(file_path)
+                .with_context(|| format!("Failed to read file for quarantine: {}", file_path.display()))?;
+            fs::write(quarantine_path, quarantine_crypto::seal(&data, key)?)
+                .with_context(|| format!("Failed to write quarantine archive: {}", quarantine_path.display()))?;
+            fs::set_permissions(quarantine_path, fs::Permissions::from_mode(0o600))?;
+            fs::remove_file(file_path)
+                .with_context(|| format!("Failed to remove original after sealing: {}", file_path.display()))?;
+        } else {
+            // Move file to quarantine
+            fs::rename(file_path, quarantine_path)
+                .with_context(|| format!("Failed to move file to quarantine: {}", file_path.display()))?;
+        }
 
-        // Move file to quarantine
-        fs::rename(file_path, &quarantine_path)
-            .with_context(|| format!("Failed to move file to quarantine: {}", file_path.display()))?;
+        if let Err(e) = fs::write(
+            QuarantineMetadata::sidecar_path(quarantine_path),
+            serde_json::to_string_pretty(&evidence)?,
+        ) {
+            warn!("Failed to write quarantine metadata for {}: {}", quarantine_path.display(), e);
+        }
 
         info!("✅ Quarantined file: {} -> {}", 
               file_path.display(), quarantine_path.display());
 
-        Ok(quarantine_path)
+        Ok(quarantine_path.to_path_buf())
+    }
+
+    /// `<timestamp>_<tag>_<name>`, with a counter appended if the same path was
+    /// already quarantined within this second
+    fn unique_quarantine_path(&self, tag: &str, file_name: &str) -> PathBuf {
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let extension = match self.encryption_key {
+            Some(_) => format!(".{}", SEALED_EXTENSION),
+            None => String::new(),
+        };
+        let mut candidate = self.quarantine_dir.join(format!("{}_{}_{}{}", timestamp, tag, file_name, extension));
+        let mut counter = 1;
+        while candidate.exists() {
+            candidate = self.quarantine_dir.join(format!("{}_{}_{}-{}{}", timestamp, tag, file_name, counter, extension));
+            counter += 1;
+        }
+        candidate
+    }
+
+    /// Quarantined copies of `original_path`, found by the path tag in their names
+    pub fn quarantined_copies(&self, original_path: &Path) -> Vec<PathBuf> {
+        let marker = format!("_{}_", path_tag(original_path));
+        let mut copies: Vec<PathBuf> = fs::read_dir(&self.quarantine_dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        copies.retain(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.contains(&marker) && !name.ends_with(".meta.json")
+        });
+        copies.sort();
+        copies
+    }
+
+    /// Restore a quarantined file to its original location with its original
+    /// permissions and ownership. Refuses to overwrite an existing file.
+    /// Encrypted archives are opened with the key from `QuarantineKey::load`.
+    pub fn restore_file(quarantine_path: &Path, original_path: &Path) -> Result<()> {
+        let key = if Self::is_sealed_file(quarantine_path) {
+            Some(QuarantineKey::load()?)
+        } else {
+            None
+        };
+        Self::restore_file_with_key(quarantine_path, original_path, key.as_ref())
+    }
+
+    fn is_sealed_file(path: &Path) -> bool {
+        let mut magic = [0u8; 4];
+        fs::File::open(path)
+            .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+            .map(|_| quarantine_crypto::is_sealed(&magic))
+            .unwrap_or(false)
+    }
+
+    pub fn restore_file_with_key(
+        quarantine_path: &Path,
+        original_path: &Path,
+        key: Option<&QuarantineKey>,
+    ) -> Result<()> {
+        if !quarantine_path.exists() {
+            return Err(anyhow::anyhow!("Quarantined file not found: {}", quarantine_path.display()));
+        }
+        if original_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Refusing to restore: {} already exists", original_path.display()
+            ));
+        }
+
+        let sidecar = QuarantineMetadata::sidecar_path(quarantine_path);
+        let evidence: Option<QuarantineMetadata> = fs::read_to_string(&sidecar)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        if evidence.is_none() {
+            warn!("No quarantine metadata for {}, restoring with mode 0600", quarantine_path.display());
+        }
+
+        if let Some(parent) = original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if Self::is_sealed_file(quarantine_path) {
+            let key = key.ok_or_else(|| anyhow::anyhow!(
+                "{} is encrypted but no quarantine key was provided", quarantine_path.display()
+            ))?;
+            let data = quarantine_crypto::open(&fs::read(quarantine_path)?, key)?;
+            fs::write(original_path, data)
+                .with_context(|| format!("Failed to restore file to {}", original_path.display()))?;
+            fs::remove_file(quarantine_path)?;
+        } else if fs::rename(quarantine_path, original_path).is_err() {
+            // Quarantine dir may live on another filesystem
+            fs::copy(quarantine_path, original_path)
+                .with_context(|| format!("Failed to restore file to {}", original_path.display()))?;
+            fs::remove_file(quarantine_path)?;
+        }
+
+        let mode = evidence.as_ref().map(|e| e.mode).unwrap_or(0o600);
+        fs::set_permissions(original_path, fs::Permissions::from_mode(mode))?;
+        if let Some(ref evidence) = evidence {
+            if let Err(e) = std::os::unix::fs::chown(original_path, Some(evidence.uid), Some(evidence.gid)) {
+                warn!("Failed to restore ownership of {}: {}", original_path.display(), e);
+            }
+        }
+        let _ = fs::remove_file(&sidecar);
+
+        info!("♻️  Restored quarantined file: {} -> {}",
+              quarantine_path.display(), original_path.display());
+
+        Ok(())
     }
 
     /// Delete a malicious file permanently
@@ -64,7 +310,7 @@ impl FileQuarantine {
 
         // Remove write protection if present
         let mut perms = fs::metadata(file_path)?.permissions();
-        perms.set_readonly(false);
+        perms.set_mode(perms.mode() | 0o200);
         fs::set_permissions(file_path, perms)?;
 
         // Delete the file
@@ -76,30 +322,35 @@ impl FileQuarantine {
         Ok(())
     }
 
+    /// Whether malware files are deleted (auto_delete, or the quarantine disk is
+    /// critically low) rather than kept in quarantine
+    pub fn deletes_files(&self) -> bool {
+        self.auto_delete || self.prefer_delete
+    }
+
     /// Quarantine or delete based on configuration
     pub fn handle_malware(&self, file_path: &Path) -> Result<QuarantineResult> {
-        if self.auto_delete {
+        self.handle_malware_at(file_path, &self.plan_quarantine_path(file_path))
+    }
+
+    /// `handle_malware`, quarantining to a path from `plan_quarantine_path`
+    pub fn handle_malware_at(&self, file_path: &Path, quarantine_path: &Path) -> Result<QuarantineResult> {
+        if self.deletes_files() {
+            if self.prefers_delete() {
+                warn!("⚠️  Quarantine disk critically low, deleting {} instead of quarantining", file_path.display());
+            }
             self.delete_file(file_path)?;
             Ok(QuarantineResult::Deleted)
         } else {
-            let quarantine_path = self.quarantine_file(file_path)?;
+            let quarantine_path = self.quarantine_file_to(file_path, quarantine_path)?;
             Ok(QuarantineResult::Quarantined(quarantine_path))
         }
     }
 
     /// Check if a process has a file open by examining /proc/PID/fd
     fn process_has_file_open(pid: i32, file_path: &Path) -> bool {
-        let fd_dir = format!("/proc/{}/fd", pid);
-        if let Ok(entries) = std::fs::read_dir(&fd_dir) {
-            for entry in entries.flatten() {
-                if let Ok(target) = std::fs::read_link(entry.path()) {
-                    if target == file_path {
-                        return true;
-                    }
-                }
-            }
-        }
-        false
+        crate::process_monitor::read_fd_targets(pid)
+            .is_some_and(|targets| targets.iter().any(|target| target == file_path))
     }
 
     /// Kill any processes using the file (enhanced with file handle detection and process tree killing)
@@ -114,8 +365,9 @@ impl FileQuarantine {
         let file_path_str = file_path.to_string_lossy();
 
         for process in processes {
-            // Method 1: Check if process binary matches the file
-            if process.binary_path == file_path_str {
+            // Method 1: Check if process binary matches the file (possibly already unlinked)
+            if process.binary_path == file_path_str
+                || process.binary_path == format!("{} (deleted)", file_path_str) {
                 info!("🔍 Found process PID {} with binary matching malicious file: {}", 
                       process.pid, file_path_str);
                 pids_to_kill.insert(process.pid);
@@ -163,35 +415,29 @@ impl FileQuarantine {
             }
             
             // Wait for children to terminate
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+            tokio::time::sleep(self.kill_timeouts.sigterm).await;
             
             // Force kill any remaining children
-            monitor.refresh();
-            for tree_pid in monitor.get_full_process_tree(pid) {
+            for tree_pid in tree_pids {
                 if tree_pid == pid {
                     continue; // Handle parent separately
                 }
-                if monitor.get_process_by_pid(tree_pid).is_some() {
+                if is_alive(tree_pid) {
                     let pid_obj = Pid::from_raw(tree_pid);
                     let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
                     warn!("⚠️  Force killed child PID {}", tree_pid);
                 }
             }
             
-            // Now kill the parent
-            let pid_obj = Pid::from_raw(pid);
-            if signal::kill(pid_obj, signal::Signal::SIGTERM).is_ok() {
-                killed_pids.push(pid);
-                info!("✅ Sent SIGTERM to parent PID {}", pid);
-                
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                
-                // Force kill if still alive
-                monitor.refresh();
-                if monitor.get_process_by_pid(pid).is_some() {
-                    let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
-                    warn!("⚠️  Force killed parent PID {}", pid);
+            // Now kill the parent, verifying it actually exits. Always SIGTERM/SIGKILL:
+            // a stopped process would keep the file open.
+            match terminate(pid, KillSignals::default(), self.kill_timeouts).await {
+                Ok(TerminationOutcome::AlreadyGone) => {}
+                Ok(_) => {
+                    killed_pids.push(pid);
+                    info!("✅ Terminated parent PID {}", pid);
                 }
+                Err(e) => warn!("⚠️  Failed to kill parent PID {}: {}", pid, e),
             }
         }
 
@@ -203,34 +449,57 @@ impl FileQuarantine {
         &self.quarantine_dir
     }
 
-    /// Aggressively clean up malware origin - delete parent directory and related files
-    pub fn delete_malware_origin(&self, malware_path: &Path) -> Result<OriginCleanupResult> {
-        if !self.aggressive_cleanup {
-            return Ok(OriginCleanupResult {
-                deleted_files: Vec::new(),
-                deleted_directories: Vec::new(),
-                cleaned_cron_jobs: Vec::new(),
-            });
-        }
-
+    /// Aggressively clean up malware origin - delete parent directory and related files.
+    /// `malware_path` must be a confirmed signature match. Its directory is only deleted
+    /// when every other file in it has a suspicious name and it is neither protected nor
+    /// an application's directory (see `is_suspicious_directory`).
+    /// With `dry_run` nothing is touched; the result lists what would be deleted.
+    /// Files are quarantined (unless `deletes_files`), to their path in `planned` when
+    /// it has one, so the rollback manifest can restore them.
+    pub fn delete_malware_origin(
+        &self,
+        malware_path: &Path,
+        dry_run: bool,
+        planned: &HashMap<PathBuf, PathBuf>,
+    ) -> Result<OriginCleanupResult> {
         let mut cleanup_result = OriginCleanupResult {
             deleted_files: Vec::new(),
             deleted_directories: Vec::new(),
             cleaned_cron_jobs: Vec::new(),
+            dry_run,
         };
 
+        if !self.aggressive_cleanup {
+            return Ok(cleanup_result);
+        }
+
+        // Siblings in system or app directories are never collateral
+        if let Some(parent_dir) = malware_path.parent() {
+            if let Some(why) = self.protected_directory(parent_dir) {
+                info!("🛡️  Not cleaning {} beyond the malware file: {}", parent_dir.display(), why);
+                cleanup_result.cleaned_cron_jobs = self.clean_cron_jobs_referencing(malware_path, dry_run)?;
+                return Ok(cleanup_result);
+            }
+        }
+
         // Get parent directory
         if let Some(parent_dir) = malware_path.parent() {
             // Check if parent directory only contains suspicious files
-            if self.is_suspicious_directory(parent_dir)? {
-                info!("🗑️  Deleting suspicious parent directory: {}", parent_dir.display());
+            if self.is_suspicious_directory(parent_dir, malware_path)? {
+                if dry_run {
+                    info!("[DRY RUN] Would delete suspicious parent directory: {}", parent_dir.display());
+                } else {
+                    info!("🗑️  Deleting suspicious parent directory: {}", parent_dir.display());
+                }
                 
                 // Delete all files in the directory first
                 if let Ok(entries) = fs::read_dir(parent_dir) {
                     for entry in entries.flatten() {
                         let path = entry.path();
                         if path.is_file() {
-                            if let Err(e) = self.force_delete_file(&path) {
+                            if dry_run {
+                                cleanup_result.deleted_files.push(path.to_string_lossy().to_string());
+                            } else if let Err(e) = self.remove_origin_file(&path, planned) {
                                 warn!("Failed to delete file {}: {}", path.display(), e);
                             } else {
                                 cleanup_result.deleted_files.push(path.to_string_lossy().to_string());
@@ -240,7 +509,9 @@ impl FileQuarantine {
                 }
 
                 // Try to remove the directory
-                if let Err(e) = fs::remove_dir(parent_dir) {
+                if dry_run {
+                    cleanup_result.deleted_directories.push(parent_dir.to_string_lossy().to_string());
+                } else if let Err(e) = fs::remove_dir(parent_dir) {
                     warn!("Failed to remove directory {}: {}", parent_dir.display(), e);
                 } else {
                     cleanup_result.deleted_directories.push(parent_dir.to_string_lossy().to_string());
@@ -257,66 +528,88 @@ impl FileQuarantine {
             if let Ok(entries) = fs::read_dir(parent_dir) {
                 for entry in entries.flatten() {
                     let path = entry.path();
-                    if path.is_file() && self.is_suspicious_file(&path) {
-                        if path != malware_path {
+                    let path_str = path.to_string_lossy().to_string();
+                    if path.is_file() && self.is_suspicious_file(&path)
+                        && path != malware_path
+                        && !cleanup_result.deleted_files.contains(&path_str) {
+                            if dry_run {
+                                info!("[DRY RUN] Would delete related suspicious file: {}", path.display());
+                                cleanup_result.deleted_files.push(path_str);
+                                continue;
+                            }
                             info!("🗑️  Deleting related suspicious file: {}", path.display());
-                            if let Err(e) = self.force_delete_file(&path) {
+                            if let Err(e) = self.remove_origin_file(&path, planned) {
                                 warn!("Failed to delete related file {}: {}", path.display(), e);
                             } else {
-                                cleanup_result.deleted_files.push(path.to_string_lossy().to_string());
+                                cleanup_result.deleted_files.push(path_str);
                             }
                         }
-                    }
                 }
             }
         }
 
         // Clean up cron jobs that reference this malware
-        cleanup_result.cleaned_cron_jobs = self.clean_cron_jobs_referencing(malware_path)?;
+        cleanup_result.cleaned_cron_jobs = self.clean_cron_jobs_referencing(malware_path, dry_run)?;
 
         Ok(cleanup_result)
     }
 
-    fn is_suspicious_directory(&self, dir: &Path) -> Result<bool> {
-        // Check if directory contains only suspicious files
-        let mut suspicious_count = 0;
-        let mut total_files = 0;
-
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    total_files += 1;
-                    if self.is_suspicious_file(&path) {
-                        suspicious_count += 1;
-                    }
-                }
-            }
+    /// A drop directory: it holds the confirmed `malware_path`, and every other file in
+    /// it has a suspicious name. Callers check `protected_directory` first.
+    fn is_suspicious_directory(&self, dir: &Path, malware_path: &Path) -> Result<bool> {
+        if malware_path.parent() != Some(dir) || !malware_path.is_file() {
+            return Ok(false);
         }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(false);
+        };
+        Ok(entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path != malware_path)
+            .all(|path| self.is_suspicious_file(&path)))
+    }
 
-        // If all files are suspicious, directory is suspicious
-        Ok(total_files > 0 && suspicious_count == total_files)
+    /// Why `dir` must not be deleted from beyond the malware file, if it mustn't
+    fn protected_directory(&self, dir: &Path) -> Option<String> {
+        // /, /tmp, /home, /root and the like
+        if dir.components().count() <= 2 {
+            return Some("top-level directory".to_string());
+        }
+        if dir.parent() == Some(Path::new("/home")) {
+            return Some("home directory".to_string());
+        }
+        if let Some(protected) = self.protected_paths.iter().find(|p| dir.starts_with(p)) {
+            return Some(format!("under protected path {}", protected.display()));
+        }
+        APP_MARKERS.iter()
+            .find(|marker| dir.join(marker).exists())
+            .map(|marker| format!("application directory (has {})", marker))
     }
 
     fn is_suspicious_file(&self, path: &Path) -> bool {
-        // Check for common malware patterns
         let file_name = path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("")
             .to_lowercase();
+        self.suspicious_names.iter().any(|name| file_name.contains(name.as_str()))
+    }
 
-        let suspicious_names = [
-            "solrz", "e386", "payload.so", "next", "miner", "xmrig", 
-            "ccminer", "cpuminer", "malware", "trojan", "virus"
-        ];
-
-        suspicious_names.iter().any(|&name| file_name.contains(name))
+    /// Quarantine an origin file (to its planned path), or delete it when files are
+    /// deleted rather than kept
+    fn remove_origin_file(&self, path: &Path, planned: &HashMap<PathBuf, PathBuf>) -> Result<()> {
+        if self.deletes_files() {
+            return self.force_delete_file(path);
+        }
+        match planned.get(path) {
+            Some(quarantine_path) => self.quarantine_file_to(path, quarantine_path),
+            None => self.quarantine_file(path),
+        }.map(|_| ())
     }
 
     fn force_delete_file(&self, path: &Path) -> Result<()> {
         // Remove all permissions and delete
         let mut perms = fs::metadata(path)?.permissions();
-        perms.set_readonly(false);
+        perms.set_mode(perms.mode() | 0o200);
         fs::set_permissions(path, perms)?;
 
         // Try to delete
@@ -326,83 +619,135 @@ impl FileQuarantine {
         Ok(())
     }
 
-    fn clean_cron_jobs_referencing(&self, malware_path: &Path) -> Result<Vec<String>> {
+    fn clean_cron_jobs_referencing(&self, malware_path: &Path, dry_run: bool) -> Result<Vec<String>> {
         use crate::cron_watcher::CronWatcher;
         
         let mut cleaned = Vec::new();
         let malware_path_str = malware_path.to_string_lossy();
+        let malware_name = malware_path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_lowercase();
 
         // Check all cron locations
         let mut cron_watcher = CronWatcher::new();
         if let Ok(jobs) = cron_watcher.scan_all() {
-            let malware_name = malware_path.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-            
             for job in jobs {
-                let job_content_lower = job.content.to_lowercase();
-                let mut should_remove = false;
-                
-                // Direct path reference
-                if job.content.contains(&*malware_path_str) {
-                    should_remove = true;
+                let malicious_lines: Vec<&str> = job.content
+                    .lines()
+                    .filter(|line| cron_line_should_be_removed(
+                        line, &malware_path_str, &malware_name, self.aggressive_cron_cleanup,
+                    ))
+                    .collect();
+                if malicious_lines.is_empty() {
+                    continue;
                 }
-                
-                // Check for obfuscated references
-                if job_content_lower.contains(&malware_name) {
-                    should_remove = true;
+
+                if dry_run {
+                    info!("[DRY RUN] Would remove {} cron line(s) from {}", malicious_lines.len(), job.file_path);
+                    cleaned.push(job.file_path.clone());
+                    continue;
                 }
-                
-                // Check for suspicious patterns
-                let suspicious_patterns = ["wget", "curl", "base64", "eval", "bash.*<"];
-                for pattern in &suspicious_patterns {
-                    if job_content_lower.contains(pattern) {
-                        should_remove = true;
-                        break;
-                    }
+                if !Path::new(&job.file_path).is_file() {
+                    warn!("Suspicious cron entry for {} is not in a file ({}), remove it with crontab -e",
+                          job.user, job.file_path);
+                    continue;
                 }
-                
-                if should_remove {
-                    info!("🗑️  Removing suspicious cron job: {}", job.file_path);
-                    
-                    // Try to remove the cron entry
-                    if let Err(e) = self.remove_cron_entry(&job.file_path, &job.content) {
-                        warn!("Failed to remove cron entry: {}", e);
-                    } else {
-                        cleaned.push(job.file_path.clone());
-                    }
+
+                info!("🗑️  Removing {} suspicious cron line(s) from {}", malicious_lines.len(), job.file_path);
+                let key = crate::rollback::get_rollback_key().ok();
+                match remove_cron_lines(
+                    Path::new(&job.file_path),
+                    &job.user,
+                    &malicious_lines,
+                    Path::new(CRON_ROLLBACK_DIR),
+                    key.as_deref(),
+                ) {
+                    Ok(()) => cleaned.push(job.file_path.clone()),
+                    Err(e) => warn!("Failed to remove cron entry: {}", e),
                 }
             }
         }
 
         Ok(cleaned)
     }
+}
 
-    fn remove_cron_entry(&self, cron_file: &str, content: &str) -> Result<()> {
-        // Read current cron file
-        let current_content = fs::read_to_string(cron_file)
-            .with_context(|| format!("Failed to read cron file: {}", cron_file))?;
+/// Where cron backups and their rollback manifests are written
+const CRON_ROLLBACK_DIR: &str = "/var/lib/hora-police/rollbacks";
 
-        // Remove lines containing the malware path
-        let lines: Vec<&str> = current_content
-            .lines()
-            .filter(|line| !line.contains(content) && !line.trim().is_empty())
-            .collect();
+/// Whether a cron line should be removed while cleaning up `malware_path`.
+/// Only direct path/name references count unless `aggressive` enables the broad
+/// download/decode patterns, which also match legitimate backup and deploy jobs.
+pub fn cron_line_should_be_removed(line: &str, malware_path: &str, malware_name: &str, aggressive: bool) -> bool {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return false;
+    }
+    let lower = trimmed.to_lowercase();
 
-        // Write back without the malicious entries
-        let new_content = lines.join("\n");
-        if !new_content.is_empty() {
-            fs::write(cron_file, new_content)
-                .with_context(|| format!("Failed to write cron file: {}", cron_file))?;
-        } else {
-            // If file is empty, remove it
-            fs::remove_file(cron_file)
-                .with_context(|| format!("Failed to remove empty cron file: {}", cron_file))?;
-        }
+    if !malware_path.is_empty() && trimmed.contains(malware_path) {
+        return true;
+    }
+    // Very short names ("x", "sh") would match unrelated jobs
+    if malware_name.len() >= 3 && lower.contains(malware_name) {
+        return true;
+    }
 
-        Ok(())
+    if aggressive {
+        let broad = regex::Regex::new(r"\b(wget|curl|base64|eval)\b|bash\s*<").unwrap();
+        return broad.is_match(&lower);
     }
+    false
+}
+
+/// Back up a cron file and write a rollback manifest, then drop `lines` from it
+fn remove_cron_lines(
+    cron_file: &Path,
+    user: &str,
+    lines: &[&str],
+    rollback_dir: &Path,
+    signing_key: Option<&[u8]>,
+) -> Result<()> {
+    use crate::rollback::{RollbackAction, RollbackManifest};
+
+    let current_content = fs::read_to_string(cron_file)
+        .with_context(|| format!("Failed to read cron file: {}", cron_file.display()))?;
+
+    // Backed up into the state directory: cron runs any file left in /etc/cron.d,
+    // so a backup next to the original would keep the malicious line scheduled
+    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+    let file_name = cron_file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
+    fs::create_dir_all(rollback_dir)?;
+    let backup_path = rollback_dir.join(format!("cron_{}_{}.backup", file_name, timestamp));
+    fs::copy(cron_file, &backup_path)
+        .with_context(|| format!("Failed to back up cron file: {}", cron_file.display()))?;
+
+    let mut manifest = RollbackManifest::new();
+    manifest.add_action(RollbackAction::RestoreCron {
+        user: user.to_string(),
+        content: current_content.clone(),
+        file: cron_file.to_string_lossy().to_string(),
+    });
+    if let Some(key) = signing_key {
+        manifest.sign(key)?;
+    }
+    manifest.save(&rollback_dir.join(format!("cron_{}_{}.rollback", file_name, timestamp)))?;
+
+    let remaining: Vec<&str> = current_content
+        .lines()
+        .filter(|line| !lines.contains(line))
+        .collect();
+
+    if remaining.iter().all(|line| line.trim().is_empty()) {
+        fs::remove_file(cron_file)
+            .with_context(|| format!("Failed to remove empty cron file: {}", cron_file.display()))?;
+    } else {
+        fs::write(cron_file, remaining.join("\n") + "\n")
+            .with_context(|| format!("Failed to write cron file: {}", cron_file.display()))?;
+    }
+
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -410,6 +755,7 @@ pub struct OriginCleanupResult {
     pub deleted_files: Vec<String>,
     pub deleted_directories: Vec<String>,
     pub cleaned_cron_jobs: Vec<String>,
+    pub dry_run: bool,  // Lists are what would have been removed; nothing was touched
 }
 
 impl OriginCleanupResult {
@@ -426,3 +772,318 @@ pub enum QuarantineResult {
     Deleted,
 }
 
+/// Other names of a file with more than one hard link
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hardlinks {
+    pub link_count: u64,  // st_nlink, including the scanned path
+    pub others: Vec<PathBuf>,  // Those found under the scan roots
+}
+
+/// Other paths under `roots` linked to the same inode as `file_path`, or None if it has
+/// a single link. Removing one name of a hard-linked payload leaves it reachable through
+/// the rest, so every name has to be handled.
+pub fn find_hardlinks(file_path: &Path, roots: &[PathBuf], max_depth: usize) -> Option<Hardlinks> {
+    let metadata = fs::symlink_metadata(file_path).ok().filter(|m| m.is_file())?;
+    if metadata.nlink() <= 1 {
+        return None;
+    }
+
+    let mut others = Vec::new();
+    'roots: for root in roots {
+        let entries = walkdir::WalkDir::new(root).max_depth(max_depth).follow_links(false);
+        for entry in entries.into_iter().flatten() {
+            if !entry.file_type().is_file() || entry.path() == file_path {
+                continue;
+            }
+            let Ok(candidate) = entry.metadata() else {
+                continue;
+            };
+            if (candidate.dev(), candidate.ino()) == (metadata.dev(), metadata.ino())
+                && !others.iter().any(|p: &PathBuf| p == entry.path())
+            {
+                others.push(entry.into_path());
+                if others.len() as u64 + 1 >= metadata.nlink() {
+                    break 'roots;
+                }
+            }
+        }
+    }
+    Some(Hardlinks { link_count: metadata.nlink(), others })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn quarantine_and_restore_preserves_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("app/payload.sh");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, "#!/bin/sh\necho hi\n").unwrap();
+        fs::set_permissions(&original, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        let quarantined = quarantine.quarantine_file(&original).unwrap();
+        assert!(!original.exists());
+        assert!(QuarantineMetadata::sidecar_path(&quarantined).exists());
+
+        // Refuse to clobber a file that reappeared at the original path
+        fs::write(&original, "new").unwrap();
+        assert!(FileQuarantine::restore_file(&quarantined, &original).is_err());
+        fs::remove_file(&original).unwrap();
+
+        FileQuarantine::restore_file(&quarantined, &original).unwrap();
+        assert!(!quarantined.exists());
+        assert_eq!(fs::metadata(&original).unwrap().mode() & 0o7777, 0o750);
+        assert_eq!(fs::read_to_string(&original).unwrap(), "#!/bin/sh\necho hi\n");
+    }
+
+    #[tokio::test]
+    async fn signature_match_kills_the_tree_running_an_unlinked_file() {
+        use std::os::unix::process::CommandExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let payload = dir.path().join("kdevtmpfsi");
+        fs::copy("/bin/sh", &payload).unwrap();
+        // argv[0] hides the path, so only the executable itself links the process to the file
+        let mut miner = std::process::Command::new(&payload)
+            .arg0("kdevtmpfsi")
+            .args(["-c", "sleep 30 & wait"])
+            .spawn()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Droppers unlink themselves once running
+        fs::remove_file(&payload).unwrap();
+
+        let mut quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        quarantine.set_kill_timeouts(KillTimeouts { sigterm: Duration::from_millis(200), sigkill: Duration::from_millis(200) });
+        let killed = quarantine.kill_processes_using_file(&payload).await.unwrap();
+
+        let pid = miner.id() as i32;
+        assert!(killed.contains(&pid), "killed {:?}, expected {}", killed, pid);
+        assert!(killed.len() >= 2, "the sleep child should be killed too: {:?}", killed);
+        assert!(miner.try_wait().unwrap().is_some(), "the payload should no longer be running");
+    }
+
+    #[test]
+    fn every_hard_link_to_malware_is_found_and_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = [dir.path().join("tmp"), dir.path().join("var")];
+        let payload = roots[0].join(".x/kdevtmpfsi");
+        let link = roots[1].join("lib/.cache/kthreadd");
+        let outside = dir.path().join("elsewhere");
+        for path in [&payload, &link] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+        }
+        fs::write(&payload, "payload").unwrap();
+        fs::hard_link(&payload, &link).unwrap();
+        fs::hard_link(&payload, &outside).unwrap();
+
+        let found = find_hardlinks(&payload, &roots, 10).unwrap();
+        assert_eq!(found, Hardlinks { link_count: 3, others: vec![link.clone()] });
+        // Overlapping roots don't list a link twice
+        let overlapping = [roots[1].clone(), roots[1].join("lib")];
+        assert_eq!(find_hardlinks(&payload, &overlapping, 10).unwrap().others, vec![link.clone()]);
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        for path in std::iter::once(&payload).chain(&found.others) {
+            assert!(matches!(quarantine.handle_malware(path).unwrap(), QuarantineResult::Quarantined(_)));
+        }
+        assert!(!payload.exists() && !link.exists());
+        assert_eq!(quarantine.quarantined_copies(&payload).len(), 1);
+        assert_eq!(quarantine.quarantined_copies(&link).len(), 1);
+
+        // A single link needs nothing extra
+        fs::write(dir.path().join("single"), "x").unwrap();
+        assert!(find_hardlinks(&dir.path().join("single"), &roots, 10).is_none());
+    }
+
+    #[test]
+    fn same_named_files_get_distinct_quarantine_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a/xmrig");
+        let second = dir.path().join("b/xmrig");
+        for (path, content) in [(&first, "one"), (&second, "two")] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        let q1 = quarantine.quarantine_file(&first).unwrap();
+        let q2 = quarantine.quarantine_file(&second).unwrap();
+        assert_ne!(q1, q2);
+        assert_eq!(fs::read_to_string(&q1).unwrap(), "one");
+        assert_eq!(fs::read_to_string(&q2).unwrap(), "two");
+
+        // The same path quarantined again within the second still gets its own name
+        fs::write(&first, "again").unwrap();
+        let q3 = quarantine.quarantine_file(&first).unwrap();
+        let mut copies = vec![q1.clone(), q3];
+        copies.sort();
+        assert_eq!(quarantine.quarantined_copies(&first), copies);
+        assert_eq!(quarantine.quarantined_copies(&second), vec![q2]);
+
+        let evidence: QuarantineMetadata =
+            serde_json::from_str(&fs::read_to_string(QuarantineMetadata::sidecar_path(&q1)).unwrap()).unwrap();
+        assert_eq!(evidence.original_path, first);
+        assert_eq!(evidence.path_tag, path_tag(&first));
+    }
+
+    #[test]
+    fn origin_cleanup_dry_run_touches_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let drop_dir = dir.path().join(".cache-x");
+        fs::create_dir_all(&drop_dir).unwrap();
+        let malware = drop_dir.join("xmrig");
+        fs::write(&malware, "bin").unwrap();
+        fs::write(drop_dir.join("miner.json"), "{}").unwrap();
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        let result = quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap();
+
+        assert!(result.dry_run);
+        assert_eq!(result.deleted_files.len(), 2);
+        assert_eq!(result.deleted_directories, vec![drop_dir.to_string_lossy().to_string()]);
+        assert!(malware.exists());
+        assert!(drop_dir.join("miner.json").exists());
+    }
+
+    #[test]
+    fn origin_cleanup_quarantines_to_the_planned_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let drop_dir = dir.path().join(".cache-x");
+        fs::create_dir_all(&drop_dir).unwrap();
+        let malware = drop_dir.join("xmrig");
+        let config = drop_dir.join("miner.json");
+        fs::write(&malware, "bin").unwrap();
+        fs::write(&config, "{}").unwrap();
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        let planned: HashMap<PathBuf, PathBuf> = [&malware, &config].into_iter()
+            .map(|path| (path.clone(), quarantine.plan_quarantine_path(path)))
+            .collect();
+        let result = quarantine.delete_malware_origin(&malware, false, &planned).unwrap();
+
+        assert_eq!(result.deleted_directories.len(), 1);
+        assert!(!drop_dir.exists());
+        assert_eq!(fs::read_to_string(&planned[&malware]).unwrap(), "bin");
+        assert_eq!(fs::read_to_string(&planned[&config]).unwrap(), "{}");
+    }
+
+    #[test]
+    fn nextjs_directory_is_never_cleaned_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("next-app");
+        fs::create_dir_all(app.join(".next")).unwrap();
+        fs::write(app.join("package.json"), "{}").unwrap();
+        fs::write(app.join("next.config.js"), "module.exports = {}").unwrap();
+        fs::write(app.join("next-miner.log"), "").unwrap();
+        let malware = app.join("xmrig");
+        fs::write(&malware, "bin").unwrap();
+
+        let quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        let result = quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap();
+        assert!(result.deleted_directories.is_empty());
+        assert!(result.deleted_files.is_empty());
+
+        // Even without app markers, "next" alone no longer marks a file as suspicious
+        let bare = dir.path().join("bare");
+        fs::create_dir_all(&bare).unwrap();
+        fs::write(bare.join("next"), "").unwrap();
+        fs::write(bare.join("next-server.js"), "").unwrap();
+        let malware = bare.join("xmrig");
+        fs::write(&malware, "bin").unwrap();
+        let result = quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap();
+        assert!(result.deleted_directories.is_empty());
+        assert!(result.deleted_files.is_empty());
+    }
+
+    #[test]
+    fn protected_and_configured_paths_limit_origin_cleanup() {
+        let dir = tempfile::tempdir().unwrap();
+        let drop_dir = dir.path().join("srv/.cache-x");
+        fs::create_dir_all(&drop_dir).unwrap();
+        let malware = drop_dir.join("xmrig");
+        fs::write(&malware, "bin").unwrap();
+        fs::write(drop_dir.join("config.json"), "{}").unwrap();
+
+        // config.json isn't suspicious by default, but is with a custom list
+        let mut quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        assert!(quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap().deleted_directories.is_empty());
+        quarantine.set_suspicious_names(&["CONFIG".to_string()]);
+        assert_eq!(quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap().deleted_directories.len(), 1);
+
+        quarantine.set_protected_paths(&[dir.path().join("srv").to_string_lossy().to_string()]);
+        let result = quarantine.delete_malware_origin(&malware, true, &HashMap::new()).unwrap();
+        assert!(result.deleted_directories.is_empty());
+        assert!(result.deleted_files.is_empty());
+    }
+
+    #[test]
+    fn encrypted_quarantine_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("bin/kworkerd");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, b"\x7fELF fake miner").unwrap();
+        fs::set_permissions(&original, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let key = QuarantineKey::derive(b"test key").unwrap();
+        let mut quarantine = FileQuarantine::new(dir.path().join("quarantine"), false);
+        quarantine.set_encryption_key(key.clone());
+
+        let sealed = quarantine.quarantine_file(&original).unwrap();
+        assert!(!original.exists());
+        assert_eq!(sealed.extension().and_then(|e| e.to_str()), Some(SEALED_EXTENSION));
+        assert_eq!(fs::metadata(&sealed).unwrap().mode() & 0o777, 0o600);
+        assert!(quarantine_crypto::is_sealed(&fs::read(&sealed).unwrap()));
+
+        assert!(FileQuarantine::restore_file_with_key(&sealed, &original, None).is_err());
+        FileQuarantine::restore_file_with_key(&sealed, &original, Some(&key)).unwrap();
+        assert_eq!(fs::read(&original).unwrap(), b"\x7fELF fake miner");
+        assert_eq!(fs::metadata(&original).unwrap().mode() & 0o7777, 0o755);
+        assert!(!sealed.exists());
+    }
+
+    #[test]
+    fn legitimate_curl_backup_cron_is_preserved_by_default() {
+        let backup = "0 3 * * * curl -fsS -X POST https://backup.example.com/hook && /usr/local/bin/backup.sh";
+        let dropper = "*/5 * * * * /tmp/.x/kdevtmpfsi >/dev/null 2>&1";
+        let path = "/tmp/.x/kdevtmpfsi";
+
+        assert!(!cron_line_should_be_removed(backup, path, "kdevtmpfsi", false));
+        assert!(cron_line_should_be_removed(dropper, path, "kdevtmpfsi", false));
+        assert!(!cron_line_should_be_removed("# curl example", path, "kdevtmpfsi", true));
+        // Broad patterns only when explicitly enabled
+        assert!(cron_line_should_be_removed(backup, path, "kdevtmpfsi", true));
+    }
+
+    #[test]
+    fn cron_removal_backs_up_and_writes_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cron = dir.path().join("deploy");
+        let backup_line = "0 3 * * * curl -fsS https://backup.example.com/hook";
+        let dropper = "*/5 * * * * /tmp/.x/kdevtmpfsi";
+        fs::write(&cron, format!("{}\n{}\n", backup_line, dropper)).unwrap();
+
+        let rollback_dir = dir.path().join("rollbacks");
+        remove_cron_lines(&cron, "deploy", &[dropper], &rollback_dir, None).unwrap();
+
+        assert_eq!(fs::read_to_string(&cron).unwrap(), format!("{}\n", backup_line));
+        // Nothing but the cleaned file is left in the cron directory
+        let cron_dir: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| n != "rollbacks")
+            .collect();
+        assert_eq!(cron_dir, vec!["deploy"]);
+        let backups: Vec<std::path::PathBuf> = fs::read_dir(&rollback_dir).unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "backup"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert!(fs::read_to_string(&backups[0]).unwrap().contains(dropper));
+    }
+}
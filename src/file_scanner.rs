@@ -5,10 +5,21 @@ use std::fs;
 use std::io::Read;
 use walkdir::WalkDir;
 use regex::Regex;
-use tracing::{info, warn, error};
+use tracing::{info, warn};
 use std::sync::Arc;
 use crate::database::IntelligenceDB;
-use crate::config::FileScanningConfig;
+use crate::config::{FileScanningConfig, SymlinkPolicy};
+use crate::signatures::load_signatures;
+use crate::network_fs::MountTable;
+use crate::scan_priority::{ScanIoClass, ScanPriority};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Per-file I/O limit on network filesystems, however high file_io_timeout_ms is
+const NETWORK_FILE_IO_TIMEOUT_MS: u64 = 1000;
 
 #[derive(Debug, Clone)]
 pub struct MalwareSignature {
@@ -27,6 +38,42 @@ pub struct DetectedMalware {
     pub file_hash: String,
     pub file_size: u64,
     pub detected_at: chrono::DateTime<chrono::Utc>,
+    pub timestomp_gap_seconds: Option<u64>,  // How far ctime is past a backdated mtime
+}
+
+impl DetectedMalware {
+    /// Alert line describing a backdated mtime, empty when there is none
+    pub fn timestomp_note(&self) -> String {
+        match self.timestomp_gap_seconds {
+            Some(gap) => format!("\nBackdated: inode changed {}h after its claimed modification time", gap / 3600),
+            None => String::new(),
+        }
+    }
+}
+
+/// How a backdated mtime weighs on a signature match
+#[derive(Debug, Clone, Copy)]
+struct TimestompPolicy {
+    min_gap_seconds: i64,
+    boost: f32,
+    kill_threshold: f32,
+}
+
+impl TimestompPolicy {
+    fn from_config(config: &FileScanningConfig) -> Self {
+        Self {
+            min_gap_seconds: config.timestomp_min_gap_hours.saturating_mul(3600).min(i64::MAX as u64) as i64,
+            boost: config.timestomp_boost,
+            kill_threshold: config.signature_kill_threshold,
+        }
+    }
+
+    /// `threat_level` plus the boost, kept below the kill threshold: a backdated mtime
+    /// (also left by unpacked archives) never decides a kill on its own
+    fn boosted(&self, threat_level: f32) -> f32 {
+        let ceiling = (self.kill_threshold - 0.01).max(threat_level);
+        (threat_level + self.boost).min(ceiling).clamp(0.0, 1.0)
+    }
 }
 
 pub struct FileScanner {
@@ -35,6 +82,9 @@ pub struct FileScanner {
     quarantine_path: PathBuf,
     db: Option<Arc<IntelligenceDB>>,
     config: FileScanningConfig,
+    mounts: RwLock<MountTable>,
+    priority: ScanPriority,
+    timestomp: TimestompPolicy,
 }
 
 impl FileScanner {
@@ -51,6 +101,25 @@ impl FileScanner {
             incremental_scan: true,
             parallel_scan: true,
             max_scan_threads: 4,
+            kill_on_signature_match: true,
+            signature_kill_threshold: 0.9,
+            encrypt_quarantine: false,
+            aggressive_cron_cleanup: false,
+            suspicious_names: crate::file_quarantine::DEFAULT_SUSPICIOUS_NAMES.iter().map(|n| n.to_string()).collect(),
+            protected_paths: crate::file_quarantine::DEFAULT_PROTECTED_PATHS.iter().map(|p| p.to_string()).collect(),
+            extract_miner_iocs: true,
+            pool_connection_boost: 0.5,
+            home_scan_mode: crate::config::HomeScanMode::ReportOnly,
+            max_scan_depth: 20,
+            symlink_policy: SymlinkPolicy::Skip,
+            signature_files: Vec::new(),
+            skip_network_filesystems: true,
+            file_io_timeout_ms: 5000,
+            scan_nice: 10,
+            scan_ioclass: ScanIoClass::BestEffort,
+            timestomp_min_gap_hours: 24,
+            timestomp_boost: 0.1,
+            min_free_mb_for_scan: 128,
         })
     }
 
@@ -60,75 +129,34 @@ impl FileScanner {
         db: Option<Arc<IntelligenceDB>>,
         config: FileScanningConfig,
     ) -> Self {
+        // Config::load has validated the range; clamp for configs built in code
+        let priority = ScanPriority { nice: config.scan_nice.clamp(-20, 19), ioclass: config.scan_ioclass };
+        let timestomp = TimestompPolicy::from_config(&config);
         let mut scanner = Self {
             signatures: Vec::new(),
             scan_paths,
             quarantine_path,
             db,
             config,
+            mounts: RwLock::new(MountTable::load()),
+            priority,
+            timestomp,
         };
         
-        // Load built-in malware signatures
-        scanner.load_builtin_signatures();
+        // Load built-in and external malware signatures
+        scanner.load_signatures();
         
         scanner
     }
 
-    fn load_builtin_signatures(&mut self) {
-        // Load the specific malware signatures from the user's list
-        let builtin_signatures = vec![
-            MalwareSignature {
-                name: "solrz".to_string(),
-                file_name_pattern: Some(Regex::new(r"^solrz$").unwrap()),
-                path_pattern: Some(Regex::new(r".*[/\\]solrz$").unwrap()),
-                file_hash: None,
-                threat_level: 1.0,
-                description: "Malicious file: solrz".to_string(),
-            },
-            MalwareSignature {
-                name: "e386".to_string(),
-                file_name_pattern: Some(Regex::new(r"^e386$").unwrap()),
-                path_pattern: Some(Regex::new(r".*[/\\]e386$").unwrap()),
-                file_hash: None,
-                threat_level: 1.0,
-                description: "Malicious file: e386".to_string(),
-            },
-            MalwareSignature {
-                name: "payload.so".to_string(),
-                file_name_pattern: Some(Regex::new(r"^payload\.so$").unwrap()),
-                path_pattern: Some(Regex::new(r".*[/\\]payload\.so$").unwrap()),
-                file_hash: None,
-                threat_level: 1.0,
-                description: "Malicious shared library: payload.so".to_string(),
-            },
-            MalwareSignature {
-                name: "next".to_string(),
-                file_name_pattern: Some(Regex::new(r"^next$").unwrap()),
-                path_pattern: Some(Regex::new(r".*[/\\]\.local[/\\]share[/\\]next$").unwrap()),
-                file_hash: None,
-                threat_level: 1.0,
-                description: "Malicious file: next".to_string(),
-            },
-            // Additional common malware patterns
-            MalwareSignature {
-                name: "crypto_miner_pattern".to_string(),
-                file_name_pattern: Some(Regex::new(r"(?i)(miner|mining|xmrig|ccminer|cpuminer)").unwrap()),
-                path_pattern: None,
-                file_hash: None,
-                threat_level: 0.9,
-                description: "Potential crypto miner binary".to_string(),
-            },
-            MalwareSignature {
-                name: "suspicious_so_pattern".to_string(),
-                file_name_pattern: Some(Regex::new(r"\.so$").unwrap()),
-                path_pattern: Some(Regex::new(r"(?i)(tmp|/tmp|/var/tmp|/dev/shm|payload|malicious|evil)").unwrap()),
-                file_hash: None,
-                threat_level: 0.8,
-                description: "Suspicious shared library location".to_string(),
-            },
-        ];
+    fn load_signatures(&mut self) {
+        // Invalid signatures are skipped, never fatal: `hora-police validate-signatures` reports them
+        let (signatures, errors) = load_signatures(&self.config.signature_files);
+        for error in &errors {
+            warn!("⚠️  Skipping invalid malware signature: {}", error);
+        }
 
-        self.signatures.extend(builtin_signatures);
+        self.signatures.extend(signatures);
         info!("Loaded {} malware signatures", self.signatures.len());
     }
 
@@ -136,14 +164,26 @@ impl FileScanner {
         self.signatures.push(signature);
     }
 
+    /// How long a stat or read of `path` may take, or None if its filesystem is skipped
+    fn io_timeout(&self, path: &Path) -> Option<Duration> {
+        let timeout = Duration::from_millis(self.config.file_io_timeout_ms);
+        let is_network = self.mounts.read().map(|m| m.is_network(path)).unwrap_or(false);
+        match (is_network, self.config.skip_network_filesystems) {
+            (false, _) => Some(timeout),
+            (true, true) => None,
+            (true, false) => Some(timeout.min(Duration::from_millis(NETWORK_FILE_IO_TIMEOUT_MS))),
+        }
+    }
+
     pub async fn scan_file(&self, file_path: &Path) -> Result<Option<DetectedMalware>> {
-        // Check if file exists and is readable
-        if !file_path.exists() || !file_path.is_file() {
+        let Some(io_timeout) = self.io_timeout(file_path) else {
             return Ok(None);
-        }
+        };
 
-        // Get file metadata
-        let metadata = fs::metadata(file_path)?;
+        // Check if file exists and is readable
+        let Some(metadata) = Self::metadata_with_timeout(file_path, io_timeout, self.priority).await? else {
+            return Ok(None);
+        };
         let file_size = metadata.len();
         let file_name = file_path.file_name()
             .and_then(|n| n.to_str())
@@ -152,6 +192,7 @@ impl FileScanner {
 
         // Get modification time for caching
         let mtime = metadata.modified()
+            .ok()
             .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
             .map(|d| d.as_secs() as i64)
             .unwrap_or(0);
@@ -159,12 +200,12 @@ impl FileScanner {
         // Check cache if enabled
         let file_hash = if self.config.use_hash_cache {
             if let Some(ref db) = self.db {
-                if let Ok(Some((cached_hash, cached_mtime))) = db.get_file_cache(&file_path_str, mtime).await {
+                if let Ok(Some((cached_hash, _cached_mtime))) = db.get_file_cache(&file_path_str, mtime).await {
                     // File hasn't changed, use cached hash
                     cached_hash
                 } else {
                     // File changed or not in cache, calculate hash
-                    let hash = self.calculate_hash(file_path)?;
+                    let hash = Self::hash_with_timeout(file_path, io_timeout, self.priority).await?;
                     // Update cache
                     if let Err(e) = db.update_file_cache(&file_path_str, &hash, file_size as i64, mtime).await {
                         warn!("Failed to update file cache for {}: {}", file_path_str, e);
@@ -173,13 +214,20 @@ impl FileScanner {
                 }
             } else {
                 // No database, calculate hash
-                self.calculate_hash(file_path)?
+                Self::hash_with_timeout(file_path, io_timeout, self.priority).await?
             }
         } else {
             // Caching disabled, calculate hash
-            self.calculate_hash(file_path)?
+            Self::hash_with_timeout(file_path, io_timeout, self.priority).await?
         };
 
+        // Operator restored this exact file from quarantine
+        if let Some(ref db) = self.db {
+            if db.is_file_whitelisted(&file_path_str, &file_hash).await.unwrap_or(false) {
+                return Ok(None);
+            }
+        }
+
         // Check against all signatures
         for signature in &self.signatures {
             let mut matches = false;
@@ -209,13 +257,7 @@ impl FileScanner {
                 info!("🚨 Malware detected: {} (signature: {})", 
                       file_path.display(), signature.name);
 
-                return Ok(Some(DetectedMalware {
-                    file_path: file_path.to_path_buf(),
-                    signature: signature.clone(),
-                    file_hash,
-                    file_size,
-                    detected_at: chrono::Utc::now(),
-                }));
+                return Ok(Some(Self::detection(file_path, signature, file_hash, &metadata, self.timestomp)));
             }
         }
 
@@ -231,45 +273,40 @@ impl FileScanner {
 
         info!("Scanning directory: {}", dir_path.display());
 
-        // Collect all files first
-        let mut files_to_scan = Vec::new();
-        for entry in WalkDir::new(dir_path)
-            .follow_links(false)
-            .max_depth(20) // Limit depth to prevent excessive scanning
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path().to_path_buf();
-
-            // Skip if it's a directory
-            if path.is_dir() {
-                continue;
-            }
-
-            // Skip if it's a symlink (to avoid following malicious symlinks)
-            if entry.file_type().is_symlink() {
-                continue;
-            }
-
-            // Skip system directories early for performance
-            let path_str = path.to_string_lossy();
-            if path_str.contains("/proc/") || path_str.contains("/sys/") || path_str.contains("/dev/") {
-                continue;
-            }
-
-            files_to_scan.push(path);
+        if self.io_timeout(dir_path).is_none() {
+            info!("Skipping {}: on a network filesystem", dir_path.display());
+            return Ok(detected);
         }
 
+        // Collect all files first, leaving out network mounts below dir_path
+        let skip_network = self.config.skip_network_filesystems;
+        let files_to_scan = {
+            let mounts = self.mounts.read().map(|m| m.clone()).unwrap_or_default();
+            collect_files(dir_path, self.config.max_scan_depth, self.config.symlink_policy, |dir| {
+                let skip = skip_network && mounts.is_network(dir);
+                if skip {
+                    info!("Skipping {}: on a network filesystem", dir.display());
+                }
+                skip
+            })
+        };
+
         // Parallel or sequential scanning
         if self.config.parallel_scan && files_to_scan.len() > 10 {
             // Use parallel scanning for large directories
             use tokio::task;
             let mut handles = Vec::new();
-            let chunk_size = (files_to_scan.len() / self.config.max_scan_threads).max(1);
+            // At most max_scan_threads chunks, each scanned one file at a time
+            let chunk_size = files_to_scan.len().div_ceil(self.config.max_scan_threads.max(1));
             let signatures = self.signatures.clone();
             let use_cache = self.config.use_hash_cache;
             let db_opt = self.db.clone();
+            let priority = self.priority;
+            let timestomp = self.timestomp;
             
+            let files_to_scan: Vec<(PathBuf, Duration)> = files_to_scan.into_iter()
+                .filter_map(|path| self.io_timeout(&path).map(|timeout| (path, timeout)))
+                .collect();
             for chunk in files_to_scan.chunks(chunk_size) {
                 let chunk = chunk.to_vec();
                 let signatures_clone = signatures.clone();
@@ -277,8 +314,8 @@ impl FileScanner {
                 
                 let handle = task::spawn(async move {
                     let mut chunk_detected = Vec::new();
-                    for path in chunk {
-                        if let Ok(Some(malware)) = Self::scan_file_internal(&path, &signatures_clone, use_cache, db_clone.as_ref()).await {
+                    for (path, io_timeout) in chunk {
+                        if let Ok(Some(malware)) = Self::scan_file_internal(&path, io_timeout, priority, timestomp, &signatures_clone, use_cache, db_clone.as_ref()).await {
                             chunk_detected.push(malware);
                         }
                     }
@@ -316,15 +353,16 @@ impl FileScanner {
     // Internal helper for parallel scanning
     async fn scan_file_internal(
         path: &Path,
+        io_timeout: Duration,
+        priority: ScanPriority,
+        timestomp: TimestompPolicy,
         signatures: &[MalwareSignature],
         use_cache: bool,
         db: Option<&Arc<IntelligenceDB>>,
     ) -> Result<Option<DetectedMalware>> {
-        if !path.exists() || !path.is_file() {
+        let Some(metadata) = Self::metadata_with_timeout(path, io_timeout, priority).await? else {
             return Ok(None);
-        }
-
-        let metadata = fs::metadata(path)?;
+        };
         let file_size = metadata.len();
         let file_name = path.file_name()
             .and_then(|n| n.to_str())
@@ -333,6 +371,7 @@ impl FileScanner {
 
         // Get modification time for caching
         let mtime = metadata.modified()
+            .ok()
             .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
             .map(|d| d.as_secs() as i64)
             .unwrap_or(0);
@@ -343,19 +382,25 @@ impl FileScanner {
                 if let Ok(Some((cached_hash, _))) = db.get_file_cache(&file_path_str, mtime).await {
                     cached_hash
                 } else {
-                    let hash = Self::calculate_hash_static(path)?;
+                    let hash = Self::hash_with_timeout(path, io_timeout, priority).await?;
                     if let Err(e) = db.update_file_cache(&file_path_str, &hash, file_size as i64, mtime).await {
                         warn!("Failed to update file cache for {}: {}", file_path_str, e);
                     }
                     hash
                 }
             } else {
-                Self::calculate_hash_static(path)?
+                Self::hash_with_timeout(path, io_timeout, priority).await?
             }
         } else {
-            Self::calculate_hash_static(path)?
+            Self::hash_with_timeout(path, io_timeout, priority).await?
         };
 
+        if let Some(db) = db {
+            if db.is_file_whitelisted(&file_path_str, &file_hash).await.unwrap_or(false) {
+                return Ok(None);
+            }
+        }
+
         // Check against signatures
         for signature in signatures {
             let mut matches = false;
@@ -379,19 +424,47 @@ impl FileScanner {
             }
 
             if matches {
-                return Ok(Some(DetectedMalware {
-                    file_path: path.to_path_buf(),
-                    signature: signature.clone(),
-                    file_hash,
-                    file_size,
-                    detected_at: chrono::Utc::now(),
-                }));
+                return Ok(Some(Self::detection(path, signature, file_hash, &metadata, timestomp)));
             }
         }
 
         Ok(None)
     }
 
+    /// A signature match, its threat level raised if the file's mtime was backdated
+    fn detection(
+        path: &Path,
+        signature: &MalwareSignature,
+        file_hash: String,
+        metadata: &fs::Metadata,
+        timestomp: TimestompPolicy,
+    ) -> DetectedMalware {
+        let mut signature = signature.clone();
+        let timestomp_gap_seconds = timestomp_gap(metadata.mtime(), metadata.ctime(), timestomp.min_gap_seconds);
+        if let Some(gap) = timestomp_gap_seconds {
+            warn!("🕰️  {} has a backdated mtime: inode changed {}h after its claimed modification",
+                  path.display(), gap / 3600);
+            signature.threat_level = timestomp.boosted(signature.threat_level);
+        }
+        DetectedMalware {
+            file_path: path.to_path_buf(),
+            signature,
+            file_hash,
+            file_size: metadata.len(),
+            detected_at: chrono::Utc::now(),
+            timestomp_gap_seconds,
+        }
+    }
+
+    /// Metadata of a regular file (following symlinks); None if it is gone or not a file
+    async fn metadata_with_timeout(path: &Path, timeout: Duration, priority: ScanPriority) -> Result<Option<fs::Metadata>> {
+        with_io_timeout(path, timeout, priority, |path| Ok(fs::metadata(path).ok().filter(|m| m.is_file()))).await
+    }
+
+    async fn hash_with_timeout(path: &Path, timeout: Duration, priority: ScanPriority) -> Result<String> {
+        with_io_timeout(path, timeout, priority, |path| Self::calculate_hash_static(&path)).await
+    }
+
     fn calculate_hash_static(file_path: &Path) -> Result<String> {
         let mut file = fs::File::open(file_path)?;
         let mut buffer = Vec::new();
@@ -405,6 +478,11 @@ impl FileScanner {
     pub async fn scan_all_paths(&self) -> Result<Vec<DetectedMalware>> {
         let mut all_detected = Vec::new();
 
+        // Pick up mounts added or removed since the last pass
+        if let Ok(mut mounts) = self.mounts.write() {
+            *mounts = MountTable::load();
+        }
+
         for scan_path in &self.scan_paths {
             if scan_path.is_file() {
                 // Single file scan
@@ -427,14 +505,17 @@ impl FileScanner {
         Ok(all_detected)
     }
 
-    fn calculate_hash(&self, file_path: &Path) -> Result<String> {
-        let mut file = fs::File::open(file_path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
-        let mut hasher = Sha256::new();
-        hasher.update(&buffer);
-        let hash = hasher.finalize();
-        Ok(hex::encode(hash))
+    /// Scan specific files ahead of the next full pass (e.g. ones growing rapidly)
+    pub async fn scan_files(&self, paths: &[PathBuf]) -> Result<Vec<DetectedMalware>> {
+        let mut detected = Vec::new();
+        for path in paths {
+            match self.scan_file(path).await {
+                Ok(Some(malware)) => detected.push(malware),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to scan file {}: {}", path.display(), e),
+            }
+        }
+        Ok(detected)
     }
 
     pub fn get_quarantine_path(&self) -> &Path {
@@ -442,4 +523,301 @@ impl FileScanner {
     }
 }
 
+/// Seconds by which ctime (set by the kernel on every inode change, so it can't be
+/// backdated) is past mtime (which `touch -d` sets to anything), if at least
+/// `min_gap_seconds`. Archive extraction keeps old mtimes too, so on its own this
+/// only strengthens a signature match. 0 disables the check.
+pub fn timestomp_gap(mtime: i64, ctime: i64, min_gap_seconds: i64) -> Option<u64> {
+    let gap = ctime.saturating_sub(mtime);
+    (min_gap_seconds > 0 && gap >= min_gap_seconds).then_some(gap as u64)
+}
+
+/// One detection per file, keyed by canonical path, in a reproducible order (highest
+/// threat first, then path). Overlapping scan paths, symlinks and parallel chunks can
+/// report a file more than once; acting on each copy would race to quarantine it.
+/// Of duplicates, the strongest signature is kept, with the path it was scanned at:
+/// the canonical path is only the key.
+pub fn dedup_detections(detected: Vec<DetectedMalware>) -> Vec<DetectedMalware> {
+    let mut by_path: HashMap<PathBuf, DetectedMalware> = HashMap::new();
+    for malware in detected {
+        let key = fs::canonicalize(&malware.file_path).unwrap_or_else(|_| malware.file_path.clone());
+        match by_path.entry(key) {
+            Entry::Occupied(mut kept) => {
+                let existing = kept.get();
+                if (malware.signature.threat_level, &existing.signature.name, &existing.file_path)
+                    > (existing.signature.threat_level, &malware.signature.name, &malware.file_path) {
+                    kept.insert(malware);
+                }
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(malware);
+            }
+        }
+    }
+    let mut unique: Vec<DetectedMalware> = by_path.into_values().collect();
+    unique.sort_by(|a, b| {
+        b.signature.threat_level.total_cmp(&a.signature.threat_level)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    unique
+}
+
+/// Run blocking file I/O on the blocking pool and give up after `timeout`, so a hung
+/// mount costs one stuck thread instead of stalling the scan. The I/O runs at `priority`.
+async fn with_io_timeout<T, F>(path: &Path, timeout: Duration, priority: ScanPriority, io: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(PathBuf) -> Result<T> + Send + 'static,
+{
+    let owned = path.to_path_buf();
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(move || priority.run(|| io(owned)))).await {
+        Ok(joined) => joined?,
+        Err(_) => {
+            warn!("⏱️  I/O on {} timed out after {}ms, skipping it", path.display(), timeout.as_millis());
+            anyhow::bail!("I/O on {} timed out after {}ms", path.display(), timeout.as_millis())
+        }
+    }
+}
+
+/// Regular files under `dir_path` down to `max_depth`, following symlinks per `policy`.
+/// With FollowAll, directories are tracked by (device, inode) so a symlink cycle
+/// (or two links to the same tree) is walked only once. Directories for which
+/// `skip_dir` returns true are not entered.
+fn collect_files(dir_path: &Path, max_depth: usize, policy: SymlinkPolicy, skip_dir: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let mut visited_dirs = HashSet::new();
+    let mut files = Vec::new();
+
+    let walker = WalkDir::new(dir_path)
+        .follow_links(policy == SymlinkPolicy::FollowAll)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|entry| {
+            if !entry.file_type().is_dir() {
+                return true;
+            }
+            if entry.depth() > 0 && skip_dir(entry.path()) {
+                return false;
+            }
+            match entry.metadata() {
+                Ok(meta) => visited_dirs.insert((meta.dev(), meta.ino())),
+                Err(_) => false,
+            }
+        });
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        // follow_links already resolved the type under FollowAll; otherwise a
+        // symlink is only kept when FollowFiles allows it and it points at a file
+        let is_file = if entry.path_is_symlink() && policy != SymlinkPolicy::FollowAll {
+            policy == SymlinkPolicy::FollowFiles && path.is_file()
+        } else {
+            entry.file_type().is_file()
+        };
+        if !is_file {
+            continue;
+        }
+
+        // Skip system directories early for performance
+        let path_str = path.to_string_lossy();
+        if path_str.contains("/proc/") || path_str.contains("/sys/") || path_str.contains("/dev/") {
+            continue;
+        }
+
+        files.push(path.to_path_buf());
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    const UNCHANGED: ScanPriority = ScanPriority { nice: 0, ioclass: ScanIoClass::None };
+
+    fn names(files: &[PathBuf], root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = files.iter()
+            .map(|p| p.strip_prefix(root).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn duplicate_detections_collapse_to_one_in_stable_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("drop")).unwrap();
+        fs::write(root.join("drop/xmrig"), b"x").unwrap();
+        fs::write(root.join("drop/a.sh"), b"x").unwrap();
+        symlink(root.join("drop"), root.join("link")).unwrap();
+
+        let detection = |path: PathBuf, name: &str, threat_level: f32| DetectedMalware {
+            file_path: path,
+            signature: MalwareSignature {
+                name: name.to_string(),
+                file_name_pattern: None,
+                path_pattern: None,
+                file_hash: None,
+                threat_level,
+                description: String::new(),
+            },
+            file_hash: String::new(),
+            file_size: 1,
+            detected_at: chrono::Utc::now(),
+            timestomp_gap_seconds: None,
+        };
+        // The same file via two chunks, a symlinked scan path and a non-normalized path
+        let detected = vec![
+            detection(root.join("drop/a.sh"), "script", 0.6),
+            detection(root.join("drop/xmrig"), "miner_name", 0.9),
+            detection(root.join("link/xmrig"), "miner_hash", 1.0),
+            detection(root.join("drop/./xmrig"), "miner_name", 0.9),
+        ];
+
+        let unique = dedup_detections(detected.clone());
+        let summary: Vec<(PathBuf, &str)> = unique.iter().map(|m| (m.file_path.clone(), m.signature.name.as_str())).collect();
+        assert_eq!(summary, vec![
+            (root.join("link/xmrig"), "miner_hash"),
+            (root.join("drop/a.sh"), "script"),
+        ]);
+
+        // Arrival order doesn't change the outcome
+        let reversed: Vec<_> = detected.into_iter().rev().collect();
+        let again: Vec<(PathBuf, String)> = dedup_detections(reversed).into_iter().map(|m| (m.file_path, m.signature.name)).collect();
+        assert_eq!(again, summary.into_iter().map(|(p, n)| (p, n.to_string())).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn symlink_policy_controls_what_is_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("payload"), b"x").unwrap();
+        fs::create_dir(outside.path().join("sub")).unwrap();
+        fs::write(outside.path().join("sub/miner"), b"x").unwrap();
+
+        let root = dir.path();
+        fs::write(root.join("plain"), b"x").unwrap();
+        symlink(outside.path().join("payload"), root.join("file_link")).unwrap();
+        symlink(outside.path().join("sub"), root.join("dir_link")).unwrap();
+
+        assert_eq!(names(&collect_files(root, 20, SymlinkPolicy::Skip, |_| false), root), vec!["plain"]);
+        assert_eq!(names(&collect_files(root, 20, SymlinkPolicy::FollowFiles, |_| false), root), vec!["file_link", "plain"]);
+        assert_eq!(names(&collect_files(root, 20, SymlinkPolicy::FollowAll, |_| false), root),
+                   vec!["dir_link/miner", "file_link", "plain"]);
+    }
 
+    #[test]
+    fn follow_all_terminates_on_symlink_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/file"), b"x").unwrap();
+        // a/b/up -> a, and a/self -> a: both loop back into the tree
+        symlink(root.join("a"), root.join("a/b/up")).unwrap();
+        symlink(".", root.join("a/self")).unwrap();
+
+        let files = collect_files(root, 100, SymlinkPolicy::FollowAll, |_| false);
+        assert_eq!(names(&files, root), vec!["a/b/file"]);
+    }
+
+    #[test]
+    fn respects_max_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("1/2/3")).unwrap();
+        fs::write(root.join("1/shallow"), b"x").unwrap();
+        fs::write(root.join("1/2/3/deep"), b"x").unwrap();
+
+        assert_eq!(names(&collect_files(root, 2, SymlinkPolicy::Skip, |_| false), root), vec!["1/shallow"]);
+        assert_eq!(names(&collect_files(root, 4, SymlinkPolicy::Skip, |_| false), root), vec!["1/2/3/deep", "1/shallow"]);
+    }
+
+    #[test]
+    fn skip_dir_prunes_subtrees() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("mnt/nfs")).unwrap();
+        fs::write(root.join("mnt/nfs/remote"), "x").unwrap();
+        fs::write(root.join("mnt/local"), "x").unwrap();
+
+        let nfs = root.join("mnt/nfs");
+        assert_eq!(names(&collect_files(root, 20, SymlinkPolicy::Skip, |d| d == nfs), root), vec!["mnt/local"]);
+    }
+
+    #[tokio::test]
+    async fn hung_read_times_out_instead_of_blocking_the_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        // Opening a FIFO for reading blocks until a writer shows up, like a dead NFS server
+        let fifo = dir.path().join("hung");
+        nix::unistd::mkfifo(&fifo, nix::sys::stat::Mode::from_bits_truncate(0o600)).unwrap();
+
+        let started = std::time::Instant::now();
+        let err = FileScanner::hash_with_timeout(&fifo, Duration::from_millis(100), UNCHANGED).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Unblock the stuck reader so the runtime can shut down
+        drop(fs::OpenOptions::new().write(true).open(&fifo).unwrap());
+
+        let file = dir.path().join("plain");
+        fs::write(&file, "hello").unwrap();
+        assert_eq!(FileScanner::hash_with_timeout(&file, Duration::from_millis(1000), UNCHANGED).await.unwrap(),
+                   "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+    }
+
+    #[tokio::test]
+    async fn backdated_mtime_raises_the_threat_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let fresh = dir.path().join("kdevtmpfsi");
+        let backdated = dir.path().join("kinsing");
+        fs::write(&fresh, "x").unwrap();
+        fs::write(&backdated, "x").unwrap();
+        // What `touch -d '30 days ago'` does; ctime becomes now
+        let month_ago = std::time::SystemTime::now() - Duration::from_secs(30 * 24 * 3600);
+        fs::File::options().write(true).open(&backdated).unwrap().set_modified(month_ago).unwrap();
+
+        let mut scanner = FileScanner::new(Vec::new(), dir.path().join("quarantine"));
+        scanner.signatures.clear();
+        scanner.add_signature(MalwareSignature {
+            name: "dropper".to_string(),
+            file_name_pattern: Some(Regex::new("^(kdevtmpfsi|kinsing)$").unwrap()),
+            path_pattern: None,
+            file_hash: None,
+            threat_level: 0.85,
+            description: String::new(),
+        });
+
+        let untouched = scanner.scan_file(&fresh).await.unwrap().unwrap();
+        assert_eq!(untouched.timestomp_gap_seconds, None);
+        assert_eq!(untouched.signature.threat_level, 0.85);
+
+        // Raised, but the backdating alone doesn't cross the 0.9 kill threshold
+        let stomped = scanner.scan_file(&backdated).await.unwrap().unwrap();
+        assert!(stomped.timestomp_gap_seconds.unwrap() >= 29 * 24 * 3600);
+        assert!((stomped.signature.threat_level - 0.89).abs() < 1e-6);
+        assert!(!scanner.config.should_kill_on_match(stomped.signature.threat_level));
+        assert!(stomped.timestomp_note().starts_with("\nBackdated: inode changed"));
+        assert_eq!(untouched.timestomp_note(), "");
+    }
+
+    #[test]
+    fn timestomp_boost_stays_below_the_kill_threshold() {
+        let policy = TimestompPolicy { min_gap_seconds: 3600, boost: 0.1, kill_threshold: 0.9 };
+        assert!((policy.boosted(0.5) - 0.6).abs() < 1e-6);
+        assert!((policy.boosted(0.85) - 0.89).abs() < 1e-6);
+        // Already over the threshold on the signature alone: left as it is
+        assert!((policy.boosted(0.95) - 0.95).abs() < 1e-6);
+    }
+
+    #[test]
+    fn timestomp_gap_needs_the_minimum_and_can_be_disabled() {
+        let day = 24 * 3600;
+        assert_eq!(timestomp_gap(1_000, 1_000 + 2 * day, day), Some(2 * day as u64));
+        assert_eq!(timestomp_gap(1_000, 1_000 + day - 1, day), None);
+        // ctime before mtime is a future-dated file, not a backdated one
+        assert_eq!(timestomp_gap(1_000 + 2 * day, 1_000, day), None);
+        assert_eq!(timestomp_gap(1_000, 1_000 + 2 * day, 0), None);
+    }
+}
@@ -1,10 +1,16 @@
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 
+use crate::config::FileGrowthConfig;
+
+/// Directory levels below each growth root that are sampled
+const GROWTH_SAMPLE_DEPTH: usize = 3;
+
 pub struct FileWatcher {
     watch_paths: Vec<PathBuf>,
     inotify: Option<inotify::Inotify>,
@@ -23,7 +29,7 @@ impl FileWatcher {
 
         // Try to initialize inotify
         match inotify::Inotify::init() {
-            Ok(mut inotify) => {
+            Ok(inotify) => {
                 use inotify::WatchMask;
                 // Add watches for all paths
                 for path in &paths {
@@ -155,3 +161,146 @@ pub async fn shallow_scan_directories(paths: &[PathBuf]) -> Result<Vec<PathBuf>>
     Ok(changed_dirs.into_iter().collect())
 }
 
+
+/// A file whose size grew faster than the configured rate between two samples
+#[derive(Debug, Clone)]
+pub struct GrowingFile {
+    pub path: PathBuf,
+    pub previous_size: u64,
+    pub size: u64,
+    pub bytes_per_second: u64,
+}
+
+/// Remembers file sizes between samples to spot payloads being assembled or data being staged
+pub struct GrowthTracker {
+    roots: Vec<PathBuf>,
+    min_bytes_per_second: u64,
+    max_tracked: usize,
+    sizes: HashMap<PathBuf, u64>,
+    last_sample: Option<Instant>,
+}
+
+impl GrowthTracker {
+    pub fn new(config: &FileGrowthConfig) -> Self {
+        Self {
+            roots: config.paths.iter().map(PathBuf::from).collect(),
+            min_bytes_per_second: config.min_bytes_per_second,
+            max_tracked: config.max_tracked_files,
+            sizes: HashMap::new(),
+            last_sample: None,
+        }
+    }
+
+    pub fn sample(&mut self) -> Vec<GrowingFile> {
+        self.sample_at(Instant::now())
+    }
+
+    /// Record current sizes and return files that grew at least min_bytes_per_second since the last sample
+    pub fn sample_at(&mut self, now: Instant) -> Vec<GrowingFile> {
+        let mut observed = Vec::new();
+        for root in &self.roots {
+            collect_file_sizes(root, &mut observed);
+        }
+        // Keep files with history ahead of new ones so a flood of new files can't evict them
+        observed.sort_by_key(|(path, _)| !self.sizes.contains_key(path));
+        observed.truncate(self.max_tracked);
+
+        let elapsed = self.last_sample
+            .map(|last| now.saturating_duration_since(last).as_secs_f64())
+            .unwrap_or(0.0);
+        let mut growing = Vec::new();
+        if elapsed > 0.0 {
+            for (path, size) in &observed {
+                let Some(&previous_size) = self.sizes.get(path) else { continue };
+                if *size <= previous_size {
+                    continue;
+                }
+                let bytes_per_second = ((size - previous_size) as f64 / elapsed) as u64;
+                if bytes_per_second >= self.min_bytes_per_second {
+                    growing.push(GrowingFile {
+                        path: path.clone(),
+                        previous_size,
+                        size: *size,
+                        bytes_per_second,
+                    });
+                }
+            }
+        }
+
+        self.sizes = observed.into_iter().collect();
+        self.last_sample = Some(now);
+        growing
+    }
+
+    pub fn tracked_count(&self) -> usize {
+        self.sizes.len()
+    }
+}
+
+fn collect_file_sizes(root: &Path, observed: &mut Vec<(PathBuf, u64)>) {
+    use walkdir::WalkDir;
+
+    for entry in WalkDir::new(root)
+        .follow_links(false)
+        .max_depth(GROWTH_SAMPLE_DEPTH)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            observed.push((entry.into_path(), metadata.len()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn tracker(root: &Path, max_tracked_files: usize) -> GrowthTracker {
+        GrowthTracker::new(&FileGrowthConfig {
+            paths: vec![root.to_string_lossy().to_string()],
+            min_bytes_per_second: 1024,
+            max_tracked_files,
+            ..FileGrowthConfig::default()
+        })
+    }
+
+    #[test]
+    fn flags_file_growing_between_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let growing = dir.path().join("payload.part");
+        let steady = dir.path().join("notes.txt");
+        std::fs::write(&growing, vec![0u8; 100]).unwrap();
+        std::fs::write(&steady, vec![0u8; 100]).unwrap();
+
+        let mut tracker = tracker(dir.path(), 100);
+        let start = Instant::now();
+        assert!(tracker.sample_at(start).is_empty(), "first sample is only a baseline");
+
+        std::fs::write(&growing, vec![0u8; 100 + 64 * 1024]).unwrap();
+        let flagged = tracker.sample_at(start + Duration::from_secs(2));
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].path, growing);
+        assert_eq!(flagged[0].previous_size, 100);
+        assert_eq!(flagged[0].bytes_per_second, 32 * 1024);
+
+        // Slow growth stays below the rate
+        std::fs::write(&steady, vec![0u8; 200]).unwrap();
+        assert!(tracker.sample_at(start + Duration::from_secs(4)).is_empty());
+    }
+
+    #[test]
+    fn tracked_set_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            std::fs::write(dir.path().join(format!("f{}", i)), b"x").unwrap();
+        }
+        let mut tracker = tracker(dir.path(), 4);
+        tracker.sample_at(Instant::now());
+        assert_eq!(tracker.tracked_count(), 4);
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{CollectorConfig, Config, SyslogConfig, TelegramConfig};
+
+const HEADER: &str = "\
+# Hora-Police configuration, generated by `hora-police init-config`.
+# Every value below is the built-in default; delete a line to keep following the default.
+# See config.toml.example in the source tree for the full explanation of each option.
+";
+
+/// Comments written above top-level keys
+const KEY_COMMENTS: &[(&str, &str)] = &[
+    ("cpu_threshold", "CPU monitoring threshold (percent of one core); auto_tune lowers it to a smaller per_core_threshold"),
+    ("duration_minutes", "Minutes a process must stay above the threshold before it is flagged"),
+    ("real_time_alerts", "Send a Telegram alert for every detection, not just kills and daily reports"),
+    ("auto_kill", "Stop processes above threat_confidence_threshold (dry_run and audit_only take precedence)"),
+    ("learning_mode", "Build intelligence from past actions"),
+    ("database_path", "SQLite intelligence database"),
+    ("polling_interval_ms", "Process polling interval (lower = more responsive, higher = less overhead)"),
+    ("threat_confidence_threshold", "Processes scored above this (0.0-1.0) are stopped"),
+    ("dry_run", "Never act; also enabled by --dry-run"),
+    ("audit_only", "Decide but never act; decisions are recorded for `hora-police decisions`"),
+    ("retention_days", "Days of process history kept by daily database maintenance"),
+    ("kill_signals", "First signal sent, and the optional second sent after sigterm_timeout_seconds"),
+    ("pause_file", "While this file exists every action is downgraded to a notification (\"\" disables)"),
+    ("event_socket_path", "Socket streaming events to `hora-police watch` (\"\" disables)"),
+    ("suspicious_path_prefixes", "Binaries under these directories (or containing the substrings below) score as staged payloads"),
+];
+
+/// Comments written above each section header
+const SECTION_COMMENTS: &[(&str, &str)] = &[
+    ("file_scanning", "Periodic signature scan of scan_paths; matches are quarantined (or deleted with auto_delete)"),
+    ("learning_report", "Onboarding: report what would have been acted on every observation_hours"),
+    ("auto_tune", "Compare CPU as a share of the machine: min(cpu_threshold, max(5 * vCPUs, per_core_threshold)) of one core"),
+    ("whitelist", "Processes never acted on: auto-detected services plus manual_patterns (substring or glob)"),
+    ("denylist", "Always-malicious binaries, matched by path, name pattern or SHA256"),
+    ("file_blocking", "Re-quarantine malware files that are recreated after cleanup"),
+    ("build_users", "Users whose builds get a higher CPU threshold and a longer window"),
+    ("thread_fingerprint", "Boost processes whose busy thread count matches the vCPU count (miner thread pools)"),
+    ("daemonized_dropper", "Boost recently started processes reparented to init from a writable location"),
+    ("web_uploads", "Boost binaries executed from web upload directories"),
+    ("nginx_logs", "Correlate new processes with suspicious requests in nginx access logs"),
+    ("miner_profiling", "Profile new processes for mining behaviour"),
+    ("spawn_chain", "Flag web server -> shell -> downloader or staged binary chains"),
+    ("memory_scan", "Search process memory for mining protocol strings"),
+    ("docker", "How containers are stopped when a container process is acted on"),
+    ("disk_space", "Alert when the database or quarantine filesystem runs low"),
+    ("resource_signals", "Boost processes holding unusually many file descriptors or sockets"),
+    ("listener_signals", "Boost processes listening on ports from writable locations or on typical backdoor ports"),
+    ("sudoers", "Alert on sudo grants to principals outside allowed_principals"),
+    ("accounts", "Alert on new accounts and new UID 0 accounts"),
+    ("kernel_modules", "Alert on kernel modules loaded after startup; the allowlist only covers shipped modules"),
+    ("systemd_persistence", "Alert on systemd units that run payloads from writable locations"),
+    ("file_growth", "Scan files that grow quickly in these directories before the next full scan"),
+    ("integrations", "Timeouts and refresh intervals for PM2, systemd and nginx discovery"),
+    ("react_detection", "Detect compromised React/Next.js/Remix server processes"),
+    ("alert_templates", "Override alert titles and messages by alert key"),
+    ("confidence_bands", "What to do with medium and high confidence detections"),
+    ("probe", "HTTP status and self-metrics endpoint, also started by --probe"),
+];
+
+/// The default configuration as a commented TOML file. Sections that are off
+/// unless present (telegram, syslog, collector) are included commented out.
+pub fn render_default_config() -> Result<String> {
+    let defaults = toml::to_string_pretty(&Config::default())
+        .context("Failed to serialize the default config")?;
+
+    let mut out = String::from(HEADER);
+    let mut in_section = false;
+    for line in defaults.lines() {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = true;
+            if let Some(comment) = lookup(SECTION_COMMENTS, section) {
+                out.push_str(&format!("# {}\n", comment));
+            }
+        } else if !in_section {
+            let key = line.split(" = ").next().unwrap_or_default();
+            if let Some(comment) = lookup(KEY_COMMENTS, key) {
+                out.push_str(&format!("\n# {}\n", comment));
+            }
+        }
+        out.push_str(&tidy_float(line));
+        out.push('\n');
+    }
+
+    out.push_str("\n# Telegram alerts and daily reports (chat_id receives every severity)\n");
+    out.push_str(&commented_example::<TelegramConfig>(
+        "telegram", "bot_token = \"123456:ABC-DEF\"\nchat_id = \"-1001234567890\"\ndaily_report_time = \"09:00\"")?);
+    out.push_str("\n# Also send every event to a remote syslog server (RFC 5424)\n");
+    out.push_str(&commented_example::<SyslogConfig>("syslog", "host = \"logs.example.com\"")?);
+    out.push_str("\n# Also forward detection records to a central HTTP collector\n");
+    out.push_str(&commented_example::<CollectorConfig>(
+        "collector", "url = \"https://collector.example.com/ingest\"")?);
+    Ok(out)
+}
+
+/// Write the default configuration to `path`, refusing to replace an existing file unless `force`
+pub fn write_default_config(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!("{:?} already exists; pass --force to overwrite it", path);
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+    }
+    fs::write(path, render_default_config()?).with_context(|| format!("Failed to write {:?}", path))
+}
+
+fn lookup(comments: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+    comments.iter().find(|(key, _)| *key == name).map(|(_, comment)| *comment)
+}
+
+/// `[name]` built from `required` plus the section's serde defaults, with every line commented out
+fn commented_example<T: DeserializeOwned + Serialize>(name: &str, required: &str) -> Result<String> {
+    let section: T = toml::from_str(required)
+        .with_context(|| format!("Invalid example [{}] section", name))?;
+    let mut table = toml::Table::new();
+    table.insert(name.to_string(), toml::Value::try_from(&section)?);
+    let body = toml::to_string_pretty(&table)?;
+    Ok(body.lines()
+        .map(|line| if line.is_empty() { "#\n".to_string() } else { format!("# {}\n", tidy_float(line)) })
+        .collect())
+}
+
+/// f32 settings serialize through f64 (`0.699999988079071`); print them as written (`0.7`)
+fn tidy_float(line: &str) -> String {
+    if let Some((key, value)) = line.split_once(" = ") {
+        if value.contains('.') {
+            if let Ok(number) = value.parse::<f64>() {
+                return format!("{} = {:?}", key, number as f32);
+            }
+        }
+    }
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_file_round_trips_through_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        write_default_config(&path, false).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("threat_confidence_threshold = 0.7\n"));
+        assert!(content.contains("# Compare CPU as a share of the machine"));
+        let loaded = Config::load(&path).unwrap();
+        assert_eq!(toml::to_string(&loaded).unwrap(), toml::to_string(&Config::default()).unwrap());
+
+        // The commented-out optional sections at the end are valid once uncommented
+        let (defaults, examples) = content.split_at(content.find("# [telegram]").unwrap());
+        let uncommented: String = examples.lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(rest) if rest.starts_with('[') || rest.contains(" = ") => format!("{}\n", rest),
+                _ => format!("{}\n", line),
+            })
+            .collect();
+        let uncommented = format!("{}{}", defaults, uncommented);
+        fs::write(&path, uncommented).unwrap();
+        let loaded = Config::load(&path).unwrap();
+        assert_eq!(loaded.telegram.unwrap().daily_report_time, "09:00");
+        assert_eq!(loaded.syslog.unwrap().port, 514);
+        assert_eq!(loaded.collector.unwrap().timeout_ms, 5000);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "cpu_threshold = 50.0\n").unwrap();
+
+        assert!(write_default_config(&path, false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "cpu_threshold = 50.0\n");
+        write_default_config(&path, true).unwrap();
+        assert!(Config::load(&path).is_ok());
+    }
+}
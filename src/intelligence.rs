@@ -1,11 +1,87 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use crate::database::{IntelligenceDB, SuspiciousProcess};
-use crate::process_monitor::ProcessInfo;
+use crate::denylist::executable_sha256;
+use crate::config::{
+    DaemonizedDropperConfig, FileScanningConfig, ListenerSignalsConfig, ResourceSignalsConfig, ThreadFingerprintConfig, WebUploadConfig,
+};
+use crate::process_monitor::{
+    file_change_age_seconds, is_kernel_thread_impostor, is_under_dir_pattern,
+    runs_foreign_home_code, ProcessInfo,
+};
+use crate::payload_detector::{find_encoded_payloads, payload_confidence};
+use crate::scoring::{
+    clamp_confidence, indicator_score, is_suspicious_command, is_system_binary,
+    score_process, signal_categories, ProcessSignals, ScoringWeights,
+    SignalCategory,
+};
+use crate::suspicious_paths::{is_suspicious_path, suspicious_dir_match};
+use crate::users::BuildUserPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A resolved pool address stops matching if it isn't re-resolved within this long
+/// (the daemon re-resolves known pools daily), so a pool moving off a shared
+/// address doesn't leave that address flagged forever
+const POOL_ADDR_TTL: Duration = Duration::from_secs(36 * 3600);
+
+/// Kills older than this no longer mark a binary as persistent
+const PRIOR_KILL_WINDOW_DAYS: i64 = 30;
+
+/// Cached executable hashes kept before the cache is cleared
+const EXE_HASH_CACHE_LIMIT: usize = 4096;
+
+/// Runtimes whose hash says nothing about the script they run
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "dash", "zsh", "python", "perl", "ruby", "node", "nodejs", "php", "php-fpm", "java", "bun", "deno",
+];
 
 pub struct BehaviorIntelligence {
     db: IntelligenceDB,
+    #[allow(dead_code)]
     learning_mode: bool,
+    build_users: BuildUserPolicy,
+    thread_fingerprint: ThreadFingerprintConfig,
+    vcpu_count: usize,
+    daemonized_max_age_seconds: u64,
+    web_upload_dirs: Vec<String>,
+    web_upload_recent_seconds: u64,
+    listener_suspicious_ports: HashSet<u16>,
+    upstream_ports: HashSet<u16>,  // Nginx backends, never suspicious listeners
+    pool_addrs: HashMap<SocketAddr, Instant>,  // Mining pools named in configs next to detected miners -> resolved at
+    min_record_confidence: f32,
+    paranoid_tmp_exec: Option<f32>,  // Confidence floor for tmpfs executables, when enabled
+    weights: ScoringWeights,
+    exe_hashes: Mutex<HashMap<(i32, u64), Option<String>>>,  // (pid, start_time) -> SHA256 of its executable
+    suspicious_seen: AtomicU64,
+    suspicious_recorded: AtomicU64,
+}
+
+/// The pieces of the "dropped and daemonized" composite, for explaining a score.
+/// CPU is the fourth piece and is judged by the scorer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonizedSignals {
+    pub no_tty: Option<bool>,  // None if tty_nr couldn't be read
+    pub age_seconds: Option<u64>,
+    pub recently_started: bool,
+    pub writable_location: bool,
+}
+
+impl DaemonizedSignals {
+    pub fn holds(&self) -> bool {
+        self.no_tty == Some(true) && self.recently_started && self.writable_location
+    }
+}
+
+/// Suspicious-process counters, including ones below the recording bar
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SuspiciousStats {
+    pub seen: u64,
+    pub recorded: u64,
 }
 
 impl BehaviorIntelligence {
@@ -13,16 +89,182 @@ impl BehaviorIntelligence {
         Ok(Self {
             db,
             learning_mode,
+            build_users: BuildUserPolicy::default(),
+            thread_fingerprint: ThreadFingerprintConfig::default(),
+            vcpu_count: 0, // Unknown until set_thread_fingerprint; disables the heuristic
+            daemonized_max_age_seconds: DaemonizedDropperConfig::default().max_age_seconds,
+            web_upload_dirs: WebUploadConfig::default().dirs,
+            web_upload_recent_seconds: WebUploadConfig::default().recent_minutes * 60,
+            listener_suspicious_ports: ListenerSignalsConfig::default().suspicious_ports.into_iter().collect(),
+            upstream_ports: HashSet::new(),
+            pool_addrs: HashMap::new(),
+            min_record_confidence: 0.0,
+            paranoid_tmp_exec: None,
+            weights: ScoringWeights::default(),
+            exe_hashes: Mutex::new(HashMap::new()),
+            suspicious_seen: AtomicU64::new(0),
+            suspicious_recorded: AtomicU64::new(0),
+        })
+    }
+
+    /// Relax CPU/duration heuristics for processes owned by build users
+    pub fn set_build_users(&mut self, policy: BuildUserPolicy) {
+        self.build_users = policy;
+    }
+
+    /// Enable the one-thread-per-core miner fingerprint for this host
+    pub fn set_thread_fingerprint(&mut self, config: ThreadFingerprintConfig, vcpu_count: usize) {
+        self.thread_fingerprint = config;
+        self.vcpu_count = vcpu_count;
+    }
+
+    /// Boost detached, freshly started processes from writable locations
+    pub fn set_daemonized_dropper(&mut self, config: &DaemonizedDropperConfig) {
+        self.weights.daemonized = if config.enabled { config.confidence_boost } else { 0.0 };
+        self.daemonized_max_age_seconds = config.max_age_seconds;
+    }
+
+    /// Evaluate the daemonized-dropper pieces for `process` as of `now`
+    pub fn daemonized_signals(&self, process: &ProcessInfo, now: DateTime<Utc>) -> DaemonizedSignals {
+        let age_seconds = (process.start_time > 0)
+            .then(|| (now.timestamp().max(0) as u64).saturating_sub(process.start_time));
+        DaemonizedSignals {
+            no_tty: process.tty_nr.map(|tty| tty == 0),
+            age_seconds,
+            recently_started: age_seconds.is_some_and(|age| age <= self.daemonized_max_age_seconds),
+            writable_location: is_suspicious_path(&process.binary_path),
+        }
+    }
+
+    /// Boost executables running from web upload directories, more so when freshly written
+    pub fn set_web_uploads(&mut self, config: &WebUploadConfig) {
+        let (boost, recent_boost) = if config.enabled {
+            (config.confidence_boost, config.recent_boost)
+        } else {
+            (0.0, 0.0)
+        };
+        self.weights.web_upload = boost;
+        self.weights.web_upload_recent = recent_boost;
+        self.web_upload_dirs = config.dirs.clone();
+        self.web_upload_recent_seconds = config.recent_minutes * 60;
+    }
+
+    /// Whether `process` runs from a web upload directory, and whether the file
+    /// changed within the recent window as of `now`
+    pub fn web_upload_signals(&self, process: &ProcessInfo, now: DateTime<Utc>) -> (bool, bool) {
+        let in_upload_dir = !process.binary_path.is_empty()
+            && self.web_upload_dirs.iter().any(|dir| is_under_dir_pattern(&process.binary_path, dir));
+        if !in_upload_dir {
+            return (false, false);
+        }
+        let recent = file_change_age_seconds(&process.binary_path, now.timestamp())
+            .is_some_and(|age| age <= self.web_upload_recent_seconds);
+        (true, recent)
+    }
+
+    /// Boost processes opening listeners they have no business opening
+    pub fn set_listener_signals(&mut self, config: &ListenerSignalsConfig) {
+        let (boost, port_boost) = if config.enabled {
+            (config.confidence_boost, config.port_boost)
+        } else {
+            (0.0, 0.0)
+        };
+        self.weights.suspicious_listener = boost;
+        self.weights.listener_port = port_boost;
+        self.listener_suspicious_ports = config.suspicious_ports.iter().copied().collect();
+    }
+
+    /// Ports Nginx proxies to, refreshed with the upstream map
+    pub fn set_upstream_ports(&mut self, ports: impl IntoIterator<Item = u16>) {
+        self.upstream_ports = ports.into_iter().collect();
+    }
+
+    /// Boost processes connected to mining pools taken from miner configs
+    pub fn set_pool_connections(&mut self, config: &FileScanningConfig) {
+        self.weights.pool_connection = if config.extract_miner_iocs { config.pool_connection_boost } else { 0.0 };
+    }
+
+    /// Freshly resolved addresses of pools found in miner configs; entries not
+    /// refreshed within POOL_ADDR_TTL are dropped
+    pub fn add_pool_addrs(&mut self, addrs: impl IntoIterator<Item = SocketAddr>) {
+        let now = Instant::now();
+        self.pool_addrs.retain(|_, resolved| now.duration_since(*resolved) < POOL_ADDR_TTL);
+        self.pool_addrs.extend(addrs.into_iter().map(|addr| (addr, now)));
+    }
+
+    /// Whether `process` has an established connection to a known pool's address and port
+    pub fn connected_to_pool(&self, process: &ProcessInfo) -> bool {
+        process.remote_addrs.iter().any(|addr| {
+            self.pool_addrs.get(addr).is_some_and(|resolved| resolved.elapsed() < POOL_ADDR_TTL)
         })
     }
 
+    /// Whether `process` listens from a writable location, and whether one of its ports
+    /// is a known mining/C2 port. Nginx upstream ports are left out. A non-root process
+    /// on a port below 1024 is not suspicious by itself: nginx and apache workers inherit
+    /// their sockets from a root master, and CAP_NET_BIND_SERVICE services bind directly.
+    pub fn listener_signals(&self, process: &ProcessInfo) -> (bool, bool) {
+        let ports: Vec<u16> = process.listening_ports.iter()
+            .copied()
+            .filter(|port| !self.upstream_ports.contains(port))
+            .collect();
+        let suspicious = !ports.is_empty() && is_suspicious_path(&process.binary_path);
+        let known_port = suspicious && ports.iter().any(|port| self.listener_suspicious_ports.contains(port));
+        (suspicious, known_port)
+    }
+
+    /// Score unusually many open descriptors/sockets
+    pub fn set_resource_signals(&mut self, config: &ResourceSignalsConfig) {
+        let (fd_threshold, socket_threshold) = if config.enabled {
+            (config.fd_threshold, config.socket_threshold)
+        } else {
+            (0, 0)
+        };
+        self.weights.high_fd_count = fd_threshold;
+        self.weights.high_fd = config.fd_boost;
+        self.weights.high_socket_count = socket_threshold;
+        self.weights.high_socket = config.socket_boost;
+    }
+
+    /// paranoid_tmp_exec: score every executable under a `suspicious_path_prefixes`
+    /// directory at least `confidence`, whatever its CPU usage
+    pub fn set_paranoid_tmp_exec(&mut self, enabled: bool, confidence: f32) {
+        self.paranoid_tmp_exec = enabled.then_some(confidence);
+    }
+
+    /// Confidence floor for `process` under paranoid_tmp_exec, if it applies
+    pub fn paranoid_confidence(&self, process: &ProcessInfo) -> Option<f32> {
+        self.paranoid_tmp_exec.filter(|_| suspicious_dir_match(&process.binary_path).is_some())
+    }
+
+    /// Only persist suspicious processes at or above this confidence
+    pub fn set_min_record_confidence(&mut self, min_confidence: f32) {
+        self.min_record_confidence = min_confidence;
+    }
+
+    pub fn suspicious_stats(&self) -> SuspiciousStats {
+        SuspiciousStats {
+            seen: self.suspicious_seen.load(Ordering::Relaxed),
+            recorded: self.suspicious_recorded.load(Ordering::Relaxed),
+        }
+    }
+
     pub async fn analyze_process(
         &self,
         process: &ProcessInfo,
         cpu_percent: f32,
         duration_seconds: u64,
-        first_seen: DateTime<Utc>,
+        _first_seen: DateTime<Utc>,
     ) -> Result<f32> {
+        let signals = self.gather_signals(process, cpu_percent, duration_seconds);
+        let floor = self.paranoid_confidence(process).unwrap_or(0.0);
+
+        // A binary we've killed before running again is almost certainly persistence
+        let prior_kill = match self.prior_kills(process).await {
+            Ok(kills) if kills > 0 => self.weights.prior_kill,
+            _ => 0.0,
+        };
+
         // Check if we've seen this binary before
         if let Ok(Some(existing)) = self.db.get_suspicious_by_binary(&process.binary_path).await {
             // Increase confidence based on repeat behavior
@@ -30,80 +272,115 @@ impl BehaviorIntelligence {
             
             // If it restarted, increase threat
             if existing.pid != process.pid && existing.binary_path == process.binary_path {
-                confidence += 0.2;
+                confidence += self.weights.restart;
             }
             
             // If spawn count is high, increase threat
-            if existing.spawn_count > 3 {
-                confidence += 0.1;
+            if existing.spawn_count > self.weights.respawn_count {
+                confidence += self.weights.respawn;
             }
             
-            // If it was previously killed, very high threat
-            // (This would require checking kill_actions table, simplified here)
-            
-            return Ok(confidence.min(1.0));
+            confidence += prior_kill;
+
+            return Ok(clamp_confidence(confidence + indicator_score(&signals, &self.weights)).max(floor));
         }
 
-        // New process - calculate initial confidence
-        let mut confidence: f32 = 0.0;
+        // New process - score from the gathered signals alone
+        Ok(clamp_confidence(score_process(&signals, &self.weights) + prior_kill).max(floor))
+    }
 
-        // Base confidence from CPU abuse
-        if cpu_percent > 30.0 {
-            confidence += 0.4;
-        } else if cpu_percent > 20.0 {
-            confidence += 0.3;
+    /// Distinct kinds of evidence behind a process's score, for `require_corroboration`
+    /// Recent kills of the executable `process` runs, wherever it was run from.
+    /// Interpreters are skipped: one killed script would mark every script.
+    async fn prior_kills(&self, process: &ProcessInfo) -> Result<u64> {
+        if is_interpreter(process) {
+            return Ok(0);
         }
+        let Some(hash) = self.exe_hash(process) else {
+            return Ok(0);
+        };
+        self.db.count_prior_kills(&hash, PRIOR_KILL_WINDOW_DAYS).await
+    }
 
-        // Increase if duration is long
-        if duration_seconds > 600 { // 10 minutes
-            confidence += 0.2;
+    fn exe_hash(&self, process: &ProcessInfo) -> Option<String> {
+        let mut cache = self.exe_hashes.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= EXE_HASH_CACHE_LIMIT {
+            cache.clear();
         }
+        cache.entry((process.pid, process.start_time))
+            .or_insert_with(|| executable_sha256(process))
+            .clone()
+    }
 
-        // Decrease if it's a known safe binary
-        if process.binary_path.starts_with("/usr/bin/") 
-            || process.binary_path.starts_with("/usr/sbin/")
-            || process.binary_path.starts_with("/bin/")
-            || process.binary_path.starts_with("/sbin/") {
-            confidence *= 0.3; // Reduce but don't eliminate
-        }
+    pub fn signal_categories(&self, process: &ProcessInfo, cpu_percent: f32, duration_seconds: u64) -> BTreeSet<SignalCategory> {
+        signal_categories(&self.gather_signals(process, cpu_percent, duration_seconds), &self.weights)
+    }
 
-        // Enhanced command line pattern matching
-        let suspicious_patterns = [
-            "miner", "xmrig", "crypto", "mining", "ccminer", "cpuminer",
-            "stratum", "pool", "hashrate", "rig", "gpu", "cuda",
-            "base64", "eval", "exec", "wget.*sh", "curl.*sh",
-            "\.sh.*\|", "bash.*-c", "sh.*-c",
-        ];
-        let cmd_lower = process.command_line.to_lowercase();
-        for pattern in &suspicious_patterns {
-            if cmd_lower.contains(pattern) {
-                confidence += 0.2;
-                break; // Only count once
-            }
+    /// Collect the inputs for `score_process` from the process and host state
+    fn gather_signals(&self, process: &ProcessInfo, cpu_percent: f32, duration_seconds: u64) -> ProcessSignals {
+        let base_duration = self.weights.long_running_seconds;
+        let (web_upload, web_upload_recent) = self.web_upload_signals(process, Utc::now());
+        let (suspicious_listener, listener_port) = self.listener_signals(process);
+        ProcessSignals {
+            cpu_percent,
+            duration_seconds,
+            // Thresholds scaled up for build users
+            cpu_threshold_scale: self.build_users.cpu_threshold(1.0, process),
+            duration_scale: if base_duration == 0 {
+                1.0
+            } else {
+                self.build_users.duration_seconds(base_duration, process) as f32 / base_duration as f32
+            },
+            system_binary: is_system_binary(&process.binary_path),
+            unusual_location: is_suspicious_path(&process.binary_path),
+            suspicious_command: is_suspicious_command(&process.command_line),
+            // Suspicious: process with unusual parent (not init/systemd)
+            non_init_parent: process.ppid > 1 && process.ppid != process.pid,
+            memory_mb: process.memory_bytes / (1024 * 1024),
+            fd_count: process.fd_count,
+            socket_count: process.socket_count,
+            // Loader hijacking: LD_PRELOAD/LD_LIBRARY_PATH pointing at writable dirs
+            suspicious_env: !process.suspicious_env.is_empty(),
+            // Fake kernel thread: bracketed kthread name but a real exe outside kthreadd
+            kernel_impostor: is_kernel_thread_impostor(process),
+            // Fileless execution from a memfd: legitimate services essentially never do this
+            fileless: process.exe_is_memfd,
+            // argv/comm rewritten so `ps` shows something benign
+            cmdline_spoofed: process.cmdline_spoofed,
+            // Privileged process executing a regular user's (possibly planted) code
+            foreign_home: runs_foreign_home_code(process),
+            // Dropped and daemonized: detached from any terminal, young, in a staging directory
+            daemonized: self.daemonized_signals(process, Utc::now()).holds(),
+            // Executing out of a web app's upload directory: the webshell -> miner pipeline
+            web_upload,
+            web_upload_recent,
+            // Listening from a staging directory or without CAP_NET_BIND_SERVICE's usual owner
+            suspicious_listener,
+            listener_port,
+            // Connected to a pool a miner we caught was configured for
+            pool_connection: self.connected_to_pool(process),
+            // Encoded (base64/hex) payloads in the command line, decoded and re-scanned
+            payload_confidence: payload_confidence(&find_encoded_payloads(&process.command_line)),
+            // One busy thread per core from a staging directory: classic miner layout
+            thread_fingerprint_boost: self.thread_fingerprint_boost(process, cpu_percent),
         }
+    }
 
-        // Increase if running from unusual locations
-        if process.binary_path.starts_with("/tmp/")
-            || process.binary_path.starts_with("/var/tmp/")
-            || process.binary_path.contains("/.cache/")
-            || process.binary_path.contains("/dev/shm/")
-            || process.binary_path.contains("/.local/") {
-            confidence += 0.2;
+    /// Boost when a high-CPU process from a writable location runs about one thread per vCPU
+    fn thread_fingerprint_boost(&self, process: &ProcessInfo, cpu_percent: f32) -> f32 {
+        let fp = &self.thread_fingerprint;
+        // Single-core hosts make every single-threaded process "match"
+        if !fp.enabled || self.vcpu_count < 2 || process.thread_count < 2 {
+            return 0.0;
         }
-
-        // File permission analysis (if we can check)
-        // Note: This would require additional file system access
-        // For now, we'll use path heuristics
-        
-        // Process relationship analysis
-        // Suspicious: process with unusual parent (not init/systemd)
-        if process.ppid > 1 && process.ppid != process.pid {
-            // Check if parent is suspicious (would need process monitor access)
-            // For now, we'll use a simple heuristic
-            confidence += 0.1;
+        if cpu_percent < fp.min_cpu_percent || !is_suspicious_path(&process.binary_path) {
+            return 0.0;
+        }
+        if process.thread_count.abs_diff(self.vcpu_count) <= fp.tolerance {
+            fp.confidence_boost
+        } else {
+            0.0
         }
-
-        Ok(confidence.min(1.0f32))
     }
 
     pub async fn record_suspicious_process(
@@ -113,7 +390,12 @@ impl BehaviorIntelligence {
         duration_seconds: u64,
         confidence: f32,
         first_seen: DateTime<Utc>,
-    ) -> Result<()> {
+    ) -> Result<bool> {
+        self.suspicious_seen.fetch_add(1, Ordering::Relaxed);
+        if confidence < self.min_record_confidence {
+            return Ok(false);
+        }
+
         // Check if this is a restart
         let existing = self.db.get_suspicious_by_binary(&process.binary_path).await?;
         let restart_detected = existing.as_ref()
@@ -140,8 +422,265 @@ impl BehaviorIntelligence {
         };
 
         self.db.upsert_suspicious_process(&suspicious).await?;
+        self.suspicious_recorded.fetch_add(1, Ordering::Relaxed);
 
-        Ok(())
+        Ok(true)
     }
 }
 
+/// Executable name is a known interpreter, optionally versioned (`python3.11`, `php8.2`)
+fn is_interpreter(process: &ProcessInfo) -> bool {
+    let name = process.binary_path.rsplit('/').next().unwrap_or_default();
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn thread_fingerprint_flags_one_thread_per_core() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        intelligence.set_thread_fingerprint(ThreadFingerprintConfig::default(), 8);
+
+        let miner = ProcessInfo {
+            pid: 4242,
+            binary_path: "/dev/shm/.x/kdevtmpfs".to_string(),
+            thread_count: 9,
+            ..Default::default()
+        };
+        assert_eq!(intelligence.thread_fingerprint_boost(&miner, 780.0), 0.25);
+        // Idle or outside a staging directory: no signal
+        assert_eq!(intelligence.thread_fingerprint_boost(&miner, 10.0), 0.0);
+        let node = ProcessInfo {
+            binary_path: "/usr/bin/node".to_string(),
+            ..miner.clone()
+        };
+        assert_eq!(intelligence.thread_fingerprint_boost(&node, 780.0), 0.0);
+        // Thread count far from the core count (Node's libuv pool, single-threaded scripts)
+        let single = ProcessInfo { thread_count: 1, ..miner.clone() };
+        assert_eq!(intelligence.thread_fingerprint_boost(&single, 99.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn previously_killed_binary_scores_higher_on_reappearance() {
+        use crate::database::{KillAction, KillOutcome};
+
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let intelligence = BehaviorIntelligence::new(db.clone(), false).await.unwrap();
+        // PIDs above pid_max, so the hash comes from binary_path
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("kthreadd");
+        std::fs::write(&binary, b"\x7fELF miner").unwrap();
+        let process = ProcessInfo {
+            pid: 99_999_001,
+            ppid: 1,
+            binary_path: binary.to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let before = intelligence.analyze_process(&process, 45.0, 60, Utc::now()).await.unwrap();
+        let hash = executable_sha256(&process).unwrap();
+
+        let kill = |outcome, signal: &str, days_ago| KillAction {
+            id: 0,
+            pid: 4000,
+            uid: 0,
+            binary_path: "/tmp/.x/somewhere-else".to_string(),
+            reason: "CPU abuse".to_string(),
+            confidence: 0.9,
+            timestamp: Utc::now() - chrono::Duration::days(days_ago),
+            signal_sent: Some(signal.to_string()),
+            outcome,
+            exe_hash: Some(hash.clone()),
+        };
+        // Kills that never landed, manager stops, SIGSTOP containment and old kills don't count
+        db.record_kill_action(&kill(KillOutcome::Failed, "SIGTERM", 0)).await.unwrap();
+        db.record_kill_action(&kill(KillOutcome::Terminated, "systemctl stop", 0)).await.unwrap();
+        db.record_kill_action(&kill(KillOutcome::Stopped, "SIGSTOP", 0)).await.unwrap();
+        db.record_kill_action(&kill(KillOutcome::Terminated, "SIGTERM", 45)).await.unwrap();
+        assert_eq!(db.count_prior_kills(&hash, PRIOR_KILL_WINDOW_DAYS).await.unwrap(), 0);
+        // Same executable killed under another name
+        db.record_kill_action(&kill(KillOutcome::Terminated, "SIGTERM", 1)).await.unwrap();
+        assert_eq!(db.count_prior_kills(&hash, PRIOR_KILL_WINDOW_DAYS).await.unwrap(), 1);
+
+        let after = intelligence.analyze_process(&ProcessInfo { pid: 99_999_002, ..process.clone() }, 45.0, 60, Utc::now()).await.unwrap();
+        assert!(after >= before + 0.39, "{} -> {}", before, after);
+
+        // An interpreter's hash says nothing about the script it runs
+        let python = ProcessInfo { binary_path: "/usr/bin/python3.11".to_string(), ..process.clone() };
+        assert!(is_interpreter(&python));
+        assert!(!is_interpreter(&process));
+    }
+
+    #[tokio::test]
+    async fn paranoid_tmp_exec_flags_idle_tmpfs_executables() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        let idle = ProcessInfo {
+            pid: 4242,
+            ppid: 1,
+            binary_path: "/dev/shm/.cache/helper".to_string(),
+            ..Default::default()
+        };
+        let confidence = intelligence.analyze_process(&idle, 0.0, 0, Utc::now()).await.unwrap();
+        assert!(confidence < 0.95);
+
+        intelligence.set_paranoid_tmp_exec(true, 0.95);
+        assert_eq!(intelligence.analyze_process(&idle, 0.0, 0, Utc::now()).await.unwrap(), 0.95);
+        for path in ["/tmp/x", "/var/tmp/.x/y", "/run/user/1000/x"] {
+            let process = ProcessInfo { binary_path: path.to_string(), ..idle.clone() };
+            assert_eq!(intelligence.paranoid_confidence(&process), Some(0.95));
+        }
+        let home = ProcessInfo { binary_path: "/home/app/.cache/tmp/x".to_string(), ..idle.clone() };
+        assert_eq!(intelligence.paranoid_confidence(&home), None);
+    }
+
+    #[tokio::test]
+    async fn daemonized_composite_requires_every_piece() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        intelligence.set_daemonized_dropper(&DaemonizedDropperConfig::default());
+        let now = Utc::now();
+
+        let dropped = ProcessInfo {
+            pid: 4242,
+            binary_path: "/tmp/.x/kdevtmpfsi".to_string(),
+            start_time: now.timestamp() as u64 - 120,
+            tty_nr: Some(0),
+            ..Default::default()
+        };
+        let signals = intelligence.daemonized_signals(&dropped, now);
+        assert_eq!(signals, DaemonizedSignals {
+            no_tty: Some(true),
+            age_seconds: Some(120),
+            recently_started: true,
+            writable_location: true,
+        });
+        assert!(signals.holds());
+        assert!(intelligence.analyze_process(&dropped, 25.0, 60, now).await.unwrap() >= 0.7);
+
+        // A user's interactive build in /tmp has a terminal
+        let interactive = ProcessInfo { tty_nr: Some(34817), ..dropped.clone() };
+        assert!(!intelligence.daemonized_signals(&interactive, now).holds());
+        // Long-running, installed or unreadable: no boost either
+        let old = ProcessInfo { start_time: now.timestamp() as u64 - 86400, ..dropped.clone() };
+        assert!(!intelligence.daemonized_signals(&old, now).holds());
+        let installed = ProcessInfo { binary_path: "/usr/local/bin/worker".to_string(), ..dropped.clone() };
+        assert!(!intelligence.daemonized_signals(&installed, now).holds());
+        let unknown = ProcessInfo { tty_nr: None, ..dropped.clone() };
+        assert_eq!(intelligence.daemonized_signals(&unknown, now).no_tty, None);
+        assert!(!intelligence.daemonized_signals(&unknown, now).holds());
+    }
+
+    #[tokio::test]
+    async fn web_upload_executables_are_boosted() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploads = dir.path().join("site/uploads/2024");
+        std::fs::create_dir_all(&uploads).unwrap();
+        let dropped = uploads.join("php-fpm");
+        std::fs::write(&dropped, "bin").unwrap();
+
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        intelligence.set_web_uploads(&WebUploadConfig {
+            dirs: vec![format!("{}/*/uploads", dir.path().display())],
+            ..Default::default()
+        });
+        let now = Utc::now();
+
+        let process = ProcessInfo { pid: 4242, binary_path: dropped.display().to_string(), ..Default::default() };
+        assert_eq!(intelligence.web_upload_signals(&process, now), (true, true));
+        // Two hours later the file is no longer fresh
+        assert_eq!(intelligence.web_upload_signals(&process, now + chrono::Duration::hours(2)), (true, false));
+        let elsewhere = ProcessInfo { binary_path: dir.path().join("site/bin/app").display().to_string(), ..process.clone() };
+        assert_eq!(intelligence.web_upload_signals(&elsewhere, now), (false, false));
+
+        // Both boosts stack on top of the rest of the score (0.25 + 0.15)
+        let boosted = intelligence.analyze_process(&process, 25.0, 60, now).await.unwrap();
+        intelligence.set_web_uploads(&WebUploadConfig { enabled: false, ..Default::default() });
+        let plain = intelligence.analyze_process(&process, 25.0, 60, now).await.unwrap();
+        assert!((boosted - plain - 0.4).abs() < 1e-4, "{} vs {}", boosted, plain);
+    }
+
+    #[tokio::test]
+    async fn flags_listeners_from_staging_dirs_but_not_nginx_backends() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        intelligence.set_listener_signals(&ListenerSignalsConfig::default());
+
+        let proxy = ProcessInfo {
+            pid: 4242,
+            uid: 1000,
+            binary_path: "/tmp/.x/proxy".to_string(),
+            listening_ports: vec![3333],
+            ..Default::default()
+        };
+        assert_eq!(intelligence.listener_signals(&proxy), (true, true));
+        let high_port = ProcessInfo { listening_ports: vec![40123], ..proxy.clone() };
+        assert_eq!(intelligence.listener_signals(&high_port), (true, false));
+        // A non-root worker on a privileged port (inherited from a root master) is normal
+        let worker = ProcessInfo { binary_path: "/usr/sbin/nginx".to_string(), listening_ports: vec![80, 443], ..proxy.clone() };
+        assert_eq!(intelligence.listener_signals(&worker), (false, false));
+        let installed = ProcessInfo { binary_path: "/opt/app/server".to_string(), listening_ports: vec![3333], ..proxy.clone() };
+        assert_eq!(intelligence.listener_signals(&installed), (false, false));
+
+        // An app Nginx proxies to is a backend, even from a writable directory
+        intelligence.set_upstream_ports([3333]);
+        assert_eq!(intelligence.listener_signals(&proxy), (false, false));
+
+        let boosted = intelligence.analyze_process(&high_port, 25.0, 60, Utc::now()).await.unwrap();
+        intelligence.set_listener_signals(&ListenerSignalsConfig { enabled: false, ..Default::default() });
+        let plain = intelligence.analyze_process(&high_port, 25.0, 60, Utc::now()).await.unwrap();
+        assert!((boosted - plain - 0.3).abs() < 1e-4, "{} vs {}", boosted, plain);
+    }
+
+    #[tokio::test]
+    async fn connections_to_known_pools_raise_the_score() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db, false).await.unwrap();
+        intelligence.set_pool_connections(&crate::config::Config::default().file_scanning);
+        let process = ProcessInfo {
+            pid: 4242,
+            binary_path: "/usr/local/bin/node-helper".to_string(),
+            remote_addrs: vec!["141.11.94.42:3333".parse().unwrap()],
+            ..Default::default()
+        };
+        let before = intelligence.analyze_process(&process, 5.0, 60, Utc::now()).await.unwrap();
+        assert!(!intelligence.connected_to_pool(&process));
+
+        // Same (possibly shared) address on another port: not the pool
+        intelligence.add_pool_addrs(["141.11.94.42:443".parse().unwrap()]);
+        assert!(!intelligence.connected_to_pool(&process));
+        intelligence.add_pool_addrs(["141.11.94.42:3333".parse().unwrap()]);
+        assert!(intelligence.connected_to_pool(&process));
+        let after = intelligence.analyze_process(&process, 5.0, 60, Utc::now()).await.unwrap();
+        assert!((after - before - 0.5).abs() < 1e-4, "{} vs {}", after, before);
+        assert!(intelligence.signal_categories(&process, 5.0, 60).contains(&SignalCategory::PoolConnection));
+    }
+
+    #[tokio::test]
+    async fn only_records_above_min_confidence() {
+        let db = IntelligenceDB::new_in_memory().await.unwrap();
+        let mut intelligence = BehaviorIntelligence::new(db.clone(), false).await.unwrap();
+        intelligence.set_min_record_confidence(0.5);
+
+        let process = ProcessInfo {
+            pid: 100,
+            binary_path: "/tmp/bursty".to_string(),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        assert!(!intelligence.record_suspicious_process(&process, 40.0, 60, 0.3, now).await.unwrap());
+        assert!(db.get_suspicious_by_binary("/tmp/bursty").await.unwrap().is_none());
+
+        assert!(intelligence.record_suspicious_process(&process, 90.0, 600, 0.8, now).await.unwrap());
+        let restarted = ProcessInfo { pid: 101, ..process.clone() };
+        assert!(intelligence.record_suspicious_process(&restarted, 90.0, 600, 0.8, now).await.unwrap());
+        let stored = db.get_suspicious_by_binary("/tmp/bursty").await.unwrap().unwrap();
+        assert!(stored.restart_detected);
+
+        assert_eq!(intelligence.suspicious_stats(), SuspiciousStats { seen: 3, recorded: 2 });
+    }
+}
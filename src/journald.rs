@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::config::AlertSeverity;
+use crate::event_stream::{DaemonEvent, EventBus, EventKind};
+
+/// systemd-journald's native protocol socket
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Stable MESSAGE_IDs so `journalctl MESSAGE_ID=...` finds one kind of event across restarts
+const DETECTION_MESSAGE_ID: &str = "5c1c7e3f0d8a4b6e9f2a1d4c7b8e6f01";
+const KILL_MESSAGE_ID: &str = "5c1c7e3f0d8a4b6e9f2a1d4c7b8e6f02";
+const SCAN_MESSAGE_ID: &str = "5c1c7e3f0d8a4b6e9f2a1d4c7b8e6f03";
+const MALWARE_MESSAGE_ID: &str = "5c1c7e3f0d8a4b6e9f2a1d4c7b8e6f04";
+const PERSISTENCE_MESSAGE_ID: &str = "5c1c7e3f0d8a4b6e9f2a1d4c7b8e6f05";
+
+/// Writes daemon events to the systemd journal with structured HORA_* fields
+pub struct JournaldNotifier {
+    socket: UnixDatagram,
+}
+
+impl JournaldNotifier {
+    /// None when journald isn't running (not a systemd host, or inside a container)
+    pub fn connect() -> Option<Self> {
+        if !Path::new(JOURNAL_SOCKET).exists() {
+            info!("systemd journal socket not found, journald events disabled");
+            return None;
+        }
+        match Self::connect_to(Path::new(JOURNAL_SOCKET)) {
+            Ok(notifier) => Some(notifier),
+            Err(e) => {
+                warn!("Failed to connect to systemd journal, journald events disabled: {}", e);
+                None
+            }
+        }
+    }
+
+    fn connect_to(socket_path: &Path) -> Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(socket_path)
+            .with_context(|| format!("Failed to connect to {:?}", socket_path))?;
+        Ok(Self { socket })
+    }
+
+    pub fn send(&self, event: &DaemonEvent) -> Result<()> {
+        self.socket.send(&encode_entry(&event_fields(event)))?;
+        Ok(())
+    }
+
+    /// Forward every published event until the bus closes
+    pub async fn forward(self, bus: EventBus) {
+        let mut receiver = bus.subscribe();
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Err(e) = self.send(&event) {
+                        debug!("Failed to write event to journal: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("journald forwarder lagged, {} events dropped", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+/// Journal fields for an event, in the order they are sent
+pub fn event_fields(event: &DaemonEvent) -> Vec<(&'static str, String)> {
+    let (message_id, kind) = match event.kind {
+        EventKind::Detection => (DETECTION_MESSAGE_ID, "detection"),
+        EventKind::Kill => (KILL_MESSAGE_ID, "kill"),
+        EventKind::Scan => (SCAN_MESSAGE_ID, "scan"),
+        EventKind::Malware => (MALWARE_MESSAGE_ID, "malware"),
+        EventKind::Persistence => (PERSISTENCE_MESSAGE_ID, "persistence"),
+    };
+    // syslog levels: crit, warning, info
+    let priority = match event.severity {
+        AlertSeverity::Critical => 2,
+        AlertSeverity::Warning => 4,
+        AlertSeverity::Info => 6,
+    };
+
+    let mut fields = vec![
+        ("MESSAGE", event.message.clone()),
+        ("MESSAGE_ID", message_id.to_string()),
+        ("PRIORITY", priority.to_string()),
+        ("SYSLOG_IDENTIFIER", "hora-police".to_string()),
+        ("HORA_EVENT", kind.to_string()),
+    ];
+    if let Some(pid) = event.pid {
+        fields.push(("HORA_PID", pid.to_string()));
+    }
+    if let Some(ref path) = event.path {
+        fields.push(("HORA_BINARY", path.clone()));
+    }
+    if let Some(confidence) = event.confidence {
+        fields.push(("HORA_CONFIDENCE", format!("{:.2}", confidence)));
+    }
+    if let Some(ref signature) = event.signature {
+        fields.push(("HORA_SIGNATURE", signature.clone()));
+    }
+    fields
+}
+
+/// Serialize fields in the journal native protocol: `KEY=value\n`, or for values
+/// containing a newline `KEY\n` + little-endian u64 length + value + `\n`
+pub fn encode_entry(fields: &[(&str, String)]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    for (key, value) in fields {
+        entry.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_monitor::ProcessInfo;
+
+    #[test]
+    fn encodes_simple_and_multiline_fields() {
+        let entry = encode_entry(&[
+            ("PRIORITY", "2".to_string()),
+            ("MESSAGE", "a\nb".to_string()),
+        ]);
+        let mut expected = b"PRIORITY=2\nMESSAGE\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert_eq!(entry, expected);
+    }
+
+    #[test]
+    fn kill_events_carry_structured_fields() {
+        let process = ProcessInfo { pid: 4242, binary_path: "/tmp/.x/xmrig".to_string(), ..Default::default() };
+        let event = DaemonEvent::new(AlertSeverity::Critical, EventKind::Kill, "Stopped")
+            .with_process(&process)
+            .with_confidence(0.93);
+        let fields = event_fields(&event);
+        let field = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+
+        assert_eq!(field("MESSAGE_ID"), Some(KILL_MESSAGE_ID));
+        assert_eq!(field("PRIORITY"), Some("2"));
+        assert_eq!(field("HORA_PID"), Some("4242"));
+        assert_eq!(field("HORA_BINARY"), Some("/tmp/.x/xmrig"));
+        assert_eq!(field("HORA_CONFIDENCE"), Some("0.93"));
+
+        let scan = event_fields(&DaemonEvent::new(AlertSeverity::Info, EventKind::Scan, "File scan started"));
+        assert!(!scan.iter().any(|(k, _)| k.starts_with("HORA_PID") || *k == "HORA_CONFIDENCE"));
+    }
+
+    #[test]
+    fn sends_datagrams_to_the_journal_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("journal.sock");
+        let journal = UnixDatagram::bind(&socket_path).unwrap();
+
+        let notifier = JournaldNotifier::connect_to(&socket_path).unwrap();
+        notifier.send(&DaemonEvent::new(AlertSeverity::Warning, EventKind::Detection, "CPU abuse")).unwrap();
+
+        let mut buf = [0u8; 1024];
+        let len = journal.recv(&mut buf).unwrap();
+        let entry = String::from_utf8_lossy(&buf[..len]);
+        assert!(entry.starts_with("MESSAGE=CPU abuse\n"));
+        assert!(entry.contains("PRIORITY=4\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::KernelModulesConfig;
+
+/// One /proc/modules entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct KernelModule {
+    pub name: String,
+    pub size: u64,
+    pub taint: String,  // e.g. "OE" for an out-of-tree, unsigned module; empty if clean
+}
+
+/// A module loaded since the previous scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleFinding {
+    pub module: KernelModule,
+    pub reasons: Vec<String>,
+    /// No matching .ko under /lib/modules/<release>: loaded from somewhere else
+    pub unbacked: bool,
+}
+
+/// Snapshots /proc/modules and reports modules that appear after the baseline.
+/// Allowlisted names (legitimate on-demand loads such as netfilter or overlay)
+/// are only skipped when a shipped .ko backs them, so a rootkit can't hide
+/// behind a familiar name.
+pub struct KernelModuleWatcher {
+    proc_modules: PathBuf,
+    modules_dir: PathBuf,
+    allowlist: Vec<String>,
+    baseline: Option<HashMap<String, KernelModule>>,
+}
+
+impl KernelModuleWatcher {
+    pub fn new(config: &KernelModulesConfig) -> Self {
+        let release = fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+        let modules_dir = Path::new("/lib/modules").join(release.trim());
+        Self::with_paths(config, Path::new("/proc/modules"), &modules_dir)
+    }
+
+    fn with_paths(config: &KernelModulesConfig, proc_modules: &Path, modules_dir: &Path) -> Self {
+        Self {
+            proc_modules: proc_modules.to_path_buf(),
+            modules_dir: modules_dir.to_path_buf(),
+            allowlist: config.allowlist.clone(),
+            baseline: None,
+        }
+    }
+
+    fn snapshot(&self) -> Option<HashMap<String, KernelModule>> {
+        let content = match fs::read_to_string(&self.proc_modules) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("⚠️  Could not read {:?}, not tracking kernel modules: {}", self.proc_modules, e);
+                return None;
+            }
+        };
+        Some(parse_proc_modules(&content).into_iter().map(|m| (m.name.clone(), m)).collect())
+    }
+
+    /// Compare against the previous snapshot. The first call only records the baseline.
+    pub fn scan(&mut self) -> Vec<ModuleFinding> {
+        let Some(current) = self.snapshot() else {
+            return Vec::new();
+        };
+        let Some(previous) = self.baseline.replace(current) else {
+            return Vec::new();
+        };
+        let current = self.baseline.as_ref().expect("baseline just set");
+        let loaded = new_modules(&previous, current);
+        if loaded.is_empty() {
+            return Vec::new();
+        }
+
+        let shipped = shipped_modules(&self.modules_dir);
+        loaded.into_iter()
+            .filter_map(|module| self.evaluate(module, &shipped))
+            .collect()
+    }
+
+    fn evaluate(&self, module: &KernelModule, shipped: &HashSet<String>) -> Option<ModuleFinding> {
+        let unbacked = !shipped.contains(&module.name);
+        if !unbacked && self.is_allowlisted(&module.name) {
+            return None;
+        }
+
+        let mut reasons = vec!["loaded after startup".to_string()];
+        if unbacked {
+            reasons.push(format!("no matching .ko under {}", self.modules_dir.display()));
+        }
+        if module.taint.contains('O') {
+            reasons.push("out-of-tree".to_string());
+        }
+        if module.taint.contains('E') {
+            reasons.push("unsigned".to_string());
+        }
+        Some(ModuleFinding { module: module.clone(), reasons, unbacked })
+    }
+
+    /// Exact names, or a prefix when the entry ends in `*` (`nf_*`)
+    fn is_allowlisted(&self, name: &str) -> bool {
+        self.allowlist.iter().any(|entry| match entry.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => normalize_name(entry) == name,
+        })
+    }
+}
+
+/// Parse `name size refcount deps state address [(taint)]` lines
+pub fn parse_proc_modules(content: &str) -> Vec<KernelModule> {
+    content.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let name = fields.next()?.to_string();
+        let size = fields.next()?.parse().ok()?;
+        let taint = fields.nth(4)
+            .and_then(|t| t.strip_prefix('(')?.strip_suffix(')'))
+            .unwrap_or_default()
+            .to_string();
+        Some(KernelModule { name, size, taint })
+    }).collect()
+}
+
+/// Modules in `current` that weren't in `baseline`, sorted by name
+pub fn new_modules<'a>(
+    baseline: &HashMap<String, KernelModule>,
+    current: &'a HashMap<String, KernelModule>,
+) -> Vec<&'a KernelModule> {
+    let mut loaded: Vec<&KernelModule> = current.iter()
+        .filter(|(name, _)| !baseline.contains_key(*name))
+        .map(|(_, module)| module)
+        .collect();
+    loaded.sort_by(|a, b| a.name.cmp(&b.name));
+    loaded
+}
+
+/// Module names the installed kernel ships, from modules.dep (falling back to
+/// walking the directory when it's missing)
+fn shipped_modules(modules_dir: &Path) -> HashSet<String> {
+    if let Ok(dep) = fs::read_to_string(modules_dir.join("modules.dep")) {
+        return dep.lines()
+            .filter_map(|line| line.split(':').next())
+            .filter_map(module_name_from_path)
+            .collect();
+    }
+    let mut names = HashSet::new();
+    let mut dirs = vec![modules_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                dirs.push(path);
+            } else if let Some(name) = path.to_str().and_then(module_name_from_path) {
+                names.insert(name);
+            }
+        }
+    }
+    names
+}
+
+/// `kernel/net/netfilter/nf-nat.ko.zst` -> `nf_nat` (the kernel reports dashes as underscores)
+fn module_name_from_path(path: &str) -> Option<String> {
+    let file = path.rsplit('/').next()?;
+    let stem = [".ko", ".ko.xz", ".ko.zst", ".ko.gz"].iter()
+        .find_map(|ext| file.strip_suffix(ext))?;
+    Some(normalize_name(stem))
+}
+
+fn normalize_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASELINE: &str = "\
+nf_tables 307200 0 - Live 0x0000000000000000
+ext4 1007616 1 - Live 0x0000000000000000
+";
+
+    fn snapshot(content: &str) -> HashMap<String, KernelModule> {
+        parse_proc_modules(content).into_iter().map(|m| (m.name.clone(), m)).collect()
+    }
+
+    #[test]
+    fn diffing_snapshots_reports_only_new_modules() {
+        let later = format!("{}diamorphine 16384 0 - Live 0x0000000000000000 (OE)\nbr_netfilter 32768 0 - Live 0x0000000000000000\n", BASELINE);
+        let (before, after) = (snapshot(BASELINE), snapshot(&later));
+        let loaded = new_modules(&before, &after);
+        let names: Vec<&str> = loaded.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["br_netfilter", "diamorphine"]);
+        assert_eq!(loaded[1].taint, "OE");
+        assert_eq!(loaded[1].size, 16384);
+        assert!(new_modules(&after, &snapshot(BASELINE)).is_empty());
+    }
+
+    #[test]
+    fn allowlist_only_covers_shipped_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let proc_modules = dir.path().join("modules");
+        let modules_dir = dir.path().join("lib");
+        fs::create_dir_all(&modules_dir).unwrap();
+        fs::write(modules_dir.join("modules.dep"),
+                  "kernel/net/bridge/br_netfilter.ko.zst: kernel/net/bridge/bridge.ko.zst\n").unwrap();
+        fs::write(&proc_modules, BASELINE).unwrap();
+
+        let config = KernelModulesConfig { enabled: true, allowlist: vec!["br_*".to_string(), "nf_hide".to_string()] };
+        let mut watcher = KernelModuleWatcher::with_paths(&config, &proc_modules, &modules_dir);
+        assert!(watcher.scan().is_empty());
+
+        fs::write(&proc_modules, format!("{}br_netfilter 32768 0 - Live 0x0\nnf_hide 16384 0 - Live 0x0 (OE)\n", BASELINE)).unwrap();
+        let findings = watcher.scan();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].module.name, "nf_hide");
+        assert!(findings[0].unbacked);
+        assert!(findings[0].reasons.iter().any(|r| r == "unsigned"));
+
+        // Already reported: the next scan compares against the new snapshot
+        assert!(watcher.scan().is_empty());
+    }
+
+    #[test]
+    fn module_names_come_from_ko_paths() {
+        assert_eq!(module_name_from_path("kernel/net/netfilter/nf-nat.ko.zst").as_deref(), Some("nf_nat"));
+        assert_eq!(module_name_from_path("extra/zfs.ko").as_deref(), Some("zfs"));
+        assert_eq!(module_name_from_path("modules.order"), None);
+    }
+}
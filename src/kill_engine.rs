@@ -3,8 +3,10 @@ use chrono::Utc;
 use nix::sys::signal;
 use nix::unistd::Pid;
 use tracing::{warn, info, error};
-use crate::database::{IntelligenceDB, KillAction};
-use crate::process_monitor::ProcessMonitor;
+use crate::database::{IntelligenceDB, KillAction, KillOutcome};
+use crate::denylist::executable_sha256;
+use crate::process_monitor::{ProcessInfo, ProcessMonitor};
+use crate::termination::{is_alive, kill_outcome, terminate, KillSignals, KillTimeouts, TerminationOutcome};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -13,6 +15,8 @@ pub struct KillEngine {
     monitor: Arc<Mutex<ProcessMonitor>>,
     auto_kill: bool,
     threshold: f32,
+    kill_timeouts: KillTimeouts,
+    kill_signals: KillSignals,
 }
 
 impl KillEngine {
@@ -22,13 +26,29 @@ impl KillEngine {
             monitor: Arc::new(Mutex::new(monitor)),
             auto_kill,
             threshold,
+            kill_timeouts: KillTimeouts::default(),
+            kill_signals: KillSignals::default(),
         }
     }
 
+    pub fn set_kill_timeouts(&mut self, timeouts: KillTimeouts) {
+        self.kill_timeouts = timeouts;
+    }
+
+    pub fn set_kill_signals(&mut self, signals: KillSignals) {
+        self.kill_signals = signals;
+    }
+
     pub async fn should_kill(&self, confidence: f32) -> bool {
         self.auto_kill && confidence >= self.threshold
     }
 
+    async fn withdraw_record(&self, id: i64) {
+        if let Err(e) = self.db.delete_kill_action(id).await {
+            warn!("Failed to withdraw kill record {}: {}", id, e);
+        }
+    }
+
     pub async fn kill_process(
         &mut self,
         pid: i32,
@@ -44,32 +64,7 @@ impl KillEngine {
         info!("🔪 Killing process PID={}, binary={}, reason={}, confidence={:.2}", 
               pid, binary_path, reason, confidence);
 
-        // Try graceful termination first (SIGTERM)
-        let pid_obj = Pid::from_raw(pid);
-        match signal::kill(pid_obj, signal::Signal::SIGTERM) {
-            Ok(_) => {
-                info!("✅ Sent SIGTERM to PID {}", pid);
-                
-                // Wait a bit and check if process still exists
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                
-                // Check if process is still alive
-                let still_alive = {
-                    let monitor = self.monitor.lock().await;
-                    monitor.get_process_by_pid(pid).is_some()
-                };
-                if still_alive {
-                    warn!("⚠️  Process {} still alive after SIGTERM, sending SIGKILL", pid);
-                    let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
-                }
-            }
-            Err(e) => {
-                error!("❌ Failed to kill PID {}: {}", pid, e);
-                return Err(anyhow::anyhow!("Failed to kill process: {}", e));
-            }
-        }
-
-        // Record kill action
+        // Record first: a kill that can't be audited is not carried out
         let action = KillAction {
             id: 0,
             pid,
@@ -78,9 +73,39 @@ impl KillEngine {
             reason: reason.to_string(),
             confidence,
             timestamp: Utc::now(),
+            signal_sent: None,
+            outcome: KillOutcome::Pending,
+            exe_hash: executable_sha256(&ProcessInfo { pid, binary_path: binary_path.to_string(), ..Default::default() }),
         };
+        let record_id = self.db.record_kill_action(&action).await
+            .map_err(|e| {
+                error!("🛑 Cannot record kill of PID {} ({}), not killing it", pid, e);
+                e
+            })?;
 
-        self.db.record_kill_action(&action).await?;
+        // Initial signal, then escalation (SIGTERM/SIGKILL by default), verifying the PID is gone after each
+        let signals = self.kill_signals;
+        let result = terminate(pid, signals, self.kill_timeouts).await;
+        if let Some((signal_sent, outcome)) = kill_outcome(&result, signals) {
+            if let Err(e) = self.db.set_kill_outcome(record_id, signal_sent, outcome).await {
+                warn!("Failed to record outcome of kill {}: {}", record_id, e);
+            }
+        }
+        match result {
+            Ok(TerminationOutcome::AlreadyGone) => {
+                info!("PID {} exited before it could be killed", pid);
+                self.withdraw_record(record_id).await;
+                return Ok(false);
+            }
+            Ok(TerminationOutcome::Terminated) => info!("✅ PID {} exited after {}", pid, signals.initial.as_str()),
+            Ok(TerminationOutcome::Killed) => warn!("⚠️  PID {} required {}", pid, signals.last().as_str()),
+            Ok(TerminationOutcome::Stopped) => warn!("⏸️  PID {} stopped (SIGSTOP), left in place for inspection", pid),
+            Err(e) => {
+                // Kept, with its outcome, so the failure shows up in reports
+                error!("❌ Failed to kill PID {}: {}", pid, e);
+                return Err(e);
+            }
+        }
 
         // Check for respawn with improved detection
         tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
@@ -135,43 +160,37 @@ impl KillEngine {
             }
             
             let pid_obj = Pid::from_raw(pid);
-            if signal::kill(pid_obj, signal::Signal::SIGTERM).is_ok() {
+            if signal::kill(pid_obj, self.kill_signals.initial).is_ok() {
                 killed_pids.push(pid);
-                info!("✅ Sent SIGTERM to child process PID {}", pid);
+                info!("✅ Sent {} to child process PID {}", self.kill_signals.initial.as_str(), pid);
             }
         }
         
         // Wait a bit for children to terminate
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        tokio::time::sleep(self.kill_timeouts.sigterm).await;
         
-        // Force kill any remaining children
-        let monitor = self.monitor.lock().await;
-        for pid in &child_pids {
-            if pid == &root_pid {
-                continue;
-            }
-            if monitor.get_process_by_pid(*pid).is_some() {
-                let pid_obj = Pid::from_raw(*pid);
-                let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
-                warn!("⚠️  Force killed child PID {}", pid);
+        // Escalate on any remaining children
+        if let Some(escalation) = self.kill_signals.escalation {
+            for pid in &child_pids {
+                if pid == &root_pid {
+                    continue;
+                }
+                if is_alive(*pid) {
+                    let pid_obj = Pid::from_raw(*pid);
+                    let _ = signal::kill(pid_obj, escalation);
+                    warn!("⚠️  Sent {} to surviving child PID {}", escalation.as_str(), pid);
+                }
             }
         }
-        drop(monitor);
         
         // Now kill the parent
-        let pid_obj = Pid::from_raw(root_pid);
-        if signal::kill(pid_obj, signal::Signal::SIGTERM).is_ok() {
-            killed_pids.push(root_pid);
-            info!("✅ Sent SIGTERM to root process PID {}", root_pid);
-            
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-            
-            // Check if parent is still alive
-            let monitor = self.monitor.lock().await;
-            if monitor.get_process_by_pid(root_pid).is_some() {
-                let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
-                warn!("⚠️  Force killed root process PID {}", root_pid);
+        match terminate(root_pid, self.kill_signals, self.kill_timeouts).await {
+            Ok(TerminationOutcome::AlreadyGone) => {}
+            Ok(_) => {
+                killed_pids.push(root_pid);
+                info!("✅ Root process PID {} terminated", root_pid);
             }
+            Err(e) => warn!("⚠️  Failed to kill root process PID {}: {}", root_pid, e),
         }
         
         Ok(killed_pids)
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::database::AuditDecision;
+use crate::whitelist::WhitelistEntry;
+
+/// Binaries listed per section in the Telegram version; the file gets all of them
+pub const TELEGRAM_MAX_ENTRIES: usize = 15;
+
+/// Every decision the engine made about one binary with one action
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearningEntry {
+    pub binary_path: String,
+    pub action: String,
+    pub occurrences: usize,
+    pub max_confidence: f32,
+    pub signals: BTreeSet<String>,
+    pub reason: String,  // Of the most recent decision
+    pub command_line: String,
+}
+
+/// What the engine would have done over an observation period, for review before
+/// auto_kill is turned on
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearningReport {
+    pub hours: u64,
+    pub generated_at: DateTime<Utc>,
+    pub would_stop: Vec<LearningEntry>,
+    pub would_notify: Vec<LearningEntry>,
+    pub whitelist: Vec<(String, String)>,  // (source, pattern)
+}
+
+impl LearningReport {
+    /// Group `decisions` (newest first, as `get_decisions` returns them) by binary and action.
+    /// Skips are left out: they are the processes the engine already leaves alone.
+    pub fn build(decisions: &[AuditDecision], whitelist: &[WhitelistEntry], hours: u64, now: DateTime<Utc>) -> Self {
+        let mut grouped: BTreeMap<(String, String), LearningEntry> = BTreeMap::new();
+        for decision in decisions.iter().filter(|d| d.action != "Skip") {
+            let entry = grouped.entry((decision.binary_path.clone(), decision.action.clone()))
+                .or_insert_with(|| LearningEntry {
+                    binary_path: decision.binary_path.clone(),
+                    action: decision.action.clone(),
+                    occurrences: 0,
+                    max_confidence: 0.0,
+                    signals: BTreeSet::new(),
+                    reason: decision.reason.clone(),
+                    command_line: decision.command_line.clone(),
+                });
+            entry.occurrences += 1;
+            entry.max_confidence = entry.max_confidence.max(decision.confidence);
+            entry.signals.extend(decision.signals.split(", ").filter(|s| !s.is_empty()).map(String::from));
+        }
+
+        let (mut would_notify, mut would_stop): (Vec<_>, Vec<_>) = grouped.into_values()
+            .partition(|entry| entry.action == "Notify");
+        for entries in [&mut would_stop, &mut would_notify] {
+            entries.sort_by(|a, b| b.max_confidence.total_cmp(&a.max_confidence)
+                .then(b.occurrences.cmp(&a.occurrences)));
+        }
+
+        Self {
+            hours,
+            generated_at: now,
+            would_stop,
+            would_notify,
+            whitelist: whitelist.iter().map(|e| (format!("{:?}", e.source), e.pattern.clone())).collect(),
+        }
+    }
+
+    /// Markdown report; `max_entries` caps each section (for Telegram)
+    pub fn render(&self, max_entries: Option<usize>) -> String {
+        let mut message = format!(
+            "🎓 *Learning report* ({}h to {})\n\n\
+            Nothing was enforced. With `auto_kill` on, this host would have seen:\n\
+            • {} binaries stopped\n\
+            • {} binaries reported only\n\
+            • {} whitelist entries protecting known apps\n",
+            self.hours,
+            self.generated_at.format("%Y-%m-%d %H:%M UTC"),
+            self.would_stop.len(),
+            self.would_notify.len(),
+            self.whitelist.len(),
+        );
+
+        for (title, entries) in [("Would have stopped", &self.would_stop), ("Would have notified", &self.would_notify)] {
+            if entries.is_empty() {
+                continue;
+            }
+            message.push_str(&format!("\n*{}:*\n", title));
+            let shown = max_entries.unwrap_or(entries.len());
+            for entry in entries.iter().take(shown) {
+                let signals = if entry.signals.is_empty() {
+                    "-".to_string()
+                } else {
+                    entry.signals.iter().cloned().collect::<Vec<_>>().join(", ")
+                };
+                message.push_str(&format!(
+                    "• `{}` - {} x{}, confidence up to {:.0}%\n  signals: {}; last reason: {}\n",
+                    entry.binary_path, entry.action, entry.occurrences, entry.max_confidence * 100.0,
+                    signals, entry.reason,
+                ));
+            }
+            if entries.len() > shown {
+                message.push_str(&format!("  … and {} more\n", entries.len() - shown));
+            }
+        }
+
+        message.push_str("\n*Whitelist:*\n");
+        if self.whitelist.is_empty() {
+            message.push_str("• (empty)\n");
+        }
+        let shown = max_entries.unwrap_or(self.whitelist.len());
+        for (source, pattern) in self.whitelist.iter().take(shown) {
+            message.push_str(&format!("• {}: `{}`\n", source, pattern));
+        }
+        if self.whitelist.len() > shown {
+            message.push_str(&format!("  … and {} more\n", self.whitelist.len() - shown));
+        }
+
+        message.push_str("\nReview false positives with `hora-police whitelist-add`, then set `learning_report.enabled = false` to enforce.");
+        message
+    }
+
+    /// Write the full report, replacing the previous period's
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        std::fs::write(path, self.render(None))
+            .with_context(|| format!("Failed to write learning report to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whitelist::WhitelistSource;
+
+    fn decision(binary: &str, action: &str, confidence: f32, signals: &str) -> AuditDecision {
+        AuditDecision {
+            id: 0,
+            pid: 1,
+            uid: 0,
+            binary_path: binary.to_string(),
+            command_line: binary.to_string(),
+            action: action.to_string(),
+            reason: "CPU abuse: 95% for 600 seconds".to_string(),
+            confidence,
+            signals: signals.to_string(),
+            decided_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn groups_decisions_by_binary_and_action() {
+        let decisions = [
+            decision("/tmp/.x/xmrig", "KillTree", 0.97, "CpuAbuse, SuspiciousLocation"),
+            decision("/tmp/.x/xmrig", "KillTree", 0.9, "CpuAbuse, MiningCommand"),
+            decision("/usr/bin/node", "Notify", 0.72, "CpuAbuse"),
+            decision("/opt/app/worker", "KillDirect", 0.8, ""),
+            decision("/usr/sbin/nginx", "Skip", 0.75, "CpuAbuse"),
+        ];
+        let whitelist = [WhitelistEntry {
+            pattern: "^/usr/sbin/nginx$".to_string(),
+            source: WhitelistSource::SystemdUnit,
+            fingerprint: None,
+            origin: None,
+        }];
+        let report = LearningReport::build(&decisions, &whitelist, 24, Utc::now());
+
+        assert_eq!(report.would_stop.iter().map(|e| e.binary_path.as_str()).collect::<Vec<_>>(),
+                   vec!["/tmp/.x/xmrig", "/opt/app/worker"]);
+        let xmrig = &report.would_stop[0];
+        assert_eq!((xmrig.occurrences, xmrig.max_confidence), (2, 0.97));
+        assert_eq!(xmrig.signals.iter().map(String::as_str).collect::<Vec<_>>(),
+                   vec!["CpuAbuse", "MiningCommand", "SuspiciousLocation"]);
+        assert_eq!(report.would_notify.len(), 1);
+
+        let full = report.render(None);
+        assert!(full.contains("• 2 binaries stopped"));
+        assert!(full.contains("`/tmp/.x/xmrig` - KillTree x2, confidence up to 97%"));
+        assert!(full.contains("SystemdUnit: `^/usr/sbin/nginx$`"));
+        assert!(!full.contains("/usr/sbin/nginx` - Skip"));
+
+        let short = report.render(Some(1));
+        assert!(short.contains("… and 1 more"));
+        assert!(!short.contains("/opt/app/worker"));
+    }
+}
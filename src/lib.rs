@@ -1,27 +1,69 @@
 pub mod config;
 pub mod daemon;
 pub mod database;
+pub mod detection_store;
 pub mod kill_engine;
 pub mod process_monitor;
 pub mod cpu_analyzer;
 pub mod cron_watcher;
+pub mod sudoers_watcher;
+pub mod accounts_watcher;
+pub mod kernel_module_watcher;
 pub mod npm_scanner;
 pub mod react_detector;
 pub mod intelligence;
+pub mod scoring;
 pub mod telegram;
+pub mod alert_templates;
 pub mod file_scanner;
+pub mod network_fs;
+pub mod scan_priority;
+pub mod signatures;
 pub mod file_quarantine;
 pub mod file_blocker;
 pub mod environment;
+#[cfg(feature = "exec-events")]
+pub mod exec_monitor;
 pub mod pm2_integration;
 pub mod systemd_integration;
+pub mod docker_integration;
 pub mod nginx_integration;
 pub mod whitelist;
+pub mod denylist;
+pub mod maintenance;
 pub mod deploy_detector;
 pub mod rollback;
 pub mod safe_kill;
 pub mod file_watcher;
 pub mod zombie_reaper;
+pub mod simulate;
+pub mod users;
+pub mod payload_detector;
+pub mod ptrace_detector;
+pub mod spawn_chain;
+pub mod selftest;
+pub mod termination;
+pub mod command;
+pub mod self_integrity;
+pub mod self_metrics;
+pub mod action_delay;
+pub mod nginx_log_watcher;
+pub mod quarantine_crypto;
+pub mod profiling_detector;
+pub mod memory_scanner;
+pub mod miner_config;
+pub mod disk_space;
+pub mod event_stream;
+pub mod journald;
+pub mod syslog;
+pub mod evidence_collector;
+pub mod action_hook;
+pub mod learning_report;
+pub mod doctor;
+pub mod init_config;
+pub mod whitelist_override;
+pub mod suspicious_paths;
+pub mod package_verify;
 
 pub use config::Config;
 pub use daemon::SentinelDaemon;
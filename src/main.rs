@@ -1,9 +1,20 @@
 use anyhow::Result;
 use hora_police::config::Config;
 use hora_police::daemon::SentinelDaemon;
+use hora_police::database::{IntelligenceDB, ProcessWhitelistEntry};
+use hora_police::doctor;
+use hora_police::event_stream;
+use hora_police::file_quarantine::FileQuarantine;
+use hora_police::init_config;
+use hora_police::selftest;
+use hora_police::self_metrics::SelfMetricsHandle;
+use hora_police::signatures;
+use hora_police::simulate;
+use hora_police::suspicious_paths::SuspiciousPaths;
+use hora_police::whitelist::binary_whitelist_entry;
 use std::path::PathBuf;
 use tracing::{error, info};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use sd_notify::NotifyState;
 use tracing::warn;
 
@@ -23,13 +34,82 @@ struct Args {
     #[arg(long)]
     canary: bool,
     
-    /// Start telemetry probe endpoint
+    /// Start telemetry probe endpoint (address from [probe]; also enabled by probe.enabled)
     #[arg(long)]
     probe: bool,
     
     /// Show version information
     #[arg(long, short)]
     version: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Archive old records and compact the database, then exit
+    Maintenance {
+        /// Keep records newer than this many days (defaults to retention_days from config)
+        #[arg(long)]
+        days: Option<u64>,
+
+        /// Skip VACUUM after archiving
+        #[arg(long)]
+        skip_vacuum: bool,
+    },
+    /// Replay recorded process snapshots (JSON) through the detectors in dry-run mode
+    Simulate {
+        /// JSON file containing an array of {timestamp, processes} snapshots
+        file: PathBuf,
+    },
+    /// Verify /proc, database, quarantine dir, Telegram and integrations, then exit
+    Selftest,
+    /// Report likely misconfigurations (conflicting flags, missing paths, inert settings), then exit
+    Doctor,
+    /// Compile builtin and file_scanning.signature_files signatures, report invalid regexes or hashes
+    ValidateSignatures,
+    /// Abort a delayed systemd/pm2/container stop that is still inside its action_delay_seconds window
+    CancelAction {
+        /// PID of the targeted process
+        pid: i32,
+    },
+    /// Stream detections, kills and scan events from the running daemon
+    Watch {
+        /// Print raw JSON lines instead of formatted output
+        #[arg(long)]
+        json: bool,
+    },
+    /// Restore a quarantined file to its original location and whitelist it
+    RestoreQuarantine {
+        /// malware_files record id or original file path
+        target: String,
+    },
+    /// Review what audit_only mode would have done
+    Decisions {
+        /// How far back to look
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+
+        /// Maximum number of decisions listed
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Whitelist the binaries of reviewed kill actions (applied when the daemon next starts)
+    WhitelistAdd {
+        /// Comma-separated kill_actions ids, e.g. 12,15,31
+        #[arg(long, value_delimiter = ',', required = true)]
+        from_kills: Vec<i64>,
+    },
+    /// Write a commented config.toml with every default, then exit (runs without a config)
+    InitConfig {
+        /// Where to write it (defaults to --config)
+        path: Option<PathBuf>,
+
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[tokio::main]
@@ -47,6 +127,13 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(Command::InitConfig { ref path, force }) = args.command {
+        let path = path.as_ref().unwrap_or(&args.config);
+        init_config::write_default_config(path, force)?;
+        println!("✅ Wrote default configuration to {:?}", path);
+        return Ok(());
+    }
+
     info!("🚀 Hora-Police Anti-Malware Daemon starting...");
 
     // Load configuration
@@ -63,16 +150,48 @@ async fn main() -> Result<()> {
     }
     
     info!("✅ Configuration loaded from: {:?}", args.config);
+    SuspiciousPaths::from_config(&config)?.install();
 
-    // Start probe endpoint if requested
-    if args.probe {
-        tokio::spawn(async move {
-            start_probe_endpoint().await;
-        });
+    if let Some(command) = args.command {
+        return match command {
+            Command::Maintenance { days, skip_vacuum } => {
+                run_maintenance(&config, days.unwrap_or(config.retention_days), skip_vacuum).await
+            }
+            Command::Simulate { file } => run_simulate(&config, &file).await,
+            Command::RestoreQuarantine { target } => run_restore_quarantine(&config, &target).await,
+            Command::WhitelistAdd { from_kills } => run_whitelist_add(&config, &from_kills).await,
+            Command::Decisions { hours, limit } => run_decisions(&config, hours, limit).await,
+            Command::Selftest => run_selftest(&config).await,
+            Command::Doctor => run_doctor(&config).await,
+            Command::ValidateSignatures => run_validate_signatures(&config),
+            Command::CancelAction { pid } => run_cancel_action(&config, pid),
+            Command::InitConfig { .. } => unreachable!("handled before the config is loaded"),
+            Command::Watch { json } => {
+                event_stream::watch(std::path::Path::new(&config.event_socket_path), json).await
+            }
+        };
     }
 
+    let probe_addr = if args.probe || config.probe.enabled {
+        Some(config.probe.socket_addr()?)
+    } else {
+        None
+    };
+
     // Initialize and run daemon
     let mut daemon = SentinelDaemon::new(config).await?;
+    daemon.enable_self_integrity(&args.config);
+
+    // Start probe endpoint if requested; the daemon runs without it if the address is taken
+    if let Some(addr) = probe_addr {
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => {
+                info!("📊 Telemetry probe endpoint started on http://{}", addr);
+                tokio::spawn(serve_probe_endpoint(listener, daemon.self_metrics()));
+            }
+            Err(e) => warn!("⚠️  Could not bind probe endpoint on {}: {} - continuing without it", addr, e),
+        }
+    }
     
     info!("🛡️  Hora-Police daemon initialized. Starting monitoring...");
     
@@ -91,30 +210,181 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn start_probe_endpoint() {
-    use tokio::net::TcpListener;
-    use std::io::Write;
-    
-    let addr = "127.0.0.1:9999";
-    let listener = match TcpListener::bind(addr).await {
-        Ok(l) => l,
-        Err(e) => {
-            error!("Failed to bind probe endpoint: {}", e);
-            return;
+async fn run_maintenance(config: &Config, days: u64, skip_vacuum: bool) -> Result<()> {
+    let db = IntelligenceDB::new(&config.database_path).await?;
+
+    if config.raw_history_hours > 0 {
+        let rollup = db.rollup_process_history(config.raw_history_hours).await?;
+        info!("📊 Rolled up {} raw process samples older than {}h into {} hourly summaries",
+              rollup.raw_pruned, config.raw_history_hours, rollup.summaries);
+    }
+
+    let stats = db.archive_old_records(days).await?;
+    info!(
+        "🗄️  Archived records older than {} days: {} process history, {} hourly summaries, {} suspicious processes, {} cron snapshots, {} audit decisions",
+        days, stats.process_history, stats.hourly_summaries, stats.suspicious_processes, stats.cron_snapshots, stats.decisions
+    );
+
+    if !skip_vacuum {
+        db.vacuum_database().await?;
+        info!("✅ Database vacuumed");
+    }
+
+    Ok(())
+}
+
+async fn run_simulate(config: &Config, file: &std::path::Path) -> Result<()> {
+    let snapshots = simulate::load_snapshots(file)?;
+    info!("🧪 Replaying {} snapshots from {:?} (dry-run)", snapshots.len(), file);
+
+    let decisions = simulate::run_simulation(config, &snapshots).await?;
+    for decision in &decisions {
+        println!("{}", serde_json::to_string(decision)?);
+    }
+
+    info!("✅ Simulation finished: {} decisions", decisions.len());
+    Ok(())
+}
+
+async fn run_restore_quarantine(config: &Config, target: &str) -> Result<()> {
+    let db = IntelligenceDB::new(&config.database_path).await?;
+
+    let record = db.find_quarantined_file(target).await?
+        .ok_or_else(|| anyhow::anyhow!("No quarantined file found for '{}'", target))?;
+    let quarantine_path = record.quarantine_path.clone().unwrap_or_default();
+
+    // Whitelist first: a scan running between the two steps would re-quarantine the file
+    db.add_file_whitelist(&record.file_path, &record.file_hash).await?;
+    FileQuarantine::restore_file(
+        std::path::Path::new(&quarantine_path),
+        std::path::Path::new(&record.file_path),
+    )?;
+    db.mark_malware_restored(record.id).await?;
+
+    info!("✅ Restored {} (record {}) and added it to the file whitelist", record.file_path, record.id);
+    Ok(())
+}
+
+async fn run_whitelist_add(config: &Config, kill_ids: &[i64]) -> Result<()> {
+    let db = IntelligenceDB::new(&config.database_path).await?;
+
+    let mut missing = Vec::new();
+    for &id in kill_ids {
+        let Some(action) = db.get_kill_action(id).await? else {
+            println!("❌ Kill action {} not found", id);
+            missing.push(id);
+            continue;
+        };
+
+        let (pattern, fingerprint) = binary_whitelist_entry(&action.binary_path);
+        db.add_process_whitelist(&ProcessWhitelistEntry {
+            pattern: pattern.clone(),
+            fingerprint: fingerprint.clone(),
+            note: format!("from kill action {} ({})", id, action.reason),
+            added_at: chrono::Utc::now(),
+        }).await?;
+
+        match fingerprint {
+            Some(fingerprint) => println!("✅ Kill action {}: whitelisted {} (pattern {}, sha256 {})",
+                                          id, action.binary_path, pattern, fingerprint),
+            None => println!("✅ Kill action {}: whitelisted {} (pattern {}; binary is gone, no fingerprint)",
+                             id, action.binary_path, pattern),
         }
-    };
+    }
+
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("{} of {} kill action(s) not found", missing.len(), kill_ids.len()));
+    }
+    println!("Restart the daemon to apply the new whitelist entries");
+    Ok(())
+}
+
+async fn run_decisions(config: &Config, hours: i64, limit: i64) -> Result<()> {
+    let db = IntelligenceDB::new(&config.database_path).await?;
+    let decisions = db.get_decisions(chrono::Utc::now() - chrono::Duration::hours(hours), limit).await?;
+    if decisions.is_empty() {
+        println!("No audit decisions in the last {}h", hours);
+        return Ok(());
+    }
+
+    for d in &decisions {
+        println!("{}  {:<12} PID {:<7} {:.0}%  {}  ({})",
+                 d.decided_at.format("%Y-%m-%d %H:%M:%S"), d.action, d.pid, d.confidence * 100.0, d.binary_path, d.reason);
+    }
+
+    let mut by_action: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for d in &decisions {
+        *by_action.entry(d.action.as_str()).or_default() += 1;
+    }
+    let summary: Vec<String> = by_action.iter().map(|(action, n)| format!("{} {}", n, action)).collect();
+    println!("\n{} decision(s) in the last {}h: {}", decisions.len(), hours, summary.join(", "));
+    Ok(())
+}
+
+fn run_cancel_action(config: &Config, pid: i32) -> Result<()> {
+    // The running daemon picks the request up on its next polling cycle
+    let dir = PathBuf::from(&config.action_cancel_dir);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(pid.to_string()), "")?;
+    println!("Cancel requested for PID {} (applied on the daemon's next cycle)", pid);
+    Ok(())
+}
+
+fn run_validate_signatures(config: &Config) -> Result<()> {
+    let (signatures, errors) = signatures::load_signatures(&config.file_scanning.signature_files);
+    for error in &errors {
+        println!("❌ {}", error);
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("{} signature problem(s) found ({} signatures valid)", errors.len(), signatures.len()));
+    }
+
+    println!("✅ {} signatures valid", signatures.len());
+    Ok(())
+}
+
+async fn run_selftest(config: &Config) -> Result<()> {
+    let results = selftest::run_selftest(config).await;
+    print!("{}", selftest::format_report(&results));
+
+    let failures = results.iter()
+        .filter(|r| r.status == selftest::CheckStatus::Fail)
+        .count();
+    if failures > 0 {
+        return Err(anyhow::anyhow!("{} self-test check(s) failed", failures));
+    }
+
+    info!("✅ Self-test passed");
+    Ok(())
+}
+
+async fn run_doctor(config: &Config) -> Result<()> {
+    let findings = doctor::run_doctor(config).await;
+    print!("{}", doctor::format_report(&findings));
+
+    let errors = findings.iter()
+        .filter(|f| f.severity == doctor::Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(anyhow::anyhow!("{} configuration error(s) found", errors));
+    }
+    Ok(())
+}
 
-    info!("📊 Telemetry probe endpoint started on http://{}", addr);
+async fn serve_probe_endpoint(listener: tokio::net::TcpListener, self_metrics: SelfMetricsHandle) {
+    use tokio::io::AsyncWriteExt;
 
     loop {
         match listener.accept().await {
             Ok((mut stream, _)) => {
+                let self_metrics = self_metrics.clone();
                 tokio::spawn(async move {
                     // Simple HTTP response
                     let summary = serde_json::json!({
                         "status": "running",
                         "timestamp": chrono::Utc::now().to_rfc3339(),
                         "version": "0.1.0",
+                        "self": self_metrics.snapshot(),
                     });
 
                     let json = serde_json::to_string_pretty(&summary).unwrap();
@@ -0,0 +1,251 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::config::MaintenanceWindowConfig;
+
+/// Longest cron-started window; keeps the backwards minute scan bounded
+const MAX_CRON_WINDOW_MINUTES: u64 = 24 * 60;
+
+/// Set of allowed values for one cron field, indexed by value
+#[derive(Debug, Clone, PartialEq)]
+struct CronField {
+    allowed: Vec<bool>,
+    any: bool,  // Written as "*" (matters for the day-of-month/day-of-week OR rule)
+}
+
+impl CronField {
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self> {
+        let mut allowed = vec![false; max as usize + 1];
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)
+                    .with_context(|| format!("Invalid step in {:?}", part))?),
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((a, b)) = range.split_once('-') {
+                (parse_value(a, min, max)?, parse_value(b, min, max)?)
+            } else {
+                let value = parse_value(range, min, max)?;
+                // "5/15" means 5, 20, 35, ...
+                (value, if part.contains('/') { max } else { value })
+            };
+            if start > end {
+                bail!("Invalid range {:?}", part);
+            }
+            for value in (start..=end).step_by(step as usize) {
+                allowed[value as usize] = true;
+            }
+        }
+        Ok(Self { allowed, any: field == "*" })
+    }
+
+    fn matches(&self, value: u32) -> bool {
+        self.allowed.get(value as usize).copied().unwrap_or(false)
+    }
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        _ => bail!("{:?} is not between {} and {}", value, min, max),
+    }
+}
+
+/// Five-field cron expression: minute hour day-of-month month day-of-week
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minute: CronField,
+    hour: CronField,
+    day_of_month: CronField,
+    month: CronField,
+    day_of_week: CronField,  // 0 and 7 are both Sunday
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields.as_slice() else {
+            bail!("Cron schedule {:?} needs 5 fields, got {}", expr, fields.len());
+        };
+        let mut day_of_week = CronField::parse(dow, 0, 7).context("day of week")?;
+        if day_of_week.allowed[7] {
+            day_of_week.allowed[0] = true;
+        }
+        Ok(Self {
+            minute: CronField::parse(minute, 0, 59).context("minute")?,
+            hour: CronField::parse(hour, 0, 23).context("hour")?,
+            day_of_month: CronField::parse(dom, 1, 31).context("day of month")?,
+            month: CronField::parse(month, 1, 12).context("month")?,
+            day_of_week,
+        })
+    }
+
+    /// Whether a run starts in the minute of `time`
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        let dom = self.day_of_month.matches(time.day());
+        let dow = self.day_of_week.matches(time.weekday().num_days_from_sunday());
+        // As in cron: when both day fields are restricted, either one matching is enough
+        let day = if self.day_of_month.any || self.day_of_week.any { dom && dow } else { dom || dow };
+        day && self.minute.matches(time.minute())
+            && self.hour.matches(time.hour())
+            && self.month.matches(time.month())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WindowKind {
+    /// Starts whenever the cron schedule fires and lasts `minutes`
+    Cron { schedule: CronSchedule, minutes: u64 },
+    /// Daily time range [start, end), wrapping past midnight when end <= start
+    Daily { start: NaiveTime, end: NaiveTime },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    kind: WindowKind,
+}
+
+impl MaintenanceWindow {
+    pub fn from_config(config: &MaintenanceWindowConfig) -> Result<Self> {
+        let kind = match (&config.cron, &config.start, &config.end) {
+            (Some(cron), None, None) => {
+                if config.duration_minutes == 0 || config.duration_minutes > MAX_CRON_WINDOW_MINUTES {
+                    bail!("duration_minutes must be between 1 and {}", MAX_CRON_WINDOW_MINUTES);
+                }
+                WindowKind::Cron { schedule: CronSchedule::parse(cron)?, minutes: config.duration_minutes }
+            }
+            (None, Some(start), Some(end)) => WindowKind::Daily { start: parse_time(start)?, end: parse_time(end)? },
+            _ => bail!("set either cron (with duration_minutes) or both start and end"),
+        };
+        Ok(Self { name: config.name.clone(), kind })
+    }
+
+    /// Whether `now` falls inside the window; the start is inclusive, the end exclusive
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        match &self.kind {
+            WindowKind::Cron { schedule, minutes } => {
+                let now = now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+                (0..*minutes as i64).any(|back| schedule.matches(now - Duration::minutes(back)))
+            }
+            WindowKind::Daily { start, end } => {
+                let time = now.time();
+                if start < end {
+                    *start <= time && time < *end
+                } else {
+                    *start <= time || time < *end
+                }
+            }
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .with_context(|| format!("Invalid time {:?} (expected HH:MM)", value))
+}
+
+/// Times when heavy CPU is expected (backups, log rotation, batch jobs) and
+/// stopping actions are downgraded to notifications
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceWindows {
+    windows: Vec<MaintenanceWindow>,
+}
+
+impl MaintenanceWindows {
+    pub fn from_config(configs: &[MaintenanceWindowConfig]) -> Result<Self> {
+        let windows = configs.iter()
+            .map(|c| MaintenanceWindow::from_config(c)
+                .with_context(|| format!("Invalid maintenance window {:?}", c.name)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { windows })
+    }
+
+    /// Name of the window `now` (local time) falls in, if any
+    pub fn active(&self, now: NaiveDateTime) -> Option<&str> {
+        self.windows.iter().find(|w| w.contains(now)).map(|w| w.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn window(cron: Option<&str>, duration_minutes: u64, range: Option<(&str, &str)>) -> MaintenanceWindow {
+        MaintenanceWindow::from_config(&MaintenanceWindowConfig {
+            name: "test".to_string(),
+            cron: cron.map(String::from),
+            duration_minutes,
+            start: range.map(|r| r.0.to_string()),
+            end: range.map(|r| r.1.to_string()),
+        }).unwrap()
+    }
+
+    #[test]
+    fn cron_window_includes_start_and_excludes_end() {
+        // Nightly backup at 02:30 for 90 minutes
+        let backup = window(Some("30 2 * * *"), 90, None);
+        assert!(!backup.contains(at("2026-03-10 02:29:59")));
+        assert!(backup.contains(at("2026-03-10 02:30:00")));
+        assert!(backup.contains(at("2026-03-10 03:59:59")));
+        assert!(!backup.contains(at("2026-03-10 04:00:00")));
+
+        // Sunday 23:00 for two hours runs into Monday
+        let weekly = window(Some("0 23 * * 0"), 120, None);
+        assert!(weekly.contains(at("2026-03-15 23:00:00")));
+        assert!(weekly.contains(at("2026-03-16 00:59:00")));
+        assert!(!weekly.contains(at("2026-03-16 01:00:00")));
+        assert!(!weekly.contains(at("2026-03-14 23:30:00")));
+    }
+
+    #[test]
+    fn daily_range_wraps_midnight() {
+        let night = window(None, 0, Some(("23:00", "01:30")));
+        assert!(night.contains(at("2026-03-10 23:00:00")));
+        assert!(night.contains(at("2026-03-11 01:29:59")));
+        assert!(!night.contains(at("2026-03-11 01:30:00")));
+        assert!(!night.contains(at("2026-03-10 22:59:59")));
+
+        let day = window(None, 0, Some(("04:00", "05:00")));
+        assert!(day.contains(at("2026-03-10 04:00:00")));
+        assert!(!day.contains(at("2026-03-10 05:00:00")));
+    }
+
+    #[test]
+    fn parses_cron_fields() {
+        let schedule = CronSchedule::parse("*/15 1-3 * * 1-5").unwrap();
+        assert!(schedule.matches(at("2026-03-10 01:45:00")));  // Tuesday
+        assert!(!schedule.matches(at("2026-03-10 01:50:00")));
+        assert!(!schedule.matches(at("2026-03-14 01:45:00")));  // Saturday
+
+        // Restricted day-of-month and day-of-week: either matches
+        let either = CronSchedule::parse("0 0 1 * 7").unwrap();
+        assert!(either.matches(at("2026-03-01 00:00:00")));
+        assert!(either.matches(at("2026-03-15 00:00:00")));  // Sunday
+        assert!(!either.matches(at("2026-03-10 00:00:00")));
+
+        assert!(CronSchedule::parse("0 2 * *").is_err());
+        assert!(CronSchedule::parse("60 2 * * *").is_err());
+        assert!(CronSchedule::parse("*/0 2 * * *").is_err());
+    }
+
+    #[test]
+    fn rejects_ambiguous_windows() {
+        let config = |cron: Option<&str>, start: Option<&str>| MaintenanceWindowConfig {
+            name: "bad".to_string(),
+            cron: cron.map(String::from),
+            duration_minutes: 60,
+            start: start.map(String::from),
+            end: start.map(String::from),
+        };
+        assert!(MaintenanceWindows::from_config(&[config(Some("0 2 * * *"), Some("02:00"))]).is_err());
+        assert!(MaintenanceWindows::from_config(&[config(None, None)]).is_err());
+        assert!(MaintenanceWindows::from_config(&[config(None, Some("25:00"))]).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use regex::bytes::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use tracing::{debug, info, warn};
+
+use crate::config::MemoryScanConfig;
+use crate::process_monitor::ProcessInfo;
+
+/// Bytes read from /proc/<pid>/mem per call
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// A writable private mapping worth searching: anonymous memory, the heap, or a memfd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemRegion {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// A mining token found in a process's memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMatch {
+    pub token: String,
+    pub address: u64,
+}
+
+/// Searches the anonymous memory of fileless processes for mining protocol strings
+/// (stratum URLs, JSON-RPC method names). Reading another process's memory needs
+/// root, so this is opt-in, and every process is read at most once, up to a budget.
+pub struct MemoryScanner {
+    config: MemoryScanConfig,
+    pattern: Option<Regex>,
+    longest_token: usize,
+    scanned: HashMap<(i32, u64), Option<MemoryMatch>>,  // (pid, start_time) -> result
+}
+
+impl MemoryScanner {
+    pub fn new(config: MemoryScanConfig) -> Self {
+        let tokens: Vec<&String> = config.tokens.iter().filter(|t| !t.is_empty()).collect();
+        let pattern = (!tokens.is_empty()).then(|| {
+            let alternation = tokens.iter().map(|t| regex::escape(t)).collect::<Vec<_>>().join("|");
+            RegexBuilder::new(&alternation)
+                .case_insensitive(true)
+                .unicode(false)
+                .build()
+                .expect("escaped tokens form a valid pattern")
+        });
+        let longest_token = tokens.iter().map(|t| t.len()).max().unwrap_or(0);
+        Self {
+            config,
+            pattern,
+            longest_token,
+            scanned: HashMap::new(),
+        }
+    }
+
+    /// Whether `process` is one the scanner looks at under the configured scope
+    pub fn is_candidate(&self, process: &ProcessInfo) -> bool {
+        !self.config.only_fileless || is_fileless(process)
+    }
+
+    /// The first token in `process`'s memory, reading it on the first call only
+    pub fn scan_process(&mut self, process: &ProcessInfo) -> Option<MemoryMatch> {
+        if !self.is_candidate(process) {
+            return None;
+        }
+        let key = (process.pid, process.start_time);
+        if let Some(result) = self.scanned.get(&key) {
+            return result.clone();
+        }
+        let result = self.scan_pid(process.pid);
+        if let Some(ref hit) = result {
+            info!("🧠 PID {} ({}) has mining token {:?} in memory at {:#x}",
+                  process.pid, process.binary_path, hit.token, hit.address);
+        }
+        self.scanned.insert(key, result.clone());
+        result
+    }
+
+    /// Forget results of processes that exited
+    pub fn retain_live(&mut self, processes: &[ProcessInfo]) {
+        self.scanned.retain(|(pid, start), _| {
+            processes.iter().any(|p| p.pid == *pid && p.start_time == *start)
+        });
+    }
+
+    fn scan_pid(&self, pid: i32) -> Option<MemoryMatch> {
+        let pattern = self.pattern.as_ref()?;
+        let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid)).ok()?;
+        let mem = match File::open(format!("/proc/{}/mem", pid)) {
+            Ok(file) => file,
+            Err(e) => {
+                debug!("Can't open memory of PID {}: {}", pid, e);
+                return None;
+            }
+        };
+
+        let mut budget = self.config.max_read_mb.saturating_mul(1024 * 1024);
+        let region_limit = self.config.max_region_mb.saturating_mul(1024 * 1024);
+        for region in parse_scan_regions(&maps) {
+            if budget == 0 {
+                debug!("Memory scan budget of PID {} exhausted", pid);
+                break;
+            }
+            let len = (region.end - region.start).min(region_limit).min(budget);
+            budget -= len;
+            match search_region(&mem, region.start, len, pattern, self.longest_token) {
+                Ok(Some(hit)) => return Some(hit),
+                Ok(None) => {}
+                Err(e) => match e.raw_os_error() {
+                    // Gone, or we lost the right to look
+                    Some(libc::ESRCH) | Some(libc::EPERM) | Some(libc::EACCES) => {
+                        debug!("Stopped memory scan of PID {}: {}", pid, e);
+                        return None;
+                    }
+                    // Unbacked or guard pages: skip the rest of the region
+                    _ => debug!("Skipping region {:#x} of PID {}: {}", region.start, pid, e),
+                },
+            }
+        }
+        None
+    }
+}
+
+/// Read `len` bytes at `start` in chunks, overlapping by a token length so matches
+/// across chunk boundaries aren't missed
+fn search_region(mem: &File, start: u64, len: u64, pattern: &Regex, longest_token: usize) -> std::io::Result<Option<MemoryMatch>> {
+    let overlap = longest_token.saturating_sub(1);
+    let mut buf = vec![0u8; CHUNK_BYTES + overlap];
+    let mut carried = 0;
+    let mut offset = 0u64;
+    while offset < len {
+        let want = (CHUNK_BYTES as u64).min(len - offset) as usize;
+        let read = mem.read_at(&mut buf[carried..carried + want], start + offset)?;
+        if read == 0 {
+            break;
+        }
+        let filled = carried + read;
+        if let Some(m) = pattern.find(&buf[..filled]) {
+            let address = start + offset + m.start() as u64 - carried as u64;
+            return Ok(Some(MemoryMatch {
+                token: String::from_utf8_lossy(m.as_bytes()).to_string(),
+                address,
+            }));
+        }
+        carried = overlap.min(filled);
+        buf.copy_within(filled - carried..filled, 0);
+        offset += read as u64;
+    }
+    Ok(None)
+}
+
+/// Writable private regions from /proc/<pid>/maps that hold data a fileless
+/// payload builds at runtime: anonymous mappings, the heap and memfd mappings
+pub fn parse_scan_regions(maps: &str) -> Vec<MemRegion> {
+    maps.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let range = fields.next()?;
+            let perms = fields.next()?;
+            let path = fields.nth(3).unwrap_or("");
+            if !perms.starts_with("rw") || perms.as_bytes().get(3) != Some(&b'p') {
+                return None;
+            }
+            if !(path.is_empty() || path == "[heap]" || path.starts_with("/memfd:")) {
+                return None;
+            }
+            let (start, end) = range.split_once('-')?;
+            let start = u64::from_str_radix(start, 16).ok()?;
+            let end = u64::from_str_radix(end, 16).ok()?;
+            (end > start).then_some(MemRegion { start, end })
+        })
+        .collect()
+}
+
+/// No executable on disk to scan: memfd, deleted, or unreadable
+pub fn is_fileless(process: &ProcessInfo) -> bool {
+    process.exe_is_memfd || !process.exe_resolves || process.binary_path.ends_with(" (deleted)")
+}
+
+/// Memory scanning reads other processes' memory and needs root
+pub fn can_scan_memory() -> bool {
+    let root = unsafe { libc::geteuid() } == 0;
+    if !root {
+        warn!("memory_scan needs root to read /proc/<pid>/mem - disabled");
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_writable_anonymous_heap_and_memfd_regions() {
+        let maps = "\
+55d0c0a00000-55d0c0a21000 rw-p 00000000 00:00 0                          [heap]
+7f1c2a000000-7f1c2a400000 rw-p 00000000 00:00 0
+7f1c2b000000-7f1c2b100000 rwxp 00000000 00:01 1044                       /memfd:x (deleted)
+7f1c2c000000-7f1c2c021000 r--p 00000000 00:00 0
+7f1c2d000000-7f1c2d021000 rw-s 00000000 00:05 12                         /dev/zero (deleted)
+7f1c2e000000-7f1c2e021000 rw-p 0001c000 fd:01 3412                       /usr/lib/x86_64-linux-gnu/libc.so.6
+7ffd1a2b0000-7ffd1a2d1000 rw-p 00000000 00:00 0                          [stack]
+";
+        assert_eq!(parse_scan_regions(maps), vec![
+            MemRegion { start: 0x55d0c0a00000, end: 0x55d0c0a21000 },
+            MemRegion { start: 0x7f1c2a000000, end: 0x7f1c2a400000 },
+            MemRegion { start: 0x7f1c2b000000, end: 0x7f1c2b100000 },
+        ]);
+    }
+
+    #[test]
+    fn finds_tokens_across_chunk_boundaries() {
+        let scanner = MemoryScanner::new(MemoryScanConfig::default());
+        let pattern = scanner.pattern.as_ref().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mem");
+        let mut data = vec![0u8; CHUNK_BYTES * 2];
+        let at = CHUNK_BYTES - 5;
+        data[at..at + 14].copy_from_slice(b"STRATUM+TCP://");
+        std::fs::write(&path, &data).unwrap();
+
+        let file = File::open(&path).unwrap();
+        let hit = search_region(&file, 0, data.len() as u64, pattern, scanner.longest_token).unwrap().unwrap();
+        assert_eq!((hit.token.as_str(), hit.address), ("STRATUM+TCP://", at as u64));
+        // Outside the bound: not read
+        assert_eq!(search_region(&file, 0, at as u64, pattern, scanner.longest_token).unwrap(), None);
+    }
+
+    #[test]
+    fn skips_processes_outside_scope_and_gone() {
+        let mut scanner = MemoryScanner::new(MemoryScanConfig { enabled: true, ..Default::default() });
+        let on_disk = ProcessInfo { pid: i32::MAX, exe_resolves: true, binary_path: "/usr/bin/node".to_string(), ..Default::default() };
+        assert!(!scanner.is_candidate(&on_disk));
+        let fileless = ProcessInfo { exe_is_memfd: true, ..on_disk.clone() };
+        assert!(scanner.is_candidate(&fileless));
+        assert_eq!(scanner.scan_process(&fileless), None);
+    }
+}
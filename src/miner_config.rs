@@ -0,0 +1,350 @@
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::file_scanner::MalwareSignature;
+
+/// Config files xmrig and its forks pick up from their own directory
+const CONFIG_NAMES: &[&str] = &["config.json", ".xmrig", ".xmrig.json", "xmrig.json", "config.ini", "config.txt"];
+
+/// Miner configs are a few KB; anything much larger isn't one
+const MAX_CONFIG_BYTES: u64 = 256 * 1024;
+
+/// Keys holding the pool (`o` is the short form of xmrig's `--url`)
+const POOL_KEYS: &[&str] = &["url", "pool", "pool_address", "o"];
+
+/// Keys holding the login, which is the wallet address for most pools
+const WALLET_KEYS: &[&str] = &["user", "wallet", "address", "wallet_address", "u"];
+
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A mining pool from a miner's config
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PoolEndpoint {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for PoolEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) if self.host.contains(':') => write!(f, "[{}]:{}", self.host, port),
+            Some(port) => write!(f, "{}:{}", self.host, port),
+            None => f.write_str(&self.host),
+        }
+    }
+}
+
+/// Pools and wallets found in one miner config file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MinerIocs {
+    pub config_path: PathBuf,
+    pub pools: Vec<PoolEndpoint>,
+    pub wallets: Vec<String>,
+}
+
+impl MinerIocs {
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty() && self.wallets.is_empty()
+    }
+
+    fn add_pool(&mut self, value: &str) {
+        if !looks_like_pool_url(value) {
+            return;
+        }
+        if let Some(pool) = parse_pool_url(value) {
+            if !self.pools.contains(&pool) {
+                self.pools.push(pool);
+            }
+        }
+    }
+
+    fn add_wallet(&mut self, value: &str) {
+        if let Some(wallet) = wallet_from_login(value) {
+            if !self.wallets.contains(&wallet) {
+                self.wallets.push(wallet);
+            }
+        }
+    }
+}
+
+/// Alert lines for the IOCs found next to a malware file, empty if there are none
+pub fn format_iocs(iocs: &[MinerIocs]) -> String {
+    let mut out = String::new();
+    for found in iocs {
+        out.push_str(&format!("\n\n⛏️ Miner config {}:", found.config_path.display()));
+        for pool in &found.pools {
+            out.push_str(&format!("\n- Pool: {}", pool));
+        }
+        for wallet in &found.wallets {
+            out.push_str(&format!("\n- Wallet: {}", wallet));
+        }
+    }
+    out
+}
+
+/// Signatures for miners, whose configs are worth looking for
+pub fn is_miner_signature(signature: &MalwareSignature) -> bool {
+    let text = format!("{} {}", signature.name, signature.description).to_lowercase();
+    ["miner", "mining", "xmrig"].iter().any(|word| text.contains(word))
+}
+
+/// Pool and wallet IOCs from the config files next to `malware_path`
+pub fn find_miner_iocs(malware_path: &Path) -> Vec<MinerIocs> {
+    let Some(dir) = malware_path.parent() else {
+        return Vec::new();
+    };
+    CONFIG_NAMES.iter()
+        .map(|name| dir.join(name))
+        .filter_map(|path| {
+            // Regular files only: don't follow a planted link somewhere else
+            let meta = std::fs::symlink_metadata(&path).ok()?;
+            if !meta.is_file() || meta.len() > MAX_CONFIG_BYTES {
+                return None;
+            }
+            let content = std::fs::read_to_string(&path).ok()?;
+            let iocs = parse_miner_config(&content);
+            if iocs.is_empty() {
+                debug!("No pool or wallet in {:?}", path);
+                return None;
+            }
+            Some(MinerIocs { config_path: path, ..iocs })
+        })
+        .collect()
+}
+
+/// Pools and wallets in a miner config: xmrig-style JSON (`pools[].url`/`user`),
+/// falling back to `key = value` lines. Other JSON (package manifests, app
+/// configs that happen to sit next to a detection) yields nothing.
+pub fn parse_miner_config(content: &str) -> MinerIocs {
+    let mut iocs = MinerIocs::default();
+    match serde_json::from_str::<Value>(content) {
+        Ok(json) => collect_json(&json, &mut iocs),
+        Err(_) => {
+            for line in content.lines() {
+                let line = line.trim();
+                if line.starts_with(['#', ';', '[']) {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once('=') {
+                    collect_pair(key, value, &mut iocs);
+                }
+            }
+        }
+    }
+    iocs
+}
+
+/// The `pools` array of an xmrig config and nothing else
+fn collect_json(value: &Value, iocs: &mut MinerIocs) {
+    let Some(pools) = value.get("pools").and_then(Value::as_array) else {
+        return;
+    };
+    for pool in pools.iter().filter_map(Value::as_object) {
+        for (key, value) in pool {
+            if let Value::String(s) = value {
+                collect_pair(key, s, iocs);
+            }
+        }
+    }
+}
+
+/// A stratum URL, or a bare `host:port` as xmrig takes it. http(s) and other
+/// schemes (update checks, APIs, proxies) and portless hosts aren't pools.
+fn looks_like_pool_url(value: &str) -> bool {
+    match value.trim().split_once("://") {
+        Some((scheme, _)) => scheme.to_ascii_lowercase().starts_with("stratum"),
+        None => parse_pool_url(value).is_some_and(|pool| pool.port.is_some()),
+    }
+}
+
+fn collect_pair(key: &str, value: &str, iocs: &mut MinerIocs) {
+    let key = key.trim().trim_start_matches('-').to_ascii_lowercase();
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    if POOL_KEYS.contains(&key.as_str()) {
+        iocs.add_pool(value);
+    } else if WALLET_KEYS.contains(&key.as_str()) {
+        iocs.add_wallet(value);
+    }
+}
+
+/// Host and port of a pool URL such as `stratum+tcp://pool.example.com:3333`.
+/// Loopback pools (local proxies) are no use as indicators and are left out.
+pub fn parse_pool_url(url: &str) -> Option<PoolEndpoint> {
+    let rest = url.trim();
+    let rest = rest.split_once("://").map_or(rest, |(_, rest)| rest);
+    let authority = rest.split('/').next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+
+    let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+        let (host, after) = v6.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else if authority.matches(':').count() > 1 {
+        (authority, None)  // Bare IPv6 address
+    } else {
+        match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => Some(port.parse::<u16>().ok()?),
+        None => None,
+    };
+
+    let host = host.to_ascii_lowercase();
+    match host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_loopback() || ip.is_unspecified() => return None,
+        Ok(_) => {}
+        Err(_) => {
+            let valid = host.contains('.')
+                && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+                && !host.starts_with(['.', '-']);
+            if !valid || host == "localhost" || host.ends_with(".localhost") {
+                return None;
+            }
+        }
+    }
+    Some(PoolEndpoint { host, port })
+}
+
+fn wallet_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(concat!(
+        r"^(?:",
+        r"[48][1-9A-HJ-NP-Za-km-z]{94}(?:[1-9A-HJ-NP-Za-km-z]{11})?",  // Monero, plain or integrated
+        r"|bc1[02-9ac-hj-np-z]{11,71}|[13][1-9A-HJ-NP-Za-km-z]{25,34}",  // Bitcoin
+        r"|0x[0-9a-fA-F]{40}",  // Ethereum and ERC-20 tokens
+        r")$",
+    )).unwrap())
+}
+
+/// The wallet address in a pool login. Pools take `WALLET.worker`, `WALLET+difficulty`
+/// or `WALLET/email`, so only the part that looks like an address is kept.
+pub fn wallet_from_login(login: &str) -> Option<String> {
+    login.split(['.', '+', '/'])
+        .map(str::trim)
+        .find(|part| wallet_regex().is_match(part))
+        .map(str::to_string)
+}
+
+/// Address and port the pools resolve to right now, for matching against open
+/// connections. The pool's port is part of the match: pools often sit behind shared
+/// hosting or CDN addresses that also serve unrelated sites on 80/443. Pools without
+/// a port can't be matched and are skipped, as are lookups that fail or time out
+/// (the host stays in the database for next time).
+pub async fn resolve_pools(pools: &[PoolEndpoint]) -> Vec<SocketAddr> {
+    let mut addrs = Vec::new();
+    for pool in pools {
+        let Some(port) = pool.port else {
+            continue;
+        };
+        if let Ok(ip) = pool.host.parse::<IpAddr>() {
+            addrs.push(SocketAddr::new(ip, port));
+            continue;
+        }
+        let lookup = tokio::net::lookup_host((pool.host.as_str(), port));
+        match tokio::time::timeout(RESOLVE_TIMEOUT, lookup).await {
+            Ok(Ok(resolved)) => addrs.extend(resolved),
+            Ok(Err(e)) => debug!("Can't resolve pool {}: {}", pool, e),
+            Err(_) => debug!("Resolving pool {} timed out", pool),
+        }
+    }
+    addrs.retain(|addr| !addr.ip().is_loopback() && !addr.ip().is_unspecified());
+    addrs.sort_unstable();
+    addrs.dedup();
+    addrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A";
+
+    fn xmrig_config() -> String {
+        format!(r#"{{
+    "autosave": true,
+    "cpu": {{ "enabled": true, "max-threads-hint": 100 }},
+    "http": {{ "enabled": false, "host": "127.0.0.1", "port": 0 }},
+    "pools": [
+        {{
+            "algo": null,
+            "url": "pool.supportxmr.com:443",
+            "user": "{wallet}.rig01",
+            "pass": "x",
+            "tls": true,
+            "keepalive": true
+        }},
+        {{
+            "url": "stratum+tcp://xmr-eu1.nanopool.org:14444",
+            "user": "{wallet}+50000",
+            "pass": "x"
+        }},
+        {{ "url": "127.0.0.1:3333", "user": "x" }}
+    ]
+}}"#, wallet = WALLET)
+    }
+
+    #[test]
+    fn parses_pools_and_wallet_from_xmrig_config() {
+        let iocs = parse_miner_config(&xmrig_config());
+        assert_eq!(iocs.pools, vec![
+            PoolEndpoint { host: "pool.supportxmr.com".to_string(), port: Some(443) },
+            PoolEndpoint { host: "xmr-eu1.nanopool.org".to_string(), port: Some(14444) },
+        ]);
+        // Worker name and difficulty suffixes stripped, and the same wallet only once
+        assert_eq!(iocs.wallets, vec![WALLET.to_string()]);
+    }
+
+    #[test]
+    fn parses_key_value_configs() {
+        let ini = format!("[pool]\n# backup below\no = stratum+ssl://[2001:db8::1]:5555\nu = {}\npass=x\n", WALLET);
+        let iocs = parse_miner_config(&ini);
+        assert_eq!(iocs.pools, vec![PoolEndpoint { host: "2001:db8::1".to_string(), port: Some(5555) }]);
+        assert_eq!(iocs.pools[0].to_string(), "[2001:db8::1]:5555");
+        assert_eq!(iocs.wallets, vec![WALLET.to_string()]);
+
+        assert_eq!(wallet_from_login("0x52908400098527886E0F7030069857D2E4169EE7.w1").as_deref(),
+                   Some("0x52908400098527886E0F7030069857D2E4169EE7"));
+        assert_eq!(wallet_from_login("worker1"), None);
+        assert_eq!(parse_pool_url("localhost:3333"), None);
+        assert_eq!(parse_pool_url("pool.example.com:notaport"), None);
+    }
+
+    #[test]
+    fn only_stratum_or_host_port_pools_from_xmrig_shaped_json_count() {
+        // An app config that happens to sit next to a detection
+        let app = r#"{ "name": "shop", "url": "https://shop.example.com:8443", "db": { "url": "db.example.com:5432" } }"#;
+        assert!(parse_miner_config(app).is_empty());
+
+        let mixed = r#"{ "pools": [
+            { "url": "https://api.example.com:443/stats" },
+            { "url": "pool.example.com" },
+            { "url": "stratum+ssl://gulf.moneroocean.stream:20128" }
+        ], "url": "pool.hashvault.pro:443" }"#;
+        assert_eq!(parse_miner_config(mixed).pools,
+                   vec![PoolEndpoint { host: "gulf.moneroocean.stream".to_string(), port: Some(20128) }]);
+    }
+
+    #[test]
+    fn finds_sibling_configs_of_a_miner() {
+        let dir = tempfile::tempdir().unwrap();
+        let miner = dir.path().join("xmrig");
+        std::fs::write(&miner, b"\x7fELF").unwrap();
+        std::fs::write(dir.path().join("config.json"), xmrig_config()).unwrap();
+        std::fs::write(dir.path().join("config.txt"), "nothing to see").unwrap();
+
+        let found = find_miner_iocs(&miner);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].config_path, dir.path().join("config.json"));
+        let alert = format_iocs(&found);
+        assert!(alert.contains("Pool: pool.supportxmr.com:443"));
+        assert!(alert.contains(&format!("Wallet: {}", WALLET)));
+    }
+}
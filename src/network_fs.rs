@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Filesystem types whose I/O can hang on a dead server
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "ceph", "glusterfs", "afs", "ncpfs",
+    "davfs", "fuse.sshfs", "fuse.s3fs", "fuse.glusterfs", "fuse.cephfs", "fuse.rclone",
+];
+
+#[derive(Debug, Clone)]
+struct Mount {
+    mount_point: PathBuf,
+    fs_type: String,
+}
+
+/// Mount points from /proc/mounts, to tell which paths live on network filesystems
+#[derive(Debug, Clone, Default)]
+pub struct MountTable {
+    mounts: Vec<Mount>,
+}
+
+impl MountTable {
+    /// Read /proc/mounts; an unreadable table treats everything as local
+    pub fn load() -> Self {
+        match fs::read_to_string("/proc/mounts") {
+            Ok(content) => Self::parse(&content),
+            Err(e) => {
+                warn!("Failed to read /proc/mounts, network filesystems won't be recognised: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mounts = content.lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let _device = fields.next()?;
+                let mount_point = unescape(fields.next()?);
+                let fs_type = fields.next()?.to_string();
+                Some(Mount { mount_point: PathBuf::from(mount_point), fs_type })
+            })
+            .collect();
+        Self { mounts }
+    }
+
+    /// Filesystem type of the mount holding `path` (the longest matching mount point;
+    /// for stacked mounts, the last one mounted)
+    pub fn fs_type(&self, path: &Path) -> Option<&str> {
+        self.mounts.iter()
+            .filter(|m| path.starts_with(&m.mount_point))
+            .max_by_key(|m| m.mount_point.components().count())
+            .map(|m| m.fs_type.as_str())
+    }
+
+    pub fn is_network(&self, path: &Path) -> bool {
+        self.fs_type(path).is_some_and(is_network_fs_type)
+    }
+}
+
+pub fn is_network_fs_type(fs_type: &str) -> bool {
+    NETWORK_FS_TYPES.contains(&fs_type)
+}
+
+/// /proc/mounts writes space, tab, newline and backslash as \040, \011, \012, \134
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).filter(|digits| digits.iter().all(|b| (b'0'..=b'7').contains(b)));
+        if let (b'\\', Some(digits)) = (bytes[i], octal) {
+            out.push(digits.iter().fold(0u8, |acc, d| acc.wrapping_mul(8).wrapping_add(d - b'0')));
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTS: &str = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec 0 0
+fileserver:/export/home /home nfs4 rw,relatime,vers=4.2 0 0
+/dev/sdb1 /home/local ext4 rw,relatime 0 0
+//nas/share /mnt/team\\040share cifs rw,relatime 0 0
+";
+
+    #[test]
+    fn longest_mount_point_decides() {
+        let table = MountTable::parse(MOUNTS);
+        assert!(!table.is_network(Path::new("/tmp/x")));
+        assert!(table.is_network(Path::new("/home/alice/.cache/xmrig")));
+        assert!(!table.is_network(Path::new("/home/local/bin/app")));
+        assert!(!table.is_network(Path::new("/homework/file")));
+        assert_eq!(table.fs_type(Path::new("/mnt/team share/doc")), Some("cifs"));
+        assert!(!table.is_network(Path::new("/mnt/team/doc")));
+    }
+}
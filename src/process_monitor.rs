@@ -1,9 +1,39 @@
 use anyhow::Result;
-use chrono::Utc;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use sysinfo::{Pid, System, Process, User, Uid};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, System, Uid};
 use num_traits::cast::AsPrimitive;
+use tracing::debug;
+
+use crate::ptrace_detector::parse_tracer_pid;
+use crate::suspicious_paths::is_suspicious_path;
+
+/// Dynamic loader variables that can inject code into a process
+const LOADER_ENV_VARS: &[&str] = &["LD_PRELOAD", "LD_LIBRARY_PATH", "LD_AUDIT"];
+
+/// PID of kthreadd, the parent of every genuine kernel thread
+const KTHREADD_PID: i32 = 2;
+
+/// Name prefixes used by common kernel threads
+const KERNEL_THREAD_PREFIXES: &[&str] = &[
+    "kworker/", "ksoftirqd/", "migration/", "kthreadd", "rcu_", "kswapd",
+    "watchdog/", "cpuhp/", "kcompactd", "khugepaged", "jbd2/", "irq/",
+];
+
+/// Daemons that rewrite their argv into a status title while running
+/// (`postgres: checkpointer`, `sshd: deploy [priv]`, `php-fpm: pool www`)
+const TITLE_SETTING_DAEMONS: &[&str] = &[
+    "postgres", "postmaster", "php-fpm", "sshd", "nginx", "httpd", "apache2",
+    "redis-server", "dovecot", "master", "qmgr", "pickup", "exim4", "sendmail",
+];
+
+/// Where packaged daemons are installed; a title-setting daemon elsewhere is not exempt
+const SYSTEM_BINARY_DIRS: &[&str] = &["/usr/sbin", "/usr/bin", "/usr/lib", "/usr/libexec", "/sbin", "/bin", "/lib"];
 
 /// Helper function to convert sysinfo Uid to u32
 /// sysinfo 0.30+ uses .as_() instead of .as_raw()
@@ -12,19 +42,142 @@ fn uid_to_u32(uid_opt: Option<&Uid>) -> u32 {
     uid_opt.map(|u| u.as_()).unwrap_or(0u32)
 }
 
-#[derive(Debug, Clone)]
+/// Whether the executable (and with it the rest of the process) could be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExeState {
+    #[default]
+    Known,  // binary_path is the running executable
+    NoExecutable,  // Alive but without a readable exe: kernel threads, zombies
+    Vanished,  // Exited while being read; the other fields may be partial
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ProcessInfo {
     pub pid: i32,
     pub ppid: i32,
     pub uid: u32,
+    /// Empty unless `exe_state` is Known
     pub binary_path: String,
+    pub exe_state: ExeState,
     pub command_line: String,
     pub cpu_percent: f32,
+    /// Process start time (seconds since epoch), used to detect PID reuse
+    pub start_time: u64,
+    /// Short process name (comm)
+    pub name: String,
+    /// Whether /proc/<pid>/exe resolves (kernel threads have no executable)
+    pub exe_resolves: bool,
+    /// Executable is an anonymous memfd (fileless execution)
+    pub exe_is_memfd: bool,
+    /// Loader variables (e.g. `LD_PRELOAD=/tmp/x.so`) pointing into writable directories
+    pub suspicious_env: Vec<String>,
+    /// Number of threads (entries in /proc/<pid>/task)
+    pub thread_count: usize,
+    /// Resident memory in bytes
+    pub memory_bytes: u64,
+    /// Open file descriptors (0 if /proc/<pid>/fd is unreadable)
+    pub fd_count: usize,
+    /// Open TCP/UDP sockets, matched against the inodes in /proc/<pid>/net
+    pub socket_count: usize,
+    /// TCP ports this process listens on, sorted
+    pub listening_ports: Vec<u16>,
+    /// Peers (address and port) of its established TCP connections, sorted
+    pub remote_addrs: Vec<SocketAddr>,
+    /// PID of the process ptrace-attached to this one (TracerPid), 0 if none
+    pub tracer_pid: i32,
+    /// Controlling terminal (tty_nr from /proc/<pid>/stat), Some(0) when there is none;
+    /// None if it couldn't be read
+    pub tty_nr: Option<i32>,
+    /// argv rewritten to hide the real command (see `cmdline_spoof_reason`)
+    pub cmdline_spoofed: bool,
+    /// Cgroup path (the unified v2 path, or the cpu controller's on v1); empty if unreadable
+    pub cgroup: String,
+}
+
+/// Inet socket inodes per network namespace, so /proc/<pid>/net/* is read once per
+/// namespace rather than once per process during a refresh
+#[derive(Default)]
+pub struct SocketInodeCache {
+    by_netns: HashMap<PathBuf, NetnsSockets>,
+}
+
+#[derive(Default)]
+struct NetnsSockets {
+    inet: HashSet<u64>,
+    listening: HashMap<u64, u16>,  // inode -> port of TCP sockets in LISTEN
+    established: HashMap<u64, SocketAddr>,  // inode -> peer of established TCP connections
+}
+
+impl SocketInodeCache {
+    fn sockets_for(&mut self, pid: i32) -> &NetnsSockets {
+        let netns = std::fs::read_link(format!("/proc/{}/ns/net", pid))
+            .unwrap_or_else(|_| PathBuf::from(format!("pid:{}", pid)));
+        self.by_netns.entry(netns).or_insert_with(|| {
+            let mut sockets = NetnsSockets::default();
+            for table in ["tcp", "tcp6", "udp", "udp6"] {
+                if let Ok(content) = std::fs::read_to_string(format!("/proc/{}/net/{}", pid, table)) {
+                    sockets.inet.extend(parse_net_inodes(&content));
+                    if table.starts_with("tcp") {
+                        sockets.listening.extend(parse_listening_sockets(&content));
+                        sockets.established.extend(parse_established_peers(&content));
+                    }
+                }
+            }
+            sockets
+        })
+    }
+}
+
+/// Longest an idle process's fd and socket snapshot is reused; sockets change state
+/// (peers closing, listeners going away) without the owner using any CPU
+const FD_SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// A process's open descriptors as last read. Reading every fd link and matching its
+/// sockets is the most expensive part of a refresh, so idle processes reuse this.
+#[derive(Debug, Clone)]
+struct FdSnapshot {
+    start_time: u64,
+    read_at: Instant,
+    fd_count: usize,
+    socket_count: usize,
+    listening_ports: Vec<u16>,
+    remote_addrs: Vec<SocketAddr>,
+}
+
+impl FdSnapshot {
+    fn read(pid: i32, start_time: u64, sockets: &mut SocketInodeCache) -> Self {
+        let fd_targets = read_fd_targets(pid).unwrap_or_default();
+        let (socket_count, listening_ports, remote_addrs) = if fd_targets.iter().any(|t| socket_inode(t).is_some()) {
+            let netns = sockets.sockets_for(pid);
+            (count_inet_sockets(&fd_targets, &netns.inet),
+             listening_ports(&fd_targets, &netns.listening),
+             remote_addrs(&fd_targets, &netns.established))
+        } else {
+            (0, Vec::new(), Vec::new())
+        };
+        Self { start_time, read_at: Instant::now(), fd_count: fd_targets.len(), socket_count, listening_ports, remote_addrs }
+    }
+
+    /// Still valid for the process started at `start_time`: the same process, which
+    /// used no CPU since the last refresh (so opened or closed nothing), read recently
+    fn is_current(&self, start_time: u64, cpu_percent: f32, now: Instant) -> bool {
+        self.start_time == start_time
+            && cpu_percent <= 0.0
+            && now.duration_since(self.read_at) < FD_SNAPSHOT_MAX_AGE
+    }
 }
 
 pub struct ProcessMonitor {
     system: System,
-    last_cpu_times: HashMap<i32, (u64, u64)>, // (pid, (total_time, timestamp))
+    fd_snapshots: Mutex<HashMap<i32, FdSnapshot>>,
+}
+
+impl Default for ProcessMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProcessMonitor {
@@ -34,7 +187,7 @@ impl ProcessMonitor {
         
         Self {
             system,
-            last_cpu_times: HashMap::new(),
+            fd_snapshots: Mutex::new(HashMap::new()),
         }
     }
 
@@ -42,86 +195,181 @@ impl ProcessMonitor {
         self.system.refresh_all();
     }
 
+    /// Read just `pids` (new executions) without a full refresh. CPU usage is left
+    /// out: sampling it moves the global CPU baseline the regular poll measures from.
+    pub fn refresh_pids(&mut self, pids: &[i32]) -> Vec<ProcessInfo> {
+        let refresh_kind = ProcessRefreshKind::everything().without_cpu();
+        let live: Vec<i32> = pids.iter()
+            .copied()
+            .filter(|pid| self.system.refresh_process_specifics(Pid::from_u32(*pid as u32), refresh_kind))
+            .collect();
+        let mut sockets = SocketInodeCache::default();
+        live.into_iter()
+            .filter_map(|pid| {
+                let process = self.system.process(Pid::from_u32(pid as u32))?;
+                Some(self.build_process_info(pid, process, &mut sockets))
+            })
+            .filter(|info| info.exe_state != ExeState::Vanished)
+            .collect()
+    }
+
+    /// Every process that was still alive when read. Ones that exited mid-enumeration
+    /// are left out rather than reported with partial fields.
     pub fn get_all_processes(&self) -> Result<Vec<ProcessInfo>> {
         let mut processes = Vec::new();
+        let mut sockets = SocketInodeCache::default();
+        let mut vanished = 0;
 
         for (pid, process) in self.system.processes() {
-            let pid_int = pid.as_u32() as i32;
-            
-            // Get binary path
-            let binary_path = process
-                .exe()
-                .and_then(|p| p.to_str().map(|s| s.to_string()))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            // Get command line
-            let command_line = process
-                .cmd()
-                .iter()
-                .map(|s| s.to_string())
-                .collect::<Vec<_>>()
-                .join(" ")
-                .chars()
-                .take(500) // Limit length
-                .collect::<String>();
-
-            // Get UID (using helper for sysinfo 0.30+ API compatibility)
-            let uid = uid_to_u32(process.user_id());
-
-            // Get PPID
-            let ppid = process.parent()
-                .map(|p| p.as_u32() as i32)
-                .unwrap_or(0);
-
-            // Calculate CPU percent
-            let cpu_percent = process.cpu_usage() as f32;
-
-            processes.push(ProcessInfo {
-                pid: pid_int,
-                ppid,
-                uid,
-                binary_path,
-                command_line,
-                cpu_percent,
-            });
+            let info = self.build_process_info(pid.as_u32() as i32, process, &mut sockets);
+            if info.exe_state == ExeState::Vanished {
+                vanished += 1;
+                continue;
+            }
+            processes.push(info);
         }
+        if vanished > 0 {
+            debug!("Skipped {} process(es) that exited while being read", vanished);
+        }
+        self.lock_fd_snapshots().retain(|pid, snapshot| {
+            processes.iter().any(|p| p.pid == *pid && p.start_time == snapshot.start_time)
+        });
 
         Ok(processes)
     }
 
     pub fn get_process_by_pid(&self, pid: i32) -> Option<ProcessInfo> {
         let pid_obj = Pid::from_u32(pid as u32);
-        self.system.process(pid_obj).map(|process| {
-            let binary_path = process
-                .exe()
-                .and_then(|p| p.to_str().map(|s| s.to_string()))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let command_line = process
-                .cmd()
-                .iter()
-                .map(|s| s.to_string())
-                .collect::<Vec<_>>()
-                .join(" ")
-                .chars()
-                .take(500)
-                .collect::<String>();
-
-            let uid = uid_to_u32(process.user_id());
-            let ppid = process.parent()
-                .map(|p| p.as_u32() as i32)
-                .unwrap_or(0);
-            let cpu_percent = process.cpu_usage() as f32;
-
-            ProcessInfo {
-                pid,
-                ppid,
-                uid,
-                binary_path,
-                command_line,
-                cpu_percent,
+        self.system.process(pid_obj)
+            .map(|process| self.build_process_info(pid, process, &mut SocketInodeCache::default()))
+            .filter(|info| info.exe_state != ExeState::Vanished)
+    }
+
+    fn lock_fd_snapshots(&self) -> std::sync::MutexGuard<'_, HashMap<i32, FdSnapshot>> {
+        self.fd_snapshots.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Fds and sockets of `pid`, re-read only when `FdSnapshot::is_current` says so
+    fn fd_snapshot(&self, pid: i32, start_time: u64, cpu_percent: f32, sockets: &mut SocketInodeCache) -> FdSnapshot {
+        if let Some(snapshot) = self.lock_fd_snapshots().get(&pid) {
+            if snapshot.is_current(start_time, cpu_percent, Instant::now()) {
+                return snapshot.clone();
             }
-        })
+        }
+        let snapshot = FdSnapshot::read(pid, start_time, sockets);
+        self.lock_fd_snapshots().insert(pid, snapshot.clone());
+        snapshot
+    }
+
+    fn build_process_info(&self, pid: i32, process: &sysinfo::Process, sockets: &mut SocketInodeCache) -> ProcessInfo {
+        // Get binary path; a non-UTF-8 name is still a known executable (and a
+        // classic way to dodge path matching), so it's analysed lossily
+        let exe = process.exe();
+        let binary_path = exe.map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+
+        // Get command line
+        let command_line = process
+            .cmd()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(500) // Limit length
+            .collect::<String>();
+
+        // Get UID (using helper for sysinfo 0.30+ API compatibility)
+        let uid = uid_to_u32(process.user_id());
+
+        // Get PPID
+        let ppid = process.parent()
+            .map(|p| p.as_u32() as i32)
+            .unwrap_or(0);
+
+        // Calculate CPU percent
+        let cpu_percent = process.cpu_usage();
+
+        let start_time = process.start_time();
+        let name = process.name().to_string();
+        let exe_target = std::fs::read_link(format!("/proc/{}/exe", pid)).ok();
+        let exe_resolves = exe_target.is_some();
+        let exe_is_memfd = exe_target
+            .map(|target| is_memfd_exe(&target.to_string_lossy()))
+            .unwrap_or(false)
+            || is_memfd_exe(&binary_path);
+
+        // Loader hijacking via environment (only readable for our own or root-visible processes)
+        let suspicious_env = Self::read_environ(pid)
+            .map(|env| suspicious_loader_env(&env))
+            .unwrap_or_default();
+
+        let thread_count = process.tasks()
+            .map(|tasks| tasks.len())
+            .unwrap_or(1);
+
+        let fds = self.fd_snapshot(pid, start_time, cpu_percent, sockets);
+
+        let cgroup = std::fs::read_to_string(format!("/proc/{}/cgroup", pid))
+            .ok()
+            .and_then(|content| parse_cgroup_path(&content))
+            .unwrap_or_default();
+
+        let tracer_pid = std::fs::read_to_string(format!("/proc/{}/status", pid))
+            .map(|status| parse_tracer_pid(&status))
+            .unwrap_or(0);
+
+        let tty_nr = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| parse_tty_nr(&stat));
+
+        // Skip PID 1: systemd renames itself while running as /sbin/init
+        let cmdline_spoofed = exe_resolves && pid != 1
+            && std::fs::read(format!("/proc/{}/cmdline", pid))
+                .ok()
+                .and_then(|raw| cmdline_spoof_reason(&name, &binary_path, &command_line, &raw))
+                .is_some();
+
+        // Checked last: gone now means some of the reads above may have failed
+        let exe_state = classify_exe(exe.is_some(), Path::new(&format!("/proc/{}", pid)).exists());
+
+        ProcessInfo {
+            pid,
+            ppid,
+            uid,
+            binary_path,
+            exe_state,
+            command_line,
+            cpu_percent,
+            start_time,
+            name,
+            exe_resolves,
+            exe_is_memfd,
+            suspicious_env,
+            thread_count,
+            memory_bytes: process.memory(),
+            fd_count: fds.fd_count,
+            socket_count: fds.socket_count,
+            listening_ports: fds.listening_ports,
+            remote_addrs: fds.remote_addrs,
+            tracer_pid,
+            tty_nr,
+            cmdline_spoofed,
+            cgroup,
+        }
+    }
+
+    /// Read the environment of a process from /proc/<pid>/environ.
+    /// Returns None if the process is gone or we lack the privileges to read it.
+    pub fn read_environ(pid: i32) -> Option<HashMap<String, String>> {
+        match std::fs::read(format!("/proc/{}/environ", pid)) {
+            Ok(raw) => Some(parse_environ(&raw)),
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    debug!("No permission to read environment of PID {}", pid);
+                }
+                None
+            }
+        }
     }
 
     pub fn get_process_tree(&self, pid: i32) -> Vec<i32> {
@@ -195,3 +443,620 @@ impl ProcessMonitor {
     }
 }
 
+/// Parse the NUL-delimited contents of /proc/<pid>/environ into key/value pairs
+pub fn parse_environ(raw: &[u8]) -> HashMap<String, String> {
+    raw.split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (key, value) = entry.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Return `KEY=value` entries for loader variables that reference writable directories
+pub fn suspicious_loader_env(env: &HashMap<String, String>) -> Vec<String> {
+    let mut flagged = Vec::new();
+    for var in LOADER_ENV_VARS {
+        if let Some(value) = env.get(*var) {
+            let suspicious = value
+                .split([':', ' '])
+                .any(is_suspicious_path);
+            if suspicious {
+                flagged.push(format!("{}={}", var, value));
+            }
+        }
+    }
+    flagged
+}
+
+/// UIDs below this are root and system service accounts
+const FIRST_REGULAR_UID: u32 = 1000;
+
+/// A root/system process executing from, or working in, a regular user's home
+/// (e.g. root running `/home/bob/.cache/x`): code the low-privileged user could
+/// have planted is running with more privileges than its owner has.
+pub fn runs_foreign_home_code(process: &ProcessInfo) -> bool {
+    let cwd = std::fs::read_link(format!("/proc/{}/cwd", process.pid)).ok();
+    foreign_home_code(process.uid, Path::new(&process.binary_path), cwd.as_deref(), Path::new("/home"))
+}
+
+fn foreign_home_code(uid: u32, exe: &Path, cwd: Option<&Path>, home_root: &Path) -> bool {
+    if uid >= FIRST_REGULAR_UID {
+        return false;
+    }
+    let owners = HomeOwners {
+        exe_home: home_dir_owner(exe, home_root),
+        exe_file: std::fs::metadata(exe).map(|m| m.uid()).ok(),
+        cwd_home: cwd.and_then(|cwd| home_dir_owner(cwd, home_root)),
+    };
+    owners.privilege_mismatch(uid, crate::scoring::is_system_binary(&exe.to_string_lossy()))
+}
+
+/// Owners stat'ed for a process's executable and working directory
+#[derive(Debug, Default)]
+struct HomeOwners {
+    exe_home: Option<u32>,  // Home directory containing the executable
+    exe_file: Option<u32>,  // The executable itself
+    cwd_home: Option<u32>,  // Home directory containing the working directory
+}
+
+impl HomeOwners {
+    fn privilege_mismatch(&self, uid: u32, system_binary: bool) -> bool {
+        if uid >= FIRST_REGULAR_UID {
+            return false;
+        }
+        // A file owned by the process's own user wasn't planted by the home's owner
+        // (covers root-owned setuid helpers an admin installed under /home)
+        let exe_mismatch = self.exe_home.is_some_and(|owner| is_foreign_owner(uid, owner))
+            && self.exe_file != Some(uid);
+        // `sudo <system tool>` from a user's home is routine admin work; interpreters and
+        // app runtimes executing there (root running bob's node app) are not
+        let cwd_mismatch = !system_binary && self.cwd_home.is_some_and(|owner| is_foreign_owner(uid, owner));
+        exe_mismatch || cwd_mismatch
+    }
+}
+
+fn is_foreign_owner(uid: u32, home_owner: u32) -> bool {
+    home_owner >= FIRST_REGULAR_UID && home_owner != uid
+}
+
+/// Owner UID of the `<home_root>/<user>` directory containing `path`
+fn home_dir_owner(path: &Path, home_root: &Path) -> Option<u32> {
+    let user = path.strip_prefix(home_root).ok()?.components().next()?;
+    std::fs::metadata(home_root.join(user)).ok().map(|m| m.uid())
+}
+
+/// Whether `path` lies under `dir_pattern`, where a `*` component matches any one component
+pub fn is_under_dir_pattern(path: &str, dir_pattern: &str) -> bool {
+    let mut components = Path::new(path).components();
+    Path::new(dir_pattern).components().all(|pattern| {
+        components.next().is_some_and(|component| {
+            pattern.as_os_str() == "*" || pattern == component
+        })
+    })
+}
+
+/// Seconds since the file's inode last changed (ctime, which `touch` can't backdate)
+pub fn file_change_age_seconds(path: &str, now: i64) -> Option<u64> {
+    let ctime = std::fs::metadata(path).ok()?.ctime();
+    Some(now.saturating_sub(ctime).max(0) as u64)
+}
+
+/// Targets of every open descriptor in /proc/<pid>/fd.
+/// None if the process is gone or we lack the privileges to read it.
+pub fn read_fd_targets(pid: i32) -> Option<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(format!("/proc/{}/fd", pid)).ok()?;
+    Some(entries.flatten()
+        .filter_map(|e| std::fs::read_link(e.path()).ok())
+        .collect())
+}
+
+/// Inode of a `socket:[12345]` descriptor target
+pub fn socket_inode(target: &std::path::Path) -> Option<u64> {
+    target.to_str()?
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Socket inodes listed in a /proc/net/{tcp,tcp6,udp,udp6} table
+pub fn parse_net_inodes(content: &str) -> impl Iterator<Item = u64> + '_ {
+    // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode
+    content.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(9)?.parse().ok())
+        .filter(|&inode| inode != 0)
+}
+
+/// (inode, local port) of the TCP sockets in LISTEN state in a /proc/net/tcp{,6} table
+pub fn parse_listening_sockets(content: &str) -> impl Iterator<Item = (u64, u16)> + '_ {
+    const TCP_LISTEN: &str = "0A";
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != TCP_LISTEN {
+                return None;
+            }
+            // local_address is hex ADDR:PORT
+            let port = u16::from_str_radix(fields[1].rsplit(':').next()?, 16).ok()?;
+            let inode: u64 = fields[9].parse().ok()?;
+            (inode != 0).then_some((inode, port))
+        })
+}
+
+/// (inode, peer address) of the established TCP connections in a /proc/net/tcp{,6} table
+pub fn parse_established_peers(content: &str) -> impl Iterator<Item = (u64, SocketAddr)> + '_ {
+    const TCP_ESTABLISHED: &str = "01";
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != TCP_ESTABLISHED {
+                return None;
+            }
+            let (addr, port) = fields[2].split_once(':')?;
+            let peer = SocketAddr::new(parse_hex_addr(addr)?, u16::from_str_radix(port, 16).ok()?);
+            let inode: u64 = fields[9].parse().ok()?;
+            (inode != 0).then_some((inode, peer))
+        })
+}
+
+/// Address as printed in /proc/net: 32-bit words in hex, each in host byte order
+fn parse_hex_addr(hex: &str) -> Option<IpAddr> {
+    let word = |i: usize| hex.get(i * 8..i * 8 + 8).and_then(|w| u32::from_str_radix(w, 16).ok());
+    match hex.len() {
+        8 => Some(IpAddr::from(word(0)?.to_ne_bytes())),
+        32 => {
+            let mut bytes = [0u8; 16];
+            for i in 0..4 {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word(i)?.to_ne_bytes());
+            }
+            // Show IPv4 peers of dual-stack sockets as plain IPv4
+            let v6 = std::net::Ipv6Addr::from(bytes);
+            Some(v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4))
+        }
+        _ => None,
+    }
+}
+
+/// Peers of the established connections among a process's descriptors, sorted and deduplicated
+pub fn remote_addrs(fd_targets: &[PathBuf], established: &HashMap<u64, SocketAddr>) -> Vec<SocketAddr> {
+    let mut addrs: Vec<SocketAddr> = fd_targets.iter()
+        .filter_map(|t| socket_inode(t))
+        .filter_map(|inode| established.get(&inode).copied())
+        .collect();
+    addrs.sort_unstable();
+    addrs.dedup();
+    addrs
+}
+
+/// Ports of the listening sockets among a process's descriptors, sorted and deduplicated
+pub fn listening_ports(fd_targets: &[PathBuf], listening: &HashMap<u64, u16>) -> Vec<u16> {
+    let mut ports: Vec<u16> = fd_targets.iter()
+        .filter_map(|t| socket_inode(t))
+        .filter_map(|inode| listening.get(&inode).copied())
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
+
+/// Descriptors that are TCP/UDP sockets (unix and netlink sockets are not counted)
+pub fn count_inet_sockets(fd_targets: &[PathBuf], inet_inodes: &HashSet<u64>) -> usize {
+    fd_targets.iter()
+        .filter_map(|t| socket_inode(t))
+        .filter(|inode| inet_inodes.contains(inode))
+        .count()
+}
+
+/// tty_nr (field 7) of /proc/<pid>/stat; fields are counted after the
+/// parenthesised comm, which may contain spaces
+pub fn parse_tty_nr(stat: &str) -> Option<i32> {
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    // State is field 3, so tty_nr is the fifth field after the comm
+    after_comm.split_whitespace().nth(4)?.parse().ok()
+}
+
+/// The cgroup a process is placed in, from /proc/<pid>/cgroup: the unified
+/// hierarchy's path (`0::/path`), else the cpu controller's on cgroup v1
+pub fn parse_cgroup_path(content: &str) -> Option<String> {
+    // hierarchy-ID:controller-list:cgroup-path
+    let entries: Vec<(&str, &str)> = content.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+            Some((controllers, path))
+        })
+        .collect();
+    entries.iter()
+        .find(|(controllers, _)| controllers.is_empty())
+        .or_else(|| entries.iter().find(|(controllers, _)| controllers.split(',').any(|c| c == "cpu")))
+        .map(|(_, path)| path.to_string())
+}
+
+fn classify_exe(exe_readable: bool, still_running: bool) -> ExeState {
+    match (still_running, exe_readable) {
+        (false, _) => ExeState::Vanished,
+        (true, true) => ExeState::Known,
+        (true, false) => ExeState::NoExecutable,
+    }
+}
+
+impl ProcessInfo {
+    /// Everything needed for scoring and recording was read this cycle
+    pub fn is_complete(&self) -> bool {
+        self.exe_state == ExeState::Known
+    }
+}
+
+/// Whether an exe link target points at a memfd_create() mapping, e.g. `/memfd:x (deleted)`
+pub fn is_memfd_exe(exe_target: &str) -> bool {
+    exe_target.starts_with("/memfd:") || exe_target.starts_with("memfd:")
+}
+
+/// Whether a process presents itself like a kernel thread (`[kworker/u8:2]` style)
+pub fn looks_like_kernel_thread(name: &str, command_line: &str) -> bool {
+    let cmd = command_line.trim();
+    let bracketed = |s: &str| s.len() > 2 && s.starts_with('[') && s.ends_with(']');
+    let inner = cmd.trim_start_matches('[').trim_end_matches(']');
+
+    bracketed(cmd)
+        || bracketed(name)
+        || KERNEL_THREAD_PREFIXES.iter().any(|p| name.starts_with(p) || inner.starts_with(p))
+}
+
+/// Why a userspace process's argv looks rewritten to hide what it runs, if it does.
+/// `raw_cmdline` is /proc/<pid>/cmdline read now; `command_line` is the sysinfo view
+/// from the last refresh and `name` the comm. Legitimate renames (nginx, postgres,
+/// node's process.title) keep comm consistent with either argv or the executable, and
+/// packaged title-setting daemons (`TITLE_SETTING_DAEMONS`) are exempt altogether.
+pub fn cmdline_spoof_reason(name: &str, exe: &str, command_line: &str, raw_cmdline: &[u8]) -> Option<String> {
+    if is_packaged_title_setter(exe) {
+        return None;
+    }
+
+    let args: Vec<String> = raw_cmdline
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    if args.iter().all(|arg| arg.trim().is_empty()) {
+        return Some("empty command line".to_string());
+    }
+
+    // Same normalization and limit as build_process_info
+    let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(500).collect::<String>();
+    let raw = normalize(&args.join(" "));
+    let seen = normalize(command_line);
+    // Prefix check: both sides were cut at 500 characters before normalizing
+    if !seen.is_empty() && !raw.starts_with(&seen) && !seen.starts_with(&raw) {
+        return Some(format!("argv changed from {:?} to {:?}", seen, raw));
+    }
+
+    // comm is cut to 15 bytes; argv[0] may carry a title ("nginx: master process").
+    // Scripts run through a shebang get comm from the script, which is argv[1].
+    let comm = first_word(name);
+    if comm.is_empty() {
+        return None;
+    }
+    let candidates = std::iter::once(exe)
+        .chain(args.iter().take(2).map(String::as_str))
+        .map(first_word);
+    if candidates.into_iter().any(|c| !c.is_empty() && (c.starts_with(comm) || comm.starts_with(c))) {
+        return None;
+    }
+    Some(format!("comm {:?} matches neither the executable nor argv {:?}", name, raw))
+}
+
+/// A daemon that retitles itself, run from a system binary directory
+fn is_packaged_title_setter(exe: &str) -> bool {
+    let path = Path::new(exe);
+    let base = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    TITLE_SETTING_DAEMONS.iter().any(|daemon| base.starts_with(daemon))
+        && SYSTEM_BINARY_DIRS.iter().any(|dir| path.starts_with(dir))
+}
+
+/// Basename of the first word, without a trailing ':' title separator
+fn first_word(s: &str) -> &str {
+    let word = s.split_whitespace().next().unwrap_or("");
+    let base = word.rsplit('/').next().unwrap_or(word);
+    base.trim_end_matches(':')
+}
+
+/// A kernel-thread lookalike that has a real executable and isn't parented by kthreadd
+pub fn is_kernel_thread_impostor(process: &ProcessInfo) -> bool {
+    looks_like_kernel_thread(&process.name, &process.command_line)
+        && process.exe_resolves
+        && process.ppid != KTHREADD_PID
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_patterns_match_one_component_per_star() {
+        assert!(is_under_dir_pattern("/var/www/shop/uploads/x/kworker", "/var/www/*/uploads"));
+        assert!(is_under_dir_pattern("/var/www/shop/uploads", "/var/www/*/uploads"));
+        assert!(!is_under_dir_pattern("/var/www/shop/public/uploads/x", "/var/www/*/uploads"));
+        assert!(!is_under_dir_pattern("/var/www/uploads-old/x", "/var/www/uploads"));
+        assert!(!is_under_dir_pattern("/var/www", "/var/www/*/uploads"));
+    }
+
+    #[test]
+    fn privilege_mismatch_needs_a_privileged_process_and_a_regular_users_home() {
+        // Root running a binary bob (1001) could have planted
+        let planted = HomeOwners { exe_home: Some(1001), exe_file: Some(1001), cwd_home: None };
+        assert!(planted.privilege_mismatch(0, false));
+        assert!(planted.privilege_mismatch(998, false));
+        // bob running his own code, or another regular user, is not a privilege mismatch
+        assert!(!planted.privilege_mismatch(1001, false));
+        assert!(!planted.privilege_mismatch(1002, false));
+
+        // Root-owned (e.g. admin-installed setuid) file under the home is not planted code
+        let setuid = HomeOwners { exe_home: Some(1001), exe_file: Some(0), cwd_home: None };
+        assert!(!setuid.privilege_mismatch(0, false));
+        // A system account's home (uid below 1000) is not a low-privileged user's
+        let service = HomeOwners { exe_home: Some(33), exe_file: Some(33), cwd_home: None };
+        assert!(!service.privilege_mismatch(0, false));
+
+        // Working in bob's home: a runtime executing his app counts, `sudo <system tool>` doesn't
+        let in_home = HomeOwners { cwd_home: Some(1001), ..Default::default() };
+        assert!(in_home.privilege_mismatch(0, false));
+        assert!(!in_home.privilege_mismatch(0, true));
+        // System setuid binaries live outside /home entirely
+        assert!(!HomeOwners::default().privilege_mismatch(0, true));
+    }
+
+    #[test]
+    fn home_dir_owner_is_the_top_level_home_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let exe = root.path().join("bob/.cache/x/kswapd");
+        std::fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        let owner = std::fs::metadata(root.path().join("bob")).unwrap().uid();
+        assert_eq!(home_dir_owner(&exe, root.path()), Some(owner));
+        assert_eq!(home_dir_owner(Path::new("/usr/bin/passwd"), root.path()), None);
+    }
+
+    #[test]
+    #[ignore = "chowns files to another user, so it only runs as root (cargo test -- --ignored)"]
+    fn flags_privileged_processes_running_another_users_home_code() {
+        let root = tempfile::tempdir().unwrap();
+        let home = root.path().join("bob");
+        let exe = home.join(".cache/x/kswapd");
+        std::fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        std::fs::write(&exe, b"").unwrap();
+        std::os::unix::fs::chown(&home, Some(1001), None).unwrap();
+
+        // Root running a binary bob could have planted
+        std::os::unix::fs::chown(&exe, Some(1001), None).unwrap();
+        assert!(foreign_home_code(0, &exe, None, root.path()));
+        assert!(foreign_home_code(998, &exe, None, root.path()));
+        // bob running his own code, or another regular user, is not a privilege mismatch
+        assert!(!foreign_home_code(1001, &exe, None, root.path()));
+        assert!(!foreign_home_code(1002, &exe, None, root.path()));
+
+        // Root-owned (e.g. admin-installed setuid) file under the home is not planted code
+        std::os::unix::fs::chown(&exe, Some(0), None).unwrap();
+        assert!(!foreign_home_code(0, &exe, None, root.path()));
+        // Working in bob's home: a runtime executing his app counts, `sudo <system tool>` doesn't
+        assert!(foreign_home_code(0, Path::new("/usr/local/bin/node"), Some(&home), root.path()));
+        assert!(!foreign_home_code(0, Path::new("/usr/bin/make"), Some(&home), root.path()));
+
+        // System setuid binaries live outside /home entirely
+        assert!(!foreign_home_code(0, Path::new("/usr/bin/passwd"), Some(Path::new("/")), root.path()));
+    }
+
+    #[test]
+    fn parses_null_delimited_environ() {
+        let raw = b"PATH=/usr/bin:/bin\0HOME=/root\0EMPTY=\0NOEQUALS\0EQ=a=b\0\0";
+        let env = parse_environ(raw);
+        assert_eq!(env.get("PATH").map(String::as_str), Some("/usr/bin:/bin"));
+        assert_eq!(env.get("HOME").map(String::as_str), Some("/root"));
+        assert_eq!(env.get("EMPTY").map(String::as_str), Some(""));
+        assert_eq!(env.get("EQ").map(String::as_str), Some("a=b"));
+        assert!(!env.contains_key("NOEQUALS"));
+        assert_eq!(env.len(), 4);
+    }
+
+    #[test]
+    fn flags_loader_vars_in_writable_dirs() {
+        let env = parse_environ(b"LD_PRELOAD=/dev/shm/libx.so\0LD_LIBRARY_PATH=/usr/lib:/home/app/.cache/lib\0");
+        let flagged = suspicious_loader_env(&env);
+        assert_eq!(flagged.len(), 2);
+        assert!(flagged.contains(&"LD_PRELOAD=/dev/shm/libx.so".to_string()));
+
+        let clean = parse_environ(b"LD_LIBRARY_PATH=/usr/local/lib:/opt/app/lib\0");
+        assert!(suspicious_loader_env(&clean).is_empty());
+
+        // Directories are matched by path component, so look-alike names don't count
+        let lookalike = parse_environ(b"LD_PRELOAD=/tmpfs/libx.so\0LD_LIBRARY_PATH=/var/tmpl/lib:/dev/shmem\0");
+        assert!(suspicious_loader_env(&lookalike).is_empty());
+        let bare = parse_environ(b"LD_LIBRARY_PATH=/tmp\0");
+        assert_eq!(suspicious_loader_env(&bare), vec!["LD_LIBRARY_PATH=/tmp".to_string()]);
+    }
+
+    #[test]
+    fn distinguishes_kernel_thread_from_impostor() {
+        let real_kworker = ProcessInfo {
+            pid: 123,
+            ppid: 2,
+            name: "kworker/u8:2".to_string(),
+            exe_state: ExeState::NoExecutable,
+            exe_resolves: false,
+            ..Default::default()
+        };
+        assert!(looks_like_kernel_thread(&real_kworker.name, &real_kworker.command_line));
+        assert!(!is_kernel_thread_impostor(&real_kworker));
+
+        let impostor = ProcessInfo {
+            pid: 4242,
+            ppid: 1,
+            name: "kworker/u8:2".to_string(),
+            binary_path: "/tmp/.x/kworker".to_string(),
+            command_line: "[kworker/u8:2]".to_string(),
+            exe_resolves: true,
+            cpu_percent: 95.0,
+            ..Default::default()
+        };
+        assert!(is_kernel_thread_impostor(&impostor));
+
+        let nginx = ProcessInfo {
+            ppid: 1,
+            name: "nginx".to_string(),
+            command_line: "nginx: worker process".to_string(),
+            exe_resolves: true,
+            ..Default::default()
+        };
+        assert!(!is_kernel_thread_impostor(&nginx));
+    }
+
+    #[test]
+    fn exited_processes_are_told_apart_from_exe_less_ones() {
+        assert_eq!(classify_exe(true, true), ExeState::Known);
+        assert_eq!(classify_exe(false, true), ExeState::NoExecutable);
+        assert_eq!(classify_exe(true, false), ExeState::Vanished);
+        assert_eq!(classify_exe(false, false), ExeState::Vanished);
+
+        let kworker = ProcessInfo { exe_state: ExeState::NoExecutable, ..Default::default() };
+        assert!(!kworker.is_complete());
+        // Replayed snapshots predate the field and count as complete
+        let replayed: ProcessInfo = serde_json::from_str(r#"{"pid": 1, "binary_path": "/usr/bin/node"}"#).unwrap();
+        assert!(replayed.is_complete());
+    }
+
+    #[test]
+    fn parses_tty_nr_from_stat() {
+        // Daemonized: no controlling terminal
+        assert_eq!(parse_tty_nr("4242 (kdevtmpfsi) S 1 4242 4242 0 -1 4194560 120 0"), Some(0));
+        // Interactive shell on /dev/pts/1, with a comm containing spaces and parens
+        assert_eq!(parse_tty_nr("777 (my (odd) sh) S 700 777 700 34817 777 4194304"), Some(34817));
+        assert_eq!(parse_tty_nr("garbage"), None);
+    }
+
+    #[test]
+    fn parses_cgroup_paths() {
+        let v2 = "0::/kubepods.slice/kubepods-besteffort.slice/cri-containerd-4f2a.scope\n";
+        assert_eq!(parse_cgroup_path(v2).as_deref(), Some("/kubepods.slice/kubepods-besteffort.slice/cri-containerd-4f2a.scope"));
+        let v1 = "12:pids:/docker/abc\n4:cpu,cpuacct:/docker/abc\n1:name=systemd:/docker/abc\n";
+        assert_eq!(parse_cgroup_path(v1).as_deref(), Some("/docker/abc"));
+        assert_eq!(parse_cgroup_path("garbage"), None);
+    }
+
+    #[test]
+    fn detects_memfd_exe_targets() {
+        assert!(is_memfd_exe("/memfd:payload (deleted)"));
+        assert!(is_memfd_exe("/memfd: (deleted)"));
+        assert!(is_memfd_exe("memfd:x"));
+        assert!(!is_memfd_exe("/usr/bin/node"));
+        assert!(!is_memfd_exe("/tmp/memfd:fake"));
+        assert!(!is_memfd_exe("unknown"));
+    }
+
+    #[test]
+    fn flags_rewritten_argv() {
+        let spoofed = |name: &str, exe: &str, seen: &str, raw: &[u8]| cmdline_spoof_reason(name, exe, seen, raw).is_some();
+
+        // Legitimate titles and renames
+        assert!(!spoofed("nginx", "/usr/sbin/nginx", "nginx: master process /usr/sbin/nginx", b"nginx: master process /usr/sbin/nginx\0\0\0"));
+        assert!(!spoofed("php-fpm8.1", "/usr/sbin/php-fpm8.1", "php-fpm: pool www", b"php-fpm: pool www\0"));
+        assert!(!spoofed("PM2 v5.3.0: God", "/usr/bin/node", "PM2 v5.3.0: God Daemon (/root/.pm2)", b"PM2 v5.3.0: God Daemon (/root/.pm2)\0"));
+        assert!(!spoofed("backup.sh", "/usr/bin/bash", "/bin/bash /opt/backup.sh --full", b"/bin/bash\0/opt/backup.sh\0--full\0"));
+        assert!(!spoofed("very-long-daem", "/opt/very-long-daemon-name", "", b"/opt/very-long-daemon-name\0"));
+        // Titles set after the sysinfo refresh that saw the original argv
+        assert!(!spoofed("postgres", "/usr/lib/postgresql/15/bin/postgres", "/usr/lib/postgresql/15/bin/postgres -D /var/lib/postgresql/15/main",
+                         b"postgres: 15/main: checkpointer \0"));
+        assert!(!spoofed("sshd", "/usr/sbin/sshd", "/usr/sbin/sshd -D", b"sshd: deploy [priv]\0"));
+        assert!(!spoofed("php-fpm8.1", "/usr/sbin/php-fpm8.1", "php-fpm: master process (/etc/php/8.1/fpm/php-fpm.conf)", b"php-fpm: pool www\0"));
+        // The same names outside the system directories are not exempt
+        assert!(spoofed("sshd", "/tmp/.x/sshd", "/tmp/.x/sshd -D", b"sshd: deploy [priv]\0"));
+
+        // comm claims nginx, argv and exe say otherwise
+        assert!(spoofed("nginx", "/tmp/.x/xmrig", "", b"/usr/sbin/sshd -D\0"));
+        // Wiped argv on a process with a real executable
+        assert!(spoofed("nginx", "/tmp/.x/xmrig", "", b"\0\0\0"));
+        assert!(spoofed("nginx", "/tmp/.x/xmrig", "", b""));
+        // argv rewritten after the refresh that produced the sysinfo view
+        assert!(spoofed("xmrig", "/tmp/.x/xmrig", "./xmrig -o pool:3333", b"xmrig [kworker/0:1]\0"));
+    }
+
+    #[test]
+    fn idle_processes_reuse_their_fd_snapshot() {
+        let read_at = Instant::now();
+        let snapshot = FdSnapshot {
+            start_time: 100, read_at, fd_count: 12, socket_count: 1, listening_ports: vec![3000], remote_addrs: Vec::new(),
+        };
+        assert!(snapshot.is_current(100, 0.0, read_at + Duration::from_secs(5)));
+        // Busy since the last refresh, a reused PID, or simply read too long ago
+        assert!(!snapshot.is_current(100, 0.5, read_at + Duration::from_secs(5)));
+        assert!(!snapshot.is_current(200, 0.0, read_at + Duration::from_secs(5)));
+        assert!(!snapshot.is_current(100, 0.0, read_at + FD_SNAPSHOT_MAX_AGE));
+
+        // A live process is read once, then served from the cache while idle
+        let monitor = ProcessMonitor::new();
+        let pid = std::process::id() as i32;
+        let mut sockets = SocketInodeCache::default();
+        let first = monitor.fd_snapshot(pid, 7, 0.0, &mut sockets);
+        let again = monitor.fd_snapshot(pid, 7, 0.0, &mut sockets);
+        assert_eq!(first.read_at, again.read_at);
+        assert!(monitor.fd_snapshot(pid, 7, 3.0, &mut sockets).read_at > first.read_at);
+    }
+
+    #[test]
+    fn finds_listening_ports() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:D2F0 0100007F:0CEA 01 00000000:00000000 00:00000000 00000000  1000        0 42424 1 0000000000000000 20 4 30 10 -1
+";
+        assert_eq!(parse_listening_sockets(tcp).collect::<Vec<_>>(), vec![(31337, 3306)]);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let pid = std::process::id() as i32;
+        let mut cache = SocketInodeCache::default();
+        let ports = listening_ports(&read_fd_targets(pid).unwrap(), &cache.sockets_for(pid).listening);
+        assert!(ports.contains(&port), "{} not in {:?}", port, ports);
+    }
+
+    #[test]
+    fn finds_established_peers() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0A00000A:D2F0 2A5E0B8D:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 42424 1 0000000000000000 20 4 30 10 -1
+";
+        let tcp6 = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0000000000000000FFFF00000A00000A:D2F2 0000000000000000FFFF00002A5E0B8D:0D05 01 00000000:00000000 00:00000000 00000000  1000        0 51515 1 0000000000000000 20 4 30 10 -1
+";
+        if cfg!(target_endian = "little") {
+            let peer = |port| SocketAddr::new("141.11.94.42".parse().unwrap(), port);
+            assert_eq!(parse_established_peers(tcp).collect::<Vec<_>>(), vec![(42424, peer(443))]);
+            assert_eq!(parse_established_peers(tcp6).collect::<Vec<_>>(), vec![(51515, peer(3333))]);
+        }
+
+        let established = HashMap::from([(42424, "141.11.94.42:443".parse().unwrap())]);
+        let fds = [PathBuf::from("socket:[42424]"), PathBuf::from("socket:[42424]"), PathBuf::from("/dev/null")];
+        assert_eq!(remote_addrs(&fds, &established), vec!["141.11.94.42:443".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn counts_only_inet_sockets() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:D2F0 0100007F:0CEA 01 00000000:00000000 00:00000000 00000000  1000        0 42424 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:D2F2 0100007F:0CEA 06 00000000:00000000 03:00000F2A 00000000     0        0 0 3 0000000000000000
+";
+        let inodes: HashSet<u64> = parse_net_inodes(tcp).collect();
+        assert_eq!(inodes, HashSet::from([31337, 42424]));
+
+        let targets: Vec<PathBuf> = ["socket:[31337]", "socket:[42424]", "socket:[999]", "/dev/null", "pipe:[5]"]
+            .iter().map(PathBuf::from).collect();
+        // socket:[999] is a unix socket: not in the inet tables
+        assert_eq!(count_inet_sockets(&targets, &inodes), 2);
+        assert_eq!(socket_inode(std::path::Path::new("socket:[7]")), Some(7));
+        assert_eq!(socket_inode(std::path::Path::new("anon_inode:[eventfd]")), None);
+    }
+}
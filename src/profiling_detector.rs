@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use crate::config::MinerProfilingConfig;
+use crate::process_monitor::ProcessInfo;
+use crate::suspicious_paths::is_suspicious_path;
+
+/// Files miners open to size their thread pools and pick algorithms
+const PROFILING_PATHS: &[&str] = &[
+    "/proc/cpuinfo",
+    "/sys/devices/system/cpu",
+    "/sys/devices/system/node",
+    "/proc/sys/vm/nr_hugepages",
+];
+
+/// Hardware-topology and cpuid libraries mapped into the process
+const PROFILING_LIBS: &[&str] = &["libhwloc", "libcpuid", "libmsr"];
+
+/// Evidence that a young process profiled the hardware
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilingSignal {
+    pub pid: i32,
+    pub evidence: Vec<String>,
+}
+
+/// Pre-arms confidence for young processes from staging directories that
+/// inspect CPU topology before they start burning CPU. Reading other users'
+/// /proc/<pid>/fd and maps needs root, so this is opt-in.
+pub struct ProfilingDetector {
+    config: MinerProfilingConfig,
+    armed: HashMap<(i32, u64), ProfilingSignal>, // (pid, start_time) -> signal
+}
+
+impl ProfilingDetector {
+    pub fn new(config: MinerProfilingConfig) -> Self {
+        Self {
+            config,
+            armed: HashMap::new(),
+        }
+    }
+
+    /// Probe new, still-quiet processes from writable locations
+    pub fn observe(&mut self, processes: &[ProcessInfo], now_secs: u64, cpu_threshold: f32) {
+        // Forget processes that exited
+        self.armed.retain(|(pid, start), _| {
+            processes.iter().any(|p| p.pid == *pid && p.start_time == *start)
+        });
+
+        for process in processes {
+            let key = (process.pid, process.start_time);
+            if self.armed.contains_key(&key)
+                || now_secs.saturating_sub(process.start_time) > self.config.new_process_seconds
+                || process.cpu_percent >= cpu_threshold
+                || !is_suspicious_path(&process.binary_path)
+            {
+                continue;
+            }
+
+            let fd_targets = read_fd_targets(process.pid);
+            let maps = std::fs::read_to_string(format!("/proc/{}/maps", process.pid)).unwrap_or_default();
+            let evidence = profiling_evidence(&fd_targets, &maps);
+            if !evidence.is_empty() {
+                info!("🧭 PID {} ({}) profiled hardware shortly after start: {}",
+                      process.pid, process.binary_path, evidence.join(", "));
+                self.armed.insert(key, ProfilingSignal { pid: process.pid, evidence });
+            }
+        }
+    }
+
+    /// Evidence for a process that was seen profiling; the daemon adds `confidence_boost` for it
+    pub fn signal_for(&self, process: &ProcessInfo) -> Option<&ProfilingSignal> {
+        self.armed.get(&(process.pid, process.start_time))
+    }
+}
+
+fn read_fd_targets(pid: i32) -> Vec<String> {
+    let Some(targets) = crate::process_monitor::read_fd_targets(pid) else {
+        debug!("Cannot read fds of PID {} (insufficient privileges?)", pid);
+        return Vec::new();
+    };
+    targets.into_iter()
+        .map(|target| target.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Profiling interfaces among open files and mapped libraries
+pub fn profiling_evidence(fd_targets: &[String], maps: &str) -> Vec<String> {
+    let mut evidence = Vec::new();
+    for target in fd_targets {
+        if PROFILING_PATHS.iter().any(|p| target.starts_with(p)) && !evidence.contains(target) {
+            evidence.push(target.clone());
+        }
+    }
+    for lib in PROFILING_LIBS {
+        if maps.lines().any(|line| line.contains(lib)) {
+            evidence.push(format!("{} mapped", lib));
+        }
+    }
+    evidence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_profiling_evidence() {
+        let fds = vec![
+            "/dev/null".to_string(),
+            "/proc/cpuinfo".to_string(),
+            "/sys/devices/system/cpu/cpu0/cache/index3/size".to_string(),
+            "socket:[1234]".to_string(),
+        ];
+        let maps = "7f00-7f10 r-xp 00000000 08:01 42 /tmp/.x/libhwloc.so.15\n";
+        let evidence = profiling_evidence(&fds, maps);
+        assert_eq!(evidence, vec![
+            "/proc/cpuinfo".to_string(),
+            "/sys/devices/system/cpu/cpu0/cache/index3/size".to_string(),
+            "libhwloc mapped".to_string(),
+        ]);
+        assert!(profiling_evidence(&["/var/log/app.log".to_string()], "").is_empty());
+    }
+
+    #[test]
+    fn only_armed_processes_have_a_signal() {
+        let mut detector = ProfilingDetector::new(MinerProfilingConfig::default());
+        let process = ProcessInfo { pid: 7, start_time: 100, ..Default::default() };
+        assert!(detector.signal_for(&process).is_none());
+
+        detector.armed.insert((7, 100), ProfilingSignal { pid: 7, evidence: vec!["/proc/cpuinfo".to_string()] });
+        assert_eq!(detector.signal_for(&process).unwrap().evidence, vec!["/proc/cpuinfo".to_string()]);
+        // Reused PID is a different process
+        assert!(detector.signal_for(&ProcessInfo { start_time: 200, ..process.clone() }).is_none());
+
+        // Exited processes are forgotten
+        detector.observe(&[], 150, 20.0);
+        assert!(detector.signal_for(&process).is_none());
+    }
+}
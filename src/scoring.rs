@@ -0,0 +1,511 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Command-line fragments that suggest mining or a dropper (matched as plain substrings)
+const SUSPICIOUS_COMMAND_PATTERNS: &[&str] = &[
+    "miner", "xmrig", "crypto", "mining", "ccminer", "cpuminer",
+    "stratum", "pool", "hashrate", "rig", "gpu", "cuda",
+    "base64", "eval", "exec", "wget.*sh", "curl.*sh",
+    r"\.sh.*\|", "bash.*-c", "sh.*-c",
+];
+
+/// Everything `score_process` looks at, gathered up front so scoring needs no I/O
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSignals {
+    pub cpu_percent: f32,
+    pub duration_seconds: u64,
+    /// Multiplier for CPU thresholds (build users get > 1.0)
+    pub cpu_threshold_scale: f32,
+    /// Multiplier for the long-running threshold (build users get > 1.0)
+    pub duration_scale: f32,
+    pub system_binary: bool,      // /usr/bin, /usr/sbin, /bin, /sbin
+    pub unusual_location: bool,   // Under a suspicious_path_prefixes/substrings location
+    pub suspicious_command: bool,
+    pub non_init_parent: bool,
+    pub memory_mb: u64,
+    pub fd_count: usize,
+    pub socket_count: usize,
+    pub suspicious_env: bool,
+    pub kernel_impostor: bool,
+    pub fileless: bool,
+    pub cmdline_spoofed: bool,  // argv rewritten to disagree with comm/exe
+    pub foreign_home: bool,  // Root/system process running code from a regular user's home
+    pub daemonized: bool,  // No TTY, recently started and in a writable location (CPU is checked when scoring)
+    pub web_upload: bool,  // Executable under a web upload directory
+    pub web_upload_recent: bool,  // ... and the file changed within web_uploads.recent_minutes
+    pub suspicious_listener: bool,  // Listens from a writable location, or on a privileged port as non-root
+    pub listener_port: bool,  // ... on a port miners' stratum proxies and C2 favour
+    pub pool_connection: bool,  // Connected to a pool named in a miner config we found
+    /// Pre-computed boosts from detectors with their own tuning
+    pub payload_confidence: f32,
+    pub thread_fingerprint_boost: f32,
+}
+
+impl Default for ProcessSignals {
+    fn default() -> Self {
+        Self {
+            cpu_percent: 0.0,
+            duration_seconds: 0,
+            cpu_threshold_scale: 1.0,
+            duration_scale: 1.0,
+            system_binary: false,
+            unusual_location: false,
+            suspicious_command: false,
+            non_init_parent: false,
+            memory_mb: 0,
+            fd_count: 0,
+            socket_count: 0,
+            suspicious_env: false,
+            kernel_impostor: false,
+            fileless: false,
+            cmdline_spoofed: false,
+            foreign_home: false,
+            daemonized: false,
+            web_upload: false,
+            web_upload_recent: false,
+            suspicious_listener: false,
+            listener_port: false,
+            pool_connection: false,
+            payload_confidence: 0.0,
+            thread_fingerprint_boost: 0.0,
+        }
+    }
+}
+
+/// Thresholds and weights for `score_process`. The defaults reproduce the
+/// historical heuristics; memory and fd/socket scoring are off unless their thresholds are set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoringWeights {
+    pub cpu_high_percent: f32,
+    pub cpu_high: f32,
+    pub cpu_medium_percent: f32,
+    pub cpu_medium: f32,
+    pub long_running_seconds: u64,
+    pub long_running: f32,
+    pub system_binary_factor: f32,  // Scales the CPU/duration score of system binaries
+    pub suspicious_command: f32,
+    pub unusual_location: f32,
+    pub non_init_parent: f32,
+    pub high_memory_mb: u64,  // 0 disables
+    pub high_memory: f32,
+    pub high_fd_count: usize,  // 0 disables
+    pub high_fd: f32,
+    pub high_socket_count: usize,  // 0 disables
+    pub high_socket: f32,
+    pub loader_env: f32,
+    pub kernel_impostor: f32,
+    pub fileless: f32,
+    pub cmdline_spoofed: f32,
+    pub foreign_home: f32,
+    pub daemonized: f32,  // Applies only with CPU above cpu_medium_percent
+    pub web_upload: f32,
+    pub web_upload_recent: f32,  // On top of web_upload
+    pub suspicious_listener: f32,
+    pub listener_port: f32,  // On top of suspicious_listener
+    pub pool_connection: f32,
+    pub restart: f32,  // Known binary seen again under a new PID
+    pub respawn: f32,  // Known binary spawned more than respawn_count times
+    pub respawn_count: i32,
+    pub prior_kill: f32,  // Binary we've killed before is running again
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            cpu_high_percent: 30.0,
+            cpu_high: 0.4,
+            cpu_medium_percent: 20.0,
+            cpu_medium: 0.3,
+            long_running_seconds: 600,
+            long_running: 0.2,
+            system_binary_factor: 0.3,
+            suspicious_command: 0.2,
+            unusual_location: 0.2,
+            non_init_parent: 0.1,
+            high_memory_mb: 0,
+            high_memory: 0.1,
+            high_fd_count: 0,
+            high_fd: 0.1,
+            high_socket_count: 0,
+            high_socket: 0.2,
+            loader_env: 0.3,
+            kernel_impostor: 0.6,
+            fileless: 0.7,
+            cmdline_spoofed: 0.4,
+            foreign_home: 0.3,
+            daemonized: 0.2,
+            web_upload: 0.25,
+            web_upload_recent: 0.15,
+            suspicious_listener: 0.3,
+            listener_port: 0.2,
+            pool_connection: 0.5,
+            restart: 0.2,
+            respawn: 0.1,
+            respawn_count: 3,
+            prior_kill: 0.4,
+        }
+    }
+}
+
+/// Independent kinds of evidence against a process. `require_corroboration`
+/// counts distinct categories, so two CPU thresholds never corroborate each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalCategory {
+    CpuAbuse,  // Sustained high CPU or long runtime
+    SuspiciousLocation,
+    MiningCommand,
+    LoaderEnv,
+    KernelImpostor,
+    Fileless,
+    ArgvSpoof,
+    EncodedPayload,
+    ThreadFingerprint,
+    ResourceUsage,  // Memory, descriptors or sockets over threshold
+    PrivilegeMismatch,
+    NpmInfection,
+    ReactAbuse,
+    MinerProfiling,
+    MiningMemory,  // Mining protocol strings in anonymous memory
+    WebExploit,  // Started right after exploitation attempts in Nginx logs
+    SuspiciousListener,  // Accepts connections from a staging directory or without the right to
+    PoolConnection,  // Talks to a mining pool taken from a miner's config
+    SpawnChain,  // Run by a shell that a web-facing daemon started
+}
+
+/// Categories among `signals` that count as positive evidence under `weights`
+pub fn signal_categories(signals: &ProcessSignals, weights: &ScoringWeights) -> BTreeSet<SignalCategory> {
+    let mut categories = BTreeSet::new();
+    let cpu = if signals.cpu_percent.is_finite() { signals.cpu_percent } else { 0.0 };
+    let long_running = (weights.long_running_seconds as f64 * signals.duration_scale as f64) as u64;
+
+    if cpu > weights.cpu_medium_percent * signals.cpu_threshold_scale || signals.duration_seconds > long_running {
+        categories.insert(SignalCategory::CpuAbuse);
+    }
+    if signals.unusual_location || (signals.web_upload && weights.web_upload > 0.0) {
+        categories.insert(SignalCategory::SuspiciousLocation);
+    }
+    if signals.suspicious_command {
+        categories.insert(SignalCategory::MiningCommand);
+    }
+    if signals.suspicious_env {
+        categories.insert(SignalCategory::LoaderEnv);
+    }
+    if signals.kernel_impostor {
+        categories.insert(SignalCategory::KernelImpostor);
+    }
+    if signals.fileless {
+        categories.insert(SignalCategory::Fileless);
+    }
+    if signals.cmdline_spoofed {
+        categories.insert(SignalCategory::ArgvSpoof);
+    }
+    if signals.foreign_home {
+        categories.insert(SignalCategory::PrivilegeMismatch);
+    }
+    if signals.suspicious_listener && weights.suspicious_listener > 0.0 {
+        categories.insert(SignalCategory::SuspiciousListener);
+    }
+    if signals.pool_connection && weights.pool_connection > 0.0 {
+        categories.insert(SignalCategory::PoolConnection);
+    }
+    if finite_or_zero(signals.payload_confidence) > 0.0 {
+        categories.insert(SignalCategory::EncodedPayload);
+    }
+    if finite_or_zero(signals.thread_fingerprint_boost) > 0.0 {
+        categories.insert(SignalCategory::ThreadFingerprint);
+    }
+    if (weights.high_memory_mb > 0 && signals.memory_mb >= weights.high_memory_mb)
+        || (weights.high_fd_count > 0 && signals.fd_count >= weights.high_fd_count)
+        || (weights.high_socket_count > 0 && signals.socket_count >= weights.high_socket_count)
+    {
+        categories.insert(SignalCategory::ResourceUsage);
+    }
+
+    categories
+}
+
+/// Confidence in 0.0..=1.0 that a process is abusive, from signals alone
+pub fn score_process(signals: &ProcessSignals, weights: &ScoringWeights) -> f32 {
+    let cpu = if signals.cpu_percent.is_finite() { signals.cpu_percent } else { 0.0 };
+    let mut confidence = 0.0;
+
+    if cpu > weights.cpu_high_percent * signals.cpu_threshold_scale {
+        confidence += weights.cpu_high;
+    } else if cpu > weights.cpu_medium_percent * signals.cpu_threshold_scale {
+        confidence += weights.cpu_medium;
+    }
+
+    let long_running = (weights.long_running_seconds as f64 * signals.duration_scale as f64) as u64;
+    if signals.duration_seconds > long_running {
+        confidence += weights.long_running;
+    }
+
+    // Reduce but don't eliminate for system binaries
+    if signals.system_binary {
+        confidence *= weights.system_binary_factor;
+    }
+
+    if signals.suspicious_command {
+        confidence += weights.suspicious_command;
+    }
+    if signals.unusual_location {
+        confidence += weights.unusual_location;
+    }
+    if signals.non_init_parent {
+        confidence += weights.non_init_parent;
+    }
+    if weights.high_memory_mb > 0 && signals.memory_mb >= weights.high_memory_mb {
+        confidence += weights.high_memory;
+    }
+    // Bots and some miners hold far more descriptors/sockets than typical services
+    if weights.high_fd_count > 0 && signals.fd_count >= weights.high_fd_count {
+        confidence += weights.high_fd;
+    }
+    if weights.high_socket_count > 0 && signals.socket_count >= weights.high_socket_count {
+        confidence += weights.high_socket;
+    }
+
+    clamp_confidence(confidence + indicator_score(signals, weights))
+}
+
+/// Strong indicators that apply regardless of history or CPU use
+pub fn indicator_score(signals: &ProcessSignals, weights: &ScoringWeights) -> f32 {
+    let mut score = 0.0;
+    if signals.suspicious_env {
+        score += weights.loader_env;
+    }
+    if signals.kernel_impostor {
+        score += weights.kernel_impostor;
+    }
+    if signals.fileless {
+        score += weights.fileless;
+    }
+    if signals.cmdline_spoofed {
+        score += weights.cmdline_spoofed;
+    }
+    if signals.foreign_home {
+        score += weights.foreign_home;
+    }
+    // Dropped and daemonized, and already climbing in CPU
+    let cpu = finite_or_zero(signals.cpu_percent);
+    if signals.daemonized && cpu > weights.cpu_medium_percent * signals.cpu_threshold_scale {
+        score += weights.daemonized;
+    }
+    // Uploads are data; something executing from there came in through the web app
+    if signals.web_upload {
+        score += weights.web_upload;
+        if signals.web_upload_recent {
+            score += weights.web_upload_recent;
+        }
+    }
+    // Dropped binaries opening listeners: stratum proxies, bind shells, C2 relays
+    if signals.suspicious_listener {
+        score += weights.suspicious_listener;
+        if signals.listener_port {
+            score += weights.listener_port;
+        }
+    }
+    // Whatever the binary is called, it's mining to a pool we've already caught a miner using
+    if signals.pool_connection {
+        score += weights.pool_connection;
+    }
+    score + finite_or_zero(signals.payload_confidence) + finite_or_zero(signals.thread_fingerprint_boost)
+}
+
+pub fn clamp_confidence(confidence: f32) -> f32 {
+    finite_or_zero(confidence).clamp(0.0, 1.0)
+}
+
+fn finite_or_zero(value: f32) -> f32 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+pub fn is_suspicious_command(command_line: &str) -> bool {
+    let cmd_lower = command_line.to_lowercase();
+    SUSPICIOUS_COMMAND_PATTERNS.iter().any(|p| cmd_lower.contains(p))
+}
+
+pub fn is_system_binary(binary_path: &str) -> bool {
+    ["/usr/bin/", "/usr/sbin/", "/bin/", "/sbin/"]
+        .iter()
+        .any(|prefix| binary_path.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(signals: ProcessSignals) -> f32 {
+        score_process(&signals, &ScoringWeights::default())
+    }
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn cpu_thresholds_are_strict() {
+        assert_eq!(score(ProcessSignals { cpu_percent: 20.0, ..Default::default() }), 0.0);
+        assert!(approx(score(ProcessSignals { cpu_percent: 20.01, ..Default::default() }), 0.3));
+        assert!(approx(score(ProcessSignals { cpu_percent: 30.0, ..Default::default() }), 0.3));
+        assert!(approx(score(ProcessSignals { cpu_percent: 30.01, ..Default::default() }), 0.4));
+    }
+
+    #[test]
+    fn build_user_scaling_raises_thresholds() {
+        let scaled = ProcessSignals { cpu_percent: 50.0, cpu_threshold_scale: 2.0, ..Default::default() };
+        assert!(approx(score(scaled.clone()), 0.3));
+        assert!(approx(score(ProcessSignals { cpu_percent: 60.01, ..scaled }), 0.4));
+
+        let long = ProcessSignals { duration_seconds: 900, ..Default::default() };
+        assert!(approx(score(long.clone()), 0.2));
+        assert_eq!(score(ProcessSignals { duration_scale: 3.0, ..long }), 0.0);
+        assert_eq!(score(ProcessSignals { duration_seconds: 600, ..Default::default() }), 0.0);
+    }
+
+    #[test]
+    fn system_binaries_are_discounted_before_other_signals() {
+        let busy = ProcessSignals { cpu_percent: 95.0, duration_seconds: 3600, ..Default::default() };
+        assert!(approx(score(busy.clone()), 0.6));
+        assert!(approx(score(ProcessSignals { system_binary: true, ..busy.clone() }), 0.18));
+        // Command flags are not discounted
+        assert!(approx(score(ProcessSignals { system_binary: true, suspicious_command: true, ..busy }), 0.38));
+    }
+
+    #[test]
+    fn daemonized_composite_needs_cpu_too() {
+        let dropped = ProcessSignals { daemonized: true, ..Default::default() };
+        assert_eq!(indicator_score(&dropped, &ScoringWeights::default()), 0.0);
+
+        let climbing = ProcessSignals { cpu_percent: 25.0, ..dropped };
+        assert!(approx(indicator_score(&climbing, &ScoringWeights::default()), 0.2));
+        // CPU 0.3 plus the composite
+        assert!(approx(score(climbing.clone()), 0.5));
+        assert!(approx(score(ProcessSignals { daemonized: false, ..climbing.clone() }), 0.3));
+
+        let disabled = ScoringWeights { daemonized: 0.0, ..Default::default() };
+        assert_eq!(indicator_score(&climbing, &disabled), 0.0);
+        // Build users' scaled CPU threshold applies to the composite as well
+        assert_eq!(indicator_score(&ProcessSignals { cpu_threshold_scale: 2.0, ..climbing }, &ScoringWeights::default()), 0.0);
+    }
+
+    #[test]
+    fn clamps_to_unit_interval() {
+        let everything = ProcessSignals {
+            cpu_percent: 400.0,
+            duration_seconds: u64::MAX,
+            unusual_location: true,
+            suspicious_command: true,
+            non_init_parent: true,
+            suspicious_env: true,
+            kernel_impostor: true,
+            fileless: true,
+            payload_confidence: 0.5,
+            thread_fingerprint_boost: 0.25,
+            ..Default::default()
+        };
+        assert_eq!(score(everything), 1.0);
+
+        let negative = ScoringWeights { unusual_location: -5.0, ..Default::default() };
+        assert_eq!(score_process(&ProcessSignals { unusual_location: true, ..Default::default() }, &negative), 0.0);
+
+        assert_eq!(score(ProcessSignals { cpu_percent: f32::NAN, payload_confidence: f32::INFINITY, ..Default::default() }), 0.0);
+        assert_eq!(score(ProcessSignals::default()), 0.0);
+    }
+
+    #[test]
+    fn memory_scoring_is_opt_in() {
+        let hungry = ProcessSignals { memory_mb: 4096, ..Default::default() };
+        assert_eq!(score(hungry.clone()), 0.0);
+        let weights = ScoringWeights { high_memory_mb: 2048, ..Default::default() };
+        assert!(approx(score_process(&hungry, &weights), 0.1));
+        assert_eq!(score_process(&ProcessSignals { memory_mb: 2047, ..hungry }, &weights), 0.0);
+    }
+
+    #[test]
+    fn fd_and_socket_scoring_uses_thresholds() {
+        let bot = ProcessSignals { fd_count: 5000, socket_count: 4000, ..Default::default() };
+        assert_eq!(score(bot.clone()), 0.0);
+
+        let weights = ScoringWeights { high_fd_count: 1024, high_socket_count: 256, ..Default::default() };
+        assert!(approx(score_process(&bot, &weights), 0.3));
+        let at_threshold = ProcessSignals { fd_count: 1024, socket_count: 255, ..Default::default() };
+        assert!(approx(score_process(&at_threshold, &weights), 0.1));
+    }
+
+    #[test]
+    fn foreign_home_code_raises_confidence() {
+        let signals = ProcessSignals { foreign_home: true, ..Default::default() };
+        assert!(approx(score(signals.clone()), 0.3));
+        // Strong indicator: survives the system-binary discount and repeat-offender scoring
+        assert!(approx(indicator_score(&ProcessSignals { system_binary: true, ..signals }, &ScoringWeights::default()), 0.3));
+    }
+
+    #[test]
+    fn spoofed_cmdline_is_a_strong_indicator() {
+        let signals = ProcessSignals { cmdline_spoofed: true, system_binary: true, ..Default::default() };
+        assert!(approx(score(signals.clone()), 0.4));
+        assert_eq!(signal_categories(&signals, &ScoringWeights::default()).into_iter().collect::<Vec<_>>(), vec![SignalCategory::ArgvSpoof]);
+    }
+
+    #[test]
+    fn suspicious_listener_scores_more_on_mining_ports() {
+        let listener = ProcessSignals { suspicious_listener: true, ..Default::default() };
+        assert!(approx(score(listener.clone()), 0.3));
+        assert!(approx(score(ProcessSignals { listener_port: true, ..listener.clone() }), 0.5));
+        // A known port alone, without a suspicious listener, is not evidence
+        assert_eq!(score(ProcessSignals { listener_port: true, ..Default::default() }), 0.0);
+        assert_eq!(signal_categories(&listener, &ScoringWeights::default()).into_iter().collect::<Vec<_>>(),
+                   vec![SignalCategory::SuspiciousListener]);
+
+        let disabled = ScoringWeights { suspicious_listener: 0.0, listener_port: 0.0, ..Default::default() };
+        assert!(signal_categories(&listener, &disabled).is_empty());
+    }
+
+    #[test]
+    fn pool_connection_is_its_own_evidence() {
+        let connected = ProcessSignals { pool_connection: true, system_binary: true, ..Default::default() };
+        assert!(approx(score(connected.clone()), 0.5));
+        assert_eq!(signal_categories(&connected, &ScoringWeights::default()).into_iter().collect::<Vec<_>>(),
+                   vec![SignalCategory::PoolConnection]);
+        let disabled = ScoringWeights { pool_connection: 0.0, ..Default::default() };
+        assert!(signal_categories(&connected, &disabled).is_empty());
+    }
+
+    #[test]
+    fn collects_independent_signal_categories() {
+        let weights = ScoringWeights { high_fd_count: 1024, high_socket_count: 256, ..Default::default() };
+        assert!(signal_categories(&ProcessSignals::default(), &weights).is_empty());
+
+        // CPU and runtime are one category; sockets and fds another
+        let miner = ProcessSignals {
+            cpu_percent: 95.0,
+            duration_seconds: 3600,
+            unusual_location: true,
+            suspicious_command: true,
+            fd_count: 2000,
+            socket_count: 300,
+            ..Default::default()
+        };
+        let categories = signal_categories(&miner, &weights);
+        assert_eq!(categories.into_iter().collect::<Vec<_>>(), vec![
+            SignalCategory::CpuAbuse,
+            SignalCategory::SuspiciousLocation,
+            SignalCategory::MiningCommand,
+            SignalCategory::ResourceUsage,
+        ]);
+
+        // Build-user scaling applies to the CPU category as it does to the score
+        let build = ProcessSignals { cpu_percent: 30.0, cpu_threshold_scale: 2.0, ..Default::default() };
+        assert!(signal_categories(&build, &weights).is_empty());
+    }
+
+    #[test]
+    fn classifies_paths_and_commands() {
+        assert!(is_system_binary("/usr/sbin/nginx"));
+        assert!(!is_system_binary("/usr/local/bin/app"));
+        assert!(is_suspicious_command("./XMRig -o stratum+tcp://pool:3333"));
+        assert!(!is_suspicious_command("node dist/main.js"));
+    }
+}
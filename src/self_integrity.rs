@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Detected change to one of hora-police's own files
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityChange {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: Option<String>, // None if the file is gone or unreadable
+}
+
+/// Baseline hashes of the running binary and its config, re-verified on a timer
+pub struct SelfIntegrity {
+    files: Vec<(PathBuf, String)>,
+    upgrade_marker: PathBuf,
+    interval: Duration,
+    last_check: Instant,
+}
+
+impl SelfIntegrity {
+    /// Hash `/proc/self/exe` (by its on-disk path) and the loaded config file
+    pub fn new(config_path: &Path, upgrade_marker: PathBuf, interval: Duration) -> Result<Self> {
+        let exe_path = std::fs::read_link("/proc/self/exe")
+            .context("Failed to resolve /proc/self/exe")?;
+        Self::with_files(&[exe_path, config_path.to_path_buf()], upgrade_marker, interval)
+    }
+
+    pub fn with_files(paths: &[PathBuf], upgrade_marker: PathBuf, interval: Duration) -> Result<Self> {
+        let mut files = Vec::new();
+        for path in paths {
+            files.push((path.clone(), hash_file(path)?));
+        }
+        Ok(Self {
+            files,
+            upgrade_marker,
+            interval,
+            last_check: Instant::now(),
+        })
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last_check.elapsed() >= self.interval
+    }
+
+    /// Re-hash every baselined file. If the upgrade marker exists the new hashes
+    /// become the baseline and the marker is removed instead of reporting changes.
+    pub fn check(&mut self) -> Vec<IntegrityChange> {
+        self.last_check = Instant::now();
+
+        if self.upgrade_marker.exists() {
+            for (path, hash) in &mut self.files {
+                match hash_file(path) {
+                    Ok(new_hash) => *hash = new_hash,
+                    Err(e) => warn!("Failed to re-baseline {:?}: {}", path, e),
+                }
+            }
+            if let Err(e) = std::fs::remove_file(&self.upgrade_marker) {
+                warn!("Failed to remove upgrade marker {:?}: {}", self.upgrade_marker, e);
+            }
+            info!("🔏 Upgrade marker found, integrity baseline refreshed");
+            return Vec::new();
+        }
+
+        let mut changes = Vec::new();
+        for (path, expected) in &self.files {
+            let actual = hash_file(path).ok();
+            if actual.as_deref() != Some(expected.as_str()) {
+                changes.push(IntegrityChange {
+                    path: path.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        changes
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_changes_and_honours_upgrade_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        let marker = dir.path().join("upgrade");
+        std::fs::write(&config, "cpu_threshold = 20.0\n").unwrap();
+
+        let mut integrity = SelfIntegrity::with_files(std::slice::from_ref(&config), marker.clone(), Duration::ZERO).unwrap();
+        assert!(integrity.is_due());
+        assert!(integrity.check().is_empty());
+
+        std::fs::write(&config, "cpu_threshold = 99.0\n").unwrap();
+        let changes = integrity.check();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, config);
+        assert!(changes[0].actual.is_some());
+
+        // Intentional upgrade: marker accepts the new contents and is consumed
+        std::fs::write(&marker, "").unwrap();
+        assert!(integrity.check().is_empty());
+        assert!(!marker.exists());
+        assert!(integrity.check().is_empty());
+
+        std::fs::remove_file(&config).unwrap();
+        assert_eq!(integrity.check()[0].actual, None);
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Unit of the /proc/<pid>/stat CPU times, falling back to the usual 100 if the
+/// kernel won't say
+fn clock_ticks_per_second() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+/// The daemon's own footprint, so operators can check the watchdog isn't the hog
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SelfMetrics {
+    pub rss_bytes: u64,
+    pub cpu_percent: f32,  // Of one core, averaged over the last monitoring cycle
+    pub open_fds: usize,
+    pub threads: usize,
+    pub loop_iterations: u64,
+    pub loop_latency_ms: u64,  // Work time of the last cycle, excluding the polling sleep
+    pub loop_latency_max_ms: u64,
+    pub enforcement_paused: bool,  // The pause file exists
+}
+
+#[derive(Default)]
+struct State {
+    metrics: SelfMetrics,
+    last_cpu_sample: Option<(u64, Instant)>,  // (utime + stime ticks, when)
+}
+
+/// Shared between the monitoring loop, which records each cycle, and the probe
+/// endpoint, which reads snapshots
+#[derive(Clone, Default)]
+pub struct SelfMetricsHandle {
+    state: Arc<Mutex<State>>,
+}
+
+impl SelfMetricsHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one monitoring cycle and refresh the CPU figure from /proc/self/stat
+    pub fn record_iteration(&self, latency: Duration) {
+        let ticks = fs::read_to_string("/proc/self/stat").ok().and_then(|stat| parse_cpu_ticks(&stat));
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let latency_ms = latency.as_millis() as u64;
+        state.metrics.loop_iterations += 1;
+        state.metrics.loop_latency_ms = latency_ms;
+        state.metrics.loop_latency_max_ms = state.metrics.loop_latency_max_ms.max(latency_ms);
+
+        if let Some(ticks) = ticks {
+            let now = Instant::now();
+            if let Some((prev_ticks, prev_at)) = state.last_cpu_sample {
+                state.metrics.cpu_percent = cpu_percent(ticks.saturating_sub(prev_ticks), now - prev_at, clock_ticks_per_second());
+            }
+            state.last_cpu_sample = Some((ticks, now));
+        }
+    }
+
+    pub fn set_enforcement_paused(&self, paused: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.metrics.enforcement_paused = paused;
+        }
+    }
+
+    /// Current RSS, descriptors and threads plus the loop figures
+    pub fn snapshot(&self) -> SelfMetrics {
+        let mut metrics = self.state.lock().map(|s| s.metrics.clone()).unwrap_or_default();
+        if let Ok(status) = fs::read_to_string("/proc/self/status") {
+            metrics.rss_bytes = parse_status_value(&status, "VmRSS:").unwrap_or(0) * 1024;
+            metrics.threads = parse_status_value(&status, "Threads:").unwrap_or(0) as usize;
+        }
+        metrics.open_fds = fs::read_dir("/proc/self/fd").map(|entries| entries.count()).unwrap_or(0);
+        metrics
+    }
+}
+
+/// Numeric value of a /proc/<pid>/status field, e.g. "VmRSS:" (in kB) or "Threads:"
+pub fn parse_status_value(status: &str, field: &str) -> Option<u64> {
+    status.lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|value| value.parse().ok())
+}
+
+/// utime + stime from /proc/<pid>/stat (fields 14 and 15, counted after the
+/// parenthesised comm, which may itself contain spaces)
+pub fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = after_comm.split_whitespace().collect();
+    // fields[0] is the state (field 3), so utime (field 14) is fields[11]
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+fn cpu_percent(ticks: u64, elapsed: Duration, ticks_per_second: u64) -> f32 {
+    if elapsed.is_zero() || ticks_per_second == 0 {
+        return 0.0;
+    }
+    (ticks as f64 / ticks_per_second as f64 / elapsed.as_secs_f64() * 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "4242 (hora police) S 1 4242 4242 0 -1 4194560 2345 0 12 0 150 50 0 0 20 0 9 0 1234 123456789 4321 18446744073709551615";
+    const STATUS: &str = "Name:\thora-police\nVmPeak:\t  60000 kB\nVmRSS:\t   18432 kB\nThreads:\t9\n";
+
+    #[test]
+    fn parses_proc_self_fields() {
+        assert_eq!(parse_cpu_ticks(STAT), Some(200));
+        assert_eq!(parse_status_value(STATUS, "VmRSS:"), Some(18432));
+        assert_eq!(parse_status_value(STATUS, "Threads:"), Some(9));
+        assert_eq!(parse_status_value(STATUS, "VmSwap:"), None);
+        // 50 ticks at 100 Hz over 5s is a tenth of a core
+        assert_eq!(cpu_percent(50, Duration::from_secs(5), 100), 10.0);
+    }
+
+    #[test]
+    fn snapshot_reports_this_process() {
+        let handle = SelfMetricsHandle::new();
+        handle.record_iteration(Duration::from_millis(30));
+        handle.record_iteration(Duration::from_millis(10));
+
+        let metrics = handle.snapshot();
+        assert_eq!(metrics.loop_iterations, 2);
+        assert_eq!((metrics.loop_latency_ms, metrics.loop_latency_max_ms), (10, 30));
+        assert!(metrics.rss_bytes > 0);
+        assert!(metrics.open_fds > 0);
+        assert!(metrics.threads >= 1);
+    }
+}
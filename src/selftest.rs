@@ -0,0 +1,216 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::database::IntelligenceDB;
+use crate::environment::SystemEnvironment;
+use crate::telegram::TelegramReporter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<&'static str>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into(), hint: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self { name, status: CheckStatus::Warn, detail: detail.into(), hint: Some(hint) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self { name, status: CheckStatus::Fail, detail: detail.into(), hint: Some(hint) }
+    }
+}
+
+/// Exercise the daemon's subsystems in a read-mostly way and report pass/fail per check
+pub async fn run_selftest(config: &Config) -> Vec<CheckResult> {
+    let mut results = vec![check_proc()];
+
+    let (db_result, db) = check_database(&config.database_path).await;
+    results.push(db_result);
+
+    if config.file_scanning.enabled {
+        results.push(check_quarantine_dir(Path::new(&config.file_scanning.quarantine_path)));
+    }
+
+    results.push(check_environment());
+    results.push(check_telegram(config, db).await);
+
+    if config.whitelist.auto_detect {
+        // Hosts without systemd (containers, OpenRC) just lose the unit integration
+        results.push(check_command("systemctl", &["--version"], CheckStatus::Warn,
+            "systemd units won't be auto-whitelisted or stopped; ignore if this host has no systemd"));
+        results.push(check_command("pm2", &["--version"], CheckStatus::Warn,
+            "Install pm2 or ignore if no PM2 apps run on this host"));
+    }
+
+    results
+}
+
+/// Render results as a checklist
+pub fn format_report(results: &[CheckResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let mark = match result.status {
+            CheckStatus::Pass => "✅ PASS",
+            CheckStatus::Warn => "⚠️  WARN",
+            CheckStatus::Fail => "❌ FAIL",
+        };
+        out.push_str(&format!("{} {:<16} {}\n", mark, result.name, result.detail));
+        if let Some(hint) = result.hint {
+            out.push_str(&format!("         ↳ {}\n", hint));
+        }
+    }
+    out
+}
+
+fn check_proc() -> CheckResult {
+    let name = "/proc access";
+    match std::fs::read_dir("/proc") {
+        Ok(entries) => {
+            let pids = entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+                .count();
+            if pids > 1 {
+                CheckResult::pass(name, format!("{} processes visible", pids))
+            } else {
+                CheckResult::fail(name, "only our own process is visible",
+                    "Run as root and make sure /proc is not mounted with hidepid")
+            }
+        }
+        Err(e) => CheckResult::fail(name, e.to_string(), "Mount procfs at /proc"),
+    }
+}
+
+async fn check_database(path: &str) -> (CheckResult, Option<IntelligenceDB>) {
+    let name = "database";
+    if let Some(parent) = Path::new(path).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return (CheckResult::fail(name, format!("{}: {}", parent.display(), e),
+                "Create the database directory or fix database_path"), None);
+        }
+    }
+    match IntelligenceDB::new(path).await {
+        Ok(db) => (CheckResult::pass(name, format!("opened {}", path)), Some(db)),
+        Err(e) => (CheckResult::fail(name, format!("{}: {}", path, e),
+            "Check permissions on database_path (daemon must run as root)"), None),
+    }
+}
+
+fn check_quarantine_dir(dir: &Path) -> CheckResult {
+    let name = "quarantine dir";
+    let probe = dir.join(".hora-police-selftest");
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&probe, b"selftest"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(_) => CheckResult::pass(name, format!("{} is writable", dir.display())),
+        Err(e) => CheckResult::fail(name, format!("{}: {}", dir.display(), e),
+            "Make file_scanning.quarantine_path writable by the daemon"),
+    }
+}
+
+fn check_environment() -> CheckResult {
+    let name = "environment";
+    match SystemEnvironment::detect() {
+        Ok(env) => CheckResult::pass(name, format!("{} vCPU, {}MB RAM, cgroups v2: {}",
+            env.vcpu_count, env.total_ram_mb, env.has_cgroups_v2)),
+        Err(e) => CheckResult::fail(name, e.to_string(), "Ensure /proc/cpuinfo and /proc/meminfo are readable"),
+    }
+}
+
+async fn check_telegram(config: &Config, db: Option<IntelligenceDB>) -> CheckResult {
+    let name = "telegram";
+    if config.telegram.is_none() {
+        return CheckResult::warn(name, "not configured", "Add a [telegram] section to receive alerts");
+    }
+
+    let db = match db {
+        Some(db) => db,
+        None => match IntelligenceDB::new_in_memory().await {
+            Ok(db) => db,
+            Err(e) => return CheckResult::fail(name, e.to_string(), "Database unavailable"),
+        },
+    };
+
+    let reporter = TelegramReporter::new(config.telegram.clone(), db);
+    match reporter.send_message("🧪 Hora-Police self-test: Telegram delivery works").await {
+        Ok(_) => CheckResult::pass(name, "test message sent"),
+        Err(e) => CheckResult::fail(name, e.to_string(),
+            "Check bot_token/chat_id and outbound HTTPS access to api.telegram.org"),
+    }
+}
+
+fn check_command(program: &'static str, args: &[&str], missing: CheckStatus, hint: &'static str) -> CheckResult {
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            CheckResult::pass(program, version.lines().next().unwrap_or("available").trim().to_string())
+        }
+        Ok(output) => CheckResult {
+            name: program,
+            status: missing,
+            detail: format!("exited with {}", output.status),
+            hint: Some(hint),
+        },
+        Err(e) => CheckResult {
+            name: program,
+            status: missing,
+            detail: format!("not found ({})", e),
+            hint: Some(hint),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_commands_get_the_given_status() {
+        let missing = check_command("hora-police-no-such-command", &[], CheckStatus::Warn, "install it");
+        assert_eq!(missing.status, CheckStatus::Warn);
+        assert!(missing.detail.starts_with("not found"));
+        assert_eq!(missing.hint, Some("install it"));
+
+        assert_eq!(check_command("false", &[], CheckStatus::Warn, "").status, CheckStatus::Warn);
+        assert_eq!(check_command("true", &[], CheckStatus::Fail, "").status, CheckStatus::Pass);
+    }
+
+    #[tokio::test]
+    async fn checks_storage_and_reports_hints() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_quarantine_dir(&dir.path().join("quarantine")).status, CheckStatus::Pass);
+        let file = dir.path().join("not-a-dir");
+        std::fs::write(&file, b"x").unwrap();
+        let unwritable = check_quarantine_dir(&file);
+        assert_eq!(unwritable.status, CheckStatus::Fail);
+
+        let db_path = dir.path().join("db/intelligence.db");
+        let (result, db) = check_database(&db_path.to_string_lossy()).await;
+        assert_eq!(result.status, CheckStatus::Pass);
+        assert!(db.is_some());
+
+        let telegram = check_telegram(&Config::default(), db).await;
+        assert_eq!(telegram.status, CheckStatus::Warn);
+
+        let report = format_report(&[result, unwritable]);
+        assert!(report.starts_with("✅ PASS database"));
+        assert!(report.contains("❌ FAIL quarantine dir"));
+        assert!(report.contains("↳ Make file_scanning.quarantine_path writable by the daemon"));
+    }
+}
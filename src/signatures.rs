@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::file_scanner::MalwareSignature;
+
+/// A signature before its patterns are compiled, as written in a signature file:
+///
+/// ```toml
+/// [[signature]]
+/// name = "kinsing"
+/// file_name_pattern = "^kinsing$"
+/// path_pattern = "/tmp/kinsing"
+/// file_hash = "<sha256 hex>"
+/// threat_level = 1.0
+/// description = "Kinsing miner dropper"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignatureSpec {
+    pub name: String,
+    #[serde(default)]
+    pub file_name_pattern: Option<String>,
+    #[serde(default)]
+    pub path_pattern: Option<String>,
+    #[serde(default)]
+    pub file_hash: Option<String>,
+    #[serde(default = "default_threat_level")]
+    pub threat_level: f32,
+    #[serde(default)]
+    pub description: String,
+}
+
+fn default_threat_level() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct SignatureFile {
+    #[serde(default)]
+    signature: Vec<SignatureSpec>,
+}
+
+/// Why a signature (or a whole signature file) was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureError {
+    pub source: String,  // "builtin" or the signature file path
+    pub name: String,  // Signature name, empty when the file itself failed to load
+    pub problem: String,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "{}: {}", self.source, self.problem)
+        } else {
+            write!(f, "{} [{}]: {}", self.source, self.name, self.problem)
+        }
+    }
+}
+
+impl SignatureSpec {
+    fn new(name: &str, file_name_pattern: Option<&str>, path_pattern: Option<&str>, threat_level: f32, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            file_name_pattern: file_name_pattern.map(str::to_string),
+            path_pattern: path_pattern.map(str::to_string),
+            file_hash: None,
+            threat_level,
+            description: description.to_string(),
+        }
+    }
+
+    /// Compile the patterns and check the hash; every problem is returned, nothing panics
+    pub fn compile(&self) -> std::result::Result<MalwareSignature, Vec<String>> {
+        let mut problems = Vec::new();
+        let mut compile = |field: &str, pattern: &Option<String>| {
+            pattern.as_deref().and_then(|p| match Regex::new(p) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    problems.push(format!("invalid {} {:?}: {}", field, p, e));
+                    None
+                }
+            })
+        };
+        let file_name_pattern = compile("file_name_pattern", &self.file_name_pattern);
+        let path_pattern = compile("path_pattern", &self.path_pattern);
+
+        if let Some(ref hash) = self.file_hash {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                problems.push(format!("malformed file_hash {:?} (expected 64 hex characters of SHA-256)", hash));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.threat_level) {
+            problems.push(format!("threat_level {} outside 0.0-1.0", self.threat_level));
+        }
+        if self.file_name_pattern.is_none() && self.path_pattern.is_none() && self.file_hash.is_none() {
+            problems.push("no file_name_pattern, path_pattern or file_hash: would never match".to_string());
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(MalwareSignature {
+            name: self.name.clone(),
+            file_name_pattern,
+            path_pattern,
+            file_hash: self.file_hash.as_ref().map(|h| h.to_ascii_lowercase()),
+            threat_level: self.threat_level,
+            description: self.description.clone(),
+        })
+    }
+}
+
+pub fn builtin_signature_specs() -> Vec<SignatureSpec> {
+    vec![
+        // Specific malware seen in the wild on these hosts
+        SignatureSpec::new("solrz", Some(r"^solrz$"), Some(r".*[/\\]solrz$"), 1.0, "Malicious file: solrz"),
+        SignatureSpec::new("e386", Some(r"^e386$"), Some(r".*[/\\]e386$"), 1.0, "Malicious file: e386"),
+        SignatureSpec::new("payload.so", Some(r"^payload\.so$"), Some(r".*[/\\]payload\.so$"), 1.0,
+                           "Malicious shared library: payload.so"),
+        SignatureSpec::new("next", Some(r"^next$"), Some(r".*[/\\]\.local[/\\]share[/\\]next$"), 1.0,
+                           "Malicious file: next"),
+        // Additional common malware patterns
+        SignatureSpec::new("crypto_miner_pattern", Some(r"(?i)(miner|mining|xmrig|ccminer|cpuminer)"), None, 0.9,
+                           "Potential crypto miner binary"),
+        SignatureSpec::new("suspicious_so_pattern", Some(r"\.so$"),
+                           Some(r"(?i)(tmp|/tmp|/var/tmp|/dev/shm|payload|malicious|evil)"), 0.8,
+                           "Suspicious shared library location"),
+    ]
+}
+
+/// Parse a TOML file of `[[signature]]` tables
+pub fn load_signature_file(path: &Path) -> Result<Vec<SignatureSpec>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read signature file {:?}", path))?;
+    let file: SignatureFile = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse signature file {:?}", path))?;
+    Ok(file.signature)
+}
+
+/// Builtin plus external signatures. Invalid ones (or unreadable files) are left
+/// out and reported instead of aborting the load.
+pub fn load_signatures(signature_files: &[String]) -> (Vec<MalwareSignature>, Vec<SignatureError>) {
+    let mut sources = vec![("builtin".to_string(), Ok(builtin_signature_specs()))];
+    for file in signature_files {
+        sources.push((file.clone(), load_signature_file(Path::new(file))));
+    }
+
+    let mut signatures = Vec::new();
+    let mut errors = Vec::new();
+    for (source, specs) in sources {
+        let specs = match specs {
+            Ok(specs) => specs,
+            Err(e) => {
+                errors.push(SignatureError { source, name: String::new(), problem: format!("{:#}", e) });
+                continue;
+            }
+        };
+        for spec in specs {
+            match spec.compile() {
+                Ok(signature) => signatures.push(signature),
+                Err(problems) => errors.extend(problems.into_iter().map(|problem| SignatureError {
+                    source: source.clone(),
+                    name: spec.name.clone(),
+                    problem,
+                })),
+            }
+        }
+    }
+    (signatures, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_signatures_are_valid() {
+        let (signatures, errors) = load_signatures(&[]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(signatures.len(), builtin_signature_specs().len());
+    }
+
+    #[test]
+    fn broken_patterns_and_hashes_are_reported_not_panicked_on() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("extra.toml");
+        fs::write(&file, r#"
+[[signature]]
+name = "good"
+file_hash = "AB2E6C3E5B1D1F5A0E4C2B7D9F8A6C5E3D1B0A9F8E7D6C5B4A3F2E1D0C9B8A7F"
+
+[[signature]]
+name = "broken"
+file_name_pattern = "^kinsing(["
+file_hash = "not-a-hash"
+"#).unwrap();
+        let missing = dir.path().join("missing.toml").to_string_lossy().to_string();
+
+        let (signatures, errors) = load_signatures(&[file.to_string_lossy().to_string(), missing.clone()]);
+
+        let good = signatures.iter().find(|s| s.name == "good").unwrap();
+        assert_eq!(good.file_hash.as_deref(), Some("ab2e6c3e5b1d1f5a0e4c2b7d9f8a6c5e3d1b0a9f8e7d6c5b4a3f2e1d0c9b8a7f"));
+        assert!(!signatures.iter().any(|s| s.name == "broken"));
+
+        let broken: Vec<_> = errors.iter().filter(|e| e.name == "broken").collect();
+        assert_eq!(broken.len(), 2);
+        assert!(broken[0].problem.starts_with("invalid file_name_pattern"));
+        assert!(broken[1].problem.starts_with("malformed file_hash"));
+        assert!(errors.iter().any(|e| e.source == missing && e.name.is_empty()));
+    }
+}
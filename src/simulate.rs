@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{Config, IntegrationsConfig};
+use crate::cpu_analyzer::CpuAnalyzer;
+use crate::database::IntelligenceDB;
+use crate::intelligence::{BehaviorIntelligence, DaemonizedSignals};
+use crate::nginx_integration::NginxIntegration;
+use crate::environment::SystemEnvironment;
+use crate::pm2_integration::Pm2Integration;
+use crate::process_monitor::ProcessInfo;
+use crate::react_detector::{ReactAbuseDetection, ReactDetector};
+use crate::safe_kill::{KillActionType, SafeKillConfig, SafeKillEngine};
+use crate::scoring::SignalCategory;
+use crate::systemd_integration::SystemdIntegration;
+use crate::docker_integration::DockerIntegration;
+use crate::users::BuildUserPolicy;
+use crate::whitelist::WhitelistManager;
+
+/// One recorded process list, as captured at `timestamp`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub processes: Vec<ProcessInfo>,
+}
+
+/// What the detection pipeline decided for a flagged process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationDecision {
+    pub timestamp: DateTime<Utc>,
+    pub pid: i32,
+    pub binary_path: String,
+    pub cpu_percent: f32,
+    pub duration_seconds: u64,
+    pub confidence: f32,
+    pub react: Option<ReactAbuseDetection>,
+    /// Pieces of the "dropped and daemonized" composite, as of the snapshot time
+    #[serde(default)]
+    pub daemonized: DaemonizedSignals,
+    /// None when confidence stayed below `threat_confidence_threshold`
+    pub action: Option<KillActionType>,
+}
+
+/// Load a JSON array of process snapshots
+pub fn load_snapshots(path: &Path) -> Result<Vec<ProcessSnapshot>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read snapshots from {:?}", path))?;
+    let mut snapshots: Vec<ProcessSnapshot> = serde_json::from_str(&content)
+        .context("Failed to parse snapshot JSON")?;
+    snapshots.sort_by_key(|s| s.timestamp);
+    Ok(snapshots)
+}
+
+/// Replay snapshots through the analyzers. Always dry-run: nothing is killed and
+/// history is kept in a throwaway in-memory database.
+pub async fn run_simulation(config: &Config, snapshots: &[ProcessSnapshot]) -> Result<Vec<SimulationDecision>> {
+    let db = IntelligenceDB::new_in_memory().await?;
+    let build_users = BuildUserPolicy::from_config(&config.build_users);
+    let mut intelligence = BehaviorIntelligence::new(db.clone(), config.learning_mode).await?;
+    intelligence.set_build_users(build_users.clone());
+    intelligence.set_min_record_confidence(config.min_record_confidence);
+    let vcpu_count = config.auto_tune.vcpu_override
+        .or_else(|| SystemEnvironment::detect().ok().map(|e| e.vcpu_count))
+        .unwrap_or(1);
+    intelligence.set_thread_fingerprint(config.thread_fingerprint.clone(), vcpu_count);
+    intelligence.set_resource_signals(&config.resource_signals);
+    intelligence.set_daemonized_dropper(&config.daemonized_dropper);
+    intelligence.set_web_uploads(&config.web_uploads);
+    intelligence.set_paranoid_tmp_exec(config.paranoid_tmp_exec, config.high_confidence_threshold);
+    let mut cpu_analyzer = CpuAnalyzer::new(config.cpu_threshold, config.duration_minutes);
+    cpu_analyzer.set_build_users(build_users);
+    let react_detector = ReactDetector::new(&config.react_detection);
+
+    let mut whitelist = WhitelistManager::new();
+    for pattern in &config.whitelist.manual_patterns {
+        whitelist.add_manual_entry(pattern.clone());
+    }
+
+    let mut safe_kill_config = SafeKillConfig::from(config);
+    safe_kill_config.dry_run = true;
+    // Recorded PIDs can't be matched against the live process managers, so never query them
+    let offline = IntegrationsConfig {
+        pm2_refresh_seconds: 0,
+        systemd_refresh_seconds: 0,
+        nginx_refresh_seconds: 0,
+        ..config.integrations.clone()
+    };
+    let mut safe_kill = SafeKillEngine::new(
+        db,
+        Pm2Integration::new_with_config(&offline),
+        SystemdIntegration::new_with_config(&offline),
+        NginxIntegration::new_with_config(&offline),
+        DockerIntegration::new(config.docker.clone(), offline.command_timeout()),
+        whitelist,
+        safe_kill_config,
+    );
+
+    let mut decisions = Vec::new();
+
+    for snapshot in snapshots {
+        for abuse in cpu_analyzer.analyze_at(&snapshot.processes, snapshot.timestamp) {
+            let Some(process) = snapshot.processes.iter().find(|p| p.pid == abuse.pid) else {
+                continue;
+            };
+
+            let mut confidence = intelligence.analyze_process(
+                process,
+                abuse.cpu_percent,
+                abuse.duration_seconds,
+                abuse.first_seen,
+            ).await?;
+
+            intelligence.record_suspicious_process(
+                process,
+                abuse.cpu_percent,
+                abuse.duration_seconds,
+                confidence,
+                abuse.first_seen,
+            ).await?;
+
+            let mut signals = intelligence.signal_categories(process, abuse.cpu_percent, abuse.duration_seconds);
+            let react = react_detector.detect(process, abuse.cpu_percent);
+            if let Some(ref react_abuse) = react {
+                confidence = confidence.max((confidence + react_abuse.confidence * 0.2).min(1.0));
+                signals.insert(SignalCategory::ReactAbuse);
+            }
+
+            let action = if confidence >= config.threat_confidence_threshold {
+                Some(safe_kill.decide_action(process, confidence, &signals).await)
+            } else {
+                None
+            };
+
+            decisions.push(SimulationDecision {
+                timestamp: snapshot.timestamp,
+                pid: process.pid,
+                binary_path: process.binary_path.clone(),
+                cpu_percent: abuse.cpu_percent,
+                duration_seconds: abuse.duration_seconds,
+                confidence,
+                react,
+                daemonized: intelligence.daemonized_signals(process, snapshot.timestamp),
+                action,
+            });
+        }
+    }
+
+    Ok(decisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(minutes: i64, processes: Vec<ProcessInfo>) -> ProcessSnapshot {
+        let start: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        ProcessSnapshot {
+            timestamp: start + chrono::Duration::minutes(minutes),
+            processes,
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_snapshots_into_dry_run_decisions() {
+        let json = r#"[
+            {"pid": 4242, "ppid": 1, "uid": 1000, "binary_path": "/tmp/xmrig", "command_line": "/tmp/xmrig -o stratum+tcp://pool", "cpu_percent": 95.0},
+            {"pid": 100, "ppid": 1, "uid": 0, "binary_path": "/usr/sbin/nginx", "command_line": "nginx: worker process", "cpu_percent": 3.0}
+        ]"#;
+        let processes: Vec<ProcessInfo> = serde_json::from_str(json).unwrap();
+        let snapshots = vec![
+            snapshot(0, processes.clone()),
+            snapshot(3, processes.clone()),
+            snapshot(6, processes),
+        ];
+
+        let decisions = run_simulation(&Config::default(), &snapshots).await.unwrap();
+
+        // Only the miner crosses the duration threshold, and only at the last snapshot
+        assert_eq!(decisions.len(), 1);
+        let decision = &decisions[0];
+        assert_eq!(decision.pid, 4242);
+        assert_eq!(decision.duration_seconds, 360);
+        assert!(decision.confidence >= 0.7);
+        assert_eq!(decision.action, Some(KillActionType::KillDirect));
+    }
+}
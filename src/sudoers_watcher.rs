@@ -0,0 +1,336 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+use crate::config::SudoersConfig;
+use crate::rollback::{RollbackAction, RollbackManifest};
+
+/// A user specification line (`who host = (runas) commands`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SudoersGrant {
+    pub line: String,
+    pub principal: String,
+    pub nopasswd: bool,
+}
+
+/// A grant that appeared since the previous scan
+#[derive(Debug, Clone)]
+pub struct SudoersFinding {
+    pub file: PathBuf,
+    pub grant: SudoersGrant,
+    pub reasons: Vec<String>,
+    pub new_file: bool,
+}
+
+/// Snapshots /etc/sudoers and /etc/sudoers.d/* and reports NOPASSWD or
+/// unexpected grants added after the first scan
+pub struct SudoersWatcher {
+    etc_dir: PathBuf,
+    allowed_principals: Vec<String>,
+    baseline: Option<HashMap<PathBuf, Vec<SudoersGrant>>>,
+}
+
+impl SudoersWatcher {
+    pub fn new(config: &SudoersConfig) -> Self {
+        Self::with_etc_dir(config, Path::new("/etc"))
+    }
+
+    fn with_etc_dir(config: &SudoersConfig, etc_dir: &Path) -> Self {
+        Self {
+            etc_dir: etc_dir.to_path_buf(),
+            allowed_principals: config.allowed_principals.clone(),
+            baseline: None,
+        }
+    }
+
+    /// Files sudo actually reads: sudoers plus sudoers.d entries without '.' or a trailing '~'
+    fn sudoers_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.etc_dir.join("sudoers")];
+        if let Ok(entries) = fs::read_dir(self.etc_dir.join("sudoers.d")) {
+            let mut included: Vec<PathBuf> = entries.flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .filter(|p| p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| !n.contains('.') && !n.ends_with('~')))
+                .collect();
+            included.sort();
+            files.extend(included);
+        }
+        files
+    }
+
+    /// Compare against the previous snapshot. The first call only records the baseline.
+    pub fn scan(&mut self) -> Vec<SudoersFinding> {
+        let mut current = HashMap::new();
+        for file in self.sudoers_files() {
+            let Ok(content) = fs::read_to_string(&file) else {
+                continue;
+            };
+            match parse_sudoers(&content) {
+                Some(grants) => {
+                    current.insert(file, grants);
+                }
+                None => warn!("⚠️  Could not parse {:?}, not tracking it", file),
+            }
+        }
+
+        let Some(previous) = self.baseline.replace(current) else {
+            return Vec::new();
+        };
+        let current = self.baseline.as_ref().expect("baseline just set");
+
+        let mut findings = Vec::new();
+        for (file, grants) in current {
+            let known = previous.get(file);
+            for grant in grants {
+                if known.is_some_and(|known| known.iter().any(|g| g.line == grant.line)) {
+                    continue;
+                }
+                let mut reasons = Vec::new();
+                if grant.nopasswd {
+                    reasons.push("NOPASSWD".to_string());
+                }
+                if !self.allowed_principals.contains(&grant.principal) {
+                    reasons.push(format!("unexpected principal {}", grant.principal));
+                }
+                if !reasons.is_empty() {
+                    findings.push(SudoersFinding {
+                        file: file.clone(),
+                        grant: grant.clone(),
+                        reasons,
+                        new_file: known.is_none(),
+                    });
+                }
+            }
+        }
+        findings
+    }
+
+    /// Remove a flagged grant, backing the file up and writing a rollback manifest first.
+    /// Refuses to touch files it can't parse or that `visudo -c` rejects after the edit.
+    pub fn restore(&mut self, finding: &SudoersFinding, rollback_dir: &Path, signing_key: Option<&[u8]>) -> Result<()> {
+        let content = fs::read_to_string(&finding.file)
+            .with_context(|| format!("Failed to read {:?}", finding.file))?;
+        if parse_sudoers(&content).is_none() {
+            bail!("{:?} no longer parses, leaving it untouched", finding.file);
+        }
+
+        let remaining: Vec<&str> = content.lines()
+            .filter(|line| line.trim() != finding.grant.line)
+            .collect();
+        if remaining.len() == content.lines().count() {
+            bail!("Grant not found verbatim in {:?} (continuation line?), leaving it untouched", finding.file);
+        }
+        let new_content = remaining.join("\n") + "\n";
+        let remaining_grants = parse_sudoers(&new_content).unwrap_or_default();
+        let remove_file = finding.new_file && remaining_grants.is_empty()
+            && finding.file != self.etc_dir.join("sudoers");
+        if !remove_file {
+            validate_with_visudo(&new_content)?;
+        }
+
+        // Backup + manifest before any modification
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let mut backup = finding.file.as_os_str().to_owned();
+        backup.push(format!(".backup.{}", timestamp));
+        let backup = PathBuf::from(backup);
+        fs::copy(&finding.file, &backup)
+            .with_context(|| format!("Failed to back up {:?}", finding.file))?;
+        // Backups in sudoers.d contain '.', so sudo ignores them
+        fs::set_permissions(&backup, fs::Permissions::from_mode(0o440))?;
+
+        let mut manifest = RollbackManifest::new();
+        manifest.add_action(RollbackAction::RestoreFile {
+            from: backup.to_string_lossy().to_string(),
+            to: finding.file.to_string_lossy().to_string(),
+        });
+        if let Some(key) = signing_key {
+            manifest.sign(key)?;
+        }
+        fs::create_dir_all(rollback_dir)?;
+        let name = finding.file.file_name().and_then(|n| n.to_str()).unwrap_or("sudoers");
+        manifest.save(&rollback_dir.join(format!("sudoers_{}_{}.rollback", name, timestamp)))?;
+
+        if remove_file {
+            fs::remove_file(&finding.file)
+                .with_context(|| format!("Failed to remove {:?}", finding.file))?;
+            info!("🧹 Removed sudoers drop-in {:?} (backup: {:?})", finding.file, backup);
+        } else {
+            let mode = fs::metadata(&finding.file)?.permissions().mode();
+            let temp = finding.file.with_extension("hora-tmp");
+            fs::write(&temp, &new_content)?;
+            fs::set_permissions(&temp, fs::Permissions::from_mode(mode))?;
+            fs::rename(&temp, &finding.file)
+                .with_context(|| format!("Failed to replace {:?}", finding.file))?;
+            info!("🧹 Removed grant {:?} from {:?} (backup: {:?})", finding.grant.line, finding.file, backup);
+        }
+
+        // Don't re-report our own edit on the next scan
+        if let Some(baseline) = self.baseline.as_mut() {
+            if let Some(grants) = baseline.get_mut(&finding.file) {
+                grants.retain(|g| g.line != finding.grant.line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run `visudo -c` on the would-be content; skipped (with a debug line) when visudo isn't installed
+fn validate_with_visudo(content: &str) -> Result<()> {
+    // Created O_EXCL with mode 0600 under a random name, so nothing else can plant or read it
+    let mut temp = tempfile::Builder::new().prefix("hora-police-sudoers-").tempfile()?;
+    temp.write_all(content.as_bytes())?;
+    temp.flush()?;
+    let output = Command::new("visudo").args(["-c", "-q", "-f"]).arg(temp.path()).output();
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => bail!("visudo rejected the edited file: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) => {
+            debug!("visudo unavailable ({}), relying on our own parse", e);
+            Ok(())
+        }
+    }
+}
+
+/// User specifications in a sudoers file, or None if any line isn't understood
+pub fn parse_sudoers(content: &str) -> Option<Vec<SudoersGrant>> {
+    let user_spec = Regex::new(r"^(\S+)\s+[^=\s]+(\s*,\s*[^=\s]+)*\s*=\s*\S.*$").unwrap();
+    let mut grants = Vec::new();
+
+    // Join backslash continuations into logical lines
+    let mut logical = Vec::new();
+    let mut pending = String::new();
+    for line in content.lines() {
+        match line.strip_suffix('\\') {
+            Some(start) => {
+                pending.push_str(start);
+                pending.push(' ');
+            }
+            None => {
+                pending.push_str(line);
+                logical.push(std::mem::take(&mut pending));
+            }
+        }
+    }
+    if !pending.is_empty() {
+        return None;
+    }
+
+    for line in logical {
+        let line = line.trim();
+        let directive = line.starts_with("#include") || line.starts_with("@include");
+        if line.is_empty() || (line.starts_with('#') && !directive) {
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        if directive
+            || keyword.starts_with("Defaults")
+            || matches!(keyword, "User_Alias" | "Runas_Alias" | "Host_Alias" | "Cmnd_Alias")
+        {
+            continue;
+        }
+        let captures = user_spec.captures(line)?;
+        grants.push(SudoersGrant {
+            line: line.to_string(),
+            principal: captures[1].to_string(),
+            nopasswd: line.contains("NOPASSWD:"),
+        });
+    }
+    Some(grants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STOCK: &str = "Defaults\tenv_reset\nDefaults\tsecure_path=\"/usr/sbin:/usr/bin\"\n\n# User privilege specification\nroot\tALL=(ALL:ALL) ALL\n%sudo\tALL=(ALL:ALL) ALL\n\n@includedir /etc/sudoers.d\n";
+
+    fn setup() -> (tempfile::TempDir, SudoersWatcher) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("sudoers"), STOCK).unwrap();
+        fs::create_dir(dir.path().join("sudoers.d")).unwrap();
+        fs::write(dir.path().join("sudoers.d/README"), "# Files in this directory are read by sudo\n").unwrap();
+        let watcher = SudoersWatcher::with_etc_dir(&SudoersConfig::default(), dir.path());
+        (dir, watcher)
+    }
+
+    #[test]
+    fn parses_stock_sudoers_and_rejects_unknown_syntax() {
+        let grants = parse_sudoers(STOCK).unwrap();
+        assert_eq!(grants.len(), 2);
+        assert_eq!(grants[1].principal, "%sudo");
+        assert!(!grants[1].nopasswd);
+
+        let continued = parse_sudoers("deploy ALL = (root) NOPASSWD: /usr/bin/systemctl restart app, \\\n    /usr/bin/systemctl status app\n").unwrap();
+        assert_eq!(continued.len(), 1);
+        assert!(continued[0].nopasswd);
+
+        assert!(parse_sudoers("this is not sudoers\n").is_none());
+    }
+
+    #[test]
+    fn flags_added_nopasswd_grant() {
+        let (dir, mut watcher) = setup();
+        assert!(watcher.scan().is_empty());  // Baseline
+        assert!(watcher.scan().is_empty());
+
+        let dropped = dir.path().join("sudoers.d/90-cloud");
+        fs::write(&dropped, "baduser ALL=(ALL) NOPASSWD:ALL\n").unwrap();
+        // Names with '.' are ignored by sudo, so not watched either
+        fs::write(dir.path().join("sudoers.d/x.bak"), "other ALL=(ALL) NOPASSWD:ALL\n").unwrap();
+
+        let findings = watcher.scan();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, dropped);
+        assert_eq!(findings[0].grant.principal, "baduser");
+        assert!(findings[0].new_file);
+        assert_eq!(findings[0].reasons, vec!["NOPASSWD", "unexpected principal baduser"]);
+
+        // Reported once
+        assert!(watcher.scan().is_empty());
+    }
+
+    #[test]
+    fn restore_removes_grant_with_backup_and_manifest() {
+        let (dir, mut watcher) = setup();
+        watcher.scan();
+        let sudoers = dir.path().join("sudoers");
+        fs::write(&sudoers, format!("{}baduser ALL=(ALL) NOPASSWD:ALL\n", STOCK)).unwrap();
+        let dropped = dir.path().join("sudoers.d/zz");
+        fs::write(&dropped, "baduser ALL=(ALL) NOPASSWD:ALL\n").unwrap();
+
+        let findings = watcher.scan();
+        assert_eq!(findings.len(), 2);
+        let rollback_dir = dir.path().join("rollbacks");
+        for finding in &findings {
+            watcher.restore(finding, &rollback_dir, Some(b"key")).unwrap();
+        }
+
+        assert!(!dropped.exists());
+        assert_eq!(fs::read_to_string(&sudoers).unwrap().trim_end(), STOCK.trim_end());
+        assert_eq!(fs::read_dir(&rollback_dir).unwrap().count(), 4);  // .json + .sh per file
+        assert!(watcher.scan().is_empty());
+    }
+
+    #[test]
+    fn never_touches_unparseable_files() {
+        let (dir, mut watcher) = setup();
+        watcher.scan();
+        let dropped = dir.path().join("sudoers.d/odd");
+        fs::write(&dropped, "baduser ALL=(ALL) NOPASSWD:ALL\n").unwrap();
+        let findings = watcher.scan();
+
+        fs::write(&dropped, "baduser ALL=(ALL) NOPASSWD:ALL\n%%%garbage\n").unwrap();
+        assert!(watcher.restore(&findings[0], &dir.path().join("rollbacks"), None).is_err());
+        assert!(dropped.exists());
+        assert!(!dir.path().join("rollbacks").exists());
+    }
+}
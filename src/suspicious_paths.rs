@@ -0,0 +1,129 @@
+use anyhow::{bail, Result};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+
+/// World-writable or per-user locations where payloads are commonly staged
+pub const DEFAULT_PREFIXES: &[&str] = &["/tmp", "/var/tmp", "/dev/shm", "/run/user"];
+
+/// Per-user dot directories, wherever the home directory is
+pub const DEFAULT_SUBSTRINGS: &[&str] = &["/.cache/", "/.local/"];
+
+static INSTALLED: OnceLock<SuspiciousPaths> = OnceLock::new();
+
+/// Where a binary (or a library it loads) is suspicious just for living there.
+/// Prefixes match whole path components (`/tmp` covers `/tmp/x` but not `/tmpfs/x`);
+/// substrings match anywhere in the path.
+#[derive(Debug, Clone)]
+pub struct SuspiciousPaths {
+    prefixes: Vec<String>,
+    substrings: Vec<String>,
+}
+
+impl SuspiciousPaths {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::new(&config.suspicious_path_prefixes, &config.suspicious_path_substrings)
+    }
+
+    pub fn new(prefixes: &[String], substrings: &[String]) -> Result<Self> {
+        if let Some(prefix) = prefixes.iter().find(|p| !p.starts_with('/')) {
+            bail!("Suspicious path prefix {:?} must be absolute", prefix);
+        }
+        if substrings.iter().any(|s| s.is_empty()) {
+            bail!("Suspicious path substrings must not be empty");
+        }
+        Ok(Self {
+            prefixes: prefixes.to_vec(),
+            substrings: substrings.to_vec(),
+        })
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.matched(path).is_some()
+    }
+
+    /// The prefix or substring `path` matched
+    pub fn matched(&self, path: &str) -> Option<&str> {
+        self.matched_prefix(path)
+            .or_else(|| self.substrings.iter().find(|substring| path.contains(substring.as_str())).map(String::as_str))
+    }
+
+    /// The prefix `path` lies under; substrings aren't considered
+    pub fn matched_prefix(&self, path: &str) -> Option<&str> {
+        self.prefixes.iter().find(|prefix| Path::new(path).starts_with(prefix)).map(String::as_str)
+    }
+
+    /// Make this the list `is_suspicious_path` checks. Only the first call takes effect.
+    pub fn install(self) {
+        let _ = INSTALLED.set(self);
+    }
+}
+
+impl Default for SuspiciousPaths {
+    fn default() -> Self {
+        Self {
+            prefixes: DEFAULT_PREFIXES.iter().map(|p| p.to_string()).collect(),
+            substrings: DEFAULT_SUBSTRINGS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// Whether `path` is under a suspicious location, using the installed list
+/// (the built-in one until `SuspiciousPaths::install` runs)
+pub fn is_suspicious_path(path: &str) -> bool {
+    installed().matches(path)
+}
+
+/// The installed prefix or substring `path` matched, for reasons shown to operators
+pub fn suspicious_path_match(path: &str) -> Option<&'static str> {
+    installed().matched(path)
+}
+
+/// The installed prefix (a whole directory, such as /tmp) `path` lies under
+pub fn suspicious_dir_match(path: &str) -> Option<&'static str> {
+    installed().matched_prefix(path)
+}
+
+fn installed() -> &'static SuspiciousPaths {
+    INSTALLED.get_or_init(SuspiciousPaths::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_list_matches_staging_directories() {
+        let paths = SuspiciousPaths::default();
+        for path in ["/tmp/.x/xmrig", "/var/tmp/kworker", "/dev/shm/payload", "/run/user/1000/x",
+                     "/home/u/.cache/x/kworker", "/root/.local/bin/miner"] {
+            assert!(paths.matches(path), "{}", path);
+        }
+        for path in ["/tmpfs/app", "/usr/bin/node", "/var/tmpl/x", "/home/u/cache/x"] {
+            assert!(!paths.matches(path), "{}", path);
+        }
+
+        assert_eq!(paths.matched("/dev/shm/.cache/x"), Some("/dev/shm"));
+        assert_eq!(paths.matched("/home/u/.cache/x"), Some("/.cache/"));
+        assert_eq!(paths.matched_prefix("/home/u/.cache/x"), None);
+    }
+
+    #[test]
+    fn configured_lists_replace_the_builtin_ones() {
+        let paths = SuspiciousPaths::new(
+            &["/var/www/app/uploads/".to_string()],
+            &["/node_modules/.bin/.".to_string()],
+        ).unwrap();
+        assert!(paths.matches("/var/www/app/uploads/shell"));
+        assert!(paths.matches("/srv/site/node_modules/.bin/.hidden"));
+        assert!(!paths.matches("/var/www/app/public/index.php"));
+        assert!(!paths.matches("/tmp/x"));
+    }
+
+    #[test]
+    fn rejects_relative_prefixes_and_empty_substrings() {
+        assert!(SuspiciousPaths::new(&["tmp".to_string()], &[]).is_err());
+        assert!(SuspiciousPaths::new(&[], &[String::new()]).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use anyhow::{Context, Result};
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
+use tracing::{debug, info, warn};
+
+use crate::config::{AlertSeverity, SyslogConfig};
+use crate::event_stream::{DaemonEvent, EventBus, EventKind};
+
+/// Structured data ID. 32473 is the private enterprise number reserved for
+/// documentation (RFC 5612); SIEM parsers key on the whole ID.
+const SD_ID: &str = "hora@32473";
+
+/// Longest a connect or write may take before the event is dropped
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    #[default]
+    Udp,  // RFC 5426, one event per datagram
+    Tcp,  // RFC 6587 octet-counted framing, reconnects after failures
+}
+
+/// RFC 5424 facility codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogFacility {
+    Kern = 0,
+    User = 1,
+    Mail = 2,
+    #[default]
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    Authpriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// RFC 5424 severity codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogSeverity {
+    Emerg = 0,
+    Alert = 1,
+    Crit = 2,
+    Err = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+    Debug = 7,
+}
+
+/// Syslog severity sent for each alert severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyslogSeverityMap {
+    #[serde(default = "default_critical_severity")]
+    pub critical: SyslogSeverity,
+    #[serde(default = "default_warning_severity")]
+    pub warning: SyslogSeverity,
+    #[serde(default = "default_info_severity")]
+    pub info: SyslogSeverity,
+}
+
+fn default_critical_severity() -> SyslogSeverity {
+    SyslogSeverity::Crit
+}
+
+fn default_warning_severity() -> SyslogSeverity {
+    SyslogSeverity::Warning
+}
+
+fn default_info_severity() -> SyslogSeverity {
+    SyslogSeverity::Info
+}
+
+impl Default for SyslogSeverityMap {
+    fn default() -> Self {
+        Self {
+            critical: default_critical_severity(),
+            warning: default_warning_severity(),
+            info: default_info_severity(),
+        }
+    }
+}
+
+impl SyslogSeverityMap {
+    fn get(&self, severity: AlertSeverity) -> SyslogSeverity {
+        match severity {
+            AlertSeverity::Critical => self.critical,
+            AlertSeverity::Warning => self.warning,
+            AlertSeverity::Info => self.info,
+        }
+    }
+}
+
+/// Ships daemon events to a remote syslog server as RFC 5424 messages, for SIEMs
+pub struct SyslogNotifier {
+    config: SyslogConfig,
+    hostname: String,
+    procid: u32,
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpStream>,
+    failing: bool,  // Log the first failure of a run at warn, the rest at debug
+}
+
+impl SyslogNotifier {
+    pub fn new(config: SyslogConfig) -> Self {
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|h| h.trim().to_string())
+            .unwrap_or_default();
+        Self {
+            config,
+            hostname,
+            procid: std::process::id(),
+            udp: None,
+            tcp: None,
+            failing: false,
+        }
+    }
+
+    /// The event as one RFC 5424 message, without transport framing
+    pub fn format(&self, event: &DaemonEvent) -> String {
+        let priority = self.config.facility as u8 * 8 + self.config.severity.get(event.severity) as u8;
+        format!(
+            "<{}>1 {} {} {} {} {} {} \u{feff}{}",
+            priority,
+            event.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            header_field(&self.hostname, 255),
+            header_field(&self.config.app_name, 48),
+            self.procid,
+            message_id(event.kind),
+            structured_data(event),
+            event.message,
+        )
+    }
+
+    pub async fn send(&mut self, event: &DaemonEvent) -> Result<()> {
+        let message = self.format(event);
+        let result = timeout(SEND_TIMEOUT, self.transmit(&message)).await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {:?}", SEND_TIMEOUT)));
+        if result.is_err() {
+            // Reconnect (and re-resolve the host) on the next event
+            self.udp = None;
+            self.tcp = None;
+        }
+        result
+    }
+
+    async fn transmit(&mut self, message: &str) -> Result<()> {
+        let target = (self.config.host.as_str(), self.config.port);
+        match self.config.protocol {
+            SyslogProtocol::Udp => {
+                if self.udp.is_none() {
+                    let address = tokio::net::lookup_host(target).await?.next()
+                        .with_context(|| format!("Failed to resolve {}:{}", target.0, target.1))?;
+                    let socket = UdpSocket::bind(if address.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }).await?;
+                    socket.connect(address).await?;
+                    self.udp = Some(socket);
+                }
+                if let Some(ref socket) = self.udp {
+                    socket.send(message.as_bytes()).await?;
+                }
+            }
+            SyslogProtocol::Tcp => {
+                if self.tcp.is_none() {
+                    let stream = TcpStream::connect(target).await
+                        .with_context(|| format!("Failed to connect to {}:{}", target.0, target.1))?;
+                    self.tcp = Some(stream);
+                }
+                if let Some(ref mut stream) = self.tcp {
+                    stream.write_all(format!("{} {}", message.len(), message).as_bytes()).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward every published event until the bus closes. A slow or unreachable
+    /// server only costs this task events (dropped after SEND_TIMEOUT or on lag),
+    /// never the monitoring loop.
+    pub async fn forward(mut self, bus: EventBus) {
+        let mut receiver = bus.subscribe();
+        loop {
+            match receiver.recv().await {
+                Ok(event) => match self.send(&event).await {
+                    Ok(()) if self.failing => {
+                        info!("📡 Syslog server {}:{} reachable again", self.config.host, self.config.port);
+                        self.failing = false;
+                    }
+                    Ok(()) => {}
+                    Err(e) if !self.failing => {
+                        warn!("Failed to send event to syslog {}:{}: {:#}", self.config.host, self.config.port, e);
+                        self.failing = true;
+                    }
+                    Err(e) => debug!("Failed to send event to syslog: {:#}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("syslog forwarder lagged, {} events dropped", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn message_id(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::Detection => "detection",
+        EventKind::Kill => "kill",
+        EventKind::Scan => "scan",
+        EventKind::Malware => "malware",
+        EventKind::Persistence => "persistence",
+    }
+}
+
+/// Header fields are printable ASCII without spaces, "-" when empty
+fn header_field(value: &str, max_len: usize) -> String {
+    let cleaned: String = value.chars()
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .take(max_len)
+        .collect();
+    if cleaned.is_empty() { "-".to_string() } else { cleaned }
+}
+
+/// `[hora@32473 pid=".." binary=".." confidence=".." signature=".."]` with the
+/// fields the event has, or "-" for none
+fn structured_data(event: &DaemonEvent) -> String {
+    let mut params = Vec::new();
+    if let Some(pid) = event.pid {
+        params.push(("pid", pid.to_string()));
+    }
+    if let Some(ref path) = event.path {
+        params.push(("binary", path.clone()));
+    }
+    if let Some(confidence) = event.confidence {
+        params.push(("confidence", format!("{:.2}", confidence)));
+    }
+    if let Some(ref signature) = event.signature {
+        params.push(("signature", signature.clone()));
+    }
+    if params.is_empty() {
+        return "-".to_string();
+    }
+    let params: Vec<String> = params.into_iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_param(&value)))
+        .collect();
+    format!("[{} {}]", SD_ID, params.join(" "))
+}
+
+/// RFC 5424 section 6.3.3: `"`, `\` and `]` are escaped in parameter values
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_monitor::ProcessInfo;
+    use tokio::io::AsyncReadExt;
+
+    fn config(protocol: SyslogProtocol, port: u16) -> SyslogConfig {
+        SyslogConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            protocol,
+            facility: SyslogFacility::Local3,
+            severity: SyslogSeverityMap::default(),
+            app_name: "hora-police".to_string(),
+        }
+    }
+
+    #[test]
+    fn formats_rfc5424_with_structured_data() {
+        let mut notifier = SyslogNotifier::new(config(SyslogProtocol::Udp, 514));
+        notifier.hostname = "web 1".to_string();
+        let process = ProcessInfo { pid: 4242, binary_path: "/tmp/.x/\"xm]rig".to_string(), ..Default::default() };
+        let mut event = DaemonEvent::new(AlertSeverity::Critical, EventKind::Kill, "Stopped xmrig")
+            .with_process(&process)
+            .with_confidence(0.93)
+            .with_signature("crypto_miner_pattern");
+        event.timestamp = "2026-03-01T10:15:30.123456Z".parse().unwrap();
+
+        // local3 (19) * 8 + crit (2)
+        assert_eq!(notifier.format(&event), format!(
+            "<154>1 2026-03-01T10:15:30.123456Z web_1 hora-police {} kill \
+             [hora@32473 pid=\"4242\" binary=\"/tmp/.x/\\\"xm\\]rig\" confidence=\"0.93\" \
+             signature=\"crypto_miner_pattern\"] \u{feff}Stopped xmrig",
+            std::process::id()));
+
+        notifier.config.severity.info = SyslogSeverity::Notice;
+        let scan = notifier.format(&DaemonEvent::new(AlertSeverity::Info, EventKind::Scan, "File scan started"));
+        assert!(scan.starts_with("<157>1 "), "{}", scan);
+        assert!(scan.contains(" scan - \u{feff}File scan started"), "{}", scan);
+    }
+
+    #[tokio::test]
+    async fn sends_over_udp_and_octet_counted_tcp() {
+        let event = DaemonEvent::new(AlertSeverity::Warning, EventKind::Detection, "CPU abuse");
+
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut notifier = SyslogNotifier::new(config(SyslogProtocol::Udp, server.local_addr().unwrap().port()));
+        notifier.send(&event).await.unwrap();
+        let mut buf = [0u8; 1024];
+        let len = server.recv(&mut buf).await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&buf[..len]), notifier.format(&event));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut notifier = SyslogNotifier::new(config(SyslogProtocol::Tcp, listener.local_addr().unwrap().port()));
+        notifier.send(&event).await.unwrap();
+        notifier.send(&event).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let message = notifier.format(&event);
+        drop(notifier);
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+        assert_eq!(received, format!("{0} {1}{0} {1}", message.len(), message));
+
+        // Nobody listening: an error, not a hang
+        drop(listener);
+        let mut unreachable = SyslogNotifier::new(config(SyslogProtocol::Tcp, 1));
+        assert!(unreachable.send(&event).await.is_err());
+    }
+}
